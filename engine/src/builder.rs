@@ -15,11 +15,12 @@
 use autocxx_parser::file_locations::FileLocationStrategy;
 use proc_macro2::TokenStream;
 
+use crate::compile_commands::{read_compile_flags, CompileCommandsError};
 use crate::{strip_system_headers, CppCodegenOptions, ParseError, RebuildDependencyRecorder};
 use std::ffi::OsString;
 use std::io::Write;
 use std::marker::PhantomData;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::{ffi::OsStr, io, process};
 use std::{fmt::Display, fs::File};
 
@@ -39,6 +40,16 @@ pub enum BuilderError {
     NoIncludeCxxMacrosFound,
     /// Unable to create one of the directories to which we need to write
     UnableToCreateDirectory(std::io::Error, PathBuf),
+    /// A preprocessor symbol was defined with two different values,
+    /// either by two calls to [`Builder::define`] or by one such call
+    /// and a `-D` argument in [`Builder::extra_clang_args`].
+    ConflictingDefine(String),
+    /// The flags couldn't be read from the compilation database given
+    /// to [`Builder::compile_commands`].
+    CompileCommands(CompileCommandsError),
+    /// A name given to [`Builder::header_source`] was absolute or went up
+    /// out of the directory the header is written to.
+    InvalidHeaderSourceName(String),
 }
 
 impl Display for BuilderError {
@@ -49,6 +60,9 @@ impl Display for BuilderError {
             BuilderError::FileWriteFail(ee, pb) => write!(f, "Unable to write to {}: {}", pb.to_string_lossy(), ee)?,
             BuilderError::NoIncludeCxxMacrosFound => write!(f, "No include_cpp! macro found")?,
             BuilderError::UnableToCreateDirectory(ee, pb) => write!(f, "Unable to create directory {}: {}", pb.to_string_lossy(), ee)?,
+            BuilderError::ConflictingDefine(name) => write!(f, "The preprocessor symbol {} was given conflicting definitions, so the bindings could not be guaranteed to match the compiled C++.", name)?,
+            BuilderError::CompileCommands(ee) => write!(f, "{}", ee)?,
+            BuilderError::InvalidHeaderSourceName(name) => write!(f, "The header name {} must be a relative path with no .. in it.", name)?,
         }
         Ok(())
     }
@@ -87,8 +101,11 @@ pub struct Builder<BuilderContext> {
     rs_file: PathBuf,
     autocxx_incs: Vec<OsString>,
     extra_clang_args: Vec<String>,
+    defines: Vec<(String, Option<String>)>,
+    compile_commands: Option<(PathBuf, PathBuf)>,
     dependency_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
     custom_gendir: Option<PathBuf>,
+    header_sources: Vec<(String, String)>,
     auto_allowlist: bool,
     cpp_codegen_options: CppCodegenOptions,
     // This member is to ensure that this type is parameterized
@@ -115,10 +132,16 @@ impl<CTX: BuilderContext> Builder<CTX> {
                 .map(|s| s.as_ref().to_os_string())
                 .collect(),
             extra_clang_args: Vec::new(),
+            defines: Vec::new(),
+            compile_commands: None,
             dependency_recorder: CTX::get_dependency_recorder(),
             custom_gendir: None,
+            header_sources: Vec::new(),
             auto_allowlist: false,
-            cpp_codegen_options: CppCodegenOptions::default(),
+            cpp_codegen_options: CppCodegenOptions {
+                trace_calls: cfg!(feature = "trace_calls"),
+                ..Default::default()
+            },
             ctx: PhantomData,
         }
     }
@@ -129,12 +152,63 @@ impl<CTX: BuilderContext> Builder<CTX> {
         self
     }
 
+    /// Define a preprocessor symbol, as if by `-Dvar=val` (or `-Dvar` if
+    /// `val` is `None`). Unlike a `-D` passed to [`Builder::extra_clang_args`]
+    /// this is honored both when analyzing the headers and when compiling
+    /// the generated C++ using the returned [`cc::Build`], so that
+    /// APIs behind `#ifdef`s are bound exactly when they're compiled.
+    /// The generated header also checks that the symbol is still defined
+    /// when it's eventually compiled.
+    pub fn define(mut self, var: &str, val: Option<&str>) -> Self {
+        self.defines
+            .push((var.to_string(), val.map(|val| val.to_string())));
+        self
+    }
+
+    /// Take include paths, preprocessor definitions and the language
+    /// standard from the entry for `source_file` in the JSON compilation
+    /// database at `db_path` (as generated by CMake's
+    /// `CMAKE_EXPORT_COMPILE_COMMANDS`, for example), so that the headers
+    /// are analyzed with exactly the flags used by the real build.
+    /// The include paths and definitions are also applied to the returned
+    /// [`cc::Build`], and definitions are treated as if passed to
+    /// [`Builder::define`]. A relative `source_file` matches any entry
+    /// whose file ends with those path components. If several entries
+    /// match, they must agree about these flags.
+    pub fn compile_commands(
+        mut self,
+        db_path: impl AsRef<Path>,
+        source_file: impl AsRef<Path>,
+    ) -> Self {
+        self.compile_commands = Some((
+            db_path.as_ref().to_path_buf(),
+            source_file.as_ref().to_path_buf(),
+        ));
+        self
+    }
+
     /// Where to generate the code.
     pub fn custom_gendir(mut self, custom_gendir: PathBuf) -> Self {
         self.custom_gendir = Some(custom_gendir);
         self
     }
 
+    /// Provide the contents of a header which isn't on disk, such as one a
+    /// tool has just synthesized, under the given name. It's written out
+    /// alongside the generated headers, so `include_cpp!` and other headers
+    /// may `#include` it by that name, both when analyzing the headers and
+    /// when compiling the generated C++; and `#include`s within it are
+    /// found on the usual include paths.
+    ///
+    /// The name may include directories, but must be relative and mustn't
+    /// contain `..`, so that the header stays within the generated code's
+    /// directory; otherwise the build fails.
+    pub fn header_source(mut self, name: &str, source: &str) -> Self {
+        self.header_sources
+            .push((name.to_string(), source.to_string()));
+        self
+    }
+
     /// Update C++ code generation options. See [`CppCodegenOptions`] for details.
     pub fn cpp_codegen_options<F>(mut self, modifier: F) -> Self
     where
@@ -144,6 +218,29 @@ impl<CTX: BuilderContext> Builder<CTX> {
         self
     }
 
+    /// Whether to generate code which logs every call into C++. The actual
+    /// logging is only switched on if the `autocxx` crate's `trace_calls`
+    /// feature is also enabled. This defaults to true if this crate's own
+    /// `trace_calls` feature is enabled.
+    pub fn trace_calls(mut self, do_it: bool) -> Self {
+        self.cpp_codegen_options.trace_calls = do_it;
+        self
+    }
+
+    /// Rename C++ methods using the given function, which is passed the
+    /// Rust name bindgen gave each method. That's usually its C++ name, but
+    /// one which is a Rust keyword has had `_` appended. For instance, pass [`crate::to_snake_case`] to
+    /// call `GetValue` as `get_value`. Constructors and operators are
+    /// left alone. If two methods of the same type end up with the same
+    /// name, bindings to the second are skipped, with an explanation.
+    pub fn rename_methods<F>(mut self, rename: F) -> Self
+    where
+        F: Fn(&str) -> String + 'static,
+    {
+        self.cpp_codegen_options.rename_methods = Some(Box::new(rename));
+        self
+    }
+
     /// Automatically discover uses of the C++ `ffi` mod and generate the allowlist
     /// from that.
     /// This is a highly experimental option, not currently recommended.
@@ -172,6 +269,15 @@ impl<CTX: BuilderContext> Builder<CTX> {
         self
     }
 
+    /// Whether to suppress the `static_assert`s in generated C++ bindings
+    /// code which check that `bool` and `char` match the size and
+    /// signedness of Rust's `bool` and `c_char` for the target. This should
+    /// only be needed if autocxx doesn't know the target's rules.
+    pub fn suppress_abi_checks(mut self, do_it: bool) -> Self {
+        self.cpp_codegen_options.suppress_abi_checks = do_it;
+        self
+    }
+
     /// An annotation optionally to include on each C++ function.
     /// For example to export the symbol from a library.
     pub fn cxx_impl_annotations(mut self, cxx_impl_annotations: Option<String>) -> Self {
@@ -194,12 +300,34 @@ impl<CTX: BuilderContext> Builder<CTX> {
 
     /// For use in tests only, this does the build and returns additional information
     /// about the files generated which can subsequently be examined for correctness.
-    pub fn build_listing_files(self) -> Result<BuilderSuccess, BuilderError> {
+    pub fn build_listing_files(mut self) -> Result<BuilderSuccess, BuilderError> {
+        if let Some((db_path, source_file)) = &self.compile_commands {
+            let flags =
+                read_compile_flags(db_path, source_file).map_err(BuilderError::CompileCommands)?;
+            self.autocxx_incs
+                .extend(flags.include_dirs.into_iter().map(PathBuf::into_os_string));
+            self.defines.extend(flags.defines);
+            self.extra_clang_args.extend(flags.other_args);
+        }
+        check_defines_consistent(&self.defines, &self.extra_clang_args)?;
+        check_header_source_names(&self.header_sources)?;
+        let define_args: Vec<_> = self
+            .defines
+            .iter()
+            .map(|(var, val)| match val {
+                Some(val) => format!("-D{}={}", var, val),
+                None => format!("-D{}", var),
+            })
+            .collect();
         let clang_args = &self
             .extra_clang_args
             .iter()
+            .chain(define_args.iter())
             .map(|s| &s[..])
             .collect::<Vec<_>>();
+        self.cpp_codegen_options
+            .required_defines
+            .extend(self.defines.iter().cloned());
         rust_version_check();
         let gen_location_strategy = match self.custom_gendir {
             None => FileLocationStrategy::new(),
@@ -222,6 +350,15 @@ impl<CTX: BuilderContext> Builder<CTX> {
             &Self::get_cxx_header_bytes(self.cpp_codegen_options.suppress_system_headers),
         )?;
 
+        for (name, source) in &self.header_sources {
+            let path = incdir.join(name);
+            if let Some(parent) = path.parent() {
+                ensure_created(parent)?;
+            }
+            try_write_to_file(&path, source.as_bytes())
+                .map_err(|e| BuilderError::FileWriteFail(e, path.clone()))?;
+        }
+
         let autocxx_inc = build_autocxx_inc(self.autocxx_incs, &incdir);
         gen_location_strategy.set_cargo_env_vars_for_build();
 
@@ -238,6 +375,9 @@ impl<CTX: BuilderContext> Builder<CTX> {
         let mut counter = 0;
         let mut builder = cc::Build::new();
         builder.cpp(true);
+        for (var, val) in &self.defines {
+            builder.define(var, val.as_deref());
+        }
         let mut generated_rs = Vec::new();
         let mut generated_cpp = Vec::new();
         builder.includes(parsed_file.include_dirs());
@@ -287,6 +427,55 @@ impl<CTX: BuilderContext> Builder<CTX> {
     }
 }
 
+/// Ensure that no preprocessor symbol is defined in two different ways,
+/// since then we can't be sure which definition the C++ will be built with.
+fn check_defines_consistent(
+    defines: &[(String, Option<String>)],
+    extra_clang_args: &[String],
+) -> Result<(), BuilderError> {
+    let mut seen = std::collections::HashMap::new();
+    // A definition may be given as `-DFOO` or as `-D FOO`.
+    let mut args = extra_clang_args.iter();
+    let mut from_clang_args = Vec::new();
+    while let Some(arg) = args.next() {
+        let def = match arg.strip_prefix("-D") {
+            Some("") => args.next().map(String::as_str),
+            def => def,
+        };
+        if let Some(def) = def {
+            from_clang_args.push(match def.split_once('=') {
+                Some((var, val)) => (var.to_string(), Some(val.to_string())),
+                None => (def.to_string(), None),
+            });
+        }
+    }
+    for (var, val) in from_clang_args.into_iter().chain(defines.iter().cloned()) {
+        match seen.get(&var) {
+            Some(previous) if *previous != val => return Err(BuilderError::ConflictingDefine(var)),
+            _ => {
+                seen.insert(var, val);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Headers given to [`Builder::header_source`] are written beneath the
+/// include directory, and mustn't be able to overwrite anything else.
+fn check_header_source_names(header_sources: &[(String, String)]) -> Result<(), BuilderError> {
+    for (name, _) in header_sources {
+        let path = Path::new(name);
+        let within_incdir = path.file_name().is_some()
+            && path
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !within_incdir {
+            return Err(BuilderError::InvalidHeaderSourceName(name.clone()));
+        }
+    }
+    Ok(())
+}
+
 fn ensure_created(dir: &Path) -> Result<(), BuilderError> {
     std::fs::create_dir_all(dir)
         .map_err(|e| BuilderError::UnableToCreateDirectory(e, dir.to_path_buf()))
@@ -328,3 +517,38 @@ fn rust_version_check() {
         panic!("Rust 1.54 or later is required.")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_defines_consistent, check_header_source_names};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_defines_consistent() {
+        let defines = vec![("FOO".to_string(), Some("1".to_string()))];
+        assert!(check_defines_consistent(&defines, &args(&["-DFOO=1", "-DBAR"])).is_ok());
+        assert!(check_defines_consistent(&defines, &args(&["-D", "FOO=1", "-I", "inc"])).is_ok());
+        assert!(check_defines_consistent(&defines, &args(&["-DFOO=2"])).is_err());
+        assert!(check_defines_consistent(&defines, &args(&["-D", "FOO=2"])).is_err());
+        assert!(check_defines_consistent(&defines, &args(&["-D", "FOO"])).is_err());
+        assert!(check_defines_consistent(&[], &args(&["-D", "BAR", "-DBAR"])).is_ok());
+        assert!(check_defines_consistent(&[], &args(&["-D", "BAR", "-DBAR=0"])).is_err());
+    }
+
+    fn header_named(name: &str) -> Vec<(String, String)> {
+        vec![(name.to_string(), String::new())]
+    }
+
+    #[test]
+    fn test_header_source_names() {
+        assert!(check_header_source_names(&header_named("synth.h")).is_ok());
+        assert!(check_header_source_names(&header_named("gen/synth.h")).is_ok());
+        assert!(check_header_source_names(&header_named("")).is_err());
+        assert!(check_header_source_names(&header_named("/etc/synth.h")).is_err());
+        assert!(check_header_source_names(&header_named("../synth.h")).is_err());
+        assert!(check_header_source_names(&header_named("gen/../../synth.h")).is_err());
+    }
+}
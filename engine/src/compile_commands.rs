@@ -0,0 +1,300 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Extraction of the compiler flags relevant to header analysis from
+//! a [JSON compilation database](https://clang.llvm.org/docs/JSONCompilationDatabase.html).
+
+use std::fmt::Display;
+use std::path::{Component, Path, PathBuf};
+
+use serde_json::Value;
+
+/// Errors reading flags from a `compile_commands.json`.
+#[derive(Debug)]
+pub enum CompileCommandsError {
+    /// The database couldn't be read.
+    Io(std::io::Error, PathBuf),
+    /// The database wasn't valid JSON, or wasn't in the expected format.
+    Malformed(String),
+    /// No entry in the database was for the requested source file.
+    NoEntry(PathBuf),
+    /// Several entries were for the requested source file, and they
+    /// disagreed about the flags to use.
+    ConflictingEntries(PathBuf),
+}
+
+impl Display for CompileCommandsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileCommandsError::Io(err, path) => write!(f, "Unable to read compilation database {}: {}", path.to_string_lossy(), err)?,
+            CompileCommandsError::Malformed(msg) => write!(f, "The compilation database was malformed: {}", msg)?,
+            CompileCommandsError::NoEntry(path) => write!(f, "The compilation database had no entry for {}", path.to_string_lossy())?,
+            CompileCommandsError::ConflictingEntries(path) => write!(f, "The compilation database had several entries for {} with different flags, so it's not clear which to use", path.to_string_lossy())?,
+        }
+        Ok(())
+    }
+}
+
+/// The flags from a compilation database entry which affect how headers
+/// are interpreted.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CompileFlags {
+    /// Include directories, made absolute.
+    pub(crate) include_dirs: Vec<PathBuf>,
+    /// Preprocessor symbols defined by `-D`.
+    pub(crate) defines: Vec<(String, Option<String>)>,
+    /// Other flags to pass to clang, such as `-std=c++17` or `-U` or
+    /// `-include`, with any paths made absolute.
+    pub(crate) other_args: Vec<String>,
+}
+
+/// Reads the flags for `source_file` from the database at `db_path`.
+/// A relative `source_file` is matched against the trailing components
+/// of each entry's file, so `src/foo.cc` matches `/work/proj/src/foo.cc`.
+pub(crate) fn read_compile_flags(
+    db_path: &Path,
+    source_file: &Path,
+) -> Result<CompileFlags, CompileCommandsError> {
+    let content = std::fs::read_to_string(db_path)
+        .map_err(|e| CompileCommandsError::Io(e, db_path.to_path_buf()))?;
+    let db_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    flags_from_database(&content, db_dir, source_file)
+}
+
+fn flags_from_database(
+    content: &str,
+    db_dir: &Path,
+    source_file: &Path,
+) -> Result<CompileFlags, CompileCommandsError> {
+    let db: Value = serde_json::from_str(content)
+        .map_err(|e| CompileCommandsError::Malformed(e.to_string()))?;
+    let entries = db
+        .as_array()
+        .ok_or_else(|| CompileCommandsError::Malformed("expected an array of entries".into()))?;
+    let mut found: Option<CompileFlags> = None;
+    for entry in entries {
+        let field = |name: &str| entry.get(name).and_then(Value::as_str);
+        let directory = field("directory")
+            .ok_or_else(|| CompileCommandsError::Malformed("an entry had no 'directory'".into()))?;
+        // The database's directories are allowed to be relative to the
+        // database itself.
+        let directory = normalize(&db_dir.join(directory));
+        let file = field("file")
+            .ok_or_else(|| CompileCommandsError::Malformed("an entry had no 'file'".into()))?;
+        let file = normalize(&directory.join(file));
+        if !file_matches(&file, source_file) {
+            continue;
+        }
+        let args = match (entry.get("arguments"), field("command")) {
+            (Some(Value::Array(args)), _) => args
+                .iter()
+                .map(|arg| {
+                    arg.as_str().map(str::to_string).ok_or_else(|| {
+                        CompileCommandsError::Malformed("an argument wasn't a string".into())
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            (_, Some(command)) => split_command(command),
+            _ => {
+                return Err(CompileCommandsError::Malformed(
+                    "an entry had neither 'arguments' nor 'command'".into(),
+                ))
+            }
+        };
+        let flags = extract_flags(&args, &directory);
+        match &found {
+            Some(previous) if *previous != flags => {
+                return Err(CompileCommandsError::ConflictingEntries(
+                    source_file.to_path_buf(),
+                ))
+            }
+            _ => found = Some(flags),
+        }
+    }
+    found.ok_or_else(|| CompileCommandsError::NoEntry(source_file.to_path_buf()))
+}
+
+fn file_matches(file: &Path, source_file: &Path) -> bool {
+    if source_file.is_absolute() {
+        file == normalize(source_file)
+    } else {
+        file.ends_with(normalize(source_file))
+    }
+}
+
+/// Lexically removes `.` and `..` components. We don't canonicalize
+/// because the database may describe files which aren't present here.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push(component);
+                }
+            }
+            _ => result.push(component),
+        }
+    }
+    result
+}
+
+/// Splits a shell command line into arguments, honoring quotes and
+/// backslash escapes as a POSIX shell would.
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_arg = true;
+            }
+            (_, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+fn extract_flags(args: &[String], directory: &Path) -> CompileFlags {
+    let absolute = |path: &str| normalize(&directory.join(path));
+    let mut flags = CompileFlags::default();
+    // The first argument is the compiler itself.
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        // Flags which may take their value either attached or as the next
+        // argument.
+        let mut split_flag = |flag: &str| -> Option<String> {
+            if arg == flag {
+                args.next().cloned()
+            } else {
+                arg.strip_prefix(flag).map(str::to_string)
+            }
+        };
+        if let Some(dir) = split_flag("-I") {
+            flags.include_dirs.push(absolute(&dir));
+        } else if let Some(def) = split_flag("-D") {
+            flags.defines.push(match def.split_once('=') {
+                Some((var, val)) => (var.to_string(), Some(val.to_string())),
+                None => (def, None),
+            });
+        } else if let Some(undef) = split_flag("-U") {
+            flags.other_args.push(format!("-U{}", undef));
+        } else if let Some(dir) = split_flag("-isystem") {
+            flags.other_args.push("-isystem".into());
+            flags
+                .other_args
+                .push(absolute(&dir).to_string_lossy().into_owned());
+        } else if let Some(dir) = split_flag("-iquote") {
+            flags.other_args.push("-iquote".into());
+            flags
+                .other_args
+                .push(absolute(&dir).to_string_lossy().into_owned());
+        } else if arg == "-include" {
+            if let Some(file) = args.next() {
+                flags.other_args.push("-include".into());
+                flags
+                    .other_args
+                    .push(absolute(file).to_string_lossy().into_owned());
+            }
+        } else if arg.starts_with("-std=") {
+            flags.other_args.push(arg.clone());
+        }
+        // Anything else (optimization, warnings, output files, the source
+        // file itself) doesn't affect the APIs we see in the headers.
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{flags_from_database, split_command, CompileCommandsError};
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command(r#"c++ -DNAME="a b" 'x y' z\ w"#),
+            vec!["c++", "-DNAME=a b", "x y", "z w"]
+        );
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let db = r#"[
+            {
+                "directory": "build",
+                "command": "c++ -I../include -I /abs/inc -isystem third_party -DFOO=1 -DBAR -std=c++17 -O2 -c ../src/a.cc -o a.o",
+                "file": "../src/a.cc"
+            }
+        ]"#;
+        let flags = flags_from_database(db, Path::new("/proj"), Path::new("src/a.cc")).unwrap();
+        assert_eq!(
+            flags.include_dirs,
+            vec![PathBuf::from("/proj/include"), PathBuf::from("/abs/inc")]
+        );
+        assert_eq!(
+            flags.defines,
+            vec![("FOO".into(), Some("1".into())), ("BAR".into(), None)]
+        );
+        assert_eq!(
+            flags.other_args,
+            vec!["-isystem", "/proj/build/third_party", "-std=c++17"]
+        );
+    }
+
+    #[test]
+    fn test_multiple_entries() {
+        let db = r#"[
+            { "directory": "/p", "arguments": ["c++", "-DX=1", "a.cc"], "file": "a.cc" },
+            { "directory": "/p", "arguments": ["c++", "-DX=1", "-O0", "a.cc"], "file": "a.cc" },
+            { "directory": "/p", "arguments": ["c++", "-DX=2", "b.cc"], "file": "b.cc" },
+            { "directory": "/p", "arguments": ["c++", "-DX=3", "b.cc"], "file": "b.cc" }
+        ]"#;
+        let flags = flags_from_database(db, Path::new("/"), Path::new("/p/a.cc")).unwrap();
+        assert_eq!(flags.defines, vec![("X".into(), Some("1".into()))]);
+        assert!(matches!(
+            flags_from_database(db, Path::new("/"), Path::new("b.cc")),
+            Err(CompileCommandsError::ConflictingEntries(_))
+        ));
+        assert!(matches!(
+            flags_from_database(db, Path::new("/"), Path::new("c.cc")),
+            Err(CompileCommandsError::NoEntry(_))
+        ));
+    }
+}
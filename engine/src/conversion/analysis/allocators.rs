@@ -93,6 +93,7 @@ fn create_alloc_and_free(ty_name: QualifiedName) -> impl Iterator<Item = Api<Pod
                     synthetic_cpp: Some((cpp_function_body, CppFunctionKind::Function)),
                     add_to_trait: Some(synthesis),
                     is_deleted: false,
+                    link_name: None,
                     provenance: Provenance::SynthesizedOther,
                 }),
                 analysis: (),
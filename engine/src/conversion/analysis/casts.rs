@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{HashMap, HashSet};
+
 use itertools::Itertools;
 use quote::quote;
 use syn::{parse_quote, FnArg};
@@ -33,7 +35,14 @@ use super::{
     pod::{PodAnalysis, PodPhase},
 };
 
-pub(crate) fn add_casts(apis: Vec<Api<PodPhase>>) -> Vec<Api<PodPhase>> {
+/// Adds casts from each type to its bases and, if `rtti` is set, downcasts
+/// back from any of those bases which are polymorphic.
+pub(crate) fn add_casts(apis: Vec<Api<PodPhase>>, rtti: bool) -> Vec<Api<PodPhase>> {
+    let polymorphic_types = if rtti {
+        find_polymorphic_types(&apis)
+    } else {
+        HashSet::new()
+    };
     apis.into_iter()
         .flat_map(|api| {
             let mut resultant_apis = match api {
@@ -41,7 +50,9 @@ pub(crate) fn add_casts(apis: Vec<Api<PodPhase>>) -> Vec<Api<PodPhase>> {
                     ref name,
                     details: _,
                     ref analysis,
-                } => create_casts(&name.name, analysis).collect_vec(),
+                } => create_casts(&name.name, analysis)
+                    .chain(create_downcasts(&name.name, analysis, &polymorphic_types))
+                    .collect_vec(),
                 _ => Vec::new(),
             };
             resultant_apis.push(api);
@@ -62,6 +73,59 @@ fn create_casts<'a>(
         .flat_map(move |base| cast_types().map(|mutable| create_cast(name, base, mutable)))
 }
 
+/// Types which have a vtable, either of their own or from a base.
+fn find_polymorphic_types(apis: &[Api<PodPhase>]) -> HashSet<QualifiedName> {
+    let bases_by_type: HashMap<_, _> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. } => Some((&name.name, analysis)),
+            _ => None,
+        })
+        .collect();
+    let mut polymorphic_types: HashSet<_> = bases_by_type
+        .iter()
+        .filter(|(_, analysis)| analysis.has_vtable)
+        .map(|(name, _)| (*name).clone())
+        .collect();
+    // Bases may come after the types derived from them, so keep going
+    // until we find nothing new.
+    loop {
+        let newly_polymorphic = bases_by_type
+            .iter()
+            .filter(|(name, analysis)| {
+                !polymorphic_types.contains(**name)
+                    && analysis
+                        .bases
+                        .iter()
+                        .any(|base| polymorphic_types.contains(base))
+            })
+            .map(|(name, _)| (*name).clone())
+            .collect_vec();
+        if newly_polymorphic.is_empty() {
+            break;
+        }
+        polymorphic_types.extend(newly_polymorphic);
+    }
+    polymorphic_types
+}
+
+/// `dynamic_cast` can only downcast from a polymorphic type.
+fn create_downcasts<'a>(
+    name: &'a QualifiedName,
+    analysis: &'a PodAnalysis,
+    polymorphic_types: &'a HashSet<QualifiedName>,
+) -> impl Iterator<Item = Api<PodPhase>> + 'a {
+    analysis
+        .castable_bases
+        .iter()
+        .filter(move |base| polymorphic_types.contains(base))
+        .flat_map(move |base| {
+            [CastMutability::ConstToConst, CastMutability::MutToMut]
+                .into_iter()
+                .map(move |mutable| create_downcast(base, name, mutable))
+        })
+}
+
 /// Iterate through the types of cast we should make.
 fn cast_types() -> impl Iterator<Item = CastMutability> {
     if SUPPORT_MUTABLE_CASTS {
@@ -117,6 +181,69 @@ fn create_cast(from: &QualifiedName, to: &QualifiedName, mutable: CastMutability
             }),
             synthetic_cpp: Some((CppFunctionBody::Cast, CppFunctionKind::Function)),
             is_deleted: false,
+            link_name: None,
+            provenance: Provenance::SynthesizedOther,
+        }),
+        analysis: (),
+    }
+}
+
+fn create_downcast(
+    from: &QualifiedName,
+    to: &QualifiedName,
+    mutable: CastMutability,
+) -> Api<PodPhase> {
+    let is_mut = matches!(mutable, CastMutability::MutToMut);
+    let suffix = if is_mut { "_mut" } else { "" };
+    let name = make_ident(format!(
+        "downcast_{}_to_{}{}",
+        from.get_final_item(),
+        to.get_final_item(),
+        suffix
+    ));
+    let name = QualifiedName::new(to.get_namespace(), name);
+    let ident = name.get_final_ident();
+    let from_typ = from.to_type_path();
+    let to_typ = to.to_type_path();
+    let mutability = if is_mut {
+        quote! { mut }
+    } else {
+        quote! { const }
+    };
+    // Unlike the parameter, the returned pointer may be null, so stays a
+    // pointer.
+    Api::Function {
+        name: ApiName::new_from_qualified_name(name),
+        name_for_gc: None,
+        fun: Box::new(crate::conversion::api::FuncToConvert {
+            ident,
+            doc_attr: None,
+            inputs: parse_quote! { this: * #mutability #from_typ },
+            output: parse_quote! {
+                -> * #mutability #to_typ
+            },
+            vis: parse_quote! { pub },
+            virtualness: crate::conversion::api::Virtualness::None,
+            cpp_vis: crate::conversion::api::CppVisibility::Public,
+            special_member: None,
+            unused_template_param: false,
+            references: References {
+                ref_params: [make_ident("this")].into_iter().collect(),
+                ..Default::default()
+            },
+            original_name: None,
+            self_ty: Some(from.clone()),
+            synthesized_this_type: None,
+            add_to_trait: Some(TraitSynthesis::Downcast {
+                to_type: to.clone(),
+                mutable,
+            }),
+            synthetic_cpp: Some((
+                CppFunctionBody::DynamicCast(to.clone(), is_mut),
+                CppFunctionKind::Function,
+            )),
+            is_deleted: false,
+            link_name: None,
             provenance: Provenance::SynthesizedOther,
         }),
         analysis: (),
@@ -0,0 +1,169 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Code to create methods which call the function pointers held in the
+//! fields of structs listed in `fn_table!`.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{
+    parse_quote, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, PathArguments,
+    ReturnType, Type, TypeBareFn,
+};
+
+use crate::{
+    conversion::api::{Api, ApiName, CppVisibility, FuncToConvert, Provenance, References},
+    types::{make_ident, validate_ident_ok_for_rust, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+pub(crate) fn create_fn_table_functions(
+    apis: Vec<Api<PodPhase>>,
+    config: &IncludeCppConfig,
+) -> Vec<Api<PodPhase>> {
+    apis.into_iter()
+        .flat_map(|api| -> Box<dyn Iterator<Item = Api<PodPhase>>> {
+            match &api {
+                Api::Struct { name, details, .. }
+                    if config.is_fn_table(&name.name.to_cpp_name()) =>
+                {
+                    let functions: Vec<_> = details
+                        .item
+                        .fields
+                        .iter()
+                        .filter_map(|f| {
+                            let fn_ptr = as_fn_ptr(&f.ty)?;
+                            Some((f.ident.as_ref()?, fn_ptr))
+                        })
+                        .flat_map(|(field, fn_ptr)| {
+                            create_has_and_call(name.name.clone(), &cpp_field_name(field), fn_ptr)
+                        })
+                        .collect();
+                    Box::new(functions.into_iter().chain(std::iter::once(api)))
+                }
+                _ => Box::new(std::iter::once(api)),
+            }
+        })
+        .collect()
+}
+
+/// The function type of a field which holds a function pointer. bindgen
+/// makes these `Option`s, since they may be null. Variadic functions are
+/// left alone, since we can't call them.
+fn as_fn_ptr(ty: &Type) -> Option<&TypeBareFn> {
+    let fn_ptr = match ty {
+        Type::BareFn(fn_ptr) => Some(fn_ptr),
+        Type::Path(typ) => {
+            let seg = typ.path.segments.last()?;
+            match &seg.arguments {
+                PathArguments::AngleBracketed(args) if seg.ident == "Option" => {
+                    match args.args.first() {
+                        Some(GenericArgument::Type(Type::BareFn(fn_ptr))) => Some(fn_ptr),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }?;
+    if fn_ptr.variadic.is_some() {
+        None
+    } else {
+        Some(fn_ptr)
+    }
+}
+
+/// bindgen adds an underscore to fields whose names are Rust keywords.
+fn cpp_field_name(field: &syn::Ident) -> String {
+    let field = field.to_string();
+    match field.strip_suffix('_') {
+        Some(stripped) if validate_ident_ok_for_rust(stripped).is_err() => stripped.to_string(),
+        _ => field,
+    }
+}
+
+fn create_has_and_call(
+    ty_name: QualifiedName,
+    field: &str,
+    fn_ptr: &TypeBareFn,
+) -> impl Iterator<Item = Api<PodPhase>> {
+    let typ = ty_name.to_type_path();
+    let mut call_inputs: Punctuated<FnArg, Comma> = parse_quote! { this: *const #typ };
+    for (counter, arg) in fn_ptr.inputs.iter().enumerate() {
+        let arg_name = match &arg.name {
+            Some((id, _)) => id.clone(),
+            None => make_ident(format!("arg{}", counter)),
+        };
+        let arg_ty = &arg.ty;
+        call_inputs.push(parse_quote! { #arg_name: #arg_ty });
+    }
+    let call_output = match &fn_ptr.output {
+        ReturnType::Default => ReturnType::Default,
+        ReturnType::Type(arrow, ty) => ReturnType::Type(*arrow, ty.clone()),
+    };
+    [
+        (
+            format!("has_{}", field),
+            parse_quote! { this: *const #typ },
+            parse_quote! { -> bool },
+            CppFunctionBody::FunctionPointerIsSet(field.to_string()),
+        ),
+        (
+            field.to_string(),
+            call_inputs,
+            call_output,
+            CppFunctionBody::FunctionPointerCall(field.to_string()),
+        ),
+    ]
+    .into_iter()
+    .map(move |(cpp_name, inputs, output, payload)| {
+        let ident = make_ident(format!("{}_{}", ty_name.get_final_item(), cpp_name));
+        Api::Function {
+            name: ApiName::new_with_cpp_name(
+                ty_name.get_namespace(),
+                ident.clone(),
+                Some(cpp_name),
+            ),
+            name_for_gc: None,
+            fun: Box::new(FuncToConvert {
+                ident,
+                doc_attr: None,
+                inputs,
+                output,
+                vis: parse_quote! { pub },
+                virtualness: crate::conversion::api::Virtualness::None,
+                cpp_vis: CppVisibility::Public,
+                special_member: None,
+                unused_template_param: false,
+                references: References {
+                    ref_params: [make_ident("this")].into_iter().collect(),
+                    ..Default::default()
+                },
+                original_name: None,
+                self_ty: Some(ty_name.clone()),
+                synthesized_this_type: None,
+                synthetic_cpp: Some((payload, CppFunctionKind::Function)),
+                add_to_trait: None,
+                is_deleted: false,
+                link_name: None,
+                provenance: Provenance::SynthesizedOther,
+            }),
+            analysis: (),
+        }
+    })
+}
@@ -0,0 +1,151 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use quote::ToTokens;
+use syn::{FnArg, Type};
+
+use crate::{
+    conversion::{analysis::pod::PodPhase, api::Api},
+    types::QualifiedName,
+};
+
+/// Suffix given to the Rust name of the more-const member of a pair of
+/// overloads which differ only in the const-ness of their parameters.
+pub(super) const CONST_OVERLOAD_SUFFIX: &str = "_const";
+
+/// Finds pairs of overloads which differ only in the const-ness of
+/// pointer or reference parameters (including the receiver), for example
+/// `void take(const A&)` and `void take(A&)`. Our normal numeric overload
+/// suffix would make it impossible to tell which is which, so instead the
+/// more-const of each pair is given a `_const` suffix. Returns the names
+/// of those more-const overloads.
+///
+/// Overloads which differ in any other way (e.g. a further by-value
+/// overload) keep the normal numeric suffixes. If a group of overloads
+/// can't be unambiguously split into a mutable and a const member, we
+/// leave it alone.
+pub(super) fn find_const_overloads(apis: &[Api<PodPhase>]) -> HashSet<QualifiedName> {
+    let mut groups: HashMap<String, Vec<(&QualifiedName, Vec<bool>)>> = HashMap::new();
+    for api in apis {
+        if let Api::Function { name, fun, .. } = api {
+            if fun.special_member.is_some()
+                || fun.synthetic_cpp.is_some()
+                || fun.add_to_trait.is_some()
+            {
+                continue;
+            }
+            let cpp_name = name
+                .cpp_name_if_present()
+                .cloned()
+                .unwrap_or_else(|| name.name.get_final_item().to_string());
+            let mut constness = Vec::new();
+            let erased_params: Vec<_> = fun
+                .inputs
+                .iter()
+                .map(|arg| match arg {
+                    FnArg::Typed(pt) => erase_constness(&pt.ty, &mut constness)
+                        .to_token_stream()
+                        .to_string(),
+                    FnArg::Receiver(_) => "self".to_string(),
+                })
+                .collect();
+            let self_ty = fun
+                .self_ty
+                .as_ref()
+                .map(|ty| ty.to_cpp_name())
+                .unwrap_or_default();
+            let key = format!(
+                "{}|{}|{}|{}",
+                name.name.get_namespace(),
+                self_ty,
+                cpp_name,
+                erased_params.join(",")
+            );
+            groups.entry(key).or_default().push((&name.name, constness));
+        }
+    }
+    groups
+        .into_values()
+        .filter_map(|group| match group.as_slice() {
+            [(a, a_const), (b, b_const)] => {
+                if more_const(a_const, b_const) {
+                    Some((*a).clone())
+                } else if more_const(b_const, a_const) {
+                    Some((*b).clone())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `a` is const everywhere `b` is, and somewhere else besides.
+fn more_const(a: &[bool], b: &[bool]) -> bool {
+    a != b && a.iter().zip(b.iter()).all(|(a, b)| *a || !*b)
+}
+
+/// Returns the type with all pointers and references made mutable,
+/// recording in `constness` whether each one was originally const.
+fn erase_constness(ty: &Type, constness: &mut Vec<bool>) -> Type {
+    match ty {
+        Type::Ptr(ptr) => {
+            constness.push(ptr.const_token.is_some());
+            let mut ptr = ptr.clone();
+            ptr.const_token = None;
+            ptr.mutability = Some(Default::default());
+            ptr.elem = Box::new(erase_constness(&ptr.elem, constness));
+            Type::Ptr(ptr)
+        }
+        Type::Reference(rf) => {
+            constness.push(rf.mutability.is_none());
+            let mut rf = rf.clone();
+            rf.mutability = Some(Default::default());
+            rf.elem = Box::new(erase_constness(&rf.elem, constness));
+            Type::Reference(rf)
+        }
+        _ => ty.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{erase_constness, more_const};
+    use quote::ToTokens;
+    use syn::{parse_quote, Type};
+
+    #[test]
+    fn test_erase_constness() {
+        let mut constness = Vec::new();
+        let ty: Type = parse_quote! { *const *mut A };
+        let erased = erase_constness(&ty, &mut constness);
+        let expected: Type = parse_quote! { *mut *mut A };
+        assert_eq!(
+            erased.to_token_stream().to_string(),
+            expected.to_token_stream().to_string()
+        );
+        assert_eq!(constness, vec![true, false]);
+    }
+
+    #[test]
+    fn test_more_const() {
+        assert!(more_const(&[true, false], &[false, false]));
+        assert!(!more_const(&[false, false], &[true, false]));
+        assert!(!more_const(&[true, false], &[false, true]));
+        assert!(!more_const(&[true], &[true]));
+    }
+}
@@ -14,9 +14,11 @@
 
 use crate::{
     conversion::api::SubclassName,
+    known_types::known_types,
     types::{Namespace, QualifiedName},
 };
-use syn::{parse_quote, Ident, Type};
+use autocxx_parser::{RustPath, StringReturn};
+use syn::{parse_quote, Ident, Type, TypeBareFn, TypePtr};
 
 #[derive(Clone, Debug)]
 pub(crate) enum CppConversionType {
@@ -24,6 +26,36 @@ pub(crate) enum CppConversionType {
     FromUniquePtrToValue,
     FromValueToUniquePtr,
     FromPtrToMove,
+    FromRustStrToStringView,
+    FromStringViewToRustSlice,
+    /// A std::filesystem::path built from the bytes of its native
+    /// representation, as passed by Rust.
+    FromRustSliceToFilesystemPath,
+    FromFilesystemPathToRustVec,
+    /// A std::bitset of the given width built from the 64-bit words
+    /// passed by Rust, least significant first.
+    FromRustSliceToBitset(usize),
+    FromBitsetToRustVec(usize),
+    /// A reference which C++ must pass on as a pointer, for instance
+    /// within a `gsl::not_null`.
+    FromReferenceToPointer,
+    FromPointerToReference,
+    /// A type to which C++ will implicitly convert, for instance a smart
+    /// pointer within a `gsl::not_null`.
+    Implicit,
+    /// A function pointer, passed by Rust as a `void*`.
+    FromVoidPtrToFnPtr,
+    /// A returned `std::unique_ptr<T[]>`, released to Rust as a `T*`.
+    FromUniqueArrayToPtr,
+    /// A reference to a C array of the given length, passed by Rust as a
+    /// pointer to its first element.
+    FromPtrToArrayReference(usize),
+    /// A `std::reference_wrapper`, constructed from the reference passed
+    /// by Rust.
+    FromReferenceToReferenceWrapper,
+    /// A `std::function`, constructed from the context of a closure which
+    /// Rust passes as a `void*`, and which it then owns.
+    FromVoidPtrToStdFunction,
 }
 
 impl CppConversionType {
@@ -33,6 +65,31 @@ impl CppConversionType {
             CppConversionType::FromUniquePtrToValue => CppConversionType::FromValueToUniquePtr,
             CppConversionType::FromValueToUniquePtr => CppConversionType::FromUniquePtrToValue,
             CppConversionType::FromPtrToMove => panic!("Did not expect to have to invert move"),
+            CppConversionType::FromReferenceToPointer => CppConversionType::FromPointerToReference,
+            CppConversionType::FromPointerToReference => CppConversionType::FromReferenceToPointer,
+            CppConversionType::Implicit => CppConversionType::Implicit,
+            CppConversionType::FromRustStrToStringView
+            | CppConversionType::FromStringViewToRustSlice => {
+                panic!("Did not expect to have to invert string_view conversion")
+            }
+            CppConversionType::FromRustSliceToFilesystemPath
+            | CppConversionType::FromFilesystemPathToRustVec => {
+                panic!("Did not expect to have to invert filesystem::path conversion")
+            }
+            CppConversionType::FromRustSliceToBitset(_)
+            | CppConversionType::FromBitsetToRustVec(_) => {
+                panic!("Did not expect to have to invert bitset conversion")
+            }
+            CppConversionType::FromVoidPtrToFnPtr | CppConversionType::FromVoidPtrToStdFunction => {
+                panic!("Did not expect to have to invert callback conversion")
+            }
+            CppConversionType::FromUniqueArrayToPtr
+            | CppConversionType::FromPtrToArrayReference(_) => {
+                panic!("Did not expect to have to invert array conversion")
+            }
+            CppConversionType::FromReferenceToReferenceWrapper => {
+                panic!("Did not expect to have to invert reference_wrapper conversion")
+            }
         }
     }
 }
@@ -45,6 +102,57 @@ pub(crate) enum RustConversionType {
     FromPinMaybeUninitToPtr,
     FromPinMoveRefToPtr,
     FromTypeToPtr,
+    /// A pointer which, together with another parameter giving its length,
+    /// the user passes as a slice.
+    FromSliceToPtr,
+    /// The length of the slice passed as the given parameter.
+    FromSliceLen(Ident),
+    /// A `const char*`, which the user passes as anything implementing
+    /// `autocxx::CStrArg`, such as a `&CStr` or a `&str`.
+    FromCStrArg,
+    /// A pointer which the function fills in, such as a pointer to a
+    /// pointer, which Rust returns instead of taking as a parameter.
+    FromOutParam,
+    /// A `&Path`, passed to C++ as the bytes of its native representation.
+    FromPath,
+    /// A returned `Vec` of native path bytes, which the wrapper turns
+    /// into a `PathBuf`.
+    ToPathBuf,
+    /// An `autocxx::bitset::BitSet` of the given width, passed to C++ as
+    /// a slice of its words.
+    FromBitSet(usize),
+    /// A returned `Vec` of words, which the wrapper turns into an
+    /// `autocxx::bitset::BitSet` of the given width.
+    ToBitSet(usize),
+    /// A function pointer which, together with another parameter giving
+    /// the context passed back to it, the user passes as a closure.
+    FromClosureToFnPtr,
+    /// The context for the closure passed as the given parameter.
+    FromClosureContext(Ident),
+    /// A `std::function`, which the user passes as a closure.
+    FromClosureToStdFunction,
+    /// A reference to a Rust array of the given length, passed to C++ as
+    /// a pointer to its first element.
+    FromArrayToPtr(usize),
+    /// A number, which the user may pass as any narrower number which
+    /// C++ would promote to it.
+    FromPromotable,
+    /// A type listed in `type_conversion!`, which the user passes as the
+    /// Rust type given there, or as a reference to it if the flag is set.
+    FromConvertedType(RustPath, bool),
+    /// A returned type listed in `type_conversion!`, which the wrapper
+    /// turns into the Rust type given there.
+    ToConvertedType(RustPath),
+    /// A returned `std::string`, which the wrapper copies into a `String`,
+    /// as requested by `string_return!`.
+    ToString(StringReturn),
+    /// A returned `const char*`, which the wrapper turns into a `&CStr`
+    /// borrowed from the object, as requested by `c_str_return!`.
+    ToCStr,
+    /// The address of the given symbol, as found at runtime by the
+    /// `autocxx::SymbolResolver` at the given path, which the wrapper
+    /// passes instead of taking a parameter.
+    FromResolvedSymbol(RustPath, String),
 }
 
 impl RustConversionType {
@@ -91,6 +199,120 @@ impl TypeConversionPolicy {
         }
     }
 
+    /// A std::string_view parameter, which we accept as a &str in Rust.
+    /// The view is built over the &str's storage, so the Rust data
+    /// must merely outlive the call.
+    pub(crate) fn new_from_str_to_string_view(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromRustStrToStringView,
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
+    /// A returned std::string_view, which we give to Rust as a &[u8]
+    /// because there is no guarantee that the contents are UTF-8.
+    pub(crate) fn new_from_string_view_to_slice(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromStringViewToRustSlice,
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
+    /// A std::filesystem::path parameter, which we accept as a &Path
+    /// in Rust. C++ constructs a temporary path from the native bytes.
+    pub(crate) fn new_from_path_to_filesystem_path(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromRustSliceToFilesystemPath,
+            rust_conversion: RustConversionType::FromPath,
+        }
+    }
+
+    /// A returned std::filesystem::path, which we give to Rust as a
+    /// PathBuf.
+    pub(crate) fn new_from_filesystem_path_to_path_buf(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromFilesystemPathToRustVec,
+            rust_conversion: RustConversionType::ToPathBuf,
+        }
+    }
+
+    /// A std::bitset parameter (via a typedef listed in `bitset!`), which
+    /// we accept as an `autocxx::bitset::BitSet` value in Rust.
+    pub(crate) fn new_from_bitset_to_std_bitset(ty: Type, width: usize) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromRustSliceToBitset(width),
+            rust_conversion: RustConversionType::FromBitSet(width),
+        }
+    }
+
+    /// A returned std::bitset, which we give to Rust as an
+    /// `autocxx::bitset::BitSet`.
+    pub(crate) fn new_from_std_bitset_to_bitset(ty: Type, width: usize) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromBitsetToRustVec(width),
+            rust_conversion: RustConversionType::ToBitSet(width),
+        }
+    }
+
+    /// A function pointer parameter (listed in `closure_callback!`), which
+    /// we accept as a closure in Rust. `ty` is the function pointer type,
+    /// but Rust passes it to C++ as a `void*`.
+    pub(crate) fn new_from_closure_to_callback(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromVoidPtrToFnPtr,
+            rust_conversion: RustConversionType::FromClosureToFnPtr,
+        }
+    }
+
+    /// A `std::function` parameter, which we accept as a closure in Rust.
+    /// `ty` is the function pointer type through which C++ calls the
+    /// closure, without its context, but Rust passes the closure to C++
+    /// as a `void*`.
+    pub(crate) fn new_from_closure_to_std_function(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromVoidPtrToStdFunction,
+            rust_conversion: RustConversionType::FromClosureToStdFunction,
+        }
+    }
+
+    /// A returned `std::unique_ptr<T[]>` (listed in
+    /// `unique_array_return!`). `ty` is the `*mut T` which C++ releases
+    /// it as.
+    pub(crate) fn new_from_unique_array_to_ptr(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromUniqueArrayToPtr,
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
+    /// A reference to a C array, such as `int (&)[8]`, which we accept as
+    /// a reference to a Rust array of the same length. `ty` is the pointer
+    /// to its first element which Rust passes to C++.
+    pub(crate) fn new_from_array_to_array_reference(ty: Type, len: usize) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromPtrToArrayReference(len),
+            rust_conversion: RustConversionType::FromArrayToPtr(len),
+        }
+    }
+
+    pub(crate) fn new_from_reference_to_reference_wrapper(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromReferenceToReferenceWrapper,
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
     pub(crate) fn cpp_work_needed(&self) -> bool {
         !matches!(self.cpp_conversion, CppConversionType::None)
     }
@@ -98,6 +320,9 @@ impl TypeConversionPolicy {
     pub(crate) fn unconverted_rust_type(&self) -> Type {
         match self.cpp_conversion {
             CppConversionType::FromValueToUniquePtr => self.make_unique_ptr_type(),
+            CppConversionType::FromStringViewToRustSlice => parse_quote! { &[u8] },
+            CppConversionType::FromFilesystemPathToRustVec => parse_quote! { Vec<u8> },
+            CppConversionType::FromBitsetToRustVec(_) => parse_quote! { Vec<u64> },
             _ => self.unwrapped_type.clone(),
         }
     }
@@ -105,6 +330,12 @@ impl TypeConversionPolicy {
     pub(crate) fn converted_rust_type(&self) -> Type {
         match self.cpp_conversion {
             CppConversionType::FromUniquePtrToValue => self.make_unique_ptr_type(),
+            CppConversionType::FromRustStrToStringView => parse_quote! { &str },
+            CppConversionType::FromRustSliceToFilesystemPath => parse_quote! { &[u8] },
+            CppConversionType::FromRustSliceToBitset(_) => parse_quote! { &[u64] },
+            CppConversionType::FromVoidPtrToFnPtr | CppConversionType::FromVoidPtrToStdFunction => {
+                parse_quote! { *mut autocxx::c_void }
+            }
             _ => self.unwrapped_type.clone(),
         }
     }
@@ -116,6 +347,15 @@ impl TypeConversionPolicy {
         }
     }
 
+    /// Whether this is a number passed by value, which could instead be
+    /// passed as anything which promotes to it.
+    pub(crate) fn is_promotable(&self) -> bool {
+        matches!(self.cpp_conversion, CppConversionType::None)
+            && matches!(self.rust_conversion, RustConversionType::None)
+            && matches!(&self.unwrapped_type, Type::Path(typ)
+                if known_types().is_promotable(&QualifiedName::from_type_path(typ)))
+    }
+
     pub(crate) fn rust_work_needed(&self) -> bool {
         !matches!(self.rust_conversion, RustConversionType::None)
     }
@@ -129,6 +369,31 @@ impl TypeConversionPolicy {
     }
 }
 
+/// The position of the parameter through which a callback receives its
+/// context: the first `void*`.
+pub(crate) fn callback_context_index(callback: &TypeBareFn) -> Option<usize> {
+    callback.inputs.iter().position(|arg| match &arg.ty {
+        Type::Ptr(TypePtr {
+            mutability: Some(_),
+            elem,
+            ..
+        }) => match elem.as_ref() {
+            Type::Path(typ) => {
+                typ.path.segments.last().map(|seg| seg.ident == "c_void") == Some(true)
+            }
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+/// The `autocxx::bitset::BitSet` with the given number of bits.
+pub(crate) fn bitset_type(width: usize) -> Type {
+    let words = proc_macro2::Literal::usize_unsuffixed((width >> 6) + (width & 63 != 0) as usize);
+    let width = proc_macro2::Literal::usize_unsuffixed(width);
+    parse_quote! { autocxx::bitset::BitSet<#width, #words> }
+}
+
 #[derive(Clone)]
 pub(crate) enum CppFunctionBody {
     FunctionCall(Namespace, Ident),
@@ -140,6 +405,46 @@ pub(crate) enum CppFunctionBody {
     Destructor(Namespace, Ident),
     AllocUninitialized(QualifiedName),
     FreeUninitialized(QualifiedName),
+    /// Call a function to release a resource, unless the argument is
+    /// equal to the given C++ expression.
+    CloseResource(Namespace, Ident, Option<String>),
+    /// Call an intrusive reference counting method on the object pointed
+    /// to by the only argument.
+    RefcountCall(Ident),
+    /// Delete the array pointed to by the only argument.
+    DeleteArray,
+    /// Find out whether the only argument is valid, by calling the given
+    /// method (negating the result if the flag is set) or, if there's no
+    /// method, converting it to `bool`.
+    ValidityCheck(Option<Ident>, bool),
+    /// `dynamic_cast` the only argument, a reference, to a pointer to the
+    /// given type, which is `const` unless the flag is set.
+    DynamicCast(QualifiedName, bool),
+    /// Find out whether the given function pointer field of the first
+    /// argument is set.
+    FunctionPointerIsSet(String),
+    /// Call the given function pointer field of the first argument with
+    /// the rest, terminating if it's null.
+    FunctionPointerCall(String),
+    /// Call a function, constructing what it returns in the storage
+    /// pointed to by the first argument, which is of the given type.
+    EmplaceReturn(Namespace, Ident, QualifiedName),
+    /// Call a function of the same type as the given one at the address
+    /// passed as the last argument.
+    ResolvedFunctionCall(Namespace, Ident),
+    /// Call a function which returns a `std::tuple`, moving its elements,
+    /// converted as given, into a new struct of the given name.
+    TupleReturn(Namespace, Ident, Ident, Vec<TypeConversionPolicy>),
+    /// Get a `std::shared_ptr` to the first argument, which derives from
+    /// `std::enable_shared_from_this`, or a `std::weak_ptr` if the flag is
+    /// set.
+    SharedFromThis(bool),
+    /// Get a reference to what the first argument, which overloads
+    /// `operator->`, points to, following any chain of `operator->`s.
+    SmartPointerDeref,
+    /// Call the given instantiation of a function template, such as
+    /// `foo<int, 3>`.
+    InstantiationCall(Namespace, String),
 }
 
 #[derive(Clone)]
@@ -160,5 +465,8 @@ pub(crate) struct CppFunction {
     pub(crate) argument_conversion: Vec<TypeConversionPolicy>,
     pub(crate) kind: CppFunctionKind,
     pub(crate) pass_obs_field: bool,
+    /// Where to pass a local `std::error_code` which the wrapper checks
+    /// after the call.
+    pub(crate) error_code_param: Option<usize>,
     pub(crate) qualification: Option<QualifiedName>,
 }
@@ -0,0 +1,165 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use syn::{FnArg, Type, TypePtr};
+
+use crate::{
+    conversion::{
+        analysis::pod::PodPhase,
+        api::{Api, CppVisibility, SpecialMemberKind},
+    },
+    types::QualifiedName,
+};
+
+/// What we know about the copy and move constructors declared by a type.
+/// `Some(true)` means declared and usable; `Some(false)` means declared
+/// but deleted or private.
+#[derive(Default, Clone, Copy)]
+struct DeclaredConstructors {
+    copy: Option<bool>,
+    move_: Option<bool>,
+}
+
+impl DeclaredConstructors {
+    /// Whether these declarations alone prevent any move or copy. If a
+    /// move constructor is declared, overload resolution picks it for
+    /// rvalues, so it must be usable. If not, a declared copy constructor
+    /// suppresses the implicit move constructor, so that must be usable.
+    fn forbid_moving(&self) -> bool {
+        match (self.move_, self.copy) {
+            (Some(usable), _) => !usable,
+            (None, Some(usable)) => !usable,
+            (None, None) => false,
+        }
+    }
+}
+
+/// Finds types which can be neither moved nor copied, for instance because
+/// they delete both their copy and move constructors, or because they
+/// contain a field of such a type and declare neither themselves. We
+/// can't pass such types by value, since that involves moving them into
+/// or out of a `UniquePtr`, so they can be used only by reference or
+/// within a `UniquePtr` created by one of their constructors.
+pub(super) fn find_immovable_types(apis: &[Api<PodPhase>]) -> HashSet<QualifiedName> {
+    let mut declared: HashMap<QualifiedName, DeclaredConstructors> = HashMap::new();
+    for api in apis {
+        if let Api::Function { fun, .. } = api {
+            let ty = match constructed_type(fun.inputs.iter().next()) {
+                Some(ty) => ty,
+                None => continue,
+            };
+            let usable = !fun.is_deleted && fun.cpp_vis != CppVisibility::Private;
+            match fun.special_member {
+                Some(SpecialMemberKind::CopyConstructor) => {
+                    let entry = declared.entry(ty).or_default();
+                    entry.copy = Some(entry.copy.unwrap_or_default() || usable);
+                }
+                Some(SpecialMemberKind::MoveConstructor) => {
+                    let entry = declared.entry(ty).or_default();
+                    entry.move_ = Some(entry.move_.unwrap_or_default() || usable);
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut immovable: HashSet<QualifiedName> = declared
+        .iter()
+        .filter(|(_, ctors)| ctors.forbid_moving())
+        .map(|(ty, _)| ty.clone())
+        .collect();
+    // A type which declares neither constructor gets implicit ones which
+    // are deleted if a base or field can't be copied or moved. (One which
+    // can be copied is fine, even if it explicitly deletes its move
+    // constructor, because the implicit move constructor is then ignored
+    // and we fall back to copying.) Keep going until nothing changes, since
+    // those may themselves be immovable only because of their own fields.
+    let mut uncopyable: HashSet<QualifiedName> = immovable
+        .iter()
+        .filter(|ty| declared[*ty].copy != Some(true))
+        .cloned()
+        .collect();
+    loop {
+        let newly_immovable: Vec<_> = apis
+            .iter()
+            .filter_map(|api| match api {
+                Api::Struct { name, details, .. }
+                    if !immovable.contains(&name.name)
+                        && !declared.contains_key(&name.name)
+                        && details
+                            .item
+                            .fields
+                            .iter()
+                            .filter_map(|f| by_value_type(&f.ty))
+                            .any(|ty| uncopyable.contains(&ty)) =>
+                {
+                    Some(name.name.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        if newly_immovable.is_empty() {
+            break;
+        }
+        uncopyable.extend(newly_immovable.iter().cloned());
+        immovable.extend(newly_immovable);
+    }
+    immovable
+}
+
+/// The type of a field which holds its value directly, rather than
+/// through a pointer or reference. Bases are fields too, in bindgen's
+/// output.
+fn by_value_type(ty: &Type) -> Option<QualifiedName> {
+    match ty {
+        Type::Path(typ) => Some(QualifiedName::from_type_path(typ)),
+        Type::Array(arr) => by_value_type(&arr.elem),
+        _ => None,
+    }
+}
+
+/// The type constructed by a constructor, which bindgen gives us as a
+/// pointer first parameter.
+fn constructed_type(arg: Option<&FnArg>) -> Option<QualifiedName> {
+    match arg {
+        Some(FnArg::Typed(pt)) => match pt.ty.as_ref() {
+            Type::Ptr(TypePtr { elem, .. }) => match elem.as_ref() {
+                Type::Path(typ) => Some(QualifiedName::from_type_path(typ)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeclaredConstructors;
+
+    #[test]
+    fn test_forbid_moving() {
+        let ctors = |copy, move_| DeclaredConstructors { copy, move_ };
+        // Both deleted.
+        assert!(ctors(Some(false), Some(false)).forbid_moving());
+        // Copy deleted, so no implicit move.
+        assert!(ctors(Some(false), None).forbid_moving());
+        // Move-only.
+        assert!(!ctors(Some(false), Some(true)).forbid_moving());
+        // Move deleted, even though copy is available.
+        assert!(ctors(Some(true), Some(false)).forbid_moving());
+        assert!(!ctors(None, None).forbid_moving());
+    }
+}
@@ -13,10 +13,15 @@
 // limitations under the License.
 
 mod bridge_name_tracker;
+mod const_overloads;
 pub(crate) mod function_wrapper;
+mod immovable;
 mod implicit_constructor_rules;
 mod implicit_constructors;
+mod named_ctors;
 mod overload_tracker;
+mod resolved_symbols;
+mod stripped_prefixes;
 mod subclass;
 
 use crate::{
@@ -34,6 +39,7 @@ use crate::{
         error_reporter::{convert_apis, report_any_error},
     },
     known_types::known_types,
+    snake_case::property_accessor_name,
     types::validate_ident_ok_for_rust,
 };
 use std::collections::{HashMap, HashSet};
@@ -43,9 +49,10 @@ use function_wrapper::{CppFunction, CppFunctionBody, TypeConversionPolicy};
 use itertools::Itertools;
 use proc_macro2::Span;
 use quote::quote;
+use quote::ToTokens;
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, FnArg, Ident, Pat, ReturnType, Type,
-    TypePtr, Visibility,
+    parse_quote, punctuated::Punctuated, token::Comma, ExprClosure, FnArg, GenericArgument, Ident,
+    Pat, PathArguments, ReturnType, Type, TypeBareFn, TypePtr, TypeReference, Visibility,
 };
 
 use crate::{
@@ -54,14 +61,23 @@ use crate::{
         ConvertError,
     },
     types::{make_ident, validate_ident_ok_for_cxx, Namespace, QualifiedName},
+    MethodRenamer,
 };
 
 use self::{
     bridge_name_tracker::BridgeNameTracker,
-    function_wrapper::RustConversionType,
+    const_overloads::{find_const_overloads, CONST_OVERLOAD_SUFFIX},
+    function_wrapper::{callback_context_index, RustConversionType},
+    immovable::find_immovable_types,
     implicit_constructors::find_missing_constructors,
+    named_ctors::{ctor_param_types, find_method_names},
     overload_tracker::OverloadTracker,
-    subclass::{create_subclass_constructor, create_subclass_fn_wrapper, create_subclass_function},
+    resolved_symbols::{find_overloaded_functions, resolved_symbol_param},
+    stripped_prefixes::{find_stripped_name_collisions, stripped_name},
+    subclass::{
+        create_subclass_constructor, create_subclass_fn_wrapper, create_subclass_function,
+        create_subclass_protected_fn_wrapper,
+    },
 };
 
 use super::{
@@ -94,6 +110,11 @@ pub(crate) enum TraitMethodKind {
     Destructor,
     Alloc,
     Dealloc,
+    IntrusiveRefcount,
+    Validity,
+    Deref,
+    SliceToBase,
+    Downcast,
 }
 
 #[derive(Clone)]
@@ -166,6 +187,160 @@ pub(crate) struct FnAnalysis {
     /// Whether this can be called by external code. Not so for
     /// protected methods.
     pub(crate) externally_callable: bool,
+    /// Whether to generate an extra variant of this function which
+    /// runs on another thread, as requested by `offload!`.
+    pub(crate) offload: bool,
+    /// Whether calls to this function should be logged.
+    pub(crate) trace: bool,
+    /// Whether a C++ exception thrown by this function should become a
+    /// Rust panic, as requested by `safety!(exceptions_as_panics)`, or
+    /// because it's a move constructor.
+    pub(crate) exceptions_as_panics: bool,
+    /// Whether the pointer returned by this function should be owned
+    /// by Rust, as requested by `owned_return!`.
+    pub(crate) owned_return: Option<OwnedReturn>,
+    /// Whether the handle returned by this function should be owned
+    /// by Rust, as requested by `resource!`.
+    pub(crate) resource_return: Option<ResourceReturn>,
+    /// A pointer-to-pointer parameter which this function fills in, and
+    /// which Rust should return instead, as requested by `out_param!`.
+    pub(crate) out_param: Option<OutParam>,
+    /// The type which this function constructs in storage provided by
+    /// Rust, rather than returning it, as requested by `emplace_return!`.
+    pub(crate) emplace_return: Option<Type>,
+    /// The elements of the `std::tuple` this function returns, which Rust
+    /// should return as a tuple, as requested by `tuple_return!`.
+    pub(crate) tuple_return: Option<Vec<TypeConversionPolicy>>,
+    /// The `std::num::NonZero...` type which this function's integer
+    /// return value should become, as requested by `nonzero!`.
+    pub(crate) nonzero_return: Option<Type>,
+    /// What to do if the `UniquePtr` this function returns is null, as
+    /// requested by `non_null_return!` or `nullable_return!`.
+    pub(crate) null_check_return: Option<NullCheck>,
+    /// The position of a `std::error_code&` parameter which the C++
+    /// wrapper provides, such that Rust gets a `Result` instead, as
+    /// requested by `error_code_param!`.
+    pub(crate) error_code_param: Option<usize>,
+    /// The parameter from which this function's returned reference
+    /// borrows, as requested by `lifetime!`.
+    pub(crate) return_lifetime_from: Option<usize>,
+    /// The function pointer parameter which, together with a context
+    /// parameter, Rust passes as a closure, as requested by
+    /// `closure_callback!`.
+    pub(crate) closure_callback: Option<Ident>,
+    /// Details of the `std::unique_ptr<T[]>` which this function returns,
+    /// and where its length comes from, as given by `unique_array_return!`.
+    pub(crate) unique_array_return: Option<UniqueArrayReturn>,
+    /// Details of the array which this function returns a pointer to, and
+    /// where its length comes from, as given by `array_return!` or
+    /// `null_terminated_array_return!`.
+    pub(crate) array_return: Option<ArrayReturn>,
+    /// Whether this is the function synthesized to return the calling
+    /// thread's instance of a variable listed in `thread_local_global!`.
+    pub(crate) thread_local_global: bool,
+    /// The checks of its arguments which this function makes in debug
+    /// builds, as given in `precondition!`.
+    pub(crate) preconditions: Vec<ExprClosure>,
+    /// Whether this is the `at` method of a container listed in
+    /// `checked_index!`, which should be accompanied by a `get` method
+    /// which checks the index against `size` in Rust.
+    pub(crate) checked_index: bool,
+    /// If this is the result method of a type listed in
+    /// `register_awaitable!`, the Rust names of its other two methods, so
+    /// that the type can implement `autocxx::Awaitable`.
+    pub(crate) awaitable: Option<AwaitableMethods>,
+}
+
+/// The Rust names of the methods which resume an awaitable type and tell
+/// whether it's done.
+#[derive(Clone)]
+pub(crate) struct AwaitableMethods {
+    pub(crate) resume: Ident,
+    pub(crate) done: Ident,
+}
+
+/// How to handle a null `UniquePtr` returned by a function.
+#[derive(Clone, Copy)]
+pub(crate) enum NullCheck {
+    /// Panic, naming the function.
+    Panic,
+    /// Return `None`; otherwise, `Some`.
+    Option,
+}
+
+/// Details of a pointer-to-pointer parameter which a function fills in.
+#[derive(Clone)]
+pub(crate) struct OutParam {
+    /// The name of the parameter.
+    pub(crate) name: Ident,
+    /// The type pointed to by the pointer which is filled in.
+    pub(crate) pointee: Type,
+    /// Whether the pointer which is filled in is a const pointer.
+    pub(crate) is_const: bool,
+}
+
+/// Details of a function whose returned pointer must be freed by a
+/// particular C++ function.
+#[derive(Clone)]
+pub(crate) struct OwnedReturn {
+    /// The type pointed to.
+    pub(crate) pointee: Type,
+    /// Whether the function returns a const pointer.
+    pub(crate) is_const: bool,
+    /// A synthesized C++ function which calls the deleter.
+    pub(crate) deleter: Box<CppFunction>,
+}
+
+/// Details of a function which returns a C-style handle which must be
+/// released by a particular C++ function.
+#[derive(Clone)]
+pub(crate) struct ResourceReturn {
+    /// The Rust type of the handle, after any typedefs are resolved.
+    pub(crate) handle: Type,
+    /// A synthesized C++ function which calls the close function, unless
+    /// the handle is the invalid value.
+    pub(crate) close: Box<CppFunction>,
+}
+
+/// Details of a function which returns a `std::unique_ptr<T[]>`, whose
+/// length is given by one of its parameters.
+#[derive(Clone)]
+pub(crate) struct UniqueArrayReturn {
+    /// The type of each element.
+    pub(crate) elem: Type,
+    /// The parameter which gives the length.
+    pub(crate) len: Ident,
+    /// The type of the length, if the function fills it in through a
+    /// pointer rather than being told it.
+    pub(crate) len_out: Option<Type>,
+    /// Whether the length is one of autocxx's C integer newtypes.
+    pub(crate) len_is_ctype: bool,
+    /// A synthesized C++ function which deletes the array.
+    pub(crate) deleter: Box<CppFunction>,
+}
+
+/// Details of a function which returns a pointer to an array which C++
+/// still owns.
+#[derive(Clone)]
+pub(crate) struct ArrayReturn {
+    /// The type of each element or, for a null-terminated array, the type
+    /// each element points to.
+    pub(crate) elem: Type,
+    /// Where the length comes from.
+    pub(crate) len: ArrayLength,
+}
+
+/// Where the length of an array returned by a function comes from.
+#[derive(Clone)]
+pub(crate) enum ArrayLength {
+    /// One of the function's parameters, or, if `out` gives its type, the
+    /// integer which that parameter points to and the function fills in.
+    Param { name: Ident, out: Option<Box<Type>> },
+    /// A function in the same namespace, which takes no parameters.
+    Function(Ident),
+    /// The array is of pointers and ends with a null one. If `strings`,
+    /// they point to C strings.
+    NullTerminated { strings: bool },
 }
 
 #[derive(Clone)]
@@ -215,6 +390,14 @@ pub(crate) struct FnAnalyzer<'a> {
     subclasses_by_superclass: HashMap<QualifiedName, Vec<SubclassName>>,
     nested_type_name_map: HashMap<QualifiedName, String>,
     generic_types: HashSet<QualifiedName>,
+    const_overloads: HashSet<QualifiedName>,
+    overloaded_functions: HashSet<QualifiedName>,
+    stripped_name_collisions: HashSet<QualifiedName>,
+    method_names: HashMap<QualifiedName, Vec<String>>,
+    immovable_types: HashSet<QualifiedName>,
+    trace_calls: bool,
+    rename_methods: Option<&'a MethodRenamer>,
+    renamed_methods: HashMap<(QualifiedName, String), String>,
 }
 
 impl<'a> FnAnalyzer<'a> {
@@ -222,9 +405,20 @@ impl<'a> FnAnalyzer<'a> {
         apis: Vec<Api<PodPhase>>,
         unsafe_policy: UnsafePolicy,
         config: &'a IncludeCppConfig,
+        trace_calls: bool,
+        rename_methods: Option<&'a MethodRenamer>,
     ) -> Vec<Api<FnPhase>> {
+        // Only the overloads picked in select_overload! are bound, through
+        // the functions generated to call them, not those bindgen found.
+        let apis: Vec<_> = apis
+            .into_iter()
+            .filter(|api| !Self::is_unselected_overload(api, config))
+            .collect();
         let mut me = Self {
             unsafe_policy,
+            trace_calls,
+            rename_methods,
+            renamed_methods: HashMap::new(),
             extra_apis: Vec::new(),
             type_converter: TypeConverter::new(config, &apis),
             bridge_name_tracker: BridgeNameTracker::new(),
@@ -234,6 +428,11 @@ impl<'a> FnAnalyzer<'a> {
             subclasses_by_superclass: subclass::subclasses_by_superclass(&apis),
             nested_type_name_map: Self::build_nested_type_map(&apis),
             generic_types: Self::build_generic_type_set(&apis),
+            const_overloads: find_const_overloads(&apis),
+            overloaded_functions: find_overloaded_functions(&apis),
+            stripped_name_collisions: find_stripped_name_collisions(&apis, config),
+            method_names: find_method_names(&apis),
+            immovable_types: find_immovable_types(&apis),
         };
         let mut results = Vec::new();
         convert_apis(
@@ -245,10 +444,29 @@ impl<'a> FnAnalyzer<'a> {
             Api::typedef_unchanged,
         );
         me.add_missing_constructors(&mut results);
+        me.add_slice_functions(&mut results);
+        Self::check_awaitables(&mut results);
         results.extend(me.extra_apis.into_iter().map(add_analysis));
         results
     }
 
+    /// Whether this is one of the overloads bindgen found of a free
+    /// function listed in `select_overload!`.
+    fn is_unselected_overload(api: &Api<PodPhase>, config: &IncludeCppConfig) -> bool {
+        match api {
+            Api::Function { name, fun, .. } if fun.self_ty.is_none() => {
+                let cpp_name = name
+                    .cpp_name_if_present()
+                    .cloned()
+                    .unwrap_or_else(|| name.name.get_final_item().to_string());
+                let qualified_cpp_name =
+                    QualifiedName::new(name.name.get_namespace(), make_ident(cpp_name));
+                config.has_selected_overload(&qualified_cpp_name.to_cpp_name())
+            }
+            _ => false,
+        }
+    }
+
     fn build_pod_safe_type_set(apis: &[Api<PodPhase>]) -> HashSet<QualifiedName> {
         apis.iter()
             .filter_map(|api| match api {
@@ -357,8 +575,21 @@ impl<'a> FnAnalyzer<'a> {
                 .iter()
                 .any(|pd| pd.self_type.is_none() && pd.requires_unsafe)
         };
-        let any_param_needs_unsafe =
-            || -> bool { param_details.iter().any(|pd| pd.requires_unsafe) };
+        let any_param_needs_unsafe = || -> bool {
+            param_details.iter().any(|pd| {
+                pd.requires_unsafe
+                    // The bridge still takes a pointer.
+                    || matches!(
+                        pd.conversion.rust_conversion,
+                        RustConversionType::FromSliceToPtr
+                            | RustConversionType::FromCStrArg
+                            | RustConversionType::FromOutParam
+                            | RustConversionType::FromClosureToFnPtr
+                            | RustConversionType::FromClosureToStdFunction
+                            | RustConversionType::FromArrayToPtr(_)
+                    )
+            })
+        };
         match kind {
             // Trait unsafety must always correspond to the norms for the
             // trait we're implementing.
@@ -367,7 +598,8 @@ impl<'a> FnAnalyzer<'a> {
                     TraitMethodKind::CopyConstructor
                     | TraitMethodKind::MoveConstructor
                     | TraitMethodKind::Alloc
-                    | TraitMethodKind::Dealloc,
+                    | TraitMethodKind::Dealloc
+                    | TraitMethodKind::IntrusiveRefcount,
                 ..
             } => UnsafetyNeeded::Always,
             FnKind::TraitMethod { .. } if any_param_needs_unsafe() => UnsafetyNeeded::JustBridge,
@@ -382,6 +614,610 @@ impl<'a> FnAnalyzer<'a> {
         }
     }
 
+    /// Arrange for a pointer parameter and a length parameter to be passed
+    /// from Rust as a single slice.
+    fn pair_slice_params(
+        param_details: &mut [ArgumentAnalysis],
+        pointer: &str,
+        length: &str,
+    ) -> Result<(), ConvertError> {
+        let find = |name: &str| {
+            param_details
+                .iter()
+                .position(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == name))
+                .ok_or_else(|| {
+                    ConvertError::InvalidSliceParam(format!("it has no parameter called {}", name))
+                })
+        };
+        let pointer_idx = find(pointer)?;
+        let length_idx = find(length)?;
+        if !matches!(
+            param_details[pointer_idx].conversion.unwrapped_type,
+            Type::Ptr(_)
+        ) {
+            return Err(ConvertError::InvalidSliceParam(format!(
+                "{} is not a pointer",
+                pointer
+            )));
+        }
+        if !is_integer_type(&param_details[length_idx].conversion.unwrapped_type) {
+            return Err(ConvertError::InvalidSliceParam(format!(
+                "{} is not an integer",
+                length
+            )));
+        }
+        // Each length describes exactly one slice, and each slice has exactly
+        // one length; otherwise we couldn't pass a length which is right
+        // for every slice.
+        for (idx, name) in [(pointer_idx, pointer), (length_idx, length)] {
+            if !matches!(
+                param_details[idx].conversion.rust_conversion,
+                RustConversionType::None
+            ) {
+                return Err(ConvertError::InvalidSliceParam(format!(
+                    "{} is already part of another slice_param!",
+                    name
+                )));
+            }
+        }
+        let pointer_pd = &mut param_details[pointer_idx];
+        pointer_pd.conversion.rust_conversion = RustConversionType::FromSliceToPtr;
+        // The slice is a borrow which can be checked, so this is no longer a
+        // reason for the function to be unsafe.
+        pointer_pd.requires_unsafe = false;
+        pointer_pd.was_reference = true;
+        param_details[length_idx].conversion.rust_conversion =
+            RustConversionType::FromSliceLen(make_ident(pointer));
+        Ok(())
+    }
+
+    /// Arrange for a `const char*` parameter to be passed from Rust as a
+    /// `&CStr` or a `&str`.
+    fn make_c_str_param(
+        param_details: &mut [ArgumentAnalysis],
+        name: &str,
+    ) -> Result<(), ConvertError> {
+        let pd = param_details
+            .iter_mut()
+            .find(|pd| {
+                pd.self_type.is_none() && matches!(&pd.name, Pat::Ident(pp) if pp.ident == name)
+            })
+            .ok_or_else(|| {
+                ConvertError::InvalidCStrParam(format!("it has no parameter called {}", name))
+            })?;
+        let is_c_char = |elem: &Type| matches!(elem, Type::Path(typ) if QualifiedName::from_type_path(typ) == QualifiedName::new_from_cpp_name("::std::os::raw::c_char"));
+        if !matches!(
+            &pd.conversion.unwrapped_type,
+            Type::Ptr(TypePtr { mutability: None, elem, .. }) if is_c_char(elem)
+        ) || !matches!(pd.conversion.rust_conversion, RustConversionType::None)
+        {
+            return Err(ConvertError::InvalidCStrParam(format!(
+                "{} is not a const char*",
+                name
+            )));
+        }
+        pd.conversion.rust_conversion = RustConversionType::FromCStrArg;
+        // The string is a borrow which can be checked, so this is no longer
+        // a reason for the function to be unsafe.
+        pd.requires_unsafe = false;
+        Ok(())
+    }
+
+    /// A `&CStr` can only borrow from the object whose method returns it.
+    fn make_c_str_return(
+        param_details: &[ArgumentAnalysis],
+        conversion: Option<&mut TypeConversionPolicy>,
+    ) -> Result<(), ConvertError> {
+        if !param_details.iter().any(|pd| pd.self_type.is_some()) {
+            return Err(ConvertError::InvalidCStrReturn(
+                "it isn't a method, so there's nothing for the string to borrow from".to_string(),
+            ));
+        }
+        let is_c_char = |elem: &Type| matches!(elem, Type::Path(typ) if QualifiedName::from_type_path(typ) == QualifiedName::new_from_cpp_name("::std::os::raw::c_char"));
+        match conversion {
+            Some(conversion)
+                if matches!(
+                    &conversion.unwrapped_type,
+                    Type::Ptr(TypePtr { mutability: None, elem, .. }) if is_c_char(elem)
+                ) && matches!(conversion.rust_conversion, RustConversionType::None) =>
+            {
+                conversion.rust_conversion = RustConversionType::ToCStr;
+                Ok(())
+            }
+            _ => Err(ConvertError::InvalidCStrReturn(
+                "it doesn't return a const char*".to_string(),
+            )),
+        }
+    }
+
+    /// Arrange for a function pointer parameter, which has already been
+    /// converted by `convert_callback_arg`, and the `void*` context which
+    /// is passed back to it to be passed from Rust as a single closure.
+    fn pair_closure_params(
+        param_details: &mut [ArgumentAnalysis],
+        callback: &str,
+        context: &str,
+    ) -> Result<Ident, ConvertError> {
+        let context_pd = param_details
+            .iter_mut()
+            .find(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == context))
+            .ok_or_else(|| {
+                ConvertError::InvalidClosureCallback(format!(
+                    "it has no parameter called {}",
+                    context
+                ))
+            })?;
+        let is_void_ptr = match &context_pd.conversion.unwrapped_type {
+            Type::Ptr(TypePtr {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => {
+                matches!(elem.as_ref(), Type::Path(typ) if known_types().is_ctype(&QualifiedName::from_type_path(typ)) && typ.path.segments.last().unwrap().ident == "c_void")
+            }
+            _ => false,
+        };
+        if !is_void_ptr {
+            return Err(ConvertError::InvalidClosureCallback(format!(
+                "{} is not a void*",
+                context
+            )));
+        }
+        let callback = make_ident(callback);
+        context_pd.conversion.rust_conversion =
+            RustConversionType::FromClosureContext(callback.clone());
+        // We provide the context, so this is no longer a reason for the
+        // function to be unsafe.
+        context_pd.requires_unsafe = false;
+        Ok(callback)
+    }
+
+    /// Analyze a function pointer parameter listed in `closure_callback!`.
+    /// The function it points to may take and return only types which
+    /// need no conversion, since the trampoline we generate is called
+    /// directly by C++.
+    fn convert_callback_arg(
+        &mut self,
+        arg: &FnArg,
+        ns: &Namespace,
+    ) -> Result<(FnArg, ArgumentAnalysis), ConvertError> {
+        let mut pt = match arg {
+            FnArg::Typed(pt) => pt.clone(),
+            FnArg::Receiver(_) => panic!("Did not expect a receiver as a callback"),
+        };
+        let name = pt.pat.to_token_stream().to_string();
+        let mut callback = callback_fn_type(&pt.ty).cloned().ok_or_else(|| {
+            ConvertError::InvalidClosureCallback(format!("{} is not a function pointer", name))
+        })?;
+        let deps = self.convert_callback_signature(&mut callback, ns, |ty| {
+            ConvertError::InvalidClosureCallback(format!(
+                "its callback uses the type {}, which can't be passed directly between C++ and Rust",
+                ty.to_token_stream()
+            ))
+        })?;
+        if callback_context_index(&callback).is_none() {
+            return Err(ConvertError::InvalidClosureCallback(format!(
+                "its callback {} takes no void* through which to pass the context",
+                name
+            )));
+        }
+        let conversion = TypeConversionPolicy::new_from_closure_to_callback(Type::BareFn(callback));
+        pt.ty = Box::new(conversion.converted_rust_type());
+        let name = (*pt.pat).clone();
+        Ok((
+            FnArg::Typed(pt),
+            ArgumentAnalysis {
+                self_type: None,
+                name,
+                conversion,
+                was_reference: false,
+                deps,
+                requires_unsafe: false,
+            },
+        ))
+    }
+
+    /// Convert the parameter and return types of a function which C++
+    /// calls, and which Rust implements as a closure. It may take and
+    /// return only types which need no conversion, since the trampoline
+    /// we generate is called directly by C++.
+    fn convert_callback_signature(
+        &mut self,
+        callback: &mut TypeBareFn,
+        ns: &Namespace,
+        unsupported: impl Fn(&Type) -> ConvertError,
+    ) -> Result<HashSet<QualifiedName>, ConvertError> {
+        let mut deps = HashSet::new();
+        for arg in callback.inputs.iter_mut() {
+            let annotated = self.convert_boxed_type(Box::new(arg.ty.clone()), ns, false)?;
+            if !is_plain_callback_type(&annotated.ty) {
+                return Err(unsupported(&arg.ty));
+            }
+            deps.extend(annotated.types_encountered);
+            arg.ty = *annotated.ty;
+        }
+        if let ReturnType::Type(_, ty) = &mut callback.output {
+            let annotated = self.convert_boxed_type(ty.clone(), ns, false)?;
+            if !is_plain_callback_type(&annotated.ty) {
+                return Err(unsupported(ty));
+            }
+            deps.extend(annotated.types_encountered);
+            *ty = annotated.ty;
+        }
+        callback.lifetimes = None;
+        Ok(deps)
+    }
+
+    /// The `std::num::NonZero...` type corresponding to a returned
+    /// fixed-width integer type.
+    fn nonzero_type_for(ret_type: &ReturnType) -> Option<Type> {
+        let ty = match ret_type {
+            ReturnType::Type(_, ty) => ty.as_ref(),
+            ReturnType::Default => return None,
+        };
+        let nonzero = match ty {
+            Type::Path(typ) if typ.qself.is_none() => {
+                match typ.path.get_ident()?.to_string().as_str() {
+                    "u8" => "NonZeroU8",
+                    "u16" => "NonZeroU16",
+                    "u32" => "NonZeroU32",
+                    "u64" => "NonZeroU64",
+                    "usize" => "NonZeroUsize",
+                    "i8" => "NonZeroI8",
+                    "i16" => "NonZeroI16",
+                    "i32" => "NonZeroI32",
+                    "i64" => "NonZeroI64",
+                    "isize" => "NonZeroIsize",
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        let nonzero = make_ident(nonzero);
+        Some(parse_quote! { ::std::num::#nonzero })
+    }
+
+    /// Arrange for a pointer-to-pointer parameter to be filled in by the
+    /// Rust wrapper, which returns the resulting pointer.
+    fn find_out_param(
+        param_details: &mut [ArgumentAnalysis],
+        name: &str,
+    ) -> Result<OutParam, ConvertError> {
+        let pd = param_details
+            .iter_mut()
+            .find(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == name))
+            .ok_or_else(|| {
+                ConvertError::InvalidOutParam(format!("it has no parameter called {}", name))
+            })?;
+        let (pointee, is_const) = match &pd.conversion.unwrapped_type {
+            Type::Ptr(TypePtr {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => match elem.as_ref() {
+                Type::Ptr(TypePtr {
+                    elem, mutability, ..
+                }) => (elem.as_ref().clone(), mutability.is_none()),
+                _ => {
+                    return Err(ConvertError::InvalidOutParam(format!(
+                        "{} is not a pointer to a pointer",
+                        name
+                    )))
+                }
+            },
+            _ => {
+                return Err(ConvertError::InvalidOutParam(format!(
+                    "{} is not a mutable pointer",
+                    name
+                )))
+            }
+        };
+        pd.conversion.rust_conversion = RustConversionType::FromOutParam;
+        // We provide the storage, so this is no longer a reason for the
+        // function to be unsafe.
+        pd.requires_unsafe = false;
+        Ok(OutParam {
+            name: make_ident(name),
+            pointee,
+            is_const,
+        })
+    }
+
+    /// The return type of a function listed in `unique_array_return!`. The
+    /// C++ wrapper releases the `std::unique_ptr<T[]>` and returns the
+    /// pointer, which the Rust wrapper owns along with the length.
+    fn convert_unique_array_return(
+        &mut self,
+        rt: &ReturnType,
+        ns: &Namespace,
+    ) -> Result<ReturnTypeAnalysis, ConvertError> {
+        let elem = match rt {
+            ReturnType::Type(_, ty) => unique_array_element_type(ty),
+            ReturnType::Default => None,
+        }
+        .ok_or_else(|| {
+            ConvertError::InvalidUniqueArrayReturn(
+                "it does not return a std::unique_ptr<T[]>".to_string(),
+            )
+        })?;
+        let annotated_type =
+            self.convert_boxed_type(Box::new(parse_quote! { *mut #elem }), ns, false)?;
+        let elem_is_pod = match annotated_type.ty.as_ref() {
+            Type::Ptr(TypePtr { elem, .. }) => match elem.as_ref() {
+                Type::Path(typ) => self
+                    .pod_safe_types
+                    .contains(&QualifiedName::from_type_path(typ)),
+                _ => false,
+            },
+            _ => false,
+        };
+        if !elem_is_pod {
+            return Err(ConvertError::InvalidUniqueArrayReturn(
+                "its elements are not POD".to_string(),
+            ));
+        }
+        let conversion =
+            TypeConversionPolicy::new_from_unique_array_to_ptr(annotated_type.ty.as_ref().clone());
+        Ok(ReturnTypeAnalysis {
+            rt: ReturnType::Type(Default::default(), annotated_type.ty),
+            conversion: Some(conversion),
+            was_reference: false,
+            deps: annotated_type.types_encountered,
+        })
+    }
+
+    /// The elements of the `std::tuple` returned by a function listed in
+    /// `tuple_return!`, each converted as if it were returned on its own.
+    /// C++ moves them into a struct shared with Rust, so each must be
+    /// something which can go in one: a value, or a `UniquePtr` to it.
+    fn convert_tuple_return(
+        &mut self,
+        rt: &ReturnType,
+        ns: &Namespace,
+    ) -> Result<(Vec<TypeConversionPolicy>, HashSet<QualifiedName>), ConvertError> {
+        let args = match rt {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Path(typ)
+                    if QualifiedName::from_type_path(typ).to_cpp_name() == "std::tuple" =>
+                {
+                    match &typ.path.segments.last().unwrap().arguments {
+                        PathArguments::AngleBracketed(args) => Some(args.args.iter().collect()),
+                        _ => Some(Vec::new()),
+                    }
+                }
+                _ => None,
+            },
+            ReturnType::Default => None,
+        }
+        .ok_or_else(|| {
+            ConvertError::InvalidTupleReturn("it does not return a std::tuple".to_string())
+        })?;
+        let mut elements = Vec::new();
+        let mut deps = HashSet::new();
+        for (idx, arg) in args.into_iter().enumerate() {
+            let ty = match arg {
+                GenericArgument::Type(ty) => ty,
+                _ => {
+                    return Err(ConvertError::InvalidTupleReturn(format!(
+                        "element {} is not a type",
+                        idx
+                    )))
+                }
+            };
+            let analysis =
+                self.convert_return_type(&parse_quote! { -> #ty }, ns, &References::default())?;
+            match analysis.conversion {
+                Some(conversion)
+                    if !analysis.was_reference
+                        && !matches!(conversion.unwrapped_type, Type::Ptr(_))
+                        && matches!(
+                            conversion.cpp_conversion,
+                            CppConversionType::None | CppConversionType::FromValueToUniquePtr
+                        ) =>
+                {
+                    elements.push(conversion)
+                }
+                _ => {
+                    return Err(ConvertError::InvalidTupleReturn(format!(
+                        "element {} can't be stored in a struct shared with Rust",
+                        idx
+                    )))
+                }
+            }
+            deps.extend(analysis.deps);
+        }
+        Ok((elements, deps))
+    }
+
+    /// Find the parameter giving the length of the array returned by a
+    /// function listed in `unique_array_return!` or `array_return!`. It
+    /// may be an integer, or a pointer to one which the function fills in,
+    /// in which case the Rust wrapper provides the storage. Returns the
+    /// type of that integer in the latter case, and whether it's one of
+    /// autocxx's C integer newtypes.
+    fn find_array_len(
+        param_details: &mut [ArgumentAnalysis],
+        name: &str,
+        make_err: fn(String) -> ConvertError,
+    ) -> Result<(Option<Type>, bool), ConvertError> {
+        let pd = param_details
+            .iter_mut()
+            .find(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == name))
+            .ok_or_else(|| make_err(format!("it has no parameter called {}", name)))?;
+        let len_ty = match &pd.conversion.unwrapped_type {
+            Type::Ptr(TypePtr {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => elem.as_ref().clone(),
+            other => other.clone(),
+        };
+        if !is_integer_type(&len_ty) {
+            return Err(make_err(format!(
+                "{} is neither an integer nor a mutable pointer to one",
+                name
+            )));
+        }
+        let len_is_ctype = matches!(&len_ty, Type::Path(typ) if known_types().is_ctype(&QualifiedName::from_type_path(typ)));
+        if matches!(pd.conversion.unwrapped_type, Type::Ptr(_)) {
+            pd.conversion.rust_conversion = RustConversionType::FromOutParam;
+            // We provide the storage, so this is no longer a reason for
+            // the function to be unsafe.
+            pd.requires_unsafe = false;
+            Ok((Some(len_ty), len_is_ctype))
+        } else {
+            // The Rust wrapper uses the length itself, as well as passing
+            // it on, so it must be of the C++ type rather than anything
+            // which promotes to it.
+            pd.conversion.rust_conversion = RustConversionType::None;
+            Ok((None, len_is_ctype))
+        }
+    }
+
+    /// Where the length of the array returned by a function listed in
+    /// `array_return!` comes from: the parameter of that name, if there is
+    /// one, or otherwise the function of that name.
+    fn find_borrowed_array_len(
+        param_details: &mut [ArgumentAnalysis],
+        name: &str,
+    ) -> Result<ArrayLength, ConvertError> {
+        if param_details
+            .iter()
+            .any(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == name))
+        {
+            Self::find_array_len(param_details, name, ConvertError::InvalidArrayReturn).map(
+                |(out, _)| ArrayLength::Param {
+                    name: make_ident(name),
+                    out: out.map(Box::new),
+                },
+            )
+        } else if syn::parse_str::<Ident>(name).is_ok() {
+            Ok(ArrayLength::Function(make_ident(name)))
+        } else {
+            Err(ConvertError::InvalidArrayReturn(format!(
+                "{} is neither one of its parameters nor a function in the same namespace",
+                name
+            )))
+        }
+    }
+
+    /// Details of the array returned by a function listed in
+    /// `array_return!` or `null_terminated_array_return!`, given the
+    /// pointer it returns.
+    fn analyze_array_return(
+        &self,
+        len: ArrayLength,
+        ret_type: &ReturnType,
+    ) -> Result<ArrayReturn, ConvertError> {
+        let pointee = match ret_type {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Ptr(TypePtr { elem, .. }) => Some(elem.as_ref()),
+                _ => None,
+            },
+            ReturnType::Default => None,
+        }
+        .ok_or_else(|| ConvertError::InvalidArrayReturn("it does not return a pointer".into()))?;
+        let elem = match (&len, pointee) {
+            (ArrayLength::NullTerminated { .. }, Type::Ptr(TypePtr { elem, .. })) => {
+                elem.as_ref().clone()
+            }
+            (ArrayLength::NullTerminated { .. }, _) => {
+                return Err(ConvertError::InvalidArrayReturn(
+                    "it does not return a pointer to pointers".into(),
+                ))
+            }
+            (_, Type::Path(typ))
+                if self
+                    .pod_safe_types
+                    .contains(&QualifiedName::from_type_path(typ)) =>
+            {
+                typ.clone().into()
+            }
+            _ => {
+                return Err(ConvertError::InvalidArrayReturn(
+                    "its elements are not POD".into(),
+                ))
+            }
+        };
+        let len = match len {
+            ArrayLength::NullTerminated { .. } => ArrayLength::NullTerminated {
+                strings: matches!(&elem, Type::Path(typ) if QualifiedName::from_type_path(typ) == QualifiedName::new_from_cpp_name("::std::os::raw::c_char")),
+            },
+            len => len,
+        };
+        Ok(ArrayReturn { elem, len })
+    }
+
+    /// Find the parameter named in `lifetime!` as the one from which a
+    /// function's returned reference borrows, returning its position.
+    fn find_lifetime_source(
+        param_details: &[ArgumentAnalysis],
+        name: &str,
+        returns_reference: bool,
+    ) -> Result<usize, ConvertError> {
+        if !returns_reference {
+            return Err(ConvertError::InvalidLifetime(
+                "it does not return a reference".to_string(),
+            ));
+        }
+        let idx = param_details
+            .iter()
+            .position(|pd| {
+                if name == "this" {
+                    pd.self_type.is_some()
+                } else {
+                    pd.self_type.is_none() && matches!(&pd.name, Pat::Ident(pp) if pp.ident == name)
+                }
+            })
+            .ok_or_else(|| {
+                ConvertError::InvalidLifetime(format!("it has no parameter called {}", name))
+            })?;
+        if !param_details[idx].was_reference {
+            return Err(ConvertError::InvalidLifetime(format!(
+                "parameter {} is not a reference",
+                name
+            )));
+        }
+        Ok(idx)
+    }
+
+    /// Find the `std::error_code&` parameter which a function uses to
+    /// report failure, returning its position.
+    fn find_error_code_param(fun: &FuncToConvert, name: &str) -> Result<usize, ConvertError> {
+        let (idx, ty) = fun
+            .inputs
+            .iter()
+            .enumerate()
+            .find_map(|(idx, arg)| match arg {
+                FnArg::Typed(pt) if matches!(pt.pat.as_ref(), Pat::Ident(pp) if pp.ident == name) => {
+                    Some((idx, pt.ty.as_ref()))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| {
+                ConvertError::InvalidErrorCodeParam(format!("it has no parameter called {}", name))
+            })?;
+        let is_error_code = match ty {
+            Type::Ptr(TypePtr {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => matches!(elem.as_ref(), Type::Path(typ) if {
+                let cpp_name = QualifiedName::from_type_path(typ).to_cpp_name();
+                cpp_name.starts_with("std::") && cpp_name.ends_with("::error_code")
+            }),
+            _ => false,
+        };
+        if is_error_code && fun.references.ref_params.contains(&make_ident(name)) {
+            Ok(idx)
+        } else {
+            Err(ConvertError::InvalidErrorCodeParam(format!(
+                "{} is not a std::error_code&",
+                name
+            )))
+        }
+    }
+
     /// Analyze a given function, and any permutations of that function which
     /// we might additionally generate (e.g. for subclasses.)
     fn analyze_foreign_fn_and_subclasses(
@@ -453,6 +1289,19 @@ impl<'a> FnAnalyzer<'a> {
                     }
                 }
             }
+            FnKind::Method(sup, MethodKind::Normal(..))
+                if fun.cpp_vis == CppVisibility::Protected =>
+            {
+                for sub in self.subclasses_by_superclass(sup) {
+                    let wrapper_name = ApiName::new_with_cpp_name(
+                        &Namespace::new(),
+                        make_ident(format!("{}_{}", sub.cpp().get_final_item(), fun.ident)),
+                        Some(name.cpp_name()),
+                    );
+                    let wrapper = create_subclass_protected_fn_wrapper(sub, &fun);
+                    self.analyze_and_add(wrapper_name, wrapper, &mut results);
+                }
+            }
             _ => {}
         }
 
@@ -514,14 +1363,48 @@ impl<'a> FnAnalyzer<'a> {
         // for diagnostics whilst we do that.
         let initial_rust_name = fun.ident.to_string();
         let diagnostic_display_name = cpp_name.as_ref().unwrap_or(&initial_rust_name);
+        let qualified_cpp_name =
+            QualifiedName::new(ns, make_ident(diagnostic_display_name)).to_cpp_name();
+        // Directives which can apply to methods, such as lifetime!, name
+        // them by their class too.
+        let lifetime_cpp_name = match &fun.self_ty {
+            Some(self_ty) => format!("{}::{}", self_ty.to_cpp_name(), diagnostic_display_name),
+            None => qualified_cpp_name.clone(),
+        };
+
+        // A function listed in error_code_param! reports failure through a
+        // std::error_code& which the C++ wrapper provides, so we don't
+        // analyze that parameter at all.
+        let error_code_param = self
+            .config
+            .get_error_code_param(&qualified_cpp_name)
+            .map(|name| Self::find_error_code_param(fun, name));
+        let error_code_param_idx = error_code_param
+            .as_ref()
+            .and_then(|idx| idx.as_ref().ok())
+            .cloned();
+
+        // A function listed in closure_callback! takes a closure in place
+        // of a function pointer and its context. The function pointer
+        // parameter needs special treatment from the start, since we
+        // can't otherwise represent it.
+        let closure_callback = match fun.self_ty {
+            None => self.config.get_closure_callback(&qualified_cpp_name),
+            Some(_) => None,
+        };
 
         // Now let's analyze all the parameters.
         // See if any have annotations which our fork of bindgen has craftily inserted...
         let (param_details, bads): (Vec<_>, Vec<_>) = fun
             .inputs
             .iter()
-            .map(|i| {
-                self.convert_fn_arg(
+            .enumerate()
+            .filter(|(idx, _)| Some(*idx) != error_code_param_idx)
+            .map(|(_, i)| match closure_callback {
+                Some(closure_callback) if is_param_named(i, &closure_callback.callback) => {
+                    self.convert_callback_arg(i, ns)
+                }
+                _ => self.convert_fn_arg(
                     i,
                     ns,
                     diagnostic_display_name,
@@ -529,7 +1412,7 @@ impl<'a> FnAnalyzer<'a> {
                     &fun.references,
                     true,
                     None,
-                )
+                ),
             })
             .partition(Result::is_ok);
         let (mut params, mut param_details): (Punctuated<_, Comma>, Vec<_>) =
@@ -558,7 +1441,7 @@ impl<'a> FnAnalyzer<'a> {
         //   method,   IRN=A_foo,  CN=foo                       output: foo    case 4
         //   method,   IRN=A_move, CN=move   (keyword problem)  output: move_  case 5
         //   method,   IRN=A_foo1, CN=foo    (overload)         output: foo    case 6
-        let ideal_rust_name = match &cpp_name {
+        let mut ideal_rust_name = match &cpp_name {
             None => initial_rust_name, // case 1
             Some(cpp_name) => {
                 if initial_rust_name.ends_with('_') {
@@ -570,6 +1453,11 @@ impl<'a> FnAnalyzer<'a> {
                 }
             }
         };
+        // Overloads differing only in const-ness get a name saying which is
+        // which, rather than an arbitrary numeric suffix.
+        if self.const_overloads.contains(&name.name) {
+            ideal_rust_name.push_str(CONST_OVERLOAD_SUFFIX);
+        }
 
         // Let's spend some time figuring out the kind of this function (i.e. method,
         // virtual function, etc.)
@@ -594,6 +1482,7 @@ impl<'a> FnAnalyzer<'a> {
             &ideal_rust_name,
             &self_ty,
         );
+        let mut rename_problem = None;
         let (kind, error_context, rust_name) = if let Some(trait_details) = trait_details {
             trait_details
         } else if let Some(self_ty) = self_ty {
@@ -688,7 +1577,18 @@ impl<'a> FnAnalyzer<'a> {
                     // Previously we made a placement "new" (MethodKind::Constructor).
                     // This time we've asked ourselves to synthesize a make_unique.
                     let constructor_suffix = rust_name.strip_prefix(nested_type_ident).unwrap();
-                    rust_name = format!("make_unique{}", constructor_suffix);
+                    rust_name = match self.named_ctor(&self_ty, &param_details) {
+                        Some(ctor_name) => {
+                            let make_unique_name = format!("make_unique_{}", ctor_name);
+                            if self.named_ctor_collides(&self_ty, &ctor_name) {
+                                rename_problem = Some(ConvertError::CtorNameCollision(ctor_name));
+                                format!("make_unique{}", constructor_suffix)
+                            } else {
+                                make_unique_name
+                            }
+                        }
+                        None => format!("make_unique{}", constructor_suffix),
+                    };
                     // Strip off the 'this' arg.
                     params = params.into_iter().skip(1).collect();
                     param_details.remove(0);
@@ -704,8 +1604,20 @@ impl<'a> FnAnalyzer<'a> {
                     // which later code will convert to
                     // fn make_unique(...args) -> UniquePtr<Type>
                     // If there are multiple constructors, bindgen generates
-                    // new, new1, new2 etc. and we'll keep those suffixes.
-                    rust_name = format!("new{}", constructor_suffix);
+                    // new, new1, new2 etc. and we'll keep those suffixes,
+                    // unless name_ctor! gives this one a name of its own. If
+                    // that's taken by a method, the error is reported under
+                    // the usual name, which is still free.
+                    rust_name = match self.named_ctor(&self_ty, &param_details) {
+                        Some(ctor_name) if !self.named_ctor_collides(&self_ty, &ctor_name) => {
+                            ctor_name
+                        }
+                        Some(ctor_name) => {
+                            rename_problem = Some(ConvertError::CtorNameCollision(ctor_name));
+                            format!("new{}", constructor_suffix)
+                        }
+                        None => format!("new{}", constructor_suffix),
+                    };
                     MethodKind::Constructor
                 } else if is_static_method {
                     MethodKind::Static
@@ -718,6 +1630,36 @@ impl<'a> FnAnalyzer<'a> {
                         Virtualness::PureVirtual => MethodKind::PureVirtual(receiver_mutability),
                     }
                 };
+                if matches!(
+                    method_kind,
+                    MethodKind::Static
+                        | MethodKind::Normal(_)
+                        | MethodKind::Virtual(_)
+                        | MethodKind::PureVirtual(_)
+                ) && matches!(fun.provenance, Provenance::Bindgen)
+                    && !cpp_name
+                        .as_ref()
+                        .map(|cpp_name| cpp_name.starts_with("operator"))
+                        .unwrap_or_default()
+                {
+                    if let Some(renamed) = self.renamed_method(&self_ty, &rust_name) {
+                        let original_name = rust_name;
+                        rust_name = renamed;
+                        if validate_ident_ok_for_rust(&rust_name).is_err() {
+                            rust_name.push('_');
+                        }
+                        // Overloads share a name already, but two
+                        // different methods mustn't end up sharing one.
+                        let previous = self
+                            .renamed_methods
+                            .entry((self_ty.clone(), rust_name.clone()))
+                            .or_insert_with(|| original_name.clone());
+                        if *previous != original_name {
+                            rename_problem =
+                                Some(ConvertError::MethodRenameCollision(rust_name.clone()));
+                        }
+                    }
+                }
                 // Disambiguate overloads.
                 let rust_name = self.get_overload_name(ns, type_ident, rust_name);
                 let error_context = error_context_for_method(&self_ty, &rust_name);
@@ -729,7 +1671,50 @@ impl<'a> FnAnalyzer<'a> {
             }
         } else {
             // Not a method.
-            // What shall we call this function? It may be overloaded.
+            // What shall we call this function? It may be overloaded. If it
+            // evaluates a function-like macro, it's named after the macro.
+            // If it returns a thread_local variable, it's named after that.
+            // If it calls an overload picked in select_overload!, it's named
+            // after the overloaded function. If it has the type of an
+            // instantiation listed in instantiate_fn_named!, it's given the
+            // name listed there.
+            let qualified_probe =
+                QualifiedName::new(ns, make_ident(&ideal_rust_name)).to_cpp_name();
+            let ideal_rust_name = match (
+                self.config.macro_for_probe(&ideal_rust_name),
+                self.config.thread_local_for_probe(&qualified_probe),
+                self.config.selected_overload_for_probe(&qualified_probe),
+                self.config.named_instantiation_for_probe(&qualified_probe),
+            ) {
+                (Some(macro_name), _, _, _) => macro_name.to_string(),
+                (None, Some(var_name), _, _) => var_name.to_string(),
+                (None, None, Some(function), _) => {
+                    cpp_name = Some(fun.ident.to_string());
+                    function.to_string()
+                }
+                (None, None, None, Some(named)) => {
+                    cpp_name = Some(fun.ident.to_string());
+                    named.rust_name.clone()
+                }
+                (None, None, None, None) => {
+                    // Or it may be named without a prefix given in
+                    // strip_prefix!, in which case the bridge needs telling
+                    // what the C++ function is really called.
+                    let original_cpp_name =
+                        cpp_name.clone().unwrap_or_else(|| fun.ident.to_string());
+                    match stripped_name(self.config, &original_cpp_name) {
+                        Some(stripped) if self.stripped_name_collisions.contains(&name.name) => {
+                            rename_problem = Some(ConvertError::StrippedPrefixCollision(stripped));
+                            ideal_rust_name
+                        }
+                        Some(stripped) => {
+                            cpp_name = Some(original_cpp_name);
+                            stripped
+                        }
+                        None => ideal_rust_name,
+                    }
+                }
+            };
             let rust_name = self.get_function_overload_name(ns, ideal_rust_name);
             (
                 FnKind::Function,
@@ -745,6 +1730,9 @@ impl<'a> FnAnalyzer<'a> {
         let mut ignore_reason = Ok(());
         let mut set_ignore_reason =
             |err| ignore_reason = Err(ConvertErrorWithContext(err, Some(error_context.clone())));
+        if let Some(rename_problem) = rename_problem {
+            set_ignore_reason(rename_problem);
+        }
 
         // Now we have figured out the type of function (from its parameters)
         // we might have determined that we have a constructor. If so,
@@ -825,26 +1813,131 @@ impl<'a> FnAnalyzer<'a> {
             _ => {}
         }
 
-        let requires_unsafe = self.should_be_unsafe(&param_details, &kind);
-
-        // Now we can add context to the error, check for a variety of error
-        // cases. In each case, we continue to record the API, because it might
-        // influence our later decisions to generate synthetic constructors
-        // or note whether the type is abstract.
-        let externally_callable = match fun.cpp_vis {
-            CppVisibility::Private => {
-                set_ignore_reason(ConvertError::PrivateMethod);
-                false
+        // A function listed in slice_param! takes a slice in place of a
+        // pointer and a length.
+        if matches!(kind, FnKind::Function) {
+            for slice_param in self.config.get_slice_params(&qualified_cpp_name) {
+                Self::pair_slice_params(
+                    &mut param_details,
+                    &slice_param.pointer,
+                    &slice_param.length,
+                )
+                .unwrap_or_else(&mut set_ignore_reason);
             }
-            CppVisibility::Protected => false,
-            CppVisibility::Public => true,
+        }
+
+        // A parameter listed in c_str_param! takes a &CStr or a &str in place
+        // of a const char*.
+        for name in self.config.get_c_str_params(&lifetime_cpp_name) {
+            Self::make_c_str_param(&mut param_details, name).unwrap_or_else(&mut set_ignore_reason);
+        }
+
+        let closure_callback = closure_callback.and_then(|closure_callback| {
+            Self::pair_closure_params(
+                &mut param_details,
+                &closure_callback.callback,
+                &closure_callback.context,
+            )
+            .map_err(&mut set_ignore_reason)
+            .ok()
+        });
+
+        // A function listed in out_param! returns the pointer it fills in,
+        // rather than needing somewhere to put it.
+        let out_param = match (&kind, self.config.get_out_param(&qualified_cpp_name)) {
+            (FnKind::Function, Some(name)) => Self::find_out_param(&mut param_details, name)
+                .map_err(&mut set_ignore_reason)
+                .ok(),
+            _ => None,
         };
-        if matches!(
-            fun.special_member,
-            Some(SpecialMemberKind::AssignmentOperator)
-        ) {
-            set_ignore_reason(ConvertError::AssignmentOperator)
-        } else if fun.references.rvalue_ref_return {
+
+        let error_code_param = match error_code_param {
+            Some(Ok(_))
+                if !matches!(
+                    kind,
+                    FnKind::Function
+                        | FnKind::Method(_, MethodKind::Normal(_) | MethodKind::Static)
+                ) =>
+            {
+                set_ignore_reason(ConvertError::InvalidErrorCodeParam(
+                    "it is not a plain function or non-virtual method".to_string(),
+                ));
+                None
+            }
+            Some(Ok(_))
+                if out_param.is_some()
+                    || self
+                        .config
+                        .get_owned_return_deleter(&qualified_cpp_name)
+                        .is_some()
+                    || self.config.is_nonzero(&qualified_cpp_name) =>
+            {
+                set_ignore_reason(ConvertError::InvalidErrorCodeParam(
+                    "it is also listed in a directive which changes its return type".to_string(),
+                ));
+                None
+            }
+            Some(Ok(idx)) => Some(idx),
+            Some(Err(err)) => {
+                set_ignore_reason(err);
+                None
+            }
+            None => None,
+        };
+
+        // A free function listed in precondition! checks its arguments in
+        // debug builds before calling C++.
+        let preconditions: Vec<ExprClosure> = match kind {
+            FnKind::Function => self
+                .config
+                .get_preconditions(&qualified_cpp_name)
+                .cloned()
+                .collect(),
+            _ => {
+                if self
+                    .config
+                    .get_preconditions(&lifetime_cpp_name)
+                    .next()
+                    .is_some()
+                {
+                    set_ignore_reason(ConvertError::PreconditionOnMethod);
+                }
+                Vec::new()
+            }
+        };
+
+        // Numbers may be passed as anything which C++ would losslessly
+        // promote to them, except to trait methods, whose signatures are
+        // fixed by the trait, and to functions whose preconditions need
+        // to know the types of the arguments.
+        if !matches!(kind, FnKind::TraitMethod { .. }) && preconditions.is_empty() {
+            for pd in param_details.iter_mut() {
+                if pd.self_type.is_none() && pd.conversion.is_promotable() {
+                    pd.conversion.rust_conversion = RustConversionType::FromPromotable;
+                }
+            }
+        }
+
+        let requires_unsafe = self.should_be_unsafe(&param_details, &kind);
+
+        // Now we can add context to the error, check for a variety of error
+        // cases. In each case, we continue to record the API, because it might
+        // influence our later decisions to generate synthetic constructors
+        // or note whether the type is abstract.
+        let externally_callable = match fun.cpp_vis {
+            CppVisibility::Private => {
+                set_ignore_reason(ConvertError::PrivateMethod);
+                false
+            }
+            CppVisibility::Protected => false,
+            CppVisibility::Public => true,
+        };
+        if matches!(
+            fun.special_member,
+            Some(SpecialMemberKind::AssignmentOperator)
+        ) {
+            set_ignore_reason(ConvertError::AssignmentOperator)
+        } else if fun.references.rvalue_ref_return {
             set_ignore_reason(ConvertError::RValueReturn)
         } else if fun.is_deleted {
             set_ignore_reason(ConvertError::Deleted)
@@ -918,6 +2011,10 @@ impl<'a> FnAnalyzer<'a> {
         }
         let mut cxxbridge_name = make_ident(&cxxbridge_name);
 
+        // A function listed in tuple_return! returns the elements of its
+        // std::tuple as a Rust tuple: C++ moves them into a struct, whose
+        // fields Rust then moves into the tuple.
+        let mut tuple_return = None;
         // Analyze the return type, just as we previously did for the
         // parameters.
         let mut return_analysis = if let FnKind::Method(ref self_ty, MethodKind::MakeUnique) = kind
@@ -933,6 +2030,33 @@ impl<'a> FnAnalyzer<'a> {
                 was_reference: false,
                 deps: std::iter::once(self_ty).cloned().collect(),
             }
+        } else if matches!(kind, FnKind::Function)
+            && self
+                .config
+                .get_unique_array_return(&qualified_cpp_name)
+                .is_some()
+        {
+            self.convert_unique_array_return(&fun.output, ns)
+                .unwrap_or_else(|err| {
+                    set_ignore_reason(err);
+                    ReturnTypeAnalysis::default()
+                })
+        } else if matches!(kind, FnKind::Function)
+            && self.config.is_tuple_return(&qualified_cpp_name)
+        {
+            match self.convert_tuple_return(&fun.output, ns) {
+                Ok((elements, deps)) => {
+                    tuple_return = Some(elements);
+                    ReturnTypeAnalysis {
+                        deps,
+                        ..Default::default()
+                    }
+                }
+                Err(err) => {
+                    set_ignore_reason(err);
+                    ReturnTypeAnalysis::default()
+                }
+            }
         } else {
             self.convert_return_type(&fun.output, ns, &fun.references)
                 .unwrap_or_else(|err| {
@@ -940,17 +2064,427 @@ impl<'a> FnAnalyzer<'a> {
                     ReturnTypeAnalysis::default()
                 })
         };
+        // Types listed in type_conversion! become Rust types, except where
+        // they're needed to write the conversions or implement subclasses.
+        let converts_types = matches!(fun.virtualness, Virtualness::None)
+            && fun.synthesized_this_type.is_none()
+            && !matches!(&fun.self_ty, Some(self_ty)
+                if self.config.get_type_conversion(&self_ty.to_cpp_name()).is_some());
+        if converts_types {
+            for pd in param_details.iter_mut().filter(|pd| pd.self_type.is_none()) {
+                self.convert_listed_type(&mut pd.conversion, false);
+            }
+            if let Some(conversion) = return_analysis.conversion.as_mut() {
+                self.convert_listed_type(conversion, true);
+            }
+        }
+        // A function listed in string_return! copies the std::string it
+        // returns into a String straight away.
+        let string_return = self.config.get_string_return(&lifetime_cpp_name);
+        if let Some(string_return) = string_return {
+            match return_analysis.conversion.as_mut() {
+                Some(conversion) if Self::returns_std_string(conversion) => {
+                    conversion.rust_conversion = RustConversionType::ToString(string_return)
+                }
+                _ => set_ignore_reason(ConvertError::InvalidStringReturn(
+                    "it doesn't return a std::string".to_string(),
+                )),
+            }
+        }
+        // A method listed in c_str_return! returns a &CStr borrowed from
+        // the object, rather than a pointer.
+        if self.config.is_c_str_return(&lifetime_cpp_name) {
+            Self::make_c_str_return(&param_details, return_analysis.conversion.as_mut())
+                .unwrap_or_else(&mut set_ignore_reason);
+        }
         let mut deps = params_deps;
         deps.extend(return_analysis.deps.drain());
 
+        // A function listed in lifetime! tells us which parameter its
+        // returned reference borrows from, so may take several references.
+        let return_lifetime_from = self
+            .config
+            .get_return_lifetime_source(&lifetime_cpp_name)
+            .and_then(|name| {
+                Self::find_lifetime_source(&param_details, name, return_analysis.was_reference)
+                    .map_err(&mut set_ignore_reason)
+                    .ok()
+            });
+        // A function listed in static_ref_return! returns a reference to
+        // something which lives forever, such as a singleton, so needn't
+        // borrow from any parameter.
+        let static_ref_return = self.config.is_static_ref_return(&lifetime_cpp_name);
+        if static_ref_return && !return_analysis.was_reference {
+            set_ignore_reason(ConvertError::StaticRefReturnNotReference);
+        }
         let num_input_references = param_details.iter().filter(|pd| pd.was_reference).count();
-        if num_input_references != 1 && return_analysis.was_reference {
+        if num_input_references != 1
+            && return_analysis.was_reference
+            && return_lifetime_from.is_none()
+            && !static_ref_return
+        {
             // cxx only allows functions to return a reference if they take exactly
             // one reference as a parameter. Let's see...
             set_ignore_reason(ConvertError::NotOneInputReference(rust_name.clone()));
         }
+
+        // An offloaded function runs on some other thread, possibly after
+        // the caller's stack frame has gone, so it mustn't borrow anything.
+        let offload =
+            matches!(kind, FnKind::Function) && self.config.is_offloaded(&qualified_cpp_name);
+        if offload
+            && (return_analysis.was_reference
+                || param_details
+                    .iter()
+                    .any(|pd| pd.was_reference || pd.requires_unsafe))
+        {
+            set_ignore_reason(ConvertError::OffloadedFunctionBorrows);
+        }
         let mut ret_type = return_analysis.rt;
-        let ret_type_conversion = return_analysis.conversion;
+        if static_ref_return {
+            if let ReturnType::Type(_, ty) = &mut ret_type {
+                make_reference_static(ty);
+            }
+        }
+        let mut ret_type_conversion = return_analysis.conversion;
+
+        // A function listed in owned_return! hands ownership of the pointer
+        // it returns (or fills in, for an out_param!) to Rust, which must
+        // later free it by calling a given C++ function.
+        let config = self.config;
+        let owned_return_deleter = match kind {
+            FnKind::Function => config.get_owned_return_deleter(&qualified_cpp_name),
+            _ => None,
+        };
+        let owned_return_pointee = match (owned_return_deleter, &out_param, &ret_type) {
+            (Some(_), Some(out_param), _) => Some((out_param.pointee.clone(), out_param.is_const)),
+            (Some(_), None, ReturnType::Type(_, ty)) => match ty.as_ref() {
+                Type::Ptr(TypePtr {
+                    elem, mutability, ..
+                }) => Some((elem.as_ref().clone(), mutability.is_none())),
+                _ => None,
+            },
+            _ => None,
+        };
+        if owned_return_deleter.is_some() && owned_return_pointee.is_none() {
+            set_ignore_reason(ConvertError::OwnedReturnNotPointer);
+        }
+
+        // A function returning a handle type listed in resource! likewise
+        // hands ownership of that resource to Rust. By now typedefs have
+        // been resolved, so we look at what bindgen said it returned.
+        let resource = match (&kind, &fun.output) {
+            (FnKind::Function, ReturnType::Type(_, ty))
+                if owned_return_deleter.is_none()
+                    && out_param.is_none()
+                    && error_code_param.is_none() =>
+            {
+                match ty.as_ref() {
+                    Type::Path(typ) => {
+                        config.get_resource(&QualifiedName::from_type_path(typ).to_cpp_name())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        // A function listed in nonzero! promises never to return zero, so
+        // Rust can return the corresponding NonZero type.
+        let nonzero_return = match kind {
+            FnKind::Function if config.is_nonzero(&qualified_cpp_name) => {
+                let nonzero_return = Self::nonzero_type_for(&ret_type);
+                if nonzero_return.is_none() {
+                    set_ignore_reason(ConvertError::NonZeroNotInteger);
+                }
+                nonzero_return
+            }
+            _ => None,
+        };
+
+        // A function listed in non_null_return! has its returned UniquePtr
+        // checked for null at once, so that a null doesn't surface as a
+        // panic somewhere else entirely; one listed in nullable_return!
+        // returns an Option instead.
+        let null_check_return = match (
+            config.is_non_null_return(&lifetime_cpp_name),
+            config.is_nullable_return(&lifetime_cpp_name),
+        ) {
+            (true, true) => {
+                set_ignore_reason(ConvertError::InvalidNullCheckedReturn(
+                    "it is listed in both".to_string(),
+                ));
+                None
+            }
+            (true, false) => Some(NullCheck::Panic),
+            (false, true) => Some(NullCheck::Option),
+            (false, false) => None,
+        };
+        if null_check_return.is_some() && !matches!(kind, FnKind::Function) {
+            set_ignore_reason(ConvertError::InvalidNullCheckedReturn(
+                "it is a method, which isn't supported".to_string(),
+            ));
+        } else if null_check_return.is_some() {
+            let returns_unique_ptr = matches!(&ret_type, ReturnType::Type(_, ty)
+                if matches!(ty.as_ref(), Type::Path(typ)
+                    if typ.path.segments.last().map(|seg| seg.ident == "UniquePtr") == Some(true)));
+            if !returns_unique_ptr {
+                set_ignore_reason(ConvertError::InvalidNullCheckedReturn(
+                    "it doesn't return a UniquePtr".to_string(),
+                ));
+            } else if error_code_param.is_some() || closure_callback.is_some() {
+                set_ignore_reason(ConvertError::InvalidNullCheckedReturn(
+                    "it is also listed in a directive which changes its return type".to_string(),
+                ));
+            }
+        }
+
+        // The closure's handle is returned too, so that's incompatible with
+        // anything else which changes what the function returns.
+        if closure_callback.is_some()
+            && (out_param.is_some()
+                || owned_return_deleter.is_some()
+                || resource.is_some()
+                || nonzero_return.is_some()
+                || error_code_param.is_some())
+        {
+            set_ignore_reason(ConvertError::InvalidClosureCallback(
+                "it is also listed in a directive which changes its return type".to_string(),
+            ));
+        }
+
+        // A function listed in unique_array_return! returns an array whose
+        // length one of its parameters gives, or which it fills in.
+        let unique_array_len = match kind {
+            FnKind::Function => config
+                .get_unique_array_return(&qualified_cpp_name)
+                .and_then(|len| {
+                    Self::find_array_len(
+                        &mut param_details,
+                        len,
+                        ConvertError::InvalidUniqueArrayReturn,
+                    )
+                    .map(|(len_out, len_is_ctype)| (make_ident(len), len_out, len_is_ctype))
+                    .map_err(&mut set_ignore_reason)
+                    .ok()
+                }),
+            _ => None,
+        };
+        if unique_array_len.is_some()
+            && (out_param.is_some()
+                || owned_return_deleter.is_some()
+                || nonzero_return.is_some()
+                || error_code_param.is_some()
+                || closure_callback.is_some())
+        {
+            set_ignore_reason(ConvertError::InvalidUniqueArrayReturn(
+                "it is also listed in a directive which changes its return type".to_string(),
+            ));
+        }
+
+        // A function listed in array_return! or null_terminated_array_return!
+        // returns a pointer to an array which C++ still owns, which we borrow.
+        let array_len = match kind {
+            FnKind::Function if config.is_null_terminated_array_return(&qualified_cpp_name) => {
+                Some(ArrayLength::NullTerminated { strings: false })
+            }
+            FnKind::Function => config
+                .get_array_return(&qualified_cpp_name)
+                .and_then(|len| {
+                    Self::find_borrowed_array_len(&mut param_details, len)
+                        .map_err(&mut set_ignore_reason)
+                        .ok()
+                }),
+            _ => None,
+        };
+        if array_len.is_some()
+            && (out_param.is_some()
+                || owned_return_deleter.is_some()
+                || nonzero_return.is_some()
+                || error_code_param.is_some()
+                || closure_callback.is_some()
+                || unique_array_len.is_some())
+        {
+            set_ignore_reason(ConvertError::InvalidArrayReturn(
+                "it is also listed in a directive which changes its return type".to_string(),
+            ));
+        }
+
+        // The const `at` method of a type listed in checked_index! gets a
+        // `get` method alongside, which checks bounds in Rust.
+        let checked_index = match &kind {
+            FnKind::Method(self_ty, MethodKind::Normal(ReceiverMutability::Const))
+                if cpp_name.as_deref() == Some("at")
+                    && config.is_checked_index(&self_ty.to_cpp_name()) =>
+            {
+                let index_is_integer = match params.iter().nth(1) {
+                    Some(FnArg::Typed(pt)) => params.len() == 2 && is_integer_type(&pt.ty),
+                    _ => false,
+                };
+                if !index_is_integer {
+                    set_ignore_reason(ConvertError::InvalidCheckedIndex(
+                        "its at method must take a single integer index".to_string(),
+                    ));
+                } else if matches!(ret_type, ReturnType::Default) {
+                    set_ignore_reason(ConvertError::InvalidCheckedIndex(
+                        "its at method doesn't return anything".to_string(),
+                    ));
+                }
+                true
+            }
+            _ => false,
+        };
+
+        // The result method of a type listed in register_awaitable! gets an
+        // implementation of autocxx::Awaitable, which calls it along with
+        // the other two methods listed.
+        let awaitable = match &kind {
+            FnKind::Method(self_ty, MethodKind::Normal(_)) => config
+                .get_awaitable(&self_ty.to_cpp_name())
+                .filter(|awaitable| cpp_name.as_deref() == Some(awaitable.result.as_str()))
+                .map(|awaitable| {
+                    if params.len() != 1 {
+                        set_ignore_reason(ConvertError::InvalidAwaitable(
+                            "its result method takes parameters".to_string(),
+                        ));
+                    }
+                    AwaitableMethods {
+                        resume: self.method_rust_name(self_ty, &awaitable.resume),
+                        done: self.method_rust_name(self_ty, &awaitable.done),
+                    }
+                }),
+            _ => None,
+        };
+        if let FnKind::Method(self_ty, method_kind) = &kind {
+            if let Some(awaitable) = config.get_awaitable(&self_ty.to_cpp_name()) {
+                let returns_bool = matches!(&ret_type, ReturnType::Type(_, ty)
+                    if matches!(ty.as_ref(), Type::Path(typ) if typ.path.is_ident("bool")));
+                if cpp_name.as_deref() == Some(awaitable.resume.as_str())
+                    && (params.len() != 1 || !matches!(ret_type, ReturnType::Default))
+                {
+                    set_ignore_reason(ConvertError::InvalidAwaitable(
+                        "its resume method takes parameters or returns something".to_string(),
+                    ));
+                } else if cpp_name.as_deref() == Some(awaitable.done.as_str())
+                    && (params.len() != 1
+                        || !returns_bool
+                        || !matches!(method_kind, MethodKind::Normal(ReceiverMutability::Const)))
+                {
+                    set_ignore_reason(ConvertError::InvalidAwaitable(
+                        "its done method isn't a const method returning bool".to_string(),
+                    ));
+                }
+            }
+        }
+
+        // A function listed in emplace_return! constructs the object it
+        // returns in storage which Rust provides, so that Rust can return a
+        // `New` for it rather than a `UniquePtr`. The storage is passed
+        // first.
+        let emplace_return = match kind {
+            FnKind::Function if config.is_emplace_return(&qualified_cpp_name) => {
+                let returned = ret_type_conversion.as_ref().and_then(|conversion| {
+                    match (&conversion.cpp_conversion, &conversion.unwrapped_type) {
+                        (CppConversionType::FromValueToUniquePtr, Type::Path(typ)) => {
+                            Some((typ.clone(), QualifiedName::from_type_path(typ)))
+                        }
+                        _ => None,
+                    }
+                });
+                if returned.is_none() {
+                    set_ignore_reason(ConvertError::InvalidEmplaceReturn(
+                        "it doesn't return a non-POD type by value".to_string(),
+                    ));
+                } else if out_param.is_some()
+                    || owned_return_deleter.is_some()
+                    || error_code_param.is_some()
+                    || closure_callback.is_some()
+                    || null_check_return.is_some()
+                    || unique_array_len.is_some()
+                    || offload
+                {
+                    set_ignore_reason(ConvertError::InvalidEmplaceReturn(
+                        "it is also listed in a directive which changes its return type"
+                            .to_string(),
+                    ));
+                }
+                returned
+            }
+            _ => None,
+        };
+        if let Some((typ, _)) = &emplace_return {
+            param_details.insert(
+                0,
+                ArgumentAnalysis {
+                    conversion: TypeConversionPolicy::new_unconverted(parse_quote! { *mut #typ }),
+                    name: parse_quote! { autocxx_placement },
+                    self_type: None,
+                    was_reference: false,
+                    deps: HashSet::new(),
+                    // We provide the storage.
+                    requires_unsafe: false,
+                },
+            );
+            ret_type = ReturnType::Default;
+            ret_type_conversion = None;
+        }
+
+        if string_return.is_some()
+            && (out_param.is_some()
+                || owned_return_deleter.is_some()
+                || closure_callback.is_some()
+                || null_check_return.is_some()
+                || offload
+                || emplace_return.is_some())
+        {
+            set_ignore_reason(ConvertError::InvalidStringReturn(
+                "it is also listed in a directive which changes its return type".to_string(),
+            ));
+        }
+
+        if tuple_return.is_some()
+            && (out_param.is_some()
+                || owned_return_deleter.is_some()
+                || error_code_param.is_some()
+                || closure_callback.is_some()
+                || null_check_return.is_some()
+                || unique_array_len.is_some()
+                || offload
+                || emplace_return.is_some())
+        {
+            set_ignore_reason(ConvertError::InvalidTupleReturn(
+                "it is also listed in a directive which changes its return type".to_string(),
+            ));
+        }
+
+        // The function declared with the type of an instantiation listed in
+        // instantiate_fn_named! isn't defined, so the C++ wrapper calls the
+        // instantiation instead.
+        let instantiation = match kind {
+            FnKind::Function => config
+                .named_instantiation_for_probe(
+                    &QualifiedName::new(ns, fun.ident.clone()).to_cpp_name(),
+                )
+                .map(|named| named.instantiated_name()),
+            _ => None,
+        };
+
+        // With symbol_resolver!, a free function calls the C++ at the
+        // address which the resolver finds for its symbol at runtime,
+        // rather than linking to it. Rust passes that address last.
+        let resolved_symbol = match (&kind, config.get_symbol_resolver()) {
+            (FnKind::Function, Some(resolver))
+                if matches!(fun.provenance, Provenance::Bindgen)
+                    && fun.synthetic_cpp.is_none()
+                    && emplace_return.is_none()
+                    && instantiation.is_none()
+                    && !self.overloaded_functions.contains(&name.name) =>
+            {
+                param_details.push(resolved_symbol_param(resolver, fun));
+                true
+            }
+            _ => false,
+        };
 
         // Do we need to convert either parameters or return type?
         let param_conversion_needed = param_details.iter().any(|b| b.conversion.cpp_work_needed());
@@ -979,10 +2513,24 @@ impl<'a> FnAnalyzer<'a> {
                 ..
             } => true,
             FnKind::Method(..) if cxxbridge_name != rust_name => true,
+            // Tracing needs a Rust wrapper for every method, and cxx would
+            // otherwise give that wrapper's name to the method itself.
+            FnKind::Method(..) if self.trace_calls => true,
+            // Likewise, exceptions must be turned into panics by a Rust
+            // wrapper; and the C++ wrapper header is where we tell cxx how
+            // to catch exceptions which aren't std::exceptions.
+            _ if config.exceptions_as_panics => true,
             _ if param_conversion_needed => true,
             _ if ret_type_conversion_needed => true,
             _ if cpp_name_incompatible_with_cxx => true,
             _ if fun.synthetic_cpp.is_some() => true,
+            _ if owned_return_pointee.is_some() => true,
+            _ if resource.is_some() => true,
+            _ if error_code_param.is_some() => true,
+            _ if emplace_return.is_some() => true,
+            _ if resolved_symbol => true,
+            _ if tuple_return.is_some() => true,
+            _ if instantiation.is_some() => true,
             _ => false,
         };
 
@@ -995,7 +2543,12 @@ impl<'a> FnAnalyzer<'a> {
             } else {
                 "_"
             };
-            cxxbridge_name = make_ident(&format!("{}{}autocxx_wrapper", cxxbridge_name, joiner));
+            // Another include_cpp! block may wrap the same function, and
+            // the wrappers must not collide at link time.
+            cxxbridge_name = make_ident(
+                self.config
+                    .uniquify_name_per_mod(&format!("{}{}autocxx_wrapper", cxxbridge_name, joiner)),
+            );
             let (payload, cpp_function_kind) = match fun.synthetic_cpp.as_ref().cloned() {
                 Some((payload, cpp_function_kind)) => (payload, cpp_function_kind),
                 None => match kind {
@@ -1031,10 +2584,49 @@ impl<'a> FnAnalyzer<'a> {
                         CppFunctionBody::FunctionCall(ns.clone(), cpp_construction_ident),
                         CppFunctionKind::Method,
                     ),
-                    _ => (
-                        CppFunctionBody::FunctionCall(ns.clone(), cpp_construction_ident),
-                        CppFunctionKind::Function,
-                    ),
+                    _ => match &emplace_return {
+                        None if instantiation.is_some() => (
+                            CppFunctionBody::InstantiationCall(
+                                ns.clone(),
+                                instantiation.clone().unwrap(),
+                            ),
+                            CppFunctionKind::Function,
+                        ),
+                        None if matches!(&tuple_return, Some(elements) if !elements.is_empty()) => {
+                            let tuple = make_ident(format!("{}_tuple", cxxbridge_name));
+                            ret_type_conversion = Some(TypeConversionPolicy::new_unconverted(
+                                parse_quote! { #tuple },
+                            ));
+                            (
+                                CppFunctionBody::TupleReturn(
+                                    ns.clone(),
+                                    cpp_construction_ident,
+                                    tuple,
+                                    tuple_return.clone().unwrap(),
+                                ),
+                                CppFunctionKind::Function,
+                            )
+                        }
+                        Some((_, returned)) => (
+                            CppFunctionBody::EmplaceReturn(
+                                ns.clone(),
+                                cpp_construction_ident,
+                                returned.clone(),
+                            ),
+                            CppFunctionKind::Function,
+                        ),
+                        None if resolved_symbol => (
+                            CppFunctionBody::ResolvedFunctionCall(
+                                ns.clone(),
+                                cpp_construction_ident,
+                            ),
+                            CppFunctionKind::Function,
+                        ),
+                        None => (
+                            CppFunctionBody::FunctionCall(ns.clone(), cpp_construction_ident),
+                            CppFunctionKind::Function,
+                        ),
+                    },
                 },
             };
             // Now modify the cxx::bridge entry we're going to make.
@@ -1072,12 +2664,107 @@ impl<'a> FnAnalyzer<'a> {
                 argument_conversion: param_details.iter().map(|d| d.conversion.clone()).collect(),
                 kind: cpp_function_kind,
                 pass_obs_field: false,
+                error_code_param,
                 qualification: None,
             })
         } else {
             None
         };
 
+        let owned_return = match (owned_return_deleter, owned_return_pointee) {
+            (Some(deleter), Some((pointee, is_const))) => {
+                let deleter = QualifiedName::new_from_cpp_name(deleter);
+                let arg_type = parse_quote! { *mut #pointee };
+                Some(OwnedReturn {
+                    pointee,
+                    is_const,
+                    deleter: Box::new(CppFunction {
+                        payload: CppFunctionBody::FunctionCall(
+                            deleter.get_namespace().clone(),
+                            deleter.get_final_ident(),
+                        ),
+                        wrapper_function_name: make_ident(format!("{}_deleter", cxxbridge_name)),
+                        original_cpp_name: deleter.to_cpp_name(),
+                        return_conversion: None,
+                        argument_conversion: vec![TypeConversionPolicy::new_unconverted(arg_type)],
+                        kind: CppFunctionKind::Function,
+                        pass_obs_field: false,
+                        error_code_param: None,
+                        qualification: None,
+                    }),
+                })
+            }
+            _ => None,
+        };
+
+        let resource_return = match (resource, &ret_type) {
+            (Some(resource), ReturnType::Type(_, handle)) => {
+                let close = QualifiedName::new_from_cpp_name(&resource.close);
+                Some(ResourceReturn {
+                    handle: handle.as_ref().clone(),
+                    close: Box::new(CppFunction {
+                        payload: CppFunctionBody::CloseResource(
+                            close.get_namespace().clone(),
+                            close.get_final_ident(),
+                            resource.invalid.clone(),
+                        ),
+                        wrapper_function_name: make_ident(format!("{}_close", cxxbridge_name)),
+                        original_cpp_name: close.to_cpp_name(),
+                        return_conversion: None,
+                        argument_conversion: vec![TypeConversionPolicy::new_unconverted(
+                            handle.as_ref().clone(),
+                        )],
+                        kind: CppFunctionKind::Function,
+                        pass_obs_field: false,
+                        error_code_param: None,
+                        qualification: None,
+                    }),
+                })
+            }
+            _ => None,
+        };
+
+        let unique_array_return = match (unique_array_len, &ret_type) {
+            (Some((len, len_out, len_is_ctype)), ReturnType::Type(_, ty)) => match ty.as_ref() {
+                Type::Ptr(TypePtr { elem, .. }) => {
+                    let elem = elem.as_ref().clone();
+                    Some(UniqueArrayReturn {
+                        len,
+                        len_out,
+                        len_is_ctype,
+                        deleter: Box::new(CppFunction {
+                            payload: CppFunctionBody::DeleteArray,
+                            wrapper_function_name: make_ident(format!(
+                                "{}_deleter",
+                                cxxbridge_name
+                            )),
+                            original_cpp_name: "delete[]".to_string(),
+                            return_conversion: None,
+                            argument_conversion: vec![TypeConversionPolicy::new_unconverted(
+                                parse_quote! { *mut #elem },
+                            )],
+                            kind: CppFunctionKind::Function,
+                            pass_obs_field: false,
+                            error_code_param: None,
+                            qualification: None,
+                        }),
+                        elem,
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let array_return = array_len.and_then(|len| {
+            self.analyze_array_return(len, &ret_type)
+                .map_err(&mut set_ignore_reason)
+                .ok()
+        });
+
+        let thread_local_global = matches!(kind, FnKind::Function)
+            && config.thread_local_for_probe(&qualified_cpp_name).is_some();
+
         let vis = fun.vis.clone();
 
         // Naming, part two.
@@ -1092,6 +2779,24 @@ impl<'a> FnAnalyzer<'a> {
             _ => (rust_name_ident, RustRenameStrategy::None),
         };
 
+        // bindgen doesn't tell us whether a move constructor is noexcept;
+        // only the C++ compiler knows, including for noexcept(expr). So any
+        // exception it throws becomes a Rust panic, rather than terminating
+        // the program mid-move, which costs nothing for a move which can't
+        // throw. moveit then destroys the moved-from object, which is still
+        // valid, just once, and never treats the destination as initialized.
+        let exceptions_as_panics = config.exceptions_as_panics
+            || matches!(
+                kind,
+                FnKind::TraitMethod {
+                    kind: TraitMethodKind::MoveConstructor,
+                    ..
+                }
+            )
+            || matches!(
+                fun.synthetic_cpp,
+                Some((CppFunctionBody::SmartPointerDeref, _))
+            );
         let analysis = FnAnalysis {
             cxxbridge_name,
             rust_name: rust_name.clone(),
@@ -1107,6 +2812,25 @@ impl<'a> FnAnalyzer<'a> {
             deps,
             ignore_reason,
             externally_callable,
+            offload,
+            trace: self.trace_calls,
+            exceptions_as_panics,
+            owned_return,
+            resource_return,
+            out_param,
+            emplace_return: emplace_return.map(|(typ, _)| Type::Path(typ)),
+            tuple_return,
+            nonzero_return,
+            null_check_return,
+            error_code_param,
+            return_lifetime_from,
+            closure_callback,
+            unique_array_return,
+            array_return,
+            thread_local_global,
+            preconditions,
+            checked_index,
+            awaitable,
         };
         let name = ApiName::new_with_cpp_name(ns, id, cpp_name);
         (analysis, name)
@@ -1218,25 +2942,143 @@ impl<'a> FnAnalyzer<'a> {
                     rust_name,
                 ))
             }
-            TraitSynthesis::AllocUninitialized(ty) => self.generate_alloc_or_deallocate(
+            TraitSynthesis::AllocUninitialized(ty) => self.generate_unsafe_trait_method(
                 ideal_rust_name,
                 ty,
+                parse_quote! { autocxx::moveit::MakeCppStorage },
                 "allocate_uninitialized_cpp_storage",
                 TraitMethodKind::Alloc,
             ),
-            TraitSynthesis::FreeUninitialized(ty) => self.generate_alloc_or_deallocate(
+            TraitSynthesis::FreeUninitialized(ty) => self.generate_unsafe_trait_method(
                 ideal_rust_name,
                 ty,
+                parse_quote! { autocxx::moveit::MakeCppStorage },
                 "free_uninitialized_cpp_storage",
                 TraitMethodKind::Dealloc,
             ),
+            TraitSynthesis::AddRef(ty) => self.generate_unsafe_trait_method(
+                ideal_rust_name,
+                ty,
+                parse_quote! { autocxx::IntrusiveRefcounted },
+                "add_ref",
+                TraitMethodKind::IntrusiveRefcount,
+            ),
+            TraitSynthesis::Release(ty) => self.generate_unsafe_trait_method(
+                ideal_rust_name,
+                ty,
+                parse_quote! { autocxx::IntrusiveRefcounted },
+                "release",
+                TraitMethodKind::IntrusiveRefcount,
+            ),
+            TraitSynthesis::Validity(ty) => self.generate_trait_method(
+                ideal_rust_name,
+                ty,
+                parse_quote! { autocxx::IsValid },
+                "is_valid",
+                TraitMethodKind::Validity,
+            ),
+            TraitSynthesis::Deref(ty) => self.generate_trait_method(
+                ideal_rust_name,
+                ty,
+                parse_quote! { std::ops::Deref },
+                "deref",
+                TraitMethodKind::Deref,
+            ),
+            TraitSynthesis::Downcast { to_type, mutable } => {
+                let rust_name = self.get_function_overload_name(ns, ideal_rust_name.to_string());
+                let from_type = self_ty.as_ref().unwrap();
+                let to_type = to_type.to_type_path();
+                let method_name = match mutable {
+                    CastMutability::MutToMut => "dynamic_cast_mut",
+                    _ => "dynamic_cast",
+                };
+                Some((
+                    FnKind::TraitMethod {
+                        kind: TraitMethodKind::Downcast,
+                        impl_for: from_type.clone(),
+                        details: Box::new(TraitMethodDetails {
+                            trt: TraitImplSignature {
+                                ty: Type::Path(from_type.to_type_path()),
+                                trait_signature: parse_quote! {
+                                    autocxx::DynamicCast < #to_type >
+                                },
+                                unsafety: None,
+                            },
+                            avoid_self: false,
+                            method_name: make_ident(method_name),
+                            parameter_reordering: None,
+                            trait_call_is_unsafe: false,
+                        }),
+                    },
+                    ErrorContext::Item(make_ident(&rust_name)),
+                    rust_name,
+                ))
+            }
+            TraitSynthesis::SliceToBase(to_type) => {
+                let rust_name = self.get_function_overload_name(ns, ideal_rust_name.to_string());
+                let from_type = self_ty.as_ref().unwrap();
+                let to_type = to_type.to_type_path();
+                Some((
+                    FnKind::TraitMethod {
+                        kind: TraitMethodKind::SliceToBase,
+                        impl_for: from_type.clone(),
+                        details: Box::new(TraitMethodDetails {
+                            trt: TraitImplSignature {
+                                ty: Type::Path(from_type.to_type_path()),
+                                trait_signature: parse_quote! {
+                                    autocxx::SliceToBase < #to_type >
+                                },
+                                unsafety: None,
+                            },
+                            avoid_self: false,
+                            method_name: make_ident("slice_to_base"),
+                            parameter_reordering: None,
+                            trait_call_is_unsafe: false,
+                        }),
+                    },
+                    ErrorContext::Item(make_ident(&rust_name)),
+                    rust_name,
+                ))
+            }
         })
     }
 
-    fn generate_alloc_or_deallocate(
+    fn generate_trait_method(
         &mut self,
         ideal_rust_name: &str,
         ty: &QualifiedName,
+        trait_signature: Type,
+        method_name: &str,
+        kind: TraitMethodKind,
+    ) -> Option<(FnKind, ErrorContext, String)> {
+        let rust_name =
+            self.get_function_overload_name(ty.get_namespace(), ideal_rust_name.to_string());
+        Some((
+            FnKind::TraitMethod {
+                impl_for: ty.clone(),
+                details: Box::new(TraitMethodDetails {
+                    trt: TraitImplSignature {
+                        ty: Type::Path(ty.to_type_path()),
+                        trait_signature,
+                        unsafety: None,
+                    },
+                    avoid_self: false,
+                    method_name: make_ident(method_name),
+                    parameter_reordering: None,
+                    trait_call_is_unsafe: false,
+                }),
+                kind,
+            },
+            ErrorContext::Item(make_ident(&rust_name)),
+            rust_name,
+        ))
+    }
+
+    fn generate_unsafe_trait_method(
+        &mut self,
+        ideal_rust_name: &str,
+        ty: &QualifiedName,
+        trait_signature: Type,
         method_name: &str,
         kind: TraitMethodKind,
     ) -> Option<(FnKind, ErrorContext, String)> {
@@ -1249,7 +3091,7 @@ impl<'a> FnAnalyzer<'a> {
                 details: Box::new(TraitMethodDetails {
                     trt: TraitImplSignature {
                         ty: Type::Path(typ),
-                        trait_signature: parse_quote! { autocxx::moveit::MakeCppStorage },
+                        trait_signature,
                         unsafety: Some(parse_quote! { unsafe }),
                     },
                     avoid_self: false,
@@ -1346,18 +3188,135 @@ impl<'a> FnAnalyzer<'a> {
                     }
                     _ => old_pat,
                 };
+                if let Some((tn, width)) = self.bitset_width(&pt.ty, treat_as_reference)? {
+                    let conversion =
+                        TypeConversionPolicy::new_from_bitset_to_std_bitset(*pt.ty, width);
+                    pt.pat = Box::new(new_pat.clone());
+                    pt.ty = Box::new(conversion.converted_rust_type());
+                    return Ok((
+                        FnArg::Typed(pt),
+                        ArgumentAnalysis {
+                            self_type,
+                            name: new_pat,
+                            conversion,
+                            was_reference: false,
+                            deps: std::iter::once(tn).collect(),
+                            requires_unsafe: false,
+                        },
+                    ));
+                }
+                // A reference to an array, such as int (&)[8], keeps its
+                // length. bindgen gives us a pointer to the array, which
+                // would otherwise decay.
+                if let Some((elem_ptr, len)) = array_reference(&pt.ty, treat_as_reference)? {
+                    let annotated_type = self.convert_boxed_type(elem_ptr, ns, false)?;
+                    let conversion = TypeConversionPolicy::new_from_array_to_array_reference(
+                        *annotated_type.ty.clone(),
+                        len,
+                    );
+                    pt.pat = Box::new(new_pat.clone());
+                    pt.ty = annotated_type.ty;
+                    return Ok((
+                        FnArg::Typed(pt),
+                        ArgumentAnalysis {
+                            self_type,
+                            name: new_pat,
+                            conversion,
+                            was_reference: true,
+                            deps: annotated_type.types_encountered,
+                            requires_unsafe: false,
+                        },
+                    ));
+                }
+                // A std::function is taken as a closure. Each copy which
+                // C++ makes of the std::function shares ownership of the
+                // closure, so it lives for as long as C++ keeps any of
+                // them, for instance in a list of event handlers.
+                if let Some(signature) = std_function_signature(&pt.ty, treat_as_reference) {
+                    let mut signature = signature.clone();
+                    let deps = self.convert_callback_signature(&mut signature, ns, |ty| {
+                        ConvertError::UnsupportedStdFunction(ty.to_token_stream().to_string())
+                    })?;
+                    let conversion = TypeConversionPolicy::new_from_closure_to_std_function(
+                        Type::BareFn(signature),
+                    );
+                    pt.pat = Box::new(new_pat.clone());
+                    pt.ty = Box::new(conversion.converted_rust_type());
+                    return Ok((
+                        FnArg::Typed(pt),
+                        ArgumentAnalysis {
+                            self_type,
+                            name: new_pat,
+                            conversion,
+                            was_reference: false,
+                            deps,
+                            requires_unsafe: false,
+                        },
+                    ));
+                }
+                // A std::reference_wrapper<T> is taken as a reference to T,
+                // from which the C++ constructs it. bindgen doesn't tell us
+                // whether T is const, so it must be a mutable reference, from
+                // which either can be constructed. The C++ may well keep the
+                // reference_wrapper, for instance in a container, after the
+                // call, so the function is unsafe.
+                if let Some(referent) = reference_wrapper_referent(&pt.ty) {
+                    let annotated_type =
+                        self.convert_boxed_type(parse_quote! { *mut #referent }, ns, true)?;
+                    let conversion = TypeConversionPolicy::new_from_reference_to_reference_wrapper(
+                        *annotated_type.ty.clone(),
+                    );
+                    pt.pat = Box::new(new_pat.clone());
+                    pt.ty = annotated_type.ty;
+                    return Ok((
+                        FnArg::Typed(pt),
+                        ArgumentAnalysis {
+                            self_type,
+                            name: new_pat,
+                            conversion,
+                            was_reference: true,
+                            deps: annotated_type.types_encountered,
+                            requires_unsafe: true,
+                        },
+                    ));
+                }
                 let annotated_type = self.convert_boxed_type(pt.ty, ns, treat_as_reference)?;
                 let new_ty = annotated_type.ty;
                 let subclass_holder = match &annotated_type.kind {
                     type_converter::TypeKind::SubclassHolder(holder) => Some(holder),
                     _ => None,
                 };
-                let conversion = self.argument_conversion_details(
+                let mut conversion = self.argument_conversion_details(
                     &new_ty,
                     &subclass_holder.cloned(),
                     treat_as_rvalue_reference,
                     force_rust_conversion,
                 );
+                // C++ wraps anything in a register_pointer_wrapper! type.
+                match annotated_type.kind {
+                    type_converter::TypeKind::WrappedPointerReference
+                    | type_converter::TypeKind::WrappedPointerMutableReference => {
+                        conversion.cpp_conversion = CppConversionType::FromReferenceToPointer
+                    }
+                    type_converter::TypeKind::WrappedSmartPointer => {
+                        conversion.cpp_conversion = CppConversionType::Implicit
+                    }
+                    _ => {}
+                }
+                self.check_movable(&conversion)?;
+                // A string_view parameter is a &str in Rust, and a
+                // filesystem::path a &Path.
+                let was_reference = matches!(
+                    annotated_type.kind,
+                    type_converter::TypeKind::Reference
+                        | type_converter::TypeKind::MutableReference
+                        | type_converter::TypeKind::WrappedPointerReference
+                        | type_converter::TypeKind::WrappedPointerMutableReference
+                ) || matches!(
+                    conversion.cpp_conversion,
+                    CppConversionType::FromRustStrToStringView
+                        | CppConversionType::FromRustSliceToFilesystemPath
+                );
                 pt.pat = Box::new(new_pat.clone());
                 pt.ty = new_ty;
                 (
@@ -1366,11 +3325,7 @@ impl<'a> FnAnalyzer<'a> {
                         self_type,
                         name: new_pat,
                         conversion,
-                        was_reference: matches!(
-                            annotated_type.kind,
-                            type_converter::TypeKind::Reference
-                                | type_converter::TypeKind::MutableReference
-                        ),
+                        was_reference,
                         deps: annotated_type.types_encountered,
                         requires_unsafe: matches!(
                             annotated_type.kind,
@@ -1383,6 +3338,107 @@ impl<'a> FnAnalyzer<'a> {
         })
     }
 
+    /// If `conversion` passes or returns a type listed in `type_conversion!`
+    /// by value or by const reference, has the Rust wrapper convert it to
+    /// or from the Rust type given there. A value always goes through a
+    /// `UniquePtr`, even for a POD type, since that's what the conversions
+    /// make and take.
+    fn convert_listed_type(&self, conversion: &mut TypeConversionPolicy, is_return: bool) {
+        if !matches!(conversion.rust_conversion, RustConversionType::None) {
+            return;
+        }
+        let (cpp_type, by_reference) = match &conversion.unwrapped_type {
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) if matches!(conversion.cpp_conversion, CppConversionType::None) => {
+                (elem.as_ref(), true)
+            }
+            ty @ Type::Path(_)
+                if matches!(
+                    conversion.cpp_conversion,
+                    CppConversionType::None
+                        | CppConversionType::FromUniquePtrToValue
+                        | CppConversionType::FromValueToUniquePtr
+                ) =>
+            {
+                (ty, false)
+            }
+            _ => return,
+        };
+        let rust_type = match cpp_type {
+            Type::Path(typ) => self
+                .config
+                .get_type_conversion(&QualifiedName::from_type_path(typ).to_cpp_name()),
+            _ => None,
+        };
+        let rust_type = match rust_type {
+            Some(rust_type) => rust_type.clone(),
+            None => return,
+        };
+        if !by_reference {
+            conversion.cpp_conversion = if is_return {
+                CppConversionType::FromValueToUniquePtr
+            } else {
+                CppConversionType::FromUniquePtrToValue
+            };
+        }
+        conversion.rust_conversion = if is_return {
+            RustConversionType::ToConvertedType(rust_type)
+        } else {
+            RustConversionType::FromConvertedType(rust_type, by_reference)
+        };
+    }
+
+    /// Types which can be neither moved nor copied can't go into or out of
+    /// a `UniquePtr` by value, which is what `conversion` would do.
+    fn check_movable(&self, conversion: &TypeConversionPolicy) -> Result<(), ConvertError> {
+        match (&conversion.cpp_conversion, &conversion.unwrapped_type) {
+            (
+                CppConversionType::FromUniquePtrToValue | CppConversionType::FromValueToUniquePtr,
+                Type::Path(typ),
+            ) => {
+                let tn = QualifiedName::from_type_path(typ);
+                if self.immovable_types.contains(&tn) {
+                    Err(ConvertError::ImmovableTypeByValue(tn))
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// If this type is a typedef listed in `bitset!`, its name and width.
+    /// Bitsets are copied in and out, so only values and const references
+    /// work.
+    fn bitset_width(
+        &self,
+        ty: &Type,
+        is_reference: bool,
+    ) -> Result<Option<(QualifiedName, usize)>, ConvertError> {
+        let width_of = |ty: &Type| match ty {
+            Type::Path(typ) => {
+                let tn = QualifiedName::from_type_path(typ);
+                self.config
+                    .get_bitset_width(&tn.to_cpp_name())
+                    .map(|width| (tn, width))
+            }
+            _ => None,
+        };
+        match ty {
+            Type::Ptr(TypePtr {
+                elem, mutability, ..
+            }) => match width_of(elem) {
+                Some(found) if is_reference && mutability.is_none() => Ok(Some(found)),
+                Some(_) => Err(ConvertError::BitsetNotByValue),
+                None => Ok(None),
+            },
+            _ => Ok(width_of(ty)),
+        }
+    }
+
     fn argument_conversion_details(
         &self,
         ty: &Type,
@@ -1404,6 +3460,28 @@ impl<'a> FnAnalyzer<'a> {
             };
         }
         match ty {
+            Type::Path(p) if known_types().is_string_view(&QualifiedName::from_type_path(p)) => {
+                TypeConversionPolicy::new_from_str_to_string_view(ty.clone())
+            }
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) if matches!(elem.as_ref(), Type::Path(p) if known_types().is_string_view(&QualifiedName::from_type_path(p))) => {
+                TypeConversionPolicy::new_from_str_to_string_view(elem.as_ref().clone())
+            }
+            Type::Path(p)
+                if known_types().is_filesystem_path(&QualifiedName::from_type_path(p)) =>
+            {
+                TypeConversionPolicy::new_from_path_to_filesystem_path(ty.clone())
+            }
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) if matches!(elem.as_ref(), Type::Path(p) if known_types().is_filesystem_path(&QualifiedName::from_type_path(p))) => {
+                TypeConversionPolicy::new_from_path_to_filesystem_path(elem.as_ref().clone())
+            }
             Type::Path(p) => {
                 let ty = ty.clone();
                 let tn = QualifiedName::from_type_path(p);
@@ -1441,16 +3519,49 @@ impl<'a> FnAnalyzer<'a> {
         }
     }
 
+    /// Whether this is the conversion of a returned `std::string`, by value
+    /// or by const reference, which no other directive has changed.
+    fn returns_std_string(conversion: &TypeConversionPolicy) -> bool {
+        let ty = match (&conversion.cpp_conversion, &conversion.unwrapped_type) {
+            (CppConversionType::FromValueToUniquePtr, ty) => ty,
+            (
+                CppConversionType::None,
+                Type::Reference(TypeReference {
+                    elem,
+                    mutability: None,
+                    ..
+                }),
+            ) => elem.as_ref(),
+            _ => return false,
+        };
+        matches!(conversion.rust_conversion, RustConversionType::None)
+            && matches!(ty, Type::Path(typ)
+                if known_types().convertible_from_strs(&QualifiedName::from_type_path(typ)))
+    }
+
     fn return_type_conversion_details(&self, ty: &Type) -> TypeConversionPolicy {
         match ty {
             Type::Path(p) => {
                 let tn = QualifiedName::from_type_path(p);
-                if self.pod_safe_types.contains(&tn) {
+                if known_types().is_string_view(&tn) {
+                    TypeConversionPolicy::new_from_string_view_to_slice(ty.clone())
+                } else if known_types().is_filesystem_path(&tn) {
+                    TypeConversionPolicy::new_from_filesystem_path_to_path_buf(ty.clone())
+                } else if self.pod_safe_types.contains(&tn) {
                     TypeConversionPolicy::new_unconverted(ty.clone())
                 } else {
                     TypeConversionPolicy::new_to_unique_ptr(ty.clone())
                 }
             }
+            // A path returned by const reference is copied into the
+            // PathBuf just as if it were returned by value.
+            Type::Reference(TypeReference {
+                elem,
+                mutability: None,
+                ..
+            }) if matches!(elem.as_ref(), Type::Path(p) if known_types().is_filesystem_path(&QualifiedName::from_type_path(p))) => {
+                TypeConversionPolicy::new_from_filesystem_path_to_path_buf(elem.as_ref().clone())
+            }
             _ => TypeConversionPolicy::new_unconverted(ty.clone()),
         }
     }
@@ -1469,12 +3580,47 @@ impl<'a> FnAnalyzer<'a> {
                 deps: HashSet::new(),
             },
             ReturnType::Type(rarrow, boxed_type) => {
+                if let Some((tn, width)) = self.bitset_width(boxed_type, references.ref_return)? {
+                    let conversion = TypeConversionPolicy::new_from_std_bitset_to_bitset(
+                        *boxed_type.clone(),
+                        width,
+                    );
+                    return Ok(ReturnTypeAnalysis {
+                        rt: ReturnType::Type(*rarrow, Box::new(conversion.unconverted_rust_type())),
+                        conversion: Some(conversion),
+                        was_reference: false,
+                        deps: std::iter::once(tn).collect(),
+                    });
+                }
                 // TODO remove the below clone
                 let annotated_type =
                     self.convert_boxed_type(boxed_type.clone(), ns, references.ref_return)?;
                 let boxed_type = annotated_type.ty;
-                let was_reference = matches!(boxed_type.as_ref(), Type::Reference(_));
-                let conversion = self.return_type_conversion_details(boxed_type.as_ref());
+                let mut conversion = self.return_type_conversion_details(boxed_type.as_ref());
+                // C++ unwraps anything in a register_pointer_wrapper! type.
+                match annotated_type.kind {
+                    type_converter::TypeKind::WrappedPointerReference
+                    | type_converter::TypeKind::WrappedPointerMutableReference => {
+                        conversion.cpp_conversion = CppConversionType::FromPointerToReference
+                    }
+                    type_converter::TypeKind::WrappedSmartPointer => {
+                        conversion.cpp_conversion = CppConversionType::Implicit
+                    }
+                    _ => {}
+                }
+                self.check_movable(&conversion)?;
+                // A returned string_view becomes a reference into someone
+                // else's storage.
+                let was_reference = (matches!(boxed_type.as_ref(), Type::Reference(_))
+                    && !matches!(
+                        conversion.cpp_conversion,
+                        CppConversionType::FromFilesystemPathToRustVec
+                    ))
+                    || matches!(
+                        conversion.cpp_conversion,
+                        CppConversionType::FromStringViewToRustSlice
+                            | CppConversionType::FromPointerToReference
+                    );
                 ReturnTypeAnalysis {
                     rt: ReturnType::Type(*rarrow, boxed_type),
                     conversion: Some(conversion),
@@ -1508,6 +3654,9 @@ impl<'a> FnAnalyzer<'a> {
                 continue;
             }
             let path = self_ty.to_type_path();
+            // The implicit move and copy constructors of a type with an
+            // immovable field are deleted.
+            let immovable = self.immovable_types.contains(&self_ty);
             if implicit_constructors_needed.default_constructor {
                 self.synthesize_constructor(
                     self_ty.clone(),
@@ -1518,7 +3667,7 @@ impl<'a> FnAnalyzer<'a> {
                     References::default(),
                 );
             }
-            if implicit_constructors_needed.move_constructor {
+            if implicit_constructors_needed.move_constructor && !immovable {
                 self.synthesize_constructor(
                     self_ty.clone(),
                     Some("move"),
@@ -1533,8 +3682,9 @@ impl<'a> FnAnalyzer<'a> {
             }
             // C++ synthesizes two different implicit copy constructors, but moveit
             // supports only one, so we'll always synthesize that one.
-            if implicit_constructors_needed.copy_constructor_taking_const_t
-                || implicit_constructors_needed.copy_constructor_taking_t
+            if (implicit_constructors_needed.copy_constructor_taking_const_t
+                || implicit_constructors_needed.copy_constructor_taking_t)
+                && !immovable
             {
                 self.synthesize_constructor(
                     self_ty.clone(),
@@ -1551,6 +3701,218 @@ impl<'a> FnAnalyzer<'a> {
         }
     }
 
+    /// What a method of this type should be renamed to, if anything: a
+    /// getter or setter of a type listed in `properties!` is named like a
+    /// property, and otherwise `Builder::rename_methods` applies.
+    fn renamed_method(&self, self_ty: &QualifiedName, cpp_name: &str) -> Option<String> {
+        if self.config.has_properties(&self_ty.to_cpp_name()) {
+            Some(property_accessor_name(cpp_name).unwrap_or_else(|| cpp_name.to_string()))
+        } else {
+            self.rename_methods
+                .map(|rename_methods| rename_methods(cpp_name))
+        }
+    }
+
+    /// The Rust name given in `name_ctor!` to the constructor of this type
+    /// with these parameters, if any.
+    fn named_ctor(
+        &self,
+        self_ty: &QualifiedName,
+        param_details: &[ArgumentAnalysis],
+    ) -> Option<String> {
+        let param_types = ctor_param_types(param_details).ok()?;
+        self.config
+            .get_ctor_name(&self_ty.to_cpp_name(), &param_types)
+            .map(|ctor_name| ctor_name.to_string())
+    }
+
+    /// Whether a method of this type, other than a constructor, has the
+    /// Rust name given in `name_ctor!` to a constructor, or that of its
+    /// `make_unique` counterpart.
+    fn named_ctor_collides(&self, self_ty: &QualifiedName, ctor_name: &str) -> bool {
+        let make_unique_name = format!("make_unique_{}", ctor_name);
+        self.method_names
+            .get(self_ty)
+            .into_iter()
+            .flatten()
+            .any(|cpp_name| {
+                let rust_name = self.method_rust_name(self_ty, cpp_name);
+                rust_name == ctor_name || rust_name == make_unique_name
+            })
+    }
+
+    /// The Rust name which a (non-overloaded) method of this C++ name gets,
+    /// after any renaming requested by `properties!` or
+    /// `Builder::rename_methods`.
+    fn method_rust_name(&self, self_ty: &QualifiedName, cpp_name: &str) -> Ident {
+        match self.renamed_method(self_ty, cpp_name) {
+            Some(mut rust_name) => {
+                if validate_ident_ok_for_rust(&rust_name).is_err() {
+                    rust_name.push('_');
+                }
+                make_ident(rust_name)
+            }
+            None => make_ident(cpp_name),
+        }
+    }
+
+    /// A type listed in `register_awaitable!` only implements
+    /// `autocxx::Awaitable` if its resume and done methods were bound as
+    /// well as its result method.
+    fn check_awaitables(apis: &mut [Api<FnPhase>]) {
+        let bound_methods: HashSet<(QualifiedName, String)> = apis
+            .iter()
+            .filter_map(|api| match api {
+                Api::Function {
+                    analysis:
+                        FnAnalysis {
+                            kind: FnKind::Method(self_ty, _),
+                            rust_name,
+                            ignore_reason: Ok(_),
+                            ..
+                        },
+                    ..
+                } => Some((self_ty.clone(), rust_name.clone())),
+                _ => None,
+            })
+            .collect();
+        for api in apis.iter_mut() {
+            if let Api::Function {
+                analysis:
+                    FnAnalysis {
+                        kind: FnKind::Method(self_ty, _),
+                        rust_name,
+                        awaitable: Some(awaitable),
+                        ignore_reason: ignore_reason @ Ok(_),
+                        ..
+                    },
+                ..
+            } = api
+            {
+                let missing = [&awaitable.resume, &awaitable.done]
+                    .into_iter()
+                    .find(|method| !bound_methods.contains(&(self_ty.clone(), method.to_string())))
+                    .cloned();
+                if let Some(missing) = missing {
+                    *ignore_reason = Err(ConvertErrorWithContext(
+                        ConvertError::InvalidAwaitable(format!(
+                            "its method {} couldn't be bound",
+                            missing
+                        )),
+                        Some(ErrorContext::Method {
+                            self_ty: self_ty.get_final_ident(),
+                            method: make_ident(&rust_name),
+                        }),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// C++ lets you pass an object of a derived class where its base class
+    /// is taken by value, silently copying only the base class part of it.
+    /// In Rust that's a type error, since the parameter is a `UniquePtr` to
+    /// the base class, so we synthesize a way to ask for the copy explicitly:
+    /// an implementation of `autocxx::SliceToBase` for each base which can
+    /// be copied. POD bases don't need one; you can already copy them out of
+    /// `as_ref()`.
+    fn add_slice_functions(&mut self, apis: &mut Vec<Api<FnPhase>>) {
+        if self.config.exclude_impls {
+            return;
+        }
+        let non_pod_types: HashSet<_> = apis
+            .iter()
+            .filter_map(|api| match api {
+                Api::Struct {
+                    name,
+                    analysis:
+                        PodAnalysis {
+                            kind: TypeKind::NonPod,
+                            ..
+                        },
+                    ..
+                } => Some(&name.name),
+                _ => None,
+            })
+            .collect();
+        let copyable_types: HashSet<_> = apis
+            .iter()
+            .filter_map(|api| match api {
+                Api::Function {
+                    analysis:
+                        FnAnalysis {
+                            kind:
+                                FnKind::TraitMethod {
+                                    kind: TraitMethodKind::CopyConstructor,
+                                    impl_for,
+                                    ..
+                                },
+                            ignore_reason: Ok(_),
+                            ..
+                        },
+                    ..
+                } => Some(impl_for),
+                _ => None,
+            })
+            .collect();
+        let slices = apis
+            .iter()
+            .filter_map(|api| match api {
+                Api::Struct { name, analysis, .. } => Some(
+                    analysis
+                        .castable_bases
+                        .iter()
+                        .filter(|base| {
+                            non_pod_types.contains(base) && copyable_types.contains(base)
+                        })
+                        .map(|base| (name.name.clone(), base.clone())),
+                ),
+                _ => None,
+            })
+            .flatten()
+            .collect_vec();
+        for (from, to) in slices {
+            let ident = make_ident(self.config.uniquify_name_per_mod(&format!(
+                "slice_{}_to_{}",
+                from.get_final_item(),
+                to.get_final_item()
+            )));
+            let fake_api_name = ApiName::new(from.get_namespace(), ident.clone());
+            let ns = from.get_namespace().clone();
+            let from_typ = from.to_type_path();
+            let to_typ = to.to_type_path();
+            let items = report_any_error(&ns, apis, || {
+                self.analyze_foreign_fn_and_subclasses(
+                    fake_api_name,
+                    Box::new(FuncToConvert {
+                        ident,
+                        doc_attr: None,
+                        inputs: parse_quote! { this: *const #from_typ },
+                        output: parse_quote! { -> #to_typ },
+                        vis: parse_quote! { pub },
+                        virtualness: Virtualness::None,
+                        cpp_vis: CppVisibility::Public,
+                        special_member: None,
+                        unused_template_param: false,
+                        references: References {
+                            ref_params: [make_ident("this")].into_iter().collect(),
+                            ..Default::default()
+                        },
+                        original_name: None,
+                        self_ty: Some(from),
+                        synthesized_this_type: None,
+                        is_deleted: false,
+                        link_name: None,
+                        add_to_trait: Some(TraitSynthesis::SliceToBase(to)),
+                        synthetic_cpp: Some((CppFunctionBody::Cast, CppFunctionKind::Function)),
+                        provenance: Provenance::SynthesizedOther,
+                    }),
+                )
+            });
+            apis.extend(items.into_iter().flatten());
+        }
+    }
+
     fn synthesize_constructor(
         &mut self,
         self_ty: QualifiedName,
@@ -1588,6 +3950,7 @@ impl<'a> FnAnalyzer<'a> {
                     original_name: None,
                     synthesized_this_type: None,
                     is_deleted: false,
+                    link_name: None,
                     add_to_trait: None,
                     synthetic_cpp: None,
                     provenance: Provenance::SynthesizedOther,
@@ -1598,6 +3961,192 @@ impl<'a> FnAnalyzer<'a> {
     }
 }
 
+/// Gives a returned reference, or `Pin<&mut T>`, the `'static` lifetime.
+fn make_reference_static(ty: &mut Type) {
+    match ty {
+        Type::Reference(tyr) => tyr.lifetime = Some(parse_quote! { 'static }),
+        Type::Path(typ) => {
+            if let Some(last_seg) = typ.path.segments.last_mut() {
+                if last_seg.ident == "Pin" {
+                    if let PathArguments::AngleBracketed(ab) = &mut last_seg.arguments {
+                        if let Some(GenericArgument::Type(ty)) = ab.args.iter_mut().next() {
+                            make_reference_static(ty);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether this is the parameter with the given name.
+fn is_param_named(arg: &FnArg, name: &str) -> bool {
+    matches!(arg, FnArg::Typed(pt) if matches!(pt.pat.as_ref(), Pat::Ident(pp) if pp.ident == name))
+}
+
+/// The function type of a function pointer parameter, which bindgen
+/// wraps in an `Option` since it may be null.
+fn callback_fn_type(ty: &Type) -> Option<&TypeBareFn> {
+    match ty {
+        Type::BareFn(callback) => Some(callback),
+        Type::Path(typ) => {
+            let last_seg = typ.path.segments.last()?;
+            match &last_seg.arguments {
+                PathArguments::AngleBracketed(ab) if last_seg.ident == "Option" => {
+                    match ab.args.iter().exactly_one().ok()? {
+                        GenericArgument::Type(ty) => callback_fn_type(ty),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// If this parameter is a reference to an array, which bindgen gives as a
+/// pointer to it, a pointer to its first element and its length.
+fn array_reference(
+    ty: &Type,
+    is_reference: bool,
+) -> Result<Option<(Box<Type>, usize)>, ConvertError> {
+    match ty {
+        Type::Ptr(TypePtr {
+            elem, mutability, ..
+        }) if is_reference => match elem.as_ref() {
+            Type::Array(arr) => {
+                let len = match &arr.len {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(len),
+                        ..
+                    }) => len.base10_parse().map_err(|_| {
+                        ConvertError::UnsupportedType(ty.to_token_stream().to_string())
+                    })?,
+                    _ => {
+                        return Err(ConvertError::UnsupportedType(
+                            ty.to_token_stream().to_string(),
+                        ))
+                    }
+                };
+                let arr_elem = &arr.elem;
+                let const_token = if mutability.is_some() {
+                    None
+                } else {
+                    Some(syn::Token![const](Span::call_site()))
+                };
+                Ok(Some((
+                    Box::new(parse_quote! { * #mutability #const_token #arr_elem }),
+                    len,
+                )))
+            }
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// If this parameter is a `std::function`, or (if it's a reference) a
+/// const reference to one, the function type it holds.
+fn std_function_signature(ty: &Type, is_reference: bool) -> Option<&TypeBareFn> {
+    match ty {
+        Type::Ptr(TypePtr {
+            mutability: None,
+            elem,
+            ..
+        }) if is_reference => std_function_signature(elem, false),
+        Type::Path(typ)
+            if QualifiedName::from_type_path(typ)
+                == QualifiedName::new_from_cpp_name("std::function") =>
+        {
+            match &typ.path.segments.last()?.arguments {
+                PathArguments::AngleBracketed(ab) => match ab.args.iter().exactly_one().ok()? {
+                    GenericArgument::Type(signature) => callback_fn_type(signature),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// If this parameter is a `std::reference_wrapper<T>`, the type `T`.
+fn reference_wrapper_referent(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(typ)
+            if QualifiedName::from_type_path(typ)
+                == QualifiedName::new_from_cpp_name("std::reference_wrapper") =>
+        {
+            match &typ.path.segments.last()?.arguments {
+                PathArguments::AngleBracketed(ab) => match ab.args.iter().exactly_one().ok()? {
+                    GenericArgument::Type(referent) => Some(referent),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether this is a Rust integer type, or one of autocxx's C integer
+/// newtypes.
+fn is_integer_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(typ) => {
+            let qn = QualifiedName::from_type_path(typ);
+            (known_types().is_ctype(&qn) && !known_types().is_byte(&qn))
+                || [
+                    "usize", "isize", "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64",
+                ]
+                .iter()
+                .any(|int| qn == QualifiedName::new_from_cpp_name(int))
+        }
+        _ => false,
+    }
+}
+
+/// The element type of a `std::unique_ptr<T[]>`, which bindgen calls a
+/// `unique_ptr<[T; 0]>`.
+fn unique_array_element_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(typ)
+            if QualifiedName::from_type_path(typ)
+                == QualifiedName::new_from_cpp_name("std::unique_ptr") =>
+        {
+            match &typ.path.segments.last()?.arguments {
+                PathArguments::AngleBracketed(ab) => match ab.args.first()? {
+                    GenericArgument::Type(Type::Array(arr)) => Some(arr.elem.as_ref()),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether a callback can take or return this type as it is, without any
+/// conversion. Pointers are fine; so are integers and other C types.
+fn is_plain_callback_type(ty: &Type) -> bool {
+    match ty {
+        Type::Ptr(_) => true,
+        Type::Path(typ) => {
+            let qn = QualifiedName::from_type_path(typ);
+            known_types().is_ctype(&qn)
+                || [
+                    "bool", "f32", "f64", "usize", "isize", "u8", "i8", "u16", "i16", "u32", "i32",
+                    "u64", "i64",
+                ]
+                .iter()
+                .any(|prim| qn == QualifiedName::new_from_cpp_name(prim))
+        }
+        _ => false,
+    }
+}
+
 fn error_context_for_method(self_ty: &QualifiedName, rust_name: &str) -> ErrorContext {
     ErrorContext::Method {
         self_ty: self_ty.get_final_ident(),
@@ -1620,6 +4169,8 @@ impl Api<FnPhase> {
                 }
             },
             Api::RustSubclassFn { subclass, .. } => subclass.0.name.clone(),
+            Api::CppAny { .. } => QualifiedName::new_from_cpp_name("std::any"),
+            Api::CppIterable { container, .. } => container.clone(),
             _ => self.name().clone(),
         }
     }
@@ -1641,10 +4192,23 @@ impl Api<FnPhase> {
                 },
                 ..
             } | Api::StringConstructor { .. }
+                | Api::CppAny { .. }
+                | Api::CppIterable { .. }
                 | Api::ConcreteType { .. }
                 | Api::CType { .. }
                 | Api::RustSubclassFn { .. }
                 | Api::Subclass { .. }
+        ) || matches!(
+            &self,
+            // The C++ subclass must make this method public.
+            Api::Function {
+                fun,
+                analysis: FnAnalysis {
+                    ignore_reason: Ok(_),
+                    ..
+                },
+                ..
+            } if matches!(fun.provenance, Provenance::SynthesizedSubclassProtectedMethod(_))
         )
     }
 
@@ -1652,6 +4216,8 @@ impl Api<FnPhase> {
         match self {
             Api::Function { ref analysis, .. } => Some(analysis.cxxbridge_name.clone()),
             Api::StringConstructor { .. }
+            | Api::CppAny { .. }
+            | Api::CppIterable { .. }
             | Api::Const { .. }
             | Api::IgnoredItem { .. }
             | Api::RustSubclassFn { .. } => None,
@@ -1683,6 +4249,13 @@ impl Api<FnPhase> {
                 superclass,
             } => Box::new(std::iter::once(superclass.clone())),
             Api::RustSubclassFn { details, .. } => Box::new(details.dependency.iter().cloned()),
+            Api::CppAny { castables, .. } => Box::new(
+                std::iter::once(QualifiedName::new_from_cpp_name("std::any"))
+                    .chain(castables.iter().cloned()),
+            ),
+            Api::CppIterable {
+                container, element, ..
+            } => Box::new(vec![container.clone(), element.clone()].into_iter()),
             _ => Box::new(std::iter::empty()),
         }
     }
@@ -0,0 +1,63 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use crate::{
+    conversion::{
+        analysis::pod::PodPhase, api::Api, codegen_cpp::type_to_cpp::type_to_cpp, ConvertError,
+    },
+    types::QualifiedName,
+};
+
+use super::ArgumentAnalysis;
+
+/// The C++ names of the methods of each type, other than its constructors
+/// and destructor, so that the names given in `name_ctor!` can be checked
+/// against them.
+pub(super) fn find_method_names(apis: &[Api<PodPhase>]) -> HashMap<QualifiedName, Vec<String>> {
+    let mut method_names: HashMap<QualifiedName, Vec<String>> = HashMap::new();
+    for api in apis {
+        if let Api::Function { name, fun, .. } = api {
+            if let Some(self_ty) = &fun.self_ty {
+                if fun.special_member.is_some() {
+                    continue;
+                }
+                let cpp_name = name
+                    .cpp_name_if_present()
+                    .cloned()
+                    .unwrap_or_else(|| fun.ident.to_string());
+                if cpp_name != self_ty.get_final_item() {
+                    method_names
+                        .entry(self_ty.clone())
+                        .or_default()
+                        .push(cpp_name);
+                }
+            }
+        }
+    }
+    method_names
+}
+
+/// The C++ types of a constructor's parameters, other than `this`, as
+/// they'd be written in `name_ctor!`.
+pub(super) fn ctor_param_types(
+    param_details: &[ArgumentAnalysis],
+) -> Result<Vec<String>, ConvertError> {
+    param_details
+        .iter()
+        .filter(|pd| pd.self_type.is_none())
+        .map(|pd| type_to_cpp(&pd.conversion.unwrapped_type, &HashMap::new()))
+        .collect()
+}
@@ -0,0 +1,86 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use autocxx_parser::RustPath;
+use syn::parse_quote;
+
+use crate::{
+    conversion::{
+        analysis::pod::PodPhase,
+        api::{Api, FuncToConvert},
+    },
+    types::{Namespace, QualifiedName},
+};
+
+use super::{
+    function_wrapper::{CppConversionType, RustConversionType, TypeConversionPolicy},
+    ArgumentAnalysis,
+};
+
+/// Finds the free functions which are overloaded. The C++ wrapper names
+/// the type of a function whose symbol is resolved at runtime by taking
+/// its address, which it can't do if there's more than one of them, so
+/// these are still linked as usual.
+pub(super) fn find_overloaded_functions(apis: &[Api<PodPhase>]) -> HashSet<QualifiedName> {
+    let mut groups: HashMap<(&Namespace, String), Vec<&QualifiedName>> = HashMap::new();
+    for api in apis {
+        if let Api::Function { name, fun, .. } = api {
+            if fun.self_ty.is_some()
+                || fun.special_member.is_some()
+                || fun.synthetic_cpp.is_some()
+                || fun.add_to_trait.is_some()
+            {
+                continue;
+            }
+            let cpp_name = name
+                .cpp_name_if_present()
+                .cloned()
+                .unwrap_or_else(|| name.name.get_final_item().to_string());
+            groups
+                .entry((name.name.get_namespace(), cpp_name))
+                .or_default()
+                .push(&name.name);
+        }
+    }
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// The parameter through which the C++ wrapper is passed the address of
+/// the function, as found by the resolver given in `symbol_resolver!`.
+/// That's looked up by the symbol bindgen says the function is linked by.
+pub(super) fn resolved_symbol_param(resolver: &RustPath, fun: &FuncToConvert) -> ArgumentAnalysis {
+    let symbol = fun
+        .link_name
+        .clone()
+        .unwrap_or_else(|| fun.ident.to_string());
+    ArgumentAnalysis {
+        conversion: TypeConversionPolicy {
+            unwrapped_type: parse_quote! { u64 },
+            cpp_conversion: CppConversionType::None,
+            rust_conversion: RustConversionType::FromResolvedSymbol(resolver.clone(), symbol),
+        },
+        name: parse_quote! { autocxx_symbol },
+        self_type: None,
+        was_reference: false,
+        deps: HashSet::new(),
+        requires_unsafe: false,
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use autocxx_parser::IncludeCppConfig;
+
+use crate::{
+    conversion::{analysis::pod::PodPhase, api::Api},
+    types::{validate_ident_ok_for_rust, Namespace, QualifiedName},
+};
+
+/// The Rust name of a free function once any prefix listed in
+/// `strip_prefix!` is removed from its C++ name, or `None` if it has no
+/// such prefix. A name which is left as a keyword gets a trailing
+/// underscore, as any other such C++ name does.
+pub(super) fn stripped_name(config: &IncludeCppConfig, cpp_name: &str) -> Option<String> {
+    config
+        .strip_function_prefix(cpp_name)
+        .map(rust_name_for_cpp_name)
+}
+
+fn rust_name_for_cpp_name(cpp_name: &str) -> String {
+    if validate_ident_ok_for_rust(cpp_name).is_err() {
+        format!("{}_", cpp_name)
+    } else {
+        cpp_name.to_string()
+    }
+}
+
+/// A free function's name, C++ name, and whether it has a prefix to strip.
+type StrippableFunction<'a> = (&'a QualifiedName, String, bool);
+
+/// Finds the free functions to which `strip_prefix!` would give the same
+/// Rust name as a different C++ function in the same namespace, whether
+/// or not that one also had a prefix stripped. Returns their names; they
+/// aren't generated, since it can't be clear which one the name means.
+/// Overloads of the same C++ function share a name anyway, so don't
+/// count.
+pub(super) fn find_stripped_name_collisions(
+    apis: &[Api<PodPhase>],
+    config: &IncludeCppConfig,
+) -> HashSet<QualifiedName> {
+    let mut groups: HashMap<(&Namespace, String), Vec<StrippableFunction>> = HashMap::new();
+    for api in apis {
+        if let Api::Function { name, fun, .. } = api {
+            if fun.self_ty.is_some()
+                || fun.special_member.is_some()
+                || fun.synthetic_cpp.is_some()
+                || fun.add_to_trait.is_some()
+            {
+                continue;
+            }
+            let cpp_name = name
+                .cpp_name_if_present()
+                .cloned()
+                .unwrap_or_else(|| name.name.get_final_item().to_string());
+            let (rust_name, was_stripped) = match stripped_name(config, &cpp_name) {
+                Some(stripped) => (stripped, true),
+                None => (rust_name_for_cpp_name(&cpp_name), false),
+            };
+            groups
+                .entry((name.name.get_namespace(), rust_name))
+                .or_default()
+                .push((&name.name, cpp_name, was_stripped));
+        }
+    }
+    groups
+        .into_values()
+        .filter(|group| group.iter().any(|(_, cpp_name, _)| *cpp_name != group[0].1))
+        .flat_map(|group| {
+            group
+                .into_iter()
+                .filter(|(_, _, was_stripped)| *was_stripped)
+                .map(|(name, _, _)| name.clone())
+        })
+        .collect()
+}
@@ -72,11 +72,28 @@ pub(super) fn create_subclass_fn_wrapper(
         references: fun.references.clone(),
         add_to_trait: fun.add_to_trait.clone(),
         is_deleted: fun.is_deleted,
+        link_name: None,
         synthetic_cpp: None,
         provenance: Provenance::SynthesizedOther,
     })
 }
 
+/// A subclass may call the protected methods of its superclass, so the C++
+/// subclass makes each such method public, and Rust can call it there.
+pub(super) fn create_subclass_protected_fn_wrapper(
+    sub: SubclassName,
+    fun: &FuncToConvert,
+) -> Box<FuncToConvert> {
+    let mut wrapper = create_subclass_fn_wrapper(
+        sub.clone(),
+        &QualifiedName::new(&Namespace::new(), fun.ident.clone()),
+        fun,
+    );
+    wrapper.original_name = fun.original_name.clone();
+    wrapper.provenance = Provenance::SynthesizedSubclassProtectedMethod(sub);
+    wrapper
+}
+
 pub(super) fn create_subclass_function(
     sub: &SubclassName,
     analysis: &super::FnAnalysis,
@@ -122,6 +139,7 @@ pub(super) fn create_subclass_function(
                     .collect(),
                 kind,
                 pass_obs_field: true,
+                error_code_param: None,
                 qualification: Some(cpp),
             },
             superclass: superclass.clone(),
@@ -163,6 +181,7 @@ pub(super) fn create_subclass_constructor(
         argument_conversion: args.collect(),
         kind: CppFunctionKind::SynthesizedConstructor,
         pass_obs_field: false,
+        error_code_param: None,
         qualification: Some(cpp.clone()),
         original_cpp_name: cpp.to_cpp_name(),
     };
@@ -210,6 +229,7 @@ pub(super) fn create_subclass_constructor(
         self_ty: Some(cpp),
         add_to_trait: None,
         is_deleted: fun.is_deleted,
+        link_name: None,
         synthetic_cpp: None,
         provenance: Provenance::SynthesizedSubclassConstructor(subclass_constructor_details),
     });
@@ -17,12 +17,17 @@ pub(crate) mod allocators;
 pub(crate) mod casts;
 pub(crate) mod ctypes;
 mod depth_first;
+pub(crate) mod fn_tables;
 pub(crate) mod fun;
 pub(crate) mod gc;
 mod name_check;
 pub(crate) mod pod; // hey, that rhymes
+pub(crate) mod refcount;
 pub(crate) mod remove_ignored;
+pub(crate) mod shared_from_this;
+pub(crate) mod smart_pointer;
 pub(crate) mod tdef;
 mod type_converter;
+pub(crate) mod validity;
 
 pub(crate) use name_check::check_names;
@@ -72,7 +72,10 @@ pub(crate) fn check_names(apis: Vec<Api<FnPhase>>) -> Vec<Api<FnPhase>> {
         Api::ConcreteType { .. }
         | Api::CType { .. }
         | Api::StringConstructor { .. }
+        | Api::CppAny { .. }
+        | Api::CppIterable { .. }
         | Api::RustType { .. }
+        | Api::ExternCppType { .. }
         | Api::RustSubclassFn { .. }
         | Api::RustFn { .. }
         | Api::IgnoredItem { .. } => Ok(Box::new(std::iter::once(api))),
@@ -123,10 +123,24 @@ impl ByValueChecker {
                 _ => {}
             }
         }
+        // Types which must be opaque override whatever we found out about
+        // them above, and any request to make them POD.
+        let force_opaques: Vec<_> = config
+            .get_force_opaques()
+            .iter()
+            .map(|ty| QualifiedName::new_from_cpp_name(ty))
+            .collect();
+        for tn in &force_opaques {
+            let safety = PodState::UnsafeToBePod(format!("type {} is forced to be opaque", tn));
+            byvalue_checker
+                .results
+                .insert(tn.clone(), StructDetails::new(safety));
+        }
         let pod_requests = config
             .get_pod_requests()
             .iter()
             .map(|ty| QualifiedName::new_from_cpp_name(ty))
+            .filter(|ty| !force_opaques.contains(ty))
             .collect();
         byvalue_checker
             .satisfy_requests(pod_requests)
@@ -242,7 +256,9 @@ impl ByValueChecker {
         results
     }
 
-    fn has_vtable(def: &ItemStruct) -> bool {
+    /// Whether bindgen has given this type a vtable pointer, which it does
+    /// only for the first polymorphic type in a hierarchy.
+    pub(crate) fn has_vtable(def: &ItemStruct) -> bool {
         for f in &def.fields {
             if f.ident.as_ref().map(|id| id == "vtable_").unwrap_or(false) {
                 return true;
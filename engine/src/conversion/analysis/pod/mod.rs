@@ -48,6 +48,9 @@ pub(crate) struct PodAnalysis {
     pub(crate) field_types: HashSet<QualifiedName>,
     pub(crate) movable: bool,
     pub(crate) is_generic: bool,
+    /// Whether this type has its own vtable pointer. Types which inherit
+    /// one from a base are polymorphic too, but this isn't set for them.
+    pub(crate) has_vtable: bool,
 }
 
 pub(crate) struct PodPhase;
@@ -92,7 +95,7 @@ pub(crate) fn analyze_pod_apis(
                 &deleted_move_constructors,
             )
         },
-        analyze_enum,
+        |name, item| analyze_enum(name, item, config),
         Api::typedef_unchanged,
     );
     // Conceivably, the process of POD-analysing the first set of APIs could result
@@ -114,7 +117,7 @@ pub(crate) fn analyze_pod_apis(
                 &deleted_move_constructors,
             )
         },
-        analyze_enum,
+        |name, item| analyze_enum(name, item, config),
         Api::typedef_unchanged,
     );
     assert!(more_extra_apis.is_empty());
@@ -124,9 +127,26 @@ pub(crate) fn analyze_pod_apis(
 fn analyze_enum(
     name: ApiName,
     mut item: ItemEnum,
+    config: &IncludeCppConfig,
 ) -> Result<Box<dyn Iterator<Item = Api<PodPhase>>>, ConvertErrorWithContext> {
     let metadata = BindgenSemanticAttributes::new_retaining_others(&mut item.attrs);
     metadata.check_for_fatal_attrs(&name.name.get_final_ident())?;
+    if let Some(flags) = config.get_flags(&name.name.to_cpp_name()) {
+        let unknown = flags
+            .exclusive_groups
+            .iter()
+            .flatten()
+            .find(|member| !item.variants.iter().any(|v| v.ident == member));
+        if let Some(unknown) = unknown {
+            return Err(ConvertErrorWithContext(
+                ConvertError::InvalidFlags(format!(
+                    "its exclusive group names {}, which is not one of its enumerators",
+                    unknown
+                )),
+                Some(ErrorContext::Item(name.name.get_final_ident())),
+            ));
+        }
+    }
     Ok(Box::new(std::iter::once(Api::Enum { name, item })))
 }
 
@@ -174,6 +194,15 @@ fn analyze_struct(
     } else {
         TypeKind::NonPod
     };
+    if config.is_transparent_newtype(&name.name.to_cpp_name()) {
+        check_transparent_newtype(&details.item, !bases.is_empty())
+            .map_err(|err| ConvertErrorWithContext(err, Some(ErrorContext::Item(id.clone()))))?;
+    }
+    let cpp_name = name.name.to_cpp_name();
+    for field in config.get_volatile_fields(&cpp_name) {
+        check_volatile_field(&details.item, &type_kind, field)
+            .map_err(|err| ConvertErrorWithContext(err, Some(ErrorContext::Item(id.clone()))))?;
+    }
     let castable_bases = bases
         .iter()
         .filter(|(_, is_public)| **is_public)
@@ -182,6 +211,7 @@ fn analyze_struct(
         .cloned()
         .collect();
     let is_generic = !details.item.generics.params.is_empty();
+    let has_vtable = ByValueChecker::has_vtable(&details.item);
     Ok(Box::new(std::iter::once(Api::Struct {
         name,
         details,
@@ -192,10 +222,62 @@ fn analyze_struct(
             field_types,
             movable,
             is_generic,
+            has_vtable,
         },
     })))
 }
 
+/// Whether a struct can be represented as a `#[repr(transparent)]`
+/// newtype around its one field.
+fn check_transparent_newtype(item: &ItemStruct, has_bases: bool) -> Result<(), ConvertError> {
+    let problem = if has_bases {
+        "it has base classes"
+    } else if !item.generics.params.is_empty() {
+        "it is templated"
+    } else if item.fields.len() != 1 {
+        "it does not have exactly one field"
+    } else if item.attrs.iter().any(|attr| {
+        attr.path.is_ident("repr") && {
+            let repr = attr.tokens.to_string();
+            repr.contains("align") || repr.contains("packed")
+        }
+    }) {
+        "it has an explicit alignment or packing"
+    } else {
+        return Ok(());
+    };
+    Err(ConvertError::InvalidTransparentNewtype(problem.to_string()))
+}
+
+/// Whether a field listed in `volatile_field!` can be given volatile
+/// accessors.
+fn check_volatile_field(
+    item: &ItemStruct,
+    type_kind: &TypeKind,
+    field: &str,
+) -> Result<(), ConvertError> {
+    if !matches!(type_kind, TypeKind::Pod) {
+        return Err(ConvertError::InvalidVolatileField(format!(
+            "{} is a field of a type which isn't POD",
+            field
+        )));
+    }
+    let escaped = format!("{}_", field);
+    if item
+        .fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref())
+        .any(|ident| ident == field || *ident == escaped)
+    {
+        Ok(())
+    } else {
+        Err(ConvertError::InvalidVolatileField(format!(
+            "there is no field called {}",
+            field
+        )))
+    }
+}
+
 fn get_struct_field_types(
     type_converter: &mut TypeConverter,
     ns: &Namespace,
@@ -245,6 +327,7 @@ fn find_deleted_move_and_copy_constructors(apis: &[Api<TypedefPhase>]) -> HashSe
                 special_member:
                     Some(SpecialMemberKind::MoveConstructor | SpecialMemberKind::CopyConstructor),
                 is_deleted: true,
+                link_name: None,
                 inputs,
                 ..
              } => match is_a_pointer_arg(inputs.iter().next()) {
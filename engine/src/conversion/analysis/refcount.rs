@@ -0,0 +1,114 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Code to create functions to add and release references to types
+//! listed in `intrusive_refcount!`.
+
+use autocxx_parser::{IncludeCppConfig, IntrusiveRefcount};
+use syn::{parse_quote, punctuated::Punctuated, token::Comma, FnArg, ReturnType};
+
+use crate::{
+    conversion::api::{
+        Api, ApiName, CppVisibility, FuncToConvert, Provenance, References, TraitSynthesis,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+pub(crate) fn create_refcount_functions(
+    apis: Vec<Api<PodPhase>>,
+    config: &IncludeCppConfig,
+) -> Vec<Api<PodPhase>> {
+    apis.into_iter()
+        .flat_map(|api| -> Box<dyn Iterator<Item = Api<PodPhase>>> {
+            match &api {
+                Api::Struct { name, .. } => {
+                    match config.get_intrusive_refcount(&name.name.to_cpp_name()) {
+                        Some(refcount) => Box::new(
+                            create_add_ref_and_release(name.name.clone(), refcount)
+                                .chain(std::iter::once(api)),
+                        ),
+                        None => Box::new(std::iter::once(api)),
+                    }
+                }
+                _ => Box::new(std::iter::once(api)),
+            }
+        })
+        .collect()
+}
+
+fn create_add_ref_and_release(
+    ty_name: QualifiedName,
+    refcount: &IntrusiveRefcount,
+) -> impl Iterator<Item = Api<PodPhase>> {
+    let typ = ty_name.to_type_path();
+    let inputs: Punctuated<FnArg, Comma> = parse_quote! {
+        arg0: *mut #typ
+    };
+    [
+        (
+            TraitSynthesis::AddRef(ty_name.clone()),
+            get_name(&ty_name, "add_ref"),
+            make_ident(&refcount.add_ref),
+        ),
+        (
+            TraitSynthesis::Release(ty_name.clone()),
+            get_name(&ty_name, "release"),
+            make_ident(&refcount.release),
+        ),
+    ]
+    .into_iter()
+    .map(move |(synthesis, name, method)| {
+        let ident = name.get_final_ident();
+        let api_name = ApiName::new_from_qualified_name(name);
+        Api::Function {
+            name: api_name,
+            name_for_gc: None,
+            fun: Box::new(FuncToConvert {
+                ident,
+                doc_attr: None,
+                inputs: inputs.clone(),
+                output: ReturnType::Default,
+                vis: parse_quote! { pub },
+                virtualness: crate::conversion::api::Virtualness::None,
+                cpp_vis: CppVisibility::Public,
+                special_member: None,
+                unused_template_param: false,
+                references: References::default(),
+                original_name: None,
+                self_ty: None,
+                synthesized_this_type: None,
+                synthetic_cpp: Some((
+                    CppFunctionBody::RefcountCall(method),
+                    CppFunctionKind::Function,
+                )),
+                add_to_trait: Some(synthesis),
+                is_deleted: false,
+                link_name: None,
+                provenance: Provenance::SynthesizedOther,
+            }),
+            analysis: (),
+        }
+    })
+}
+
+fn get_name(ty_name: &QualifiedName, label: &str) -> QualifiedName {
+    let name = format!("{}_{}", ty_name.get_final_item(), label);
+    let name_id = make_ident(name);
+    QualifiedName::new(ty_name.get_namespace(), name_id)
+}
@@ -0,0 +1,105 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Code to create `shared_from_this` and `weak_from_this` methods for
+//! types which derive from `std::enable_shared_from_this`. bindgen doesn't
+//! give us the methods they inherit from it, since it's a template.
+
+use syn::parse_quote;
+
+use crate::{
+    conversion::api::{Api, ApiName, CppVisibility, FuncToConvert, Provenance, References},
+    types::{make_ident, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+pub(crate) fn create_shared_from_this_functions(apis: Vec<Api<PodPhase>>) -> Vec<Api<PodPhase>> {
+    apis.into_iter()
+        .flat_map(|api| -> Box<dyn Iterator<Item = Api<PodPhase>>> {
+            match &api {
+                Api::Struct { name, analysis, .. }
+                    if analysis.bases.iter().any(is_enable_shared_from_this) =>
+                {
+                    Box::new(create_shared_and_weak(name.name.clone()).chain(std::iter::once(api)))
+                }
+                _ => Box::new(std::iter::once(api)),
+            }
+        })
+        .collect()
+}
+
+/// The standard library may put it in an inline namespace within `std`.
+fn is_enable_shared_from_this(base: &QualifiedName) -> bool {
+    base.get_final_item() == "enable_shared_from_this"
+        && base.get_namespace().iter().next().map(String::as_str) == Some("std")
+}
+
+fn create_shared_and_weak(ty_name: QualifiedName) -> impl Iterator<Item = Api<PodPhase>> {
+    let typ = ty_name.to_type_path();
+    [
+        (
+            "shared_from_this",
+            parse_quote! { -> root::std::shared_ptr<#typ> },
+            false,
+        ),
+        (
+            "weak_from_this",
+            parse_quote! { -> root::std::weak_ptr<#typ> },
+            true,
+        ),
+    ]
+    .into_iter()
+    .map(move |(cpp_name, output, weak)| {
+        let ident = make_ident(format!("{}_{}", ty_name.get_final_item(), cpp_name));
+        Api::Function {
+            name: ApiName::new_with_cpp_name(
+                ty_name.get_namespace(),
+                ident.clone(),
+                Some(cpp_name.to_string()),
+            ),
+            name_for_gc: None,
+            fun: Box::new(FuncToConvert {
+                ident,
+                doc_attr: None,
+                inputs: parse_quote! { this: *const #typ },
+                output,
+                vis: parse_quote! { pub },
+                virtualness: crate::conversion::api::Virtualness::None,
+                cpp_vis: CppVisibility::Public,
+                special_member: None,
+                unused_template_param: false,
+                references: References {
+                    ref_params: [make_ident("this")].into_iter().collect(),
+                    ..Default::default()
+                },
+                original_name: None,
+                self_ty: Some(ty_name.clone()),
+                synthesized_this_type: None,
+                synthetic_cpp: Some((
+                    CppFunctionBody::SharedFromThis(weak),
+                    CppFunctionKind::Function,
+                )),
+                add_to_trait: None,
+                is_deleted: false,
+                link_name: None,
+                provenance: Provenance::SynthesizedOther,
+            }),
+            analysis: (),
+        }
+    })
+}
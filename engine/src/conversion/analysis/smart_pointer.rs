@@ -0,0 +1,112 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Code to create functions which dereference types listed in
+//! `smart_pointer!`. bindgen doesn't tell us about `operator->`.
+
+use autocxx_parser::{IncludeCppConfig, SmartPointer};
+use syn::parse_quote;
+
+use crate::{
+    conversion::api::{
+        Api, ApiName, CppVisibility, FuncToConvert, Provenance, References, TraitSynthesis,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+pub(crate) fn create_smart_pointer_functions(
+    apis: Vec<Api<PodPhase>>,
+    config: &IncludeCppConfig,
+) -> Vec<Api<PodPhase>> {
+    apis.into_iter()
+        .flat_map(|api| -> Box<dyn Iterator<Item = Api<PodPhase>>> {
+            match &api {
+                Api::Struct { name, .. } => {
+                    match config.get_smart_pointer(&name.name.to_cpp_name()) {
+                        Some(smart_pointer) => Box::new(
+                            create_derefs(name.name.clone(), smart_pointer)
+                                .chain(std::iter::once(api)),
+                        ),
+                        None => Box::new(std::iter::once(api)),
+                    }
+                }
+                _ => Box::new(std::iter::once(api)),
+            }
+        })
+        .collect()
+}
+
+/// `deref`, which implements `Deref`, and, if the pointee may be modified,
+/// `deref_pin_mut`.
+fn create_derefs(
+    ty_name: QualifiedName,
+    smart_pointer: &SmartPointer,
+) -> impl Iterator<Item = Api<PodPhase>> {
+    let typ = ty_name.to_type_path();
+    let target = QualifiedName::new_from_cpp_name(&smart_pointer.target).to_type_path();
+    let deref = (
+        "deref",
+        parse_quote! { this: *const #typ },
+        parse_quote! { -> *const #target },
+        Some(TraitSynthesis::Deref(ty_name.clone())),
+    );
+    let deref_pin_mut = (
+        "deref_pin_mut",
+        parse_quote! { this: *mut #typ },
+        parse_quote! { -> *mut #target },
+        None,
+    );
+    std::iter::once(deref)
+        .chain(Some(deref_pin_mut).filter(|_| smart_pointer.mutable))
+        .map(move |(cpp_name, inputs, output, add_to_trait)| {
+            let ident = make_ident(format!("{}_{}", ty_name.get_final_item(), cpp_name));
+            Api::Function {
+                name: ApiName::new_with_cpp_name(
+                    ty_name.get_namespace(),
+                    ident.clone(),
+                    Some(cpp_name.to_string()),
+                ),
+                name_for_gc: None,
+                fun: Box::new(FuncToConvert {
+                    ident,
+                    doc_attr: None,
+                    inputs,
+                    output,
+                    vis: parse_quote! { pub },
+                    virtualness: crate::conversion::api::Virtualness::None,
+                    cpp_vis: CppVisibility::Public,
+                    special_member: None,
+                    unused_template_param: false,
+                    references: References::new_with_this_and_return_as_reference(),
+                    original_name: None,
+                    self_ty: Some(ty_name.clone()),
+                    synthesized_this_type: None,
+                    synthetic_cpp: Some((
+                        CppFunctionBody::SmartPointerDeref,
+                        CppFunctionKind::Function,
+                    )),
+                    add_to_trait,
+                    is_deleted: false,
+                    link_name: None,
+                    provenance: Provenance::SynthesizedOther,
+                }),
+                analysis: (),
+            }
+        })
+}
@@ -19,7 +19,12 @@ use syn::ItemType;
 
 use crate::{
     conversion::{
-        analysis::type_converter::{add_analysis, Annotated, TypeConversionContext, TypeConverter},
+        analysis::{
+            fun::function_wrapper::bitset_type,
+            type_converter::{
+                add_analysis, is_alias_template, Annotated, TypeConversionContext, TypeConverter,
+            },
+        },
         api::{AnalysisPhase, Api, ApiName, TypedefKind, UnanalyzedApi},
         convert_error::{ConvertErrorWithContext, ErrorContext},
         error_reporter::convert_apis,
@@ -60,6 +65,12 @@ pub(crate) fn convert_typedef_targets(
         Api::enum_unchanged,
         |name, item, old_tyname, _| {
             Ok(Box::new(std::iter::once(match item {
+                TypedefKind::Type(ity)
+                    if config.get_bitset_width(&name.name.to_cpp_name()).is_some() =>
+                {
+                    let width = config.get_bitset_width(&name.name.to_cpp_name()).unwrap();
+                    get_bitset_typedef(name, ity, old_tyname, width)
+                }
                 TypedefKind::Type(ity) => get_replacement_typedef(
                     name,
                     ity,
@@ -83,6 +94,27 @@ pub(crate) fn convert_typedef_targets(
     results
 }
 
+/// A typedef listed in `bitset!` becomes an alias for the Rust type,
+/// whatever `bindgen` made of the `std::bitset` itself.
+fn get_bitset_typedef(
+    name: ApiName,
+    ity: ItemType,
+    old_tyname: Option<QualifiedName>,
+    width: usize,
+) -> Api<TypedefPhase> {
+    let mut converted_type = ity.clone();
+    converted_type.ty = Box::new(bitset_type(width));
+    Api::Typedef {
+        name,
+        item: TypedefKind::Type(ity),
+        old_tyname,
+        analysis: TypedefAnalysis {
+            kind: TypedefKind::Type(converted_type),
+            deps: HashSet::new(),
+        },
+    }
+}
+
 fn get_replacement_typedef(
     name: ApiName,
     ity: ItemType,
@@ -93,11 +125,15 @@ fn get_replacement_typedef(
     let mut converted_type = ity.clone();
     let metadata = BindgenSemanticAttributes::new_retaining_others(&mut converted_type.attrs);
     metadata.check_for_fatal_attrs(&ity.ident)?;
-    let type_conversion_results = type_converter.convert_type(
-        (*ity.ty).clone(),
-        name.name.get_namespace(),
-        &TypeConversionContext::CxxInnerType,
-    );
+    let type_conversion_results = if is_alias_template(&ity) {
+        type_converter.convert_alias_template_target(&ity, name.name.get_namespace())
+    } else {
+        type_converter.convert_type(
+            (*ity.ty).clone(),
+            name.name.get_namespace(),
+            &TypeConversionContext::CxxInnerType,
+        )
+    };
     match type_conversion_results {
         Err(err) => Err(ConvertErrorWithContext(
             err,
@@ -27,8 +27,8 @@ use proc_macro2::Ident;
 use quote::ToTokens;
 use std::collections::{HashMap, HashSet};
 use syn::{
-    parse_quote, punctuated::Punctuated, GenericArgument, PathArguments, PathSegment, Type,
-    TypePath, TypePtr,
+    parse_quote, punctuated::Punctuated, GenericArgument, ItemType, PathArguments, PathSegment,
+    Type, TypePath, TypePtr,
 };
 
 use super::tdef::TypedefAnalysis;
@@ -40,6 +40,13 @@ pub(crate) enum TypeKind {
     SubclassHolder(Ident),
     Reference,
     MutableReference,
+    /// A reference which C++ sees as a pointer within a
+    /// `register_pointer_wrapper!` type such as `gsl::not_null`.
+    WrappedPointerReference,
+    WrappedPointerMutableReference,
+    /// A smart pointer which C++ sees within a `register_pointer_wrapper!`
+    /// type, to and from which it converts implicitly.
+    WrappedSmartPointer,
 }
 
 /// Results of some type conversion, annotated with a list of every type encountered,
@@ -114,8 +121,19 @@ impl TypeConversionContext {
 pub(crate) struct TypeConverter<'a> {
     types_found: HashSet<QualifiedName>,
     typedefs: HashMap<QualifiedName, Type>,
+    /// Alias templates, as `bindgen` gave them to us, since each use
+    /// needs its arguments substituting into the original target type.
+    alias_templates: HashMap<QualifiedName, ItemType>,
+    /// The parameters of the alias template whose target we're currently
+    /// converting, if any.
+    generic_params_in_scope: HashSet<Ident>,
     concrete_templates: HashMap<String, QualifiedName>,
     forward_declarations: HashSet<QualifiedName>,
+    /// Enums which become newtypes around their underlying integer, so
+    /// can hold any value C++ writes into them.
+    newtype_enums: HashSet<QualifiedName>,
+    /// Enums which become Rust `enum`s, which can't.
+    closed_enums: HashSet<QualifiedName>,
     config: &'a IncludeCppConfig,
 }
 
@@ -127,8 +145,12 @@ impl<'a> TypeConverter<'a> {
         Self {
             types_found: find_types(apis),
             typedefs: Self::find_typedefs(apis),
+            alias_templates: Self::find_alias_templates(apis),
+            generic_params_in_scope: HashSet::new(),
             concrete_templates: Self::find_concrete_templates(apis),
             forward_declarations: Self::find_incomplete_types(apis),
+            newtype_enums: Self::find_enums(apis, config, true),
+            closed_enums: Self::find_enums(apis, config, false),
             config,
         }
     }
@@ -196,6 +218,8 @@ impl<'a> TypeConverter<'a> {
                 crate::known_types::ensure_pointee_is_valid(&ptr)?;
                 let innerty =
                     self.convert_boxed_type(ptr.elem, ns, &TypeConversionContext::CxxInnerType)?;
+                crate::known_types::ensure_not_string_view(&innerty.ty)?;
+                crate::known_types::ensure_not_filesystem_path(&innerty.ty)?;
                 ptr.elem = innerty.ty;
                 Annotated::new(
                     Type::Ptr(ptr),
@@ -209,12 +233,41 @@ impl<'a> TypeConverter<'a> {
         Ok(result)
     }
 
+    /// Converts the target of an alias template such as
+    /// `template<typename T> using Ptr = std::shared_ptr<T>`, leaving its
+    /// parameters as they are, so that it can become a generic Rust type
+    /// alias.
+    pub(crate) fn convert_alias_template_target(
+        &mut self,
+        ity: &ItemType,
+        ns: &Namespace,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        self.generic_params_in_scope = ity
+            .generics
+            .type_params()
+            .map(|tp| tp.ident.clone())
+            .collect();
+        let result = self.convert_type((*ity.ty).clone(), ns, &TypeConversionContext::CxxInnerType);
+        self.generic_params_in_scope.clear();
+        result
+    }
+
     fn convert_type_path(
         &mut self,
         mut typ: TypePath,
         ns: &Namespace,
         ctx: &TypeConversionContext,
     ) -> Result<Annotated<Type>, ConvertError> {
+        if let Some(param) = typ.path.get_ident() {
+            if self.generic_params_in_scope.contains(param) {
+                return Ok(Annotated::new(
+                    Type::Path(typ),
+                    HashSet::new(),
+                    Vec::new(),
+                    TypeKind::Regular,
+                ));
+            }
+        }
         // First, qualify any unqualified paths.
         if typ.path.segments.iter().next().unwrap().ident != "root" {
             let ty = QualifiedName::from_type_path(&typ);
@@ -245,6 +298,18 @@ impl<'a> TypeConverter<'a> {
         if self.config.is_on_blocklist(&original_tn.to_cpp_name()) {
             return Err(ConvertError::Blocked(original_tn));
         }
+        if self.config.is_pointer_wrapper(&original_tn.to_cpp_name()) {
+            return self.convert_pointer_wrapper(typ, original_tn, ns);
+        }
+        // An alias template stands for its target with our arguments
+        // substituted in; that's what C++ will see, so it's what we bind.
+        if let Some(alias) = self.alias_templates.get(&original_tn) {
+            let target = instantiate_alias_template(alias, &typ)
+                .ok_or_else(|| ConvertError::AliasTemplateArguments(original_tn.clone()))?;
+            let mut result = self.convert_type(target, original_tn.get_namespace(), ctx)?;
+            result.types_encountered.insert(original_tn);
+            return Ok(result);
+        }
         let mut deps = HashSet::new();
 
         // Now convert this type itself.
@@ -316,6 +381,11 @@ impl<'a> TypeConverter<'a> {
                 // Oh poop. It's a generic type which cxx won't be able to handle.
                 // We'll have to come up with a concrete type in both the cxx::bridge (in Rust)
                 // and a corresponding typedef in C++.
+                if !self.generic_params_in_scope.is_empty() {
+                    // ... which we can't do without knowing the alias
+                    // template's arguments.
+                    return Err(ConvertError::GenericAliasToUnsupportedTemplate(tn));
+                }
                 let (new_tn, api) = self.get_templated_typename(&Type::Path(typ))?;
                 extra_apis.extend(api.into_iter());
                 deps.remove(&tn);
@@ -349,6 +419,8 @@ impl<'a> TypeConverter<'a> {
                 GenericArgument::Type(t) => {
                     let mut innerty =
                         self.convert_type(t, ns, &TypeConversionContext::CxxInnerType)?;
+                    crate::known_types::ensure_not_string_view(&innerty.ty)?;
+                    crate::known_types::ensure_not_filesystem_path(&innerty.ty)?;
                     types_encountered.extend(innerty.types_encountered.drain());
                     extra_apis.append(&mut innerty.extra_apis);
                     GenericArgument::Type(innerty.ty)
@@ -393,11 +465,32 @@ impl<'a> TypeConverter<'a> {
     ) -> Result<Annotated<Type>, ConvertError> {
         let mutability = ptr.mutability;
         let elem = self.convert_boxed_type(ptr.elem, ns, &TypeConversionContext::CxxInnerType)?;
+        if mutability.is_some() {
+            crate::known_types::ensure_not_string_view(&elem.ty)?;
+            crate::known_types::ensure_not_filesystem_path(&elem.ty)?;
+        }
         // TODO - in the future, we should check if this is a rust::Str and throw
         // a wobbler if not. rust::Str should only be seen _by value_ in C++
         // headers; it manifests as &str in Rust but on the C++ side it must
         // be a plain value. We should detect and abort.
+        // An enum which C++ may modify has to be able to hold whatever
+        // value it writes, which a Rust `enum` can't, but a newtype can;
+        // it's also `Unpin`, so doesn't need pinning.
+        let enum_name = match elem.ty.as_ref() {
+            Type::Path(typ) if mutability.is_some() => Some(QualifiedName::from_type_path(typ)),
+            _ => None,
+        };
+        let mut is_newtype_enum = false;
+        if let Some(enum_name) = enum_name {
+            if self.closed_enums.contains(&enum_name) {
+                return Err(ConvertError::MutableReferenceToEnum(enum_name));
+            }
+            is_newtype_enum = self.newtype_enums.contains(&enum_name);
+        }
         let mut outer = elem.map(|elem| match mutability {
+            Some(_) if is_newtype_enum => Type::Reference(parse_quote! {
+                &mut #elem
+            }),
             Some(_) => Type::Path(parse_quote! {
                 ::std::pin::Pin < & #mutability #elem >
             }),
@@ -413,6 +506,49 @@ impl<'a> TypeConverter<'a> {
         Ok(outer)
     }
 
+    /// A pointer wrapped in something like `gsl::not_null`, which we treat
+    /// as a plain reference (or the smart pointer it wraps) and leave C++
+    /// to wrap and unwrap.
+    fn convert_pointer_wrapper(
+        &mut self,
+        mut typ: TypePath,
+        tn: QualifiedName,
+        ns: &Namespace,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        let inner = match Self::get_generic_args(&mut typ).map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) if ab.args.len() == 1 => {
+                match ab.args.first() {
+                    Some(GenericArgument::Type(inner)) => inner.clone(),
+                    _ => return Err(ConvertError::UnsupportedPointerWrapper(tn)),
+                }
+            }
+            _ => return Err(ConvertError::UnsupportedPointerWrapper(tn)),
+        };
+        match inner {
+            Type::Ptr(ptr) => {
+                let mut reference = self.convert_ptr_to_reference(ptr, ns)?;
+                reference.kind = match reference.kind {
+                    TypeKind::MutableReference => TypeKind::WrappedPointerMutableReference,
+                    _ => TypeKind::WrappedPointerReference,
+                };
+                Ok(reference)
+            }
+            Type::Path(inner) => {
+                let mut smart_pointer =
+                    self.convert_type_path(inner, ns, &TypeConversionContext::CxxInnerType)?;
+                if !matches!(&smart_pointer.ty, Type::Path(tp)
+                    if known_types().cxx_generic_behavior(&QualifiedName::from_type_path(tp))
+                        != CxxGenericType::Not)
+                {
+                    return Err(ConvertError::UnsupportedPointerWrapper(tn));
+                }
+                smart_pointer.kind = TypeKind::WrappedSmartPointer;
+                Ok(smart_pointer)
+            }
+            _ => Err(ConvertError::UnsupportedPointerWrapper(tn)),
+        }
+    }
+
     fn get_templated_typename(
         &mut self,
         rs_definition: &Type,
@@ -524,12 +660,28 @@ impl<'a> TypeConverter<'a> {
         }
     }
 
+    fn find_alias_templates<A: AnalysisPhase>(apis: &[Api<A>]) -> HashMap<QualifiedName, ItemType> {
+        apis.iter()
+            .filter_map(|api| match &api {
+                Api::Typedef {
+                    item: TypedefKind::Type(ity),
+                    ..
+                } if is_alias_template(ity) => Some((api.name().clone(), ity.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
     fn find_typedefs<A: AnalysisPhase>(apis: &[Api<A>]) -> HashMap<QualifiedName, Type>
     where
         A::TypedefAnalysis: TypedefTarget,
     {
         apis.iter()
             .filter_map(|api| match &api {
+                Api::Typedef {
+                    item: TypedefKind::Type(ity),
+                    ..
+                } if is_alias_template(ity) => None,
                 Api::Typedef { analysis, .. } => analysis
                     .get_target()
                     .cloned()
@@ -561,6 +713,25 @@ impl<'a> TypeConverter<'a> {
             .cloned()
             .collect()
     }
+
+    /// Enums listed in `flags!`, and those with no enumerators, become
+    /// newtypes; others become Rust `enum`s.
+    fn find_enums<A: AnalysisPhase>(
+        apis: &[Api<A>],
+        config: &IncludeCppConfig,
+        newtypes: bool,
+    ) -> HashSet<QualifiedName> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::Enum { name, item } => {
+                    let is_newtype = item.variants.is_empty()
+                        || config.get_flags(&name.name.to_cpp_name()).is_some();
+                    (is_newtype == newtypes).then(|| name.name.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 /// Processing functions sometimes results in new types being materialized.
@@ -583,6 +754,81 @@ pub(crate) fn add_analysis<A: AnalysisPhase>(api: UnanalyzedApi) -> Api<A> {
         _ => panic!("Function analysis created an unexpected type of extra API"),
     }
 }
+/// Whether a typedef from `bindgen` is in fact an alias template, which
+/// is generic over its template parameters.
+pub(crate) fn is_alias_template(ity: &ItemType) -> bool {
+    !ity.generics.params.is_empty()
+}
+
+/// The target of an alias template with the arguments from `typ`
+/// substituted for its parameters, using any defaults for missing trailing
+/// arguments. `None` if the arguments don't fit the parameters.
+fn instantiate_alias_template(alias: &ItemType, typ: &TypePath) -> Option<Type> {
+    let args = match &typ.path.segments.last()?.arguments {
+        PathArguments::None => Vec::new(),
+        PathArguments::AngleBracketed(ab) => ab
+            .args
+            .iter()
+            .map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?,
+        PathArguments::Parenthesized(_) => return None,
+    };
+    let params: Vec<_> = alias.generics.type_params().collect();
+    if args.len() > params.len() {
+        return None;
+    }
+    let substitutions = params
+        .iter()
+        .enumerate()
+        .map(|(idx, param)| {
+            args.get(idx)
+                .or(param.default.as_ref())
+                .map(|ty| (param.ident.clone(), ty.clone()))
+        })
+        .collect::<Option<HashMap<_, _>>>()?;
+    Some(substitute_type_params((*alias.ty).clone(), &substitutions))
+}
+
+fn substitute_type_params(ty: Type, substitutions: &HashMap<Ident, Type>) -> Type {
+    match ty {
+        Type::Path(mut typ) => {
+            if let Some(substitute) = typ
+                .path
+                .get_ident()
+                .and_then(|param| substitutions.get(param))
+            {
+                return substitute.clone();
+            }
+            for seg in typ.path.segments.iter_mut() {
+                if let PathArguments::AngleBracketed(ab) = &mut seg.arguments {
+                    for arg in ab.args.iter_mut() {
+                        if let GenericArgument::Type(inner) = arg {
+                            *inner = substitute_type_params(inner.clone(), substitutions);
+                        }
+                    }
+                }
+            }
+            Type::Path(typ)
+        }
+        Type::Ptr(mut ptr) => {
+            ptr.elem = Box::new(substitute_type_params(*ptr.elem, substitutions));
+            Type::Ptr(ptr)
+        }
+        Type::Reference(mut reference) => {
+            reference.elem = Box::new(substitute_type_params(*reference.elem, substitutions));
+            Type::Reference(reference)
+        }
+        Type::Array(mut array) => {
+            array.elem = Box::new(substitute_type_params(*array.elem, substitutions));
+            Type::Array(array)
+        }
+        _ => ty,
+    }
+}
+
 pub(crate) trait TypedefTarget {
     fn get_target(&self) -> Option<&Type>;
 }
@@ -611,8 +857,11 @@ pub(crate) fn find_types<A: AnalysisPhase>(apis: &[Api<A>]) -> HashSet<Qualified
             | Api::Enum { .. }
             | Api::Struct { .. }
             | Api::Subclass { .. }
-            | Api::RustType { .. } => Some(api.name()),
+            | Api::RustType { .. }
+            | Api::ExternCppType { .. } => Some(api.name()),
             Api::StringConstructor { .. }
+            | Api::CppAny { .. }
+            | Api::CppIterable { .. }
             | Api::Function { .. }
             | Api::Const { .. }
             | Api::CType { .. }
@@ -623,3 +872,37 @@ pub(crate) fn find_types<A: AnalysisPhase>(apis: &[Api<A>]) -> HashSet<Qualified
         .cloned()
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+    use syn::{parse_quote, ItemType, TypePath};
+
+    use super::instantiate_alias_template;
+
+    #[test]
+    fn test_instantiate_alias_template() {
+        let alias: ItemType = parse_quote! {
+            pub type Pair<T, U = root::Thing> = root::std::pair<*mut T, root::Holder<U>>;
+        };
+        let instantiate = |typ: TypePath| {
+            instantiate_alias_template(&alias, &typ).map(|ty| ty.to_token_stream().to_string())
+        };
+        let expected: syn::Type = parse_quote! {
+            root::std::pair<*mut u32, root::Holder<root::Other> >
+        };
+        assert_eq!(
+            instantiate(parse_quote! { root::Pair<u32, root::Other> }),
+            Some(expected.to_token_stream().to_string())
+        );
+        let expected: syn::Type = parse_quote! {
+            root::std::pair<*mut u32, root::Holder<root::Thing> >
+        };
+        assert_eq!(
+            instantiate(parse_quote! { root::Pair<u32> }),
+            Some(expected.to_token_stream().to_string())
+        );
+        assert_eq!(instantiate(parse_quote! { root::Pair }), None);
+        assert_eq!(instantiate(parse_quote! { root::Pair<u8, u16, u32> }), None);
+    }
+}
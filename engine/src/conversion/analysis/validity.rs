@@ -0,0 +1,95 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Code to create functions which find out whether objects of types
+//! listed in `validity!` are valid.
+
+use autocxx_parser::{IncludeCppConfig, Validity};
+use syn::parse_quote;
+
+use crate::{
+    conversion::api::{
+        Api, ApiName, CppVisibility, FuncToConvert, Provenance, References, TraitSynthesis,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+pub(crate) fn create_validity_functions(
+    apis: Vec<Api<PodPhase>>,
+    config: &IncludeCppConfig,
+) -> Vec<Api<PodPhase>> {
+    apis.into_iter()
+        .flat_map(|api| -> Box<dyn Iterator<Item = Api<PodPhase>>> {
+            match &api {
+                Api::Struct { name, .. } => match config.get_validity(&name.name.to_cpp_name()) {
+                    Some(validity) => Box::new(
+                        std::iter::once(create_is_valid(name.name.clone(), validity))
+                            .chain(std::iter::once(api)),
+                    ),
+                    None => Box::new(std::iter::once(api)),
+                },
+                _ => Box::new(std::iter::once(api)),
+            }
+        })
+        .collect()
+}
+
+fn create_is_valid(ty_name: QualifiedName, validity: &Validity) -> Api<PodPhase> {
+    let typ = ty_name.to_type_path();
+    let (method, negated) = match &validity.check {
+        Some(check) => match check.strip_prefix('!') {
+            Some(method) => (Some(make_ident(method.trim())), true),
+            None => (Some(make_ident(check)), false),
+        },
+        None => (None, false),
+    };
+    let name = QualifiedName::new(
+        ty_name.get_namespace(),
+        make_ident(format!("{}_is_valid", ty_name.get_final_item())),
+    );
+    let ident = name.get_final_ident();
+    Api::Function {
+        name: ApiName::new_from_qualified_name(name),
+        name_for_gc: None,
+        fun: Box::new(FuncToConvert {
+            ident,
+            doc_attr: None,
+            inputs: parse_quote! { this: *const #typ },
+            output: parse_quote! { -> bool },
+            vis: parse_quote! { pub },
+            virtualness: crate::conversion::api::Virtualness::None,
+            cpp_vis: CppVisibility::Public,
+            special_member: None,
+            unused_template_param: false,
+            references: References::new_with_this_and_return_as_reference(),
+            original_name: None,
+            self_ty: Some(ty_name.clone()),
+            synthesized_this_type: None,
+            synthetic_cpp: Some((
+                CppFunctionBody::ValidityCheck(method, negated),
+                CppFunctionKind::Function,
+            )),
+            add_to_trait: Some(TraitSynthesis::Validity(ty_name)),
+            is_deleted: false,
+            link_name: None,
+            provenance: Provenance::SynthesizedOther,
+        }),
+        analysis: (),
+    }
+}
@@ -15,7 +15,7 @@
 use std::collections::HashSet;
 
 use crate::types::{make_ident, Namespace, QualifiedName};
-use autocxx_parser::RustPath;
+use autocxx_parser::{IteratorCategory, RustPath};
 use quote::ToTokens;
 use syn::{
     parse::Parse,
@@ -109,8 +109,20 @@ pub(crate) enum TraitSynthesis {
         to_type: QualifiedName,
         mutable: CastMutability,
     },
+    /// A `dynamic_cast` from a polymorphic base class to the given
+    /// derived class.
+    Downcast {
+        to_type: QualifiedName,
+        mutable: CastMutability,
+    },
     AllocUninitialized(QualifiedName),
     FreeUninitialized(QualifiedName),
+    AddRef(QualifiedName),
+    Release(QualifiedName),
+    Validity(QualifiedName),
+    /// `Deref` for a type listed in `smart_pointer!`.
+    Deref(QualifiedName),
+    SliceToBase(QualifiedName),
 }
 
 /// Details of a subclass constructor.
@@ -199,6 +211,8 @@ pub(crate) enum Provenance {
     SynthesizedOther,
     SynthesizedMakeUnique,
     SynthesizedSubclassConstructor(Box<SubclassConstructorDetails>),
+    /// A protected method of a superclass, made public by its subclass.
+    SynthesizedSubclassProtectedMethod(SubclassName),
 }
 
 /// A C++ function for which we need to generate bindings, but haven't
@@ -238,6 +252,9 @@ pub(crate) struct FuncToConvert {
     /// C++ and instead we're synthesizing it.
     pub(crate) synthetic_cpp: Option<(CppFunctionBody, CppFunctionKind)>,
     pub(crate) is_deleted: bool,
+    /// The symbol by which bindgen says the function is linked, if that's
+    /// not its name, for instance because C++ mangles it.
+    pub(crate) link_name: Option<String>,
 }
 
 /// Layers of analysis which may be applied to decorate each API.
@@ -397,6 +414,21 @@ pub(crate) enum Api<T: AnalysisPhase> {
     /// A simple note that we want to make a constructor for
     /// a `std::string` on the heap.
     StringConstructor { name: ApiName },
+    /// Helpers for `std::any`, and for storing the types listed in
+    /// `any_castable!` in one and downcasting them back.
+    CppAny {
+        name: ApiName,
+        castables: Vec<QualifiedName>,
+    },
+    /// Helpers for iterating over a C++ container listed in `iterable!`,
+    /// or over a set listed in `cpp_set!` and asking what's in it.
+    CppIterable {
+        name: ApiName,
+        container: QualifiedName,
+        element: QualifiedName,
+        category: IteratorCategory,
+        is_set: bool,
+    },
     /// A function. May include some analysis.
     Function {
         name: ApiName,
@@ -443,6 +475,9 @@ pub(crate) enum Api<T: AnalysisPhase> {
     },
     /// A Rust type which is not a C++ type.
     RustType { name: ApiName, path: RustPath },
+    /// A C++ type which another `include_cpp!` block has already bound,
+    /// as requested by `reuse_from!`.
+    ExternCppType { name: ApiName, path: RustPath },
     /// A function for the 'extern Rust' block which is not a C++ type.
     RustFn {
         name: ApiName,
@@ -480,6 +515,8 @@ impl<T: AnalysisPhase> Api<T> {
             Api::ForwardDeclaration { name } => name,
             Api::ConcreteType { name, .. } => name,
             Api::StringConstructor { name } => name,
+            Api::CppAny { name, .. } => name,
+            Api::CppIterable { name, .. } => name,
             Api::Function { name, .. } => name,
             Api::Const { name, .. } => name,
             Api::Typedef { name, .. } => name,
@@ -488,6 +525,7 @@ impl<T: AnalysisPhase> Api<T> {
             Api::CType { name, .. } => name,
             Api::IgnoredItem { name, .. } => name,
             Api::RustType { name, .. } => name,
+            Api::ExternCppType { name, .. } => name,
             Api::RustFn { name, .. } => name,
             Api::RustSubclassFn { name, .. } => name,
             Api::Subclass { name, .. } => &name.0,
@@ -0,0 +1,37 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use indoc::indoc;
+
+/// This is logic to report a `std::error_code` set by a function listed
+/// in `error_code_param!`. cxx turns the exception into an `Err` for
+/// the Rust wrapper, which recognizes the prefix and unpacks the fields,
+/// separated by ASCII unit separators, into a `std::io::Error`.
+pub(super) static ERROR_CODE_PRELUDE: &str = indoc! {"
+    #include <stdexcept>
+    #include <string>
+    #include <system_error>
+    #ifndef AUTOCXX_ERROR_CODE_PRELUDE
+    #define AUTOCXX_ERROR_CODE_PRELUDE
+    inline void autocxx_throw_if_error_code(const std::error_code& ec) {
+      if (ec) {
+        const char* category = ec.category() == std::system_category() ? \"system\"
+            : ec.category() == std::generic_category() ? \"generic\"
+            : ec.category().name();
+        throw std::runtime_error(std::string(\"autocxx_error_code\\x1f\") + std::to_string(ec.value())
+            + \"\\x1f\" + category + \"\\x1f\" + ec.message());
+      }
+    }
+    #endif // AUTOCXX_ERROR_CODE_PRELUDE
+"};
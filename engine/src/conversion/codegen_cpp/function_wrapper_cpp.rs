@@ -18,6 +18,7 @@ use crate::conversion::{
 };
 
 use super::type_to_cpp::{type_to_cpp, CppNameMap};
+use syn::{ReturnType, Type};
 
 impl TypeConversionPolicy {
     pub(super) fn unconverted_type(
@@ -26,6 +27,16 @@ impl TypeConversionPolicy {
     ) -> Result<String, ConvertError> {
         match self.cpp_conversion {
             CppConversionType::FromUniquePtrToValue => self.wrapped_type(cpp_name_map),
+            CppConversionType::FromRustStrToStringView => Ok("rust::Str".to_string()),
+            CppConversionType::FromRustSliceToFilesystemPath => {
+                Ok("rust::Slice<const uint8_t>".to_string())
+            }
+            CppConversionType::FromRustSliceToBitset(_) => {
+                Ok("rust::Slice<const uint64_t>".to_string())
+            }
+            CppConversionType::FromVoidPtrToFnPtr | CppConversionType::FromVoidPtrToStdFunction => {
+                Ok("void*".to_string())
+            }
             _ => self.unwrapped_type_as_string(cpp_name_map),
         }
     }
@@ -33,14 +44,104 @@ impl TypeConversionPolicy {
     pub(super) fn converted_type(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
         match self.cpp_conversion {
             CppConversionType::FromValueToUniquePtr => self.wrapped_type(cpp_name_map),
+            CppConversionType::FromStringViewToRustSlice => {
+                Ok("rust::Slice<const uint8_t>".to_string())
+            }
+            CppConversionType::FromFilesystemPathToRustVec => Ok("rust::Vec<uint8_t>".to_string()),
+            CppConversionType::FromBitsetToRustVec(_) => Ok("rust::Vec<uint64_t>".to_string()),
             _ => self.unwrapped_type_as_string(cpp_name_map),
         }
     }
 
+    /// Whether the conversion involves std::filesystem::path, and thus
+    /// needs the relevant headers.
+    pub(super) fn involves_filesystem_path(&self) -> bool {
+        matches!(
+            self.cpp_conversion,
+            CppConversionType::FromRustSliceToFilesystemPath
+                | CppConversionType::FromFilesystemPathToRustVec
+        )
+    }
+
+    /// Whether the conversion involves std::bitset, and thus needs the
+    /// relevant headers.
+    pub(super) fn involves_bitset(&self) -> bool {
+        matches!(
+            self.cpp_conversion,
+            CppConversionType::FromRustSliceToBitset(_) | CppConversionType::FromBitsetToRustVec(_)
+        )
+    }
+
+    /// Whether the conversion involves std::string_view and rust::Str
+    /// or rust::Slice, and thus needs the relevant headers.
+    pub(super) fn involves_string_view(&self) -> bool {
+        matches!(
+            self.cpp_conversion,
+            CppConversionType::FromRustStrToStringView
+                | CppConversionType::FromStringViewToRustSlice
+        )
+    }
+
     fn unwrapped_type_as_string(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
         type_to_cpp(&self.unwrapped_type, cpp_name_map)
     }
 
+    /// The C++ function pointer type of a callback, such as
+    /// `void (*)(void*, int)`.
+    fn fn_ptr_type(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
+        let (ret, args) = self.callback_types(cpp_name_map)?;
+        Ok(format!("{} (*)({})", ret, args.join(", ")))
+    }
+
+    /// The C++ return and parameter types of a callback.
+    fn callback_types(
+        &self,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<(String, Vec<String>), ConvertError> {
+        let callback = match &self.unwrapped_type {
+            Type::BareFn(callback) => callback,
+            _ => panic!("Not a function pointer"),
+        };
+        let ret = match &callback.output {
+            ReturnType::Default => "void".to_string(),
+            ReturnType::Type(_, ty) => type_to_cpp(ty, cpp_name_map)?,
+        };
+        let args: Result<Vec<_>, _> = callback
+            .inputs
+            .iter()
+            .map(|arg| type_to_cpp(&arg.ty, cpp_name_map))
+            .collect();
+        Ok((ret, args?))
+    }
+
+    /// A lambda, to be converted to a `std::function`, which calls the
+    /// closure whose context is `var_name`. The context starts with the
+    /// functions to free the closure and to call it, taking the context
+    /// first; the lambda, and so every copy of the `std::function`,
+    /// shares ownership of the context, freeing it when the last is gone.
+    fn std_function_lambda(
+        &self,
+        var_name: &str,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<String, ConvertError> {
+        let (ret, args) = self.callback_types(cpp_name_map)?;
+        let params = args
+            .iter()
+            .enumerate()
+            .map(|(idx, ty)| format!("{} arg{}", ty, idx))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let call_args: String = (0..args.len()).map(|idx| format!(", arg{}", idx)).collect();
+        let call_type = std::iter::once("void*".to_string())
+            .chain(args)
+            .collect::<Vec<_>>()
+            .join(", ");
+        Ok(format!(
+            "[](void* ctx) {{ struct closure_header {{ void (*free)(void*); void* call; }}; auto header = static_cast<closure_header*>(ctx); auto call = reinterpret_cast<{0} (*)({1})>(header->call); std::shared_ptr<void> owner(ctx, header->free); return [owner, call]({2}) {{ return call(owner.get(){3}); }}; }}({4})",
+            ret, call_type, params, call_args, var_name
+        ))
+    }
+
     fn wrapped_type(&self, original_name_map: &CppNameMap) -> Result<String, ConvertError> {
         Ok(format!(
             "std::unique_ptr<{}>",
@@ -57,7 +158,7 @@ impl TypeConversionPolicy {
         // If is_return we want to avoid unnecessary std::moves because they
         // make RVO less effective
         Ok(match self.cpp_conversion {
-            CppConversionType::None => {
+            CppConversionType::None | CppConversionType::Implicit => {
                 if is_return {
                     var_name.to_string()
                 } else {
@@ -67,11 +168,67 @@ impl TypeConversionPolicy {
             CppConversionType::FromUniquePtrToValue | CppConversionType::FromPtrToMove => {
                 format!("std::move(*{})", var_name)
             }
+            // A parameter is ours to move from, as it would be for any
+            // other callee taking it by value.
+            CppConversionType::FromValueToUniquePtr if !is_return => format!(
+                "std::make_unique<{}>(std::move({}))",
+                self.unconverted_type(cpp_name_map)?,
+                var_name
+            ),
             CppConversionType::FromValueToUniquePtr => format!(
                 "std::make_unique<{}>({})",
                 self.unconverted_type(cpp_name_map)?,
                 var_name
             ),
+            CppConversionType::FromReferenceToPointer => format!("&{}", var_name),
+            CppConversionType::FromPointerToReference => format!("*{}", var_name),
+            CppConversionType::FromRustStrToStringView => {
+                format!("std::string_view({0}.data(), {0}.size())", var_name)
+            }
+            // The view may be the result of a function call, so bind it
+            // once rather than evaluating the expression twice.
+            CppConversionType::FromStringViewToRustSlice => format!(
+                "[](std::string_view sv) {{ return rust::Slice<const uint8_t>(reinterpret_cast<const uint8_t*>(sv.data()), sv.size()); }}({})",
+                var_name
+            ),
+            // The bytes from Rust need not be aligned for the path's
+            // native character type (wchar_t on Windows), so copy them
+            // rather than casting the pointer.
+            CppConversionType::FromRustSliceToFilesystemPath => format!(
+                "[](rust::Slice<const uint8_t> bytes) {{ std::filesystem::path::string_type native(bytes.size() / sizeof(std::filesystem::path::value_type), 0); std::memcpy(&native[0], bytes.data(), native.size() * sizeof(std::filesystem::path::value_type)); return std::filesystem::path(std::move(native)); }}({})",
+                var_name
+            ),
+            CppConversionType::FromFilesystemPathToRustVec => format!(
+                "[](const std::filesystem::path& path) {{ const auto& native = path.native(); const auto* data = reinterpret_cast<const uint8_t*>(native.data()); size_t len = native.size() * sizeof(std::filesystem::path::value_type); rust::Vec<uint8_t> bytes; bytes.reserve(len); for (size_t i = 0; i < len; ++i) {{ bytes.push_back(data[i]); }} return bytes; }}({})",
+                var_name
+            ),
+            // Bit by bit, so as not to depend on the library's storage.
+            // Rust always passes whole words, but we check anyway.
+            CppConversionType::FromRustSliceToBitset(width) => format!(
+                "[](rust::Slice<const uint64_t> words) {{ std::bitset<{0}> bits; for (size_t i = 0; i < {0} && i / 64 < words.size(); ++i) {{ bits[i] = (words[i / 64] >> (i % 64)) & 1; }} return bits; }}({1})",
+                width, var_name
+            ),
+            CppConversionType::FromVoidPtrToStdFunction => {
+                self.std_function_lambda(var_name, cpp_name_map)?
+            }
+            CppConversionType::FromVoidPtrToFnPtr => format!(
+                "reinterpret_cast<{}>({})",
+                self.fn_ptr_type(cpp_name_map)?,
+                var_name
+            ),
+            CppConversionType::FromUniqueArrayToPtr => format!("{}.release()", var_name),
+            // Either sort of reference_wrapper can be constructed from a
+            // mutable reference.
+            CppConversionType::FromReferenceToReferenceWrapper => var_name.to_string(),
+            CppConversionType::FromPtrToArrayReference(len) => {
+                let ptr = self.unwrapped_type_as_string(cpp_name_map)?;
+                let elem = ptr.strip_suffix('*').unwrap_or(&ptr);
+                format!("*reinterpret_cast<{}(*)[{}]>({})", elem, len, var_name)
+            }
+            CppConversionType::FromBitsetToRustVec(width) => format!(
+                "[](const std::bitset<{0}>& bits) {{ rust::Vec<uint64_t> words; for (size_t i = 0; i < ({0} + 63) / 64; ++i) {{ words.push_back(0); }} for (size_t i = 0; i < {0}; ++i) {{ if (bits[i]) {{ words[i / 64] |= uint64_t(1) << (i % 64); }} }} return words; }}({1})",
+                width, var_name
+            ),
         })
     }
 }
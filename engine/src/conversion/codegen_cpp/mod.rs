@@ -12,16 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod error_code_prelude;
 mod function_wrapper_cpp;
 mod new_and_delete_prelude;
 pub(crate) mod type_to_cpp;
 
 use crate::{
     conversion::analysis::fun::{function_wrapper::CppFunctionKind, FnAnalysis},
-    types::{make_ident, QualifiedName},
+    types::{make_helper_names, make_ident, QualifiedName},
     CppCodegenOptions, CppFilePair,
 };
-use autocxx_parser::IncludeCppConfig;
+use autocxx_parser::{IncludeCppConfig, IteratorCategory};
+use indoc::indoc;
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 use type_to_cpp::{original_name_map_from_apis, type_to_cpp, CppNameMap};
@@ -45,6 +47,10 @@ enum Header {
     CxxH,
     CxxgenH,
     NewDeletePrelude,
+    ErrorCodePrelude,
+    RttiCheck,
+    SharedFromThisPrelude,
+    SmartPointerPrelude,
 }
 
 impl Header {
@@ -64,6 +70,10 @@ impl Header {
                 format!("#include \"{}cxxgen.h\"", prefix)
             }
             Header::NewDeletePrelude => new_and_delete_prelude::NEW_AND_DELETE_PRELUDE.to_string(),
+            Header::ErrorCodePrelude => error_code_prelude::ERROR_CODE_PRELUDE.to_string(),
+            Header::RttiCheck => RTTI_CHECK.to_string(),
+            Header::SharedFromThisPrelude => SHARED_FROM_THIS_PRELUDE.to_string(),
+            Header::SmartPointerPrelude => SMART_POINTER_PRELUDE.to_string(),
         }
     }
 
@@ -72,6 +82,64 @@ impl Header {
     }
 }
 
+/// Downcasts use `dynamic_cast`, so they're generated unless autocxx was
+/// told that RTTI is off; this catches the C++ being built without it
+/// anyway, which would otherwise fail with a less helpful error.
+static RTTI_CHECK: &str = indoc! {"
+    #if !defined(__GXX_RTTI) && !defined(_CPPRTTI)
+    #error \"autocxx generated downcasts using dynamic_cast, but RTTI is disabled. Pass -fno-rtti to autocxx as well.\"
+    #endif
+"};
+
+/// Calling `shared_from_this` on an object which no `std::shared_ptr` owns
+/// is undefined behavior before C++17, and throws after. Where the library
+/// has `weak_from_this`, we ask it instead, so that we get an empty
+/// pointer; otherwise, we've no way to tell.
+static SHARED_FROM_THIS_PRELUDE: &str = indoc! {"
+    #include <memory>
+    #ifndef AUTOCXX_SHARED_FROM_THIS_PRELUDE
+    #define AUTOCXX_SHARED_FROM_THIS_PRELUDE
+    template <typename T> std::shared_ptr<T> autocxx_shared_from_this(const T& obj) {
+    #if defined(__cpp_lib_enable_shared_from_this)
+      auto shared = obj.weak_from_this().lock();
+    #else
+      auto shared = obj.shared_from_this();
+    #endif
+      return std::const_pointer_cast<T>(std::static_pointer_cast<const T>(shared));
+    }
+    template <typename T> std::weak_ptr<T> autocxx_weak_from_this(const T& obj) {
+      return autocxx_shared_from_this(obj);
+    }
+    #endif // AUTOCXX_SHARED_FROM_THIS_PRELUDE
+"};
+
+/// Follows `operator->` until it gets to a plain pointer, as C++ does for
+/// `p->member`, so that a smart pointer may return another. A null pointer
+/// is thrown as an exception, which the Rust side turns into a panic, rather
+/// than becoming a null Rust reference.
+static SMART_POINTER_PRELUDE: &str = indoc! {"
+    #include <stdexcept>
+    #ifndef AUTOCXX_SMART_POINTER_PRELUDE
+    #define AUTOCXX_SMART_POINTER_PRELUDE
+    template <typename P> struct autocxx_arrow {
+      template <typename Q> static auto get(Q&& p)
+          -> decltype(autocxx_arrow<decltype(p.operator->())>::get(p.operator->())) {
+        return autocxx_arrow<decltype(p.operator->())>::get(p.operator->());
+      }
+    };
+    template <typename T> struct autocxx_arrow<T*> {
+      static T* get(T* p) { return p; }
+    };
+    template <typename P> auto autocxx_deref(P& p) -> decltype(*autocxx_arrow<P>::get(p)) {
+      auto ptr = autocxx_arrow<P>::get(p);
+      if (ptr == nullptr) {
+        throw std::logic_error(\"autocxx: dereferenced a null smart pointer\");
+      }
+      return *ptr;
+    }
+    #endif // AUTOCXX_SMART_POINTER_PRELUDE
+"};
+
 enum ConversionDirection {
     RustCallsCpp,
     CppCallsCpp,
@@ -146,16 +214,46 @@ impl<'a> CppCodeGenerator<'a> {
     ) -> Result<(), ConvertError> {
         let mut constructors_by_subclass: HashMap<SubclassName, Vec<&CppFunction>> = HashMap::new();
         let mut methods_by_subclass: HashMap<SubclassName, Vec<SubclassFunction>> = HashMap::new();
+        let mut protected_methods_by_subclass: HashMap<SubclassName, Vec<String>> = HashMap::new();
         let mut deferred_apis = Vec::new();
         for api in apis {
+            if let Api::Function { fun, name, .. } = &api {
+                if let Provenance::SynthesizedSubclassProtectedMethod(sub) = &fun.provenance {
+                    let methods = protected_methods_by_subclass
+                        .entry(sub.clone())
+                        .or_default();
+                    // Overloads are all made public at once.
+                    let cpp_name = name.cpp_name();
+                    if !methods.contains(&cpp_name) {
+                        methods.push(cpp_name);
+                    }
+                }
+            }
             match &api {
                 Api::StringConstructor { .. } => self.generate_string_constructor(),
+                Api::CppAny { castables, .. } => self.generate_any_helpers(castables),
+                Api::CppIterable {
+                    name,
+                    container,
+                    element,
+                    category,
+                    is_set,
+                } => self.generate_iterable_helpers(
+                    name.name.get_final_item(),
+                    container,
+                    element,
+                    *category,
+                    *is_set,
+                ),
                 Api::Function {
                     analysis:
                         FnAnalysis {
                             cpp_wrapper: Some(cpp_wrapper),
                             ignore_reason: Ok(_),
                             externally_callable: true,
+                            owned_return,
+                            resource_return,
+                            unique_array_return,
                             ..
                         },
                     fun,
@@ -167,7 +265,16 @@ impl<'a> CppCodeGenerator<'a> {
                             .or_default()
                             .push(&details.cpp_impl);
                     }
-                    self.generate_cpp_function(cpp_wrapper)?
+                    self.generate_cpp_function(cpp_wrapper)?;
+                    if let Some(owned_return) = owned_return {
+                        self.generate_cpp_function(&owned_return.deleter)?
+                    }
+                    if let Some(resource_return) = resource_return {
+                        self.generate_cpp_function(&resource_return.close)?
+                    }
+                    if let Some(unique_array_return) = unique_array_return {
+                        self.generate_cpp_function(&unique_array_return.deleter)?
+                    }
                 }
                 Api::ConcreteType { rs_definition, .. } => self.generate_typedef(
                     api.name(),
@@ -186,6 +293,8 @@ impl<'a> CppCodeGenerator<'a> {
                             is_pure_virtual: details.is_pure_virtual,
                         });
                 }
+                // A protected method which needs no wrapper of its own.
+                Api::Function { .. } => {}
                 _ => panic!("Should have filtered on needs_cpp_codegen"),
             }
         }
@@ -197,24 +306,82 @@ impl<'a> CppCodeGenerator<'a> {
                     name,
                     constructors_by_subclass.remove(name).unwrap_or_default(),
                     methods_by_subclass.remove(name).unwrap_or_default(),
+                    protected_methods_by_subclass
+                        .remove(name)
+                        .unwrap_or_default(),
                 )?,
                 _ => panic!("Unexpected deferred API"),
             }
         }
+        if self.config.exceptions_as_panics && !self.additional_functions.is_empty() {
+            self.generate_exception_handler();
+        }
         Ok(())
     }
 
+    /// cxx catches only `std::exception`s thrown by functions which return
+    /// a `Result`. It allows this behavior to be overridden by a
+    /// `rust::behavior::trycatch` function, which we provide so that other
+    /// exceptions also reach Rust rather than terminating the program.
+    fn generate_exception_handler(&mut self) {
+        let declaration = indoc! {"
+            #ifndef AUTOCXX_EXCEPTIONS_AS_PANICS
+            #define AUTOCXX_EXCEPTIONS_AS_PANICS
+            namespace rust {
+            namespace behavior {
+            template <typename Try, typename Fail>
+            static void trycatch(Try &&func, Fail &&fail) noexcept try {
+              func();
+            } catch (const ::std::exception &e) {
+              fail(e.what());
+            } catch (...) {
+              fail(\"C++ exception not derived from std::exception\");
+            }
+            } // namespace behavior
+            } // namespace rust
+            #endif // AUTOCXX_EXCEPTIONS_AS_PANICS
+        "};
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: None,
+            declaration: Some(declaration.to_string()),
+            definition: None,
+            headers: vec![Header::System("exception")],
+            cpp_headers: Vec::new(),
+        })
+    }
+
     fn generate(&self) -> Option<CppFilePair> {
-        if self.additional_functions.is_empty() {
+        // The checks are needed even if there's no other C++ to generate,
+        // so that every set of bindings is checked.
+        let define_checks = self.generate_define_checks();
+        let abi_checks = if self.cpp_codegen_options.suppress_abi_checks {
+            String::new()
+        } else {
+            generate_abi_checks(rust_char_is_signed())
+        };
+        if self.additional_functions.is_empty() && define_checks.is_empty() && abi_checks.is_empty()
+        {
             None
         } else {
             let headers = self.collect_headers(|additional_need| &additional_need.headers);
             let cpp_headers = self.collect_headers(|additional_need| &additional_need.cpp_headers);
             let type_definitions = self.concat_additional_items(|x| x.type_definition.as_ref());
             let declarations = self.concat_additional_items(|x| x.declaration.as_ref());
+            // Each include_cpp! block has its own header, and C++ may
+            // include several of them.
+            let guard = format!(
+                "__AUTOCXXGEN_{}_H__",
+                self.config.get_mod_name().to_string().to_uppercase()
+            );
             let declarations = format!(
-                "#ifndef __AUTOCXXGEN_H__\n#define __AUTOCXXGEN_H__\n\n{}\n{}\n{}\n{}#endif // __AUTOCXXGEN_H__\n",
-                headers, self.inclusions, type_definitions, declarations
+                "#ifndef {0}\n#define {0}\n\n{1}{2}{3}\n{4}\n{5}\n{6}#endif // {0}\n",
+                guard,
+                define_checks,
+                abi_checks,
+                headers,
+                self.inclusions,
+                type_definitions,
+                declarations
             );
             log::info!("Additional C++ decls:\n{}", declarations);
             let header_name = format!("autocxxgen_{}.h", self.config.get_mod_name());
@@ -241,6 +408,23 @@ impl<'a> CppCodeGenerator<'a> {
         }
     }
 
+    /// The bindings are only valid if the C++ is compiled with the same
+    /// preprocessor symbols as were used when we analyzed it.
+    fn generate_define_checks(&self) -> String {
+        self.cpp_codegen_options
+            .required_defines
+            .iter()
+            .map(|(var, val)| {
+                let mut check = format!("#ifndef {0}\n#error \"autocxx bindings were generated with {0} defined, but it is not defined now\"\n#endif\n", var);
+                // Only numeric values can be compared by the preprocessor.
+                if let Some(val) = val.as_ref().filter(|val| val.parse::<i64>().is_ok()) {
+                    check.push_str(&format!("#if {0} != {1}\n#error \"autocxx bindings were generated with {0}={1}, but it has a different value now\"\n#endif\n", var, val));
+                }
+                check
+            })
+            .join("")
+    }
+
     fn collect_headers<F>(&self, filter: F) -> String
     where
         F: Fn(&AdditionalFunction) -> &[Header],
@@ -286,15 +470,121 @@ impl<'a> CppCodeGenerator<'a> {
         })
     }
 
+    /// Functions to inspect a `std::any`, and for each type listed in
+    /// `any_castable!`, to store one in a `std::any` and get it back.
+    fn generate_any_helpers(&mut self, castables: &[QualifiedName]) {
+        let prefix = self.config.get_any_helper_prefix();
+        let mut declaration = format!(
+            indoc! {"
+            inline std::unique_ptr<std::string> {0}_type_name(const std::any& any) {{ return std::make_unique<std::string>(any.type().name()); }}
+            inline bool {0}_has_value(const std::any& any) {{ return any.has_value(); }}
+            "},
+            prefix
+        );
+        for (castable, helper_name) in castables.iter().zip(make_helper_names(castables)) {
+            declaration.push_str(&format!(
+                indoc! {"
+                inline const {1}* {0}_cast_{2}(const std::any& any) {{ return std::any_cast<{1}>(&any); }}
+                inline std::unique_ptr<std::any> {0}_wrap_{2}(const {1}& value) {{ return std::make_unique<std::any>(value); }}
+                "},
+                prefix,
+                castable.to_cpp_name(),
+                helper_name
+            ));
+        }
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: None,
+            declaration: Some(declaration),
+            definition: None,
+            headers: vec![
+                Header::System("any"),
+                Header::System("memory"),
+                Header::System("string"),
+            ],
+            cpp_headers: Vec::new(),
+        })
+    }
+
+    /// A cursor holding the current front and back iterators of a container
+    /// listed in `iterable!`, and functions to make one and move it along,
+    /// from the back too if the category allows.
+    fn generate_iterable_helpers(
+        &mut self,
+        name: &str,
+        container: &QualifiedName,
+        element: &QualifiedName,
+        category: IteratorCategory,
+        is_set: bool,
+    ) {
+        let container = container.to_cpp_name();
+        let element = element.to_cpp_name();
+        let mut declaration = format!(
+            indoc! {"
+            struct {0}_cursor {{
+                decltype(std::begin(std::declval<const {1}&>())) front;
+                decltype(std::end(std::declval<const {1}&>())) back;
+            }};
+            inline std::unique_ptr<{0}_cursor> {0}_begin(const {1}& container) {{ return std::unique_ptr<{0}_cursor>(new {0}_cursor{{std::begin(container), std::end(container)}}); }}
+            inline const {2}* {0}_next({0}_cursor& cursor) {{ return cursor.front == cursor.back ? nullptr : &*cursor.front++; }}
+            "},
+            name, container, element
+        );
+        if !matches!(category, IteratorCategory::Forward) {
+            declaration.push_str(&format!(
+                "inline const {1}* {0}_next_back({0}_cursor& cursor) {{ return cursor.front == cursor.back ? nullptr : &*--cursor.back; }}\n",
+                name, element
+            ));
+        }
+        if matches!(category, IteratorCategory::RandomAccess) {
+            declaration.push_str(&format!(
+                "inline size_t {0}_len(const {0}_cursor& cursor) {{ return cursor.back - cursor.front; }}\n",
+                name
+            ));
+        }
+        if is_set {
+            declaration.push_str(&format!(
+                indoc! {"
+                inline bool {0}_contains(const {1}& container, const {2}& value) {{ return container.find(value) != container.end(); }}
+                inline const {2}* {0}_insert({1}& container, const {2}& value, bool& inserted) {{ auto result = container.insert(value); inserted = result.second; return &*result.first; }}
+                inline size_t {0}_size(const {1}& container) {{ return container.size(); }}
+                "},
+                name, container, element
+            ));
+        }
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: None,
+            declaration: Some(declaration),
+            definition: None,
+            headers: vec![
+                Header::System("cstddef"),
+                Header::System("iterator"),
+                Header::System("memory"),
+                Header::System("utility"),
+            ],
+            cpp_headers: Vec::new(),
+        })
+    }
+
     fn generate_cpp_function(&mut self, details: &CppFunction) -> Result<(), ConvertError> {
-        self.additional_functions
-            .push(self.generate_cpp_function_inner(
-                details,
-                false,
-                ConversionDirection::RustCallsCpp,
-                false,
-                None,
-            )?);
+        // A struct shared with Rust is defined by cxx, in a header which
+        // includes ours, so a function returning one must be defined in
+        // our .cc file instead.
+        let shared_struct = match &details.payload {
+            CppFunctionBody::TupleReturn(_, _, tuple, _) => Some(tuple),
+            _ => None,
+        };
+        let mut function = self.generate_cpp_function_inner(
+            details,
+            false,
+            ConversionDirection::RustCallsCpp,
+            shared_struct.is_some(),
+            None,
+        )?;
+        if let Some(shared_struct) = shared_struct {
+            function.type_definition = Some(format!("struct {};", shared_struct));
+            function.cpp_headers.push(Header::CxxgenH);
+        }
+        self.additional_functions.push(function);
         Ok(())
     }
 
@@ -403,8 +693,34 @@ impl<'a> CppCodeGenerator<'a> {
                 ),
             })
             .collect();
-        let mut arg_list = arg_list?.into_iter();
-        let receiver = if is_a_method { arg_list.next() } else { None };
+        let mut arg_list = arg_list?;
+        // A std::error_code& parameter is given a local which we check
+        // after the call.
+        let error_code_param = match conversion_direction {
+            ConversionDirection::RustCallsCpp => details.error_code_param,
+            _ => None,
+        };
+        if let Some(idx) = error_code_param {
+            arg_list.insert(idx, "autocxx_ec".to_string());
+        }
+        let resolved_address = match &details.payload {
+            CppFunctionBody::ResolvedFunctionCall(..) => arg_list.pop(),
+            _ => None,
+        };
+        let mut arg_list = arg_list.into_iter();
+        let receiver = if is_a_method
+            || matches!(
+                &details.payload,
+                CppFunctionBody::FunctionPointerIsSet(_)
+                    | CppFunctionBody::FunctionPointerCall(_)
+                    | CppFunctionBody::EmplaceReturn(..)
+                    | CppFunctionBody::SharedFromThis(_)
+                    | CppFunctionBody::SmartPointerDeref
+            ) {
+            arg_list.next()
+        } else {
+            None
+        };
         if matches!(&details.payload, CppFunctionBody::ConstructSuperclass(_)) {
             arg_list.next();
         }
@@ -423,8 +739,11 @@ impl<'a> CppCodeGenerator<'a> {
                 CppFunctionBody::PlacementNew(ns, id) => {
                     let ty_id = QualifiedName::new(ns, id.clone());
                     let ty_id = self.namespaced_name(&ty_id);
+                    // The memory has already been allocated, so we want the
+                    // global placement new even if the class has its own
+                    // operator new, which would hide it.
                     (
-                        format!("new ({}) {}({})", receiver.unwrap(), ty_id, arg_list),
+                        format!("::new ({}) {}({})", receiver.unwrap(), ty_id, arg_list),
                         "".to_string(),
                         false,
                     )
@@ -453,6 +772,18 @@ impl<'a> CppCodeGenerator<'a> {
                         )
                     }
                 },
+                CppFunctionBody::InstantiationCall(ns, instantiated) => (
+                    format!(
+                        "{}({})",
+                        ns.into_iter()
+                            .cloned()
+                            .chain(std::iter::once(instantiated.clone()))
+                            .join("::"),
+                        arg_list
+                    ),
+                    "".to_string(),
+                    false,
+                ),
                 CppFunctionBody::StaticMethodCall(ns, ty_id, fn_id) => {
                     let underlying_function_call = ns
                         .into_iter()
@@ -477,13 +808,161 @@ impl<'a> CppCodeGenerator<'a> {
                         true,
                     )
                 }
+                CppFunctionBody::CloseResource(ns, id, invalid) => {
+                    let close_call = format!(
+                        "{}({})",
+                        ns.into_iter()
+                            .cloned()
+                            .chain(std::iter::once(id.to_string()))
+                            .join("::"),
+                        arg_list
+                    );
+                    let close_call = match invalid {
+                        Some(invalid) => {
+                            format!("if ({} != ({})) {}", arg_list, invalid, close_call)
+                        }
+                        None => close_call,
+                    };
+                    (close_call, "".to_string(), false)
+                }
+                CppFunctionBody::RefcountCall(method) => {
+                    (format!("{}->{}()", arg_list, method), "".to_string(), false)
+                }
+                CppFunctionBody::ValidityCheck(method, negated) => {
+                    let check = match method {
+                        Some(method) => format!("{}.{}()", arg_list, method),
+                        None => format!("static_cast<bool>({})", arg_list),
+                    };
+                    let check = if *negated {
+                        format!("!{}", check)
+                    } else {
+                        check
+                    };
+                    (check, "".to_string(), false)
+                }
+                CppFunctionBody::DynamicCast(ty, mutable) => (
+                    format!(
+                        "dynamic_cast<{}{}*>(&{})",
+                        if *mutable { "" } else { "const " },
+                        self.namespaced_name(ty),
+                        arg_list
+                    ),
+                    "".to_string(),
+                    false,
+                ),
+                CppFunctionBody::SharedFromThis(weak) => (
+                    format!(
+                        "autocxx_{}_from_this({})",
+                        if *weak { "weak" } else { "shared" },
+                        receiver.unwrap()
+                    ),
+                    "".to_string(),
+                    false,
+                ),
+                CppFunctionBody::SmartPointerDeref => (
+                    format!("autocxx_deref({})", receiver.unwrap()),
+                    "".to_string(),
+                    false,
+                ),
+                CppFunctionBody::FunctionPointerIsSet(field) => (
+                    format!("{}.{} != nullptr", receiver.unwrap(), field),
+                    "".to_string(),
+                    false,
+                ),
+                CppFunctionBody::FunctionPointerCall(field) => {
+                    let fn_ptr = format!("{}.{}", receiver.unwrap(), field);
+                    (
+                        format!(
+                            "({} ? void() : std::terminate(), {})({})",
+                            fn_ptr, fn_ptr, arg_list
+                        ),
+                        "".to_string(),
+                        false,
+                    )
+                }
+                CppFunctionBody::EmplaceReturn(ns, id, ty) => (
+                    // As for PlacementNew, we want the global placement new.
+                    format!(
+                        "::new ({}) {}({}({}))",
+                        receiver.unwrap(),
+                        self.namespaced_name(ty),
+                        ns.into_iter()
+                            .cloned()
+                            .chain(std::iter::once(id.to_string()))
+                            .join("::"),
+                        arg_list
+                    ),
+                    "".to_string(),
+                    false,
+                ),
+                CppFunctionBody::TupleReturn(ns, id, tuple, elements) => {
+                    let fields: Result<Vec<_>, _> = elements
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, conv)| {
+                            conv.cpp_conversion(
+                                &format!("std::get<{}>(std::move(autocxx_tuple))", idx),
+                                &self.original_name_map,
+                                true,
+                            )
+                        })
+                        .collect();
+                    (
+                        format!(
+                            "[](auto autocxx_tuple) {{ return {}{{ {} }}; }}({}({}))",
+                            tuple,
+                            fields?.join(", "),
+                            ns.into_iter()
+                                .cloned()
+                                .chain(std::iter::once(id.to_string()))
+                                .join("::"),
+                            arg_list
+                        ),
+                        "".to_string(),
+                        false,
+                    )
+                }
+                // Taking the function's address here doesn't use it, so
+                // it needn't be linked.
+                CppFunctionBody::ResolvedFunctionCall(ns, id) => (
+                    format!(
+                        "reinterpret_cast<decltype(&{})>(static_cast<std::uintptr_t>({}))({})",
+                        ns.into_iter()
+                            .cloned()
+                            .chain(std::iter::once(id.to_string()))
+                            .join("::"),
+                        resolved_address.unwrap(),
+                        arg_list
+                    ),
+                    "".to_string(),
+                    false,
+                ),
+                CppFunctionBody::DeleteArray => {
+                    (format!("delete[] {}", arg_list), "".to_string(), false)
+                }
                 CppFunctionBody::FreeUninitialized(ty) => (
                     format!("delete_appropriately<{}>(arg0);", self.namespaced_name(ty)),
                     "".to_string(),
                     true,
                 ),
             };
-        if let Some(ret) = &details.return_conversion {
+        if error_code_param.is_some() {
+            underlying_function_call = match &details.return_conversion {
+                Some(ret) => format!(
+                    "std::error_code autocxx_ec; auto&& autocxx_ret = {}; autocxx_throw_if_error_code(autocxx_ec); return {}",
+                    underlying_function_call,
+                    ret.cpp_conversion(
+                        "std::forward<decltype(autocxx_ret)>(autocxx_ret)",
+                        &self.original_name_map,
+                        true
+                    )?
+                ),
+                None => format!(
+                    "std::error_code autocxx_ec; {}; autocxx_throw_if_error_code(autocxx_ec)",
+                    underlying_function_call
+                ),
+            };
+        } else if let Some(ret) = &details.return_conversion {
             underlying_function_call = format!(
                 "return {}",
                 match conversion_direction {
@@ -535,6 +1014,58 @@ impl<'a> CppCodeGenerator<'a> {
         if need_allocators {
             headers.push(Header::NewDeletePrelude);
         }
+        if error_code_param.is_some() {
+            headers.push(Header::ErrorCodePrelude);
+        }
+        if matches!(details.payload, CppFunctionBody::DynamicCast(..)) {
+            headers.push(Header::RttiCheck);
+        }
+        if matches!(details.payload, CppFunctionBody::FunctionPointerCall(_)) {
+            headers.push(Header::System("exception"));
+        }
+        if matches!(details.payload, CppFunctionBody::ResolvedFunctionCall(..)) {
+            headers.push(Header::System("cstdint"));
+        }
+        if matches!(details.payload, CppFunctionBody::TupleReturn(..)) {
+            headers.push(Header::System("tuple"));
+        }
+        if matches!(details.payload, CppFunctionBody::SharedFromThis(_)) {
+            headers.push(Header::SharedFromThisPrelude);
+        }
+        if matches!(details.payload, CppFunctionBody::SmartPointerDeref) {
+            headers.push(Header::SmartPointerPrelude);
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .chain(details.return_conversion.iter())
+            .any(|conv| conv.involves_string_view())
+        {
+            headers.push(Header::System("string_view"));
+            headers.push(Header::System("cstdint"));
+            headers.push(Header::CxxH);
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .chain(details.return_conversion.iter())
+            .any(|conv| conv.involves_filesystem_path())
+        {
+            headers.push(Header::System("filesystem"));
+            headers.push(Header::System("cstdint"));
+            headers.push(Header::System("cstring"));
+            headers.push(Header::CxxH);
+        }
+        if details
+            .argument_conversion
+            .iter()
+            .chain(details.return_conversion.iter())
+            .any(|conv| conv.involves_bitset())
+        {
+            headers.push(Header::System("bitset"));
+            headers.push(Header::System("cstdint"));
+            headers.push(Header::CxxH);
+        }
         Ok(AdditionalFunction {
             type_definition: None,
             declaration,
@@ -570,6 +1101,7 @@ impl<'a> CppCodeGenerator<'a> {
         subclass: &SubclassName,
         constructors: Vec<&CppFunction>,
         methods: Vec<SubclassFunction>,
+        protected_methods: Vec<String>,
     ) -> Result<(), ConvertError> {
         let holder = subclass.holder();
         self.additional_functions.push(AdditionalFunction {
@@ -616,6 +1148,9 @@ impl<'a> CppCodeGenerator<'a> {
                 self.additional_functions.push(super_fn_impl);
             }
         }
+        for method in protected_methods {
+            method_decls.push(format!("using {}::{};", superclass.to_cpp_name(), method));
+        }
         // In future, for each superclass..
         let super_name = superclass.get_final_item();
         method_decls.push(format!(
@@ -640,6 +1175,21 @@ impl<'a> CppCodeGenerator<'a> {
             constructor_decls.push(decl);
             self.additional_functions.push(fn_impl);
         }
+        // bindgen doesn't tell us whether a class is final, so we can't
+        // refuse to subclass one. Instead, make sure the C++ compiler says
+        // why it fails.
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: Some(format!(
+                "static_assert(!std::is_final<{}>::value, \"{} is final, so can't be subclassed by {}\");",
+                superclass.to_cpp_name(),
+                superclass.to_cpp_name(),
+                subclass.0.name.get_final_item(),
+            )),
+            declaration: None,
+            definition: None,
+            headers: vec![Header::System("type_traits")],
+            cpp_headers: Vec::new(),
+        });
         self.additional_functions.push(AdditionalFunction {
             type_definition: Some(format!(
                 "class {} : {}\n{{\npublic:\n{}\n{}\nvoid {}() const;\nprivate:rust::Box<{}> obs;\nvoid really_remove_ownership();\n\n}};",
@@ -665,3 +1215,45 @@ impl<'a> CppCodeGenerator<'a> {
         Ok(())
     }
 }
+
+/// The bindings assume C++ `bool` and `char` are the same size as Rust's
+/// `bool` and `c_char`, and that `char` has the same signedness as
+/// `c_char`. These don't need any system headers.
+fn generate_abi_checks(char_is_signed: bool) -> String {
+    format!(
+        "static_assert(sizeof(bool) == 1, \"autocxx bindings require bool to be one byte, as it is in Rust\");\n\
+         static_assert(static_cast<unsigned char>(-1) == 255, \"autocxx bindings require CHAR_BIT to be 8\");\n\
+         static_assert(static_cast<char>(-1) {} 0, \"autocxx bindings require char to be {} to match c_char for this Rust target; check for -fsigned-char or -funsigned-char\");\n",
+        if char_is_signed { "<" } else { ">" },
+        if char_is_signed { "signed" } else { "unsigned" }
+    )
+}
+
+/// Whether `c_char` is signed for the Rust target. When we're run from a
+/// build script the target may not be the platform we're running on, so
+/// this mirrors the rules in `core::ffi`.
+fn rust_char_is_signed() -> bool {
+    let cfg = |name| std::env::var(format!("CARGO_CFG_TARGET_{}", name)).unwrap_or_default();
+    let arch = cfg("ARCH");
+    if arch.is_empty() {
+        return std::os::raw::c_char::MIN != 0;
+    }
+    let unsigned_arch = matches!(
+        arch.as_str(),
+        "aarch64"
+            | "arm"
+            | "csky"
+            | "hexagon"
+            | "msp430"
+            | "powerpc"
+            | "powerpc64"
+            | "riscv32"
+            | "riscv64"
+            | "s390x"
+            | "xtensa"
+    );
+    let signed_platform = cfg("FAMILY").split(',').any(|f| f == "windows")
+        || cfg("VENDOR") == "apple"
+        || cfg("OS") == "vita";
+    !unsigned_arch || signed_platform
+}
@@ -16,9 +16,11 @@ use indoc::indoc;
 
 /// This is logic to call either an overloaded operator new/delete
 /// or the standard one.
-/// The SFINAE magic here is: int is a better match than long,
-/// and so the versions which match class-specific operator new/delete
-/// will be used in preference to the general global ::operator new/delete.
+/// The SFINAE magic here is: int is a better match than long, which is
+/// better than an ellipsis, and so the versions which match class-specific
+/// operator new/delete will be used in preference to the general global
+/// ::operator new/delete. As for a delete expression, a class's sized
+/// operator delete is used only if it has no unsized one.
 pub(super) static NEW_AND_DELETE_PRELUDE: &str = indoc! {"
     #include <stddef.h>
     #ifndef AUTOCXX_NEW_AND_DELETE_PRELUDE
@@ -28,7 +30,11 @@ pub(super) static NEW_AND_DELETE_PRELUDE: &str = indoc! {"
       T::operator delete(ptr);
     }
     template<class T>
-    auto delete_imp(T* ptr, long) -> decltype(::operator delete(ptr), void()) {
+    auto delete_imp(T* ptr, long) -> decltype(T::operator delete(ptr, sizeof(T)), void()) {
+      T::operator delete(ptr, sizeof(T));
+    }
+    template<class T>
+    auto delete_imp(T* ptr, ...) -> decltype(::operator delete(ptr), void()) {
       ::operator delete(ptr);
     }
     template<class T>
@@ -104,6 +104,17 @@ pub(crate) fn type_to_cpp(ty: &Type, cpp_name_map: &CppNameMap) -> Result<String
             get_mut_string(&typr.mutability),
             type_to_cpp(typr.elem.as_ref(), cpp_name_map)?
         )),
+        // For a pointer to a pointer, any const applies to the inner
+        // pointer itself, not what it points to.
+        Type::Ptr(typp) if matches!(*typp.elem, Type::Ptr(_)) => Ok(format!(
+            "{}{}*",
+            type_to_cpp(typp.elem.as_ref(), cpp_name_map)?,
+            if typp.mutability.is_some() {
+                ""
+            } else {
+                " const"
+            }
+        )),
         Type::Ptr(typp) => Ok(format!(
             "{}{}*",
             get_mut_string(&typp.mutability),
@@ -0,0 +1,97 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::{parse_quote, ForeignItem, Item};
+
+use crate::types::{make_helper_names, make_ident, QualifiedName};
+
+/// The declarations of the C++ helpers for `std::any`, for the
+/// [cxx::bridge], followed by the `CppAny` alias, its methods and an
+/// implementation of `autocxx::AnyCastable` for each type listed in
+/// `any_castable!`.
+pub(crate) fn make_any_helpers(
+    prefix: &str,
+    castables: &[QualifiedName],
+) -> (Vec<ForeignItem>, Vec<Item>) {
+    let any_name = QualifiedName::new_from_cpp_name("std::any");
+    let any_id = any_name.get_final_ident();
+    let any_path = any_name.to_type_path();
+    let type_name_id = make_ident(format!("{}_type_name", prefix));
+    let has_value_id = make_ident(format!("{}_has_value", prefix));
+    let mut extern_c_mod_items = vec![
+        parse_quote! {
+            fn #type_name_id(any: &#any_id) -> UniquePtr<CxxString>;
+        },
+        parse_quote! {
+            fn #has_value_id(any: &#any_id) -> bool;
+        },
+    ];
+    let mut global_items = vec![
+        parse_quote! {
+            /// A C++ `std::any`.
+            pub type CppAny = #any_path;
+        },
+        parse_quote! {
+            impl #any_path {
+                /// The name of the type of the value this holds, as given by
+                /// `std::type_info::name`, so it's up to the compiler: GCC and
+                /// clang give the mangled name. An empty `std::any` holds
+                /// `void`.
+                pub fn type_name(&self) -> String {
+                    cxxbridge::#type_name_id(self).to_string_lossy().into_owned()
+                }
+
+                /// Whether this holds a value.
+                pub fn has_value(&self) -> bool {
+                    cxxbridge::#has_value_id(self)
+                }
+
+                /// The value this holds, if it's a `T`. `None` if it holds a
+                /// value of another type, or none.
+                pub fn downcast_ref<T: autocxx::AnyCastable<Self>>(&self) -> Option<&T> {
+                    unsafe { T::any_cast(self).as_ref() }
+                }
+
+                /// Makes a `std::any` holding a copy of `value`.
+                pub fn wrap<T: autocxx::AnyCastable<Self>>(value: &T) -> cxx::UniquePtr<Self> {
+                    T::wrap(value)
+                }
+            }
+        },
+    ];
+    for (castable, helper_name) in castables.iter().zip(make_helper_names(castables)) {
+        let id = castable.get_final_ident();
+        let path = castable.to_type_path();
+        let cast_id = make_ident(format!("{}_cast_{}", prefix, helper_name));
+        let wrap_id = make_ident(format!("{}_wrap_{}", prefix, helper_name));
+        extern_c_mod_items.push(parse_quote! {
+            fn #cast_id(any: &#any_id) -> *const #id;
+        });
+        extern_c_mod_items.push(parse_quote! {
+            fn #wrap_id(value: &#id) -> UniquePtr<#any_id>;
+        });
+        global_items.push(parse_quote! {
+            unsafe impl autocxx::AnyCastable<#any_path> for #path {
+                fn any_cast(any: &#any_path) -> *const Self {
+                    cxxbridge::#cast_id(any)
+                }
+
+                fn wrap(value: &Self) -> cxx::UniquePtr<#any_path> {
+                    cxxbridge::#wrap_id(value)
+                }
+            }
+        });
+    }
+    (extern_c_mod_items, global_items)
+}
@@ -0,0 +1,173 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use autocxx_parser::IteratorCategory;
+use syn::{parse_quote, ForeignItem, Ident, Item, Type};
+
+use crate::{
+    known_types::known_types,
+    types::{make_ident, QualifiedName},
+};
+
+/// The declarations of the C++ cursor type and helpers for iterating over
+/// a container listed in `iterable!`, for the [cxx::bridge], followed by
+/// the implementations of `autocxx::CppIterable` and its subtraits which
+/// its category allows, and of `IntoIterator` for references to it. A set
+/// listed in `cpp_set!` gets `contains`, `insert`, `len` and `is_empty`
+/// methods too.
+pub(crate) fn make_iterable_helpers(
+    name: &Ident,
+    container: &QualifiedName,
+    element: &QualifiedName,
+    category: IteratorCategory,
+    is_set: bool,
+) -> (Vec<ForeignItem>, Vec<Item>) {
+    let cursor_id = make_ident(format!("{}_cursor", name));
+    let begin_id = make_ident(format!("{}_begin", name));
+    let next_id = make_ident(format!("{}_next", name));
+    let container_id = container.get_final_ident();
+    let container_path = container.to_type_path();
+    // Primitive elements are the same everywhere; others are named by
+    // their final identifier within the bridge.
+    let (bridge_element, element_path): (Type, Type) =
+        match known_types().known_type_type_path(element) {
+            Some(path) => (Type::Path(path.clone()), Type::Path(path)),
+            None => {
+                let id = element.get_final_ident();
+                (parse_quote! { #id }, Type::Path(element.to_type_path()))
+            }
+        };
+    let mut extern_c_mod_items = vec![
+        parse_quote! {
+            type #cursor_id;
+        },
+        parse_quote! {
+            fn #begin_id(container: &#container_id) -> UniquePtr<#cursor_id>;
+        },
+        parse_quote! {
+            fn #next_id(cursor: Pin<&mut #cursor_id>) -> *const #bridge_element;
+        },
+    ];
+    let len_fn = if matches!(category, IteratorCategory::RandomAccess) {
+        let len_id = make_ident(format!("{}_len", name));
+        extern_c_mod_items.push(parse_quote! {
+            fn #len_id(cursor: &#cursor_id) -> usize;
+        });
+        Some(quote::quote! {
+            fn len(cursor: &Self::Cursor) -> Option<usize> {
+                Some(cxxbridge::#len_id(cursor))
+            }
+        })
+    } else {
+        None
+    };
+    let mut global_items = vec![
+        parse_quote! {
+            unsafe impl autocxx::CppIterable for #container_path {
+                type Element = #element_path;
+                type Cursor = cxxbridge::#cursor_id;
+
+                fn begin(&self) -> cxx::UniquePtr<Self::Cursor> {
+                    cxxbridge::#begin_id(self)
+                }
+
+                fn next(cursor: ::std::pin::Pin<&mut Self::Cursor>) -> *const Self::Element {
+                    cxxbridge::#next_id(cursor)
+                }
+
+                #len_fn
+            }
+        },
+        parse_quote! {
+            impl<'a> IntoIterator for &'a #container_path {
+                type Item = &'a #element_path;
+                type IntoIter = autocxx::CppIter<'a, #container_path>;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    autocxx::CppIter::new(self)
+                }
+            }
+        },
+    ];
+    if !matches!(category, IteratorCategory::Forward) {
+        let next_back_id = make_ident(format!("{}_next_back", name));
+        extern_c_mod_items.push(parse_quote! {
+            fn #next_back_id(cursor: Pin<&mut #cursor_id>) -> *const #bridge_element;
+        });
+        global_items.push(parse_quote! {
+            unsafe impl autocxx::CppDoubleEndedIterable for #container_path {
+                fn next_back(cursor: ::std::pin::Pin<&mut Self::Cursor>) -> *const Self::Element {
+                    cxxbridge::#next_back_id(cursor)
+                }
+            }
+        });
+    }
+    if matches!(category, IteratorCategory::RandomAccess) {
+        global_items.push(parse_quote! {
+            unsafe impl autocxx::CppExactSizeIterable for #container_path {}
+        });
+    }
+    if is_set {
+        let contains_id = make_ident(format!("{}_contains", name));
+        let insert_id = make_ident(format!("{}_insert", name));
+        let size_id = make_ident(format!("{}_size", name));
+        extern_c_mod_items.push(parse_quote! {
+            fn #contains_id(container: &#container_id, value: &#bridge_element) -> bool;
+        });
+        extern_c_mod_items.push(parse_quote! {
+            fn #insert_id(
+                container: Pin<&mut #container_id>,
+                value: &#bridge_element,
+                inserted: &mut bool,
+            ) -> *const #bridge_element;
+        });
+        extern_c_mod_items.push(parse_quote! {
+            fn #size_id(container: &#container_id) -> usize;
+        });
+        global_items.push(parse_quote! {
+            impl #container_path {
+                /// Whether this set contains `value`.
+                pub fn contains(&self, value: &#element_path) -> bool {
+                    cxxbridge::#contains_id(self, value)
+                }
+
+                /// Inserts a copy of `value` unless this set already
+                /// contains it. Returns whether it was inserted, and the
+                /// element in the set equal to it, which is the existing
+                /// one if it wasn't.
+                pub fn insert(
+                    self: ::std::pin::Pin<&mut Self>,
+                    value: &#element_path,
+                ) -> (bool, &#element_path) {
+                    let mut inserted = false;
+                    let element = cxxbridge::#insert_id(self, value, &mut inserted);
+                    // The element belongs to the set, which can't be changed
+                    // while it's borrowed.
+                    (inserted, unsafe { &*element })
+                }
+
+                /// The number of values in this set.
+                pub fn len(&self) -> usize {
+                    cxxbridge::#size_id(self)
+                }
+
+                /// Whether this set is empty.
+                pub fn is_empty(&self) -> bool {
+                    self.len() == 0
+                }
+            }
+        });
+    }
+    (extern_c_mod_items, global_items)
+}
@@ -0,0 +1,201 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use autocxx_parser::FlagsEnum;
+use quote::quote;
+use syn::{parse_quote, Expr, Ident, Item, ItemEnum, Type};
+
+use crate::{to_snake_case, types::make_ident};
+
+/// Turn a bindgen enum listed in `flags!` into a `#[repr(transparent)]`
+/// newtype around its underlying integer, with an associated constant for
+/// each enumerator. A Rust enum can't hold the bitwise OR of several
+/// enumerators, but this can. Returns the newtype, followed by its
+/// operator implementations and a builder type, and the builder's name.
+pub(crate) fn make_flags(item: &ItemEnum, flags: &FlagsEnum) -> (Vec<Item>, Ident) {
+    let id = &item.ident;
+    let builder_id = make_ident(format!("{}Builder", id));
+    let repr = underlying_type(item);
+    let docs = item.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+    let mut consts = Vec::new();
+    let mut builder_methods = Vec::new();
+    let mut next_value: Expr = parse_quote! { 0 };
+    for variant in &item.variants {
+        let variant_id = &variant.ident;
+        let value = match &variant.discriminant {
+            Some((_, value)) => value.clone(),
+            None => next_value,
+        };
+        next_value = parse_quote! { (#value) + 1 };
+        let variant_docs = variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"));
+        consts.push(quote! {
+            #(#variant_docs)*
+            pub const #variant_id: #id = #id(#value);
+        });
+        // Setting a member of an exclusive group first clears the others.
+        let excluded: Vec<_> = flags
+            .exclusive_groups
+            .iter()
+            .filter(|group| group.iter().any(|member| variant_id == member))
+            .flatten()
+            .filter(|member| variant_id != member)
+            .map(make_ident)
+            .collect();
+        let method_id = builder_method_name(&variant_id.to_string());
+        builder_methods.push(quote! {
+            pub fn #method_id(mut self) -> Self {
+                #(self.0 .0 &= !#id::#excluded.0;)*
+                self.0 .0 |= #id::#variant_id.0;
+                self
+            }
+        });
+    }
+    let items = vec![
+        parse_quote! {
+            #(#docs)*
+            #[repr(transparent)]
+            #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+            pub struct #id(pub #repr);
+        },
+        parse_quote! {
+            #[allow(non_upper_case_globals)]
+            impl #id {
+                #(#consts)*
+
+                /// Returns a builder with no flags set.
+                pub fn builder() -> #builder_id {
+                    #builder_id::new()
+                }
+
+                /// The underlying value.
+                pub fn bits(&self) -> #repr {
+                    self.0
+                }
+
+                /// Creates a value from its underlying representation.
+                pub fn from_bits(bits: #repr) -> Self {
+                    Self(bits)
+                }
+
+                /// Whether all the flags set in `other` are also set in this.
+                pub fn contains(&self, other: Self) -> bool {
+                    self.0 & other.0 == other.0
+                }
+
+                /// Whether no flags are set.
+                pub fn is_empty(&self) -> bool {
+                    self.0 == 0
+                }
+            }
+        },
+        parse_quote! {
+            impl ::std::ops::BitOr for #id {
+                type Output = Self;
+                fn bitor(self, other: Self) -> Self {
+                    Self(self.0 | other.0)
+                }
+            }
+        },
+        parse_quote! {
+            impl ::std::ops::BitOrAssign for #id {
+                fn bitor_assign(&mut self, other: Self) {
+                    self.0 |= other.0;
+                }
+            }
+        },
+        parse_quote! {
+            impl ::std::ops::BitAnd for #id {
+                type Output = Self;
+                fn bitand(self, other: Self) -> Self {
+                    Self(self.0 & other.0)
+                }
+            }
+        },
+        parse_quote! {
+            impl ::std::ops::BitAndAssign for #id {
+                fn bitand_assign(&mut self, other: Self) {
+                    self.0 &= other.0;
+                }
+            }
+        },
+        parse_quote! {
+            impl ::std::ops::BitXor for #id {
+                type Output = Self;
+                fn bitxor(self, other: Self) -> Self {
+                    Self(self.0 ^ other.0)
+                }
+            }
+        },
+        parse_quote! {
+            /// Builds a combination of flags, one method per flag.
+            #[derive(Clone, Copy, Default, Debug)]
+            pub struct #builder_id(#id);
+        },
+        parse_quote! {
+            impl #builder_id {
+                /// Starts with no flags set.
+                pub fn new() -> Self {
+                    Self::default()
+                }
+
+                #(#builder_methods)*
+
+                /// Returns the combined flags.
+                pub fn build(self) -> #id {
+                    self.0
+                }
+            }
+        },
+    ];
+    (items, builder_id)
+}
+
+/// The integer type underlying a bindgen enum, from its `#[repr]`.
+pub(crate) fn underlying_type(item: &ItemEnum) -> Type {
+    item.attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("repr"))
+        .and_then(|attr| attr.parse_args().ok())
+        .unwrap_or_else(|| parse_quote! { u32 })
+}
+
+/// The builder method for an enumerator: its name in snake case, so
+/// `READ_WRITE` and `ReadWrite` both become `read_write`. Names which
+/// would clash with a Rust keyword or the builder's own methods get
+/// a trailing underscore.
+fn builder_method_name(variant: &str) -> Ident {
+    let mut name = to_snake_case(variant);
+    if name == "new" || name == "build" || syn::parse_str::<Ident>(&name).is_err() {
+        name.push('_');
+    }
+    make_ident(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::builder_method_name;
+
+    #[test]
+    fn test_builder_method_name() {
+        assert_eq!(builder_method_name("READ").to_string(), "read");
+        assert_eq!(builder_method_name("READ_WRITE").to_string(), "read_write");
+        assert_eq!(builder_method_name("ReadWrite").to_string(), "read_write");
+        assert_eq!(builder_method_name("kRead2Bit").to_string(), "k_read2_bit");
+        assert_eq!(builder_method_name("Move").to_string(), "move_");
+        assert_eq!(builder_method_name("BUILD").to_string(), "build_");
+    }
+}
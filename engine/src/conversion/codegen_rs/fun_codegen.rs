@@ -12,14 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use proc_macro2::TokenStream;
-use quote::quote;
+use std::borrow::Cow;
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
 use syn::{
     parse::Parser,
     parse_quote,
     punctuated::Punctuated,
     token::{Comma, Unsafe},
-    Attribute, FnArg, ForeignItem, Ident, ImplItem, Item, ReturnType,
+    Attribute, ExprClosure, FnArg, ForeignItem, Ident, ImplItem, Item, Pat, PatType, ReturnType,
+    Type, TypePtr,
 };
 
 use super::{
@@ -29,8 +32,12 @@ use super::{
 use crate::{
     conversion::{
         analysis::fun::{
-            ArgumentAnalysis, FnAnalysis, FnKind, MethodKind, RustRenameStrategy,
-            TraitMethodDetails, UnsafetyNeeded,
+            function_wrapper::{
+                CppFunction, CppFunctionBody, RustConversionType, TypeConversionPolicy,
+            },
+            ArgumentAnalysis, ArrayLength, ArrayReturn, AwaitableMethods, FnAnalysis, FnKind,
+            MethodKind, NullCheck, OutParam, OwnedReturn, ResourceReturn, RustRenameStrategy,
+            TraitMethodDetails, TraitMethodKind, UniqueArrayReturn, UnsafetyNeeded,
         },
         codegen_rs::lifetime::add_lifetime_to_all_params,
     },
@@ -79,13 +86,40 @@ pub(super) fn gen_function(
     let mut cpp_name_attr = Vec::new();
     let mut impl_entry = None;
     let mut trait_impl_entry = None;
+    let mut materialization_extras = Vec::new();
     let wrapper_unsafety = analysis.requires_unsafe.wrapper_token();
+    let ret_conversion = analysis
+        .ret_conversion
+        .as_ref()
+        .filter(|conversion| conversion.rust_work_needed());
+    let trace_name = if analysis.trace {
+        Some(match kind {
+            FnKind::Function => QualifiedName::new(ns, make_ident(&rust_name)).to_cpp_name(),
+            FnKind::Method(ref type_name, _) => {
+                format!("{}::{}", type_name.to_cpp_name(), rust_name)
+            }
+            FnKind::TraitMethod {
+                ref impl_for,
+                ref details,
+                ..
+            } => format!("{}::{}", impl_for.to_cpp_name(), details.method_name),
+        })
+    } else {
+        None
+    };
     let fn_generator = FnGenerator {
         param_details: &param_details,
         cxxbridge_name: &cxxbridge_name,
         rust_name: &rust_name,
         unsafety: &wrapper_unsafety,
         doc_attr: &doc_attr,
+        trace_name: &trace_name,
+        exceptions_as_panics: analysis.exceptions_as_panics,
+        bridge_only_unsafe: matches!(analysis.requires_unsafe, UnsafetyNeeded::JustBridge),
+        ret_conversion,
+        error_code: analysis.error_code_param.is_some(),
+        return_lifetime_from: analysis.return_lifetime_from,
+        preconditions: &analysis.preconditions,
     };
     let mut materialization = match kind {
         FnKind::Method(..) | FnKind::TraitMethod { .. } => None,
@@ -97,19 +131,64 @@ pub(super) fn gen_function(
         },
     };
     // In rare occasions, we might need to give an explicit lifetime.
-    let (lifetime_tokens, params, ret_type) =
-        add_explicit_lifetime_if_necessary(&param_details, params, &ret_type);
-    let any_param_needs_rust_conversion = param_details
+    let bridge_lifetime_source = analysis
+        .return_lifetime_from
+        .and_then(|idx| params.iter().nth(idx))
+        .and_then(|param| match param {
+            FnArg::Typed(PatType { pat, .. }) => match pat.as_ref() {
+                Pat::Ident(pp) => Some(pp.ident.clone()),
+                _ => None,
+            },
+            _ => None,
+        });
+    let (lifetime_tokens, params, ret_type) = add_explicit_lifetime_if_necessary(
+        &param_details,
+        bridge_lifetime_source.as_ref(),
+        params,
+        &ret_type,
+    );
+    // The type returned by any Rust wrapper, if it differs from that
+    // returned by the cxx::bridge function.
+    let wrapper_ret_type = match ret_conversion {
+        Some(ret_conversion) => {
+            let ty = ret_conversion.rust_wrapper_unconverted_type();
+            parse_quote! { -> #ty }
+        }
+        None => ret_type.clone().into_owned(),
+    };
+    let wrapper_ret_type = if analysis.error_code_param.is_some() {
+        match wrapper_ret_type {
+            ReturnType::Default => parse_quote! { -> ::std::io::Result<()> },
+            ReturnType::Type(_, ty) => parse_quote! { -> ::std::io::Result<#ty> },
+        }
+    } else {
+        wrapper_ret_type
+    };
+    let any_rust_conversion_needed = param_details
         .iter()
-        .any(|pd| pd.conversion.rust_work_needed());
+        .any(|pd| pd.conversion.rust_work_needed())
+        || ret_conversion.is_some()
+        || analysis.error_code_param.is_some();
     let rust_wrapper_needed = match kind {
         FnKind::TraitMethod { .. } => true,
         FnKind::Method(..) => {
-            any_param_needs_rust_conversion
-                || cxxbridge_name != rust_name
-                || wrapper_function_needed
+            any_rust_conversion_needed || cxxbridge_name != rust_name || wrapper_function_needed
+        }
+        _ => {
+            any_rust_conversion_needed
+                || analysis.trace
+                || analysis.exceptions_as_panics
+                || analysis.owned_return.is_some()
+                || analysis.resource_return.is_some()
+                || analysis.nonzero_return.is_some()
+                || analysis.null_check_return.is_some()
+                || analysis.unique_array_return.is_some()
+                || analysis.array_return.is_some()
+                || analysis.thread_local_global
+                || analysis.emplace_return.is_some()
+                || analysis.tuple_return.is_some()
+                || !analysis.preconditions.is_empty()
         }
-        _ => any_param_needs_rust_conversion,
     };
     if rust_wrapper_needed {
         match kind {
@@ -125,18 +204,112 @@ pub(super) fn gen_function(
                         MethodKind::MakeUnique | MethodKind::Constructor
                     ),
                     type_name,
-                    &ret_type,
+                    &wrapper_ret_type,
                 ));
             }
-            FnKind::TraitMethod { ref details, .. } => {
-                trait_impl_entry = Some(fn_generator.generate_trait_impl(details, &ret_type));
+            FnKind::TraitMethod {
+                ref details,
+                ref kind,
+                ..
+            } => {
+                let mut entry = fn_generator.generate_trait_impl(
+                    details,
+                    &wrapper_ret_type,
+                    matches!(kind, TraitMethodKind::Downcast),
+                );
+                if let (TraitMethodKind::Deref, ReturnType::Type(_, ty)) = (kind, &wrapper_ret_type)
+                {
+                    if let Type::Reference(syn::TypeReference { elem, .. }) = ty.as_ref() {
+                        entry.items.insert(0, parse_quote! { type Target = #elem; });
+                    }
+                }
+                trait_impl_entry = Some(entry);
             }
             _ => {
                 // Generate plain old function
-                materialization = Some(Use::Custom(fn_generator.generate_function_impl(&ret_type)));
+                materialization = Some(Use::Custom(
+                    match (
+                        &analysis.out_param,
+                        &analysis.owned_return,
+                        &analysis.resource_return,
+                    ) {
+                        (Some(out_param), owned_return, _) => fn_generator
+                            .generate_out_param_function_impl(
+                                &ret_type,
+                                out_param,
+                                owned_return.as_ref(),
+                            ),
+                        (None, Some(owned_return), _) => {
+                            fn_generator.generate_owned_return_function_impl(owned_return)
+                        }
+                        (None, None, Some(resource_return)) => {
+                            fn_generator.generate_resource_return_function_impl(resource_return)
+                        }
+                        (None, None, None) if analysis.thread_local_global => {
+                            fn_generator.generate_thread_local_function_impl(&wrapper_ret_type)
+                        }
+                        (None, None, None) if analysis.emplace_return.is_some() => fn_generator
+                            .generate_emplace_return_function_impl(
+                                analysis.emplace_return.as_ref().unwrap(),
+                            ),
+                        (None, None, None) if analysis.tuple_return.is_some() => fn_generator
+                            .generate_tuple_return_function_impl(
+                                analysis.tuple_return.as_ref().unwrap(),
+                            ),
+                        (None, None, None) if analysis.array_return.is_some() => fn_generator
+                            .generate_array_return_function_impl(
+                                analysis.array_return.as_ref().unwrap(),
+                            ),
+                        (None, None, None) if analysis.null_check_return.is_some() => fn_generator
+                            .generate_null_checked_return_function_impl(
+                                &wrapper_ret_type,
+                                analysis.null_check_return.unwrap(),
+                            ),
+                        (None, None, None) => match (
+                            &analysis.nonzero_return,
+                            &analysis.closure_callback,
+                            &analysis.unique_array_return,
+                        ) {
+                            (Some(nonzero), _, _) => {
+                                fn_generator.generate_nonzero_return_function_impl(nonzero)
+                            }
+                            (None, Some(closure_callback), _) => fn_generator
+                                .generate_closure_callback_function_impl(
+                                    &wrapper_ret_type,
+                                    closure_callback,
+                                ),
+                            (None, None, Some(unique_array_return)) => fn_generator
+                                .generate_unique_array_return_function_impl(unique_array_return),
+                            (None, None, None) => {
+                                fn_generator.generate_function_impl(&wrapper_ret_type)
+                            }
+                        },
+                    },
+                ));
             }
         }
     }
+    let mut bindgen_mod_items = Vec::new();
+    if analysis.checked_index {
+        if let FnKind::Method(ref type_name, _) = kind {
+            bindgen_mod_items
+                .push(fn_generator.generate_checked_index_impl(type_name, &wrapper_ret_type));
+        }
+    }
+    if let Some(awaitable) = &analysis.awaitable {
+        if let FnKind::Method(ref type_name, _) = kind {
+            bindgen_mod_items.push(fn_generator.generate_awaitable_impl(
+                type_name,
+                &wrapper_ret_type,
+                awaitable,
+            ));
+        }
+    }
+    if analysis.offload {
+        materialization_extras.push(Use::Custom(
+            fn_generator.generate_offload_impl(&wrapper_ret_type),
+        ));
+    }
     if cxxbridge_name != cpp_call_name && !wrapper_function_needed {
         cpp_name_attr = Attribute::parse_outer
             .parse2(quote!(
@@ -155,6 +328,15 @@ pub(super) fn gen_function(
     // which the user has declared.
     let params = unqualify_params(params);
     let ret_type = unqualify_ret_type(ret_type.into_owned());
+    // cxx catches exceptions from functions returning Result.
+    let ret_type = if analysis.exceptions_as_panics || analysis.error_code_param.is_some() {
+        match ret_type {
+            ReturnType::Default => parse_quote! { -> Result<()> },
+            ReturnType::Type(_, ty) => parse_quote! { -> Result<#ty> },
+        }
+    } else {
+        ret_type
+    };
     // And we need to make an attribute for the namespace that the function
     // itself is in.
     let namespace_attr = if ns.is_empty() || wrapper_function_needed {
@@ -175,15 +357,201 @@ pub(super) fn gen_function(
         #doc_attr
         #vis #bridge_unsafety fn #cxxbridge_name #lifetime_tokens ( #params ) #ret_type;
     ));
+    let mut extern_c_mod_items = vec![extern_c_mod_item];
+    if let Some(owned_return) = analysis.owned_return {
+        // Declare the synthesized C++ function which frees the returned
+        // pointer.
+        let deleter_name = &owned_return.deleter.wrapper_function_name;
+        let pointee = owned_return.pointee;
+        let deleter_params = unqualify_params(parse_quote! { arg0: *mut #pointee });
+        extern_c_mod_items.push(ForeignItem::Fn(parse_quote!(
+            unsafe fn #deleter_name ( #deleter_params );
+        )));
+    }
+    if let Some(unique_array_return) = analysis.unique_array_return {
+        // Declare the synthesized C++ function which deletes the returned
+        // array.
+        let deleter_name = &unique_array_return.deleter.wrapper_function_name;
+        let elem = unique_array_return.elem;
+        let deleter_params = unqualify_params(parse_quote! { arg0: *mut #elem });
+        extern_c_mod_items.push(ForeignItem::Fn(parse_quote!(
+            unsafe fn #deleter_name ( #deleter_params );
+        )));
+    }
+    if let Some(resource_return) = analysis.resource_return {
+        // Declare the synthesized C++ function which releases the
+        // returned handle.
+        let close_name = &resource_return.close.wrapper_function_name;
+        let handle = resource_return.handle;
+        let close_params = unqualify_params(parse_quote! { arg0: #handle });
+        extern_c_mod_items.push(ForeignItem::Fn(parse_quote!(
+            unsafe fn #close_name ( #close_params );
+        )));
+    }
+    // The struct into which C++ moves the elements of a returned tuple.
+    let bridge_items = match (&analysis.tuple_return, &analysis.cpp_wrapper) {
+        (
+            Some(elements),
+            Some(CppFunction {
+                payload: CppFunctionBody::TupleReturn(_, _, tuple, _),
+                ..
+            }),
+        ) => {
+            let fields = tuple_fields(elements.len());
+            let types = elements.iter().map(|element| {
+                match unqualify_ret_type(ReturnType::Type(
+                    Default::default(),
+                    Box::new(element.unconverted_rust_type()),
+                )) {
+                    ReturnType::Type(_, ty) => ty,
+                    ReturnType::Default => unreachable!(),
+                }
+            });
+            vec![parse_quote! {
+                pub struct #tuple {
+                    #(pub #fields: #types,)*
+                }
+            }]
+        }
+        _ => Vec::new(),
+    };
     RsCodegenResult {
-        extern_c_mod_items: vec![extern_c_mod_item],
+        extern_c_mod_items,
+        bridge_items,
         impl_entry,
         trait_impl_entry,
-        materializations: materialization.into_iter().collect(),
+        bindgen_mod_items,
+        materializations: materialization
+            .into_iter()
+            .chain(materialization_extras)
+            .collect(),
         ..Default::default()
     }
 }
 
+/// Whether a reference returned by a function with these parameters would
+/// borrow from one of them by lifetime elision: that is, whether it's a
+/// method, or has exactly one parameter which borrows.
+fn has_single_borrowed_param(params: &Punctuated<FnArg, Comma>) -> bool {
+    let mut borrows = 0;
+    for param in params {
+        match param {
+            FnArg::Receiver(_) => return true,
+            FnArg::Typed(PatType { pat, .. }) if matches!(pat.as_ref(), Pat::Ident(pp) if pp.ident == "self") => {
+                return true
+            }
+            FnArg::Typed(PatType { ty, .. }) => borrows += count_borrows(ty.to_token_stream()),
+        }
+    }
+    borrows == 1
+}
+
+fn count_borrows(tokens: TokenStream) -> usize {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Punct(p) if p.as_char() == '&' => 1,
+            TokenTree::Group(g) => count_borrows(g.stream()),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Where a token goes, for the purposes of laying out an expression as a
+/// person would write it.
+#[derive(Clone, Copy, PartialEq)]
+enum TokenPlace {
+    /// Nothing yet, or an opening bracket.
+    Start,
+    /// Something like an identifier, which is followed by a space unless
+    /// it's called or indexed.
+    Operand,
+    /// A binary operator or comma, which is followed by a space.
+    Operator,
+    /// A unary operator, `.` or `::`, which is followed by nothing.
+    Prefix,
+}
+
+/// Writes out an expression readably, for messages, rather than with a
+/// space between every token as `TokenStream`'s `Display` does:
+/// `*args.1 != 0` rather than `* args . 1 != 0`.
+fn readable_expr(tokens: TokenStream) -> String {
+    let mut text = String::new();
+    let mut place = TokenPlace::Start;
+    let mut joined = false;
+    // How many turbofish `::<` we're within.
+    let mut generic_depth = 0;
+    for tt in tokens {
+        let (space_before, new_place) = match &tt {
+            // As in `&mut x`.
+            TokenTree::Ident(ident) if ident == "mut" => (false, TokenPlace::Operator),
+            TokenTree::Ident(_) | TokenTree::Literal(_) => (
+                matches!(place, TokenPlace::Operand | TokenPlace::Operator),
+                TokenPlace::Operand,
+            ),
+            TokenTree::Group(group) => (
+                place == TokenPlace::Operator
+                    || (place == TokenPlace::Operand && group.delimiter() == Delimiter::Brace),
+                TokenPlace::Operand,
+            ),
+            TokenTree::Punct(punct) => {
+                let ch = punct.as_char();
+                let joint = punct.spacing() == Spacing::Joint;
+                if ch == '<' && text.ends_with("::") {
+                    generic_depth += 1;
+                    (false, TokenPlace::Prefix)
+                } else if joined {
+                    (false, place)
+                } else if ch == '>' && generic_depth > 0 {
+                    generic_depth -= 1;
+                    (false, TokenPlace::Operand)
+                } else if ch == '.' || (ch == ':' && joint) {
+                    (false, TokenPlace::Prefix)
+                } else if ch == ',' || ch == ';' {
+                    (false, TokenPlace::Operator)
+                } else if ch == '?' {
+                    (false, TokenPlace::Operand)
+                } else if matches!(ch, '*' | '&' | '-' | '!') && place != TokenPlace::Operand {
+                    (place == TokenPlace::Operator, TokenPlace::Prefix)
+                } else {
+                    (place != TokenPlace::Start, TokenPlace::Operator)
+                }
+            }
+        };
+        if space_before {
+            text.push(' ');
+        }
+        match tt {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                text.push_str(open);
+                text.push_str(&readable_expr(group.stream()));
+                text.push_str(close);
+            }
+            TokenTree::Punct(punct) => {
+                joined = punct.spacing() == Spacing::Joint;
+                text.push(punct.as_char());
+            }
+            tt => {
+                joined = false;
+                text.push_str(&tt.to_string());
+            }
+        }
+        place = new_place;
+    }
+    text
+}
+
+/// The names of the fields of the struct holding a returned tuple.
+fn tuple_fields(len: usize) -> impl Iterator<Item = Ident> {
+    (0..len).map(|idx| make_ident(format!("f{}", idx)))
+}
+
 /// Knows how to generate a given function.
 #[derive(Clone)]
 struct FnGenerator<'a> {
@@ -192,20 +560,140 @@ struct FnGenerator<'a> {
     rust_name: &'a str,
     unsafety: &'a Option<Unsafe>,
     doc_attr: &'a Option<Attribute>,
+    trace_name: &'a Option<String>,
+    exceptions_as_panics: bool,
+    bridge_only_unsafe: bool,
+    ret_conversion: Option<&'a TypeConversionPolicy>,
+    error_code: bool,
+    return_lifetime_from: Option<usize>,
+    preconditions: &'a [ExprClosure],
 }
 
 impl<'a> FnGenerator<'a> {
+    /// A statement to log a call into C++, if tracing was requested.
+    fn trace_call(&self) -> Option<TokenStream> {
+        self.trace_name.as_ref().map(|trace_name| {
+            quote! {
+                autocxx::trace_ffi_call!(#trace_name);
+            }
+        })
+    }
+
+    /// Statements which check, in debug builds, the preconditions given in
+    /// `precondition!`. Each is passed a tuple of references to the
+    /// arguments.
+    fn precondition_checks(&self, wrapper_params: &Punctuated<FnArg, Comma>) -> Vec<TokenStream> {
+        let args: Vec<_> = wrapper_params
+            .iter()
+            .filter_map(|param| match param {
+                FnArg::Typed(PatType { pat, .. }) => match pat.as_ref() {
+                    Pat::Ident(pat) => Some(&pat.ident),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+        self.preconditions
+            .iter()
+            .map(|predicate| {
+                let message = format!(
+                    "precondition of {} violated: {}",
+                    self.rust_name,
+                    readable_expr(predicate.body.to_token_stream())
+                );
+                quote! {
+                    #[cfg(debug_assertions)]
+                    autocxx::check_precondition((#(&#args,)*), #predicate, #message);
+                }
+            })
+            .collect()
+    }
+
+    /// The call through to the cxx::bridge function. If C++ exceptions are
+    /// to become panics, that function returns a `Result`, which we unwrap.
+    /// Its return value may need converting too, for instance into a
+    /// `PathBuf`. If the function reports failure through a
+    /// `std::error_code`, the `Result` instead becomes a `std::io::Result`.
+    fn cxxbridge_call(&self, args: TokenStream) -> TokenStream {
+        let cxxbridge_name = self.cxxbridge_name;
+        let call = quote! { cxxbridge::#cxxbridge_name ( #args ) };
+        if self.error_code {
+            let call = quote! { autocxx::error_code::into_io_result(#call) };
+            return match self.ret_conversion {
+                Some(ret_conversion) => {
+                    let conversion = ret_conversion.rust_return_conversion(quote! { ret });
+                    quote! { #call.map(|ret| #conversion) }
+                }
+                None => call,
+            };
+        }
+        let call = if self.exceptions_as_panics {
+            quote! { autocxx::unwrap_cpp_exception(#call) }
+        } else {
+            call
+        };
+        match self.ret_conversion {
+            Some(ret_conversion) => ret_conversion.rust_return_conversion(call),
+            None => call,
+        }
+    }
+
+    /// If the cxx::bridge function is unsafe only because of pointers which
+    /// our wrapper already makes safe (for instance, those passed as
+    /// slices), the wrapper needs an `unsafe` block.
+    fn unsafe_block_if_only_bridge_is_unsafe(&self, call: TokenStream) -> TokenStream {
+        if self.bridge_only_unsafe {
+            quote! { unsafe { #call } }
+        } else {
+            call
+        }
+    }
+
+    fn wrapper_arg_name(pd: &ArgumentAnalysis, avoid_self: bool) -> Pat {
+        if pd.self_type.is_some() && !avoid_self {
+            parse_quote!(self)
+        } else {
+            pd.name.clone()
+        }
+    }
+
+    /// The wrapper parameter from which the returned reference borrows,
+    /// if `lifetime!` said so.
+    fn lifetime_source(&self, avoid_self: bool) -> Option<Ident> {
+        let pd = &self.param_details[self.return_lifetime_from?];
+        match Self::wrapper_arg_name(pd, avoid_self) {
+            Pat::Ident(pp) => Some(pp.ident),
+            _ => None,
+        }
+    }
+
     fn generate_arg_lists(&self, avoid_self: bool) -> (Punctuated<FnArg, Comma>, Vec<TokenStream>) {
         let mut wrapper_params: Punctuated<FnArg, Comma> = Punctuated::new();
         let mut arg_list = Vec::new();
 
         for pd in self.param_details {
+            if let RustConversionType::FromSliceLen(_) = pd.conversion.rust_conversion {
+                // Taken from the slice instead.
+                arg_list.push(pd.conversion.rust_conversion(pd.name.clone()));
+                continue;
+            }
+            if let RustConversionType::FromClosureContext(_) = pd.conversion.rust_conversion {
+                // Taken from the closure's handle instead.
+                arg_list.push(pd.conversion.rust_conversion(pd.name.clone()));
+                continue;
+            }
+            if let RustConversionType::FromOutParam = pd.conversion.rust_conversion {
+                // A local which the wrapper returns.
+                arg_list.push(pd.conversion.rust_conversion(pd.name.clone()));
+                continue;
+            }
+            if let RustConversionType::FromResolvedSymbol(..) = pd.conversion.rust_conversion {
+                // Found by the resolver instead.
+                arg_list.push(pd.conversion.rust_conversion(pd.name.clone()));
+                continue;
+            }
             let type_name = pd.conversion.rust_wrapper_unconverted_type();
-            let wrapper_arg_name = if pd.self_type.is_some() && !avoid_self {
-                parse_quote!(self)
-            } else {
-                pd.name.clone()
-            };
+            let wrapper_arg_name = Self::wrapper_arg_name(pd, avoid_self);
             let param_mutability = pd.conversion.rust_conversion.requires_mutability();
             wrapper_params.push(parse_quote!(
                 #param_mutability #wrapper_arg_name: #type_name
@@ -223,17 +711,24 @@ impl<'a> FnGenerator<'a> {
         ret_type: &ReturnType,
     ) -> Box<ImplBlockDetails> {
         let (wrapper_params, arg_list) = self.generate_arg_lists(avoid_self);
-        let (lifetime_tokens, wrapper_params, ret_type) =
-            add_explicit_lifetime_if_necessary(self.param_details, wrapper_params, ret_type);
+        let (lifetime_tokens, wrapper_params, ret_type) = add_explicit_lifetime_if_necessary(
+            self.param_details,
+            self.lifetime_source(avoid_self).as_ref(),
+            wrapper_params,
+            ret_type,
+        );
         let rust_name = make_ident(self.rust_name);
         let unsafety = self.unsafety;
         let doc_attr = self.doc_attr;
-        let cxxbridge_name = self.cxxbridge_name;
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let trace_call = self.trace_call();
         Box::new(ImplBlockDetails {
             item: ImplItem::Method(parse_quote! {
                 #doc_attr
                 pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
-                    cxxbridge::#cxxbridge_name ( #(#arg_list),* )
+                    #trace_call
+                    #call
                 }
             }),
             ty: impl_block_type_name.get_final_ident(),
@@ -241,24 +736,53 @@ impl<'a> FnGenerator<'a> {
     }
 
     /// Generate an 'impl Trait for Type { methods-go-here }' in its entrety.
+    /// If `pointer_as_option` is set, the returned pointer, which may be
+    /// null, is instead returned as an `Option` of a reference.
     fn generate_trait_impl(
         &self,
         details: &TraitMethodDetails,
         ret_type: &ReturnType,
+        pointer_as_option: bool,
     ) -> Box<TraitImplBlockDetails> {
         let (mut wrapper_params, arg_list) = self.generate_arg_lists(details.avoid_self);
         if let Some(parameter_reordering) = &details.parameter_reordering {
             wrapper_params = Self::reorder_parameters(wrapper_params, parameter_reordering);
         }
-        let (lifetime_tokens, wrapper_params, ret_type) =
-            add_explicit_lifetime_if_necessary(self.param_details, wrapper_params, ret_type);
+        let (lifetime_tokens, wrapper_params, ret_type) = add_explicit_lifetime_if_necessary(
+            self.param_details,
+            self.lifetime_source(details.avoid_self).as_ref(),
+            wrapper_params,
+            ret_type,
+        );
         let doc_attr = self.doc_attr;
         let unsafety = self.unsafety;
-        let cxxbridge_name = self.cxxbridge_name;
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
         let key = details.trt.clone();
         let method_name = &details.method_name;
         let call_body = quote! {
-            cxxbridge::#cxxbridge_name ( #(#arg_list),* )
+            #call
+        };
+        let (ret_type, call_body) = match ret_type.as_ref() {
+            ReturnType::Type(_, ty) if pointer_as_option => match ty.as_ref() {
+                Type::Ptr(TypePtr {
+                    mutability: Some(_),
+                    elem,
+                    ..
+                }) => (
+                    Cow::Owned(parse_quote! { -> Option<::std::pin::Pin<&mut #elem>> }),
+                    quote! {
+                        unsafe { #call_body.as_mut().map(|r| ::std::pin::Pin::new_unchecked(r)) }
+                    },
+                ),
+                Type::Ptr(TypePtr { elem, .. }) => (
+                    Cow::Owned(parse_quote! { -> Option<&#elem> }),
+                    quote! {
+                        unsafe { #call_body.as_ref() }
+                    },
+                ),
+                _ => (ret_type, call_body),
+            },
+            _ => (ret_type, call_body),
         };
         let call_body = if details.trait_call_is_unsafe {
             quote! {
@@ -269,13 +793,18 @@ impl<'a> FnGenerator<'a> {
         } else {
             call_body
         };
+        let trace_call = self.trace_call();
         let item = parse_quote! {
             #doc_attr
             #unsafety fn #method_name #lifetime_tokens ( #wrapper_params ) #ret_type {
+                #trace_call
                 #call_body
             }
         };
-        Box::new(TraitImplBlockDetails { item, key })
+        Box::new(TraitImplBlockDetails {
+            items: vec![item],
+            key,
+        })
     }
 
     /// Generate a 'impl Type { methods-go-here }' item which is a constructor
@@ -296,11 +825,13 @@ impl<'a> FnGenerator<'a> {
         } else {
             (quote! {}, quote! {})
         };
-        let cxxbridge_name = self.cxxbridge_name;
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let trace_call = self.trace_call();
         let body = quote! {
             autocxx::moveit::new::by_raw(move |#ptr_arg_name| {
+                #trace_call
                 let #ptr_arg_name = #ptr_arg_name.get_unchecked_mut().as_mut_ptr();
-                cxxbridge::#cxxbridge_name(#(#arg_list),* )
+                #call
             })
         };
         let body = if self.unsafety.is_some() {
@@ -324,17 +855,687 @@ impl<'a> FnGenerator<'a> {
         })
     }
 
+    /// Generate a function call wrapper which returns a `New` which
+    /// constructs the returned object in place.
+    fn generate_emplace_return_function_impl(&self, emplaced: &Type) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let mut wrapper_params: Punctuated<FnArg, Comma> =
+            wrapper_params.into_iter().skip(1).collect();
+        let ptr_arg_name = &arg_list[0];
+        let rust_name = make_ident(self.rust_name);
+        let any_references = self.param_details.iter().any(|pd| pd.was_reference);
+        let (lifetime_param, lifetime_addition) = if any_references {
+            add_lifetime_to_all_params(&mut wrapper_params);
+            (quote! { <'a> }, quote! { + 'a })
+        } else {
+            (quote! {}, quote! {})
+        };
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let body = quote! {
+            autocxx::moveit::new::by_raw(move |#ptr_arg_name| {
+                #trace_call
+                let #ptr_arg_name = #ptr_arg_name.get_unchecked_mut().as_mut_ptr();
+                #call
+            })
+        };
+        let body = if self.unsafety.is_some() {
+            body
+        } else {
+            quote! {
+                unsafe { #body }
+            }
+        };
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name #lifetime_param ( #wrapper_params ) -> impl autocxx::moveit::new::New<Output = #emplaced> #lifetime_addition {
+                #(#precondition_checks)*
+                #body
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which moves the fields of the
+    /// struct returned by the C++ wrapper into a tuple.
+    fn generate_tuple_return_function_impl(&self, elements: &[TypeConversionPolicy]) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let types = elements
+            .iter()
+            .map(|element| element.unconverted_rust_type());
+        let body = if elements.is_empty() {
+            call
+        } else {
+            let fields = tuple_fields(elements.len());
+            quote! {
+                let ret = #call;
+                ( #(ret.#fields,)* )
+            }
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> ( #(#types,)* ) {
+                #trace_call
+                #(#precondition_checks)*
+                #body
+            }
+        }))
+    }
+
     /// Generate a function call wrapper
     fn generate_function_impl(&self, ret_type: &ReturnType) -> Box<Item> {
         let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let (lifetime_tokens, wrapper_params, ret_type) = add_explicit_lifetime_if_necessary(
+            self.param_details,
+            self.lifetime_source(false).as_ref(),
+            wrapper_params,
+            ret_type,
+        );
         let rust_name = make_ident(self.rust_name);
-        let cxxbridge_name = self.cxxbridge_name;
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
         let doc_attr = self.doc_attr;
         let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
+                #trace_call
+                #(#precondition_checks)*
+                #call
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which takes ownership of the
+    /// returned pointer.
+    fn generate_owned_return_function_impl(&self, owned_return: &OwnedReturn) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let pointee = &owned_return.pointee;
+        let deleter_name = &owned_return.deleter.wrapper_function_name;
+        let cast = if owned_return.is_const {
+            Some(quote! { as *mut #pointee })
+        } else {
+            None
+        };
+        let body = quote! {
+            autocxx::OwnedPtr::from_raw(
+                #call #cast,
+                cxxbridge::#deleter_name,
+            )
+        };
+        let body = if unsafety.is_some() {
+            body
+        } else {
+            quote! {
+                unsafe { #body }
+            }
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> autocxx::OwnedPtr<#pointee> {
+                #trace_call
+                #(#precondition_checks)*
+                #body
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which takes ownership of the
+    /// returned array, along with its length.
+    fn generate_unique_array_return_function_impl(
+        &self,
+        unique_array_return: &UniqueArrayReturn,
+    ) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let elem = &unique_array_return.elem;
+        let deleter_name = &unique_array_return.deleter.wrapper_function_name;
+        let len = &unique_array_return.len;
+        // If the function fills in the length, we provide somewhere for it
+        // to do so.
+        let len_decl = unique_array_return.len_out.as_ref().map(|len_ty| {
+            quote! { let mut #len: #len_ty = Default::default(); }
+        });
+        let len_value = quote! { #len };
+        let len_value = if unique_array_return.len_is_ctype {
+            quote! { #len_value.0 }
+        } else {
+            len_value
+        };
+        let owned = quote! {
+            autocxx::UniqueArray::from_raw(
+                ptr,
+                ::std::convert::TryInto::try_into(#len_value).expect("array length out of range"),
+                cxxbridge::#deleter_name,
+            )
+        };
+        let owned = if unsafety.is_some() {
+            owned
+        } else {
+            quote! {
+                unsafe { #owned }
+            }
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> autocxx::UniqueArray<#elem> {
+                #trace_call
+                #(#precondition_checks)*
+                #len_decl
+                let ptr = #call;
+                #owned
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which returns the calling thread's
+    /// instance of a `thread_local` variable, given the function which
+    /// returns its address.
+    fn generate_thread_local_function_impl(&self, ret_type: &ReturnType) -> Box<Item> {
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! {});
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let var_type = match ret_type {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Ptr(ptr) => ptr.elem.as_ref().clone(),
+                ty => ty.clone(),
+            },
+            ReturnType::Default => parse_quote! { () },
+        };
+        let from_raw = quote! {
+            autocxx::ThreadLocalRef::from_raw(ptr as *mut #var_type)
+        };
+        let from_raw = if unsafety.is_some() {
+            from_raw
+        } else {
+            quote! {
+                unsafe { #from_raw }
+            }
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name () -> autocxx::ThreadLocalRef<#var_type> {
+                #trace_call
+                let ptr = #call;
+                #from_raw
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which borrows the returned array,
+    /// finding its length as the function was told in `array_return!`, or
+    /// by looking for a null element.
+    fn generate_array_return_function_impl(&self, array_return: &ArrayReturn) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        // The array borrows from the function's only borrowed parameter,
+        // if it has exactly one. Otherwise Rust can't know how long the
+        // array lasts, so the caller must promise not to use it once C++
+        // has changed or freed it.
+        let borrows_from_param = has_single_borrowed_param(&wrapper_params);
+        let (unsafety, safety_doc) = match self.unsafety {
+            None if !borrows_from_param => (
+                Some(parse_quote! { unsafe }),
+                Some(quote! {
+                    #[doc = ""]
+                    #[doc = " # Safety"]
+                    #[doc = ""]
+                    #[doc = " The returned array still belongs to C++. It mustn't be used once C++ has changed or freed it."]
+                }),
+            ),
+            unsafety => (*unsafety, None),
+        };
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = if safety_doc.is_some() {
+            call
+        } else {
+            self.unsafe_block_if_only_bridge_is_unsafe(call)
+        };
+        let doc_attr = self.doc_attr;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let elem = &array_return.elem;
+        let lifetime = if borrows_from_param {
+            None
+        } else {
+            Some(quote! { 'static })
+        };
+        // If the function fills in the length, we provide somewhere for it
+        // to do so.
+        let len_decl = match &array_return.len {
+            ArrayLength::Param {
+                name,
+                out: Some(len_ty),
+            } => Some(quote! { let mut #name: #len_ty = Default::default(); }),
+            _ => None,
+        };
+        let (ret_type, borrowed) = match &array_return.len {
+            ArrayLength::NullTerminated { strings: true } => (
+                quote! { Vec<&#lifetime ::std::ffi::CStr> },
+                quote! {
+                    autocxx::array_return::borrow_null_terminated_strings(
+                        ptr as *const *const ::std::os::raw::c_char,
+                    )
+                },
+            ),
+            ArrayLength::NullTerminated { strings: false } => (
+                quote! { Vec<&#lifetime #elem> },
+                quote! {
+                    autocxx::array_return::borrow_null_terminated(ptr as *const *const #elem)
+                },
+            ),
+            ArrayLength::Param { name, .. } => (
+                quote! { &#lifetime [#elem] },
+                quote! {
+                    autocxx::array_return::borrow_array(
+                        ptr as *const #elem,
+                        autocxx::array_return::ArrayLength::array_length(#name),
+                    )
+                },
+            ),
+            ArrayLength::Function(len_fn) => (
+                quote! { &#lifetime [#elem] },
+                quote! {
+                    autocxx::array_return::borrow_array(
+                        ptr as *const #elem,
+                        autocxx::array_return::ArrayLength::array_length({
+                            #[allow(unused_unsafe)]
+                            let len = unsafe { #len_fn() };
+                            len
+                        }),
+                    )
+                },
+            ),
+        };
+        let borrowed = if unsafety.is_some() {
+            borrowed
+        } else {
+            quote! {
+                unsafe { #borrowed }
+            }
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            #safety_doc
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> #ret_type {
+                #trace_call
+                #(#precondition_checks)*
+                #len_decl
+                let ptr = #call;
+                #borrowed
+            }
+        }))
+    }
+
+    /// Generate an extra `impl Type` block containing a `get` method,
+    /// which calls this `at` method only if the index is less than
+    /// `size()`, as requested by `checked_index!`.
+    fn generate_checked_index_impl(
+        &self,
+        impl_block_type_name: &QualifiedName,
+        ret_type: &ReturnType,
+    ) -> Item {
+        let (wrapper_params, _) = self.generate_arg_lists(false);
+        let index = Self::wrapper_arg_name(&self.param_details[1], false);
+        let ret_type = match ret_type {
+            ReturnType::Type(_, ty) => ty.as_ref().clone(),
+            ReturnType::Default => parse_quote! { () },
+        };
+        let ty = impl_block_type_name.get_final_ident();
+        let rust_name = make_ident(self.rust_name);
+        let unsafety = self.unsafety;
+        let doc = format!(
+            " Like [`{}`](Self::{}), but returns `None` rather than calling into\n \
+            C++ if the index is out of range, checking it against `size()`. The\n \
+            check and the access aren't atomic, so nothing else may change the\n \
+            size in between.",
+            self.rust_name, self.rust_name
+        );
+        parse_quote! {
+            impl #ty {
+                #[doc = #doc]
+                pub #unsafety fn get( #wrapper_params ) -> Option<#ret_type> {
+                    if #index < self.size() {
+                        Some(self.#rust_name(#index))
+                    } else {
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generate an implementation of `autocxx::Awaitable` which gets its
+    /// result from this method, as requested by `register_awaitable!`.
+    fn generate_awaitable_impl(
+        &self,
+        impl_block_type_name: &QualifiedName,
+        ret_type: &ReturnType,
+        awaitable: &AwaitableMethods,
+    ) -> Item {
+        let ret_type = match ret_type {
+            ReturnType::Type(_, ty) => ty.as_ref().clone(),
+            ReturnType::Default => parse_quote! { () },
+        };
+        let ty = impl_block_type_name.get_final_ident();
+        let rust_name = make_ident(self.rust_name);
+        let AwaitableMethods { resume, done } = awaitable;
+        let wrap_call = |call: TokenStream| match self.unsafety {
+            Some(_) => quote! { unsafe { #call } },
+            None => call,
+        };
+        let resume_call = wrap_call(quote! { self.#resume() });
+        let done_call = wrap_call(quote! { self.#done() });
+        let result_call = wrap_call(quote! { self.#rust_name() });
+        parse_quote! {
+            impl autocxx::Awaitable for #ty {
+                type Output = #ret_type;
+                fn resume(self: ::std::pin::Pin<&mut Self>) {
+                    #resume_call
+                }
+                fn is_done(&self) -> bool {
+                    #done_call
+                }
+                fn result(self: ::std::pin::Pin<&mut Self>) -> #ret_type {
+                    #result_call
+                }
+            }
+        }
+    }
+
+    /// Generate a function call wrapper which checks whether the returned
+    /// `UniquePtr` is null, as requested by `non_null_return!` or
+    /// `nullable_return!`.
+    fn generate_null_checked_return_function_impl(
+        &self,
+        ret_type: &ReturnType,
+        null_check: NullCheck,
+    ) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let ty = match ret_type {
+            ReturnType::Type(_, ty) => ty.as_ref(),
+            ReturnType::Default => panic!("Expected a UniquePtr return type"),
+        };
+        let (ret_type, check): (Type, _) = match null_check {
+            NullCheck::Panic => {
+                let message = format!("{} returned a null pointer", self.rust_name);
+                (
+                    ty.clone(),
+                    quote! {
+                        if ret.is_null() {
+                            panic!(#message);
+                        }
+                        ret
+                    },
+                )
+            }
+            NullCheck::Option => (
+                parse_quote! { Option<#ty> },
+                quote! {
+                    if ret.is_null() {
+                        None
+                    } else {
+                        Some(ret)
+                    }
+                },
+            ),
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> #ret_type {
+                #trace_call
+                #(#precondition_checks)*
+                let ret = #call;
+                #check
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which returns a `NonZero` integer.
+    fn generate_nonzero_return_function_impl(&self, nonzero: &Type) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let message = format!("{} returned zero", self.rust_name);
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> #nonzero {
+                #trace_call
+                #(#precondition_checks)*
+                let ret = #call;
+                #nonzero::new(ret).expect(#message)
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which returns the pointer filled
+    /// in by an out parameter, owning it if `owned_return` says so.
+    fn generate_out_param_function_impl(
+        &self,
+        ret_type: &ReturnType,
+        out_param: &OutParam,
+        owned_return: Option<&OwnedReturn>,
+    ) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let out_name = &out_param.name;
+        let pointee = &out_param.pointee;
+        let (out_ptr_type, null) = if out_param.is_const {
+            (quote! { *const #pointee }, quote! { ::std::ptr::null() })
+        } else {
+            (quote! { *mut #pointee }, quote! { ::std::ptr::null_mut() })
+        };
+        let (out_type, out_value) = match owned_return {
+            Some(owned_return) => {
+                let deleter_name = &owned_return.deleter.wrapper_function_name;
+                let cast = if out_param.is_const {
+                    Some(quote! { as *mut #pointee })
+                } else {
+                    None
+                };
+                let owned = quote! {
+                    autocxx::OwnedPtr::from_raw(#out_name #cast, cxxbridge::#deleter_name)
+                };
+                let owned = if unsafety.is_some() {
+                    owned
+                } else {
+                    quote! {
+                        unsafe { #owned }
+                    }
+                };
+                (quote! { autocxx::OwnedPtr<#pointee> }, owned)
+            }
+            None => (out_ptr_type.clone(), quote! { #out_name }),
+        };
+        let (ret_type, body) = match ret_type {
+            ReturnType::Default => (
+                quote! { -> #out_type },
+                quote! {
+                    #call;
+                    #out_value
+                },
+            ),
+            ReturnType::Type(_, ty) => (
+                quote! { -> (#ty, #out_type) },
+                quote! {
+                    let ret = #call;
+                    (ret, #out_value)
+                },
+            ),
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) #ret_type {
+                #trace_call
+                #(#precondition_checks)*
+                let mut #out_name: #out_ptr_type = #null;
+                #body
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which takes a closure in place of
+    /// a callback and its context, returning the closure's handle too.
+    fn generate_closure_callback_function_impl(
+        &self,
+        ret_type: &ReturnType,
+        closure_callback: &Ident,
+    ) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let call = self.unsafe_block_if_only_bridge_is_unsafe(call);
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let closure_pd = self
+            .param_details
+            .iter()
+            .find(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == *closure_callback))
+            .expect("Closure parameter not found");
+        let closure_to_trampoline = closure_pd
+            .conversion
+            .closure_to_trampoline(&closure_pd.name);
+        let (ret_type, body) = match ret_type {
+            ReturnType::Default => (
+                quote! { -> autocxx::callback::CallbackHandle },
+                quote! {
+                    #call;
+                    #closure_callback.1
+                },
+            ),
+            ReturnType::Type(_, ty) => (
+                quote! { -> (#ty, autocxx::callback::CallbackHandle) },
+                quote! {
+                    let ret = #call;
+                    (ret, #closure_callback.1)
+                },
+            ),
+        };
         Box::new(Item::Fn(parse_quote! {
             #doc_attr
             pub #unsafety fn #rust_name ( #wrapper_params ) #ret_type {
-                cxxbridge::#cxxbridge_name ( #(#arg_list),* )
+                #trace_call
+                #(#precondition_checks)*
+                #closure_to_trampoline
+                #body
+            }
+        }))
+    }
+
+    /// Generate a function call wrapper which takes ownership of the
+    /// returned handle.
+    fn generate_resource_return_function_impl(
+        &self,
+        resource_return: &ResourceReturn,
+    ) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        let rust_name = make_ident(self.rust_name);
+        let call = self.cxxbridge_call(quote! { #(#arg_list),* });
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let precondition_checks = self.precondition_checks(&wrapper_params);
+        let handle = &resource_return.handle;
+        let close_name = &resource_return.close.wrapper_function_name;
+        let body = quote! {
+            autocxx::OwnedHandle::from_raw(#call, cxxbridge::#close_name)
+        };
+        let body = if unsafety.is_some() {
+            body
+        } else {
+            quote! {
+                unsafe { #body }
+            }
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #rust_name ( #wrapper_params ) -> autocxx::OwnedHandle<#handle> {
+                #trace_call
+                #(#precondition_checks)*
+                #body
+            }
+        }))
+    }
+
+    /// Generate a variant of a function call wrapper which runs the call
+    /// using an `autocxx::offload::Spawner` and returns a future.
+    fn generate_offload_impl(&self, ret_type: &ReturnType) -> Box<Item> {
+        let (wrapper_params, arg_list) = self.generate_arg_lists(false);
+        // Do any parameter conversions on the calling thread, so that only
+        // the converted values need to be sent to the other thread.
+        let conversions = self
+            .param_details
+            .iter()
+            .zip(arg_list.iter())
+            .filter(|(pd, _)| pd.conversion.rust_work_needed())
+            .map(|(pd, conversion)| {
+                let name = &pd.name;
+                quote! { let #name = #conversion; }
+            });
+        let arg_names = self.param_details.iter().map(|pd| &pd.name);
+        let offloaded_name = make_ident(format!("{}_offloaded", self.rust_name));
+        let call = self.cxxbridge_call(quote! { #(#arg_names),* });
+        let doc_attr = self.doc_attr;
+        let unsafety = self.unsafety;
+        let trace_call = self.trace_call();
+        let output_type = match ret_type {
+            ReturnType::Default => quote! { () },
+            ReturnType::Type(_, ty) => quote! { #ty },
+        };
+        Box::new(Item::Fn(parse_quote! {
+            #doc_attr
+            pub #unsafety fn #offloaded_name ( spawner: impl autocxx::offload::Spawner, #wrapper_params ) -> autocxx::offload::Offloaded<#output_type> {
+                #(#conversions)*
+                autocxx::offload::offload(spawner, move || {
+                    #trace_call
+                    #call
+                })
             }
         }))
     }
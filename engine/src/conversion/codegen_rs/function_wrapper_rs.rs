@@ -12,11 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use autocxx_parser::StringReturn;
 use proc_macro2::TokenStream;
-use syn::{Pat, Type, TypePtr};
+use syn::{Pat, Type, TypeBareFn, TypePtr, TypeReference};
 
-use crate::conversion::analysis::fun::function_wrapper::{
-    RustConversionType, TypeConversionPolicy,
+use crate::{
+    conversion::analysis::fun::function_wrapper::{
+        bitset_type, callback_context_index, RustConversionType, TypeConversionPolicy,
+    },
+    known_types::known_types,
+    types::{make_ident, QualifiedName},
 };
 use quote::quote;
 use syn::parse_quote;
@@ -57,6 +62,60 @@ impl TypeConversionPolicy {
                 };
                 parse_quote! { &mut #ty }
             }
+            RustConversionType::FromSliceToPtr => {
+                let (elem, mutability) = match &self.unwrapped_type {
+                    Type::Ptr(TypePtr {
+                        elem, mutability, ..
+                    }) => (slice_element_type(elem), mutability),
+                    _ => panic!("Not a ptr"),
+                };
+                parse_quote! { & #mutability [ #elem ] }
+            }
+            RustConversionType::FromSliceLen(_) => {
+                panic!("Slice lengths are not wrapper parameters")
+            }
+            RustConversionType::FromCStrArg => parse_quote! { impl autocxx::CStrArg },
+            RustConversionType::FromOutParam => {
+                panic!("Out parameters are not wrapper parameters")
+            }
+            RustConversionType::FromPath => parse_quote! { &::std::path::Path },
+            RustConversionType::ToPathBuf => parse_quote! { ::std::path::PathBuf },
+            RustConversionType::FromBitSet(width) | RustConversionType::ToBitSet(width) => {
+                bitset_type(width)
+            }
+            RustConversionType::FromClosureToFnPtr
+            | RustConversionType::FromClosureToStdFunction => {
+                let closure_bound = self.closure_bound();
+                parse_quote! { impl #closure_bound }
+            }
+            RustConversionType::FromClosureContext(_) => {
+                panic!("Closure contexts are not wrapper parameters")
+            }
+            RustConversionType::FromResolvedSymbol(..) => {
+                panic!("Resolved symbols are not wrapper parameters")
+            }
+            RustConversionType::FromArrayToPtr(len) => {
+                let (elem, mutability) = match &self.unwrapped_type {
+                    Type::Ptr(TypePtr {
+                        elem, mutability, ..
+                    }) => (elem, mutability),
+                    _ => panic!("Not a ptr"),
+                };
+                let len = proc_macro2::Literal::usize_unsuffixed(len);
+                parse_quote! { & #mutability [ #elem; #len ] }
+            }
+            RustConversionType::FromPromotable => {
+                let ty = &self.unwrapped_type;
+                parse_quote! { impl autocxx::PromotesTo<#ty> }
+            }
+            RustConversionType::FromConvertedType(ref path, true) => parse_quote! { &#path },
+            RustConversionType::FromConvertedType(ref path, false)
+            | RustConversionType::ToConvertedType(ref path) => parse_quote! { #path },
+            RustConversionType::ToString(StringReturn::Lossy) => parse_quote! { String },
+            RustConversionType::ToString(StringReturn::Strict) => parse_quote! {
+                ::std::result::Result<String, ::std::string::FromUtf8Error>
+            },
+            RustConversionType::ToCStr => parse_quote! { &::std::ffi::CStr },
         }
     }
 
@@ -81,6 +140,228 @@ impl TypeConversionPolicy {
             RustConversionType::FromTypeToPtr => quote! {
                 #var
             },
+            RustConversionType::FromSliceToPtr => {
+                let ty = &self.unwrapped_type;
+                match ty {
+                    Type::Ptr(TypePtr {
+                        mutability: Some(_),
+                        ..
+                    }) => quote! { #var.as_mut_ptr() as #ty },
+                    _ => quote! { #var.as_ptr() as #ty },
+                }
+            }
+            // The C string lives until the end of the statement which
+            // makes the call.
+            RustConversionType::FromCStrArg => {
+                quote! { autocxx::CStrArg::as_c_str(&#var).as_ptr() }
+            }
+            RustConversionType::FromOutParam => quote! { &mut #var },
+            RustConversionType::FromArrayToPtr(_) => match &self.unwrapped_type {
+                Type::Ptr(TypePtr {
+                    mutability: Some(_),
+                    ..
+                }) => quote! { #var.as_mut_ptr() },
+                _ => quote! { #var.as_ptr() },
+            },
+            RustConversionType::FromPath => quote! { &autocxx::path::path_to_native(#var) },
+            RustConversionType::ToPathBuf => panic!("PathBufs are return values, not parameters"),
+            RustConversionType::FromBitSet(_) => quote! { #var.as_words() },
+            RustConversionType::ToBitSet(_) => {
+                panic!("Returned bitsets are return values, not parameters")
+            }
+            // By now, the closure has become a trampoline and a handle.
+            RustConversionType::FromClosureToFnPtr => quote! { #var.0 },
+            RustConversionType::FromClosureContext(ref closure) => quote! { #closure.1.as_ptr() },
+            RustConversionType::FromClosureToStdFunction => self.closure_to_owned_context(&var),
+            RustConversionType::FromPromotable => quote! { autocxx::PromotesTo::promote(#var) },
+            // A value made for a reference lasts until the end of the
+            // statement which makes the call.
+            RustConversionType::FromConvertedType(ref path, by_reference) => {
+                let cpp_type = self.converted_cpp_type();
+                let conversion = quote! { <#path as autocxx::CppConversion<#cpp_type>> };
+                if by_reference {
+                    quote! { &*#conversion::to_cpp(#var) }
+                } else {
+                    quote! { #conversion::to_cpp(&#var) }
+                }
+            }
+            RustConversionType::ToConvertedType(_) => {
+                panic!("Returned converted types are return values, not parameters")
+            }
+            RustConversionType::ToString(_) | RustConversionType::ToCStr => {
+                panic!("Returned strings are return values, not parameters")
+            }
+            // Each function remembers the address of its own symbol.
+            RustConversionType::FromResolvedSymbol(ref resolver, ref symbol) => quote! {
+                {
+                    static AUTOCXX_SYMBOL: autocxx::ResolvedSymbol = autocxx::ResolvedSymbol::new();
+                    AUTOCXX_SYMBOL.get::<#resolver>(#symbol) as u64
+                }
+            },
+            RustConversionType::FromSliceLen(ref slice) => {
+                let len = quote! {
+                    ::std::convert::TryInto::try_into(#slice.len()).expect("slice too long")
+                };
+                match &self.unwrapped_type {
+                    Type::Path(typ)
+                        if known_types().is_ctype(&QualifiedName::from_type_path(typ)) =>
+                    {
+                        quote! { #typ(#len) }
+                    }
+                    _ => len,
+                }
+            }
+        }
+    }
+
+    /// Convert the value returned by the cxx::bridge function into what
+    /// the wrapper returns.
+    pub(super) fn rust_return_conversion(&self, call: TokenStream) -> TokenStream {
+        match self.rust_conversion {
+            RustConversionType::ToPathBuf => quote! { autocxx::path::path_from_native(#call) },
+            RustConversionType::ToBitSet(width) => {
+                let ty = bitset_type(width);
+                quote! { <#ty>::from_words(&#call) }
+            }
+            RustConversionType::ToConvertedType(ref path) => {
+                let cpp_type = self.converted_cpp_type();
+                quote! { <#path as autocxx::CppConversion<#cpp_type>>::from_cpp(&*#call) }
+            }
+            // Both keep any NULs, as the bytes of a std::string may.
+            RustConversionType::ToString(StringReturn::Lossy) => {
+                quote! { (*#call).to_string_lossy().into_owned() }
+            }
+            RustConversionType::ToString(StringReturn::Strict) => {
+                quote! { String::from_utf8((*#call).as_bytes().to_vec()) }
+            }
+            // The wrapper's signature ties the string's lifetime to the
+            // object's, and the wrapper may or may not itself be unsafe.
+            RustConversionType::ToCStr => quote! {
+                {
+                    let ptr = #call;
+                    assert!(!ptr.is_null(), "a function listed in c_str_return! returned a null pointer");
+                    #[allow(unused_unsafe)]
+                    let s = unsafe { ::std::ffi::CStr::from_ptr(ptr) };
+                    s
+                }
+            },
+            _ => call,
+        }
+    }
+
+    /// The C++ type listed in `type_conversion!` which is passed or
+    /// returned, whether by value or by reference.
+    fn converted_cpp_type(&self) -> &Type {
+        match &self.unwrapped_type {
+            Type::Reference(TypeReference { elem, .. }) => elem,
+            ty => ty,
+        }
+    }
+
+    /// The trait bound for a closure which C++ calls through a function
+    /// pointer. It takes the function's parameters other than the context,
+    /// which a `std::function`'s signature doesn't include.
+    fn closure_bound(&self) -> TokenStream {
+        let callback = self.callback_type();
+        let ctx_idx = match self.rust_conversion {
+            RustConversionType::FromClosureToStdFunction => None,
+            _ => callback_context_index(callback),
+        };
+        let args = callback
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| Some(*idx) != ctx_idx)
+            .map(|(_, arg)| &arg.ty);
+        let output = &callback.output;
+        quote! { FnMut(#(#args),*) #output + Send + 'static }
+    }
+
+    fn callback_type(&self) -> &TypeBareFn {
+        match &self.unwrapped_type {
+            Type::BareFn(callback) => callback,
+            _ => panic!("Not a function pointer"),
+        }
+    }
+
+    /// Turns the closure passed as `var`, which C++ will make a
+    /// `std::function` of, into a context which holds the closure along
+    /// with the trampoline function which calls it. The `std::function`
+    /// takes ownership of the context.
+    fn closure_to_owned_context(&self, var: &Pat) -> TokenStream {
+        let callback = self.callback_type();
+        let closure_bound = self.closure_bound();
+        let arg_names: Vec<_> = (0..callback.inputs.len())
+            .map(|idx| make_ident(format!("arg{}", idx)))
+            .collect();
+        let params = callback
+            .inputs
+            .iter()
+            .zip(arg_names.iter())
+            .map(|(arg, name)| {
+                let ty = &arg.ty;
+                quote! { #name: #ty }
+            });
+        let output = &callback.output;
+        quote! {
+            {
+                unsafe extern "C" fn trampoline<F: #closure_bound>(context: *mut autocxx::c_void, #(#params),*) #output {
+                    autocxx::callback::call_closure::<F, _>(context, |closure| closure(#(#arg_names),*))
+                }
+                fn context_for<F: #closure_bound>(closure: F) -> *mut autocxx::c_void {
+                    autocxx::callback::owned_context(closure, trampoline::<F> as *const autocxx::c_void)
+                }
+                context_for(#var)
+            }
+        }
+    }
+
+    /// Turns the closure passed as `var` into a trampoline function to
+    /// pass to C++ as the function pointer, and a `CallbackHandle` whose
+    /// pointer C++ passes back to the trampoline as its context.
+    pub(super) fn closure_to_trampoline(&self, var: &Pat) -> TokenStream {
+        let callback = self.callback_type();
+        let closure_bound = self.closure_bound();
+        let ctx_idx = callback_context_index(callback).expect("Callback has no context");
+        let arg_names: Vec<_> = (0..callback.inputs.len())
+            .map(|idx| make_ident(format!("arg{}", idx)))
+            .collect();
+        let params = callback
+            .inputs
+            .iter()
+            .zip(arg_names.iter())
+            .map(|(arg, name)| {
+                let ty = &arg.ty;
+                quote! { #name: #ty }
+            });
+        let ctx = &arg_names[ctx_idx];
+        let closure_args = arg_names
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != ctx_idx)
+            .map(|(_, name)| name);
+        let output = &callback.output;
+        quote! {
+            let #var = {
+                unsafe extern "C" fn trampoline<F: #closure_bound>(#(#params),*) #output {
+                    autocxx::callback::call_closure::<F, _>(#ctx, |closure| closure(#(#closure_args),*))
+                }
+                fn trampoline_for<F: #closure_bound>(_: &F) -> *mut autocxx::c_void {
+                    trampoline::<F> as *mut autocxx::c_void
+                }
+                (trampoline_for(&#var), autocxx::callback::CallbackHandle::new(#var))
+            };
+        }
+    }
+}
+
+/// The element type of a slice passed to a pointer of the given type.
+/// std::bytes are passed as plain old u8s.
+fn slice_element_type(pointee: &Type) -> Type {
+    match pointee {
+        Type::Path(typ) if known_types().is_byte(&QualifiedName::from_type_path(typ)) => {
+            parse_quote! { u8 }
         }
+        _ => pointee.clone(),
     }
 }
@@ -16,17 +16,20 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use std::borrow::Cow;
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, PatType, Path,
-    PathSegment, ReturnType, Type, TypePath,
+    parse_quote, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, Ident, Pat, PatType,
+    Path, PathSegment, ReturnType, Type, TypePath,
 };
 
 /// Function which can add explicit lifetime parameters to function signatures
 /// where necessary, based on analysis of parameters and return types.
-/// This is necessary only in one case - where the parameter is a Pin<&mut T>
-/// and the return type is some kind of reference - because lifetime elision
-/// is not smart enough to see inside a Pin.
+/// This is necessary in two cases. Firstly, where the user has told us
+/// (with `lifetime!`) which parameter the returned reference borrows from.
+/// Secondly, where the parameter is a Pin<&mut T> and the return type is
+/// some kind of reference - because lifetime elision is not smart enough
+/// to see inside a Pin.
 pub(crate) fn add_explicit_lifetime_if_necessary<'r>(
     param_details: &[ArgumentAnalysis],
+    lifetime_source: Option<&Ident>,
     mut params: Punctuated<FnArg, Comma>,
     ret_type: &'r ReturnType,
 ) -> (
@@ -34,6 +37,9 @@ pub(crate) fn add_explicit_lifetime_if_necessary<'r>(
     Punctuated<FnArg, Comma>,
     Cow<'r, ReturnType>,
 ) {
+    if let Some(lifetime_source) = lifetime_source {
+        return add_lifetime_from_param(lifetime_source, params, ret_type);
+    }
     let has_mutable_receiver = param_details
         .iter()
         .any(|pd| matches!(pd.self_type, Some((_, ReceiverMutability::Mutable))));
@@ -82,6 +88,45 @@ pub(crate) fn add_explicit_lifetime_if_necessary<'r>(
     }
 }
 
+/// Ties the lifetime of the returned reference to the given parameter
+/// alone, leaving any other references with elided lifetimes.
+fn add_lifetime_from_param<'r>(
+    source: &Ident,
+    mut params: Punctuated<FnArg, Comma>,
+    ret_type: &'r ReturnType,
+) -> (
+    Option<TokenStream>,
+    Punctuated<FnArg, Comma>,
+    Cow<'r, ReturnType>,
+) {
+    let mut new_return_type = ret_type.clone();
+    if let ReturnType::Type(_, boxed_type) = &mut new_return_type {
+        add_lifetime_to_type(boxed_type);
+    }
+    for param in params.iter_mut() {
+        if let FnArg::Typed(PatType { pat, ty, .. }) = param {
+            if matches!(pat.as_ref(), Pat::Ident(pp) if pp.ident == *source) {
+                add_lifetime_to_type(ty);
+            }
+        }
+    }
+    (Some(quote! { <'a> }), params, Cow::Owned(new_return_type))
+}
+
+fn add_lifetime_to_type(ty: &mut Type) {
+    match ty {
+        Type::Reference(tyr) => add_lifetime_to_reference(tyr),
+        Type::Path(TypePath {
+            path: Path { segments, .. },
+            ..
+        }) => {
+            // Not a Pin means not a reference, so nothing to do.
+            let _ = add_lifetime_to_pinned_reference(segments);
+        }
+        _ => {}
+    }
+}
+
 #[derive(Debug)]
 enum AddLifetimeError {
     WasNotPin,
@@ -119,17 +164,12 @@ fn add_lifetime_to_reference(tyr: &mut syn::TypeReference) {
     tyr.lifetime = Some(parse_quote! { 'a })
 }
 
+/// Adds a lifetime to every reference parameter, whether a plain
+/// reference or a `Pin<&mut T>`. Others, such as integers, are left alone.
 pub(crate) fn add_lifetime_to_all_params(params: &mut Punctuated<FnArg, Comma>) {
-    for mut param in params.iter_mut() {
-        match &mut param {
-            FnArg::Typed(PatType { ty, .. }) => match ty.as_mut() {
-                Type::Path(TypePath {
-                    path: Path { segments, .. },
-                    ..
-                }) => add_lifetime_to_pinned_reference(segments).unwrap(),
-                Type::Reference(tyr) => add_lifetime_to_reference(tyr),
-                _ => {}
-            },
+    for param in params.iter_mut() {
+        match param {
+            FnArg::Typed(PatType { ty, .. }) => add_lifetime_to_type(ty),
             _ => panic!("Unexpected fnarg"),
         }
     }
@@ -12,13 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod cpp_any;
+mod cpp_iterable;
+mod flags;
 mod fun_codegen;
 mod function_wrapper_rs;
 mod impl_item_creator;
 mod lifetime;
 mod namespace_organizer;
 mod non_pod_struct;
+mod opaque_enum;
+mod transparent_newtype;
 pub(crate) mod unqualify;
+mod volatile_fields;
 
 use std::collections::{HashMap, HashSet};
 
@@ -28,14 +34,21 @@ use proc_macro2::{Span, TokenStream};
 use syn::{
     parse_quote, punctuated::Punctuated, token::Comma, Attribute, Expr, FnArg, ForeignItem,
     ForeignItemFn, Ident, ImplItem, Item, ItemForeignMod, ItemMod, Pat, ReturnType, TraitItem,
+    Visibility,
 };
 
 use crate::{
     conversion::{
         analysis::fun::MethodKind,
         codegen_rs::{
+            cpp_any::make_any_helpers,
+            cpp_iterable::make_iterable_helpers,
+            flags::make_flags,
             non_pod_struct::{make_non_pod, new_non_pod_struct},
+            opaque_enum::make_opaque_enum,
+            transparent_newtype::make_transparent_newtype,
             unqualify::{unqualify_params, unqualify_ret_type},
+            volatile_fields::make_volatile_accessors,
         },
         doc_attr::get_doc_attr,
     },
@@ -71,8 +84,10 @@ struct ImplBlockDetails {
     ty: Ident,
 }
 
+/// Entries which need to go into an `impl` block for a given trait and
+/// type: a method, and any associated types it needs.
 struct TraitImplBlockDetails {
-    item: TraitItem,
+    items: Vec<TraitItem>,
     key: TraitImplSignature,
 }
 
@@ -161,6 +176,7 @@ impl<'a> RsCodeGenerator<'a> {
         include_list: &'a [String],
         bindgen_mod: ItemMod,
         config: &'a IncludeCppConfig,
+        has_additional_cpp: bool,
     ) -> Vec<Item> {
         let c = Self {
             include_list,
@@ -168,10 +184,10 @@ impl<'a> RsCodeGenerator<'a> {
             original_name_map: original_name_map_from_apis(&all_apis),
             config,
         };
-        c.rs_codegen(all_apis)
+        c.rs_codegen(all_apis, has_additional_cpp)
     }
 
-    fn rs_codegen(mut self, all_apis: Vec<Api<FnPhase>>) -> Vec<Item> {
+    fn rs_codegen(mut self, all_apis: Vec<Api<FnPhase>>, has_additional_cpp: bool) -> Vec<Item> {
         // ... and now let's start to generate the output code.
         // First off, when we generate structs we may need to add some methods
         // if they're superclasses.
@@ -179,23 +195,22 @@ impl<'a> RsCodeGenerator<'a> {
         let subclasses_with_a_single_trivial_constructor =
             find_trivially_constructed_subclasses(&all_apis);
         // Now let's generate the Rust code.
-        let (rs_codegen_results_and_namespaces, additional_cpp_needs): (Vec<_>, Vec<_>) = all_apis
+        let rs_codegen_results_and_namespaces: Vec<_> = all_apis
             .into_iter()
             .map(|api| {
-                let more_cpp_needed = api.needs_cpp_codegen();
                 let name = api.name().clone();
                 let gen = self.generate_rs_for_api(
                     api,
                     &methods_by_superclass,
                     &subclasses_with_a_single_trivial_constructor,
                 );
-                ((name, gen), more_cpp_needed)
+                (name, gen)
             })
-            .unzip();
+            .collect();
         // First, the hierarchy of mods containing lots of 'use' statements
         // which is the final API exposed as 'ffi'.
         let mut use_statements =
-            Self::generate_final_use_statements(&rs_codegen_results_and_namespaces);
+            self.generate_final_use_statements(&rs_codegen_results_and_namespaces);
         // And work out what we need for the bindgen mod.
         let bindgen_root_items =
             self.generate_final_bindgen_mods(&rs_codegen_results_and_namespaces);
@@ -225,8 +240,7 @@ impl<'a> RsCodeGenerator<'a> {
         // And a list of global items to include at the top level.
         let mut all_items: Vec<Item> = all_items.into_iter().flatten().collect();
         // And finally any C++ we need to generate. And by "we" I mean autocxx not cxx.
-        let has_additional_cpp_needs = additional_cpp_needs.into_iter().any(std::convert::identity);
-        extern_c_mod_items.extend(self.build_include_foreign_items(has_additional_cpp_needs));
+        extern_c_mod_items.extend(self.build_include_foreign_items(has_additional_cpp));
         // We will always create an extern "C" mod even if bindgen
         // didn't generate one, e.g. because it only generated types.
         // We still want cxx to know about those types.
@@ -344,21 +358,25 @@ impl<'a> RsCodeGenerator<'a> {
     /// Generate lots of 'use' statements to pull cxxbridge items into the output
     /// mod hierarchy according to C++ namespaces.
     fn generate_final_use_statements(
+        &self,
         input_items: &[(QualifiedName, RsCodegenResult)],
     ) -> Vec<Item> {
         let mut output_items = Vec::new();
         let ns_entries = NamespaceEntries::new(input_items);
-        Self::append_child_use_namespace(&ns_entries, &mut output_items);
+        self.append_child_use_namespace(&ns_entries, &mut output_items, &Namespace::new());
         output_items
     }
 
     fn append_child_use_namespace(
+        &self,
         ns_entries: &NamespaceEntries<(QualifiedName, RsCodegenResult)>,
         output_items: &mut Vec<Item>,
+        ns: &Namespace,
     ) {
         for (name, codegen) in ns_entries.entries() {
+            let vis = self.config.get_visibility(&name.to_cpp_name());
             output_items.extend(codegen.materializations.iter().map(|materialization| {
-                match materialization {
+                let mut item = match materialization {
                     Use::UsedFromCxxBridgeWithAlias(alias) => {
                         Self::generate_cxx_use_stmt(name, Some(alias))
                     }
@@ -369,26 +387,51 @@ impl<'a> RsCodeGenerator<'a> {
                         Self::generate_bindgen_use_stmt(&name)
                     }
                     Use::Custom(item) => *item.clone(),
+                };
+                if let Some(vis) = vis {
+                    Self::set_visibility(&mut item, vis);
                 }
+                item
             }));
         }
         for (child_name, child_ns_entries) in ns_entries.children() {
             if child_ns_entries.is_empty() {
                 continue;
             }
+            let child_ns = ns.push((*child_name).clone());
             let child_id = make_ident(child_name);
             let mut new_mod: ItemMod = parse_quote!(
                 pub mod #child_id {
                 }
             );
-            Self::append_child_use_namespace(
+            if let Some(vis) = self.config.get_visibility(&child_ns.to_string()) {
+                new_mod.vis = vis.clone();
+            }
+            self.append_child_use_namespace(
                 child_ns_entries,
                 &mut new_mod.content.as_mut().unwrap().1,
+                &child_ns,
             );
             output_items.push(Item::Mod(new_mod));
         }
     }
 
+    /// Applies a visibility requested using the `visibility!` directive.
+    /// The items to which this is applied are all re-exports or thin
+    /// wrappers; the underlying types remain `pub` within their own private
+    /// mods, so a public function can still refer to a type which has been
+    /// given a restricted visibility here. It just can't be named
+    /// from outside.
+    fn set_visibility(item: &mut Item, vis: &Visibility) {
+        match item {
+            Item::Use(u) => u.vis = vis.clone(),
+            Item::Fn(f) => f.vis = vis.clone(),
+            Item::Struct(s) => s.vis = vis.clone(),
+            Item::Type(t) => t.vis = vis.clone(),
+            _ => {}
+        }
+    }
+
     fn append_uses_for_ns(&mut self, items: &mut Vec<Item>, ns: &Namespace) {
         let super_duper = std::iter::repeat(make_ident("super")); // I'll get my coat
         let supers = super_duper.clone().take(ns.depth() + 2);
@@ -436,7 +479,7 @@ impl<'a> RsCodeGenerator<'a> {
                 trait_impl_entries_by_trait_and_ty
                     .entry(trait_impl_entry.key.clone())
                     .or_default()
-                    .push(&trait_impl_entry.item);
+                    .extend(&trait_impl_entry.items);
             }
         }
         for (ty, entries) in impl_entries_by_type.into_iter() {
@@ -513,6 +556,30 @@ impl<'a> RsCodeGenerator<'a> {
                     ..Default::default()
                 }
             }
+            Api::CppAny { castables, .. } => {
+                let (extern_c_mod_items, global_items) =
+                    make_any_helpers(&self.config.get_any_helper_prefix(), &castables);
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    global_items,
+                    ..Default::default()
+                }
+            }
+            Api::CppIterable {
+                container,
+                element,
+                category,
+                is_set,
+                ..
+            } => {
+                let (extern_c_mod_items, global_items) =
+                    make_iterable_helpers(&id, &container, &element, category, is_set);
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    global_items,
+                    ..Default::default()
+                }
+            }
             Api::Function { fun, analysis, .. } => {
                 gen_function(name.get_namespace(), *fun, analysis, cpp_call_name)
             }
@@ -530,11 +597,26 @@ impl<'a> RsCodeGenerator<'a> {
                 ..Default::default()
             },
             Api::Struct {
-                details, analysis, ..
+                mut details,
+                analysis,
+                ..
             } => {
                 let doc_attr = get_doc_attr(&details.item.attrs);
                 let layout = details.layout.clone();
-                self.generate_type(
+                let mut extra_impls = if matches!(analysis.kind, TypeKind::Pod) {
+                    make_volatile_accessors(
+                        &mut details.item,
+                        self.config.get_volatile_fields(&name.to_cpp_name()),
+                    )
+                } else {
+                    Vec::new()
+                };
+                if matches!(analysis.kind, TypeKind::Pod)
+                    && self.config.is_transparent_newtype(&name.to_cpp_name())
+                {
+                    extra_impls.extend(make_transparent_newtype(&mut details.item));
+                }
+                let mut result = self.generate_type(
                     &name,
                     id,
                     analysis.kind,
@@ -542,19 +624,56 @@ impl<'a> RsCodeGenerator<'a> {
                     || Some((Item::Struct(details.item), doc_attr)),
                     associated_methods,
                     layout,
-                )
+                );
+                result.bindgen_mod_items.extend(extra_impls);
+                result
             }
             Api::Enum { item, .. } => {
                 let doc_attr = get_doc_attr(&item.attrs);
-                self.generate_type(
-                    &name,
-                    id,
-                    TypeKind::Pod,
-                    true,
-                    || Some((Item::Enum(item), doc_attr)),
-                    associated_methods,
-                    None,
-                )
+                match self.config.get_flags(&name.to_cpp_name()) {
+                    Some(flags) => {
+                        let (mut flags_items, builder_id) = make_flags(&item, flags);
+                        let newtype = flags_items.remove(0);
+                        let mut result = self.generate_type(
+                            &name,
+                            id,
+                            TypeKind::Pod,
+                            true,
+                            || Some((newtype, doc_attr)),
+                            associated_methods,
+                            None,
+                        );
+                        result.bindgen_mod_items.extend(flags_items);
+                        result
+                            .materializations
+                            .push(Use::SpecificNameFromBindgen(builder_id));
+                        result
+                    }
+                    None if item.variants.is_empty() => {
+                        let mut items = make_opaque_enum(&item);
+                        let newtype = items.remove(0);
+                        let mut result = self.generate_type(
+                            &name,
+                            id,
+                            TypeKind::Pod,
+                            true,
+                            || Some((newtype, doc_attr)),
+                            associated_methods,
+                            None,
+                        );
+                        result.bindgen_mod_items.extend(items);
+                        result
+                    }
+                    None => self.generate_type(
+                        &name,
+                        id,
+                        TypeKind::Pod,
+                        true,
+                        || Some((Item::Enum(item), doc_attr)),
+                        associated_methods,
+                        None,
+                    ),
+                }
             }
             Api::ForwardDeclaration { .. } | Api::ConcreteType { .. } => self.generate_type(
                 &name,
@@ -580,6 +699,15 @@ impl<'a> RsCodeGenerator<'a> {
                 }],
                 ..Default::default()
             },
+            Api::ExternCppType { path, .. } => RsCodegenResult {
+                extern_c_mod_items: vec![self.generate_cxxbridge_type_alias(
+                    &name,
+                    Some(path.to_token_stream()),
+                    None,
+                )],
+                materializations: vec![Use::UsedFromCxxBridge],
+                ..Default::default()
+            },
             Api::RustFn { sig, path, .. } => RsCodegenResult {
                 global_items: vec![parse_quote! {
                     use super::#path;
@@ -591,7 +719,7 @@ impl<'a> RsCodeGenerator<'a> {
             },
             Api::RustSubclassFn {
                 details, subclass, ..
-            } => Self::generate_subclass_fn(id, *details, subclass),
+            } => self.generate_subclass_fn(id, *details, subclass),
             Api::Subclass {
                 name, superclass, ..
             } => {
@@ -747,6 +875,7 @@ impl<'a> RsCodeGenerator<'a> {
     }
 
     fn generate_subclass_fn(
+        &self,
         api_name: Ident,
         details: RustSubclassFnDetails,
         subclass: SubclassName,
@@ -754,7 +883,22 @@ impl<'a> RsCodeGenerator<'a> {
         let params = details.params;
         let ret = details.ret;
         let unsafe_token = get_unsafe_token(details.requires_unsafe);
-        let global_def = quote! { #unsafe_token fn #api_name(#params) #ret };
+        let panics_as_exceptions = self.config.panics_as_exceptions;
+        // cxx throws a C++ exception when an extern "Rust" function returns
+        // an Err, so a panic caught here becomes one.
+        let (global_ret, ret) = if panics_as_exceptions {
+            let ret_ty: syn::Type = match ret {
+                ReturnType::Default => parse_quote! { () },
+                ReturnType::Type(_, ty) => *ty,
+            };
+            (
+                parse_quote! { -> ::std::result::Result<#ret_ty, String> },
+                parse_quote! { -> Result<#ret_ty> },
+            )
+        } else {
+            (ret.clone(), ret)
+        };
+        let global_def = quote! { #unsafe_token fn #api_name(#params) #global_ret };
         let params = unqualify_params(params);
         let ret = unqualify_ret_type(ret);
         let method_name = details.method_name;
@@ -779,20 +923,30 @@ impl<'a> RsCodeGenerator<'a> {
         let borrow = make_ident(borrow);
         let destroy_panic_msg = format!("Rust subclass API (method {} of subclass {} of superclass {}) called after subclass destroyed", method_name, subclass.0.name, superclass_id);
         let reentrancy_panic_msg = format!("Rust subclass API (method {} of subclass {} of superclass {}) called whilst subclass already borrowed - likely a re-entrant call",  method_name, subclass.0.name, superclass_id);
+        let body = quote! {
+            let rc = me.0
+                .get()
+                .expect(#destroy_panic_msg);
+            let #mut_token b = rc
+                .as_ref()
+                .#borrow()
+                .expect(#reentrancy_panic_msg);
+            let r = std::ops::#deref_ty::#deref_call(& #mut_token b);
+            #methods_trait :: #method_name
+                (r,
+                #args)
+        };
+        let body = if panics_as_exceptions {
+            quote! {
+                autocxx::subclass::catch_panic(move || { #body })
+            }
+        } else {
+            body
+        };
         RsCodegenResult {
             global_items: vec![parse_quote! {
                 #global_def {
-                    let rc = me.0
-                        .get()
-                        .expect(#destroy_panic_msg);
-                    let #mut_token b = rc
-                        .as_ref()
-                        .#borrow()
-                        .expect(#reentrancy_panic_msg);
-                    let r = std::ops::#deref_ty::#deref_call(& #mut_token b);
-                    #methods_trait :: #method_name
-                        (r,
-                        #args)
+                    #body
                 }
             }],
             extern_rust_mod_items: vec![ForeignItem::Fn(cxxbridge_decl)],
@@ -1066,6 +1220,26 @@ impl<'a> RsCodeGenerator<'a> {
         name: &QualifiedName,
         references_bindgen: bool,
         doc_attr: Option<Attribute>,
+    ) -> ForeignItem {
+        let target = if references_bindgen {
+            let ns = name.get_namespace().iter().map(make_ident);
+            let id = name.get_final_ident();
+            Some(quote! {
+                super::bindgen::root:: #(#ns::)* #id
+            })
+        } else {
+            None
+        };
+        self.generate_cxxbridge_type_alias(name, target, doc_attr)
+    }
+
+    /// Declare a type in the cxx::bridge, which is either opaque or an
+    /// alias of the given Rust type.
+    fn generate_cxxbridge_type_alias(
+        &self,
+        name: &QualifiedName,
+        target: Option<TokenStream>,
+        doc_attr: Option<Attribute>,
     ) -> ForeignItem {
         let ns = name.get_namespace();
         let id = name.get_final_ident();
@@ -1100,17 +1274,9 @@ impl<'a> RsCodeGenerator<'a> {
             doc_attr.to_tokens(&mut for_extern_c_ts);
         }
 
-        if references_bindgen {
-            for_extern_c_ts.extend(quote! {
-                type #id = super::bindgen::root::
-            });
-            for_extern_c_ts.extend(ns.iter().map(make_ident).map(|id| {
-                quote! {
-                    #id::
-                }
-            }));
+        if let Some(target) = target {
             for_extern_c_ts.extend(quote! {
-                #id;
+                type #id = #target;
             });
         } else {
             for_extern_c_ts.extend(quote! {
@@ -0,0 +1,53 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::{parse_quote, Item, ItemEnum};
+
+use super::flags::underlying_type;
+
+/// Turn a bindgen enum with no enumerators into a `#[repr(transparent)]`
+/// newtype around its underlying integer. That's what bindgen gives us
+/// for an opaque declaration like `enum class E : int;` whose definition
+/// isn't visible; if the definition appears later in the translation
+/// unit, bindgen sees the enumerators and we never get here. A Rust enum
+/// with no variants could never be constructed, and can't have a `repr`,
+/// whereas this can hold any value. Returns the newtype, followed by
+/// `From` implementations to convert to and from the integer.
+pub(crate) fn make_opaque_enum(item: &ItemEnum) -> Vec<Item> {
+    let id = &item.ident;
+    let repr = underlying_type(item);
+    let docs = item.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+    vec![
+        parse_quote! {
+            #(#docs)*
+            #[repr(transparent)]
+            #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+            pub struct #id(pub #repr);
+        },
+        parse_quote! {
+            impl From<#repr> for #id {
+                fn from(value: #repr) -> Self {
+                    Self(value)
+                }
+            }
+        },
+        parse_quote! {
+            impl From<#id> for #repr {
+                fn from(value: #id) -> Self {
+                    value.0
+                }
+            }
+        },
+    ]
+}
@@ -0,0 +1,56 @@
+// Copyright 2021 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::{parse_quote, Fields, FieldsUnnamed, Item, ItemStruct};
+
+/// Turn a bindgen struct with a single field into a `#[repr(transparent)]`
+/// tuple struct, returning `From` implementations to convert to and from
+/// the field's type. The pod analysis has already checked that there's
+/// exactly one field and no explicit alignment.
+pub(crate) fn make_transparent_newtype(s: &mut ItemStruct) -> Vec<Item> {
+    s.attrs.retain(|attr| !attr.path.is_ident("repr"));
+    s.attrs.push(parse_quote! { #[repr(transparent)] });
+    let mut field = s
+        .fields
+        .iter()
+        .next()
+        .expect("Transparent newtype must have one field")
+        .clone();
+    field.ident = None;
+    field.colon_token = None;
+    field.vis = parse_quote! { pub };
+    let inner = field.ty.clone();
+    s.fields = Fields::Unnamed(FieldsUnnamed {
+        paren_token: Default::default(),
+        unnamed: std::iter::once(field).collect(),
+    });
+    s.semi_token = Some(Default::default());
+    let id = &s.ident;
+    vec![
+        parse_quote! {
+            impl From<#inner> for #id {
+                fn from(inner: #inner) -> Self {
+                    Self(inner)
+                }
+            }
+        },
+        parse_quote! {
+            impl From<#id> for #inner {
+                fn from(newtype: #id) -> Self {
+                    newtype.0
+                }
+            }
+        },
+    ]
+}
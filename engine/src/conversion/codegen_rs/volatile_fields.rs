@@ -0,0 +1,69 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::{parse_quote, ImplItem, Item, ItemStruct, Visibility};
+
+use crate::types::make_ident;
+
+/// Makes the fields of a POD type which were listed in `volatile_field!`
+/// private, and gives each a pair of methods which read and write it
+/// using volatile accesses, so that the compiler can't elide or merge
+/// them as it could accesses to an ordinary field.
+pub(crate) fn make_volatile_accessors<'a>(
+    s: &mut ItemStruct,
+    fields: impl Iterator<Item = &'a str>,
+) -> Vec<Item> {
+    let mut accessors: Vec<ImplItem> = Vec::new();
+    for name in fields {
+        let escaped = format!("{}_", name);
+        let field = match s.fields.iter_mut().find(|f| {
+            f.ident
+                .as_ref()
+                .map(|ident| ident == name || *ident == escaped)
+                .unwrap_or_default()
+        }) {
+            Some(field) => field,
+            None => continue,
+        };
+        field.vis = Visibility::Inherited;
+        let field_id = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let read_id = make_ident(format!("read_{}", name));
+        let write_id = make_ident(format!("write_{}", name));
+        let read_doc = format!("Reads the volatile field `{}`.", name);
+        let write_doc = format!("Writes the volatile field `{}`.", name);
+        accessors.push(parse_quote! {
+            #[doc = #read_doc]
+            pub fn #read_id(&self) -> #ty {
+                unsafe { std::ptr::read_volatile(std::ptr::addr_of!(self.#field_id)) }
+            }
+        });
+        accessors.push(parse_quote! {
+            #[doc = #write_doc]
+            pub fn #write_id(&mut self, value: #ty) {
+                unsafe { std::ptr::write_volatile(std::ptr::addr_of_mut!(self.#field_id), value) }
+            }
+        });
+    }
+    if accessors.is_empty() {
+        return Vec::new();
+    }
+    let id = &s.ident;
+    let (impl_generics, ty_generics, where_clause) = s.generics.split_for_impl();
+    vec![parse_quote! {
+        impl #impl_generics #id #ty_generics #where_clause {
+            #(#accessors)*
+        }
+    }]
+}
@@ -40,6 +40,7 @@ fn do_test(input: ItemMod) {
         UnsafePolicy::AllFunctionsSafe,
         inclusions,
         &CppCodegenOptions::default(),
+        true,
     )
     .unwrap();
 }
@@ -28,6 +28,7 @@ pub enum ConvertError {
     ComplexTypedefTarget(String),
     UnexpectedThisType(Namespace, String),
     UnsupportedBuiltInType(QualifiedName),
+    UnsupportedPointerWrapper(QualifiedName),
     ConflictingTemplatedArgsWithTypedef(QualifiedName),
     UnacceptableParam(String),
     NotOneInputReference(String),
@@ -35,10 +36,13 @@ pub enum ConvertError {
     UnknownType(String),
     StaticData(String),
     InfinitelyRecursiveTypedef(QualifiedName),
+    AliasTemplateArguments(QualifiedName),
+    GenericAliasToUnsupportedTemplate(QualifiedName),
     UnexpectedUseStatement(Option<Ident>),
     TemplatedTypeContainingNonPathArg(QualifiedName),
     InvalidPointee,
     DidNotGenerateAnything(String),
+    DidNotGenerateListedItems(Vec<(String, String)>),
     TypeContainingForwardDeclaration(QualifiedName),
     Blocked(QualifiedName),
     UnusedTemplateParam,
@@ -60,6 +64,38 @@ pub enum ConvertError {
     RValueReferenceField,
     MethodOfNonAllowlistedType,
     MethodOfGenericType,
+    StringViewNotByValue,
+    FilesystemPathNotByValue,
+    BitsetNotByValue,
+    OffloadedFunctionBorrows,
+    OwnedReturnNotPointer,
+    StaticRefReturnNotReference,
+    NonZeroNotInteger,
+    InvalidNullCheckedReturn(String),
+    InvalidSliceParam(String),
+    InvalidCStrParam(String),
+    InvalidCStrReturn(String),
+    InvalidClosureCallback(String),
+    UnsupportedStdFunction(String),
+    InvalidUniqueArrayReturn(String),
+    InvalidArrayReturn(String),
+    InvalidCheckedIndex(String),
+    InvalidAwaitable(String),
+    PreconditionOnMethod,
+    InvalidOutParam(String),
+    InvalidEmplaceReturn(String),
+    InvalidTupleReturn(String),
+    InvalidStringReturn(String),
+    InvalidErrorCodeParam(String),
+    InvalidTransparentNewtype(String),
+    InvalidVolatileField(String),
+    InvalidFlags(String),
+    MutableReferenceToEnum(QualifiedName),
+    InvalidLifetime(String),
+    ImmovableTypeByValue(QualifiedName),
+    MethodRenameCollision(String),
+    StrippedPrefixCollision(String),
+    CtorNameCollision(String),
 }
 
 fn format_maybe_identifier(id: &Option<Ident>) -> String {
@@ -79,6 +115,7 @@ impl Display for ConvertError {
             ConvertError::UnexpectedItemInMod => write!(f, "Bindgen generated some unexpected code in an inner namespace mod. You may have specified something in a 'generate' directive which is not currently compatible with autocxx.")?,
             ConvertError::ComplexTypedefTarget(ty) => write!(f, "autocxx was unable to produce a typdef pointing to the complex type {}.", ty)?,
             ConvertError::UnexpectedThisType(ns, fn_name) => write!(f, "Unexpected type for 'this' in the function {}{}.", fn_name, ns.to_display_suffix())?,
+            ConvertError::UnsupportedPointerWrapper(ty) => write!(f, "{} was listed in register_pointer_wrapper! but is not used with a single pointer or smart pointer type argument.", ty.to_cpp_name())?,
             ConvertError::UnsupportedBuiltInType(ty) => write!(f, "autocxx does not yet know how to support the built-in C++ type {} - please raise an issue on github", ty.to_cpp_name())?,
             ConvertError::ConflictingTemplatedArgsWithTypedef(tn) => write!(f, "Type {} has templated arguments and so does the typedef to which it points", tn)?,
             ConvertError::UnacceptableParam(fn_name) => write!(f, "Function {} has a parameter or return type which is either on the blocklist or a forward declaration", fn_name)?,
@@ -87,10 +124,13 @@ impl Display for ConvertError {
             ConvertError::UnknownType(ty_desc) => write!(f, "Encountered type not yet known by autocxx: {}", ty_desc)?,
             ConvertError::StaticData(ty_desc) => write!(f, "Encountered mutable static data, not yet supported: {}", ty_desc)?,
             ConvertError::InfinitelyRecursiveTypedef(tn) => write!(f, "Encountered typedef to itself - this is a known bindgen bug: {}", tn.to_cpp_name())?,
+            ConvertError::AliasTemplateArguments(tn) => write!(f, "The alias template {} was used with template arguments which didn't match its parameters", tn.to_cpp_name())?,
+            ConvertError::GenericAliasToUnsupportedTemplate(tn) => write!(f, "This alias template refers to the template {}, which cxx can't represent generically. Its instantiations can still be used.", tn.to_cpp_name())?,
             ConvertError::UnexpectedUseStatement(maybe_ident) => write!(f, "Unexpected 'use' statement encountered: {}", format_maybe_identifier(maybe_ident))?,
             ConvertError::TemplatedTypeContainingNonPathArg(tn) => write!(f, "Type {} was parameterized over something complex which we don't yet support", tn)?,
             ConvertError::InvalidPointee => write!(f, "Pointer pointed to something unsupported")?,
-            ConvertError::DidNotGenerateAnything(directive) => write!(f, "The 'generate' or 'generate_pod' directive for '{}' did not result in any code being generated. Perhaps this was mis-spelled or you didn't qualify the name with any namespaces? Otherwise please report a bug.", directive)?,
+            ConvertError::DidNotGenerateListedItems(items) => write!(f, "These items did not result in any code being generated: {}. Perhaps they were mis-spelled or you didn't qualify the names with any namespaces?", items.iter().map(|(name, origin)| format!("'{}' ({})", name, origin)).collect::<Vec<_>>().join(", "))?,
+            ConvertError::DidNotGenerateAnything(directive) => write!(f, "The 'generate' or 'generate_pod' directive for '{}' did not result in any code being generated. Perhaps this was mis-spelled or you didn't qualify the name with any namespaces? If it's a function whose return type is deduced (auto or decltype), perhaps the type couldn't be resolved outside a template context. Functions with internal linkage, such as those declared static or within an anonymous namespace, can't currently be bound. Otherwise please report a bug.", directive)?,
             ConvertError::TypeContainingForwardDeclaration(tn) => write!(f, "Found an attempt at using a forward declaration ({}) inside a templated cxx type such as UniquePtr or CxxVector", tn.to_cpp_name())?,
             ConvertError::Blocked(tn) => write!(f, "Found an attempt at using a type marked as blocked! ({})", tn.to_cpp_name())?,
             ConvertError::UnusedTemplateParam => write!(f, "This function or method uses a type where one of the template parameters was incomprehensible to bindgen/autocxx - probably because it uses template specialization.")?,
@@ -112,6 +152,38 @@ impl Display for ConvertError {
             ConvertError::RValueReferenceField => write!(f, "This structure has an rvalue reference field (&&) which is not yet supported.")?,
             ConvertError::MethodOfNonAllowlistedType => write!(f, "This type was not on the allowlist, so we are not generating methods for it.")?,
             ConvertError::MethodOfGenericType => write!(f, "This type is templated, so we can't generate bindings. We will instead generate bindings for each instantiation.")?,
+            ConvertError::StringViewNotByValue => write!(f, "std::string_view is only supported by value, or by const reference as a parameter.")?,
+            ConvertError::FilesystemPathNotByValue => write!(f, "std::filesystem::path is only supported by value, or by const reference.")?,
+            ConvertError::BitsetNotByValue => write!(f, "A std::bitset listed in bitset! is only supported by value, or by const reference.")?,
+            ConvertError::InvalidNullCheckedReturn(problem) => write!(f, "This function was listed in non_null_return! or nullable_return! but {}. It must return a std::unique_ptr, or a C++ object by value.", problem)?,
+            ConvertError::NonZeroNotInteger => write!(f, "This function was listed in nonzero! but does not return a fixed-width integer type such as uint32_t.")?,
+            ConvertError::StaticRefReturnNotReference => write!(f, "This function was listed in static_ref_return! but does not return a reference.")?,
+            ConvertError::OwnedReturnNotPointer => write!(f, "This function was listed in owned_return! but does not return a pointer.")?,
+            ConvertError::InvalidTransparentNewtype(problem) => write!(f, "This type was listed in transparent_newtype! but {}. It must be a plain struct with exactly one field.", problem)?,
+            ConvertError::InvalidVolatileField(problem) => write!(f, "A field of this type was listed in volatile_field! but {}. Volatile fields can only be accessed for POD types.", problem)?,
+            ConvertError::InvalidFlags(problem) => write!(f, "This enum was listed in flags! but {}.", problem)?,
+            ConvertError::MutableReferenceToEnum(tn) => write!(f, "This function takes or returns a non-const reference to the enum {}, through which C++ could store a value which isn't one of its enumerators. That's undefined behavior for a Rust enum, so list it in flags! to make it a newtype instead.", tn.to_cpp_name())?,
+            ConvertError::ImmovableTypeByValue(tn) => write!(f, "This function takes or returns {} by value, but that type can be neither moved nor copied, so can only be used by reference or within a UniquePtr.", tn.to_cpp_name())?,
+            ConvertError::MethodRenameCollision(renamed) => write!(f, "Another method of this type was also renamed to {}, so bindings to this one were skipped.", renamed)?,
+            ConvertError::StrippedPrefixCollision(stripped) => write!(f, "Without the prefix given in strip_prefix!, this function would be called {}, as is another function, so bindings to it were skipped.", stripped)?,
+            ConvertError::CtorNameCollision(name) => write!(f, "This constructor would be called {} as given in name_ctor!, but so is a method of this type, so bindings to it were skipped.", name)?,
+            ConvertError::InvalidLifetime(problem) => write!(f, "This function was listed in lifetime! but {}.", problem)?,
+            ConvertError::InvalidErrorCodeParam(problem) => write!(f, "This function was listed in error_code_param! but {}.", problem)?,
+            ConvertError::InvalidOutParam(problem) => write!(f, "This function was listed in out_param! but {}. The out parameter must be a non-const pointer to a pointer.", problem)?,
+            ConvertError::InvalidEmplaceReturn(problem) => write!(f, "This function was listed in emplace_return! but {}. It must be a free function which returns a non-POD type by value.", problem)?,
+            ConvertError::InvalidTupleReturn(problem) => write!(f, "This function was listed in tuple_return! but {}. It must be a free function which returns a std::tuple of values which could each be returned on their own.", problem)?,
+            ConvertError::InvalidStringReturn(problem) => write!(f, "This function was listed in string_return! but {}. It must return a std::string by value or by const reference.", problem)?,
+            ConvertError::InvalidSliceParam(problem) => write!(f, "This function was listed in slice_param! but {}. The pointer parameter must be a pointer and the length parameter a plain integer.", problem)?,
+            ConvertError::InvalidCStrParam(problem) => write!(f, "This function was listed in c_str_param! but {}. The parameter must be a const char*.", problem)?,
+            ConvertError::InvalidCStrReturn(problem) => write!(f, "This function was listed in c_str_return! but {}. It must be a method returning a const char*.", problem)?,
+            ConvertError::InvalidCheckedIndex(problem) => write!(f, "This type was listed in checked_index! but {}. It must have a const at method taking an integer index, and a const size method returning the same type.", problem)?,
+            ConvertError::InvalidAwaitable(problem) => write!(f, "This type was listed in register_awaitable! but {}. Its result method must take no parameters; its resume method must take no parameters and return nothing; and its done method must be const, taking no parameters and returning bool.", problem)?,
+            ConvertError::PreconditionOnMethod => write!(f, "This method was listed in precondition!, but only free functions may have preconditions, so bindings to it were skipped.")?,
+            ConvertError::InvalidUniqueArrayReturn(problem) => write!(f, "This function was listed in unique_array_return! but {}. It must return a std::unique_ptr<T[]> of POD T, and the named parameter must be an integer giving its length or a pointer to one which the function fills in.", problem)?,
+            ConvertError::InvalidArrayReturn(problem) => write!(f, "This function was listed in array_return! or null_terminated_array_return! but {}. For array_return!, it must return a pointer to POD T, and be given the name of an integer parameter, a pointer to one which the function fills in, or a function taking no parameters which returns the length. For null_terminated_array_return!, it must return a pointer to pointers.", problem)?,
+            ConvertError::UnsupportedStdFunction(ty) => write!(f, "This function takes a std::function whose signature uses the type {}. A std::function can be passed from Rust as a closure only if it takes and returns pointers, integers or other C types.", ty)?,
+            ConvertError::InvalidClosureCallback(problem) => write!(f, "This function was listed in closure_callback! but {}. The callback parameter must be a function pointer which takes a void* context, and the context parameter that void*.", problem)?,
+            ConvertError::OffloadedFunctionBorrows => write!(f, "This function was listed in offload! but takes or returns references or pointers. Offloaded functions run on another thread, so may only take and return owned values.")?,
         }
         Ok(())
     }
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use syn::Attribute;
+use syn::{parse_quote, Attribute, ForeignItem, Item, Lit, Meta, MetaNameValue};
 
 /// Returns the attribute (if any) which contains a doc comment.
 pub(super) fn get_doc_attr(attrs: &[Attribute]) -> Option<Attribute> {
@@ -21,3 +21,185 @@ pub(super) fn get_doc_attr(attrs: &[Attribute]) -> Option<Attribute> {
         .find(|a| a.path.get_ident().iter().any(|p| *p == "doc"))
         .cloned()
 }
+
+/// Rewrites the Doxygen in every doc comment within these bindgen items,
+/// as in [`doxygen_to_rustdoc`].
+pub(super) fn translate_doxygen(items: &mut [Item]) {
+    for item in items {
+        match item {
+            Item::Mod(itm) => {
+                if let Some((_, items)) = &mut itm.content {
+                    translate_doxygen(items);
+                }
+            }
+            Item::Struct(s) => {
+                translate_doc_attrs(&mut s.attrs);
+                for f in s.fields.iter_mut() {
+                    translate_doc_attrs(&mut f.attrs);
+                }
+            }
+            Item::Union(u) => {
+                translate_doc_attrs(&mut u.attrs);
+                for f in u.fields.named.iter_mut() {
+                    translate_doc_attrs(&mut f.attrs);
+                }
+            }
+            Item::Enum(e) => {
+                translate_doc_attrs(&mut e.attrs);
+                for v in e.variants.iter_mut() {
+                    translate_doc_attrs(&mut v.attrs);
+                }
+            }
+            Item::ForeignMod(fm) => {
+                for i in fm.items.iter_mut() {
+                    if let ForeignItem::Fn(f) = i {
+                        translate_doc_attrs(&mut f.attrs);
+                    }
+                }
+            }
+            Item::Type(t) => translate_doc_attrs(&mut t.attrs),
+            Item::Const(c) => translate_doc_attrs(&mut c.attrs),
+            _ => {}
+        }
+    }
+}
+
+fn translate_doc_attrs(attrs: &mut [Attribute]) {
+    for attr in attrs.iter_mut() {
+        if let Ok(Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(doc),
+            ..
+        })) = attr.parse_meta()
+        {
+            if path.is_ident("doc") {
+                let doc = doxygen_to_rustdoc(&doc.value());
+                *attr = parse_quote! { #[doc = #doc] };
+            }
+        }
+    }
+}
+
+/// The Doxygen tag, written `@tag` or `\tag`, with which this line starts,
+/// if it's one of `tags`, and what follows it.
+fn strip_tag<'a>(line: &'a str, tags: &[&str]) -> Option<&'a str> {
+    let line = line.strip_prefix('@').or_else(|| line.strip_prefix('\\'))?;
+    tags.iter().find_map(|tag| {
+        let rest = line.strip_prefix(tag)?;
+        if rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '[') {
+            Some(rest)
+        } else {
+            None
+        }
+    })
+}
+
+/// Turns the `@brief`, `@param` and `@return` tags of a Doxygen comment
+/// into Markdown: a list of parameters, and a sentence saying what's
+/// returned. A tag's description may continue on the following lines,
+/// up to a blank line or another tag. Other text is left alone.
+pub(super) fn doxygen_to_rustdoc(doc: &str) -> String {
+    enum Section {
+        Body,
+        Param,
+        Return,
+    }
+    let mut body: Vec<String> = Vec::new();
+    let mut params: Vec<String> = Vec::new();
+    let mut returns: Vec<String> = Vec::new();
+    let mut section = Section::Body;
+    let mut found_tags = false;
+    for line in doc.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = strip_tag(trimmed, &["param"]) {
+            // Skip any direction, as in @param[in].
+            let rest = match rest.strip_prefix('[') {
+                Some(rest) => rest.split_once(']').map(|(_, rest)| rest).unwrap_or(rest),
+                None => rest,
+            }
+            .trim();
+            let (name, description) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            params.push(format!("* `{}`: {}", name, description.trim()));
+            section = Section::Param;
+        } else if let Some(rest) = strip_tag(trimmed, &["returns", "return"]) {
+            returns.push(rest.trim().to_string());
+            section = Section::Return;
+        } else if let Some(rest) = strip_tag(trimmed, &["brief"]) {
+            body.push(format!(" {}", rest.trim()));
+            section = Section::Body;
+        } else {
+            let continued = match section {
+                _ if trimmed.is_empty() => None,
+                Section::Body => None,
+                Section::Param => params.last_mut(),
+                Section::Return => returns.last_mut(),
+            };
+            match continued {
+                Some(description) => {
+                    description.push(' ');
+                    description.push_str(trimmed);
+                }
+                None => {
+                    body.push(line.to_string());
+                    section = Section::Body;
+                }
+            }
+            continue;
+        }
+        found_tags = true;
+    }
+    if !found_tags {
+        return doc.to_string();
+    }
+    while body.last().map(|line| line.trim().is_empty()) == Some(true) {
+        body.pop();
+    }
+    if !params.is_empty() {
+        if !body.is_empty() {
+            body.push(String::new());
+        }
+        body.push(" # Parameters".to_string());
+        body.push(String::new());
+        body.extend(params.into_iter().map(|param| format!(" {}", param)));
+    }
+    for description in returns {
+        if !body.is_empty() {
+            body.push(String::new());
+        }
+        body.push(format!(" Returns {}", lowercase_first_word(&description)));
+    }
+    body.join("\n")
+}
+
+/// Lets a description such as "The area" follow "Returns", unless its
+/// first word looks like an acronym or a name, such as "HTTP".
+fn lowercase_first_word(description: &str) -> String {
+    let mut chars = description.chars();
+    match (chars.next(), chars.next()) {
+        (Some(first), Some(second)) if first.is_uppercase() && !second.is_uppercase() => first
+            .to_lowercase()
+            .chain(description[first.len_utf8()..].chars())
+            .collect(),
+        _ => description.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::doxygen_to_rustdoc;
+
+    #[test]
+    fn test_doxygen_to_rustdoc() {
+        assert_eq!(doxygen_to_rustdoc(" Elephants!"), " Elephants!");
+        assert_eq!(
+            doxygen_to_rustdoc(
+                " @brief Computes an area.\n\n @param[in] width The width,\n   in pixels.\n \\param height The height.\n @return The area."
+            ),
+            " Computes an area.\n\n # Parameters\n\n * `width`: The width, in pixels.\n * `height`: The height.\n\n Returns the area."
+        );
+        assert_eq!(
+            doxygen_to_rustdoc(" Counts.\n @returns ID of the thing\n\n More."),
+            " Counts.\n\n More.\n\n Returns ID of the thing"
+        );
+    }
+}
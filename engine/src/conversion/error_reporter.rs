@@ -100,6 +100,22 @@ pub(crate) fn convert_apis<FF, SF, EF, TF, A, B: 'static>(
             Api::StringConstructor { name } => {
                 Ok(Box::new(std::iter::once(Api::StringConstructor { name })))
             }
+            Api::CppAny { name, castables } => {
+                Ok(Box::new(std::iter::once(Api::CppAny { name, castables })))
+            }
+            Api::CppIterable {
+                name,
+                container,
+                element,
+                category,
+                is_set,
+            } => Ok(Box::new(std::iter::once(Api::CppIterable {
+                name,
+                container,
+                element,
+                category,
+                is_set,
+            }))),
             Api::Const { name, const_item } => {
                 Ok(Box::new(std::iter::once(Api::Const { name, const_item })))
             }
@@ -109,6 +125,9 @@ pub(crate) fn convert_apis<FF, SF, EF, TF, A, B: 'static>(
             Api::RustType { name, path } => {
                 Ok(Box::new(std::iter::once(Api::RustType { name, path })))
             }
+            Api::ExternCppType { name, path } => {
+                Ok(Box::new(std::iter::once(Api::ExternCppType { name, path })))
+            }
             Api::RustFn { name, sig, path } => {
                 Ok(Box::new(std::iter::once(Api::RustFn { name, sig, path })))
             }
@@ -39,11 +39,16 @@ use self::{
         allocators::create_alloc_and_frees,
         casts::add_casts,
         check_names,
+        fn_tables::create_fn_table_functions,
         fun::FnPhase,
         gc::filter_apis_by_following_edges_from_allowlist,
         pod::analyze_pod_apis,
+        refcount::create_refcount_functions,
         remove_ignored::filter_apis_by_ignored_dependents,
+        shared_from_this::create_shared_from_this_functions,
+        smart_pointer::create_smart_pointer_functions,
         tdef::convert_typedef_targets,
+        validity::create_validity_functions,
     },
     api::{AnalysisPhase, Api},
     codegen_rs::RsCodeGenerator,
@@ -110,17 +115,22 @@ impl<'a> BridgeConverter<'a> {
     ///
     /// This is really the heart of autocxx. It parses the output of `bindgen`
     /// (although really by "parse" we mean to interpret the structures already built
-    /// up by the `syn` crate).
+    /// up by the `syn` crate). `rtti` is whether the C++ will be built
+    /// with RTTI, without which we can't generate downcasts.
     pub(crate) fn convert(
         &self,
         mut bindgen_mod: ItemMod,
         unsafe_policy: UnsafePolicy,
         inclusions: String,
         cpp_codegen_options: &CppCodegenOptions,
+        rtti: bool,
     ) -> Result<CodegenResults, ConvertError> {
         match &mut bindgen_mod.content {
             None => Err(ConvertError::NoContent),
             Some((_, items)) => {
+                if self.config.translate_doxygen() {
+                    doc_attr::translate_doxygen(items);
+                }
                 // Parse the bindgen mod.
                 let items_to_process = items.drain(..).collect();
                 let parser = ParseBindgen::new(self.config);
@@ -142,16 +152,26 @@ impl<'a> BridgeConverter<'a> {
                 // by subsequent phases to work out which objects are POD.
                 let analyzed_apis = analyze_pod_apis(apis, self.config)?;
                 Self::dump_apis("pod analysis", &analyzed_apis);
-                let analyzed_apis = add_casts(analyzed_apis);
+                let analyzed_apis = add_casts(analyzed_apis, rtti);
                 let analyzed_apis = create_alloc_and_frees(analyzed_apis);
+                let analyzed_apis = create_refcount_functions(analyzed_apis, self.config);
+                let analyzed_apis = create_validity_functions(analyzed_apis, self.config);
+                let analyzed_apis = create_fn_table_functions(analyzed_apis, self.config);
+                let analyzed_apis = create_shared_from_this_functions(analyzed_apis);
+                let analyzed_apis = create_smart_pointer_functions(analyzed_apis, self.config);
                 // Next, figure out how we materialize different functions.
                 // Some will be simple entries in the cxx::bridge module; others will
                 // require C++ wrapper functions. This is probably the most complex
                 // part of `autocxx`. Again, this returns a new set of `Api`s, but
                 // parameterized by a richer set of metadata.
                 Self::dump_apis("adding casts", &analyzed_apis);
-                let analyzed_apis =
-                    FnAnalyzer::analyze_functions(analyzed_apis, unsafe_policy, self.config);
+                let analyzed_apis = FnAnalyzer::analyze_functions(
+                    analyzed_apis,
+                    unsafe_policy,
+                    self.config,
+                    cpp_codegen_options.trace_calls,
+                    cpp_codegen_options.rename_methods.as_deref(),
+                );
                 // If any of those functions turned out to be pure virtual, don't attempt
                 // to generate UniquePtr implementations for the type, since it can't
                 // be instantiated.
@@ -188,6 +208,7 @@ impl<'a> BridgeConverter<'a> {
                     self.include_list,
                     bindgen_mod,
                     self.config,
+                    cpp.is_some(),
                 );
                 Ok(CodegenResults { rs, cpp })
             }
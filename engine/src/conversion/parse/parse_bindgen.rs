@@ -19,6 +19,8 @@ use crate::{
         api::{Api, ApiName, StructDetails, SubclassName, TypedefKind, UnanalyzedApi},
         ConvertError,
     },
+    types::make_helper_names,
+    types::make_ident,
     types::Namespace,
     types::QualifiedName,
 };
@@ -111,6 +113,66 @@ impl<'a> ParseBindgen<'a> {
                 path: path.clone(),
             }
         }));
+        if !self.config.get_any_castables().is_empty() {
+            self.apis.push(Api::CppAny {
+                name: ApiName::new_in_root_namespace(make_ident(
+                    self.config.get_any_helper_prefix(),
+                )),
+                castables: self
+                    .config
+                    .get_any_castables()
+                    .iter()
+                    .map(|ty| QualifiedName::new_from_cpp_name(ty))
+                    .collect(),
+            });
+        }
+        let iterable_prefix = self.config.get_iterable_helper_prefix();
+        let containers: Vec<_> = self
+            .config
+            .get_iterables()
+            .iter()
+            .map(|iterable| QualifiedName::new_from_cpp_name(&iterable.container))
+            .collect();
+        let helper_names = make_helper_names(&containers);
+        self.apis.extend(
+            self.config
+                .get_iterables()
+                .iter()
+                .zip(containers.into_iter().zip(helper_names))
+                .map(|(iterable, (container, helper_name))| Api::CppIterable {
+                    name: ApiName::new_in_root_namespace(make_ident(format!(
+                        "{}_{}",
+                        iterable_prefix, helper_name
+                    ))),
+                    container,
+                    element: QualifiedName::new_from_cpp_name(&iterable.element),
+                    category: iterable.category,
+                    is_set: iterable.is_set,
+                }),
+        );
+    }
+
+    /// If a type was already bound by another `include_cpp!` block, or a
+    /// hand-written `cxx::bridge`, an API referring to it there.
+    fn reused_type(&self, name: &ApiName) -> Option<UnanalyzedApi> {
+        let cpp_name = name.name.to_cpp_name();
+        if let Some(path) = self.config.get_extern_cxx_type(&cpp_name) {
+            return Some(UnanalyzedApi::ExternCppType {
+                name: name.clone(),
+                path: path.clone(),
+            });
+        }
+        let module = self.config.get_reused_type_module(&cpp_name)?;
+        let path = name
+            .name
+            .ns_segment_iter()
+            .map(make_ident)
+            .chain(std::iter::once(name.name.get_final_ident()))
+            .fold(module.clone(), |path, id| path.append(id));
+        Some(UnanalyzedApi::ExternCppType {
+            name: name.clone(),
+            path,
+        })
     }
 
     fn find_items_in_root(items: Vec<Item>) -> Result<Vec<Item>, ConvertError> {
@@ -144,7 +206,7 @@ impl<'a> ParseBindgen<'a> {
             });
         }
         self.apis.append(&mut more_apis);
-        mod_converter.finished(&mut self.apis);
+        mod_converter.finished(&mut self.apis, self.config);
     }
 
     fn parse_item(
@@ -169,6 +231,8 @@ impl<'a> ParseBindgen<'a> {
                 let name = api_name_qualified(ns, s.ident.clone(), &annotations)?;
                 let api = if ns.is_empty() && self.config.is_rust_type(&s.ident) {
                     None
+                } else if let Some(api) = self.reused_type(&name) {
+                    Some(api)
                 } else if is_forward_declaration {
                     Some(UnanalyzedApi::ForwardDeclaration { name })
                 } else {
@@ -195,9 +259,21 @@ impl<'a> ParseBindgen<'a> {
             }
             Item::Enum(e) => {
                 let annotations = BindgenSemanticAttributes::new(&e.attrs);
-                let api = UnanalyzedApi::Enum {
-                    name: api_name_qualified(ns, e.ident.clone(), &annotations)?,
-                    item: e,
+                let mut name = api_name_qualified(ns, e.ident.clone(), &annotations)?;
+                // C++ names an enum nested in a class template by way of the
+                // typedef for the instantiation listed in template_enum!.
+                let generic_name = if ns.is_empty() {
+                    name.cpp_name()
+                } else {
+                    format!("{}::{}", ns, name.cpp_name())
+                };
+                if let Some(probe) = self.config.template_enum_probe_for(&generic_name) {
+                    let typedef = probe.rsplit("::").next().unwrap().to_string();
+                    name = ApiName::new_with_cpp_name(ns, e.ident.clone(), Some(typedef));
+                }
+                let api = match self.reused_type(&name) {
+                    Some(api) => api,
+                    None => UnanalyzedApi::Enum { name, item: e },
                 };
                 if !self.config.is_on_blocklist(&api.name().to_cpp_name()) {
                     self.apis.push(api);
@@ -277,7 +353,16 @@ impl<'a> ParseBindgen<'a> {
                 }
                 Ok(())
             }
-            Item::Const(const_item) => {
+            Item::Const(mut const_item) => {
+                let id = const_item.ident.to_string();
+                if self.config.is_macro_const(&id) {
+                    // bindgen may have evaluated this macro itself, but so
+                    // has our probe, which we'll bind under its name below.
+                    return Ok(());
+                }
+                if let Some(macro_name) = self.config.macro_for_probe(&id) {
+                    const_item.ident = make_ident(macro_name);
+                }
                 let annotations = BindgenSemanticAttributes::new(&const_item.attrs);
                 self.apis.push(UnanalyzedApi::Const {
                     name: api_name(ns, const_item.ident.clone(), &annotations),
@@ -314,11 +399,34 @@ impl<'a> ParseBindgen<'a> {
             .iter()
             .map(|api| api.name().to_cpp_name())
             .collect();
-        for generate_directive in self.config.must_generate_list() {
-            if !api_names.contains(&generate_directive) {
-                return Err(ConvertError::DidNotGenerateAnything(generate_directive));
+        // Report all the missing items which came from elsewhere, such as
+        // a list file, together with where they came from. There may be
+        // many of them, whereas generate! directives are usually few.
+        let (listed, written): (Vec<_>, Vec<_>) = self
+            .config
+            .must_generate_list()
+            .filter(|generate_directive| !api_names.contains(generate_directive))
+            .partition(|generate_directive| {
+                self.config
+                    .get_generate_origin(generate_directive)
+                    .is_some()
+            });
+        if !listed.is_empty() {
+            return Err(ConvertError::DidNotGenerateListedItems(
+                listed
+                    .into_iter()
+                    .map(|name| {
+                        let origin = self.config.get_generate_origin(&name).unwrap().to_string();
+                        (name, origin)
+                    })
+                    .collect(),
+            ));
+        }
+        match written.into_iter().next() {
+            Some(generate_directive) => {
+                Err(ConvertError::DidNotGenerateAnything(generate_directive))
             }
+            None => Ok(()),
         }
-        Ok(())
     }
 }
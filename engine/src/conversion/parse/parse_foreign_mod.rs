@@ -22,10 +22,14 @@ use crate::conversion::{
 };
 use crate::{
     conversion::ConvertError,
-    types::{Namespace, QualifiedName},
+    types::{make_ident, Namespace, QualifiedName},
 };
+use autocxx_parser::IncludeCppConfig;
 use std::collections::HashMap;
-use syn::{Block, Expr, ExprCall, ForeignItem, Ident, ImplItem, ItemImpl, Stmt, Type};
+use syn::{
+    Attribute, Block, Expr, ExprCall, FnArg, ForeignItem, Ident, ImplItem, ItemImpl, Lit, Meta,
+    MetaNameValue, Pat, Stmt, Type,
+};
 
 use super::bindgen_semantic_attributes::BindgenSemanticAttributes;
 
@@ -92,6 +96,7 @@ impl ParseForeignMod {
                     synthesized_this_type: None,
                     add_to_trait: None,
                     is_deleted: annotations.has_attr("deleted"),
+                    link_name: get_link_name(&item.attrs),
                     synthetic_cpp: None,
                 });
                 Ok(())
@@ -131,11 +136,20 @@ impl ParseForeignMod {
     /// Indicate that all foreign mods and all impl blocks have been
     /// fed into us, and we should process that information to generate
     /// the resulting APIs.
-    pub(crate) fn finished(mut self, apis: &mut Vec<UnanalyzedApi>) {
+    pub(crate) fn finished(mut self, apis: &mut Vec<UnanalyzedApi>, config: &IncludeCppConfig) {
         apis.append(&mut self.ignored_apis);
         while !self.funcs_to_convert.is_empty() {
             let mut fun = self.funcs_to_convert.remove(0);
             fun.self_ty = self.method_receivers.get(&fun.ident).cloned();
+            let cpp_name = fun
+                .original_name
+                .clone()
+                .unwrap_or_else(|| fun.ident.to_string());
+            if let Some(inherited) = config.inherited_method_for_probe(
+                &QualifiedName::new(&self.ns, make_ident(cpp_name)).to_cpp_name(),
+            ) {
+                make_inherited_method(&mut fun, &inherited.method);
+            }
             apis.push(UnanalyzedApi::Function {
                 name: ApiName::new_with_cpp_name(
                     &self.ns,
@@ -150,6 +164,35 @@ impl ParseForeignMod {
     }
 }
 
+/// Turns the C++ function generated to call a method listed in
+/// `inherited_method!` into that method, just as if bindgen had found it
+/// in the class: its first parameter, the object, becomes `this`.
+fn make_inherited_method(fun: &mut FuncToConvert, method: &str) {
+    if let Some(FnArg::Typed(pt)) = fun.inputs.first_mut() {
+        if let Pat::Ident(pp) = pt.pat.as_mut() {
+            fun.references.ref_params.remove(&pp.ident);
+            pp.ident = make_ident("this");
+        }
+    }
+    fun.original_name = Some(method.to_string());
+}
+
+/// The symbol given by a `#[link_name]` attribute. bindgen starts it with
+/// `\u{1}` to say that the compiler shouldn't add a platform prefix.
+fn get_link_name(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match attr.parse_meta().ok()? {
+        Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(name),
+            ..
+        }) if path.is_ident("link_name") => {
+            let name = name.value();
+            Some(name.strip_prefix('\u{1}').unwrap_or(&name).to_string())
+        }
+        _ => None,
+    })
+}
+
 /// bindgen sometimes generates an impl fn called a which calls
 /// a function called a1(), if it's dealing with conflicting names.
 /// We actually care about the name a1, so we have to parse the
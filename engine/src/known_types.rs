@@ -27,6 +27,8 @@ enum Behavior {
     CxxContainerByValueSafe,
     CxxContainerNotByValueSafe,
     CxxString,
+    CxxStringView,
+    CxxFilesystemPath,
     RustStr,
     RustString,
     RustByValue,
@@ -76,6 +78,8 @@ impl TypeDetails {
             Behavior::RustString
             | Behavior::RustStr
             | Behavior::CxxString
+            | Behavior::CxxStringView
+            | Behavior::CxxFilesystemPath
             | Behavior::CxxContainerByValueSafe
             | Behavior::CxxContainerNotByValueSafe
             | Behavior::RustContainerByValueSafe => {
@@ -208,6 +212,8 @@ impl TypeDatabase {
                         | Behavior::CVariableLengthByValue
                         | Behavior::RustContainerByValueSafe => true,
                         Behavior::CxxString
+                        | Behavior::CxxStringView
+                        | Behavior::CxxFilesystemPath
                         | Behavior::CxxContainerNotByValueSafe
                         | Behavior::CVoid => false,
                     },
@@ -272,11 +278,14 @@ impl TypeDatabase {
     }
 
     /// Get the list of types to give to bindgen to ask it _not_ to
-    /// generate code for.
+    /// generate code for. That's those for which we supply a simpler
+    /// replacement in the prelude, and also std::byte, which bindgen would
+    /// otherwise generate as a (useless) empty enum.
     pub(crate) fn get_initial_blocklist(&self) -> impl Iterator<Item = &str> + '_ {
         self.by_rs_name
             .iter()
             .filter_map(|(_, td)| td.get_prelude_entry().map(|_| td.cpp_name.as_str()))
+            .chain(std::iter::once("std::byte"))
     }
 
     /// Whether this is one of the ctypes (mostly variable length integers)
@@ -292,6 +301,20 @@ impl TypeDatabase {
             .unwrap_or(false)
     }
 
+    /// Whether a parameter of this type may be passed as any number which
+    /// C++ would losslessly promote to it: this is so for the ctypes
+    /// integers, and `double`. `std::byte` isn't a number, so it has to be
+    /// passed as a `c_byte`.
+    pub(crate) fn is_promotable(&self, ty: &QualifiedName) -> bool {
+        !self.is_byte(ty)
+            && self
+                .get(ty)
+                .map(|td| {
+                    matches!(td.behavior, Behavior::CVariableLengthByValue) || td.rs_name == "f64"
+                })
+                .unwrap_or(false)
+    }
+
     /// Whether this is a generic type acceptable to cxx. Otherwise,
     /// if we encounter a generic, we'll replace it with a synthesized concrete
     /// type.
@@ -316,6 +339,31 @@ impl TypeDatabase {
             .unwrap_or(false)
     }
 
+    /// Whether this is std::string_view, which we never expose to Rust
+    /// directly: parameters are passed as &str and return values
+    /// come back as &[u8].
+    pub(crate) fn is_string_view(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|x| matches!(x.behavior, Behavior::CxxStringView))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is std::filesystem::path, which we never expose to
+    /// Rust directly: parameters are passed as &Path and return values
+    /// come back as PathBuf.
+    pub(crate) fn is_filesystem_path(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|x| matches!(x.behavior, Behavior::CxxFilesystemPath))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is std::byte, which in slices we expose as `u8`.
+    pub(crate) fn is_byte(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|x| x.cpp_name == "std::byte")
+            .unwrap_or(false)
+    }
+
     fn insert(&mut self, td: TypeDetails) {
         let rs_name = td.to_typename();
         if let Some(extra_non_canonical_name) = &td.extra_non_canonical_name {
@@ -374,6 +422,22 @@ fn create_type_database() -> TypeDatabase {
         true,
         true,
     ));
+    db.insert(TypeDetails::new(
+        "std::string_view",
+        "std::string_view",
+        Behavior::CxxStringView,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "std::filesystem::path",
+        "std::filesystem::path",
+        Behavior::CxxFilesystemPath,
+        None,
+        true,
+        true,
+    ));
     db.insert(TypeDetails::new(
         "str",
         "rust::Str",
@@ -496,6 +560,14 @@ fn create_type_database() -> TypeDatabase {
         true,
         true,
     ));
+    db.insert(TypeDetails::new(
+        "autocxx::c_byte",
+        "std::byte",
+        Behavior::CVariableLengthByValue,
+        None,
+        true,
+        true,
+    ));
     db.insert(TypeDetails::new(
         "autocxx::c_void",
         "void",
@@ -509,7 +581,34 @@ fn create_type_database() -> TypeDatabase {
 
 pub(crate) fn ensure_pointee_is_valid(ptr: &TypePtr) -> Result<(), ConvertError> {
     match *ptr.elem {
-        Type::Path(..) => Ok(()),
+        // A pointer to a pointer is fine so long as the inner pointer is,
+        // which the caller checks when it converts the inner pointer.
+        Type::Path(..) | Type::Ptr(..) => Ok(()),
         _ => Err(ConvertError::InvalidPointee),
     }
 }
+
+/// std::string_view is only understood as a plain parameter or return
+/// value; reject it as a pointee, a mutable referent or a template
+/// argument.
+pub(crate) fn ensure_not_string_view(ty: &Type) -> Result<(), ConvertError> {
+    match ty {
+        Type::Path(typ) if known_types().is_string_view(&QualifiedName::from_type_path(typ)) => {
+            Err(ConvertError::StringViewNotByValue)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Likewise, std::filesystem::path is only understood as a parameter
+/// taken by value or const reference, or as a return value.
+pub(crate) fn ensure_not_filesystem_path(ty: &Type) -> Result<(), ConvertError> {
+    match ty {
+        Type::Path(typ)
+            if known_types().is_filesystem_path(&QualifiedName::from_type_path(typ)) =>
+        {
+            Err(ConvertError::FilesystemPathNotByValue)
+        }
+        _ => Ok(()),
+    }
+}
@@ -27,12 +27,19 @@ mod known_types;
 mod parse_callbacks;
 mod parse_file;
 mod rust_pretty_printer;
+mod snake_case;
+mod target;
 mod types;
 
 #[cfg(any(test, feature = "build"))]
 mod builder;
+#[cfg(any(test, feature = "build"))]
+mod compile_commands;
 
-use autocxx_parser::{IncludeCppConfig, UnsafePolicy};
+use autocxx_parser::{
+    inherited_method_probe_name, macro_probe_name, thread_local_probe_name, IncludeCppConfig,
+    UnsafePolicy,
+};
 use conversion::BridgeConverter;
 use parse_callbacks::AutocxxParseCallbacks;
 use parse_file::CppBuildable;
@@ -44,6 +51,7 @@ use std::{
     path::Path,
     process::{Command, Stdio},
 };
+use target::TargetProperties;
 use tempfile::NamedTempFile;
 
 use quote::ToTokens;
@@ -65,9 +73,12 @@ use autocxx_bindgen as bindgen;
 pub use builder::{
     Builder, BuilderBuild, BuilderContext, BuilderError, BuilderResult, BuilderSuccess,
 };
-pub use parse_file::{parse_file, ParseError, ParsedFile};
+#[cfg(any(test, feature = "build"))]
+pub use compile_commands::CompileCommandsError;
+pub use parse_file::{parse_file, parse_file_with_generate_lists, ParseError, ParsedFile};
 
 pub use cxx_gen::HEADER;
+pub use snake_case::to_snake_case;
 
 /// Re-export cxx such that clients can use the same version as
 /// us. This doesn't enable clients to avoid depending on the cxx
@@ -273,13 +284,140 @@ impl IncludeCppEngine {
     }
 
     fn build_header(&self) -> String {
-        join(
+        let mut header = join(
             self.config
                 .inclusions
                 .iter()
                 .map(|path| format!("#include \"{}\"\n", path)),
             "",
-        )
+        );
+        // Macros are invisible to bindgen unless they expand to a literal,
+        // so evaluate each macro we've been asked for in a C++ item which
+        // bindgen can see.
+        for name in self.config.get_macro_consts() {
+            header.push_str(&format!(
+                "constexpr auto {} = ({});\n",
+                macro_probe_name(name),
+                name
+            ));
+        }
+        for function in self.config.get_macro_functions() {
+            let params = join(
+                function
+                    .param_types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| format!("{} arg{}", ty, i)),
+                ", ",
+            );
+            let args = join(
+                (0..function.param_types.len()).map(|i| format!("arg{}", i)),
+                ", ",
+            );
+            header.push_str(&format!(
+                "inline {} {}({}) {{ return {}({}); }}\n",
+                function.return_type,
+                macro_probe_name(&function.name),
+                params,
+                function.name,
+                args
+            ));
+        }
+        // bindgen can't bind a thread_local variable, so give each one
+        // we've been asked for a function which returns the address of the
+        // calling thread's instance. That also initializes it, if this is
+        // the first time this thread has used it.
+        for var in self.config.get_thread_local_globals() {
+            header.push_str(&in_namespaces(&thread_local_probe_name(var), |probe| {
+                format!(
+                    "inline decltype(&{}) {}() {{ return &{}; }}",
+                    var, probe, var
+                )
+            }));
+        }
+        // bindgen can't tell us about function templates, and can't always
+        // tell which of a function's overloads is which, so give each
+        // overload we've been asked to pick a function which calls it
+        // through a function pointer of the right type. That picks the
+        // overload, or instantiates the template, which matches exactly.
+        for (probe, selected) in self.config.get_selected_overloads() {
+            let params = join(
+                selected
+                    .param_types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| format!("{} arg{}", ty, i)),
+                ", ",
+            );
+            let args = join(
+                (0..selected.param_types.len()).map(|i| format!("arg{}", i)),
+                ", ",
+            );
+            header.push_str(&in_namespaces(&probe, |probe| {
+                format!(
+                    "inline {} {}({}) {{ return static_cast<{}(*)({})>(&{})({}); }}",
+                    selected.return_type,
+                    probe,
+                    params,
+                    selected.return_type,
+                    selected.param_types.join(", "),
+                    selected.function,
+                    args
+                )
+            }));
+        }
+        // A function template which takes explicit template arguments may
+        // not be instantiable through any function pointer type, since not
+        // all of them can be deduced, so declare a function with the type
+        // of each instantiation we've been asked for. That's never defined;
+        // the C++ wrapper calls the instantiation itself.
+        for (probe, named) in self.config.get_named_instantiations() {
+            header.push_str(&in_namespaces(&probe, |probe| {
+                format!("decltype({}) {};", named.instantiated_name(), probe)
+            }));
+        }
+        // bindgen describes an enum nested in a class template as a single
+        // enum, which C++ can only name within some instantiation, so give
+        // each instantiation we've been asked for a typedef.
+        for (probe, instantiated) in self.config.get_template_enums() {
+            header.push_str(&in_namespaces(&probe, |probe| {
+                format!("typedef {} {};", instantiated, probe)
+            }));
+        }
+        // bindgen doesn't describe the methods of a class template's
+        // instantiations, so can't tell us about those which a class
+        // inherits from one, as it does from a CRTP base. Give each we've
+        // been asked for a function which calls it, which we'll turn back
+        // into a method.
+        for inherited in self.config.get_inherited_methods() {
+            let probe = inherited_method_probe_name(&inherited.class, &inherited.method);
+            let params = join(
+                std::iter::once(format!(
+                    "{}::{}& autocxx_self",
+                    if inherited.is_const { "const " } else { "" },
+                    inherited.class
+                ))
+                .chain(
+                    inherited
+                        .param_types
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| format!("{} arg{}", ty, i)),
+                ),
+                ", ",
+            );
+            let args = join(
+                (0..inherited.param_types.len()).map(|i| format!("arg{}", i)),
+                ", ",
+            );
+            header.push_str(&in_namespaces(&probe, |probe| {
+                format!(
+                    "inline {} {}({}) {{ return autocxx_self.{}({}); }}",
+                    inherited.return_type, probe, params, inherited.method, args
+                )
+            }));
+        }
+        header
     }
 
     fn make_bindgen_builder(
@@ -380,6 +518,10 @@ impl IncludeCppEngine {
             State::Generated(_) => panic!("Only call generate once"),
         }
 
+        let target = TargetProperties::new(extra_clang_args);
+        self.config
+            .apply_target(target.pointer_width, target.big_endian);
+
         let mod_name = self.config.get_mod_name();
         let mut builder = self.make_bindgen_builder(&inc_dirs, extra_clang_args);
         if let Some(dep_recorder) = dep_recorder {
@@ -402,6 +544,7 @@ impl IncludeCppEngine {
                 self.config.unsafe_policy.clone(),
                 header_contents,
                 cpp_codegen_options,
+                rtti_enabled(extra_clang_args),
             )
             .map_err(Error::Conversion)?;
         let mut items = conversion.rs;
@@ -597,6 +740,33 @@ pub fn make_clang_args<'a>(
         .chain(extra_args.iter().map(|s| s.to_string()))
 }
 
+/// Whether the C++ will be built with RTTI, going by the last clang flag
+/// which turns it on or off. It's on by default.
+fn rtti_enabled(extra_clang_args: &[&str]) -> bool {
+    extra_clang_args
+        .iter()
+        .rev()
+        .find(|arg| matches!(**arg, "-frtti" | "-fno-rtti" | "/GR" | "/GR-"))
+        .map(|arg| *arg == "-frtti" || *arg == "/GR")
+        .unwrap_or(true)
+}
+
+/// A line of C++ declaring an item with a namespace-qualified name, such
+/// as `ns::item`, inside that namespace. `make_item` declares it given its
+/// unqualified name.
+fn in_namespaces(qualified_name: &str, make_item: impl FnOnce(&str) -> String) -> String {
+    let (namespaces, name) = match qualified_name.rsplit_once("::") {
+        Some((ns, name)) => (ns.split("::").collect(), name),
+        None => (Vec::new(), qualified_name),
+    };
+    let mut item = make_item(name);
+    for ns in namespaces.iter().rev() {
+        item = format!("namespace {} {{ {} }}", ns, item);
+    }
+    item.push('\n');
+    item
+}
+
 /// Preprocess a file using the same options
 /// as is used by autocxx. Input: listing_path, output: preprocess_path.
 pub fn preprocess(
@@ -630,6 +800,9 @@ pub fn get_clang_path() -> String {
         .unwrap_or_else(|_| "clang++".to_string())
 }
 
+/// A function to rename C++ methods. See [`CppCodegenOptions::rename_methods`].
+pub type MethodRenamer = dyn Fn(&str) -> String;
+
 /// Options for C++ codegen
 #[derive(Default)]
 pub struct CppCodegenOptions {
@@ -647,4 +820,21 @@ pub struct CppCodegenOptions {
     /// Whether to skip using [`cxx_gen`] to generate the C++ code,
     /// so that some other process can handle that.
     pub skip_cxx_gen: bool,
+    /// Preprocessor symbols (and optional values) which were defined
+    /// when the bindings were generated. The generated header will refuse
+    /// to compile if they're not also defined when it is built.
+    pub required_defines: Vec<(String, Option<String>)>,
+    /// Whether to generate Rust wrappers for every function, such that each
+    /// call is logged if the `autocxx` crate's `trace_calls` feature is enabled.
+    pub trace_calls: bool,
+    /// Optionally, a function to rename C++ methods, given the Rust name
+    /// bindgen gave each. That's usually its C++ name, but one which is a
+    /// Rust keyword has had `_` appended, for instance. Constructors and
+    /// operators keep their usual names.
+    pub rename_methods: Option<Box<MethodRenamer>>,
+    /// Whether to avoid generating `static_assert`s that the C++ `bool`
+    /// and `char` types have the size and signedness the bindings assume
+    /// for the Rust target. You may need this if the target isn't one
+    /// autocxx knows about.
+    pub suppress_abi_checks: bool,
 }
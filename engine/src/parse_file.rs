@@ -49,6 +49,8 @@ pub enum ParseError {
     ZeroModsForDynamicDiscovery,
     MultipleModsForDynamicDiscovery,
     DiscoveredRustItemsWhenNotInAutoDiscover,
+    /// A file listing items to generate couldn't be read.
+    GenerateListRead(PathBuf, std::io::Error),
 }
 
 impl Display for ParseError {
@@ -67,6 +69,8 @@ impl Display for ParseError {
                 write!(f, "This file contains extra information to append to an include_cpp! but multiple such include_cpp! declarations were found in this file.")?,
             ParseError::DiscoveredRustItemsWhenNotInAutoDiscover =>
                 write!(f, "This file contains extra information to append to an \"extern Rust\" but auto-discover was switched off.")?,
+            ParseError::GenerateListRead(path, err) =>
+                write!(f, "Unable to read the list of items to generate from {}: {}", path.to_string_lossy(), err)?,
         }
         Ok(())
     }
@@ -77,16 +81,55 @@ pub fn parse_file<P1: AsRef<Path>>(
     rs_file: P1,
     auto_allowlist: bool,
 ) -> Result<ParsedFile, ParseError> {
+    parse_file_with_generate_lists(rs_file, auto_allowlist, &[])
+}
+
+/// Parse a Rust file, and spot any include_cpp macros within it. Then
+/// act as if the include_cpp macro contained a `generate!` directive
+/// for every name in the given list files. These list one fully-qualified
+/// C++ name per line; blank lines and lines starting with `#` are ignored.
+/// If there are any such names, the file must contain exactly one
+/// include_cpp macro.
+pub fn parse_file_with_generate_lists<P1: AsRef<Path>>(
+    rs_file: P1,
+    auto_allowlist: bool,
+    generate_lists: &[PathBuf],
+) -> Result<ParsedFile, ParseError> {
+    let mut generates = Vec::new();
+    for generate_list in generate_lists {
+        generates.extend(read_generate_list(generate_list)?);
+    }
     let mut source = String::new();
     let mut file = std::fs::File::open(rs_file).map_err(ParseError::FileOpen)?;
     file.read_to_string(&mut source)
         .map_err(ParseError::FileRead)?;
     proc_macro2::fallback::force();
     let source = syn::parse_file(&source).map_err(ParseError::Syntax)?;
-    parse_file_contents(source, auto_allowlist)
+    parse_file_contents(source, auto_allowlist, generates)
+}
+
+/// Reads the names from a list file, along with where each came from.
+fn read_generate_list(path: &Path) -> Result<Vec<(String, String)>, ParseError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| ParseError::GenerateListRead(path.to_path_buf(), err))?;
+    Ok(parse_generate_list(&content, &path.to_string_lossy()))
+}
+
+fn parse_generate_list(content: &str, list_name: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(idx, line)| (line.to_string(), format!("{} line {}", list_name, idx + 1)))
+        .collect()
 }
 
-fn parse_file_contents(source: syn::File, auto_allowlist: bool) -> Result<ParsedFile, ParseError> {
+fn parse_file_contents(
+    source: syn::File,
+    auto_allowlist: bool,
+    mut generates: Vec<(String, String)>,
+) -> Result<ParsedFile, ParseError> {
     #[derive(Default)]
     struct State {
         auto_allowlist: bool,
@@ -201,7 +244,10 @@ fn parse_file_contents(source: syn::File, auto_allowlist: bool) -> Result<Parsed
     {
         return Err(ParseError::DiscoveredRustItemsWhenNotInAutoDiscover);
     }
-    if !extra_superclasses.is_empty() || (auto_allowlist && !discoveries.is_empty()) {
+    if !extra_superclasses.is_empty()
+        || (auto_allowlist && !discoveries.is_empty())
+        || !generates.is_empty()
+    {
         let mut autocxx_seg_iterator = results.iter_mut().filter_map(|seg| match seg {
             Segment::Autocxx(engine) => Some(engine),
             _ => None,
@@ -223,6 +269,14 @@ fn parse_file_contents(source: syn::File, auto_allowlist: bool) -> Result<Parsed
                             .map_err(ParseError::Syntax)?;
                     }
                 }
+                for (name, _) in &generates {
+                    engine
+                        .config_mut()
+                        .allowlist
+                        .push(LitStr::new(name, Span::call_site()))
+                        .map_err(ParseError::Syntax)?;
+                }
+                engine.config_mut().generate_origins.append(&mut generates);
                 engine
                     .config_mut()
                     .extern_rust_funs
@@ -0,0 +1,86 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Converts a C++ name in `PascalCase`, `camelCase` or `SCREAMING_CASE`
+/// to `snake_case`. A run of capitals is treated as one word, so
+/// `HTTPClient` becomes `http_client`. This is intended for use with
+/// `Builder::rename_methods`.
+pub fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map(|n| n.is_lowercase()) == Some(true);
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// The Rust name for a getter or setter of a type listed in `properties!`:
+/// `getValue` becomes `value`, `setValue` becomes `set_value`, and
+/// `isValid` and `hasValue` become `is_valid` and `has_value`. Returns
+/// `None` for anything else.
+pub(crate) fn property_accessor_name(name: &str) -> Option<String> {
+    let name = to_snake_case(name);
+    if let Some(property) = name.strip_prefix("get_") {
+        Some(property.to_string())
+    } else if ["set_", "is_", "has_"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{property_accessor_name, to_snake_case};
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("GetValue"), "get_value");
+        assert_eq!(to_snake_case("getValue"), "get_value");
+        assert_eq!(to_snake_case("get_value"), "get_value");
+        assert_eq!(to_snake_case("HTTPClient"), "http_client");
+        assert_eq!(to_snake_case("GetHTTP"), "get_http");
+        assert_eq!(to_snake_case("READ_WRITE"), "read_write");
+        assert_eq!(to_snake_case("kRead2Bit"), "k_read2_bit");
+    }
+
+    #[test]
+    fn test_property_accessor_name() {
+        assert_eq!(property_accessor_name("getValue").unwrap(), "value");
+        assert_eq!(property_accessor_name("GetValue").unwrap(), "value");
+        assert_eq!(property_accessor_name("setValue").unwrap(), "set_value");
+        assert_eq!(property_accessor_name("isEnabled").unwrap(), "is_enabled");
+        assert_eq!(
+            property_accessor_name("hasChildren").unwrap(),
+            "has_children"
+        );
+        assert_eq!(property_accessor_name("get_value").unwrap(), "value");
+        assert!(property_accessor_name("getter").is_none());
+        assert!(property_accessor_name("get").is_none());
+        assert!(property_accessor_name("reset").is_none());
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The properties of the target on which `generate_if!` directives may
+/// depend.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TargetProperties {
+    pub(crate) pointer_width: u32,
+    pub(crate) big_endian: bool,
+}
+
+impl TargetProperties {
+    /// The target for which the C++ is analyzed. That's the one given to
+    /// clang by a `--target` argument, if there is one. Otherwise bindgen
+    /// uses the target Cargo is building for, which a build script can
+    /// find from its environment; failing that, it's the host.
+    pub(crate) fn new(extra_clang_args: &[&str]) -> Self {
+        if let Some(triple) = clang_target(extra_clang_args) {
+            return Self::from_triple(triple);
+        }
+        let pointer_width = std::env::var("CARGO_CFG_TARGET_POINTER_WIDTH")
+            .ok()
+            .and_then(|width| width.parse().ok());
+        let endian = std::env::var("CARGO_CFG_TARGET_ENDIAN").ok();
+        Self {
+            pointer_width: pointer_width.unwrap_or(usize::BITS),
+            big_endian: match endian.as_deref() {
+                Some(endian) => endian == "big",
+                None => cfg!(target_endian = "big"),
+            },
+        }
+    }
+
+    fn from_triple(triple: &str) -> Self {
+        let arch = triple.split('-').next().unwrap_or_default();
+        let pointer_width = if triple.ends_with("gnux32") || triple.contains("ilp32") {
+            32
+        } else if matches!(arch, "avr" | "msp430") {
+            16
+        } else if arch.contains("64") || matches!(arch, "s390x" | "sparcv9" | "bpfel" | "bpfeb") {
+            64
+        } else {
+            32
+        };
+        let big_endian = matches!(
+            arch,
+            "powerpc"
+                | "powerpc64"
+                | "ppc"
+                | "ppc64"
+                | "s390x"
+                | "mips"
+                | "mips64"
+                | "sparc"
+                | "sparc64"
+                | "sparcv9"
+                | "m68k"
+                | "bpfeb"
+        ) || arch.ends_with("_be")
+            || arch.starts_with("armeb")
+            || arch.starts_with("thumbeb");
+        Self {
+            pointer_width,
+            big_endian,
+        }
+    }
+}
+
+/// The target triple given to clang, if any. The last one wins.
+fn clang_target<'a>(extra_clang_args: &[&'a str]) -> Option<&'a str> {
+    let mut target = None;
+    let mut args = extra_clang_args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(triple) = arg
+            .strip_prefix("--target=")
+            .or_else(|| arg.strip_prefix("-target="))
+        {
+            target = Some(triple);
+        } else if *arg == "--target" || *arg == "-target" {
+            target = args.next().copied().or(target);
+        }
+    }
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TargetProperties;
+
+    fn props(pointer_width: u32, big_endian: bool) -> TargetProperties {
+        TargetProperties {
+            pointer_width,
+            big_endian,
+        }
+    }
+
+    #[test]
+    fn test_clang_target() {
+        assert_eq!(
+            TargetProperties::new(&["-x", "c++", "--target=i686-unknown-linux-gnu"]),
+            props(32, false)
+        );
+        assert_eq!(
+            TargetProperties::new(&["-target", "powerpc64-unknown-linux-gnu"]),
+            props(64, true)
+        );
+        assert_eq!(
+            TargetProperties::new(&[
+                "--target=powerpc64-unknown-linux-gnu",
+                "--target=x86_64-unknown-linux-gnu"
+            ]),
+            props(64, false)
+        );
+    }
+
+    #[test]
+    fn test_from_triple() {
+        assert_eq!(
+            TargetProperties::from_triple("aarch64-apple-darwin"),
+            props(64, false)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("aarch64_be-unknown-linux-gnu"),
+            props(64, true)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("x86_64-unknown-linux-gnux32"),
+            props(32, false)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("armv7-unknown-linux-gnueabihf"),
+            props(32, false)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("mips-unknown-linux-gnu"),
+            props(32, true)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("mipsel-unknown-linux-gnu"),
+            props(32, false)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("s390x-ibm-linux"),
+            props(64, true)
+        );
+        assert_eq!(
+            TargetProperties::from_triple("avr-unknown-gnu"),
+            props(16, false)
+        );
+    }
+}
@@ -15,6 +15,7 @@
 use itertools::Itertools;
 use proc_macro2::Span;
 use quote::ToTokens;
+use std::collections::HashMap;
 use std::iter::Peekable;
 use std::{fmt::Display, sync::Arc};
 use syn::{parse_quote, Ident, PathSegment, TypePath};
@@ -253,9 +254,44 @@ pub fn validate_ident_ok_for_rust(label: &str) -> Result<(), ConvertError> {
         .map(|_| ())
 }
 
+/// Names, unique among themselves, made from the namespaces and names of
+/// these types, for helper functions generated for each of them; so that
+/// same-named types in different namespaces don't share helpers. If two
+/// still come out the same, as `a::b_c` and `a_b::c` would, the later ones
+/// are numbered, as overloads are.
+pub(crate) fn make_helper_names<'a>(
+    names: impl IntoIterator<Item = &'a QualifiedName>,
+) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let joined = name.segment_iter().join("_");
+            let count = counts.entry(joined.clone()).or_default();
+            *count += 1;
+            match *count {
+                1 => joined,
+                n => format!("{}{}", joined, n - 1),
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::QualifiedName;
+    use super::{make_helper_names, QualifiedName};
+
+    #[test]
+    fn test_helper_names() {
+        let names: Vec<_> = ["Widget", "a::Widget", "b::Widget", "a::b_c", "a_b::c"]
+            .into_iter()
+            .map(QualifiedName::new_from_cpp_name)
+            .collect();
+        assert_eq!(
+            make_helper_names(&names),
+            ["Widget", "a_Widget", "b_Widget", "a_b_c", "a_b_c1"]
+        );
+    }
 
     #[test]
     fn test_ints() {
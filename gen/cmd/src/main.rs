@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use autocxx_engine::parse_file;
+use autocxx_engine::parse_file_with_generate_lists;
 use clap::{crate_authors, crate_version, App, Arg, ArgGroup};
 use proc_macro2::TokenStream;
 use quote::ToTokens;
@@ -153,6 +153,20 @@ fn main() {
                 .long("auto-allowlist")
                 .help("Dynamically construct allowlist from real uses of APIs.")
         )
+        .arg(
+            Arg::with_name("generate-list")
+                .long("generate-list")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("FILE")
+                .help("file listing fully-qualified C++ names to generate, one per line, as if each were given in a generate! directive. Blank lines and lines starting with # are ignored. The Rust file must contain exactly one include_cpp! directive.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("trace-calls")
+                .long("trace-calls")
+                .help("Generate code to log every call into C++, if the autocxx crate's trace_calls feature is enabled.")
+        )
         .arg(
             Arg::with_name("suppress-system-headers")
                 .long("suppress-system-headers")
@@ -188,9 +202,15 @@ fn main() {
         .get_matches();
 
     env_logger::builder().init();
-    let mut parsed_file = parse_file(
+    let generate_lists: Vec<_> = matches
+        .values_of("generate-list")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    let mut parsed_file = parse_file_with_generate_lists(
         matches.value_of("INPUT").unwrap(),
         matches.is_present("auto-allowlist"),
+        &generate_lists,
     )
     .expect("Unable to parse Rust file and interpret autocxx macro");
     let incs = matches
@@ -209,6 +229,10 @@ fn main() {
         path_to_cxx_h: get_option_string("cxx-h-path", &matches),
         path_to_cxxgen_h: get_option_string("cxxgen-h-path", &matches),
         skip_cxx_gen: matches.is_present("skip-cxx-gen"),
+        required_defines: Vec::new(),
+        trace_calls: matches.is_present("trace-calls"),
+        rename_methods: None,
+        suppress_abi_checks: false,
     };
     // In future, we should provide an option to write a .d file here
     // by passing a callback into the dep_recorder parameter here.
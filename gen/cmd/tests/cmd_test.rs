@@ -147,6 +147,78 @@ fn test_skip_cxx_gen() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+static LIST_MAIN_RS: &str = r#"
+use autocxx::prelude::*;
+
+include_cpp! {
+    #include "input.h"
+    safety!(unsafe_ffi)
+}
+
+fn main() {}
+"#;
+
+static LIST_INPUT_H: &str = "
+#include <cstdint>
+inline uint32_t do_a() { return 1; }
+namespace ns {
+    inline uint32_t do_b() { return 2; }
+    struct Thing { uint32_t x; };
+}
+";
+
+static NAMES_TXT: &str = "
+# Functions
+do_a
+  ns::do_b
+
+# Types
+ns::Thing
+";
+
+fn generate_list_test(
+    tmp_dir: &TempDir,
+    names: &str,
+) -> Result<assert_cmd::assert::Assert, Box<dyn std::error::Error>> {
+    let code_dir = tmp_dir.path().join("list");
+    std::fs::create_dir(&code_dir).unwrap();
+    write_to_file(&code_dir, "input.h", LIST_INPUT_H.as_bytes());
+    write_to_file(&code_dir, "main.rs", LIST_MAIN_RS.as_bytes());
+    write_to_file(&code_dir, "names.txt", names.as_bytes());
+    let mut cmd = Command::cargo_bin("autocxx-gen")?;
+    Ok(cmd
+        .arg("--inc")
+        .arg(code_dir.to_str().unwrap())
+        .arg(code_dir.join("main.rs"))
+        .arg("--generate-list")
+        .arg(code_dir.join("names.txt"))
+        .arg("--outdir")
+        .arg(tmp_dir.path().to_str().unwrap())
+        .arg("--gen-cpp")
+        .arg("--gen-rs-include")
+        .assert())
+}
+
+#[test]
+fn test_generate_list() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    generate_list_test(&tmp_dir, NAMES_TXT)?.success();
+    assert_contains(&tmp_dir, "gen0.include.rs", "do_a");
+    assert_contains(&tmp_dir, "gen0.include.rs", "do_b");
+    assert_contains(&tmp_dir, "gen0.include.rs", "Thing");
+    Ok(())
+}
+
+#[test]
+fn test_generate_list_unknown_name() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    let assert = generate_list_test(&tmp_dir, "do_a\n\n# Oops\nns::do_c\n")?.failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("'ns::do_c'"), "{}", stderr);
+    assert!(stderr.contains("names.txt line 4)"), "{}", stderr);
+    Ok(())
+}
+
 fn write_to_file(dir: &Path, filename: &str, content: &[u8]) {
     let path = dir.join(filename);
     let mut f = File::create(&path).expect("Unable to create file");
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::PathBuf;
+
 use autocxx_engine::Builder;
 
 use autocxx_integration_tests::{BuilderModifier, BuilderModifierFns, TestBuilderContext};
@@ -72,3 +74,58 @@ impl BuilderModifierFns for SkipCxxGen {
         builder.skip_cxx_gen(true)
     }
 }
+
+pub(crate) struct DefineSymbol(pub(crate) &'static str, pub(crate) Option<&'static str>);
+
+impl BuilderModifierFns for DefineSymbol {
+    fn modify_autocxx_builder(
+        &self,
+        builder: Builder<TestBuilderContext>,
+    ) -> Builder<TestBuilderContext> {
+        builder.define(self.0, self.1)
+    }
+}
+
+pub(crate) struct HeaderSource(pub(crate) &'static str, pub(crate) &'static str);
+
+impl BuilderModifierFns for HeaderSource {
+    fn modify_autocxx_builder(
+        &self,
+        builder: Builder<TestBuilderContext>,
+    ) -> Builder<TestBuilderContext> {
+        builder.header_source(self.0, self.1)
+    }
+}
+
+pub(crate) struct EnableTraceCalls;
+
+impl BuilderModifierFns for EnableTraceCalls {
+    fn modify_autocxx_builder(
+        &self,
+        builder: Builder<TestBuilderContext>,
+    ) -> Builder<TestBuilderContext> {
+        builder.trace_calls(true)
+    }
+}
+
+pub(crate) struct UseCompileCommands(pub(crate) PathBuf, pub(crate) &'static str);
+
+impl BuilderModifierFns for UseCompileCommands {
+    fn modify_autocxx_builder(
+        &self,
+        builder: Builder<TestBuilderContext>,
+    ) -> Builder<TestBuilderContext> {
+        builder.compile_commands(&self.0, self.1)
+    }
+}
+
+pub(crate) struct RenameMethodsToSnakeCase;
+
+impl BuilderModifierFns for RenameMethodsToSnakeCase {
+    fn modify_autocxx_builder(
+        &self,
+        builder: Builder<TestBuilderContext>,
+    ) -> Builder<TestBuilderContext> {
+        builder.rename_methods(autocxx_engine::to_snake_case)
+    }
+}
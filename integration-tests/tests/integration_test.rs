@@ -14,7 +14,8 @@
 
 use crate::{
     builder_modifiers::{
-        make_clang_arg_adder, EnableAutodiscover, SetSuppressSystemHeaders, SkipCxxGen,
+        make_clang_arg_adder, DefineSymbol, EnableAutodiscover, EnableTraceCalls, HeaderSource,
+        RenameMethodsToSnakeCase, SetSuppressSystemHeaders, SkipCxxGen, UseCompileCommands,
     },
     code_checkers::{
         make_error_finder, make_string_finder, CppCounter, CppMatcher, NoSystemHeadersChecker,
@@ -459,6 +460,202 @@ fn test_take_pod_by_value() {
     run_test(cxx, hdr, rs, &["take_bob"], &["Bob"]);
 }
 
+#[test]
+fn test_transparent_newtype() {
+    let cxx = indoc! {"
+        UserId next_user(UserId id) {
+            return UserId(id.v + 1);
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct UserId {
+            UserId(uint64_t v_) : v(v_) {}
+            uint64_t v;
+        };
+        UserId next_user(UserId id);
+    "};
+    let rs = quote! {
+        let id = ffi::UserId::from(41u64);
+        let next = ffi::next_user(id);
+        assert_eq!(next.0, 42);
+        let raw: u64 = next.into();
+        assert_eq!(raw, 42);
+        assert_eq!(
+            std::mem::size_of::<ffi::UserId>(),
+            std::mem::size_of::<u64>()
+        );
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["next_user"],
+            &[],
+            Some(quote! { transparent_newtype!("UserId") }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_negative_transparent_newtype_with_two_fields() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+    "};
+    let rs = quote! {
+        let _ = ffi::Point::from(1u32);
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&[], &[], Some(quote! { transparent_newtype!("Point") })),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_volatile_field() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Registers {
+            volatile uint32_t status;
+            uint32_t control;
+        };
+        inline void set_status(Registers& regs, uint32_t status) { regs.status = status; }
+        inline uint32_t get_status(const Registers& regs) { return regs.status; }
+    "};
+    let rs = quote! {
+        let mut regs = ffi::Registers::make_unique();
+        regs.pin_mut().get_mut().write_status(7);
+        assert_eq!(ffi::get_status(&regs), 7);
+        ffi::set_status(regs.pin_mut(), 9);
+        assert_eq!(regs.read_status(), 9);
+        regs.pin_mut().get_mut().control = 1;
+        assert_eq!(regs.control, 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["set_status", "get_status"],
+            &["Registers"],
+            Some(quote! { volatile_field!("Registers", "status") }),
+        ),
+        None,
+        Some(make_string_finder(
+            ["read_volatile", "write_volatile"].to_vec(),
+        )),
+        None,
+    );
+}
+
+#[test]
+fn test_volatile_field_not_found() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Registers {
+            volatile uint32_t status;
+        };
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &[],
+            &["Registers"],
+            Some(quote! { volatile_field!("Registers", "control") }),
+        ),
+        None,
+        Some(make_error_finder("Registers")),
+        None,
+    );
+}
+
+#[test]
+fn test_unique_ptr_into_box() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        inline std::unique_ptr<Point> make_point(uint32_t x, uint32_t y) {
+            return std::make_unique<Point>(Point { x, y });
+        }
+        inline uint32_t sum(const Point& p) { return p.x + p.y; }
+    "};
+    let rs = quote! {
+        use autocxx::IntoBox;
+        let point: Box<ffi::Point> = ffi::make_point(3, 4).into_box();
+        assert_eq!(point.x, 3);
+        let mut moved = point;
+        moved.y = 5;
+        assert_eq!(ffi::sum(&moved), 8);
+        let default_point = ffi::Point::make_unique().into_box();
+        assert_eq!(ffi::sum(&default_point), 0);
+    };
+    run_test("", hdr, rs, &["make_point", "sum"], &["Point"]);
+}
+
+#[test]
+fn test_negative_unique_ptr_into_box_non_pod() {
+    let hdr = indoc! {"
+        #include <string>
+        struct Named {
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        use autocxx::IntoBox;
+        let _ = ffi::Named::make_unique().into_box();
+    };
+    run_test_expect_fail("", hdr, rs, &["Named"], &[]);
+}
+
+#[test]
+fn test_small_pod_aggregate_by_value() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Pair {
+            int32_t a;
+            int32_t b;
+        };
+        inline Pair swap_pair(Pair p) { return Pair { p.b, p.a }; }
+    "};
+    let rs = quote! {
+        let swapped = ffi::swap_pair(ffi::Pair { a: 1, b: 2 });
+        assert_eq!(swapped.a, 2);
+        assert_eq!(swapped.b, 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["swap_pair"], &["Pair"], None),
+        None,
+        Some(Box::new(CppMatcher::new(
+            &[],
+            &["swap_pair_autocxx_wrapper"],
+        ))),
+        None,
+    );
+}
+
 #[test]
 fn test_negative_take_as_pod_with_destructor() {
     let cxx = indoc! {"
@@ -1158,6 +1355,73 @@ fn test_pod_method() {
     run_test(cxx, hdr, rs, &[], &["Bob"]);
 }
 
+#[test]
+fn test_force_opaque() {
+    let cxx = indoc! {"
+        uint32_t Bob::get_bob() const {
+            return a;
+        }
+        Bob make_bob() {
+            return Bob { 12, 13 };
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+        public:
+            uint32_t a;
+            uint32_t b;
+            uint32_t get_bob() const;
+        };
+        Bob make_bob();
+    "};
+    let rs = quote! {
+        let a: cxx::UniquePtr<ffi::Bob> = ffi::make_bob();
+        assert_eq!(a.get_bob(), 12);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_bob"],
+            &["Bob"],
+            Some(quote! { force_opaque!("Bob") }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_force_opaque_fields_inaccessible() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+        public:
+            uint32_t a;
+            uint32_t b;
+        };
+        inline Bob make_bob() {
+            return Bob { 12, 13 };
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::make_bob();
+        assert_eq!(a.a, 12);
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["make_bob"], &[], Some(quote! { force_opaque!("Bob") })),
+        None,
+        None,
+        None,
+    );
+}
+
 #[test]
 #[ignore] // https://github.com/google/autocxx/issues/723
 fn test_constructors_for_specialized_types() {
@@ -2149,6 +2413,39 @@ fn test_overload_methods() {
     run_test(cxx, hdr, rs, &["Norma"], &["Fred", "Bob"]);
 }
 
+#[test]
+fn test_overload_on_constness() {
+    let cxx = indoc! {"
+        uint32_t Data::get() { return 1; }
+        uint32_t Data::get() const { return 2; }
+        uint32_t process(Data&) { return 3; }
+        uint32_t process(const Data&) { return 4; }
+        uint32_t process(Data) { return 5; }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Data {
+            Data() {}
+            uint32_t get();
+            uint32_t get() const;
+            std::string s;
+        };
+        uint32_t process(Data&);
+        uint32_t process(const Data&);
+        uint32_t process(Data);
+    "};
+    let rs = quote! {
+        let mut d = ffi::Data::make_unique();
+        assert_eq!(d.pin_mut().get(), 1);
+        assert_eq!(d.get_const(), 2);
+        assert_eq!(ffi::process(d.pin_mut()), 3);
+        assert_eq!(ffi::process_const(&d), 4);
+        assert_eq!(ffi::process1(ffi::Data::make_unique()), 5);
+    };
+    run_test(cxx, hdr, rs, &["Data", "process"], &[]);
+}
+
 #[test]
 fn test_ns_constructor() {
     let cxx = indoc! {"
@@ -2284,6 +2581,58 @@ fn test_return_reference() {
     run_test(cxx, hdr, rs, &["give_bob"], &["Bob"]);
 }
 
+#[test]
+fn test_trailing_return_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
+        };
+        inline auto make_bob(uint32_t a) -> Bob { return Bob { a, 4 }; }
+        inline auto get_a(const Bob& bob) -> decltype(bob.a) { return bob.a; }
+    "};
+    let rs = quote! {
+        let b = ffi::make_bob(3);
+        assert_eq!(b.b, 4);
+        assert_eq!(ffi::get_a(&b), 3);
+    };
+    run_test("", hdr, rs, &["make_bob", "get_a"], &["Bob"]);
+}
+
+#[test]
+fn test_deduced_return_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
+        };
+        struct Holder {
+            Bob bob;
+        };
+        inline auto make_bob(uint32_t a) { return Bob { a, 4 }; }
+        // decltype(auto) of a parenthesized member is a reference...
+        inline decltype(auto) bob_ref(const Holder& h) { return (h.bob); }
+        // ... but of an unparenthesized one, a value.
+        inline decltype(auto) bob_copy(const Holder& h) { return h.bob; }
+    "};
+    let rs = quote! {
+        let h = ffi::Holder { bob: ffi::make_bob(3) };
+        let r: &ffi::Bob = ffi::bob_ref(&h);
+        assert_eq!(r.a, 3);
+        let c: ffi::Bob = ffi::bob_copy(&h);
+        assert_eq!(c.b, 4);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["make_bob", "bob_ref", "bob_copy"],
+        &["Bob", "Holder"],
+    );
+}
+
 #[test]
 fn test_member_return_reference() {
     let hdr = indoc! {"
@@ -2303,3116 +2652,6376 @@ fn test_member_return_reference() {
 }
 
 #[test]
-fn test_destructor() {
+fn test_builder_chaining() {
     let hdr = indoc! {"
-        struct WithDtor {
-            ~WithDtor();
+        #include <cstdint>
+        #include <string>
+        class RequestBuilder {
+        public:
+            RequestBuilder& method(const std::string& m) { description = m + description; return *this; }
+            RequestBuilder& header(const std::string& name, const std::string& value) {
+                description += \" \" + name + \"=\" + value;
+                return *this;
+            }
+            RequestBuilder& timeout(uint32_t seconds) { description += \" timeout=\" + std::to_string(seconds); return *this; }
+            // This returns another builder, so isn't generated without a
+            // lifetime! directive saying which.
+            RequestBuilder& pick(RequestBuilder& other) { return other; }
+            const std::string& describe() const { return description; }
+        private:
+            std::string description;
+        };
+    "};
+    let rs = quote! {
+        let mut builder = ffi::RequestBuilder::make_unique();
+        let method = ffi::make_string("GET");
+        let name = ffi::make_string("Accept");
+        let value = ffi::make_string("text/plain");
+        builder
+            .pin_mut()
+            .method(&method)
+            .header(&name, &value)
+            .timeout(30);
+        assert_eq!(
+            builder.describe().to_str().unwrap(),
+            "GET Accept=text/plain timeout=30"
+        );
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["RequestBuilder"],
+            &[],
+            Some(quote! {
+                lifetime!("RequestBuilder::method", "return", "from", "this")
+                lifetime!("RequestBuilder::header", "return", "from", "this")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_lifetime_from_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Selector {
+            bool second;
         };
-        WithDtor make_with_dtor();
-    "};
-    let cxx = indoc! {"
-        WithDtor::~WithDtor() {}
-        WithDtor make_with_dtor() {
-            return {};
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
+            const uint32_t& pick(const Selector& s) const { return s.second ? b : a; }
+        };
+        inline const uint32_t& field(const Bob& bob, const Selector& s) {
+            return s.second ? bob.b : bob.a;
         }
     "};
     let rs = quote! {
-        use ffi::*;
-        let with_dtor: cxx::UniquePtr<WithDtor> = make_with_dtor();
-        drop(with_dtor);
+        let bob = ffi::Bob { a: 3, b: 4 };
+        // The selectors need only live as long as the calls.
+        let from_fn = {
+            let s = ffi::Selector { second: false };
+            ffi::field(&bob, &s)
+        };
+        let from_method = {
+            let s = ffi::Selector { second: true };
+            bob.pick(&s)
+        };
+        assert_eq!(*from_fn, 3);
+        assert_eq!(*from_method, 4);
     };
-    run_test(cxx, hdr, rs, &["WithDtor", "make_with_dtor"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["field"],
+            &["Bob", "Selector"],
+            Some(quote! {
+                lifetime!("field", "return", "from", "bob")
+                lifetime!("Bob::pick", "return", "from", "this")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
-// Even without a `safety!`, we still need to generate a safe `fn drop`.
 #[test]
-fn test_destructor_no_safety() {
+fn test_negative_lifetime_from_param() {
     let hdr = indoc! {"
-        struct WithDtor {
-            ~WithDtor();
+        #include <cstdint>
+        struct Selector {
+            bool second;
         };
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
+        };
+        inline const uint32_t& field(const Bob& bob, const Selector& s) {
+            return s.second ? bob.b : bob.a;
+        }
     "};
-    let cxx = indoc! {"
-        WithDtor::~WithDtor() {}
-    "};
-    let hexathorpe = Token![#](Span::call_site());
-    let unexpanded_rust = quote! {
-        use autocxx::prelude::*;
-
-        include_cpp!(
-            #hexathorpe include "input.h"
-            generate!("WithDtor")
-        );
-
-        fn main() {}
+    // The returned reference mustn't outlive the Bob it came from.
+    let rs = quote! {
+        let s = ffi::Selector { second: true };
+        let r = {
+            let bob = ffi::Bob { a: 3, b: 4 };
+            ffi::field(&bob, &s)
+        };
+        assert_eq!(*r, 4);
     };
-
-    do_run_test_manual(cxx, hdr, unexpanded_rust, None, None).unwrap();
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["field"],
+            &["Bob", "Selector"],
+            Some(quote! { lifetime!("field", "return", "from", "bob") }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_static_func() {
+fn test_tuple_return() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct WithStaticMethod {
-            static uint32_t call();
-        };
-    "};
-    let cxx = indoc! {"
-        uint32_t WithStaticMethod::call() {
-            return 42;
+        #include <string>
+        #include <tuple>
+        inline std::tuple<bool, uint32_t, std::string> parse(uint32_t len) {
+            return std::make_tuple(len > 2, len, std::string(len, 'a'));
         }
+        inline std::tuple<> nothing() { return std::tuple<>(); }
     "};
     let rs = quote! {
-        assert_eq!(ffi::WithStaticMethod::call(), 42);
+        let (ok, len, s) = ffi::parse(3);
+        assert!(ok);
+        assert_eq!(len, 3);
+        assert_eq!(s.to_str().unwrap(), "aaa");
+        let () = ffi::nothing();
     };
-    run_test(cxx, hdr, rs, &["WithStaticMethod"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["parse", "nothing"],
+            &[],
+            Some(quote! {
+                tuple_return!("parse")
+                tuple_return!("nothing")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_static_func_wrapper() {
+fn test_shared_from_this() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <string>
-        struct A {
-            std::string a;
-            static A CreateA(std::string a, std::string) {
-                A c;
-                c.a = a;
-                return c;
-            }
+        #include <memory>
+        class Widget : public std::enable_shared_from_this<Widget> {
+        public:
+            Widget(uint32_t size) : size(size) {}
+            uint32_t get_size() const { return size; }
+        private:
+            uint32_t size;
         };
+        inline std::shared_ptr<Widget> make_shared_widget(uint32_t size) {
+            return std::make_shared<Widget>(size);
+        }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        ffi::A::CreateA("a".into_cpp(), "b".into_cpp());
+        let widget = ffi::make_shared_widget(3);
+        let another_owner = widget.as_ref().unwrap().shared_from_this();
+        assert!(std::ptr::eq(
+            widget.as_ref().unwrap(),
+            another_owner.as_ref().unwrap()
+        ));
+        let weak = widget.as_ref().unwrap().weak_from_this();
+        drop(widget);
+        assert_eq!(another_owner.as_ref().unwrap().get_size(), 3);
+        assert!(!weak.upgrade().is_null());
+        drop(another_owner);
+        assert!(weak.upgrade().is_null());
     };
-    run_test("", hdr, rs, &["A"], &[]);
+    run_test("", hdr, rs, &["Widget", "make_shared_widget"], &[]);
 }
 
 #[test]
-fn test_give_pod_typedef_by_value() {
-    let cxx = indoc! {"
-        Horace give_bob() {
-            Horace a;
-            a.a = 3;
-            a.b = 4;
-            return a;
-        }
-    "};
+fn test_string_return() {
     let hdr = indoc! {"
-        #include <cstdint>
-        struct Bob {
-            uint32_t a;
-            uint32_t b;
+        #include <string>
+        inline std::string valid() { return std::string(\"a\\0b\", 3); }
+        inline std::string invalid() { return \"x\\xffy\"; }
+        inline std::string valid_strict() { return valid(); }
+        inline std::string invalid_strict() { return invalid(); }
+        class Widget {
+        public:
+            Widget() : label_(\"caf\\xc3\\xa9\") {}
+            const std::string& label() const { return label_; }
+        private:
+            std::string label_;
         };
-        using Horace = Bob;
-        Horace give_bob();
     "};
     let rs = quote! {
-        assert_eq!(ffi::give_bob().b, 4);
+        assert_eq!(ffi::valid(), "a\0b");
+        assert_eq!(ffi::invalid(), "x\u{fffd}y");
+        assert_eq!(ffi::valid_strict().unwrap(), "a\0b");
+        assert!(ffi::invalid_strict().is_err());
+        let widget = ffi::Widget::new().within_unique_ptr();
+        assert_eq!(widget.label().unwrap(), "café");
     };
-    run_test(cxx, hdr, rs, &["give_bob"], &["Bob"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &[
+                "valid",
+                "invalid",
+                "valid_strict",
+                "invalid_strict",
+                "Widget",
+            ],
+            &[],
+            Some(quote! {
+                string_return!("valid")
+                string_return!("invalid")
+                string_return!("valid_strict", strict)
+                string_return!("invalid_strict", strict)
+                string_return!("Widget::label", strict)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
-#[ignore] // because we need to put some aliases in the output ffi mod.
 #[test]
-fn test_use_pod_typedef() {
-    let cxx = indoc! {"
-    "};
+fn test_symbol_resolver() {
+    // Neither function is defined anywhere: both are found at runtime.
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
-            uint32_t b;
-        };
-        using Horace = Bob;
+        extern \"C\" uint32_t plugin_add(uint32_t a, uint32_t b);
+        extern \"C\" void plugin_missing();
     "};
     let rs = quote! {
-        let h = Horace { a: 3, b: 4 };
-        assert_eq!(h.b, 4);
+        assert_eq!(ffi::plugin_add(2, 3), 105);
+        let panic = std::panic::catch_unwind(|| ffi::plugin_missing()).unwrap_err();
+        assert!(panic
+            .downcast_ref::<String>()
+            .unwrap()
+            .contains("plugin_missing"));
     };
-    run_test(cxx, hdr, rs, &[], &["Bob"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["plugin_add", "plugin_missing"],
+            &[],
+            Some(quote! {
+                symbol_resolver!(crate::MockPlugin)
+            }),
+        ),
+        None,
+        None,
+        Some(quote! {
+            pub struct MockPlugin;
+
+            extern "C" fn mock_add(a: u32, b: u32) -> u32 {
+                a + b + 100
+            }
+
+            unsafe impl autocxx::SymbolResolver for MockPlugin {
+                fn resolve(symbol: &str) -> Option<*const std::ffi::c_void> {
+                    match symbol {
+                        "plugin_add" => Some(mock_add as *const std::ffi::c_void),
+                        _ => None,
+                    }
+                }
+            }
+        }),
+    );
 }
 
 #[test]
-fn test_typedef_to_ns() {
+fn test_emplace_return() {
     let hdr = indoc! {"
         #include <cstdint>
-        namespace A {
-            template<typename T>
-            struct C {
-                T* t;
-            };
-            typedef C<char> B;
+        #include <string>
+        class Widget {
+        public:
+            Widget(uint32_t size) : size(size) {}
+            uint32_t get_size() const { return size; }
+        private:
+            uint32_t size;
+            std::string so_we_are_non_trivial;
+        };
+        inline Widget make_widget(uint32_t size) { return Widget(size); }
+        inline Widget bigger_widget(const Widget& w, uint32_t extra) {
+            return Widget(w.get_size() + extra);
         }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A::B"], &[]);
+    let rs = quote! {
+        use autocxx::moveit::Emplace;
+        moveit! {
+            let widget = ffi::make_widget(3);
+        }
+        assert_eq!(widget.get_size(), 3);
+        moveit! {
+            let bigger = ffi::bigger_widget(&widget, 2);
+        }
+        assert_eq!(bigger.get_size(), 5);
+        let boxed = Box::emplace(ffi::make_widget(4));
+        assert_eq!(boxed.get_size(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Widget", "make_widget", "bigger_widget"],
+            &[],
+            Some(quote! {
+                emplace_return!("make_widget")
+                emplace_return!("bigger_widget")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
-#[ignore] // we don't yet allow typedefs to be listed in allow_pod
 #[test]
-fn test_use_pod_typedef_with_allowpod() {
-    let cxx = indoc! {"
-    "};
+fn test_static_ref_return() {
     let hdr = indoc! {"
-        #include <cstdint>
-        struct Bob {
-            uint32_t a;
-            uint32_t b;
+        class Logger {
+        public:
+            Logger() : total(0) {}
+            void log(int amount) { total += amount; }
+            int get_total() const { return total; }
+        private:
+            int total;
         };
-        using Horace = Bob;
+        inline Logger& instance() {
+            static Logger logger;
+            return logger;
+        }
+        inline const Logger& const_instance() { return instance(); }
     "};
     let rs = quote! {
-        let h = Horace { a: 3, b: 4 };
-        assert_eq!(h.b, 4);
+        fn keep(logger: &'static ffi::Logger) -> &'static ffi::Logger {
+            logger
+        }
+        let logger: ::std::pin::Pin<&'static mut ffi::Logger> = ffi::instance();
+        logger.log(c_int(3));
+        let logger = keep(ffi::const_instance());
+        assert_eq!(logger.get_total(), c_int(3));
     };
-    run_test(cxx, hdr, rs, &[], &["Horace"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Logger", "instance", "const_instance"],
+            &[],
+            Some(quote! {
+                static_ref_return!("instance")
+                static_ref_return!("const_instance")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_give_nonpod_typedef_by_value() {
+fn test_thread_local_global() {
     let cxx = indoc! {"
-        Horace give_bob() {
-            Horace a;
-            a.a = 3;
-            a.b = 4;
-            return a;
+        thread_local uint32_t counter = 0;
+        void increment() {
+            ++counter;
         }
     "};
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
-            uint32_t b;
-        };
-        using Horace = Bob;
-        Horace give_bob();
-        inline uint32_t take_horace(const Horace& horace) { return horace.b; }
+        extern thread_local uint32_t counter;
+        void increment();
     "};
     let rs = quote! {
-        assert_eq!(ffi::take_horace(ffi::give_bob().as_ref().unwrap()), 4);
+        ffi::increment();
+        ffi::increment();
+        assert_eq!(*ffi::counter(), 2);
+        let other = std::thread::spawn(|| {
+            assert_eq!(*ffi::counter(), 0);
+            ffi::increment();
+            *ffi::counter()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(other, 1);
+        assert_eq!(*ffi::counter(), 2);
     };
-    run_test(cxx, hdr, rs, &["give_bob", "take_horace"], &[]);
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["increment"],
+            &[],
+            Some(quote! {
+                thread_local_global!("counter")
+                safety!(unsafe_ffi)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_conflicting_static_functions() {
-    let cxx = indoc! {"
-        Bob Bob::create() { Bob a; return a; }
-        Fred Fred::create() { Fred b; return b; }
-    "};
+fn test_any_castable() {
     let hdr = indoc! {"
+        #include <any>
         #include <cstdint>
-        struct Bob {
-            Bob() : a(0) {}
-            uint32_t a;
-            static Bob create();
+        struct Point {
+            uint32_t x;
+            uint32_t y;
         };
-        struct Fred {
-            Fred() : b(0) {}
-            uint32_t b;
-            static Fred create();
+        struct Other {
+            uint32_t z;
         };
+        inline std::any keep(std::any value) { return value; }
+        inline std::any make_int() { return std::any(3); }
+        inline std::any make_empty() { return std::any(); }
     "};
     let rs = quote! {
-        ffi::Bob::create();
-        ffi::Fred::create();
+        let point = ffi::Point { x: 3, y: 4 };
+        let any = ffi::CppAny::wrap(&point);
+        assert!(any.has_value());
+        let kept = ffi::keep(any);
+        assert_eq!(kept.downcast_ref::<ffi::Point>().unwrap().y, 4);
+        assert!(kept.downcast_ref::<ffi::Other>().is_none());
+        let int = ffi::make_int();
+        assert!(int.has_value());
+        assert!(int.downcast_ref::<ffi::Point>().is_none());
+        assert_ne!(int.type_name(), kept.type_name());
+        let empty = ffi::make_empty();
+        assert!(!empty.has_value());
+        assert!(empty.downcast_ref::<ffi::Point>().is_none());
     };
-    run_test(cxx, hdr, rs, &[], &["Bob", "Fred"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["keep", "make_int", "make_empty"],
+            &["Point", "Other"],
+            Some(quote! {
+                any_castable!("Point")
+                any_castable!("Other")
+            }),
+        ),
+        make_clang_arg_adder(&["-std=c++17"]),
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_conflicting_ns_up_functions() {
-    let cxx = indoc! {"
-        uint32_t A::create(C) { return 3; }
-        uint32_t B::create(C) { return 4; }
-    "};
+fn test_inherited_crtp_method() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct C {
-            C() {}
-            uint32_t a;
-        };
-        namespace A {
-            uint32_t create(C c);
+        template<typename D> struct Sided {
+            uint32_t sides() const { return static_cast<const D*>(this)->count(); }
         };
-        namespace B {
-            uint32_t create(C c);
+        template<typename D> struct Scaled : Sided<D> {
+            uint32_t scale = 1;
+            void grow(uint32_t by) { scale *= by; }
+            uint32_t perimeter(uint32_t side) const { return this->sides() * side * scale; }
         };
+        namespace shapes {
+            struct Square : Scaled<Square> {
+                uint32_t count() const { return 4; }
+            };
+        }
     "};
     let rs = quote! {
-        let c = ffi::C::make_unique();
-        let c2 = ffi::C::make_unique();
-        assert_eq!(ffi::A::create(c), 3);
-        assert_eq!(ffi::B::create(c2), 4);
+        let mut square = ffi::shapes::Square::make_unique();
+        assert_eq!(square.sides(), 4);
+        square.pin_mut().grow(3);
+        assert_eq!(square.perimeter(2), 24);
     };
-    run_test(cxx, hdr, rs, &["A::create", "B::create", "C"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["shapes::Square"],
+            &[],
+            Some(quote! {
+                inherited_method!("shapes::Square", "sides", "uint32_t() const")
+                inherited_method!("shapes::Square", "grow", "void(uint32_t)")
+                inherited_method!("shapes::Square", "perimeter", "uint32_t(uint32_t) const")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_conflicting_methods() {
-    let cxx = indoc! {"
-        uint32_t Bob::get() const { return a; }
-        uint32_t Fred::get() const { return b; }
-    "};
+fn test_any_castable_in_namespace() {
+    // The helpers are named after the namespace too, so that they wouldn't
+    // clash with those for another type called Point.
     let hdr = indoc! {"
+        #include <any>
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
-            uint32_t get() const;
-        };
-        struct Fred {
-            uint32_t b;
-            uint32_t get() const;
-        };
+        namespace geo {
+            struct Point {
+                uint32_t x;
+            };
+        }
+        inline std::any keep(std::any value) { return value; }
     "};
     let rs = quote! {
-        let a = ffi::Bob { a: 10 };
-        let b = ffi::Fred { b: 20 };
-        assert_eq!(a.get(), 10);
-        assert_eq!(b.get(), 20);
+        let point = ffi::geo::Point { x: 3 };
+        let kept = ffi::keep(ffi::CppAny::wrap(&point));
+        assert_eq!(kept.downcast_ref::<ffi::geo::Point>().unwrap().x, 3);
     };
-    run_test(cxx, hdr, rs, &[], &["Bob", "Fred"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["keep"],
+            &["geo::Point"],
+            Some(quote! { any_castable!("geo::Point") }),
+        ),
+        make_clang_arg_adder(&["-std=c++17"]),
+        Some(Box::new(CppMatcher::new(&["_cast_geo_Point"], &[]))),
+        None,
+    );
 }
 
 #[test]
-// There's a bindgen bug here. bindgen generates
-// functions called 'get' and 'get1' but then generates impl
-// blocks which call 'get' and 'get'. By luck, we currently
-// should not be broken by this, but at some point we should take
-// the time to create a minimal bindgen test case and submit it
-// as a bindgen bug.
-fn test_conflicting_up_wrapper_methods_not_in_ns() {
-    // Ensures the two names 'get' do not conflict in the flat
-    // cxx::bridge mod namespace.
-    let cxx = indoc! {"
-        Bob::Bob() : a(\"hello\") {}
-        Fred::Fred() : b(\"goodbye\") {}
-        std::string Bob::get() const { return a; }
-        std::string Fred::get() const { return b; }
-    "};
+fn test_iterable() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <string>
-        struct Bob {
-            Bob();
-            std::string a;
-            std::string get() const;
+        #include <forward_list>
+        #include <list>
+        #include <vector>
+        class Playlist {
+        public:
+            void add(uint32_t track) { tracks.push_back(track); }
+            std::list<uint32_t>::const_iterator begin() const { return tracks.begin(); }
+            std::list<uint32_t>::const_iterator end() const { return tracks.end(); }
+        private:
+            std::list<uint32_t> tracks;
         };
-        struct Fred {
-            Fred();
-            std::string b;
-            std::string get() const;
+        class Samples {
+        public:
+            void add(uint32_t sample) { samples.push_back(sample); }
+            std::vector<uint32_t>::const_iterator begin() const { return samples.begin(); }
+            std::vector<uint32_t>::const_iterator end() const { return samples.end(); }
+        private:
+            std::vector<uint32_t> samples;
+        };
+        class History {
+        public:
+            void add(uint32_t event) { events.push_front(event); }
+            std::forward_list<uint32_t>::const_iterator begin() const { return events.begin(); }
+            std::forward_list<uint32_t>::const_iterator end() const { return events.end(); }
+        private:
+            std::forward_list<uint32_t> events;
         };
     "};
     let rs = quote! {
-        let a = ffi::Bob::make_unique();
-        let b = ffi::Fred::make_unique();
-        assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
-        assert_eq!(b.get().as_ref().unwrap().to_str().unwrap(), "goodbye");
-    };
-    run_test(cxx, hdr, rs, &["Bob", "Fred"], &[]);
-}
+        let mut playlist = ffi::Playlist::make_unique();
+        for track in 1..4 {
+            playlist.pin_mut().add(track);
+        }
+        let forwards: Vec<u32> = (&*playlist).into_iter().copied().collect();
+        assert_eq!(forwards, vec![1, 2, 3]);
+        let backwards: Vec<u32> = (&*playlist).into_iter().rev().copied().collect();
+        assert_eq!(backwards, vec![3, 2, 1]);
+        let mut ends = (&*playlist).into_iter();
+        assert_eq!(ends.next(), Some(&1));
+        assert_eq!(ends.next_back(), Some(&3));
+        assert_eq!(ends.next_back(), Some(&2));
+        assert_eq!(ends.next(), None);
+        assert_eq!(ends.next_back(), None);
 
-#[test]
-fn test_conflicting_methods_in_ns() {
-    let cxx = indoc! {"
-        uint32_t A::Bob::get() const { return a; }
-        uint32_t B::Fred::get() const { return b; }
-    "};
-    let hdr = indoc! {"
-        #include <cstdint>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-                uint32_t get() const;
-            };
+        let mut samples = ffi::Samples::make_unique();
+        for sample in &[5, 6, 7, 8] {
+            samples.pin_mut().add(*sample);
         }
-        namespace B {
-            struct Fred {
-                uint32_t b;
-                uint32_t get() const;
-            };
+        let mut iter = (&*samples).into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next_back(), Some(&8));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.map(|s| *s).sum::<u32>(), 18);
+
+        let mut history = ffi::History::make_unique();
+        assert_eq!((&*history).into_iter().next(), None);
+        history.pin_mut().add(10);
+        history.pin_mut().add(20);
+        let mut total = 0;
+        for event in &*history {
+            total += event;
         }
-    "};
-    let rs = quote! {
-        let a = ffi::A::Bob { a: 10 };
-        let b = ffi::B::Fred { b: 20 };
-        assert_eq!(a.get(), 10);
-        assert_eq!(b.get(), 20);
+        assert_eq!(total, 30);
     };
-    run_test(cxx, hdr, rs, &[], &["A::Bob", "B::Fred"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Playlist", "Samples", "History"],
+            &[],
+            Some(quote! {
+                iterable!("Playlist", "uint32_t", bidirectional)
+                iterable!("Samples", "uint32_t", random_access)
+                iterable!("History", "uint32_t")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_conflicting_up_wrapper_methods_in_ns() {
-    let cxx = indoc! {"
-        A::Bob::Bob() : a(\"hello\") {}
-        B::Fred::Fred() : b(\"goodbye\") {}
-        std::string A::Bob::get() const { return a; }
-        std::string B::Fred::get() const { return b; }
-    "};
+fn test_cpp_set() {
     let hdr = indoc! {"
         #include <cstdint>
+        #include <memory>
+        #include <set>
         #include <string>
-        namespace A {
-            struct Bob {
-                Bob();
-                std::string a;
-                std::string get() const;
-            };
-        }
-        namespace B {
-            struct Fred {
-                Fred();
-                std::string b;
-                std::string get() const;
-            };
-        }
-    "};
-    let rs = quote! {
-        let a = ffi::A::Bob::make_unique();
-        let b = ffi::B::Fred::make_unique();
-        assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
-        assert_eq!(b.get().as_ref().unwrap().to_str().unwrap(), "goodbye");
+        #include <unordered_set>
+        typedef std::set<uint32_t> Ids;
+        inline Ids make_ids() { return Ids{5, 1, 3}; }
+        typedef std::set<int> Numbers;
+        inline std::unique_ptr<Numbers> make_numbers() { return std::make_unique<Numbers>(Numbers{2, 6}); }
+        typedef std::unordered_set<uint32_t> Seen;
+        inline std::unique_ptr<Seen> make_seen() { return std::make_unique<Seen>(); }
+        inline void see(Seen& seen, uint32_t id) { seen.insert(id); }
+        typedef std::set<std::string> Names;
+        inline Names make_names() { return Names{\"bob\", \"alice\"}; }
+    "};
+    let rs = quote! {
+        let ids = ffi::make_ids();
+        assert!(ids.contains(&3));
+        assert!(!ids.contains(&4));
+        assert_eq!(ids.len(), 3);
+        let ascending: Vec<u32> = (&*ids).into_iter().copied().collect();
+        assert_eq!(ascending, vec![1, 3, 5]);
+        let descending: Vec<u32> = (&*ids).into_iter().rev().copied().collect();
+        assert_eq!(descending, vec![5, 3, 1]);
+
+        let mut numbers = ffi::make_numbers();
+        assert_eq!(numbers.pin_mut().insert(&c_int(4)), (true, &c_int(4)));
+        assert_eq!(numbers.pin_mut().insert(&c_int(2)), (false, &c_int(2)));
+        assert_eq!(numbers.len(), 3);
+
+        let mut seen = ffi::make_seen();
+        assert!(seen.is_empty());
+        ffi::see(seen.pin_mut(), 7);
+        ffi::see(seen.pin_mut(), 7);
+        ffi::see(seen.pin_mut(), 9);
+        assert_eq!(seen.len(), 2);
+        assert!(seen.contains(&9));
+        let mut total = 0;
+        for id in &*seen {
+            total += id;
+        }
+        assert_eq!(total, 16);
+
+        let names = ffi::make_names();
+        cxx::let_cxx_string!(alice = "alice");
+        assert!(names.contains(&alice));
+        let sorted: Vec<String> = (&*names).into_iter().map(|name| name.to_string()).collect();
+        assert_eq!(sorted, vec!["alice".to_string(), "bob".to_string()]);
     };
-    run_test(cxx, hdr, rs, &["A::Bob", "B::Fred"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_ids", "make_numbers", "make_seen", "see", "make_names"],
+            &[],
+            Some(quote! {
+                cpp_set!("Ids", "uint32_t", ordered)
+                cpp_set!("Numbers", "int", ordered)
+                cpp_set!("Seen", "uint32_t")
+                cpp_set!("Names", "std::string", ordered)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_ns_struct_pod_request() {
+fn test_macro_const() {
     let hdr = indoc! {"
-        #include <cstdint>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-            };
-        }
+        #define BUFFER_SIZE (512 * 2)
+        #define HALF_SIZE BUFFER_SIZE / 2
+        #define SQUARE(x) ((x) * (x))
+        inline int buffer_size() { return BUFFER_SIZE; }
     "};
     let rs = quote! {
-        ffi::A::Bob { a: 12 };
+        const SIZE: i32 = ffi::BUFFER_SIZE;
+        assert_eq!(SIZE, 1024);
+        assert_eq!(ffi::HALF_SIZE, 512);
+        assert_eq!(c_int(ffi::BUFFER_SIZE), ffi::buffer_size());
+        assert_eq!(ffi::SQUARE(c_int(3)), c_int(9));
     };
-    run_test("", hdr, rs, &[], &["A::Bob"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["buffer_size"],
+            &[],
+            Some(quote! {
+                macro_const!("BUFFER_SIZE")
+                macro_const!("HALF_SIZE")
+                macro_function!("SQUARE", "int(int)")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_conflicting_ns_funcs() {
-    let cxx = indoc! {"
-        uint32_t A::get() { return 10; }
-        uint32_t B::get() { return 20; }
-    "};
+fn test_select_overload() {
     let hdr = indoc! {"
-        #include <cstdint>
-        namespace A {
-            uint32_t get();
-        }
-        namespace B {
-            uint32_t get();
+        #include <type_traits>
+        template <typename T, typename std::enable_if<std::is_integral<T>::value, int>::type = 0>
+        T twice(T v) { return v * 2; }
+        template <typename T, typename std::enable_if<std::is_floating_point<T>::value, int>::type = 0>
+        T twice(T v) { return v + v + 0.5; }
+        inline int describe(int) { return 1; }
+        inline int describe(long) { return 2; }
+        namespace units {
+            inline int scale(short v) { return v; }
+            inline int scale(int v) { return v * 10; }
         }
     "};
     let rs = quote! {
-        assert_eq!(ffi::A::get(), 10);
-        assert_eq!(ffi::B::get(), 20);
+        assert_eq!(ffi::twice(c_int(3)), c_int(6));
+        assert_eq!(ffi::twice1(1.0), 2.5);
+        assert_eq!(ffi::describe(c_long(5)), c_int(2));
+        assert_eq!(ffi::units::scale(c_int(4)), c_int(40));
     };
-    run_test(cxx, hdr, rs, &["A::get", "B::get"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["describe", "units::scale"],
+            &[],
+            Some(quote! {
+                select_overload!("twice", "(int) -> int")
+                select_overload!("twice", "(double) -> double")
+                select_overload!("describe", "(long) -> int")
+                select_overload!("units::scale", "(int) -> int")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
-#[ignore]
-// because currently we feed a flat namespace to cxx
-// This would be relatively easy to enable now that we have the facility
-// to add aliases to the 'use' statements we generate, plus
-// bridge_name_tracker to pick a unique name. TODO.
 #[test]
-fn test_conflicting_ns_structs() {
+fn test_instantiate_fn_named() {
     let hdr = indoc! {"
-        #include <cstdint>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-            };
-        }
-        namespace B {
-            struct Bob {
-                uint32_t a;
-            };
+        template <typename T, int N>
+        T foo() { return T(N); }
+        namespace units {
+            template <int Scale>
+            int scale(int v) { return v * Scale; }
         }
     "};
     let rs = quote! {
-        ffi::A::Bob { a: 12 };
-        ffi::B::Bob { b: 12 };
+        assert_eq!(ffi::foo_int_3(), c_int(3));
+        assert_eq!(ffi::foo_double_2(), 2.0);
+        assert_eq!(ffi::units::scale_by_10(c_int(4)), c_int(40));
     };
-    run_test("", hdr, rs, &[], &["A::Bob", "B::Bob"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &[],
+            &[],
+            Some(quote! {
+                instantiate_fn_named!("foo", "<int, 3>", "foo_int_3")
+                instantiate_fn_named!("foo", "<double, 2>", "foo_double_2")
+                instantiate_fn_named!("units::scale", "<10>", "scale_by_10")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_make_string() {
+fn test_name_ctor() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
+        struct Point {
+            Point() : x(0), y(0) {}
+            Point(int x_, int y_) : x(x_), y(y_) {}
+            Point(int64_t packed) : x(packed >> 32), y(packed & 0xffffffff) {}
+            Point(const Point& other, int scale) : x(other.x * scale), y(other.y * scale) {}
+            int sum() const { return x + y; }
+            int x;
+            int y;
         };
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        let a = "hello".into_cpp();
-        assert_eq!(a.to_str().unwrap(), "hello");
+        use autocxx::moveit::EmplaceUnpinned;
+        let p = cxx::UniquePtr::emplace(ffi::Point::from_coords(c_int(3), c_int(4)));
+        assert_eq!(p.sum(), c_int(7));
+        let q = cxx::UniquePtr::emplace(ffi::Point::scaled(&p, c_int(2)));
+        assert_eq!(q.sum(), c_int(14));
+        let r = ffi::Point::make_unique_from_coords(c_int(1), c_int(2));
+        assert_eq!(r.sum(), c_int(3));
+        let o = cxx::UniquePtr::emplace(ffi::Point::new());
+        assert_eq!(o.sum(), c_int(0));
+        let u = cxx::UniquePtr::emplace(ffi::Point::new2((5i64 << 32) | 6));
+        assert_eq!(u.sum(), c_int(11));
     };
-    run_test("", hdr, rs, &["Bob"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Point"],
+            &[],
+            Some(quote! {
+                name_ctor!("Point", "(int, int)", "from_coords")
+                name_ctor!("Point", "(const Point &, int)", "scaled")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_string_make_unique() {
+fn test_name_ctor_collision() {
     let hdr = indoc! {"
-        #include <string>
-        inline void take_string(const std::string*) {};
+        struct Point {
+            Point(int x_, int y_) : x(x_), y(y_) {}
+            static Point from_coords(int x, int y) { return Point(y, x); }
+            int first() const { return x; }
+            int x;
+            int y;
+        };
     "};
     let rs = quote! {
-        let s = ffi::make_string("");
-        unsafe { ffi::take_string(s.as_ref().unwrap()) };
+        let p = ffi::Point::from_coords(c_int(1), c_int(2));
+        assert_eq!(p.first(), c_int(2));
     };
-    run_test("", hdr, rs, &["take_string"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Point"],
+            &[],
+            Some(quote! {
+                name_ctor!("Point", "(int, int)", "from_coords")
+            }),
+        ),
+        None,
+        Some(make_string_finder(vec!["name_ctor", "from_coords"])),
+        None,
+    );
 }
 
 #[test]
-fn test_string_constant() {
+fn test_destructor() {
     let hdr = indoc! {"
-        #include <cstdint>
-        const char* STRING = \"Foo\";
+        struct WithDtor {
+            ~WithDtor();
+        };
+        WithDtor make_with_dtor();
+    "};
+    let cxx = indoc! {"
+        WithDtor::~WithDtor() {}
+        WithDtor make_with_dtor() {
+            return {};
+        }
     "};
     let rs = quote! {
-        let a = std::str::from_utf8(ffi::STRING).unwrap().trim_end_matches(char::from(0));
-        assert_eq!(a, "Foo");
+        use ffi::*;
+        let with_dtor: cxx::UniquePtr<WithDtor> = make_with_dtor();
+        drop(with_dtor);
     };
-    run_test("", hdr, rs, &["STRING"], &[]);
+    run_test(cxx, hdr, rs, &["WithDtor", "make_with_dtor"], &[]);
 }
 
+// Even without a `safety!`, we still need to generate a safe `fn drop`.
 #[test]
-fn test_pod_constant_harmless_inside_type() {
-    // Check that the presence of this constant doesn't break anything.
+fn test_destructor_no_safety() {
     let hdr = indoc! {"
-        #include <cstdint>
-        struct Bob {
-            uint32_t a;
-        };
-        struct Anna {
-            uint32_t a;
-            const Bob BOB = Bob { 10 };
+        struct WithDtor {
+            ~WithDtor();
         };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &[], &["Anna"]);
+    let cxx = indoc! {"
+        WithDtor::~WithDtor() {}
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let unexpanded_rust = quote! {
+        use autocxx::prelude::*;
+
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate!("WithDtor")
+        );
+
+        fn main() {}
+    };
+
+    do_run_test_manual(cxx, hdr, unexpanded_rust, None, None).unwrap();
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/93
-fn test_pod_constant() {
+fn test_static_func() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
+        struct WithStaticMethod {
+            static uint32_t call();
         };
-        const Bob BOB = Bob { 10 };
+    "};
+    let cxx = indoc! {"
+        uint32_t WithStaticMethod::call() {
+            return 42;
+        }
     "};
     let rs = quote! {
-        let a = &ffi::BOB;
-        assert_eq!(a.a, 10);
+        assert_eq!(ffi::WithStaticMethod::call(), 42);
     };
-    run_test("", hdr, rs, &["BOB"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["WithStaticMethod"], &[]);
 }
 
 #[test]
-fn test_pod_static_harmless_inside_type() {
-    // Check that the presence of this constant doesn't break anything.
-    // Remove this test when the following one is enabled.
+fn test_static_func_wrapper() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct Bob {
-            uint32_t a;
-        };
-        struct Anna {
-            uint32_t a;
-            static Bob BOB;
+        #include <string>
+        struct A {
+            std::string a;
+            static A CreateA(std::string a, std::string) {
+                A c;
+                c.a = a;
+                return c;
+            }
         };
-        Bob Anna::BOB = Bob { 10 };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &[], &["Anna"]);
+    let rs = quote! {
+        use ffi::ToCppString;
+        ffi::A::CreateA("a".into_cpp(), "b".into_cpp());
+    };
+    run_test("", hdr, rs, &["A"], &[]);
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/93
-fn test_pod_static() {
+fn test_give_pod_typedef_by_value() {
+    let cxx = indoc! {"
+        Horace give_bob() {
+            Horace a;
+            a.a = 3;
+            a.b = 4;
+            return a;
+        }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
         struct Bob {
             uint32_t a;
+            uint32_t b;
         };
-        static Bob BOB = Bob { 10 };
+        using Horace = Bob;
+        Horace give_bob();
     "};
     let rs = quote! {
-        let a = &ffi::BOB;
-        assert_eq!(a.a, 10);
+        assert_eq!(ffi::give_bob().b, 4);
     };
-    run_test("", hdr, rs, &["BOB"], &["Bob"]);
+    run_test(cxx, hdr, rs, &["give_bob"], &["Bob"]);
 }
 
+#[ignore] // because we need to put some aliases in the output ffi mod.
 #[test]
-#[ignore] // this probably requires code generation on the C++
-          // side. It's not at all clear how best to handle this.
-fn test_non_pod_constant() {
+fn test_use_pod_typedef() {
+    let cxx = indoc! {"
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <string>
         struct Bob {
-            std::string a;
-            std::string get() { return a };
+            uint32_t a;
+            uint32_t b;
         };
-        const Bob BOB = Bob { \"hello\" };
+        using Horace = Bob;
     "};
     let rs = quote! {
-        let a = ffi::BOB;
-        // following line assumes that 'a' is a &Bob
-        // but who knows how we'll really do this.
-        assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
+        let h = Horace { a: 3, b: 4 };
+        assert_eq!(h.b, 4);
     };
-    run_test("", hdr, rs, &["BOB"], &[]);
+    run_test(cxx, hdr, rs, &[], &["Bob"]);
 }
 
 #[test]
-fn test_templated_typedef() {
+fn test_typedef_to_ns() {
     let hdr = indoc! {"
-        #include <string>
         #include <cstdint>
-
-        template <typename STRING_TYPE> class BasicStringPiece {
-        public:
-            const STRING_TYPE* ptr_;
-            size_t length_;
-        };
-        typedef BasicStringPiece<uint8_t> StringPiece;
-
-        struct Origin {
-            Origin() {}
-            StringPiece host;
-        };
+        namespace A {
+            template<typename T>
+            struct C {
+                T* t;
+            };
+            typedef C<char> B;
+        }
     "};
-    let rs = quote! {
-        ffi::Origin::make_unique();
-    };
-    run_test("", hdr, rs, &["Origin"], &[]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A::B"], &[]);
 }
 
+#[ignore] // we don't yet allow typedefs to be listed in allow_pod
 #[test]
-fn test_struct_templated_typedef() {
+fn test_use_pod_typedef_with_allowpod() {
+    let cxx = indoc! {"
+    "};
     let hdr = indoc! {"
-        #include <string>
         #include <cstdint>
-
-        struct Concrete {
-            uint8_t a;
-        };
-        template <typename STRING_TYPE> class BasicStringPiece {
-        public:
-            const STRING_TYPE* ptr_;
-            size_t length_;
-        };
-        typedef BasicStringPiece<Concrete> StringPiece;
-
-        struct Origin {
-            Origin() {}
-            StringPiece host;
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
         };
+        using Horace = Bob;
     "};
     let rs = quote! {
-        ffi::Origin::make_unique();
+        let h = Horace { a: 3, b: 4 };
+        assert_eq!(h.b, 4);
     };
-    run_test("", hdr, rs, &["Origin"], &[]);
+    run_test(cxx, hdr, rs, &[], &["Horace"]);
 }
 
 #[test]
-fn test_enum_typedef() {
+fn test_give_nonpod_typedef_by_value() {
+    let cxx = indoc! {"
+        Horace give_bob() {
+            Horace a;
+            a.a = 3;
+            a.b = 4;
+            return a;
+        }
+    "};
     let hdr = indoc! {"
-        enum ConstraintSolverParameters_TrailCompression : int {
-            ConstraintSolverParameters_TrailCompression_NO_COMPRESSION = 0,
-            ConstraintSolverParameters_TrailCompression_COMPRESS_WITH_ZLIB = 1,
-            ConstraintSolverParameters_TrailCompression_ConstraintSolverParameters_TrailCompression_INT_MIN_SENTINEL_DO_NOT_USE_ = -2147483648,
-            ConstraintSolverParameters_TrailCompression_ConstraintSolverParameters_TrailCompression_INT_MAX_SENTINEL_DO_NOT_USE_ = 2147483647
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+            uint32_t b;
         };
-        typedef ConstraintSolverParameters_TrailCompression TrailCompression;
+        using Horace = Bob;
+        Horace give_bob();
+        inline uint32_t take_horace(const Horace& horace) { return horace.b; }
     "};
     let rs = quote! {
-        let _ = ffi::TrailCompression::ConstraintSolverParameters_TrailCompression_NO_COMPRESSION;
+        assert_eq!(ffi::take_horace(ffi::give_bob().as_ref().unwrap()), 4);
     };
-    run_test("", hdr, rs, &["TrailCompression"], &[]);
+    run_test(cxx, hdr, rs, &["give_bob", "take_horace"], &[]);
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/264
-fn test_conflicting_usings() {
+fn test_conflicting_static_functions() {
+    let cxx = indoc! {"
+        Bob Bob::create() { Bob a; return a; }
+        Fred Fred::create() { Fred b; return b; }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <cstddef>
-        typedef size_t diff;
-        struct A {
-            using diff = diff;
-            diff a;
+        struct Bob {
+            Bob() : a(0) {}
+            uint32_t a;
+            static Bob create();
         };
-        struct B {
-            using diff = diff;
-            diff a;
+        struct Fred {
+            Fred() : b(0) {}
+            uint32_t b;
+            static Fred create();
         };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &[], &["A", "B"]);
+    let rs = quote! {
+        ffi::Bob::create();
+        ffi::Fred::create();
+    };
+    run_test(cxx, hdr, rs, &[], &["Bob", "Fred"]);
 }
 
 #[test]
-fn test_conflicting_usings_with_self_declaration1() {
+fn test_conflicting_ns_up_functions() {
+    let cxx = indoc! {"
+        uint32_t A::create(C) { return 3; }
+        uint32_t B::create(C) { return 4; }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <cstddef>
-        struct common_params {
-            using difference_type = ptrdiff_t;
+        struct C {
+            C() {}
+            uint32_t a;
         };
-        template <typename Params>
-        class btree_node {
-            public:
-            using difference_type = typename Params::difference_type;
-            Params params;
+        namespace A {
+            uint32_t create(C c);
         };
-        template <typename Tree>
-        class btree_container {
-            public:
-            using difference_type = typename Tree::difference_type;
-            void clear() {}
-            Tree b;
-            uint32_t a;
+        namespace B {
+            uint32_t create(C c);
         };
-        typedef btree_container<btree_node<common_params>> my_tree;
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["my_tree"], &[]);
+    let rs = quote! {
+        let c = ffi::C::make_unique();
+        let c2 = ffi::C::make_unique();
+        assert_eq!(ffi::A::create(c), 3);
+        assert_eq!(ffi::B::create(c2), 4);
+    };
+    run_test(cxx, hdr, rs, &["A::create", "B::create", "C"], &[]);
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/106
-fn test_string_templated_typedef() {
+fn test_conflicting_methods() {
+    let cxx = indoc! {"
+        uint32_t Bob::get() const { return a; }
+        uint32_t Fred::get() const { return b; }
+    "};
     let hdr = indoc! {"
-        #include <string>
         #include <cstdint>
-
-        template <typename STRING_TYPE> class BasicStringPiece {
-        public:
-            const STRING_TYPE* ptr_;
-            size_t length_;
+        struct Bob {
+            uint32_t a;
+            uint32_t get() const;
         };
-        typedef BasicStringPiece<std::string> StringPiece;
-
-        struct Origin {
-            Origin() {}
-            StringPiece host;
+        struct Fred {
+            uint32_t b;
+            uint32_t get() const;
         };
     "};
     let rs = quote! {
-        ffi::Origin::make_unique();
+        let a = ffi::Bob { a: 10 };
+        let b = ffi::Fred { b: 20 };
+        assert_eq!(a.get(), 10);
+        assert_eq!(b.get(), 20);
     };
-    run_test("", hdr, rs, &["Origin"], &[]);
+    run_test(cxx, hdr, rs, &[], &["Bob", "Fred"]);
 }
 
 #[test]
-fn test_associated_type_problem() {
-    // Regression test for a potential bindgen bug
-    let hdr = indoc! {"
-        namespace a {
-        template <typename> class b {};
-        } // namespace a
-        class bl {
-          a::b<bl> bm;
-        };
-        struct B {
-            int a;
-        };
+// There's a bindgen bug here. bindgen generates
+// functions called 'get' and 'get1' but then generates impl
+// blocks which call 'get' and 'get'. By luck, we currently
+// should not be broken by this, but at some point we should take
+// the time to create a minimal bindgen test case and submit it
+// as a bindgen bug.
+fn test_conflicting_up_wrapper_methods_not_in_ns() {
+    // Ensures the two names 'get' do not conflict in the flat
+    // cxx::bridge mod namespace.
+    let cxx = indoc! {"
+        Bob::Bob() : a(\"hello\") {}
+        Fred::Fred() : b(\"goodbye\") {}
+        std::string Bob::get() const { return a; }
+        std::string Fred::get() const { return b; }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["B"], &[]);
-}
-
-#[ignore] // https://github.com/rust-lang/rust-bindgen/issues/1924
-#[test]
-fn test_associated_type_templated_typedef() {
     let hdr = indoc! {"
-        #include <string>
         #include <cstdint>
-
-        template <typename STRING_TYPE> class BasicStringPiece {
-        public:
-            typedef size_t size_type;
-            typedef typename STRING_TYPE::value_type value_type;
-            const value_type* ptr_;
-            size_type length_;
+        #include <string>
+        struct Bob {
+            Bob();
+            std::string a;
+            std::string get() const;
         };
-
-        typedef BasicStringPiece<std::string> StringPiece;
-
-        struct Origin {
-            // void SetHost(StringPiece host);
-            StringPiece host;
+        struct Fred {
+            Fred();
+            std::string b;
+            std::string get() const;
         };
     "};
     let rs = quote! {
-        ffi::Origin::make_unique();
+        let a = ffi::Bob::make_unique();
+        let b = ffi::Fred::make_unique();
+        assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
+        assert_eq!(b.get().as_ref().unwrap().to_str().unwrap(), "goodbye");
     };
-    run_test("", hdr, rs, &["Origin"], &[]);
+    run_test(cxx, hdr, rs, &["Bob", "Fred"], &[]);
 }
 
 #[test]
-fn test_foreign_ns_func_arg_pod() {
+fn test_conflicting_methods_in_ns() {
+    let cxx = indoc! {"
+        uint32_t A::Bob::get() const { return a; }
+        uint32_t B::Fred::get() const { return b; }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
         namespace A {
             struct Bob {
                 uint32_t a;
+                uint32_t get() const;
             };
         }
         namespace B {
-            inline uint32_t daft(A::Bob a) { return a.a; }
+            struct Fred {
+                uint32_t b;
+                uint32_t get() const;
+            };
         }
     "};
     let rs = quote! {
-        let a = ffi::A::Bob { a: 12 };
-        assert_eq!(ffi::B::daft(a), 12);
+        let a = ffi::A::Bob { a: 10 };
+        let b = ffi::B::Fred { b: 20 };
+        assert_eq!(a.get(), 10);
+        assert_eq!(b.get(), 20);
     };
-    run_test("", hdr, rs, &["B::daft"], &["A::Bob"]);
+    run_test(cxx, hdr, rs, &[], &["A::Bob", "B::Fred"]);
 }
 
 #[test]
-fn test_foreign_ns_func_arg_nonpod() {
+fn test_conflicting_up_wrapper_methods_in_ns() {
+    let cxx = indoc! {"
+        A::Bob::Bob() : a(\"hello\") {}
+        B::Fred::Fred() : b(\"goodbye\") {}
+        std::string A::Bob::get() const { return a; }
+        std::string B::Fred::get() const { return b; }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
+        #include <string>
         namespace A {
             struct Bob {
-                uint32_t a;
-                Bob(uint32_t _a) :a(_a) {}
+                Bob();
+                std::string a;
+                std::string get() const;
             };
         }
         namespace B {
-            inline uint32_t daft(A::Bob a) { return a.a; }
+            struct Fred {
+                Fred();
+                std::string b;
+                std::string get() const;
+            };
         }
     "};
     let rs = quote! {
-        let a = ffi::A::Bob::make_unique(12);
-        assert_eq!(ffi::B::daft(a), 12);
+        let a = ffi::A::Bob::make_unique();
+        let b = ffi::B::Fred::make_unique();
+        assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
+        assert_eq!(b.get().as_ref().unwrap().to_str().unwrap(), "goodbye");
     };
-    run_test("", hdr, rs, &["B::daft", "A::Bob"], &[]);
+    run_test(cxx, hdr, rs, &["A::Bob", "B::Fred"], &[]);
 }
 
 #[test]
-fn test_foreign_ns_meth_arg_pod() {
+fn test_ns_struct_pod_request() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
         namespace A {
             struct Bob {
                 uint32_t a;
             };
         }
-        namespace B {
-            struct C {
-                uint32_t a;
-                uint32_t daft(A::Bob a) const { return a.a; }
-            };
-        }
     "};
     let rs = quote! {
-        let a = ffi::A::Bob { a: 12 };
-        let b = ffi::B::C { a: 12 };
-        assert_eq!(b.daft(a), 12);
+        ffi::A::Bob { a: 12 };
     };
-    run_test("", hdr, rs, &[], &["A::Bob", "B::C"]);
+    run_test("", hdr, rs, &[], &["A::Bob"]);
 }
 
 #[test]
-fn test_foreign_ns_meth_arg_nonpod() {
+fn test_conflicting_ns_funcs() {
+    let cxx = indoc! {"
+        uint32_t A::get() { return 10; }
+        uint32_t B::get() { return 20; }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
         namespace A {
-            struct Bob {
-                uint32_t a;
-                Bob(uint32_t _a) :a(_a) {}
-            };
+            uint32_t get();
         }
         namespace B {
-            struct C {
-                uint32_t a;
-                uint32_t daft(A::Bob a) const { return a.a; }
-            };
+            uint32_t get();
         }
     "};
     let rs = quote! {
-        let a = ffi::A::Bob::make_unique(12);
-        let b = ffi::B::C { a: 12 };
-        assert_eq!(b.daft(a), 12);
+        assert_eq!(ffi::A::get(), 10);
+        assert_eq!(ffi::B::get(), 20);
     };
-    run_test("", hdr, rs, &["A::Bob"], &["B::C"]);
+    run_test(cxx, hdr, rs, &["A::get", "B::get"], &[]);
 }
 
+#[ignore]
+// because currently we feed a flat namespace to cxx
+// This would be relatively easy to enable now that we have the facility
+// to add aliases to the 'use' statements we generate, plus
+// bridge_name_tracker to pick a unique name. TODO.
 #[test]
-fn test_foreign_ns_cons_arg_pod() {
+fn test_conflicting_ns_structs() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
         namespace A {
             struct Bob {
                 uint32_t a;
             };
         }
         namespace B {
-            struct C {
+            struct Bob {
                 uint32_t a;
-                C(const A::Bob& input) : a(input.a) {}
             };
         }
     "};
     let rs = quote! {
-        let a = ffi::A::Bob { a: 12 };
-        let b = ffi::B::C::make_unique(&a);
-        assert_eq!(b.as_ref().unwrap().a, 12);
+        ffi::A::Bob { a: 12 };
+        ffi::B::Bob { b: 12 };
     };
-    run_test("", hdr, rs, &[], &["B::C", "A::Bob"]);
+    run_test("", hdr, rs, &[], &["A::Bob", "B::Bob"]);
 }
 
 #[test]
-fn test_foreign_ns_cons_arg_nonpod() {
+fn test_make_string() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        namespace A {
-            struct Bob {
-                Bob(uint32_t _a) :a(_a) {}
-                uint32_t a;
-            };
-        }
-        namespace B {
-            struct C {
-                uint32_t a;
-                C(const A::Bob& input) : a(input.a) {}
-            };
-        }
+        struct Bob {
+            uint32_t a;
+        };
     "};
     let rs = quote! {
-        let a = ffi::A::Bob::make_unique(12);
-        let b = ffi::B::C::make_unique(&a);
-        assert_eq!(b.as_ref().unwrap().a, 12);
+        use ffi::ToCppString;
+        let a = "hello".into_cpp();
+        assert_eq!(a.to_str().unwrap(), "hello");
     };
-    run_test("", hdr, rs, &["A::Bob"], &["B::C"]);
+    run_test("", hdr, rs, &["Bob"], &[]);
 }
 
 #[test]
-fn test_foreign_ns_func_ret_pod() {
+fn test_string_make_unique() {
     let hdr = indoc! {"
-        #include <cstdint>
-        #include <memory>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-            };
-        }
-        namespace B {
-            inline A::Bob daft() { A::Bob bob; bob.a = 12; return bob; }
-        }
+        #include <string>
+        inline void take_string(const std::string*) {};
     "};
     let rs = quote! {
-        assert_eq!(ffi::B::daft().a, 12);
+        let s = ffi::make_string("");
+        unsafe { ffi::take_string(s.as_ref().unwrap()) };
     };
-    run_test("", hdr, rs, &["B::daft"], &["A::Bob"]);
+    run_test("", hdr, rs, &["take_string"], &[]);
 }
 
 #[test]
-fn test_foreign_ns_func_ret_nonpod() {
+fn test_string_constant() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-            };
-        }
-        namespace B {
-            inline A::Bob daft() { A::Bob bob; bob.a = 12; return bob; }
-        }
+        const char* STRING = \"Foo\";
     "};
     let rs = quote! {
-        ffi::B::daft().as_ref().unwrap();
+        let a = std::str::from_utf8(ffi::STRING).unwrap().trim_end_matches(char::from(0));
+        assert_eq!(a, "Foo");
     };
-    run_test("", hdr, rs, &["B::daft", "A::Bob"], &[]);
+    run_test("", hdr, rs, &["STRING"], &[]);
 }
 
 #[test]
-fn test_foreign_ns_meth_ret_pod() {
+fn test_pod_constant_harmless_inside_type() {
+    // Check that the presence of this constant doesn't break anything.
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-            };
-        }
-        namespace B {
-            struct C {
-                uint32_t a;
-                A::Bob daft() const { A::Bob bob; bob.a = 12; return bob; }
-            };
-        }
+        struct Bob {
+            uint32_t a;
+        };
+        struct Anna {
+            uint32_t a;
+            const Bob BOB = Bob { 10 };
+        };
     "};
-    let rs = quote! {
-        let b = ffi::B::C { a: 12 };
-        assert_eq!(b.daft().a, 12);
-    };
-    run_test("", hdr, rs, &[], &["A::Bob", "B::C"]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &[], &["Anna"]);
 }
 
 #[test]
-fn test_foreign_ns_meth_ret_nonpod() {
+#[ignore] // https://github.com/google/autocxx/issues/93
+fn test_pod_constant() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        namespace A {
-            struct Bob {
-                uint32_t a;
-            };
-        }
-        namespace B {
-            struct C {
-                uint32_t a;
-                A::Bob daft() const { A::Bob bob; bob.a = 12; return bob; }
-            };
-        }
+        struct Bob {
+            uint32_t a;
+        };
+        const Bob BOB = Bob { 10 };
     "};
     let rs = quote! {
-        let b = ffi::B::C { a: 14 };
-        b.daft().as_ref().unwrap();
+        let a = &ffi::BOB;
+        assert_eq!(a.a, 10);
     };
-    run_test("", hdr, rs, &["A::Bob"], &["B::C"]);
+    run_test("", hdr, rs, &["BOB"], &["Bob"]);
 }
 
 #[test]
-fn test_root_ns_func_arg_pod() {
+fn test_pod_static_harmless_inside_type() {
+    // Check that the presence of this constant doesn't break anything.
+    // Remove this test when the following one is enabled.
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
         struct Bob {
             uint32_t a;
         };
-        namespace B {
-            inline uint32_t daft(Bob a) { return a.a; }
-        }
+        struct Anna {
+            uint32_t a;
+            static Bob BOB;
+        };
+        Bob Anna::BOB = Bob { 10 };
     "};
-    let rs = quote! {
-        let a = ffi::Bob { a: 12 };
-        assert_eq!(ffi::B::daft(a), 12);
-    };
-    run_test("", hdr, rs, &["B::daft"], &["Bob"]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &[], &["Anna"]);
 }
 
 #[test]
-fn test_root_ns_func_arg_nonpod() {
+#[ignore] // https://github.com/google/autocxx/issues/93
+fn test_pod_static() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
         struct Bob {
             uint32_t a;
-            Bob(uint32_t _a) :a(_a) {}
         };
-        namespace B {
-            inline uint32_t daft(Bob a) { return a.a; }
-        }
+        static Bob BOB = Bob { 10 };
     "};
     let rs = quote! {
-        let a = ffi::Bob::make_unique(12);
-        assert_eq!(ffi::B::daft(a), 12);
+        let a = &ffi::BOB;
+        assert_eq!(a.a, 10);
     };
-    run_test("", hdr, rs, &["B::daft", "Bob"], &[]);
+    run_test("", hdr, rs, &["BOB"], &["Bob"]);
 }
 
 #[test]
-fn test_root_ns_meth_arg_pod() {
+#[ignore] // this probably requires code generation on the C++
+          // side. It's not at all clear how best to handle this.
+fn test_non_pod_constant() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
+        #include <string>
         struct Bob {
-            uint32_t a;
+            std::string a;
+            std::string get() { return a };
         };
-        namespace B {
-            struct C {
-                uint32_t a;
-                uint32_t daft(Bob a) const { return a.a; }
-            };
-        }
+        const Bob BOB = Bob { \"hello\" };
     "};
     let rs = quote! {
-        let a = ffi::Bob { a: 12 };
-        let b = ffi::B::C { a: 12 };
-        assert_eq!(b.daft(a), 12);
+        let a = ffi::BOB;
+        // following line assumes that 'a' is a &Bob
+        // but who knows how we'll really do this.
+        assert_eq!(a.get().as_ref().unwrap().to_str().unwrap(), "hello");
     };
-    run_test("", hdr, rs, &[], &["Bob", "B::C"]);
+    run_test("", hdr, rs, &["BOB"], &[]);
 }
 
 #[test]
-fn test_root_ns_meth_arg_nonpod() {
+fn test_templated_typedef() {
     let hdr = indoc! {"
+        #include <string>
         #include <cstdint>
-        #include <memory>
-        struct Bob {
-            uint32_t a;
-            Bob(uint32_t _a) :a(_a) {}
+
+        template <typename STRING_TYPE> class BasicStringPiece {
+        public:
+            const STRING_TYPE* ptr_;
+            size_t length_;
+        };
+        typedef BasicStringPiece<uint8_t> StringPiece;
+
+        struct Origin {
+            Origin() {}
+            StringPiece host;
         };
-        namespace B {
-            struct C {
-                uint32_t a;
-                uint32_t daft(Bob a) const { return a.a; }
-            };
-        }
     "};
     let rs = quote! {
-        let a = ffi::Bob::make_unique(12);
-        let b = ffi::B::C { a: 12 };
-        assert_eq!(b.daft(a), 12);
+        ffi::Origin::make_unique();
     };
-    run_test("", hdr, rs, &["Bob"], &["B::C"]);
+    run_test("", hdr, rs, &["Origin"], &[]);
 }
 
 #[test]
-fn test_root_ns_cons_arg_pod() {
+fn test_struct_templated_typedef() {
     let hdr = indoc! {"
+        #include <string>
         #include <cstdint>
-        #include <memory>
-        struct Bob {
-            uint32_t a;
+
+        struct Concrete {
+            uint8_t a;
+        };
+        template <typename STRING_TYPE> class BasicStringPiece {
+        public:
+            const STRING_TYPE* ptr_;
+            size_t length_;
+        };
+        typedef BasicStringPiece<Concrete> StringPiece;
+
+        struct Origin {
+            Origin() {}
+            StringPiece host;
         };
-        namespace B {
-            struct C {
-                uint32_t a;
-                C(const Bob& input) : a(input.a) {}
-            };
-        }
     "};
     let rs = quote! {
-        let a = ffi::Bob { a: 12 };
-        let b = ffi::B::C::make_unique(&a);
-        assert_eq!(b.as_ref().unwrap().a, 12);
+        ffi::Origin::make_unique();
     };
-    run_test("", hdr, rs, &[], &["B::C", "Bob"]);
+    run_test("", hdr, rs, &["Origin"], &[]);
 }
 
 #[test]
-fn test_root_ns_cons_arg_nonpod() {
+fn test_enum_flags() {
+    let cxx = indoc! {"
+        uint32_t check_perms(Perms perms) {
+            return perms == (READ | WRITE) ? 1 : 0;
+        }
+        uint32_t check_mode(Perms perms) {
+            return perms == (EXEC | READ_WRITE) ? 1 : 0;
+        }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        struct Bob {
-            Bob(uint32_t _a) :a(_a) {}
-            uint32_t a;
+        enum Perms : uint32_t {
+            NONE = 0,
+            READ = 1,
+            WRITE = 2,
+            EXEC = 4,
+            READ_ONLY = 8,
+            READ_WRITE = 16,
+            ALL = 31,
         };
-        namespace B {
-            struct C {
-                uint32_t a;
-                C(const Bob& input) : a(input.a) {}
-            };
-        }
+        uint32_t check_perms(Perms perms);
+        uint32_t check_mode(Perms perms);
     "};
     let rs = quote! {
-        let a = ffi::Bob::make_unique(12);
-        let b = ffi::B::C::make_unique(&a);
-        assert_eq!(b.as_ref().unwrap().a, 12);
+        let perms = ffi::PermsBuilder::new().read().write().build();
+        assert_eq!(ffi::check_perms(perms), 1);
+        assert_eq!(perms, ffi::Perms::READ | ffi::Perms::WRITE);
+        assert!(perms.contains(ffi::Perms::READ));
+        assert!(!perms.contains(ffi::Perms::EXEC));
+        assert!(ffi::Perms::ALL.contains(perms));
+        assert!(ffi::Perms::NONE.is_empty());
+        let mode = ffi::Perms::builder().read_only().exec().read_write().build();
+        assert_eq!(ffi::check_mode(mode), 1);
     };
-    run_test("", hdr, rs, &["Bob"], &["B::C"]);
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["check_perms", "check_mode"],
+            &[],
+            Some(quote! {
+                flags!("Perms", ["READ_ONLY", "READ_WRITE"])
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_root_ns_func_ret_pod() {
+fn test_enum_flags_by_reference() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        struct Bob {
-            uint32_t a;
+        enum Perms : uint32_t {
+            NONE = 0,
+            READ = 1,
+            WRITE = 2,
+            EXEC = 4,
         };
-        namespace B {
-            inline Bob daft() { Bob bob; bob.a = 12; return bob; }
-        }
+        inline void add_exec(Perms& perms) { perms = Perms(perms | EXEC); }
+        inline bool is_readable(const Perms& perms) { return perms & READ; }
     "};
     let rs = quote! {
-        assert_eq!(ffi::B::daft().a, 12);
+        let mut perms = ffi::Perms::READ | ffi::Perms::WRITE;
+        ffi::add_exec(&mut perms);
+        assert_eq!(perms, ffi::Perms::READ | ffi::Perms::WRITE | ffi::Perms::EXEC);
+        assert!(ffi::is_readable(&perms));
+        let mut none = ffi::Perms::NONE;
+        ffi::add_exec(&mut none);
+        assert!(!ffi::is_readable(&none));
+        assert!(none.contains(ffi::Perms::EXEC));
     };
-    run_test("", hdr, rs, &["B::daft"], &["Bob"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["add_exec", "is_readable"],
+            &[],
+            Some(quote! {
+                flags!("Perms")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_root_ns_func_ret_nonpod() {
+fn test_enum_by_mutable_reference_needs_flags() {
     let hdr = indoc! {"
-        #include <cstdint>
-        #include <memory>
-        struct Bob {
-            uint32_t a;
-        };
-        namespace B {
-            inline Bob daft() { Bob bob; bob.a = 12; return bob; }
-        }
+        enum Color { RED, GREEN };
+        inline void make_green(Color& c) { c = GREEN; }
     "};
     let rs = quote! {
-        ffi::B::daft().as_ref().unwrap();
+        let mut c = ffi::Color::RED;
+        ffi::make_green(&mut c);
     };
-    run_test("", hdr, rs, &["B::daft", "Bob"], &[]);
+    run_test_expect_fail("", hdr, rs, &["make_green"], &[]);
 }
 
 #[test]
-fn test_root_ns_meth_ret_pod() {
+fn test_enum_typedef() {
     let hdr = indoc! {"
-        #include <cstdint>
-        #include <memory>
-        struct Bob {
-            uint32_t a;
-        };
-        namespace B {
-            struct C {
-                uint32_t a;
-                Bob daft() const { Bob bob; bob.a = 12; return bob; }
-            };
-        }
+        enum ConstraintSolverParameters_TrailCompression : int {
+            ConstraintSolverParameters_TrailCompression_NO_COMPRESSION = 0,
+            ConstraintSolverParameters_TrailCompression_COMPRESS_WITH_ZLIB = 1,
+            ConstraintSolverParameters_TrailCompression_ConstraintSolverParameters_TrailCompression_INT_MIN_SENTINEL_DO_NOT_USE_ = -2147483648,
+            ConstraintSolverParameters_TrailCompression_ConstraintSolverParameters_TrailCompression_INT_MAX_SENTINEL_DO_NOT_USE_ = 2147483647
+        };
+        typedef ConstraintSolverParameters_TrailCompression TrailCompression;
     "};
     let rs = quote! {
-        let b = ffi::B::C { a: 12 };
-        assert_eq!(b.daft().a, 12);
+        let _ = ffi::TrailCompression::ConstraintSolverParameters_TrailCompression_NO_COMPRESSION;
     };
-    run_test("", hdr, rs, &[], &["Bob", "B::C"]);
+    run_test("", hdr, rs, &["TrailCompression"], &[]);
 }
 
 #[test]
-fn test_root_ns_meth_ret_nonpod() {
+fn test_enum_opaque_declaration() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        struct Bob {
-            uint32_t a;
-        };
-        namespace B {
-            struct C {
-                uint32_t a;
-                Bob daft() const { Bob bob; bob.a = 12; return bob; }
-            };
-        }
+        enum class Color : int32_t;
+        inline int32_t color_value(Color c) { return static_cast<int32_t>(c); }
+        inline Color make_color(int32_t v) { return static_cast<Color>(v); }
     "};
     let rs = quote! {
-        let b = ffi::B::C { a: 12 };
-        b.daft().as_ref().unwrap();
+        assert_eq!(ffi::color_value(ffi::Color::from(3)), 3);
+        assert_eq!(i32::from(ffi::make_color(5)), 5);
+        assert_eq!(ffi::make_color(7), ffi::Color(7));
     };
-    run_test("", hdr, rs, &["Bob"], &["B::C"]);
+    run_test("", hdr, rs, &["color_value", "make_color", "Color"], &[]);
 }
 
 #[test]
-fn test_forward_declaration() {
+fn test_enum_opaque_declaration_then_definition() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <memory>
-        struct A;
-        struct B {
-            B() {}
-            uint32_t a;
-            void daft(const A&) const {}
-            static B daft3(const A&) { B b; return b; }
-        };
-        A* get_a();
-        void delete_a(A*);
-    "};
-    let cpp = indoc! {"
-        struct A {
-            uint32_t a;
-        };
-        A* get_a() {
-            return new A();
-        }
-        void delete_a(A* a) {
-            delete a;
-        }
+        enum class Color : int32_t;
+        inline int32_t color_value(Color c) { return static_cast<int32_t>(c); }
+        enum class Color : int32_t { Red = 1, Green = 2 };
     "};
     let rs = quote! {
-        let b = ffi::B::make_unique();
-        let a = ffi::get_a();
-        b.daft(unsafe { a.as_ref().unwrap() });
-        unsafe { ffi::delete_a(a) };
+        assert_eq!(ffi::color_value(ffi::Color::Green), 2);
     };
-    run_test(cpp, hdr, rs, &["B", "get_a", "delete_a"], &[]);
+    run_test("", hdr, rs, &["color_value", "Color"], &[]);
 }
 
 #[test]
-fn test_ulong() {
+#[ignore] // https://github.com/google/autocxx/issues/264
+fn test_conflicting_usings() {
     let hdr = indoc! {"
-    inline unsigned long daft(unsigned long a) { return a; }
+        #include <cstdint>
+        #include <cstddef>
+        typedef size_t diff;
+        struct A {
+            using diff = diff;
+            diff a;
+        };
+        struct B {
+            using diff = diff;
+            diff a;
+        };
     "};
-    let rs = quote! {
-        assert_eq!(ffi::daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
-    };
-    run_test("", hdr, rs, &["daft"], &[]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &[], &["A", "B"]);
 }
 
-#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
-#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
 #[test]
-fn test_typedef_to_ulong() {
+fn test_conflicting_usings_with_self_declaration1() {
     let hdr = indoc! {"
+        #include <cstdint>
         #include <cstddef>
-        inline size_t daft(size_t a) { return a; }
+        struct common_params {
+            using difference_type = ptrdiff_t;
+        };
+        template <typename Params>
+        class btree_node {
+            public:
+            using difference_type = typename Params::difference_type;
+            Params params;
+        };
+        template <typename Tree>
+        class btree_container {
+            public:
+            using difference_type = typename Tree::difference_type;
+            void clear() {}
+            Tree b;
+            uint32_t a;
+        };
+        typedef btree_container<btree_node<common_params>> my_tree;
     "};
-    let rs = quote! {
-        assert_eq!(ffi::daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
-    };
-    run_test("", hdr, rs, &["daft"], &[]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &["my_tree"], &[]);
 }
 
 #[test]
-fn test_generate_typedef_to_ulong() {
+#[ignore] // https://github.com/google/autocxx/issues/106
+fn test_string_templated_typedef() {
     let hdr = indoc! {"
+        #include <string>
         #include <cstdint>
-        typedef uint32_t fish_t;
+
+        template <typename STRING_TYPE> class BasicStringPiece {
+        public:
+            const STRING_TYPE* ptr_;
+            size_t length_;
+        };
+        typedef BasicStringPiece<std::string> StringPiece;
+
+        struct Origin {
+            Origin() {}
+            StringPiece host;
+        };
     "};
     let rs = quote! {
-        let _: ffi::fish_t;
+        ffi::Origin::make_unique();
     };
-    run_test("", hdr, rs, &[], &["fish_t"]);
+    run_test("", hdr, rs, &["Origin"], &[]);
 }
 
 #[test]
-fn test_ulong_method() {
+fn test_associated_type_problem() {
+    // Regression test for a potential bindgen bug
     let hdr = indoc! {"
-    class A {
-        public:
-        A() {};
-        unsigned long daft(unsigned long a) const { return a; }
-    };
+        namespace a {
+        template <typename> class b {};
+        } // namespace a
+        class bl {
+          a::b<bl> bm;
+        };
+        struct B {
+            int a;
+        };
     "};
-    let rs = quote! {
-        let a = ffi::A::make_unique();
-        assert_eq!(a.as_ref().unwrap().daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
-    };
-    run_test("", hdr, rs, &["A"], &[]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &["B"], &[]);
 }
 
+#[ignore] // https://github.com/rust-lang/rust-bindgen/issues/1924
 #[test]
-fn test_ulong_wrapped_method() {
+fn test_associated_type_templated_typedef() {
     let hdr = indoc! {"
-    #include <cstdint>
-    struct B {
-        B() {};
-        uint32_t a;
-    };
-    class A {
+        #include <string>
+        #include <cstdint>
+
+        template <typename STRING_TYPE> class BasicStringPiece {
         public:
-        A() {};
-        unsigned long daft(unsigned long a, B) const { return a; }
-    };
+            typedef size_t size_type;
+            typedef typename STRING_TYPE::value_type value_type;
+            const value_type* ptr_;
+            size_type length_;
+        };
+
+        typedef BasicStringPiece<std::string> StringPiece;
+
+        struct Origin {
+            // void SetHost(StringPiece host);
+            StringPiece host;
+        };
     "};
     let rs = quote! {
-        let b = ffi::B::make_unique();
-        let a = ffi::A::make_unique();
-        assert_eq!(a.as_ref().unwrap().daft(autocxx::c_ulong(34), b), autocxx::c_ulong(34));
+        ffi::Origin::make_unique();
     };
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    run_test("", hdr, rs, &["Origin"], &[]);
 }
 
 #[test]
-fn test_reserved_name() {
+fn test_foreign_ns_func_arg_pod() {
     let hdr = indoc! {"
         #include <cstdint>
-        inline uint32_t async(uint32_t a) { return a; }
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+            };
+        }
+        namespace B {
+            inline uint32_t daft(A::Bob a) { return a.a; }
+        }
     "};
     let rs = quote! {
-        assert_eq!(ffi::async_(34), 34);
+        let a = ffi::A::Bob { a: 12 };
+        assert_eq!(ffi::B::daft(a), 12);
     };
-    run_test("", hdr, rs, &["async_"], &[]);
+    run_test("", hdr, rs, &["B::daft"], &["A::Bob"]);
 }
 
-#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
-#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
 #[test]
-fn test_nested_type() {
-    // Test that we can import APIs that use nested types.
-    // As a regression test, we also test that the nested type `A::B` doesn't conflict with the
-    // top-level type `B`. This used to cause compile errors.
+fn test_foreign_ns_func_arg_nonpod() {
     let hdr = indoc! {"
-        struct A {
-            A() {}
-            struct B {
-                B() {}
+        #include <cstdint>
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+                Bob(uint32_t _a) :a(_a) {}
             };
-            enum C {};
-            using D = int;
-        };
-        struct B {
-            B() {}
-            void method_on_top_level_type() const {}
-        };
-        void take_A_B(A::B);
-        void take_A_C(A::C);
-        void take_A_D(A::D);
+        }
+        namespace B {
+            inline uint32_t daft(A::Bob a) { return a.a; }
+        }
     "};
     let rs = quote! {
-        let _ = ffi::A::make_unique();
-        let b = ffi::B::make_unique();
-        b.as_ref().unwrap().method_on_top_level_type();
+        let a = ffi::A::Bob::make_unique(12);
+        assert_eq!(ffi::B::daft(a), 12);
     };
-    run_test("", hdr, rs, &["A", "B", "take_A_B", "take_A_C"], &[]);
+    run_test("", hdr, rs, &["B::daft", "A::Bob"], &[]);
 }
 
 #[test]
-fn test_nested_type_in_namespace() {
-    // Test that we can import APIs that use nested types in a namespace.
-    // We can't make this part of the previous test as autocxx drops the
-    // namespace, so `A::B` and `N::A::B` would be imported as the same
-    // type.
+fn test_foreign_ns_meth_arg_pod() {
     let hdr = indoc! {"
-        namespace N {
-            struct A {
-                A() {}
-                struct B {
-                    B() {}
-                };
-            };
-        };
-        void take_A_B(N::A::B);
+        #include <cstdint>
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+            };
+        }
+        namespace B {
+            struct C {
+                uint32_t a;
+                uint32_t daft(A::Bob a) const { return a.a; }
+            };
+        }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["take_A_B"], &[]);
+    let rs = quote! {
+        let a = ffi::A::Bob { a: 12 };
+        let b = ffi::B::C { a: 12 };
+        assert_eq!(b.daft(a), 12);
+    };
+    run_test("", hdr, rs, &[], &["A::Bob", "B::C"]);
 }
 
 #[test]
-fn test_nested_enum_in_namespace() {
+fn test_foreign_ns_meth_arg_nonpod() {
     let hdr = indoc! {"
-        namespace N {
-            struct A {
-                A() {}
-                enum B {
-                    C,
-                    D,
-                };
+        #include <cstdint>
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+                Bob(uint32_t _a) :a(_a) {}
             };
-        };
-        void take_A_B(N::A::B);
+        }
+        namespace B {
+            struct C {
+                uint32_t a;
+                uint32_t daft(A::Bob a) const { return a.a; }
+            };
+        }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["take_A_B"], &[]);
+    let rs = quote! {
+        let a = ffi::A::Bob::make_unique(12);
+        let b = ffi::B::C { a: 12 };
+        assert_eq!(b.daft(a), 12);
+    };
+    run_test("", hdr, rs, &["A::Bob"], &["B::C"]);
 }
 
 #[test]
-fn test_abstract_nested_type() {
+fn test_foreign_ns_cons_arg_pod() {
     let hdr = indoc! {"
-        namespace N {
-            class A {
-            public:
-                A() {}
-                class B {
-                private:
-                    B() {}
-                public:
-                    virtual ~B() {}
-                    virtual void Foo() = 0;
-                };
+        #include <cstdint>
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
             };
-        };
-        void take_A_B(const N::A::B&);
+        }
+        namespace B {
+            struct C {
+                uint32_t a;
+                C(const A::Bob& input) : a(input.a) {}
+            };
+        }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["take_A_B", "N::A_B"], &[]);
+    let rs = quote! {
+        let a = ffi::A::Bob { a: 12 };
+        let b = ffi::B::C::make_unique(&a);
+        assert_eq!(b.as_ref().unwrap().a, 12);
+    };
+    run_test("", hdr, rs, &[], &["B::C", "A::Bob"]);
 }
 
 #[test]
-fn test_nested_type_constructor() {
+fn test_foreign_ns_cons_arg_nonpod() {
     let hdr = indoc! {"
-        #include <string>
-        class A {
-        public:
-            class B {
-            public:
-                B(const std::string&) {}
-                int b;
+        #include <cstdint>
+        #include <memory>
+        namespace A {
+            struct Bob {
+                Bob(uint32_t _a) :a(_a) {}
+                uint32_t a;
             };
-            int a;
-        };
+        }
+        namespace B {
+            struct C {
+                uint32_t a;
+                C(const A::Bob& input) : a(input.a) {}
+            };
+        }
     "};
     let rs = quote! {
-        ffi::A_B::make_unique(&ffi::make_string("Hello"));
+        let a = ffi::A::Bob::make_unique(12);
+        let b = ffi::B::C::make_unique(&a);
+        assert_eq!(b.as_ref().unwrap().a, 12);
     };
-    run_test("", hdr, rs, &["A_B"], &[]);
+    run_test("", hdr, rs, &["A::Bob"], &["B::C"]);
 }
 
 #[test]
-fn test_generic_type() {
+fn test_foreign_ns_func_ret_pod() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <string>
-        template<typename TY>
-        struct Container {
-            Container(TY a_) : a(a_) {}
-            TY a;
-        };
-        struct Secondary {
-            Secondary() {}
-            void take_a(const Container<char>) const {}
-            void take_b(const Container<uint16_t>) const {}
-            uint16_t take_c(std::string a) const { return 10 + a.size(); }
-        };
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+            };
+        }
+        namespace B {
+            inline A::Bob daft() { A::Bob bob; bob.a = 12; return bob; }
+        }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        let item = ffi::Secondary::make_unique();
-        assert_eq!(item.take_c("hello".into_cpp()), 15)
+        assert_eq!(ffi::B::daft().a, 12);
     };
-    run_test("", hdr, rs, &["Secondary"], &[]);
+    run_test("", hdr, rs, &["B::daft"], &["A::Bob"]);
 }
 
 #[test]
-fn test_cycle_generic_type() {
+fn test_foreign_ns_func_ret_nonpod() {
     let hdr = indoc! {"
         #include <cstdint>
-        template<typename TY>
-        struct Container {
-            Container(TY a_) : a(a_) {}
-            TY a;
-        };
-        inline Container<char> make_thingy() {
-            Container<char> a('a');
-            return a;
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+            };
         }
-        typedef Container<char> Concrete;
-        inline uint32_t take_thingy(Concrete a) {
-            return a.a;
+        namespace B {
+            inline A::Bob daft() { A::Bob bob; bob.a = 12; return bob; }
         }
     "};
     let rs = quote! {
-        assert_eq!(ffi::take_thingy(ffi::make_thingy()), 'a' as u32)
+        ffi::B::daft().as_ref().unwrap();
     };
-    run_test("", hdr, rs, &["take_thingy", "make_thingy"], &[]);
+    run_test("", hdr, rs, &["B::daft", "A::Bob"], &[]);
 }
 
 #[test]
-fn test_virtual_fns() {
+fn test_foreign_ns_meth_ret_pod() {
     let hdr = indoc! {"
         #include <cstdint>
-        class A {
-        public:
-            A(uint32_t num) : b(num) {}
-            virtual uint32_t foo(uint32_t a) { return a+1; };
-            virtual ~A() {}
-            uint32_t b;
-        };
-        class B: public A {
-        public:
-            B() : A(3), c(4) {}
-            virtual uint32_t foo(uint32_t a) { return a+2; };
-            uint32_t c;
-        };
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+            };
+        }
+        namespace B {
+            struct C {
+                uint32_t a;
+                A::Bob daft() const { A::Bob bob; bob.a = 12; return bob; }
+            };
+        }
     "};
     let rs = quote! {
-        let mut a = ffi::A::make_unique(12);
-        assert_eq!(a.pin_mut().foo(2), 3);
-        let mut b = ffi::B::make_unique();
-        assert_eq!(b.pin_mut().foo(2), 4);
+        let b = ffi::B::C { a: 12 };
+        assert_eq!(b.daft().a, 12);
     };
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    run_test("", hdr, rs, &[], &["A::Bob", "B::C"]);
 }
 
 #[test]
-fn test_const_virtual_fns() {
+fn test_foreign_ns_meth_ret_nonpod() {
     let hdr = indoc! {"
         #include <cstdint>
-        class A {
-        public:
-            A(uint32_t num) : b(num) {}
-            virtual uint32_t foo(uint32_t a) const { return a+1; };
-            virtual ~A() {}
-            uint32_t b;
-        };
-        class B: public A {
-        public:
-            B() : A(3), c(4) {}
-            virtual uint32_t foo(uint32_t a) const { return a+2; };
-            uint32_t c;
-        };
+        #include <memory>
+        namespace A {
+            struct Bob {
+                uint32_t a;
+            };
+        }
+        namespace B {
+            struct C {
+                uint32_t a;
+                A::Bob daft() const { A::Bob bob; bob.a = 12; return bob; }
+            };
+        }
     "};
     let rs = quote! {
-        let a = ffi::A::make_unique(12);
-        assert_eq!(a.foo(2), 3);
-        let b = ffi::B::make_unique();
-        assert_eq!(b.foo(2), 4);
+        let b = ffi::B::C { a: 14 };
+        b.daft().as_ref().unwrap();
     };
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    run_test("", hdr, rs, &["A::Bob"], &["B::C"]);
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/197
-fn test_virtual_fns_inheritance() {
+fn test_root_ns_func_arg_pod() {
     let hdr = indoc! {"
         #include <cstdint>
-        class A {
-        public:
-            A(uint32_t num) : b(num) {}
-            virtual uint32_t foo(uint32_t a) { return a+1; };
-            virtual ~A() {}
-            uint32_t b;
-        };
-        class B: public A {
-        public:
-            B() : A(3), c(4) {}
-            uint32_t c;
+        #include <memory>
+        struct Bob {
+            uint32_t a;
         };
+        namespace B {
+            inline uint32_t daft(Bob a) { return a.a; }
+        }
     "};
     let rs = quote! {
-        let mut b = ffi::B::make_unique();
-        assert_eq!(b.pin_mut().foo(2), 3);
+        let a = ffi::Bob { a: 12 };
+        assert_eq!(ffi::B::daft(a), 12);
     };
-    run_test("", hdr, rs, &["B"], &[]);
+    run_test("", hdr, rs, &["B::daft"], &["Bob"]);
 }
 
 #[test]
-fn test_vector_cycle_up() {
+fn test_root_ns_func_arg_nonpod() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <vector>
         #include <memory>
-        struct A {
+        struct Bob {
             uint32_t a;
+            Bob(uint32_t _a) :a(_a) {}
         };
-        inline uint32_t take_vec(std::unique_ptr<std::vector<A>> many_as) {
-            return many_as->size();
-        }
-        inline std::unique_ptr<std::vector<A>> get_vec() {
-            auto items = std::make_unique<std::vector<A>>();
-            items->push_back(A { 3 });
-            items->push_back(A { 4 });
-            return items;
+        namespace B {
+            inline uint32_t daft(Bob a) { return a.a; }
         }
     "};
     let rs = quote! {
-        let v = ffi::get_vec();
-        assert_eq!(v.as_ref().unwrap().is_empty(), false);
-        assert_eq!(ffi::take_vec(v), 2);
+        let a = ffi::Bob::make_unique(12);
+        assert_eq!(ffi::B::daft(a), 12);
     };
-    run_test("", hdr, rs, &["take_vec", "get_vec"], &[]);
+    run_test("", hdr, rs, &["B::daft", "Bob"], &[]);
 }
 
 #[test]
-fn test_vector_cycle_bare() {
+fn test_root_ns_meth_arg_pod() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <vector>
-        struct A {
+        #include <memory>
+        struct Bob {
             uint32_t a;
         };
-        inline uint32_t take_vec(std::vector<A> many_as) {
-            return many_as.size();
-        }
-        inline std::vector<A> get_vec() {
-            std::vector<A> items;
-            items.push_back(A { 3 });
-            items.push_back(A { 4 });
-            return items;
+        namespace B {
+            struct C {
+                uint32_t a;
+                uint32_t daft(Bob a) const { return a.a; }
+            };
         }
     "};
     let rs = quote! {
-        assert_eq!(ffi::take_vec(ffi::get_vec()), 2);
+        let a = ffi::Bob { a: 12 };
+        let b = ffi::B::C { a: 12 };
+        assert_eq!(b.daft(a), 12);
     };
-    run_test("", hdr, rs, &["take_vec", "get_vec"], &[]);
+    run_test("", hdr, rs, &[], &["Bob", "B::C"]);
 }
 
 #[test]
-fn test_typedef_to_std() {
+fn test_root_ns_meth_arg_nonpod() {
     let hdr = indoc! {"
-        #include <string>
-        typedef std::string my_string;
-        inline uint32_t take_str(my_string a) {
-            return a.size();
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            uint32_t a;
+            Bob(uint32_t _a) :a(_a) {}
+        };
+        namespace B {
+            struct C {
+                uint32_t a;
+                uint32_t daft(Bob a) const { return a.a; }
+            };
         }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        assert_eq!(ffi::take_str("hello".into_cpp()), 5);
+        let a = ffi::Bob::make_unique(12);
+        let b = ffi::B::C { a: 12 };
+        assert_eq!(b.daft(a), 12);
     };
-    run_test("", hdr, rs, &["take_str"], &[]);
+    run_test("", hdr, rs, &["Bob"], &["B::C"]);
 }
 
 #[test]
-fn test_typedef_to_up_in_fn_call() {
+fn test_root_ns_cons_arg_pod() {
     let hdr = indoc! {"
-        #include <string>
+        #include <cstdint>
         #include <memory>
-        typedef std::unique_ptr<std::string> my_string;
-        inline uint32_t take_str(my_string a) {
-            return a->size();
+        struct Bob {
+            uint32_t a;
+        };
+        namespace B {
+            struct C {
+                uint32_t a;
+                C(const Bob& input) : a(input.a) {}
+            };
         }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        assert_eq!(ffi::take_str("hello".into_cpp()), 5);
+        let a = ffi::Bob { a: 12 };
+        let b = ffi::B::C::make_unique(&a);
+        assert_eq!(b.as_ref().unwrap().a, 12);
     };
-    run_test("", hdr, rs, &["take_str"], &[]);
+    run_test("", hdr, rs, &[], &["B::C", "Bob"]);
 }
 
 #[test]
-fn test_typedef_in_pod_struct() {
+fn test_root_ns_cons_arg_nonpod() {
     let hdr = indoc! {"
-        #include <string>
-        typedef uint32_t my_int;
-        struct A {
-            my_int a;
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            Bob(uint32_t _a) :a(_a) {}
+            uint32_t a;
         };
-        inline uint32_t take_a(A a) {
-            return a.a;
+        namespace B {
+            struct C {
+                uint32_t a;
+                C(const Bob& input) : a(input.a) {}
+            };
         }
     "};
     let rs = quote! {
-        let a = ffi::A {
-            a: 32,
-        };
-        assert_eq!(ffi::take_a(a), 32);
+        let a = ffi::Bob::make_unique(12);
+        let b = ffi::B::C::make_unique(&a);
+        assert_eq!(b.as_ref().unwrap().a, 12);
     };
-    run_test("", hdr, rs, &["take_a"], &["A"]);
+    run_test("", hdr, rs, &["Bob"], &["B::C"]);
 }
 
 #[test]
-fn test_cint_in_pod_struct() {
+fn test_root_ns_func_ret_pod() {
     let hdr = indoc! {"
-        #include <string>
-        struct A {
-            int a;
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            uint32_t a;
         };
-        inline uint32_t take_a(A a) {
-            return a.a;
+        namespace B {
+            inline Bob daft() { Bob bob; bob.a = 12; return bob; }
         }
     "};
     let rs = quote! {
-        let a = ffi::A {
-            a: 32,
-        };
-        assert_eq!(ffi::take_a(a), 32);
+        assert_eq!(ffi::B::daft().a, 12);
     };
-    run_test("", hdr, rs, &["take_a"], &["A"]);
+    run_test("", hdr, rs, &["B::daft"], &["Bob"]);
 }
 
 #[test]
-fn test_string_in_struct() {
+fn test_root_ns_func_ret_nonpod() {
     let hdr = indoc! {"
-        #include <string>
+        #include <cstdint>
         #include <memory>
-        struct A {
-            std::string a;
+        struct Bob {
+            uint32_t a;
         };
-        inline A make_a(std::string b) {
-            A bob;
-            bob.a = b;
-            return bob;
-        }
-        inline uint32_t take_a(A a) {
-            return a.a.size();
+        namespace B {
+            inline Bob daft() { Bob bob; bob.a = 12; return bob; }
         }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+        ffi::B::daft().as_ref().unwrap();
     };
-    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+    run_test("", hdr, rs, &["B::daft", "Bob"], &[]);
 }
 
 #[test]
-fn test_up_in_struct() {
+fn test_root_ns_meth_ret_pod() {
     let hdr = indoc! {"
-        #include <string>
+        #include <cstdint>
         #include <memory>
-        struct A {
-            std::unique_ptr<std::string> a;
+        struct Bob {
+            uint32_t a;
         };
-        inline A make_a(std::string b) {
-            A bob;
-            bob.a = std::make_unique<std::string>(b);
-            return bob;
-        }
-        inline uint32_t take_a(A a) {
-            return a.a->size();
+        namespace B {
+            struct C {
+                uint32_t a;
+                Bob daft() const { Bob bob; bob.a = 12; return bob; }
+            };
         }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+        let b = ffi::B::C { a: 12 };
+        assert_eq!(b.daft().a, 12);
     };
-    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+    run_test("", hdr, rs, &[], &["Bob", "B::C"]);
 }
 
 #[test]
-fn test_typedef_to_std_in_struct() {
+fn test_root_ns_meth_ret_nonpod() {
     let hdr = indoc! {"
-        #include <string>
-        typedef std::string my_string;
-        struct A {
-            my_string a;
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            uint32_t a;
         };
-        inline A make_a(std::string b) {
-            A bob;
-            bob.a = b;
-            return bob;
-        }
-        inline uint32_t take_a(A a) {
-            return a.a.size();
+        namespace B {
+            struct C {
+                uint32_t a;
+                Bob daft() const { Bob bob; bob.a = 12; return bob; }
+            };
         }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+        let b = ffi::B::C { a: 12 };
+        b.daft().as_ref().unwrap();
     };
-    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+    run_test("", hdr, rs, &["Bob"], &["B::C"]);
 }
 
 #[test]
-fn test_typedef_to_up_in_struct() {
+fn test_forward_declaration() {
     let hdr = indoc! {"
-        #include <string>
+        #include <cstdint>
         #include <memory>
-        typedef std::unique_ptr<std::string> my_string;
+        struct A;
+        struct B {
+            B() {}
+            uint32_t a;
+            void daft(const A&) const {}
+            static B daft3(const A&) { B b; return b; }
+        };
+        A* get_a();
+        void delete_a(A*);
+    "};
+    let cpp = indoc! {"
         struct A {
-            my_string a;
+            uint32_t a;
         };
-        inline A make_a(std::string b) {
-            A bob;
-            bob.a = std::make_unique<std::string>(b);
-            return bob;
-        }
-        inline uint32_t take_a(A a) {
-            return a.a->size();
+        A* get_a() {
+            return new A();
+        }
+        void delete_a(A* a) {
+            delete a;
         }
     "};
     let rs = quote! {
-        use ffi::ToCppString;
-        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+        let b = ffi::B::make_unique();
+        let a = ffi::get_a();
+        b.daft(unsafe { a.as_ref().unwrap() });
+        unsafe { ffi::delete_a(a) };
     };
-    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+    run_test(cpp, hdr, rs, &["B", "get_a", "delete_a"], &[]);
 }
 
 #[test]
-fn test_float() {
+fn test_ulong() {
     let hdr = indoc! {"
-    inline float daft(float a) { return a; }
+    inline unsigned long daft(unsigned long a) { return a; }
     "};
     let rs = quote! {
-        assert_eq!(ffi::daft(34.0f32), 34.0f32);
+        assert_eq!(ffi::daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
     };
     run_test("", hdr, rs, &["daft"], &[]);
 }
 
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
 #[test]
-fn test_double() {
+fn test_typedef_to_ulong() {
     let hdr = indoc! {"
-    inline double daft(double a) { return a; }
+        #include <cstddef>
+        inline size_t daft(size_t a) { return a; }
     "};
     let rs = quote! {
-        assert_eq!(ffi::daft(34.0f64), 34.0f64);
+        assert_eq!(ffi::daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
     };
     run_test("", hdr, rs, &["daft"], &[]);
 }
 
 #[test]
-fn test_issues_217_222() {
+fn test_generate_typedef_to_ulong() {
     let hdr = indoc! {"
-    #include <string>
-    #include <cstdint>
-    #include <cstddef>
+        #include <cstdint>
+        typedef uint32_t fish_t;
+    "};
+    let rs = quote! {
+        let _: ffi::fish_t;
+    };
+    run_test("", hdr, rs, &[], &["fish_t"]);
+}
 
-    template <typename STRING_TYPE> class BasicStringPiece {
+#[test]
+fn test_ulong_method() {
+    let hdr = indoc! {"
+    class A {
         public:
-         typedef size_t size_type;
-         typedef typename STRING_TYPE::traits_type traits_type;
-         typedef typename STRING_TYPE::value_type value_type;
-         typedef const value_type* pointer;
-         typedef const value_type& reference;
-         typedef const value_type& const_reference;
-         typedef ptrdiff_t difference_type;
-         typedef const value_type* const_iterator;
-         typedef std::reverse_iterator<const_iterator> const_reverse_iterator;
-         static const size_type npos;
+        A() {};
+        unsigned long daft(unsigned long a) const { return a; }
     };
-
-    template<typename CHAR>
-    class Replacements {
-     public:
-      Replacements() {
-      }
-      void SetScheme(const CHAR*) {
-      }
-      uint16_t a;
+    "};
+    let rs = quote! {
+        let a = ffi::A::make_unique();
+        assert_eq!(a.as_ref().unwrap().daft(autocxx::c_ulong(34)), autocxx::c_ulong(34));
     };
+    run_test("", hdr, rs, &["A"], &[]);
+}
 
-    struct Component {
-        uint16_t a;
+#[test]
+fn test_ulong_wrapped_method() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct B {
+        B() {};
+        uint32_t a;
     };
-
-    template <typename STR>
-    class StringPieceReplacements : public Replacements<typename STR::value_type> {
-        private:
-         using CharT = typename STR::value_type;
-         using StringPieceT = BasicStringPiece<STR>;
-         using ParentT = Replacements<CharT>;
-         using SetterFun = void (ParentT::*)(const CharT*, const Component&);
-         void SetImpl(SetterFun, StringPieceT) {
-        }
+    class A {
         public:
-        void SetSchemeStr(const CharT* str) { SetImpl(&ParentT::SetScheme, str); }
+        A() {};
+        unsigned long daft(unsigned long a, B) const { return a; }
+    };
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        let a = ffi::A::make_unique();
+        assert_eq!(a.as_ref().unwrap().daft(autocxx::c_ulong(34), b), autocxx::c_ulong(34));
     };
+    run_test("", hdr, rs, &["A", "B"], &[]);
+}
 
-    class GURL {
+#[test]
+fn test_promoted_params() {
+    let hdr = indoc! {"
+    inline long add(long a, long b) { return a + b; }
+    inline unsigned long long widen(unsigned long long a) { return a; }
+    inline double half(double a) { return a / 2; }
+    class A {
         public:
-        typedef StringPieceReplacements<std::string> UrlReplacements;
-        GURL() {}
-        GURL ReplaceComponents(const Replacements<char>&) const {
-            return GURL();
-        }
-        uint16_t a;
+        A() {};
+        long twice(long a) const { return a * 2; }
     };
     "};
     let rs = quote! {
-        ffi::GURL::make_unique();
+        let a: i32 = 3;
+        let b: i16 = 4;
+        assert_eq!(ffi::add(a, b), autocxx::c_long(7));
+        assert_eq!(ffi::add(autocxx::c_long(1), 2), autocxx::c_long(3));
+        assert_eq!(ffi::widen(5u32), autocxx::c_ulonglong(5));
+        assert_eq!(ffi::half(3.0f32), 1.5);
+        let a = ffi::A::make_unique();
+        assert_eq!(a.twice(21), autocxx::c_long(42));
+    };
+    run_test("", hdr, rs, &["add", "widen", "half", "A"], &[]);
+}
+
+#[test]
+fn test_byte_param_not_promoted() {
+    let hdr = indoc! {"
+    #include <cstddef>
+    inline std::byte mask(std::byte value, long bits) {
+        return value & static_cast<std::byte>(bits);
+    }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::mask(autocxx::c_byte(7), 3i32), autocxx::c_byte(3));
     };
-    // The block! directives here are to avoid running into
-    // https://github.com/rust-lang/rust-bindgen/pull/1975
     run_test_ex(
         "",
         hdr,
         rs,
-        quote! { generate!("GURL") block!("StringPiece") block!("Replacements") },
-        None,
+        directives_from_lists(&["mask"], &[], None),
+        make_clang_arg_adder(&["-std=c++17"]),
         None,
         None,
     );
 }
 
 #[test]
-#[ignore] // https://github.com/rust-lang/rust-bindgen/pull/1975, https://github.com/google/autocxx/issues/106
-fn test_dependent_qualified_type() {
+fn test_reserved_name() {
     let hdr = indoc! {"
-    #include <stddef.h>
-    struct MyString {
-        typedef char value_type;
-    };
-    template<typename T> struct MyStringView {
-        typedef typename T::value_type view_value_type;
-        const view_value_type* start;
-        size_t length;
-    };
-    const char* HELLO = \"hello\";
-    inline MyStringView<MyString> make_string_view() {
-        MyStringView<MyString> r;
-        r.start = HELLO;
-        r.length = 2;
-        return r;
-    }
-    inline size_t take_string_view(const MyStringView<MyString>& bit) {
-        return bit.length;
-    }
+        #include <cstdint>
+        inline uint32_t async(uint32_t a) { return a; }
     "};
     let rs = quote! {
-        let sv = ffi::make_string_view();
-        assert_eq!(ffi::take_string_view(sv.as_ref().unwrap()), 2);
+        assert_eq!(ffi::async_(34), 34);
     };
-    run_test("", hdr, rs, &["take_string_view", "make_string_view"], &[]);
+    run_test("", hdr, rs, &["async_"], &[]);
 }
 
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
 #[test]
-fn test_simple_dependent_qualified_type() {
-    // bindgen seems to cope with this case just fine
+fn test_nested_type() {
+    // Test that we can import APIs that use nested types.
+    // As a regression test, we also test that the nested type `A::B` doesn't conflict with the
+    // top-level type `B`. This used to cause compile errors.
     let hdr = indoc! {"
-    #include <stddef.h>
-    #include <stdint.h>
-    struct MyString {
-        typedef char value_type;
-    };
-    template<typename T> struct MyStringView {
-        typedef typename T::value_type view_value_type;
-        const view_value_type* start;
-        size_t length;
-    };
-    typedef MyStringView<MyString>::view_value_type MyChar;
-    inline MyChar make_char() {
-        return 'a';
-    }
-    inline uint32_t take_char(MyChar c) {
-        return static_cast<unsigned char>(c);
-    }
+        struct A {
+            A() {}
+            struct B {
+                B() {}
+            };
+            enum C {};
+            using D = int;
+        };
+        struct B {
+            B() {}
+            void method_on_top_level_type() const {}
+        };
+        void take_A_B(A::B);
+        void take_A_C(A::C);
+        void take_A_D(A::D);
     "};
     let rs = quote! {
-        let c = ffi::make_char();
-        assert_eq!(ffi::take_char(c), 97);
+        let _ = ffi::A::make_unique();
+        let b = ffi::B::make_unique();
+        b.as_ref().unwrap().method_on_top_level_type();
     };
-    run_test("", hdr, rs, &["make_char", "take_char"], &[]);
+    run_test("", hdr, rs, &["A", "B", "take_A_B", "take_A_C"], &[]);
 }
 
 #[test]
-fn test_ignore_dependent_qualified_type() {
+fn test_nested_type_in_namespace() {
+    // Test that we can import APIs that use nested types in a namespace.
+    // We can't make this part of the previous test as autocxx drops the
+    // namespace, so `A::B` and `N::A::B` would be imported as the same
+    // type.
     let hdr = indoc! {"
-    #include <stddef.h>
-    struct MyString {
-        typedef char value_type;
-    };
-    template<typename T> struct MyStringView {
-        typedef typename T::value_type view_value_type;
-        const view_value_type* start;
-        size_t length;
-    };
-    MyStringView<MyString> make_string_view();
-    struct B {
-        B() {}
-        inline size_t take_string_view(const MyStringView<MyString> bit) {
-            return bit.length;
-        }
-    };
-    "};
-    let cpp = indoc! {"
-    const char* HELLO = \"hello\";
-    MyStringView<MyString> make_string_view() {
-        MyStringView<MyString> r;
-        r.start = HELLO;
-        r.length = 2;
-        return r;
-    }
+        namespace N {
+            struct A {
+                A() {}
+                struct B {
+                    B() {}
+                };
+            };
+        };
+        void take_A_B(N::A::B);
     "};
-    let rs = quote! {
-        ffi::B::make_unique();
-    };
-    run_test(cpp, hdr, rs, &["B"], &[]);
+    let rs = quote! {};
+    run_test("", hdr, rs, &["take_A_B"], &[]);
 }
 
 #[test]
-fn test_ignore_dependent_qualified_type_reference() {
+fn test_template_enum() {
     let hdr = indoc! {"
-    #include <stddef.h>
-    struct MyString {
-        typedef char value_type;
-    };
-    template<typename T> struct MyStringView {
-        typedef typename T::value_type view_value_type;
-        const view_value_type* start;
-        size_t length;
-    };
-    MyStringView<MyString> make_string_view();
-    struct B {
-        B() {}
-        inline size_t take_string_view(const MyStringView<MyString>& bit) {
-            return bit.length;
+        #include <cstdint>
+        namespace ns {
+            template <typename T> class Widget {
+            public:
+                enum class State { Idle, Busy };
+                T value;
+            };
+        }
+        inline ns::Widget<int>::State get_state() { return ns::Widget<int>::State::Busy; }
+        inline uint32_t state_code(ns::Widget<int>::State state) {
+            return state == ns::Widget<int>::State::Busy ? 1 : 0;
         }
-    };
-    "};
-    let cpp = indoc! {"
-    const char* HELLO = \"hello\";
-    MyStringView<MyString> make_string_view() {
-        MyStringView<MyString> r;
-        r.start = HELLO;
-        r.length = 2;
-        return r;
-    }
     "};
     let rs = quote! {
-        ffi::B::make_unique();
+        assert!(matches!(ffi::get_state(), ffi::ns::Widget_int_State::Busy));
+        assert_eq!(ffi::state_code(ffi::ns::Widget_int_State::Busy), 1);
+        assert_eq!(ffi::state_code(ffi::ns::Widget_int_State::Idle), 0);
     };
-    run_test(cpp, hdr, rs, &["B"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["get_state", "state_code", "ns::Widget_int_State"],
+            &[],
+            Some(quote! {
+                template_enum!("ns::Widget<int>::State")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_specialization() {
+fn test_nested_enum_in_namespace() {
     let hdr = indoc! {"
-    #include <stddef.h>
-    #include <stdint.h>
-    #include <string>
-    #include <type_traits>
-
-    template <typename T, bool = std::is_trivially_destructible<T>::value>
-    struct OptionalStorageBase {
-        T value_;
-    };
-
-    template <typename T,
-    bool = std::is_trivially_copy_constructible<T>::value,
-    bool = std::is_trivially_move_constructible<T>::value>
-    struct OptionalStorage : OptionalStorageBase<T> {};
-
-    template <typename T>
-    struct OptionalStorage<T,
-                       true /* trivially copy constructible */,
-                       false /* trivially move constructible */>
-    : OptionalStorageBase<T> {
-    };
-
-    template <typename T>
-    struct OptionalStorage<T,
-                       false /* trivially copy constructible */,
-                       true /* trivially move constructible */>
-    : OptionalStorageBase<T> {
-    };
+        namespace N {
+            struct A {
+                A() {}
+                enum B {
+                    C,
+                    D,
+                };
+            };
+        };
+        void take_A_B(N::A::B);
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["take_A_B"], &[]);
+}
 
-    template <typename T>
-    struct OptionalStorage<T,
-                       true /* trivially copy constructible */,
-                       true /* trivially move constructible */>
-    : OptionalStorageBase<T> {
-    };
+#[test]
+fn test_abstract_nested_type() {
+    let hdr = indoc! {"
+        namespace N {
+            class A {
+            public:
+                A() {}
+                class B {
+                private:
+                    B() {}
+                public:
+                    virtual ~B() {}
+                    virtual void Foo() = 0;
+                };
+            };
+        };
+        void take_A_B(const N::A::B&);
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["take_A_B", "N::A_B"], &[]);
+}
 
-    template <typename T>
-    class OptionalBase {
-    private:
-        OptionalStorage<T> storage_;
+#[test]
+fn test_nested_type_constructor() {
+    let hdr = indoc! {"
+        #include <string>
+        class A {
+        public:
+            class B {
+            public:
+                B(const std::string&) {}
+                int b;
+            };
+            int a;
+        };
+    "};
+    let rs = quote! {
+        ffi::A_B::make_unique(&ffi::make_string("Hello"));
     };
+    run_test("", hdr, rs, &["A_B"], &[]);
+}
 
-    template <typename T>
-    class Optional : public OptionalBase<T> {
-
+#[test]
+fn test_generic_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        template<typename TY>
+        struct Container {
+            Container(TY a_) : a(a_) {}
+            TY a;
+        };
+        struct Secondary {
+            Secondary() {}
+            void take_a(const Container<char>) const {}
+            void take_b(const Container<uint16_t>) const {}
+            uint16_t take_c(std::string a) const { return 10 + a.size(); }
+        };
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        let item = ffi::Secondary::make_unique();
+        assert_eq!(item.take_c("hello".into_cpp()), 15)
     };
+    run_test("", hdr, rs, &["Secondary"], &[]);
+}
 
-    struct B {
-        B() {}
-        void take_optional(Optional<std::string>) {}
-        uint32_t a;
-    };
+#[test]
+fn test_cycle_generic_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        template<typename TY>
+        struct Container {
+            Container(TY a_) : a(a_) {}
+            TY a;
+        };
+        inline Container<char> make_thingy() {
+            Container<char> a('a');
+            return a;
+        }
+        typedef Container<char> Concrete;
+        inline uint32_t take_thingy(Concrete a) {
+            return a.a;
+        }
     "};
     let rs = quote! {
-        ffi::B::make_unique();
+        assert_eq!(ffi::take_thingy(ffi::make_thingy()), 'a' as u32)
     };
-    run_test("", hdr, rs, &["B"], &[]);
+    run_test("", hdr, rs, &["take_thingy", "make_thingy"], &[]);
 }
 
 #[test]
-fn test_private_constructor_make_unique() {
+fn test_virtual_fns() {
     let hdr = indoc! {"
-    #include <stdint.h>
-    struct A {
-    private:
-        A() {};
-    public:
-        uint32_t a;
-    };
+        #include <cstdint>
+        class A {
+        public:
+            A(uint32_t num) : b(num) {}
+            virtual uint32_t foo(uint32_t a) { return a+1; };
+            virtual ~A() {}
+            uint32_t b;
+        };
+        class B: public A {
+        public:
+            B() : A(3), c(4) {}
+            virtual uint32_t foo(uint32_t a) { return a+2; };
+            uint32_t c;
+        };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A"], &[]);
+    let rs = quote! {
+        let mut a = ffi::A::make_unique(12);
+        assert_eq!(a.pin_mut().foo(2), 3);
+        let mut b = ffi::B::make_unique();
+        assert_eq!(b.pin_mut().foo(2), 4);
+    };
+    run_test("", hdr, rs, &["A", "B"], &[]);
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/266
-fn test_take_array() {
+fn test_const_virtual_fns() {
     let hdr = indoc! {"
-    #include <cstdint>
-    uint32_t take_array(const uint32_t a[4]) {
-        return a[0] + a[2];
-    }
+        #include <cstdint>
+        class A {
+        public:
+            A(uint32_t num) : b(num) {}
+            virtual uint32_t foo(uint32_t a) const { return a+1; };
+            virtual ~A() {}
+            uint32_t b;
+        };
+        class B: public A {
+        public:
+            B() : A(3), c(4) {}
+            virtual uint32_t foo(uint32_t a) const { return a+2; };
+            uint32_t c;
+        };
     "};
     let rs = quote! {
-        let c: [u32; 4usize] = [ 10, 20, 30, 40 ];
-        let c = c as *const [_];
-        assert_eq!(ffi::take_array(&c), 40);
+        let a = ffi::A::make_unique(12);
+        assert_eq!(a.foo(2), 3);
+        let b = ffi::B::make_unique();
+        assert_eq!(b.foo(2), 4);
     };
-    run_test("", hdr, rs, &["take_array"], &[]);
+    run_test("", hdr, rs, &["A", "B"], &[]);
 }
 
 #[test]
-fn test_union_ignored() {
+#[ignore] // https://github.com/google/autocxx/issues/197
+fn test_virtual_fns_inheritance() {
     let hdr = indoc! {"
-    #include <cstdint>
-    union A {
-        uint32_t a;
-        float b;
-    };
-    struct B {
-        B() :a(1) {}
-        uint32_t take_union(A) const {
-            return 3;
+        #include <cstdint>
+        class A {
+        public:
+            A(uint32_t num) : b(num) {}
+            virtual uint32_t foo(uint32_t a) { return a+1; };
+            virtual ~A() {}
+            uint32_t b;
+        };
+        class B: public A {
+        public:
+            B() : A(3), c(4) {}
+            uint32_t c;
+        };
+    "};
+    let rs = quote! {
+        let mut b = ffi::B::make_unique();
+        assert_eq!(b.pin_mut().foo(2), 3);
+    };
+    run_test("", hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_vector_cycle_up() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <vector>
+        #include <memory>
+        struct A {
+            uint32_t a;
+        };
+        inline uint32_t take_vec(std::unique_ptr<std::vector<A>> many_as) {
+            return many_as->size();
+        }
+        inline std::unique_ptr<std::vector<A>> get_vec() {
+            auto items = std::make_unique<std::vector<A>>();
+            items->push_back(A { 3 });
+            items->push_back(A { 4 });
+            return items;
+        }
+    "};
+    let rs = quote! {
+        let v = ffi::get_vec();
+        assert_eq!(v.as_ref().unwrap().is_empty(), false);
+        assert_eq!(ffi::take_vec(v), 2);
+    };
+    run_test("", hdr, rs, &["take_vec", "get_vec"], &[]);
+}
+
+#[test]
+fn test_vector_cycle_bare() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <vector>
+        struct A {
+            uint32_t a;
+        };
+        inline uint32_t take_vec(std::vector<A> many_as) {
+            return many_as.size();
+        }
+        inline std::vector<A> get_vec() {
+            std::vector<A> items;
+            items.push_back(A { 3 });
+            items.push_back(A { 4 });
+            return items;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::take_vec(ffi::get_vec()), 2);
+    };
+    run_test("", hdr, rs, &["take_vec", "get_vec"], &[]);
+}
+
+#[test]
+fn test_typedef_to_std() {
+    let hdr = indoc! {"
+        #include <string>
+        typedef std::string my_string;
+        inline uint32_t take_str(my_string a) {
+            return a.size();
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::take_str("hello".into_cpp()), 5);
+    };
+    run_test("", hdr, rs, &["take_str"], &[]);
+}
+
+#[test]
+fn test_typedef_to_up_in_fn_call() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <memory>
+        typedef std::unique_ptr<std::string> my_string;
+        inline uint32_t take_str(my_string a) {
+            return a->size();
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::take_str("hello".into_cpp()), 5);
+    };
+    run_test("", hdr, rs, &["take_str"], &[]);
+}
+
+#[test]
+fn test_typedef_in_pod_struct() {
+    let hdr = indoc! {"
+        #include <string>
+        typedef uint32_t my_int;
+        struct A {
+            my_int a;
+        };
+        inline uint32_t take_a(A a) {
+            return a.a;
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::A {
+            a: 32,
+        };
+        assert_eq!(ffi::take_a(a), 32);
+    };
+    run_test("", hdr, rs, &["take_a"], &["A"]);
+}
+
+#[test]
+fn test_cint_in_pod_struct() {
+    let hdr = indoc! {"
+        #include <string>
+        struct A {
+            int a;
+        };
+        inline uint32_t take_a(A a) {
+            return a.a;
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::A {
+            a: 32,
+        };
+        assert_eq!(ffi::take_a(a), 32);
+    };
+    run_test("", hdr, rs, &["take_a"], &["A"]);
+}
+
+#[test]
+fn test_string_in_struct() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <memory>
+        struct A {
+            std::string a;
+        };
+        inline A make_a(std::string b) {
+            A bob;
+            bob.a = b;
+            return bob;
+        }
+        inline uint32_t take_a(A a) {
+            return a.a.size();
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+    };
+    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+}
+
+#[test]
+fn test_up_in_struct() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <memory>
+        struct A {
+            std::unique_ptr<std::string> a;
+        };
+        inline A make_a(std::string b) {
+            A bob;
+            bob.a = std::make_unique<std::string>(b);
+            return bob;
+        }
+        inline uint32_t take_a(A a) {
+            return a.a->size();
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+    };
+    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+}
+
+#[test]
+fn test_typedef_to_std_in_struct() {
+    let hdr = indoc! {"
+        #include <string>
+        typedef std::string my_string;
+        struct A {
+            my_string a;
+        };
+        inline A make_a(std::string b) {
+            A bob;
+            bob.a = b;
+            return bob;
+        }
+        inline uint32_t take_a(A a) {
+            return a.a.size();
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+    };
+    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+}
+
+#[test]
+fn test_typedef_to_up_in_struct() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <memory>
+        typedef std::unique_ptr<std::string> my_string;
+        struct A {
+            my_string a;
+        };
+        inline A make_a(std::string b) {
+            A bob;
+            bob.a = std::make_unique<std::string>(b);
+            return bob;
+        }
+        inline uint32_t take_a(A a) {
+            return a.a->size();
+        }
+    "};
+    let rs = quote! {
+        use ffi::ToCppString;
+        assert_eq!(ffi::take_a(ffi::make_a("hello".into_cpp())), 5);
+    };
+    run_test("", hdr, rs, &["make_a", "take_a"], &[]);
+}
+
+#[test]
+fn test_float() {
+    let hdr = indoc! {"
+    inline float daft(float a) { return a; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::daft(34.0f32), 34.0f32);
+    };
+    run_test("", hdr, rs, &["daft"], &[]);
+}
+
+#[test]
+fn test_double() {
+    let hdr = indoc! {"
+    inline double daft(double a) { return a; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::daft(34.0f64), 34.0f64);
+    };
+    run_test("", hdr, rs, &["daft"], &[]);
+}
+
+#[test]
+fn test_issues_217_222() {
+    let hdr = indoc! {"
+    #include <string>
+    #include <cstdint>
+    #include <cstddef>
+
+    template <typename STRING_TYPE> class BasicStringPiece {
+        public:
+         typedef size_t size_type;
+         typedef typename STRING_TYPE::traits_type traits_type;
+         typedef typename STRING_TYPE::value_type value_type;
+         typedef const value_type* pointer;
+         typedef const value_type& reference;
+         typedef const value_type& const_reference;
+         typedef ptrdiff_t difference_type;
+         typedef const value_type* const_iterator;
+         typedef std::reverse_iterator<const_iterator> const_reverse_iterator;
+         static const size_type npos;
+    };
+
+    template<typename CHAR>
+    class Replacements {
+     public:
+      Replacements() {
+      }
+      void SetScheme(const CHAR*) {
+      }
+      uint16_t a;
+    };
+
+    struct Component {
+        uint16_t a;
+    };
+
+    template <typename STR>
+    class StringPieceReplacements : public Replacements<typename STR::value_type> {
+        private:
+         using CharT = typename STR::value_type;
+         using StringPieceT = BasicStringPiece<STR>;
+         using ParentT = Replacements<CharT>;
+         using SetterFun = void (ParentT::*)(const CharT*, const Component&);
+         void SetImpl(SetterFun, StringPieceT) {
+        }
+        public:
+        void SetSchemeStr(const CharT* str) { SetImpl(&ParentT::SetScheme, str); }
+    };
+
+    class GURL {
+        public:
+        typedef StringPieceReplacements<std::string> UrlReplacements;
+        GURL() {}
+        GURL ReplaceComponents(const Replacements<char>&) const {
+            return GURL();
+        }
+        uint16_t a;
+    };
+    "};
+    let rs = quote! {
+        ffi::GURL::make_unique();
+    };
+    // The block! directives here are to avoid running into
+    // https://github.com/rust-lang/rust-bindgen/pull/1975
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("GURL") block!("StringPiece") block!("Replacements") },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_template_specialization_with_different_api() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #include <memory>
+    // The primary template is never defined, so can't be instantiated.
+    template<typename T> class Vec;
+    // The specialization has its own bit-packed representation and API.
+    template<> class Vec<bool> {
+    public:
+        Vec() : bits(0) {}
+        void set_bit(uint32_t i) { bits |= (1u << i); }
+        bool get_bit(uint32_t i) const { return (bits & (1u << i)) != 0; }
+    private:
+        uint32_t bits;
+    };
+    inline std::unique_ptr<Vec<bool>> make_bits() { return std::make_unique<Vec<bool>>(); }
+    inline void set_bit(Vec<bool>& v, uint32_t i) { v.set_bit(i); }
+    inline bool get_bit(const Vec<bool>& v, uint32_t i) { return v.get_bit(i); }
+    "};
+    let rs = quote! {
+        let mut bits = ffi::make_bits();
+        ffi::set_bit(bits.pin_mut(), 3);
+        assert!(ffi::get_bit(&bits, 3));
+        assert!(!ffi::get_bit(&bits, 2));
+    };
+    run_test("", hdr, rs, &["make_bits", "set_bit", "get_bit"], &[]);
+}
+
+#[test]
+#[ignore] // https://github.com/rust-lang/rust-bindgen/pull/1975, https://github.com/google/autocxx/issues/106
+fn test_dependent_qualified_type() {
+    let hdr = indoc! {"
+    #include <stddef.h>
+    struct MyString {
+        typedef char value_type;
+    };
+    template<typename T> struct MyStringView {
+        typedef typename T::value_type view_value_type;
+        const view_value_type* start;
+        size_t length;
+    };
+    const char* HELLO = \"hello\";
+    inline MyStringView<MyString> make_string_view() {
+        MyStringView<MyString> r;
+        r.start = HELLO;
+        r.length = 2;
+        return r;
+    }
+    inline size_t take_string_view(const MyStringView<MyString>& bit) {
+        return bit.length;
+    }
+    "};
+    let rs = quote! {
+        let sv = ffi::make_string_view();
+        assert_eq!(ffi::take_string_view(sv.as_ref().unwrap()), 2);
+    };
+    run_test("", hdr, rs, &["take_string_view", "make_string_view"], &[]);
+}
+
+#[test]
+fn test_simple_dependent_qualified_type() {
+    // bindgen seems to cope with this case just fine
+    let hdr = indoc! {"
+    #include <stddef.h>
+    #include <stdint.h>
+    struct MyString {
+        typedef char value_type;
+    };
+    template<typename T> struct MyStringView {
+        typedef typename T::value_type view_value_type;
+        const view_value_type* start;
+        size_t length;
+    };
+    typedef MyStringView<MyString>::view_value_type MyChar;
+    inline MyChar make_char() {
+        return 'a';
+    }
+    inline uint32_t take_char(MyChar c) {
+        return static_cast<unsigned char>(c);
+    }
+    "};
+    let rs = quote! {
+        let c = ffi::make_char();
+        assert_eq!(ffi::take_char(c), 97);
+    };
+    run_test("", hdr, rs, &["make_char", "take_char"], &[]);
+}
+
+#[test]
+fn test_ignore_dependent_qualified_type() {
+    let hdr = indoc! {"
+    #include <stddef.h>
+    struct MyString {
+        typedef char value_type;
+    };
+    template<typename T> struct MyStringView {
+        typedef typename T::value_type view_value_type;
+        const view_value_type* start;
+        size_t length;
+    };
+    MyStringView<MyString> make_string_view();
+    struct B {
+        B() {}
+        inline size_t take_string_view(const MyStringView<MyString> bit) {
+            return bit.length;
+        }
+    };
+    "};
+    let cpp = indoc! {"
+    const char* HELLO = \"hello\";
+    MyStringView<MyString> make_string_view() {
+        MyStringView<MyString> r;
+        r.start = HELLO;
+        r.length = 2;
+        return r;
+    }
+    "};
+    let rs = quote! {
+        ffi::B::make_unique();
+    };
+    run_test(cpp, hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_ignore_dependent_qualified_type_reference() {
+    let hdr = indoc! {"
+    #include <stddef.h>
+    struct MyString {
+        typedef char value_type;
+    };
+    template<typename T> struct MyStringView {
+        typedef typename T::value_type view_value_type;
+        const view_value_type* start;
+        size_t length;
+    };
+    MyStringView<MyString> make_string_view();
+    struct B {
+        B() {}
+        inline size_t take_string_view(const MyStringView<MyString>& bit) {
+            return bit.length;
+        }
+    };
+    "};
+    let cpp = indoc! {"
+    const char* HELLO = \"hello\";
+    MyStringView<MyString> make_string_view() {
+        MyStringView<MyString> r;
+        r.start = HELLO;
+        r.length = 2;
+        return r;
+    }
+    "};
+    let rs = quote! {
+        ffi::B::make_unique();
+    };
+    run_test(cpp, hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_specialization() {
+    let hdr = indoc! {"
+    #include <stddef.h>
+    #include <stdint.h>
+    #include <string>
+    #include <type_traits>
+
+    template <typename T, bool = std::is_trivially_destructible<T>::value>
+    struct OptionalStorageBase {
+        T value_;
+    };
+
+    template <typename T,
+    bool = std::is_trivially_copy_constructible<T>::value,
+    bool = std::is_trivially_move_constructible<T>::value>
+    struct OptionalStorage : OptionalStorageBase<T> {};
+
+    template <typename T>
+    struct OptionalStorage<T,
+                       true /* trivially copy constructible */,
+                       false /* trivially move constructible */>
+    : OptionalStorageBase<T> {
+    };
+
+    template <typename T>
+    struct OptionalStorage<T,
+                       false /* trivially copy constructible */,
+                       true /* trivially move constructible */>
+    : OptionalStorageBase<T> {
+    };
+
+    template <typename T>
+    struct OptionalStorage<T,
+                       true /* trivially copy constructible */,
+                       true /* trivially move constructible */>
+    : OptionalStorageBase<T> {
+    };
+
+    template <typename T>
+    class OptionalBase {
+    private:
+        OptionalStorage<T> storage_;
+    };
+
+    template <typename T>
+    class Optional : public OptionalBase<T> {
+
+    };
+
+    struct B {
+        B() {}
+        void take_optional(Optional<std::string>) {}
+        uint32_t a;
+    };
+    "};
+    let rs = quote! {
+        ffi::B::make_unique();
+    };
+    run_test("", hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_private_constructor_make_unique() {
+    let hdr = indoc! {"
+    #include <stdint.h>
+    struct A {
+    private:
+        A() {};
+    public:
+        uint32_t a;
+    };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A"], &[]);
+}
+
+#[test]
+#[ignore] // https://github.com/google/autocxx/issues/266
+fn test_take_array() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    uint32_t take_array(const uint32_t a[4]) {
+        return a[0] + a[2];
+    }
+    "};
+    let rs = quote! {
+        let c: [u32; 4usize] = [ 10, 20, 30, 40 ];
+        let c = c as *const [_];
+        assert_eq!(ffi::take_array(&c), 40);
+    };
+    run_test("", hdr, rs, &["take_array"], &[]);
+}
+
+#[test]
+fn test_union_ignored() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    union A {
+        uint32_t a;
+        float b;
+    };
+    struct B {
+        B() :a(1) {}
+        uint32_t take_union(A) const {
+            return 3;
+        }
+        uint32_t get_a() const { return 2; }
+        uint32_t a;
+    };
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        assert_eq!(b.get_a(), 2);
+    };
+    run_test("", hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_double_underscores_ignored() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    struct __FOO {
+        uint32_t a;
+    };
+    struct B {
+        B() :a(1) {}
+        uint32_t take_foo(__FOO a) const {
+            return 3;
+        }
+        void do__something() const { }
+        uint32_t get_a() const { return 2; }
+        uint32_t a;
+    };
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        assert_eq!(b.get_a(), 2);
+    };
+    run_test("", hdr, rs, &["B"], &[]);
+}
+
+// This test fails on Windows gnu but not on Windows msvc
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
+#[test]
+fn test_double_underscore_typedef_ignored() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    typedef int __int32_t;
+    typedef __int32_t __darwin_pid_t;
+    typedef __darwin_pid_t pid_t;
+    struct B {
+        B() :a(1) {}
+        uint32_t take_foo(pid_t) const {
+            return 3;
+        }
+        uint32_t get_a() const { return 2; }
+        uint32_t a;
+    };
+    "};
+    let rs = quote! {
+        let b = ffi::B::make_unique();
+        assert_eq!(b.get_a(), 2);
+    };
+    run_test("", hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_double_underscores_fn_namespace() {
+    let hdr = indoc! {"
+    namespace __B {
+        inline void a() {}
+    };
+    "};
+    run_test_ex(
+        "",
+        hdr,
+        quote! {},
+        quote! { generate_all!() },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_typedef_to_ptr_is_marked_unsafe() {
+    let hdr = indoc! {"
+    struct _xlocalefoo; /* forward reference */
+    typedef struct _xlocalefoo * locale_tfoo;
+    extern \"C\" {
+        locale_tfoo duplocalefoo(locale_tfoo);
+    }
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["duplocalefoo"], &[]);
+}
+
+#[test]
+fn test_issue_264() {
+    let hdr = indoc! {"
+    namespace a {
+        typedef int b;
+        inline namespace c {}
+        template <typename> class aa;
+        namespace c {
+        template <typename d, typename = d, typename = aa<d>> class e;
+        }
+        typedef e<char> f;
+        template <typename g, typename, template <typename> typename> struct h {
+          using i = g;
+        };
+        template <typename g, template <typename> class k> using j = h<g, void, k>;
+        template <typename g, template <typename> class k>
+        using m = typename j<g, k>::i;
+        template <typename> struct l { typedef b ab; };
+        template <typename p> class aa {
+        public:
+          typedef p n;
+        };
+        struct r {
+          template <typename p> using o = typename p::c;
+        };
+        template <typename ad> struct u : r {
+          typedef typename ad::n n;
+          using ae = m<n, o>;
+          template <typename af, typename> struct v { using i = typename l<f>::ab; };
+          using ab = typename v<ad, ae>::i;
+        };
+        } // namespace a
+        namespace q {
+        template <typename ad> struct w : a::u<ad> {};
+        } // namespace q
+        namespace a {
+        namespace c {
+        template <typename, typename, typename ad> class e {
+          typedef q::w<ad> s;
+        public:
+          typedef typename s::ab ab;
+        };
+        } // namespace c
+        } // namespace a
+        namespace ag {
+        namespace ah {
+        typedef a::f::ab t;
+        class ai {
+          t aj;
+        };
+        class al;
+        namespace am {
+        class an {
+        public:
+          void ao(ai);
+        };
+        } // namespace am
+        class ap {
+        public:
+          al aq();
+        };
+        class ar {
+          am::an as;
+        };
+        class al {
+          ar at;
+        };
+        struct au {
+          ap av;
+        };
+        } // namespace ah
+        } // namespace ag
+        namespace operations_research {
+        class aw {
+          ag::ah::au ax;
+        };
+        class Solver {
+          aw ay;
+        };
+        } // namespace operations_research
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+}
+
+#[test]
+fn test_unexpected_use() {
+    // https://github.com/google/autocxx/issues/303
+    let hdr = indoc! {"
+        typedef int a;
+        namespace b {
+        namespace c {
+        enum d : a;
+        }
+        } // namespace b
+        namespace {
+        using d = b::c::d;
+        }
+        namespace content {
+        class RenderFrameHost {
+        public:
+            RenderFrameHost() {}
+        d e;
+        };
+        } // namespace content
+        "};
+    let rs = quote! {
+        let _ = ffi::content::RenderFrameHost::make_unique();
+    };
+    run_test("", hdr, rs, &["content::RenderFrameHost"], &[]);
+}
+
+#[test]
+fn test_get_pure_virtual() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            virtual uint32_t get_val() const = 0;
+        };
+        class B : public A {
+        public:
+            virtual uint32_t get_val() const { return 3; }
+        };
+        const B b;
+        inline const A* get_a() { return &b; };
+    "};
+    let rs = quote! {
+        let a = ffi::get_a();
+        let a_ref = unsafe { a.as_ref() }.unwrap();
+        assert_eq!(a_ref.get_val(), 3);
+    };
+    run_test("", hdr, rs, &["A", "get_a"], &[]);
+}
+
+#[test]
+fn test_abstract_class_no_make_unique() {
+    // We shouldn't generate a make_unique() for abstract classes.
+    // The test is successful if the bindings compile, i.e. if autocxx doesn't
+    // attempt to instantiate the class.
+    let hdr = indoc! {"
+        class A {
+        public:
+            A();
+            virtual void foo() const = 0;
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A"], &[]);
+}
+
+#[test]
+fn test_derived_abstract_class_no_make_unique() {
+    let hdr = indoc! {"
+        class A {
+        public:
+            A();
+            virtual void foo() const = 0;
+        };
+
+        class B : public A {
+        public:
+            B();
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A", "B"], &[]);
+}
+
+#[test]
+fn test_recursive_derived_abstract_class_no_make_unique() {
+    let hdr = indoc! {"
+        class A {
+        public:
+            A() {};
+            virtual void foo() const = 0;
+        };
+
+        class B : public A {
+        public:
+            B() {};
+        };
+
+        class C : public B {
+        public:
+            C() {};
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A", "B", "C"], &[]);
+}
+
+#[test]
+fn test_derived_abstract_class_with_no_allowlisting_no_make_unique() {
+    let hdr = indoc! {"
+        class A {
+        public:
+            A();
+            virtual void foo() const = 0;
+        };
+
+        class B : public A {
+        public:
+            B();
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["B"], &[]);
+}
+
+#[test]
+fn test_vector_of_pointers() {
+    // Just ensures the troublesome API is ignored
+    let hdr = indoc! {"
+        #include <vector>
+        namespace operations_research {
+        class a;
+        class Solver {
+        public:
+          struct b c(std::vector<a *>);
+        };
+        class a {};
+        } // namespace operations_research
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+}
+
+#[test]
+fn test_pointer_to_pointer() {
+    // Just ensures the troublesome API is ignored
+    let hdr = indoc! {"
+        namespace operations_research {
+        class a;
+        class Solver {
+        public:
+          struct b c(a **);
+        };
+        class a {};
+        } // namespace operations_research
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+}
+
+#[test]
+fn test_defines_effective() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #ifdef FOO
+        inline uint32_t a() { return 4; }
+        #endif
+    "};
+    let rs = quote! {
+        ffi::a();
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("a") },
+        make_clang_arg_adder(&["-DFOO"]),
+        None,
+        None,
+    );
+}
+
+#[test]
+#[ignore] // https://github.com/google/autocxx/issues/227
+fn test_function_pointer_template() {
+    let hdr = indoc! {"
+        typedef int a;
+        namespace std {
+        template <typename> class b;
+        }
+        typedef a c;
+        namespace operations_research {
+        class d;
+        class Solver {
+        public:
+            typedef std::b<c()> IndexEvaluator3;
+            d e(IndexEvaluator3);
+        };
+        class d {};
+        } // namespace operations_research
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+}
+
+#[test]
+fn test_cvoid() {
+    let hdr = indoc! {"
+        #include <memory>
+        #include <cstdint>
+        inline void* a() {
+            return static_cast<void*>(new int(3));
+        }
+        inline uint32_t b(void* p) {
+            int* p_int = static_cast<int*>(p);
+            auto val = *p_int;
+            delete p_int;
+            return val;
+        }
+    "};
+    let rs = quote! {
+        let ptr = ffi::a();
+        let res = unsafe { ffi::b(ptr) };
+        assert_eq!(res, 3);
+    };
+    run_test("", hdr, rs, &["a", "b"], &[]);
+}
+
+#[test]
+fn test_c_schar() {
+    let hdr = indoc! {"
+        inline signed char a() {
+            return 8;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::a(), 8);
+    };
+    run_test("", hdr, rs, &["a"], &[]);
+}
+
+#[test]
+fn test_c_uchar() {
+    let hdr = indoc! {"
+        inline unsigned char a() {
+            return 8;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::a(), 8);
+    };
+    run_test("", hdr, rs, &["a"], &[]);
+}
+
+#[test]
+fn test_c_ulonglong() {
+    // We don't test all the different variable-length integer types which we populate.
+    // If one works, they probably all do. Hopefully.
+    let hdr = indoc! {"
+        inline unsigned long long a() {
+            return 8;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::a(), autocxx::c_ulonglong(8));
+    };
+    run_test("", hdr, rs, &["a"], &[]);
+}
+
+#[test]
+fn test_string_transparent_function() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <cstdint>
+        inline uint32_t take_string(std::string a) { return a.size(); }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::take_string("hello"), 5);
+    };
+    run_test("", hdr, rs, &["take_string"], &[]);
+}
+
+#[test]
+fn test_string_transparent_method() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <cstdint>
+        struct A {
+            A() {}
+            inline uint32_t take_string(std::string a) const { return a.size(); }
+        };
+    "};
+    let rs = quote! {
+        let a = ffi::A::make_unique();
+        assert_eq!(a.take_string("hello"), 5);
+    };
+    run_test("", hdr, rs, &["A"], &[]);
+}
+
+#[test]
+fn test_string_transparent_static_method() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <cstdint>
+        struct A {
+            A() {}
+            static inline uint32_t take_string(std::string a) { return a.size(); }
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::A::take_string("hello"), 5);
+    };
+    run_test("", hdr, rs, &["A"], &[]);
+}
+
+#[test]
+#[ignore] // https://github.com/google/autocxx/issues/490
+fn test_issue_490() {
+    let hdr = indoc! {"
+        typedef int a;
+        typedef long unsigned size_t;
+        namespace std {
+        namespace {
+        using ::size_t;
+        template <class b, b c> struct g { static const b value = c; };
+        template <bool d> using e = g<bool, d>;
+        typedef e<true> true_type;
+        template <size_t, size_t> struct ag {};
+        template <class b> typename b ::h move();
+        template <class> class allocator;
+        template <class> class vector;
+        } // namespace
+        } // namespace std
+        void *operator new(size_t, void *);
+        namespace std {
+        namespace {
+        template <class> struct iterator;
+        template <class b, class> struct ay { using h = b *; };
+        template <class b> struct bj { b bk; };
+        template <class bm, class> class bn : bj<bm> {};
+        template <class b, class i = b> class unique_ptr {
+        typedef i bp;
+        typedef typename ay<b, bp>::h bh;
+        bn<bh, bp> bq;
+
+        public:
+        unique_ptr();
+        unique_ptr(bh);
+        bh get() const;
+        bh release();
+        };
+        template <class = void> struct bt;
+        } // namespace
+        } // namespace std
+        typedef a bv;
+        namespace absl {
+        template <typename ce> class cj {
+        public:
+        using bh = ce *;
+        using iterator = bh;
+        };
+        namespace j {
+        template <class ce> struct cp {
+        using k = ce;
+        using cq = std::bt<>;
+        };
+        template <class ce> using cr = typename cp<ce>::k;
+        template <class ce> using cs = typename cp<ce>::cq;
+        template <class, class, class, class> class ct {
+        public:
+        class iterator {};
+        class cu {
+            cu(iterator);
+            iterator cv;
+        };
+        };
+        template <typename> struct cw;
+        } // namespace j
+        template <class ce, class k = j::cr<ce>, class cq = j::cs<ce>,
+                class cx = std::allocator<ce>>
+        class cy : public j::ct<j::cw<ce>, k, cq, cx> {};
+        } // namespace absl
+        namespace cz {
+        template <typename da> class db { std::ag<sizeof(a), alignof(da)> c; };
+        } // namespace cz
+        namespace spanner {
+        class l;
+        class ColumnList {
+        public:
+        typedef absl::cj<l>::iterator iterator;
+        iterator begin();
+        };
+        class dd {
+        union {
+            cz::db<absl::cy<bv>::cu> e;
+        };
+        };
+        class Row {
+        public:
+        bool f(dd);
+        };
+        } // namespace spanner
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["spanner::Row", "spanner::ColumnList"], &[]);
+}
+
+#[test]
+fn test_immovable_object() {
+    let hdr = indoc! {"
+        class A {
+        public:
+            A();
+            A(A&&) = delete;
+        };
+
+        class B{
+        public:
+            B();
+            B(const B&) = delete;
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A", "B"], &[]);
+}
+
+#[test]
+fn test_immovable_object_by_reference() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Immovable {
+        public:
+            Immovable(uint32_t v) : value(v) {}
+            Immovable(const Immovable&) = delete;
+            Immovable(Immovable&&) = delete;
+            uint32_t get() const { return value; }
+            void set(uint32_t v) { value = v; }
+        private:
+            uint32_t value;
+        };
+        struct Holder {
+            Holder() : inner(1) {}
+            Immovable inner;
+        };
+        inline uint32_t read(const Immovable& i) { return i.get(); }
+        inline void bump(Immovable& i) { i.set(i.get() + 1); }
+        inline uint32_t take(Immovable i) { return i.get(); }
+        inline uint32_t take_holder(Holder h) { return h.inner.get(); }
+    "};
+    let rs = quote! {
+        let mut i = ffi::Immovable::make_unique(3);
+        assert_eq!(ffi::read(&i), 3);
+        ffi::bump(i.pin_mut());
+        assert_eq!(i.get(), 4);
+        i.pin_mut().set(5);
+        assert_eq!(ffi::read(&i), 5);
+        let _ = ffi::Holder::make_unique();
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["Immovable", "Holder", "read", "bump", "take", "take_holder"],
+        &[],
+    );
+}
+
+#[test]
+fn test_negative_immovable_object_by_value() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Immovable {
+        public:
+            Immovable(uint32_t v) : value(v) {}
+            Immovable(const Immovable&) = delete;
+            Immovable(Immovable&&) = delete;
+            uint32_t get() const { return value; }
+        private:
+            uint32_t value;
+        };
+        inline uint32_t take(Immovable i) { return i.get(); }
+    "};
+    let rs = quote! {
+        let _ = ffi::Immovable::make_unique(3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Immovable", "take"], &[], None),
+        None,
+        Some(make_string_finder(vec![
+            "This function takes or returns Immovable by value, but that type can be neither moved nor copied",
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_struct_with_reference() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <utility>
+        struct A {
+            uint32_t a;
+        };
+        struct B {
+            B(const A& param) : a(param) {}
+            const A& a;
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A", "B"], &[]);
+}
+
+#[test]
+fn test_struct_with_rvalue() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <utility>
+        struct A {
+            uint32_t a;
+        };
+        struct B {
+            B(A&& param) : a(std::move(param)) {}
+            A&& a;
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A", "B"], &[]);
+}
+
+#[test]
+fn test_immovable_nested_object() {
+    let hdr = indoc! {"
+        struct C {
+            class A {
+            public:
+                A();
+                A(A&&) = delete;
+            };
+
+            class B{
+            public:
+                B();
+                B(const B&) = delete;
+            };
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["C_A", "C_B"], &[]);
+}
+
+#[test]
+fn test_type_called_type() {
+    let hdr = indoc! {"
+        namespace a {
+            template<int _Len>
+            struct b
+            {
+                union type
+                {
+                    unsigned char __data[_Len];
+                    struct foo {
+                        int a;
+                    };
+                };
+            };
+        }
+        inline void take_type(a::b<4>::type) {}
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["take_type"], &[]);
+}
+
+#[test]
+fn test_bridge_conflict_ty() {
+    let hdr = indoc! {"
+        namespace a {
+            struct Key { int a; };
+        }
+        namespace b {
+            struct Key { int a; };
+        }
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["a::Key", "b::Key"], &[]);
+}
+
+#[test]
+fn test_bridge_conflict_ty_fn() {
+    let hdr = indoc! {"
+        namespace a {
+            struct Key { int a; };
+        }
+        namespace b {
+            inline void Key() {}
+        }
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["a::Key", "b::Key"], &[]);
+}
+
+#[test]
+fn test_issue_506() {
+    let hdr = indoc! {"
+        namespace std {
+            template <class, class> class am;
+            typedef am<char, char> an;
+        } // namespace std
+        namespace be {
+            class bf {
+            virtual std::an bg() = 0;
+            };
+            class bh : bf {};
+        } // namespace be
+        namespace spanner {
+            class Database;
+            class Row {
+            public:
+            Row(be::bh *);
+            };
+        } // namespace spanner
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["spanner::Database", "spanner::Row"], &[]);
+}
+
+#[test]
+fn test_private_inheritance() {
+    let hdr = indoc! {"
+        class A {
+        public:
+            void foo() {}
+            int a;
+        };
+        class B : A {
+        public:
+            void bar() {}
+            int b;
+        };
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["A", "B"], &[]);
+}
+
+#[test]
+fn test_slice_to_base() {
+    let hdr = indoc! {"
+        class Base {
+        public:
+            Base() : a(3) {}
+            virtual ~Base() {}
+            virtual int get() const { return a; }
+            int a;
+        };
+        class Derived : public Base {
+        public:
+            Derived() : b(4) {}
+            int get() const override { return b; }
+            int b;
+        };
+        inline int take_base(Base b) { return b.get(); }
+        inline int take_base_ref(const Base& b) { return b.get(); }
+    "};
+    let rs = quote! {
+        let d = ffi::Derived::make_unique();
+        assert_eq!(ffi::take_base_ref(d.as_ref().unwrap().as_ref()), 4);
+        assert_eq!(ffi::take_base(d.as_ref().unwrap().slice_to_base()), 3);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["Base", "Derived", "take_base", "take_base_ref"],
+        &[],
+    );
+}
+
+#[test]
+fn test_derived_by_value_needs_slice() {
+    let hdr = indoc! {"
+        class Base {
+        public:
+            Base() : a(3) {}
+            int a;
+        };
+        class Derived : public Base {
+        public:
+            Derived() : b(4) {}
+            int b;
+        };
+        inline int take_base(Base b) { return b.a; }
+    "};
+    let rs = quote! {
+        let d = ffi::Derived::make_unique();
+        ffi::take_base(d);
+    };
+    run_test_expect_fail("", hdr, rs, &["Base", "Derived", "take_base"], &[]);
+}
+
+#[test]
+fn test_downcast() {
+    let hdr = indoc! {"
+        class Shape {
+        public:
+            virtual ~Shape() {}
+            virtual int sides() const = 0;
+        };
+        class Square : public Shape {
+        public:
+            Square() : size(2) {}
+            int sides() const override { return 4; }
+            int get_size() const { return size; }
+            void grow() { size++; }
+        private:
+            int size;
+        };
+        class Triangle : public Shape {
+        public:
+            int sides() const override { return 3; }
+        };
+        inline const Shape& as_shape(const Square& s) { return s; }
+        inline Shape& as_shape_mut(Square& s) { return s; }
+    "};
+    let rs = quote! {
+        let mut s = ffi::Square::make_unique();
+        let shape = ffi::as_shape(s.as_ref().unwrap());
+        assert!(shape.downcast::<ffi::Triangle>().is_none());
+        assert_eq!(shape.downcast::<ffi::Square>().unwrap().get_size(), 2);
+        let mut shape = ffi::as_shape_mut(s.pin_mut());
+        assert!(shape.as_mut().downcast_mut::<ffi::Triangle>().is_none());
+        shape.downcast_mut::<ffi::Square>().unwrap().grow();
+        assert_eq!(s.get_size(), 3);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["Shape", "Square", "Triangle", "as_shape", "as_shape_mut"],
+        &[],
+    );
+}
+
+#[test]
+fn test_ptr_eq() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Widget {
+        public:
+            Widget() : id(0) {}
+            uint32_t id;
+        };
+        class Named {
+        public:
+            uint32_t name_id = 2;
+        };
+        class Both : public Named, public Widget {};
+        inline Widget& the_widget() {
+            static Widget w;
+            return w;
         }
-        uint32_t get_a() const { return 2; }
-        uint32_t a;
-    };
+        inline const Widget& widget_of(const Both& b) { return b; }
     "};
     let rs = quote! {
-        let b = ffi::B::make_unique();
-        assert_eq!(b.get_a(), 2);
+        let a = ffi::Widget::make_unique();
+        let b = ffi::Widget::make_unique();
+        assert!(a.as_ref().unwrap().ptr_eq(a.as_ref().unwrap()));
+        assert!(!a.as_ref().unwrap().ptr_eq(b.as_ref().unwrap()));
+        assert!(a.ptr_eq(&a));
+        assert!(!a.ptr_eq(&b));
+        let pinned = ffi::the_widget();
+        let shared: *const ffi::Widget = &*pinned;
+        assert!(unsafe { &*shared }.ptr_eq(&*pinned));
+        let both = ffi::Both::make_unique();
+        let widget: &ffi::Widget = both.as_ref().unwrap().as_ref();
+        assert!(widget.ptr_eq(ffi::widget_of(both.as_ref().unwrap())));
     };
-    run_test("", hdr, rs, &["B"], &[]);
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["Widget", "Named", "Both", "the_widget", "widget_of"],
+        &[],
+    );
 }
 
 #[test]
-fn test_double_underscores_ignored() {
+fn test_error_generated_for_static_data() {
     let hdr = indoc! {"
-    #include <cstdint>
-    struct __FOO {
-        uint32_t a;
-    };
-    struct B {
-        B() :a(1) {}
-        uint32_t take_foo(__FOO a) const {
-            return 3;
-        }
-        void do__something() const { }
-        uint32_t get_a() const { return 2; }
-        uint32_t a;
-    };
+        #include <cstdint>
+        struct A {
+            A() {}
+            uint32_t a;
+        };
+        static A FOO = A();
     "};
-    let rs = quote! {
-        let b = ffi::B::make_unique();
-        assert_eq!(b.get_a(), 2);
-    };
-    run_test("", hdr, rs, &["B"], &[]);
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("FOO")},
+        None,
+        Some(make_error_finder("FOO")),
+        None,
+    );
 }
 
-// This test fails on Windows gnu but not on Windows msvc
-#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
 #[test]
-fn test_double_underscore_typedef_ignored() {
+fn test_error_generated_for_array_dependent_function() {
     let hdr = indoc! {"
-    #include <cstdint>
-    typedef int __int32_t;
-    typedef __int32_t __darwin_pid_t;
-    typedef __darwin_pid_t pid_t;
-    struct B {
-        B() :a(1) {}
-        uint32_t take_foo(pid_t) const {
-            return 3;
+        #include <cstdint>
+        #include <functional>
+        inline void take_func(std::function<bool(const uint32_t number)>) {
         }
-        uint32_t get_a() const { return 2; }
-        uint32_t a;
-    };
     "};
-    let rs = quote! {
-        let b = ffi::B::make_unique();
-        assert_eq!(b.get_a(), 2);
-    };
-    run_test("", hdr, rs, &["B"], &[]);
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate! ("take_func")},
+        None,
+        Some(make_error_finder("take_func")),
+        None,
+    );
 }
 
 #[test]
-fn test_double_underscores_fn_namespace() {
+fn test_error_generated_for_array_dependent_method() {
     let hdr = indoc! {"
-    namespace __B {
-        inline void a() {}
-    };
+        #include <cstdint>
+        #include <functional>
+        struct A {
+            void take_func(std::function<bool(const uint32_t number)>) {
+            }
+        };
     "};
+    let rs = quote! {};
     run_test_ex(
         "",
         hdr,
-        quote! {},
-        quote! { generate_all!() },
-        None,
+        rs,
+        quote! { generate! ("A")},
         None,
+        Some(make_string_finder(
+            ["take_func", "couldn't be generated"].to_vec(),
+        )),
         None,
     );
 }
 
 #[test]
-fn test_typedef_to_ptr_is_marked_unsafe() {
+#[ignore] // https://github.com/google/autocxx/issues/835
+fn test_error_generated_for_pod_with_nontrivial_destructor() {
     let hdr = indoc! {"
-    struct _xlocalefoo; /* forward reference */
-    typedef struct _xlocalefoo * locale_tfoo;
-    extern \"C\" {
-        locale_tfoo duplocalefoo(locale_tfoo);
-    }
+        #include <cstdint>
+        #include <functional>
+        struct A {
+            ~A() {}
+        };
     "};
     let rs = quote! {};
-    run_test("", hdr, rs, &["duplocalefoo"], &[]);
+    run_test_expect_fail("", hdr, rs, &[], &["A"]);
 }
 
 #[test]
-fn test_issue_264() {
+#[ignore] // https://github.com/google/autocxx/issues/835
+fn test_error_generated_for_pod_with_nontrivial_move_constructor() {
     let hdr = indoc! {"
-    namespace a {
-        typedef int b;
-        inline namespace c {}
-        template <typename> class aa;
-        namespace c {
-        template <typename d, typename = d, typename = aa<d>> class e;
-        }
-        typedef e<char> f;
-        template <typename g, typename, template <typename> typename> struct h {
-          using i = g;
-        };
-        template <typename g, template <typename> class k> using j = h<g, void, k>;
-        template <typename g, template <typename> class k>
-        using m = typename j<g, k>::i;
-        template <typename> struct l { typedef b ab; };
-        template <typename p> class aa {
-        public:
-          typedef p n;
-        };
-        struct r {
-          template <typename p> using o = typename p::c;
-        };
-        template <typename ad> struct u : r {
-          typedef typename ad::n n;
-          using ae = m<n, o>;
-          template <typename af, typename> struct v { using i = typename l<f>::ab; };
-          using ab = typename v<ad, ae>::i;
-        };
-        } // namespace a
-        namespace q {
-        template <typename ad> struct w : a::u<ad> {};
-        } // namespace q
-        namespace a {
-        namespace c {
-        template <typename, typename, typename ad> class e {
-          typedef q::w<ad> s;
-        public:
-          typedef typename s::ab ab;
-        };
-        } // namespace c
-        } // namespace a
-        namespace ag {
-        namespace ah {
-        typedef a::f::ab t;
-        class ai {
-          t aj;
-        };
-        class al;
-        namespace am {
-        class an {
-        public:
-          void ao(ai);
-        };
-        } // namespace am
-        class ap {
-        public:
-          al aq();
-        };
-        class ar {
-          am::an as;
-        };
-        class al {
-          ar at;
-        };
-        struct au {
-          ap av;
-        };
-        } // namespace ah
-        } // namespace ag
-        namespace operations_research {
-        class aw {
-          ag::ah::au ax;
+        #include <cstdint>
+        #include <functional>
+        struct A {
+            A() = default;
+            A(A&&) {}
         };
-        class Solver {
-          aw ay;
+    "};
+    let rs = quote! {};
+    run_test_expect_fail("", hdr, rs, &[], &["A"]);
+}
+
+#[test]
+fn test_keyword_function() {
+    let hdr = indoc! {"
+        inline void move(int a) {};
+    "};
+    let rs = quote! {};
+    run_test("", hdr, rs, &["move_"], &[]);
+}
+
+#[test]
+fn test_keyword_method() {
+    let hdr = indoc! {"
+        struct A {
+            int a;
+            inline void move() {};
         };
-        } // namespace operations_research
     "};
     let rs = quote! {};
-    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+    run_test("", hdr, rs, &["A"], &[]);
 }
 
 #[test]
-fn test_unexpected_use() {
-    // https://github.com/google/autocxx/issues/303
+fn test_doc_passthru() {
     let hdr = indoc! {"
-        typedef int a;
-        namespace b {
-        namespace c {
-        enum d : a;
-        }
-        } // namespace b
-        namespace {
-        using d = b::c::d;
-        }
-        namespace content {
-        class RenderFrameHost {
-        public:
-            RenderFrameHost() {}
-        d e;
+        #include <cstdint>
+        /// Elephants!
+        struct A {
+            uint32_t a;
         };
-        } // namespace content
-        "};
-    let rs = quote! {
-        let _ = ffi::content::RenderFrameHost::make_unique();
-    };
-    run_test("", hdr, rs, &["content::RenderFrameHost"], &[]);
+        /// Giraffes!
+        struct B {
+            uint32_t a;
+        };
+        /// Rhinos!
+        inline uint32_t get_a() { return 3; }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["A", "get_a"], &["B"], None),
+        None,
+        Some(make_string_finder(
+            ["Giraffes", "Elephants", "Rhinos"].to_vec(),
+        )),
+        None,
+    );
 }
 
 #[test]
-fn test_get_pure_virtual() {
+fn test_translate_doxygen() {
     let hdr = indoc! {"
         #include <cstdint>
-        class A {
-        public:
-            virtual uint32_t get_val() const = 0;
-        };
-        class B : public A {
-        public:
-            virtual uint32_t get_val() const { return 3; }
-        };
-        const B b;
-        inline const A* get_a() { return &b; };
+        /// @brief Computes an area.
+        ///
+        /// @param[in] width The width,
+        ///     in pixels.
+        /// @param height The height.
+        /// @return The area.
+        inline uint32_t area(uint32_t width, uint32_t height) { return width * height; }
+        /// @return Half the cube.
+        inline uint32_t area(uint32_t side) { return side * side * side / 2; }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["area"], &[], Some(quote! { translate_doxygen!() })),
+        None,
+        Some(make_string_finder(
+            [
+                "Computes an area.",
+                "# Parameters",
+                "* `width`: The width, in pixels.",
+                "* `height`: The height.",
+                "Returns the area.",
+                "Returns half the cube.",
+            ]
+            .to_vec(),
+        )),
+        None,
+    );
+}
+
+#[test]
+fn test_closure() {
+    // Ensuring presence of this closure doesn't break other things
+    let hdr = indoc! {"
+    #include <functional>
+    #include <cstdint>
+
+    inline bool take_closure(std::function<bool(const uint32_t number)> fn) {
+        return fn(5);
+    }
+    inline uint32_t get_a() {
+        return 3;
+    }
     "};
     let rs = quote! {
-        let a = ffi::get_a();
-        let a_ref = unsafe { a.as_ref() }.unwrap();
-        assert_eq!(a_ref.get_val(), 3);
+        assert_eq!(ffi::get_a(), 3);
     };
-    run_test("", hdr, rs, &["A", "get_a"], &[]);
+    run_test("", hdr, rs, &["get_a"], &[]);
 }
 
 #[test]
-fn test_abstract_class_no_make_unique() {
-    // We shouldn't generate a make_unique() for abstract classes.
-    // The test is successful if the bindings compile, i.e. if autocxx doesn't
-    // attempt to instantiate the class.
+fn test_underscored_namespace_for_inner_type() {
     let hdr = indoc! {"
-        class A {
-        public:
-            A();
-            virtual void foo() const = 0;
-        };
+        namespace __foo {
+            struct daft {
+                struct bob {
+                    int a;
+                };
+                int a;
+            };
+        }
+        inline void bar(__foo::daft::bob) {}
     "};
     let rs = quote! {};
-    run_test("", hdr, rs, &["A"], &[]);
+    run_test("", hdr, rs, &["bar"], &[]);
 }
 
 #[test]
-fn test_derived_abstract_class_no_make_unique() {
+fn test_blocklist_not_overly_broad() {
+    // This is a regression test. We used to block anything that starts with "rust" or "std",
+    // not just items in the "rust" and "std" namespaces. We therefore test that functions starting
+    // with "rust" or "std" get imported.
     let hdr = indoc! {"
-        class A {
-        public:
-            A();
-            virtual void foo() const = 0;
-        };
-
-        class B : public A {
-        public:
-            B();
-        };
+    inline void rust_func() { }
+    inline void std_func() { }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    let rs = quote! {
+        ffi::rust_func();
+        ffi::std_func();
+    };
+    run_test("", hdr, rs, &["rust_func", "std_func"], &[]);
 }
 
 #[test]
-fn test_recursive_derived_abstract_class_no_make_unique() {
+#[ignore] // https://github.com/google/autocxx/issues/837
+fn test_ref_qualified_method() {
     let hdr = indoc! {"
-        class A {
-        public:
-            A() {};
-            virtual void foo() const = 0;
-        };
-
-        class B : public A {
-        public:
-            B() {};
-        };
-
-        class C : public B {
-        public:
-            C() {};
+        struct A {
+            void foo() & {}
         };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A", "B", "C"], &[]);
+    let rs = quote! {
+        A::make_unique().foo();
+    };
+    run_test("", hdr, rs, &["A"], &[]);
 }
 
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
 #[test]
-fn test_derived_abstract_class_with_no_allowlisting_no_make_unique() {
+fn test_stringview() {
+    // Test that APIs using std::string_view do not otherwise cause errors.
     let hdr = indoc! {"
-        class A {
-        public:
-            A();
-            virtual void foo() const = 0;
-        };
-
-        class B : public A {
-        public:
-            B();
-        };
+        #include <string_view>
+        #include <string>
+        void take_string_view(std::string_view) {}
+        std::string_view return_string_view(std::string a) { return std::string_view(a); }
     "};
     let rs = quote! {};
-    run_test("", hdr, rs, &["B"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["take_string_view", "return_string_view"], &[], None),
+        make_clang_arg_adder(&["-std=c++17"]),
+        None,
+        None,
+    );
 }
 
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
 #[test]
-fn test_vector_of_pointers() {
-    // Just ensures the troublesome API is ignored
+fn test_pass_str_to_string_view() {
     let hdr = indoc! {"
-        #include <vector>
-        namespace operations_research {
-        class a;
-        class Solver {
-        public:
-          struct b c(std::vector<a *>);
-        };
-        class a {};
-        } // namespace operations_research
+        #include <string_view>
+        #include <cstdint>
+        inline uint32_t measure_string_view(std::string_view s) { return s.size(); }
+        inline bool string_view_is_bob(const std::string_view& s) { return s == \"bob\"; }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+    let rs = quote! {
+        assert_eq!(ffi::measure_string_view("hello"), 5);
+        assert!(ffi::string_view_is_bob("bob"));
+        assert!(!ffi::string_view_is_bob("fred"));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["measure_string_view", "string_view_is_bob"], &[], None),
+        make_clang_arg_adder(&["-std=c++17"]),
+        None,
+        None,
+    );
 }
 
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
 #[test]
-fn test_pointer_to_pointer() {
-    // Just ensures the troublesome API is ignored
+fn test_return_string_view_as_slice() {
     let hdr = indoc! {"
-        namespace operations_research {
-        class a;
-        class Solver {
+        #include <string_view>
+        #include <string>
+        class Name {
         public:
-          struct b c(a **);
+            Name() : name(\"bob\") {}
+            std::string_view get_name() const { return name; }
+        private:
+            std::string name;
         };
-        class a {};
-        } // namespace operations_research
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+    let rs = quote! {
+        let n = ffi::Name::make_unique();
+        assert_eq!(n.get_name(), b"bob");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Name"], &[], None),
+        make_clang_arg_adder(&["-std=c++17"]),
+        None,
+        None,
+    );
 }
 
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
 #[test]
-fn test_defines_effective() {
+fn test_filesystem_path() {
     let hdr = indoc! {"
+        #include <filesystem>
         #include <cstdint>
-        #ifdef FOO
-        inline uint32_t a() { return 4; }
-        #endif
+        inline std::filesystem::path join_paths(const std::filesystem::path& a, std::filesystem::path b) {
+            return a / b;
+        }
+        inline uint32_t native_length(const std::filesystem::path& p) { return p.native().size(); }
     "};
     let rs = quote! {
-        ffi::a();
+        use std::path::{Path, PathBuf};
+        assert_eq!(
+            ffi::join_paths(Path::new("foo"), Path::new("bar")),
+            PathBuf::from("foo").join("bar")
+        );
+        assert_eq!(ffi::native_length(Path::new("baz")), 3);
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+            let not_utf8 = Path::new(OsStr::from_bytes(b"caf\xe9"));
+            assert_eq!(ffi::native_length(not_utf8), 4);
+            assert_eq!(ffi::join_paths(not_utf8, Path::new("")), PathBuf::from(not_utf8).join(""));
+        }
     };
     run_test_ex(
         "",
         hdr,
         rs,
-        quote! { generate!("a") },
-        make_clang_arg_adder(&["-DFOO"]),
+        directives_from_lists(&["join_paths", "native_length"], &[], None),
+        make_clang_arg_adder(&["-std=c++17"]),
         None,
         None,
     );
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/227
-fn test_function_pointer_template() {
+fn test_bitset_typedef() {
     let hdr = indoc! {"
-        typedef int a;
-        namespace std {
-        template <typename> class b;
-        }
-        typedef a c;
-        namespace operations_research {
-        class d;
-        class Solver {
-        public:
-            typedef std::b<c()> IndexEvaluator3;
-            d e(IndexEvaluator3);
-        };
-        class d {};
-        } // namespace operations_research
+        #include <bitset>
+        #include <cstdint>
+        typedef std::bitset<16> Flags;
+        inline Flags flip_flags(Flags f) { return ~f; }
+        inline uint32_t count_flags(const Flags& f) { return f.count(); }
+        typedef std::bitset<100> Wide;
+        inline Wide shift_wide(const Wide& w) { return w << 1; }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["operations_research::Solver"], &[]);
+    let rs = quote! {
+        use std::convert::TryFrom;
+        let mut f = ffi::Flags::new();
+        f.set(0).set(15);
+        assert_eq!(ffi::count_flags(f), 2);
+        let flipped = ffi::flip_flags(f);
+        assert_eq!(flipped.count(), 14);
+        assert!(!flipped.get(0));
+        assert!(flipped.get(1));
+        assert!((f | flipped).all());
+        assert!(!(f & flipped).any());
+        assert_eq!(u64::try_from(flipped).unwrap(), 0x7ffe);
+        assert_eq!(ffi::Flags::try_from(0x7ffe).unwrap(), flipped);
+        assert!(ffi::Flags::try_from(0x10000).is_err());
+        let mut w = ffi::Wide::new();
+        w.set(63).set(99);
+        let shifted = ffi::shift_wide(w);
+        assert!(shifted.get(64));
+        assert_eq!(shifted.count(), 1);
+        assert!(u64::try_from(shifted).is_err());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["flip_flags", "count_flags", "shift_wide"],
+            &[],
+            Some(quote! {
+                bitset!("Flags", 16)
+                bitset!("Wide", 100)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+/// A minimal executor, so that offload tests needn't depend on an
+/// async runtime.
+fn offload_block_on() -> proc_macro2::TokenStream {
+    quote! {
+        fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+            struct ThreadWaker(std::thread::Thread);
+            impl std::task::Wake for ThreadWaker {
+                fn wake(self: std::sync::Arc<Self>) {
+                    self.0.unpark();
+                }
+            }
+            let waker = std::sync::Arc::new(ThreadWaker(std::thread::current())).into();
+            let mut cx = std::task::Context::from_waker(&waker);
+            let mut fut = Box::pin(fut);
+            loop {
+                match fut.as_mut().poll(&mut cx) {
+                    std::task::Poll::Ready(result) => return result,
+                    std::task::Poll::Pending => std::thread::park(),
+                }
+            }
+        }
+    }
 }
 
 #[test]
-fn test_cvoid() {
+fn test_offload() {
     let hdr = indoc! {"
-        #include <memory>
         #include <cstdint>
-        inline void* a() {
-            return static_cast<void*>(new int(3));
+        #include <chrono>
+        #include <thread>
+        #include <string>
+        inline uint32_t slow_add(uint32_t a, uint32_t b) {
+            std::this_thread::sleep_for(std::chrono::milliseconds(100));
+            return a + b;
         }
-        inline uint32_t b(void* p) {
-            int* p_int = static_cast<int*>(p);
-            auto val = *p_int;
-            delete p_int;
-            return val;
+        inline uint32_t slow_len(std::string s) {
+            std::this_thread::sleep_for(std::chrono::milliseconds(100));
+            return s.size();
         }
     "};
     let rs = quote! {
-        let ptr = ffi::a();
-        let res = unsafe { ffi::b(ptr) };
-        assert_eq!(res, 3);
+        let caller = std::thread::current().id();
+        let spawner = move |job: autocxx::offload::Job| {
+            std::thread::spawn(move || {
+                assert_ne!(std::thread::current().id(), caller);
+                job()
+            });
+        };
+        let sum = ffi::slow_add_offloaded(spawner, 2, 3);
+        let len = ffi::slow_len_offloaded(autocxx::offload::ThreadSpawner, "hello");
+        assert_eq!(block_on(sum), 5);
+        assert_eq!(block_on(len), 5);
+        assert_eq!(ffi::slow_add(1, 1), 2);
     };
-    run_test("", hdr, rs, &["a", "b"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["slow_add", "slow_len"],
+            &[],
+            Some(quote! {
+                offload!("slow_add")
+                offload!("slow_len")
+            }),
+        ),
+        None,
+        None,
+        Some(offload_block_on()),
+    );
 }
 
 #[test]
-fn test_c_schar() {
+fn test_offload_rejects_references() {
     let hdr = indoc! {"
-        inline signed char a() {
-            return 8;
+        #include <cstdint>
+        inline uint32_t read_later(const uint32_t& a) {
+            return a;
         }
     "};
     let rs = quote! {
-        assert_eq!(ffi::a(), 8);
+        let a = 3u32;
+        block_on(ffi::read_later_offloaded(autocxx::offload::ThreadSpawner, &a));
     };
-    run_test("", hdr, rs, &["a"], &[]);
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["read_later"],
+            &[],
+            Some(quote! { offload!("read_later") }),
+        ),
+        None,
+        None,
+        Some(offload_block_on()),
+    );
 }
 
 #[test]
-fn test_c_uchar() {
-    let hdr = indoc! {"
-        inline unsigned char a() {
-            return 8;
+fn test_owned_return_with_custom_deleter() {
+    let cxx = indoc! {"
+        uint32_t frees = 0;
+        void free_thing(Thing* t) {
+            delete t;
+            frees++;
         }
+        uint32_t get_frees() {
+            return frees;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Thing {
+            uint32_t value;
+        };
+        inline Thing* make_thing(uint32_t value) { return new Thing { value }; }
+        inline Thing* make_nothing() { return nullptr; }
+        inline uint32_t get_value(const Thing& t) { return t.value; }
+        void free_thing(Thing* t);
+        uint32_t get_frees();
     "};
     let rs = quote! {
-        assert_eq!(ffi::a(), 8);
+        let thing = ffi::make_thing(42);
+        assert!(!thing.is_null());
+        assert_eq!(ffi::get_value(&thing), 42);
+        assert_eq!(ffi::get_frees(), 0);
+        drop(thing);
+        assert_eq!(ffi::get_frees(), 1);
+        let nothing = ffi::make_nothing();
+        assert!(nothing.is_null());
+        drop(nothing);
+        assert_eq!(ffi::get_frees(), 1);
     };
-    run_test("", hdr, rs, &["a"], &[]);
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &[
+                "Thing",
+                "make_thing",
+                "make_nothing",
+                "get_value",
+                "get_frees",
+            ],
+            &[],
+            Some(quote! {
+                owned_return!("make_thing", "free_thing")
+                owned_return!("make_nothing", "free_thing")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_c_ulonglong() {
-    // We don't test all the different variable-length integer types which we populate.
-    // If one works, they probably all do. Hopefully.
+fn test_pointer_wrapper_not_null() {
     let hdr = indoc! {"
-        inline unsigned long long a() {
-            return 8;
+        #include <cstdint>
+        #include <memory>
+        namespace gsl {
+        template <typename T> class not_null {
+        public:
+            not_null(T ptr) : ptr_(ptr) {}
+            T get() const { return ptr_; }
+            operator T() const { return ptr_; }
+            T operator->() const { return ptr_; }
+            typename std::pointer_traits<T>::element_type& operator*() const { return *ptr_; }
+        private:
+            T ptr_;
+        };
+        }
+        struct Thing {
+            uint32_t value;
+        };
+        inline std::unique_ptr<Thing> make_thing(uint32_t value) {
+            return std::make_unique<Thing>(Thing { value });
+        }
+        inline std::shared_ptr<Thing> make_shared_thing(uint32_t value) {
+            return std::make_shared<Thing>(Thing { value });
+        }
+        inline uint32_t get_value(gsl::not_null<const Thing*> t) { return t->value; }
+        inline void set_value(gsl::not_null<Thing*> t, uint32_t value) { t->value = value; }
+        inline gsl::not_null<const Thing*> same_thing(const Thing& t) { return &t; }
+        inline uint32_t get_shared_value(gsl::not_null<std::shared_ptr<Thing>> t) {
+            return t->value;
         }
     "};
     let rs = quote! {
-        assert_eq!(ffi::a(), autocxx::c_ulonglong(8));
+        let mut thing = ffi::make_thing(42);
+        assert_eq!(ffi::get_value(&thing), 42);
+        ffi::set_value(thing.pin_mut(), 3);
+        assert_eq!(ffi::get_value(&thing), 3);
+        assert_eq!(ffi::get_value(ffi::same_thing(&thing)), 3);
+        let shared = ffi::make_shared_thing(7);
+        assert_eq!(ffi::get_shared_value(shared), 7);
     };
-    run_test("", hdr, rs, &["a"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &[
+                "Thing",
+                "make_thing",
+                "make_shared_thing",
+                "get_value",
+                "set_value",
+                "same_thing",
+                "get_shared_value",
+            ],
+            &[],
+            Some(quote! {
+                register_pointer_wrapper!("gsl::not_null")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_string_transparent_function() {
+fn test_nonzero_return() {
     let hdr = indoc! {"
-        #include <string>
         #include <cstdint>
-        inline uint32_t take_string(std::string a) { return a.size(); }
+        inline uint32_t get_id() { return 42; }
+        inline int64_t get_offset() { return -3; }
+        inline uint16_t get_broken_id() { return 0; }
     "};
     let rs = quote! {
-        assert_eq!(ffi::take_string("hello"), 5);
+        let id: std::num::NonZeroU32 = ffi::get_id();
+        assert_eq!(id.get(), 42);
+        let offset: std::num::NonZeroI64 = ffi::get_offset();
+        assert_eq!(offset.get(), -3);
+        assert_eq!(
+            std::mem::size_of::<Option<std::num::NonZeroU32>>(),
+            std::mem::size_of::<u32>()
+        );
+        assert!(std::panic::catch_unwind(ffi::get_broken_id).is_err());
     };
-    run_test("", hdr, rs, &["take_string"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["get_id", "get_offset", "get_broken_id"],
+            &[],
+            Some(quote! {
+                nonzero!("get_id")
+                nonzero!("get_offset")
+                nonzero!("get_broken_id")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_string_transparent_method() {
+fn test_null_checked_return() {
     let hdr = indoc! {"
-        #include <string>
+        #include <memory>
         #include <cstdint>
-        struct A {
-            A() {}
-            inline uint32_t take_string(std::string a) const { return a.size(); }
+        struct Widget {
+            uint32_t size;
         };
+        inline std::unique_ptr<Widget> make_widget(bool succeed) {
+            if (!succeed) {
+                return nullptr;
+            }
+            auto widget = std::make_unique<Widget>();
+            widget->size = 3;
+            return widget;
+        }
+        inline std::unique_ptr<Widget> find_widget(bool found) {
+            return make_widget(found);
+        }
     "};
     let rs = quote! {
-        let a = ffi::A::make_unique();
-        assert_eq!(a.take_string("hello"), 5);
+        assert_eq!(ffi::make_widget(true).size, 3);
+        let message = *std::panic::catch_unwind(|| {
+            ffi::make_widget(false);
+        })
+        .unwrap_err()
+        .downcast::<&str>()
+        .unwrap();
+        assert_eq!(message, "make_widget returned a null pointer");
+        assert_eq!(ffi::find_widget(true).unwrap().size, 3);
+        assert!(ffi::find_widget(false).is_none());
     };
-    run_test("", hdr, rs, &["A"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_widget", "find_widget"],
+            &["Widget"],
+            Some(quote! {
+                non_null_return!("make_widget")
+                nullable_return!("find_widget")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_string_transparent_static_method() {
+fn test_null_checked_return_listed_twice() {
     let hdr = indoc! {"
-        #include <string>
-        #include <cstdint>
-        struct A {
-            A() {}
-            static inline uint32_t take_string(std::string a) { return a.size(); }
+        #include <memory>
+        struct Widget {};
+        inline std::unique_ptr<Widget> make_widget() { return nullptr; }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_widget"],
+            &["Widget"],
+            Some(quote! {
+                non_null_return!("make_widget")
+                nullable_return!("make_widget")
+            }),
+        ),
+        None,
+        Some(make_error_finder("make_widget")),
+        None,
+    );
+}
+
+#[test]
+fn test_null_checked_return_method() {
+    let hdr = indoc! {"
+        #include <memory>
+        struct Widget {};
+        class Factory {
+        public:
+            std::unique_ptr<Widget> make_widget() const { return nullptr; }
         };
     "};
     let rs = quote! {
-        assert_eq!(ffi::A::take_string("hello"), 5);
+        ffi::Factory::make_unique().make_widget();
     };
-    run_test("", hdr, rs, &["A"], &[]);
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Factory"],
+            &["Widget"],
+            Some(quote! { non_null_return!("Factory::make_widget") }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/490
-fn test_issue_490() {
+fn test_register_awaitable() {
     let hdr = indoc! {"
-        typedef int a;
-        typedef long unsigned size_t;
-        namespace std {
-        namespace {
-        using ::size_t;
-        template <class b, b c> struct g { static const b value = c; };
-        template <bool d> using e = g<bool, d>;
-        typedef e<true> true_type;
-        template <size_t, size_t> struct ag {};
-        template <class b> typename b ::h move();
-        template <class> class allocator;
-        template <class> class vector;
-        } // namespace
-        } // namespace std
-        void *operator new(size_t, void *);
-        namespace std {
-        namespace {
-        template <class> struct iterator;
-        template <class b, class> struct ay { using h = b *; };
-        template <class b> struct bj { b bk; };
-        template <class bm, class> class bn : bj<bm> {};
-        template <class b, class i = b> class unique_ptr {
-        typedef i bp;
-        typedef typename ay<b, bp>::h bh;
-        bn<bh, bp> bq;
-
-        public:
-        unique_ptr();
-        unique_ptr(bh);
-        bh get() const;
-        bh release();
-        };
-        template <class = void> struct bt;
-        } // namespace
-        } // namespace std
-        typedef a bv;
-        namespace absl {
-        template <typename ce> class cj {
-        public:
-        using bh = ce *;
-        using iterator = bh;
-        };
-        namespace j {
-        template <class ce> struct cp {
-        using k = ce;
-        using cq = std::bt<>;
-        };
-        template <class ce> using cr = typename cp<ce>::k;
-        template <class ce> using cs = typename cp<ce>::cq;
-        template <class, class, class, class> class ct {
-        public:
-        class iterator {};
-        class cu {
-            cu(iterator);
-            iterator cv;
-        };
-        };
-        template <typename> struct cw;
-        } // namespace j
-        template <class ce, class k = j::cr<ce>, class cq = j::cs<ce>,
-                class cx = std::allocator<ce>>
-        class cy : public j::ct<j::cw<ce>, k, cq, cx> {};
-        } // namespace absl
-        namespace cz {
-        template <typename da> class db { std::ag<sizeof(a), alignof(da)> c; };
-        } // namespace cz
-        namespace spanner {
-        class l;
-        class ColumnList {
+        class IntTask {
         public:
-        typedef absl::cj<l>::iterator iterator;
-        iterator begin();
-        };
-        class dd {
-        union {
-            cz::db<absl::cy<bv>::cu> e;
+            IntTask(int steps, int value) : steps(steps), value(value) {}
+            void resume() {
+                if (steps > 0) {
+                    steps--;
+                }
+            }
+            bool done() const { return steps == 0; }
+            int result() { return value; }
+        private:
+            int steps;
+            int value;
         };
+        inline IntTask compute() { return IntTask(3, 42); }
+    "};
+    let rs = quote! {
+        use std::future::Future;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+        fn noop_raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                noop_raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+        let mut cx = Context::from_waker(&waker);
+        let mut future = autocxx::CppFuture::new(ffi::compute());
+        let mut polls = 0;
+        let result = loop {
+            polls += 1;
+            if let Poll::Ready(result) = std::pin::Pin::new(&mut future).poll(&mut cx) {
+                break result;
+            }
         };
-        class Row {
+        assert_eq!(result, c_int(42));
+        assert_eq!(polls, 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["IntTask", "compute"],
+            &[],
+            Some(quote! {
+                register_awaitable!("IntTask", "resume", "done", "result")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_register_awaitable_with_rejected_method() {
+    // done() isn't const, so it's rejected. IntTask mustn't implement
+    // Awaitable, which would call it, but its other methods are still bound.
+    let hdr = indoc! {"
+        class IntTask {
         public:
-        bool f(dd);
+            IntTask(int steps) : steps(steps) {}
+            void resume() {
+                if (steps > 0) {
+                    steps--;
+                }
+            }
+            bool done() { return steps == 0; }
+            int result() { return 42; }
+        private:
+            int steps;
         };
-        } // namespace spanner
-    "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["spanner::Row", "spanner::ColumnList"], &[]);
+        inline IntTask compute() { return IntTask(3); }
+    "};
+    let rs = quote! {
+        let mut task = ffi::compute();
+        task.pin_mut().resume();
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["IntTask", "compute"],
+            &[],
+            Some(quote! {
+                register_awaitable!("IntTask", "resume", "done", "result")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_immovable_object() {
+fn test_checked_index() {
     let hdr = indoc! {"
-        class A {
-        public:
-            A();
-            A(A&&) = delete;
-        };
-
-        class B{
+        #include <cstdint>
+        #include <cstddef>
+        #include <stdexcept>
+        class IntList {
         public:
-            B();
-            B(const B&) = delete;
+            IntList() : values{1, 2, 3} {}
+            uint32_t& at(size_t i) { check(i); return values[i]; }
+            const uint32_t& at(size_t i) const { check(i); return values[i]; }
+            size_t size() const { return 3; }
+            static uint32_t throws() { return throw_count; }
+        private:
+            void check(size_t i) const {
+                if (i >= size()) {
+                    throw_count++;
+                    throw std::out_of_range(\"IntList\");
+                }
+            }
+            uint32_t values[3];
+            static uint32_t throw_count;
         };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    let cxx = indoc! {"
+        uint32_t IntList::throw_count = 0;
+    "};
+    let rs = quote! {
+        let list = ffi::IntList::make_unique();
+        assert_eq!(list.get(0), Some(&1));
+        assert_eq!(list.get(2), Some(&3));
+        assert_eq!(list.get(3), None);
+        assert_eq!(list.get(usize::MAX), None);
+        assert_eq!(ffi::IntList::throws(), 0);
+        assert_eq!(*list.at_const(1), 2);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["IntList"],
+            &[],
+            Some(quote! { checked_index!("IntList") }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_struct_with_reference() {
+fn test_error_code_param() {
     let hdr = indoc! {"
+        #include <system_error>
+        #include <ios>
         #include <cstdint>
-        #include <utility>
-        struct A {
-            uint32_t a;
-        };
-        struct B {
-            B(const A& param) : a(param) {}
-            const A& a;
-        };
+        inline uint32_t open_thing(uint32_t id, std::error_code& ec) {
+            if (id == 0) {
+                ec = std::make_error_code(std::errc::no_such_file_or_directory);
+                return 0;
+            }
+            ec.clear();
+            return id + 1;
+        }
+        inline void check_stream(bool fail, std::error_code& ec) {
+            if (fail) {
+                ec = std::make_error_code(std::io_errc::stream);
+            }
+        }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    let rs = quote! {
+        assert_eq!(ffi::open_thing(41).unwrap(), 42);
+        let err = ffi::open_thing(0).unwrap_err();
+        #[cfg(unix)]
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(ffi::check_stream(false).is_ok());
+        let err = ffi::check_stream(true).unwrap_err();
+        let code = err
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<autocxx::error_code::CppErrorCode>()
+            .unwrap();
+        assert_eq!(code.category(), "iostream");
+        assert_eq!(code.value(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["open_thing", "check_stream"],
+            &[],
+            Some(quote! {
+                error_code_param!("open_thing", "ec")
+                error_code_param!("check_stream", "ec")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_struct_with_rvalue() {
+fn test_out_param_pointer_to_pointer() {
+    let cxx = indoc! {"
+        uint32_t frees = 0;
+        void free_thing(Thing* t) {
+            delete t;
+            frees++;
+        }
+        uint32_t get_frees() {
+            return frees;
+        }
+    "};
     let hdr = indoc! {"
         #include <cstdint>
-        #include <utility>
-        struct A {
-            uint32_t a;
-        };
-        struct B {
-            B(A&& param) : a(std::move(param)) {}
-            A&& a;
+        struct Thing {
+            uint32_t value;
         };
+        inline int create(uint32_t value, Thing** out) {
+            *out = new Thing { value };
+            return 0;
+        }
+        inline void make(Thing** out) { *out = new Thing { 7 }; }
+        inline uint32_t count(Thing** things, uint32_t len) {
+            uint32_t total = 0;
+            for (uint32_t i = 0; i < len; i++) {
+                total += things[i]->value;
+            }
+            return total;
+        }
+        inline uint32_t get_value(const Thing& t) { return t.value; }
+        void free_thing(Thing* t);
+        uint32_t get_frees();
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    let rs = quote! {
+        let (status, thing) = ffi::create(42);
+        assert_eq!(status, autocxx::c_int(0));
+        assert_eq!(ffi::get_value(&thing), 42);
+        drop(thing);
+        assert_eq!(ffi::get_frees(), 1);
+        let mut raw = ffi::make();
+        assert!(!raw.is_null());
+        assert_eq!(ffi::get_value(unsafe { &*raw }), 7);
+        assert_eq!(unsafe { ffi::count(&mut raw, 1) }, 7);
+        unsafe { ffi::free_thing(raw) };
+        assert_eq!(ffi::get_frees(), 2);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &[
+                "Thing",
+                "create",
+                "make",
+                "count",
+                "get_value",
+                "free_thing",
+                "get_frees",
+            ],
+            &[],
+            Some(quote! {
+                out_param!("create", "out")
+                out_param!("make", "out")
+                owned_return!("create", "free_thing")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_immovable_nested_object() {
+fn test_unique_array_return() {
     let hdr = indoc! {"
-        struct C {
-            class A {
-            public:
-                A();
-                A(A&&) = delete;
-            };
+        #include <cstdint>
+        #include <cstddef>
+        #include <memory>
+        inline std::unique_ptr<uint32_t[]> make_squares(uint32_t count) {
+            std::unique_ptr<uint32_t[]> squares(new uint32_t[count]);
+            for (uint32_t i = 0; i < count; ++i) {
+                squares[i] = i * i;
+            }
+            return squares;
+        }
+        inline std::unique_ptr<int[]> read_values(size_t* count) {
+            *count = 3;
+            return std::unique_ptr<int[]>(new int[3] { 4, -5, 6 });
+        }
+    "};
+    let rs = quote! {
+        let squares = ffi::make_squares(4);
+        assert_eq!(squares.as_slice(), &[0, 1, 4, 9]);
+        assert_eq!(squares[3], 9);
+        let mut values = ffi::read_values();
+        assert_eq!(values.len(), 3);
+        values[1] = autocxx::c_int(5);
+        let total: i32 = values.iter().map(|v| v.0).sum();
+        assert_eq!(total, 15);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_squares", "read_values"],
+            &[],
+            Some(quote! {
+                unique_array_return!("make_squares", "count")
+                unique_array_return!("read_values", "count")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
 
-            class B{
-            public:
-                B();
-                B(const B&) = delete;
-            };
+#[test]
+fn test_array_return() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstddef>
+        struct Item {
+            uint32_t id;
+            uint32_t weight;
+        };
+        inline const Item* get_items() {
+            static const Item items[] = { { 1, 10 }, { 2, 20 }, { 3, 30 } };
+            return items;
+        }
+        inline size_t item_count() {
+            return 3;
+        }
+        inline const uint32_t* get_primes(size_t* count) {
+            static const uint32_t primes[] = { 2, 3, 5, 7 };
+            *count = 4;
+            return primes;
+        }
+        inline const uint32_t* get_none(size_t* count) {
+            *count = 4;
+            return nullptr;
+        }
+        struct Readings {
+            uint32_t values[3];
         };
+        inline const uint32_t* get_values(const Readings& readings, size_t* count) {
+            *count = 3;
+            return readings.values;
+        }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["C_A", "C_B"], &[]);
+    let rs = quote! {
+        let items: &[ffi::Item] = unsafe { ffi::get_items() };
+        assert_eq!(items.len(), 3);
+        assert_eq!(items.iter().map(|item| item.weight).sum::<u32>(), 60);
+        assert_eq!(unsafe { ffi::get_primes() }, &[2, 3, 5, 7]);
+        assert!(unsafe { ffi::get_none() }.is_empty());
+        // This borrows from the readings, so needs no unsafe.
+        let readings = ffi::Readings { values: [4, 5, 6] };
+        assert_eq!(ffi::get_values(&readings), &[4, 5, 6]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &[
+                "get_items",
+                "item_count",
+                "get_primes",
+                "get_none",
+                "get_values",
+            ],
+            &["Item", "Readings"],
+            Some(quote! {
+                array_return!("get_items", "item_count")
+                array_return!("get_primes", "count")
+                array_return!("get_none", "count")
+                array_return!("get_values", "count")
+                safety!(unsafe_ffi)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_type_called_type() {
+fn test_null_terminated_array_return() {
     let hdr = indoc! {"
-        namespace a {
-            template<int _Len>
-            struct b
-            {
-                union type
-                {
-                    unsigned char __data[_Len];
-                    struct foo {
-                        int a;
-                    };
-                };
-            };
+        inline const char* const* get_names() {
+            static const char* const names[] = { \"alpha\", \"beta\", \"gamma\", nullptr };
+            return names;
         }
-        inline void take_type(a::b<4>::type) {}
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["take_type"], &[]);
+    let rs = quote! {
+        let names: Vec<&str> = unsafe { ffi::get_names() }
+            .into_iter()
+            .map(|name| name.to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["alpha", "beta", "gamma"]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["get_names"],
+            &[],
+            Some(quote! {
+                null_terminated_array_return!("get_names")
+                safety!(unsafe_ffi)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_bridge_conflict_ty() {
-    let hdr = indoc! {"
-        namespace a {
-            struct Key { int a; };
+fn test_resource_handle_closed_on_drop() {
+    let cxx = indoc! {"
+        uint32_t closes = 0;
+        void close_handle(handle h) {
+            closes++;
         }
-        namespace b {
-            struct Key { int a; };
+        uint32_t get_closes() {
+            return closes;
         }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["a::Key", "b::Key"], &[]);
+    let hdr = indoc! {"
+        #include <cstdint>
+        typedef int handle;
+        inline handle open_handle(int id) { return id; }
+        inline handle open_nothing() { return -1; }
+        inline int get_id(handle h) { return h; }
+        void close_handle(handle h);
+        uint32_t get_closes();
+    "};
+    let rs = quote! {
+        let h = ffi::open_handle(autocxx::c_int(42));
+        assert_eq!(ffi::get_id(h.get()), autocxx::c_int(42));
+        assert_eq!(ffi::get_closes(), 0);
+        drop(h);
+        assert_eq!(ffi::get_closes(), 1);
+        let nothing = ffi::open_nothing();
+        drop(nothing);
+        assert_eq!(ffi::get_closes(), 1);
+        let released = ffi::open_handle(autocxx::c_int(3)).into_raw();
+        assert_eq!(released, autocxx::c_int(3));
+        assert_eq!(ffi::get_closes(), 1);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["open_handle", "open_nothing", "get_id", "get_closes"],
+            &[],
+            Some(quote! {
+                resource!("handle", "close_handle", "-1")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_bridge_conflict_ty_fn() {
-    let hdr = indoc! {"
-        namespace a {
-            struct Key { int a; };
-        }
-        namespace b {
-            inline void Key() {}
+fn test_intrusive_refcount() {
+    let cxx = indoc! {"
+        uint32_t destructions = 0;
+        uint32_t get_destructions() {
+            return destructions;
         }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["a::Key", "b::Key"], &[]);
+    let hdr = indoc! {"
+        #include <cstdint>
+        uint32_t get_destructions();
+        extern uint32_t destructions;
+        class Thing {
+        public:
+            Thing() : refs(1) {}
+            void AddRef() { refs++; }
+            void Release() {
+                if (--refs == 0) {
+                    delete this;
+                }
+            }
+            uint32_t get_refs() const { return refs; }
+            ~Thing() { destructions++; }
+        private:
+            uint32_t refs;
+        };
+        inline Thing* create_thing() { return new Thing(); }
+    "};
+    let rs = quote! {
+        let thing = unsafe { autocxx::IntrusivePtr::from_raw(ffi::create_thing()) }.unwrap();
+        assert_eq!(thing.get_refs(), 1);
+        let another = thing.clone();
+        assert!(autocxx::IntrusivePtr::ptr_eq(&thing, &another));
+        assert_eq!(thing.get_refs(), 2);
+        drop(another);
+        assert_eq!(thing.get_refs(), 1);
+        let borrowed = unsafe { autocxx::IntrusivePtr::from_raw_add_ref(thing.as_mut_ptr()) }.unwrap();
+        assert_eq!(thing.get_refs(), 2);
+        drop(thing);
+        assert_eq!(borrowed.get_refs(), 1);
+        assert_eq!(ffi::get_destructions(), 0);
+        drop(borrowed);
+        assert_eq!(ffi::get_destructions(), 1);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Thing", "create_thing", "get_destructions"],
+            &[],
+            Some(quote! {
+                intrusive_refcount!("Thing", "AddRef", "Release")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_issue_506() {
+fn test_smart_pointer() {
     let hdr = indoc! {"
-        namespace std {
-            template <class, class> class am;
-            typedef am<char, char> an;
-        } // namespace std
-        namespace be {
-            class bf {
-            virtual std::an bg() = 0;
-            };
-            class bh : bf {};
-        } // namespace be
-        namespace spanner {
-            class Database;
-            class Row {
-            public:
-            Row(be::bh *);
-            };
-        } // namespace spanner
+        #include <cstdint>
+        #include <memory>
+        class Widget {
+        public:
+            Widget(uint32_t size) : size_(size) {}
+            uint32_t size() const { return size_; }
+            void resize(uint32_t size) { size_ = size; }
+        private:
+            uint32_t size_;
+        };
+        class WidgetPtr {
+        public:
+            WidgetPtr(uint32_t size) : w(std::make_shared<Widget>(size)) {}
+            WidgetPtr() {}
+            const Widget* operator->() const { return w.get(); }
+            Widget* operator->() { return w.get(); }
+            const Widget& operator*() const { return *w; }
+        private:
+            std::shared_ptr<Widget> w;
+        };
+        class WidgetHandle {
+        public:
+            WidgetHandle(uint32_t size) : p(size) {}
+            const WidgetPtr& operator->() const { return p; }
+        private:
+            WidgetPtr p;
+        };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["spanner::Database", "spanner::Row"], &[]);
+    let rs = quote! {
+        let mut ptr = ffi::WidgetPtr::make_unique(3);
+        assert_eq!(ptr.size(), 3);
+        ptr.pin_mut().deref_pin_mut().resize(5);
+        assert_eq!(ptr.size(), 5);
+        let widget: &ffi::Widget = &ptr;
+        assert_eq!(widget.size(), 5);
+        let handle = ffi::WidgetHandle::make_unique(7);
+        assert_eq!(handle.size(), 7);
+        let empty = ffi::WidgetPtr::make_unique1();
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| empty.size())).is_err());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Widget", "WidgetPtr", "WidgetHandle"],
+            &[],
+            Some(quote! {
+                smart_pointer!("WidgetPtr", "Widget", mut)
+                smart_pointer!("WidgetHandle", "Widget")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_private_inheritance() {
+fn test_validity() {
     let hdr = indoc! {"
-        class A {
+        #include <cstdint>
+        class Handle {
         public:
-            void foo() {}
-            int a;
+            Handle(uint32_t fd) : fd(fd) {}
+            explicit operator bool() const { return fd != 0; }
+            bool empty() const { return fd == 0; }
+            uint32_t get() const { return fd; }
+        private:
+            uint32_t fd;
         };
-        class B : A {
+        class Name {
         public:
-            void bar() {}
-            int b;
+            Name(bool present) : present(present) {}
+            bool empty() const { return !present; }
+        private:
+            bool present;
         };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A", "B"], &[]);
+    let rs = quote! {
+        use autocxx::IsValid;
+        let good = ffi::Handle::make_unique(3);
+        let bad = ffi::Handle::make_unique(0);
+        assert!(good.is_valid());
+        assert!(!bad.is_valid());
+        assert_eq!(good.as_option().map(|h| h.get()), Some(3));
+        assert!(bad.as_option().is_none());
+        assert!(ffi::Name::make_unique(true).is_valid());
+        assert!(!ffi::Name::make_unique(false).is_valid());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Handle", "Name"],
+            &[],
+            Some(quote! {
+                validity!("Handle")
+                validity!("Name", "!empty")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_error_generated_for_static_data() {
+fn test_fn_table() {
     let hdr = indoc! {"
         #include <cstdint>
-        struct A {
-            A() {}
-            uint32_t a;
+        #include <cstring>
+        struct FileOps {
+            uint32_t (*read)(void* ctx, char* buf, uint32_t len);
+            void (*close)(void* ctx);
         };
-        static A FOO = A();
+        inline uint32_t read_hello(void* ctx, char* buf, uint32_t len) {
+            *static_cast<uint32_t*>(ctx) += 1;
+            uint32_t n = len < 5 ? len : 5;
+            std::memcpy(buf, \"hello\", n);
+            return n;
+        }
+        inline const FileOps& get_file_ops() {
+            static FileOps ops = { read_hello, nullptr };
+            return ops;
+        }
     "};
-    let rs = quote! {};
+    let rs = quote! {
+        let ops = ffi::get_file_ops();
+        let mut reads = 0u32;
+        let mut buf = [0u8; 8];
+        assert!(ops.has_read());
+        assert!(!ops.has_close());
+        let n = unsafe {
+            ops.read(
+                &mut reads as *mut u32 as *mut autocxx::c_void,
+                buf.as_mut_ptr() as *mut std::os::raw::c_char,
+                buf.len() as u32,
+            )
+        };
+        assert_eq!(&buf[..n as usize], b"hello");
+        assert_eq!(reads, 1);
+    };
     run_test_ex(
         "",
         hdr,
         rs,
-        quote! { generate!("FOO")},
+        directives_from_lists(
+            &["FileOps", "get_file_ops"],
+            &[],
+            Some(quote! {
+                fn_table!("FileOps")
+            }),
+        ),
+        None,
         None,
-        Some(make_error_finder("FOO")),
         None,
     );
 }
 
 #[test]
-fn test_error_generated_for_array_dependent_function() {
+fn test_restricted_visibility() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <functional>
-        inline void take_func(std::function<bool(const uint32_t number)>) {
+        struct Secret {
+            uint32_t a;
+        };
+        inline uint32_t reveal(const Secret& s) { return s.a; }
+        namespace internal {
+            inline uint32_t helper() { return 3; }
         }
     "};
-    let rs = quote! {};
+    let rs = quote! {
+        let s = ffi::Secret { a: 4 };
+        assert_eq!(ffi::reveal(&s), 4);
+        assert_eq!(ffi::internal::helper(), 3);
+    };
     run_test_ex(
         "",
         hdr,
         rs,
-        quote! { generate! ("take_func")},
+        directives_from_lists(
+            &["reveal", "internal::helper"],
+            &["Secret"],
+            Some(quote! {
+                visibility!("Secret", pub(crate))
+                visibility!("internal", pub(crate))
+            }),
+        ),
         None,
-        Some(make_error_finder("take_func")),
+        Some(make_string_finder(vec![
+            "pub (crate) use bindgen :: root :: Secret ;",
+            "pub (crate) mod internal",
+        ])),
         None,
     );
 }
 
 #[test]
-fn test_error_generated_for_array_dependent_method() {
+fn test_exceptions_as_panics() {
     let hdr = indoc! {"
         #include <cstdint>
-        #include <functional>
-        struct A {
-            void take_func(std::function<bool(const uint32_t number)>) {
+        #include <stdexcept>
+        inline uint32_t checked_div(uint32_t a, uint32_t b) {
+            if (b == 0) {
+                throw std::invalid_argument(\"division by zero\");
+            }
+            return a / b;
+        }
+        inline void throw_int() { throw 42; }
+        struct Checker {
+            uint32_t limit;
+            void check(uint32_t a) const {
+                if (a > limit) {
+                    throw std::out_of_range(\"too big\");
+                }
             }
         };
     "};
-    let rs = quote! {};
+    let rs = quote! {
+        fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+            *std::panic::catch_unwind(f)
+                .unwrap_err()
+                .downcast::<String>()
+                .unwrap()
+        }
+        assert_eq!(ffi::checked_div(6, 3), 2);
+        assert_eq!(
+            panic_message(|| {
+                ffi::checked_div(6, 0);
+            }),
+            "division by zero"
+        );
+        assert_eq!(
+            panic_message(ffi::throw_int),
+            "C++ exception not derived from std::exception"
+        );
+        let checker = ffi::Checker { limit: 3 };
+        checker.check(2);
+        assert_eq!(panic_message(move || checker.check(4)), "too big");
+    };
     run_test_ex(
         "",
         hdr,
         rs,
-        quote! { generate! ("A")},
+        directives_from_lists(
+            &["checked_div", "throw_int"],
+            &["Checker"],
+            Some(quote! {
+                safety!(unsafe_ffi, exceptions_as_panics)
+            }),
+        ),
+        None,
         None,
-        Some(make_string_finder(
-            ["take_func", "couldn't be generated"].to_vec(),
-        )),
         None,
     );
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/835
-fn test_error_generated_for_pod_with_nontrivial_destructor() {
+fn test_byte_slice_param() {
     let hdr = indoc! {"
+        #include <cstddef>
         #include <cstdint>
-        #include <functional>
-        struct A {
-            ~A() {}
-        };
+        inline uint32_t sum_bytes(const std::byte* data, size_t len) {
+            uint32_t total = 0;
+            for (size_t i = 0; i < len; i++) {
+                total += static_cast<uint32_t>(data[i]);
+            }
+            return total;
+        }
+        inline void fill_bytes(std::byte* data, uint32_t len, std::byte value) {
+            for (uint32_t i = 0; i < len; i++) {
+                data[i] = value;
+            }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum_bytes(&[1u8, 2, 3]), 6);
+        assert_eq!(ffi::sum_bytes(&[]), 0);
+        let mut buf = [0u8; 4];
+        ffi::fill_bytes(&mut buf, autocxx::c_byte(7));
+        assert_eq!(buf, [7, 7, 7, 7]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["sum_bytes", "fill_bytes"],
+            &[],
+            Some(quote! {
+                slice_param!("sum_bytes", "data", "len")
+                slice_param!("fill_bytes", "data", "len")
+            }),
+        ),
+        make_clang_arg_adder(&["-std=c++17"]),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_slice_params_sharing_length() {
+    let hdr = indoc! {"
+        #include <cstddef>
+        inline int add(const int* a, const int* b, size_t n) {
+            int total = 0;
+            for (size_t i = 0; i < n; i++) {
+                total += a[i] + b[i];
+            }
+            return total;
+        }
     "};
     let rs = quote! {};
-    run_test_expect_fail("", hdr, rs, &[], &["A"]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["add"],
+            &[],
+            Some(quote! {
+                slice_param!("add", "a", "n")
+                slice_param!("add", "b", "n")
+            }),
+        ),
+        None,
+        Some(make_string_finder(vec![
+            "This function was listed in slice_param! but n is already part of another slice_param!",
+        ])),
+        None,
+    );
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/835
-fn test_error_generated_for_pod_with_nontrivial_move_constructor() {
+fn test_c_str_param() {
     let hdr = indoc! {"
-        #include <cstdint>
-        #include <functional>
-        struct A {
-            A() = default;
-            A(A&&) {}
+        #include <cstddef>
+        #include <cstring>
+        inline size_t count_vowels(const char* text) {
+            size_t count = 0;
+            for (; *text; text++) {
+                count += std::strchr(\"aeiou\", *text) != nullptr;
+            }
+            return count;
+        }
+        struct Text {
+            size_t find(const char* needle, const char* haystack) const {
+                const char* found = std::strstr(haystack, needle);
+                return found ? found - haystack : std::strlen(haystack);
+            }
         };
     "};
-    let rs = quote! {};
-    run_test_expect_fail("", hdr, rs, &[], &["A"]);
+    let rs = quote! {
+        assert_eq!(ffi::count_vowels("banana"), 3);
+        let apple = std::ffi::CStr::from_bytes_with_nul(b"apple\0").unwrap();
+        assert_eq!(ffi::count_vowels(apple), 2);
+        let owned = String::from("queue");
+        assert_eq!(ffi::count_vowels(&owned), 4);
+        assert!(std::panic::catch_unwind(|| ffi::count_vowels("ba\0na")).is_err());
+        let text = ffi::Text::make_unique();
+        assert_eq!(text.find("na", apple), 5);
+        assert_eq!(text.find(&std::ffi::CString::new("na").unwrap(), "banana"), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["count_vowels", "Text"],
+            &[],
+            Some(quote! {
+                c_str_param!("count_vowels", "text")
+                c_str_param!("Text::find", "needle")
+                c_str_param!("Text::find", "haystack")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_keyword_function() {
+fn test_c_str_return() {
     let hdr = indoc! {"
-        inline void move(int a) {};
+        #include <string>
+        class Person {
+        public:
+            Person() : name_(\"Ada\") {}
+            const char* c_name() const { return name_.c_str(); }
+            const std::string& name() const { return name_; }
+        private:
+            std::string name_;
+        };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["move_"], &[]);
+    let rs = quote! {
+        let person = ffi::Person::make_unique();
+        let c_name: &std::ffi::CStr = person.c_name();
+        assert_eq!(c_name.to_str().unwrap(), "Ada");
+        let name = person.name();
+        assert_eq!(name.to_str().unwrap(), "Ada");
+        // Both borrow the same bytes from the object.
+        assert_eq!(c_name.as_ptr() as *const u8, name.as_bytes().as_ptr());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Person"],
+            &[],
+            Some(quote! {
+                c_str_return!("Person::c_name")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_keyword_method() {
+fn test_c_str_return_outlives_object() {
     let hdr = indoc! {"
-        struct A {
-            int a;
-            inline void move() {};
+        #include <string>
+        class Person {
+        public:
+            Person() : name_(\"Ada\") {}
+            const char* c_name() const { return name_.c_str(); }
+        private:
+            std::string name_;
         };
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["A"], &[]);
+    let rs = quote! {
+        let person = ffi::Person::make_unique();
+        let c_name = person.c_name();
+        drop(person);
+        assert_eq!(c_name.to_str().unwrap(), "Ada");
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Person"],
+            &[],
+            Some(quote! {
+                c_str_return!("Person::c_name")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_doc_passthru() {
+fn test_precondition() {
     let hdr = indoc! {"
+        #include <cassert>
         #include <cstdint>
-        /// Elephants!
-        struct A {
-            uint32_t a;
-        };
-        /// Giraffes!
-        struct B {
-            uint32_t a;
-        };
-        /// Rhinos!
-        inline uint32_t get_a() { return 3; }
+        inline uint32_t divide(uint32_t a, uint32_t b) { assert(b != 0); return a / b; }
+        inline uint32_t clamp(uint32_t v, uint32_t lo, uint32_t hi) {
+            assert(lo <= hi);
+            return v < lo ? lo : (v > hi ? hi : v);
+        }
     "};
-    let rs = quote! {};
+    let rs = quote! {
+        fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+            *std::panic::catch_unwind(f)
+                .unwrap_err()
+                .downcast::<String>()
+                .unwrap()
+        }
+        assert_eq!(ffi::divide(6, 3), 2);
+        assert_eq!(ffi::clamp(7, 1, 5), 5);
+        if cfg!(debug_assertions) {
+            assert_eq!(
+                panic_message(|| {
+                    ffi::divide(6, 0);
+                }),
+                "precondition of divide violated: *args.1 != 0"
+            );
+            assert_eq!(
+                panic_message(|| {
+                    ffi::clamp(7, 5, 1);
+                }),
+                "precondition of clamp violated: lo <= hi"
+            );
+        }
+    };
     run_test_ex(
         "",
         hdr,
         rs,
-        directives_from_lists(&["A", "get_a"], &["B"], None),
+        directives_from_lists(
+            &["divide", "clamp"],
+            &[],
+            Some(quote! {
+                precondition!("divide", |args| *args.1 != 0)
+                precondition!("clamp", |(_, lo, hi)| lo <= hi)
+            }),
+        ),
+        None,
         None,
-        Some(make_string_finder(
-            ["Giraffes", "Elephants", "Rhinos"].to_vec(),
-        )),
         None,
     );
 }
 
 #[test]
-fn test_closure() {
-    // Ensuring presence of this closure doesn't break other things
+fn test_precondition_on_method() {
     let hdr = indoc! {"
-    #include <functional>
-    #include <cstdint>
-
-    inline bool take_closure(std::function<bool(const uint32_t number)> fn) {
-        return fn(5);
-    }
-    inline uint32_t get_a() {
-        return 3;
-    }
+        #include <cstdint>
+        class Divider {
+        public:
+            uint32_t divide(uint32_t a, uint32_t b) const { return a / b; }
+        };
     "};
     let rs = quote! {
-        assert_eq!(ffi::get_a(), 3);
+        ffi::Divider::make_unique().divide(6, 3);
     };
-    run_test("", hdr, rs, &["get_a"], &[]);
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Divider"],
+            &[],
+            Some(quote! { precondition!("Divider::divide", |args| *args.2 != 0) }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_underscored_namespace_for_inner_type() {
+fn test_strip_prefix() {
     let hdr = indoc! {"
-        namespace __foo {
-            struct daft {
-                struct bob {
-                    int a;
-                };
-                int a;
-            };
-        }
-        inline void bar(__foo::daft::bob) {}
+        #include <cstdint>
+        inline uint32_t mylib_open(uint32_t flags) { return flags + 3; }
+        inline bool mylib_close(uint32_t fd) { return fd == 3; }
+        inline uint32_t mylib_read(uint32_t fd, uint32_t count) { return fd * count; }
+        inline uint32_t mylib_type() { return 7; }
+        inline uint32_t mylib_2d_area(uint32_t w, uint32_t h) { return w * h; }
+        inline uint32_t version() { return 2; }
     "};
-    let rs = quote! {};
-    run_test("", hdr, rs, &["bar"], &[]);
+    let rs = quote! {
+        let fd = ffi::open(0);
+        assert_eq!(ffi::read(fd, 2), 6);
+        assert!(ffi::close(fd));
+        assert_eq!(ffi::type_(), 7);
+        assert_eq!(ffi::mylib_2d_area(2, 3), 6);
+        assert_eq!(ffi::version(), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &[
+                "mylib_open",
+                "mylib_close",
+                "mylib_read",
+                "mylib_type",
+                "mylib_2d_area",
+                "version",
+            ],
+            &[],
+            Some(quote! {
+                strip_prefix!("mylib_")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-fn test_blocklist_not_overly_broad() {
-    // This is a regression test. We used to block anything that starts with "rust" or "std",
-    // not just items in the "rust" and "std" namespaces. We therefore test that functions starting
-    // with "rust" or "std" get imported.
+fn test_strip_prefix_collision() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t mylib_seek(uint32_t pos) { return pos + 1; }
+        inline uint32_t seek(uint32_t pos) { return pos + 2; }
+        inline uint32_t mylib_tell() { return 5; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::seek(1), 3);
+        assert_eq!(ffi::tell(), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["mylib_seek", "seek", "mylib_tell"],
+            &[],
+            Some(quote! {
+                strip_prefix!("mylib_")
+            }),
+        ),
+        None,
+        Some(make_error_finder("mylib_seek")),
+        None,
+    );
+}
+
+#[test]
+fn test_array_reference_param() {
     let hdr = indoc! {"
-    inline void rust_func() { }
-    inline void std_func() { }
+        #include <cstdint>
+        struct Point {
+            int32_t x;
+            int32_t y;
+        };
+        inline void fill(int32_t (&arr)[8]) {
+            for (int32_t i = 0; i < 8; ++i) {
+                arr[i] = i * i;
+            }
+        }
+        inline int32_t sum(const int32_t (&arr)[4]) {
+            return arr[0] + arr[1] + arr[2] + arr[3];
+        }
+        inline void scale(Point (&points)[2], int32_t factor) {
+            for (auto& point : points) {
+                point.x *= factor;
+                point.y *= factor;
+            }
+        }
     "};
     let rs = quote! {
-        ffi::rust_func();
-        ffi::std_func();
+        let mut arr = [-1i32; 8];
+        ffi::fill(&mut arr);
+        assert_eq!(arr, [0, 1, 4, 9, 16, 25, 36, 49]);
+        assert_eq!(ffi::sum(&[1, 2, 3, 4]), 10);
+        let mut points = [ffi::Point { x: 1, y: 2 }, ffi::Point { x: 3, y: 4 }];
+        ffi::scale(&mut points, 10);
+        assert_eq!((points[1].x, points[1].y), (30, 40));
     };
-    run_test("", hdr, rs, &["rust_func", "std_func"], &[]);
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["fill", "sum", "scale"], &["Point"], None),
+        None,
+        None,
+        None,
+    );
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/837
-fn test_ref_qualified_method() {
+fn test_reference_wrapper_param() {
     let hdr = indoc! {"
-        struct A {
-            void foo() & {}
+        #include <cstdint>
+        #include <functional>
+        struct Counter {
+            uint32_t count;
         };
+        inline void bump(std::reference_wrapper<Counter> c) { c.get().count++; }
+        inline uint32_t read(std::reference_wrapper<const Counter> c) {
+            return c.get().count;
+        }
     "};
     let rs = quote! {
-        A::make_unique().foo();
+        let mut c = ffi::Counter { count: 1 };
+        unsafe { ffi::bump(&mut c) };
+        assert_eq!(unsafe { ffi::read(&mut c) }, 2);
     };
-    run_test("", hdr, rs, &["A"], &[]);
+    run_test("", hdr, rs, &["bump", "read"], &["Counter"]);
 }
 
-#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
-#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
 #[test]
-fn test_stringview() {
-    // Test that APIs using std::string_view do not otherwise cause errors.
+fn test_closure_callback() {
+    let cxx = indoc! {"
+        static void (*registered_cb)(void*, uint32_t) = nullptr;
+        static void* registered_ctx = nullptr;
+        void register_callback(void (*cb)(void* ctx, uint32_t value), void* ctx) {
+            registered_cb = cb;
+            registered_ctx = ctx;
+        }
+        void unregister_callback() {
+            registered_cb = nullptr;
+            registered_ctx = nullptr;
+        }
+        void fire(uint32_t value) {
+            if (registered_cb) {
+                registered_cb(registered_ctx, value);
+            }
+        }
+    "};
     let hdr = indoc! {"
-        #include <string_view>
-        #include <string>
-        void take_string_view(std::string_view) {}
-        std::string_view return_string_view(std::string a) { return std::string_view(a); }
+        #include <cstdint>
+        void register_callback(void (*cb)(void* ctx, uint32_t value), void* ctx);
+        void unregister_callback();
+        void fire(uint32_t value);
     "};
-    let rs = quote! {};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        let total = Arc::new(AtomicU32::new(0));
+        let total_in_closure = total.clone();
+        let handle = ffi::register_callback(move |value| {
+            total_in_closure.fetch_add(value, Ordering::SeqCst);
+        });
+        ffi::fire(3);
+        ffi::fire(4);
+        assert_eq!(total.load(Ordering::SeqCst), 7);
+        ffi::unregister_callback();
+        ffi::fire(5);
+        assert_eq!(total.load(Ordering::SeqCst), 7);
+        // The closure still holds its reference until it's freed.
+        assert_eq!(Arc::strong_count(&total), 2);
+        unsafe { handle.free() };
+        assert_eq!(Arc::strong_count(&total), 1);
+    };
     run_test_ex(
-        "",
+        cxx,
         hdr,
         rs,
-        directives_from_lists(&["take_string_view", "return_string_view"], &[], None),
-        make_clang_arg_adder(&["-std=c++17"]),
+        directives_from_lists(
+            &["register_callback", "unregister_callback", "fire"],
+            &[],
+            Some(quote! {
+                closure_callback!("register_callback", "cb", "ctx")
+            }),
+        ),
         None,
         None,
+        None,
+    );
+}
+
+#[test]
+fn test_std_function_closure() {
+    let cxx = indoc! {"
+        static std::function<void(uint32_t)> registered_handler;
+        void register_handler(const std::function<void(uint32_t)>& handler) {
+            registered_handler = handler;
+        }
+        void unregister_handler() {
+            registered_handler = nullptr;
+        }
+        void fire(uint32_t value) {
+            if (registered_handler) {
+                registered_handler(value);
+            }
+        }
+        uint32_t apply(std::function<uint32_t(uint32_t)> f, uint32_t value) {
+            return f(value);
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <functional>
+        void register_handler(const std::function<void(uint32_t)>& handler);
+        void unregister_handler();
+        void fire(uint32_t value);
+        uint32_t apply(std::function<uint32_t(uint32_t)> f, uint32_t value);
+    "};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        let total = Arc::new(AtomicU32::new(0));
+        let total_in_closure = total.clone();
+        ffi::register_handler(move |value| {
+            total_in_closure.fetch_add(value, Ordering::SeqCst);
+        });
+        // C++ kept a copy of the std::function, so the closure is alive.
+        assert_eq!(Arc::strong_count(&total), 2);
+        ffi::fire(3);
+        ffi::fire(4);
+        assert_eq!(total.load(Ordering::SeqCst), 7);
+        ffi::unregister_handler();
+        assert_eq!(Arc::strong_count(&total), 1);
+        ffi::fire(5);
+        assert_eq!(total.load(Ordering::SeqCst), 7);
+        let offset = 10;
+        assert_eq!(ffi::apply(move |value| value + offset, 5), 15);
+    };
+    run_test(
+        cxx,
+        hdr,
+        rs,
+        &["register_handler", "unregister_handler", "fire", "apply"],
+        &[],
     );
 }
 
@@ -5520,6 +9129,87 @@ fn test_cint_vector() {
     run_test("", hdr, rs, &["give_vec"], &[]);
 }
 
+#[test]
+fn test_vector_as_slice() {
+    let hdr = indoc! {"
+        #include <vector>
+        #include <cstdint>
+        struct Point {
+            double x;
+            double y;
+        };
+        inline std::vector<double> give_samples(uint32_t n) {
+            std::vector<double> samples;
+            for (uint32_t i = 0; i < n; i++) {
+                samples.push_back(i * 0.5);
+            }
+            return samples;
+        }
+        inline std::vector<Point> give_points() {
+            return std::vector<Point> { {1.0, 2.0}, {3.0, 4.0} };
+        }
+        inline double sum_samples(const std::vector<double>& samples) {
+            double total = 0;
+            for (double s : samples) {
+                total += s;
+            }
+            return total;
+        }
+    "};
+    let rs = quote! {
+        let mut samples = ffi::give_samples(5);
+        let view: &[f64] = samples.as_ref().unwrap().as_slice();
+        assert_eq!(view.iter().sum::<f64>(), 5.0);
+        for s in samples.pin_mut().as_mut_slice() {
+            *s *= 2.0;
+        }
+        assert_eq!(ffi::sum_samples(&samples), 10.0);
+        assert!(ffi::give_samples(0).as_ref().unwrap().as_slice().is_empty());
+        let points = ffi::give_points();
+        let ys: f64 = points.as_ref().unwrap().as_slice().iter().map(|p| p.y).sum();
+        assert_eq!(ys, 6.0);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["give_samples", "give_points", "sum_samples"],
+        &["Point"],
+    );
+}
+
+#[test]
+fn test_vector_into_iter() {
+    let hdr = indoc! {"
+        #include <vector>
+        #include <cstdint>
+        struct Point {
+            int32_t x;
+            int32_t y;
+        };
+        inline std::vector<int32_t> give_ints() {
+            return std::vector<int32_t> { 1, 2, 3, 4 };
+        }
+        inline std::vector<Point> give_points() {
+            return std::vector<Point> { {1, 2}, {3, 4}, {5, 6} };
+        }
+    "};
+    let rs = quote! {
+        let ints: Vec<i32> = ffi::give_ints().into_iter().collect();
+        assert_eq!(ints, vec![1, 2, 3, 4]);
+        let mut ints = ffi::give_ints().into_iter();
+        assert_eq!(ints.len(), 4);
+        assert_eq!(ints.next(), Some(1));
+        assert_eq!(ints.next_back(), Some(4));
+        assert_eq!(ints.len(), 2);
+        // The rest are destroyed along with the vector.
+        drop(ints);
+        let ys: Vec<i32> = ffi::give_points().into_iter().map(|p| p.y).collect();
+        assert_eq!(ys, vec![2, 4, 6]);
+    };
+    run_test("", hdr, rs, &["give_ints", "give_points"], &["Point"]);
+}
+
 #[test]
 #[ignore] // https://github.com/google/autocxx/issues/422
 fn test_int_vector() {
@@ -5690,6 +9380,68 @@ fn test_generate_all() {
     );
 }
 
+#[test]
+fn test_generate_if_pointer_width() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t wide() {
+            return 64;
+        }
+        inline uint32_t narrow() {
+            return 32;
+        }
+    "};
+    let rs = quote! {
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(ffi::wide(), 64);
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(ffi::narrow(), 32);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_if!(target_pointer_width = "64", "wide")
+            generate_if!(target_pointer_width = "32", "narrow")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_generate_if_pointer_width_mismatch() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t wide() {
+            return 64;
+        }
+        inline uint32_t narrow() {
+            return 32;
+        }
+    "};
+    let rs = quote! {
+        #[cfg(target_pointer_width = "64")]
+        ffi::narrow();
+        #[cfg(not(target_pointer_width = "64"))]
+        ffi::wide();
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_if!(target_pointer_width = "64", "wide")
+            generate_if!(target_pointer_width = "32", "narrow")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
 #[test]
 fn test_std_thing() {
     let hdr = indoc! {"
@@ -5766,6 +9518,83 @@ fn test_two_mods() {
     do_run_test_manual("", hdr, rs, None, None).unwrap();
 }
 
+#[test]
+fn test_two_mods_reusing_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        namespace common {
+        struct Thing {
+            uint32_t value;
+        };
+        }
+        inline std::unique_ptr<common::Thing> make_thing(uint32_t value) {
+            return std::make_unique<common::Thing>(common::Thing { value });
+        }
+        inline uint32_t get_value(const common::Thing& thing) {
+            return thing.value;
+        }
+        inline void set_value(common::Thing& thing, uint32_t value) {
+            thing.value = value;
+        }
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        autocxx::include_cpp! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            generate!("common::Thing")
+            generate!("make_thing")
+        }
+        autocxx::include_cpp! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            name!(ffi2)
+            reuse_from!(crate::ffi, "common::Thing")
+            generate!("get_value")
+            generate!("set_value")
+        }
+        fn main() {
+            let mut thing = ffi::make_thing(42);
+            ffi2::set_value(thing.pin_mut(), 3);
+            let thing_again: &ffi2::common::Thing = &thing;
+            assert_eq!(ffi2::get_value(thing_again), 3);
+        }
+    };
+    do_run_test_manual("", hdr, rs, None, None).unwrap();
+}
+
+#[test]
+fn test_two_named_mods_wrapping_same_function() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        inline std::string describe(uint32_t n) {
+            return std::string(n, 'x');
+        }
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        autocxx::include_cpp! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            name!(graphics_ffi)
+            generate!("describe")
+        }
+        autocxx::include_cpp! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            name!(audio_ffi)
+            generate!("describe")
+        }
+        fn main() {
+            assert_eq!(graphics_ffi::describe(2).to_str().unwrap(), "xx");
+            assert_eq!(audio_ffi::describe(3).to_str().unwrap(), "xxx");
+        }
+    };
+    do_run_test_manual("", hdr, rs, None, None).unwrap();
+}
+
 #[test]
 fn test_manual_bridge() {
     let hdr = indoc! {"
@@ -5815,28 +9644,156 @@ fn test_manual_bridge_mixed_types() {
             return a;
         }
     "};
-    let hexathorpe = Token![#](Span::call_site());
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+            autocxx::include_cpp! {
+                #hexathorpe include "input.h"
+                safety!(unsafe_ffi)
+                generate!("take_A")
+                generate!("A")
+            }
+            #[cxx::bridge]
+            mod ffi2 {
+                unsafe extern "C++" {
+                    include!("input.h");
+                    type A = crate::ffi::A;
+                    fn give_A() -> UniquePtr<A>;
+                }
+            }
+            fn main() {
+                let a = ffi2::give_A();
+                assert_eq!(ffi::take_A(&a), autocxx::c_int(5));
+            }
+    };
+    do_run_test_manual("", hdr, rs, None, None).unwrap();
+}
+
+#[test]
+fn test_extern_cxx_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        class Counter {
+        public:
+            uint32_t count = 0;
+        };
+        inline std::unique_ptr<Counter> make_counter() {
+            return std::make_unique<Counter>();
+        }
+        inline void increment(Counter& counter) {
+            counter.count++;
+        }
+        inline uint32_t counter_value(const Counter& counter) {
+            return counter.count;
+        }
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let rs = quote! {
+        #[cxx::bridge]
+        mod bridge {
+            unsafe extern "C++" {
+                include!("input.h");
+                type Counter;
+                fn make_counter() -> UniquePtr<Counter>;
+            }
+        }
+        autocxx::include_cpp! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            extern_cxx_type!("Counter", crate::bridge::Counter)
+            generate!("increment")
+            generate!("counter_value")
+        }
+        fn main() {
+            let mut counter = bridge::make_counter();
+            ffi::increment(counter.pin_mut());
+            ffi::increment(counter.pin_mut());
+            let counter_again: &bridge::Counter = &counter;
+            assert_eq!(ffi::counter_value(counter_again), 2);
+        }
+    };
+    do_run_test_manual("", hdr, rs, None, None).unwrap();
+}
+
+#[test]
+fn test_type_conversion() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class MyColor {
+        public:
+            MyColor(uint8_t r, uint8_t g, uint8_t b) : r(r), g(g), b(b) {}
+            uint8_t red() const { return r; }
+            uint8_t green() const { return g; }
+            uint8_t blue() const { return b; }
+        private:
+            uint8_t r, g, b;
+        };
+        inline MyColor brighten(MyColor color, uint8_t by) {
+            return MyColor(color.red() + by, color.green() + by, color.blue() + by);
+        }
+        inline uint32_t pack(const MyColor& color) {
+            return (color.red() << 16) | (color.green() << 8) | color.blue();
+        }
+        class Palette {
+        public:
+            Palette() : background(0, 0, 0) {}
+            const MyColor& get_background() const { return background; }
+            void set_background(const MyColor& color) { background = color; }
+            MyColor& background_mut() { return background; }
+        private:
+            MyColor background;
+        };
+    "};
     let rs = quote! {
-            autocxx::include_cpp! {
-                #hexathorpe include "input.h"
-                safety!(unsafe_ffi)
-                generate!("take_A")
-                generate!("A")
-            }
-            #[cxx::bridge]
-            mod ffi2 {
-                unsafe extern "C++" {
-                    include!("input.h");
-                    type A = crate::ffi::A;
-                    fn give_A() -> UniquePtr<A>;
+        let brighter = ffi::brighten(rgb::Color { r: 1, g: 2, b: 3 }, 10u8);
+        assert_eq!(brighter, rgb::Color { r: 11, g: 12, b: 13 });
+        assert_eq!(ffi::pack(&rgb::Color { r: 1, g: 2, b: 3 }), 0x010203);
+        let mut palette = ffi::Palette::make_unique();
+        palette.pin_mut().set_background(&brighter);
+        assert_eq!(palette.get_background(), brighter);
+        // Changes through a mutable reference have to be made to the C++
+        // type.
+        let background: std::pin::Pin<&mut ffi::MyColor> = palette.pin_mut().background_mut();
+        assert_eq!(background.red(), 11);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["MyColor", "Palette", "brighten", "pack"],
+            &[],
+            Some(quote! {
+                type_conversion!("MyColor", crate::rgb::Color)
+            }),
+        ),
+        None,
+        None,
+        Some(quote! {
+            mod rgb {
+                #[derive(Debug, PartialEq, Clone, Copy)]
+                pub struct Color {
+                    pub r: u8,
+                    pub g: u8,
+                    pub b: u8,
+                }
+
+                impl autocxx::CppConversion<crate::ffi::MyColor> for Color {
+                    fn from_cpp(cpp: &crate::ffi::MyColor) -> Self {
+                        Color {
+                            r: cpp.red(),
+                            g: cpp.green(),
+                            b: cpp.blue(),
+                        }
+                    }
+
+                    fn to_cpp(&self) -> cxx::UniquePtr<crate::ffi::MyColor> {
+                        crate::ffi::MyColor::make_unique(self.r, self.g, self.b)
+                    }
                 }
             }
-            fn main() {
-                let a = ffi2::give_A();
-                assert_eq!(ffi::take_A(&a), autocxx::c_int(5));
-            }
-    };
-    do_run_test_manual("", hdr, rs, None, None).unwrap();
+        }),
+    );
 }
 
 #[test]
@@ -5908,6 +9865,31 @@ fn test_shared_ptr() {
     );
 }
 
+#[test]
+fn test_alias_template() {
+    let hdr = indoc! {"
+        #include <memory>
+        #include <cstdint>
+        struct Thing {
+            uint32_t a;
+        };
+        template <typename T> using Ptr = std::shared_ptr<T>;
+        template <typename T> using OtherPtr = Ptr<T>;
+        inline Ptr<Thing> make_thing() {
+            return std::make_shared<Thing>(Thing { 3 });
+        }
+        inline uint32_t take_thing(OtherPtr<Thing> t) {
+            return t->a;
+        }
+    "};
+    let rs = quote! {
+        let t: ffi::Ptr<ffi::Thing> = ffi::make_thing();
+        let t: cxx::SharedPtr<ffi::Thing> = t;
+        assert_eq!(ffi::take_thing(t), 3);
+    };
+    run_test("", hdr, rs, &["make_thing", "take_thing"], &["Thing"]);
+}
+
 #[test]
 #[ignore] // https://github.com/google/autocxx/issues/799
 fn test_shared_ptr_const() {
@@ -6350,6 +10332,63 @@ fn test_pv_subclass_not_pub() {
     );
 }
 
+#[test]
+fn test_subclass_final() {
+    let hdr = indoc! {"
+    #include <cstdint>
+
+    class Observer final {
+    public:
+        Observer() {}
+        virtual void foo() const {}
+        virtual ~Observer() {}
+    };
+    inline void bar() {}
+    "};
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        quote! {
+            MyObserver::new_rust_owned(MyObserver { a: 3, cpp_peer: Default::default() });
+        },
+        quote! {
+            generate!("bar")
+            subclass!("Observer",MyObserver)
+        },
+        None,
+        None,
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Observer_methods;
+            #[autocxx::subclass::subclass]
+            pub struct MyObserver {
+                a: u32
+            }
+            impl Observer_methods for MyObserver {
+            }
+        }),
+    );
+}
+
+#[test]
+fn test_final_class() {
+    let hdr = indoc! {"
+    #include <cstdint>
+
+    class Observer final {
+    public:
+        Observer() {}
+        virtual uint32_t foo() const { return 3; }
+        virtual ~Observer() {}
+    };
+    "};
+    let rs = quote! {
+        let obs = ffi::Observer::make_unique();
+        assert_eq!(obs.foo(), 3);
+    };
+    run_test("", hdr, rs, &["Observer"], &[]);
+}
+
 #[test]
 fn test_pv_subclass_ptr_param() {
     let hdr = indoc! {"
@@ -6477,6 +10516,62 @@ fn test_pv_subclass_passed_to_fn() {
     );
 }
 
+#[test]
+fn test_pv_subclass_panics_as_exceptions() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #include <exception>
+    #include <string>
+
+    class Observer {
+    public:
+        Observer() {}
+        virtual uint32_t foo(uint32_t a) const = 0;
+        virtual ~Observer() {}
+    };
+    inline std::string call_foo(const Observer& obs, uint32_t a) {
+        try {
+            return std::to_string(obs.foo(a));
+        } catch (const std::exception& e) {
+            return std::string(\"caught: \") + e.what();
+        }
+    }
+    "};
+    run_test_ex(
+        "",
+        hdr,
+        quote! {
+            let o = MyObserver::new_rust_owned(MyObserver { cpp_peer: Default::default() });
+            assert_eq!(ffi::call_foo(o.borrow().as_ref(), 4).to_str().unwrap(), "5");
+            assert_eq!(
+                ffi::call_foo(o.borrow().as_ref(), 0).to_str().unwrap(),
+                "caught: zero is not allowed"
+            );
+        },
+        quote! {
+            generate!("call_foo")
+            subclass!("Observer",MyObserver)
+            safety!(unsafe_ffi, panics_as_exceptions)
+        },
+        None,
+        None,
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Observer_methods;
+            #[autocxx::subclass::subclass]
+            pub struct MyObserver;
+            impl Observer_methods for MyObserver {
+                fn foo(&self, a: u32) -> u32 {
+                    if a == 0 {
+                        panic!("zero is not allowed");
+                    }
+                    a + 1
+                }
+            }
+        }),
+    );
+}
+
 #[test]
 fn test_pv_subclass_derive_defaults() {
     let hdr = indoc! {"
@@ -6750,6 +10845,65 @@ fn test_pv_protected_method() {
     );
 }
 
+#[test]
+fn test_pv_protected_nonvirtual_method() {
+    let hdr = indoc! {"
+    #include <cstdint>
+
+    class Observer {
+    public:
+        Observer() : total(0) {}
+        virtual uint32_t foo(uint32_t a) { return a; }
+        uint32_t get_total() const { return total; }
+        virtual ~Observer() {}
+    protected:
+        uint32_t double_it(uint32_t a) const { return a * 2; }
+        void add(uint32_t a) { total += a; }
+    private:
+        uint32_t total;
+    };
+
+    extern Observer* obs;
+
+    inline void register_observer(Observer& a) {
+        obs = &a;
+    }
+    inline uint32_t call_foo(uint32_t a) {
+        return obs->foo(a);
+    }
+    "};
+    run_test_ex(
+        "Observer* obs;",
+        hdr,
+        quote! {
+            let obs = MyObserver::new_rust_owned(MyObserver { cpp_peer: Default::default() });
+            ffi::register_observer(obs.as_ref().borrow_mut().pin_mut());
+            assert_eq!(ffi::call_foo(3), 6);
+            assert_eq!(obs.borrow().as_ref().get_total(), 6);
+        },
+        quote! {
+            generate!("register_observer")
+            generate!("call_foo")
+            subclass!("Observer",MyObserver)
+        },
+        None,
+        None,
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Observer_methods;
+            #[autocxx::subclass::subclass]
+            pub struct MyObserver;
+            impl Observer_methods for MyObserver {
+                fn foo(&mut self, a: u32) -> u32 {
+                    let doubled = self.peer().double_it(a);
+                    self.peer_mut().add(doubled);
+                    doubled
+                }
+            }
+        }),
+    );
+}
+
 #[test]
 fn test_pv_subclass_allocation_not_self_owned() {
     let hdr = indoc! {"
@@ -7249,6 +11403,86 @@ fn test_pv_subclass_calls() {
     );
 }
 
+#[test]
+fn test_value_param_setter_moves() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        #include <utility>
+        struct Tracked {
+            Tracked() {}
+            Tracked(const Tracked&) { ++copies(); }
+            Tracked(Tracked&&) = default;
+            Tracked& operator=(const Tracked&) { ++copies(); return *this; }
+            Tracked& operator=(Tracked&&) = default;
+            static uint32_t& copies() { static uint32_t count = 0; return count; }
+        };
+        inline uint32_t copies_made() { return Tracked::copies(); }
+        class Holder {
+        public:
+            void set_name(std::string s) { name = std::move(s); }
+            const std::string& get_name() const { return name; }
+            void set_tracked(Tracked t) { tracked = std::move(t); }
+        private:
+            std::string name;
+            Tracked tracked;
+        };
+        class Receiver {
+        public:
+            virtual void receive(Tracked t) { kept = std::move(t); }
+            virtual ~Receiver() {}
+        private:
+            Tracked kept;
+        };
+        inline void deliver(Receiver& receiver) { receiver.receive(Tracked()); }
+    "};
+    let rs = quote! {
+        let mut holder = ffi::Holder::make_unique();
+        let name = "a name too long for the small string buffer".to_string();
+        holder.pin_mut().set_name(name);
+        assert_eq!(
+            holder.get_name().to_str().unwrap(),
+            "a name too long for the small string buffer"
+        );
+        let name = ffi::make_string("another name too long for the small string buffer");
+        let buffer = name.as_bytes().as_ptr();
+        holder.pin_mut().set_name(name);
+        assert_eq!(holder.get_name().as_bytes().as_ptr(), buffer);
+        holder.pin_mut().set_tracked(ffi::Tracked::make_unique());
+        assert_eq!(ffi::copies_made(), 0);
+
+        let receiver = MyReceiver::new_rust_owned(MyReceiver::default());
+        ffi::deliver(receiver.as_ref().borrow_mut().pin_mut());
+        assert_eq!(ffi::copies_made(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Holder")
+            generate!("Tracked")
+            generate!("copies_made")
+            generate!("deliver")
+            subclass!("Receiver", MyReceiver)
+        },
+        None,
+        None,
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Receiver_methods;
+            #[autocxx::subclass::subclass]
+            #[derive(Default)]
+            pub struct MyReceiver {}
+            impl Receiver_methods for MyReceiver {
+                fn receive(&mut self, t: cxx::UniquePtr<ffi::Tracked>) {
+                    self.peer_mut().receive_super(t)
+                }
+            }
+        }),
+    );
+}
+
 #[test]
 fn test_pv_subclass_types() {
     let hdr = indoc! {"
@@ -7639,6 +11873,44 @@ fn test_constructor_moveit() {
     run_test("", hdr, rs, &["A"], &[]);
 }
 
+#[test]
+fn test_constructor_taking_other_type_by_reference() {
+    let hdr = indoc! {"
+    #include <stdint.h>
+    #include <string>
+    struct Bob {
+        Bob(uint32_t val) : a(val) {}
+        uint32_t a;
+        std::string so_we_are_non_trivial;
+    };
+    struct A {
+        A(const Bob& bob, uint32_t extra) : a(bob.a + extra) {}
+        A(Bob& bob) : a(bob.a) { bob.a = 0; }
+        uint32_t get() const { return a; }
+        uint32_t a;
+        std::string so_we_are_non_trivial;
+    };
+    "};
+    let rs = quote! {
+        let mut bob = ffi::Bob::make_unique(12);
+        let up_obj = ffi::A::make_unique(&bob, 1);
+        assert_eq!(up_obj.get(), 13);
+        moveit! {
+            let stack_obj = ffi::A::new(&bob, 2);
+        }
+        assert_eq!(stack_obj.get(), 14);
+        moveit! {
+            let copied = autocxx::moveit::new::copy(stack_obj.as_ref());
+        }
+        assert_eq!(copied.get(), 14);
+        moveit! {
+            let taken = ffi::A::new1(bob.pin_mut());
+        }
+        assert_eq!(taken.get(), 12);
+    };
+    run_test("", hdr, rs, &["A", "Bob"], &[]);
+}
+
 #[test]
 fn test_implicit_constructor_moveit() {
     let hdr = indoc! {"
@@ -7684,15 +11956,83 @@ fn test_destructor_moveit() {
         assert!(!ffi::is_constructed());
         {
             moveit! {
-                let mut stack_obj = ffi::A::new();
+                let mut stack_obj = ffi::A::new();
+            }
+            assert!(ffi::is_constructed());
+            stack_obj.as_mut().set(42);
+            assert_eq!(stack_obj.get(), 42);
+        }
+        assert!(!ffi::is_constructed());
+    };
+    run_test(cpp, hdr, rs, &["A", "is_constructed"], &[]);
+}
+
+#[test]
+fn test_throwing_move_constructor() {
+    let hdr = indoc! {"
+        #include <stdexcept>
+        #include <string>
+        inline int& live_count() { static int count = 0; return count; }
+        inline int live_objects() { return live_count(); }
+        struct Fragile {
+            Fragile(bool fail) : fail_on_move(fail) { live_count()++; }
+            Fragile(Fragile&& other) : fail_on_move(other.fail_on_move) {
+                if (fail_on_move) {
+                    throw std::runtime_error(\"move failed\");
+                }
+                live_count()++;
+            }
+            ~Fragile() { live_count()--; }
+            bool fail_on_move;
+            std::string so_we_are_non_trivial;
+        };
+        template <typename T> struct Holder {
+            Holder() {}
+            Holder(Holder&& other) noexcept(noexcept(T(std::move(other.value)))) : value(std::move(other.value)) {}
+            T value;
+        };
+        struct Sturdy {
+            Sturdy() { live_count()++; }
+            Sturdy(Sturdy&& other) noexcept(sizeof(int) >= 2) : held(std::move(other.held)) { live_count()++; }
+            ~Sturdy() { live_count()--; }
+            Holder<std::string> held;
+        };
+    "};
+    let rs = quote! {
+        let result = std::panic::catch_unwind(|| {
+            moveit! {
+                let a = ffi::Fragile::new(true);
+            }
+            moveit! {
+                let _b = autocxx::moveit::new::mov(a);
+            }
+        });
+        assert_eq!(
+            *result.unwrap_err().downcast::<String>().unwrap(),
+            "move failed"
+        );
+        assert_eq!(ffi::live_objects(), c_int(0));
+        {
+            moveit! {
+                let a = ffi::Fragile::new(false);
             }
-            assert!(ffi::is_constructed());
-            stack_obj.as_mut().set(42);
-            assert_eq!(stack_obj.get(), 42);
+            moveit! {
+                let _b = autocxx::moveit::new::mov(a);
+            }
+            assert_eq!(ffi::live_objects(), c_int(1));
         }
-        assert!(!ffi::is_constructed());
+        {
+            moveit! {
+                let a = ffi::Sturdy::new();
+            }
+            moveit! {
+                let _b = autocxx::moveit::new::mov(a);
+            }
+            assert_eq!(ffi::live_objects(), c_int(1));
+        }
+        assert_eq!(ffi::live_objects(), c_int(0));
     };
-    run_test(cpp, hdr, rs, &["A", "is_constructed"], &[]);
+    run_test("", hdr, rs, &["Fragile", "Sturdy", "live_objects"], &[]);
 }
 
 #[test]
@@ -7785,6 +12125,55 @@ fn test_various_emplacement() {
     run_test("", hdr, rs, &["A"], &[]);
 }
 
+#[test]
+fn test_cpp_object_inline_in_rust_struct() {
+    let hdr = indoc! {"
+    #include <stdint.h>
+    #include <string>
+    struct A {
+        A() : home(this) {}
+        void increment() { a++; }
+        uint32_t get() const { return a; }
+        bool is_at_home() const { return home == this; }
+        uint32_t a = 0;
+        const A* home;
+        std::string so_we_are_non_trivial;
+    };
+    "};
+    let rs = quote! {
+        use autocxx::moveit::Emplace;
+        moveit! { let mut holder = Holder::new("hello".to_string(), ffi::A::new()); }
+        let mut fields = holder.as_mut().project();
+        fields.a.as_mut().increment();
+        fields.a.as_mut().increment();
+        fields.label.push('!');
+        assert_eq!(holder.a.get(), 2);
+        assert!(holder.a.is_at_home());
+        assert_eq!(holder.label, "hello!");
+        let mut boxed = Box::emplace(Holder::new("boxed".to_string(), ffi::A::new()));
+        boxed.as_mut().project().a.increment();
+        assert_eq!(boxed.a.get(), 1);
+        assert!(boxed.a.is_at_home());
+    };
+    let extra_rust = quote! {
+        #[autocxx::cpp_fields]
+        struct Holder {
+            label: String,
+            #[autocxx_field]
+            a: ffi::A,
+        }
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["A"], &[], None),
+        None,
+        None,
+        Some(extra_rust),
+    );
+}
+
 #[test]
 fn test_emplace_uses_overridden_new_and_delete() {
     let hdr = indoc! {"
@@ -7849,6 +12238,74 @@ fn test_emplace_uses_overridden_new_and_delete() {
     );
 }
 
+#[test]
+fn test_sized_delete_and_placement_overload() {
+    let hdr = indoc! {"
+    #include <stdint.h>
+    #include <stddef.h>
+    #include <string>
+    struct A {
+        A() {}
+        void* operator new(size_t count);
+        // This hides the global placement new.
+        void* operator new(size_t count, const char* tag);
+        void operator delete(void* ptr, size_t size) noexcept;
+        std::string so_we_are_non_trivial;
+    };
+    void reset_counts();
+    uint32_t get_new_count();
+    uint32_t get_delete_count();
+    "};
+    let cxx = indoc! {"
+        uint32_t new_count;
+        uint32_t delete_count;
+        void reset_counts() {
+            new_count = 0;
+            delete_count = 0;
+        }
+        void* A::operator new(size_t count) {
+            new_count++;
+            return ::operator new(count);
+        }
+        void* A::operator new(size_t count, const char*) {
+            return A::operator new(count);
+        }
+        void A::operator delete(void* ptr, size_t size) noexcept {
+            if (size == sizeof(A)) {
+                delete_count++;
+            }
+            ::operator delete(ptr);
+        }
+        uint32_t get_new_count() {
+            return new_count;
+        }
+        uint32_t get_delete_count() {
+            return delete_count;
+        }
+    "};
+    let rs = quote! {
+        ffi::reset_counts();
+        {
+            let _ = ffi::A::make_unique();
+        }
+        assert_eq!(ffi::get_new_count(), 1);
+        assert_eq!(ffi::get_delete_count(), 1);
+        {
+            use autocxx::moveit::EmplaceUnpinned;
+            let _ = cxx::UniquePtr::emplace(ffi::A::new());
+        }
+        assert_eq!(ffi::get_new_count(), 2);
+        assert_eq!(ffi::get_delete_count(), 2);
+    };
+    run_test(
+        cxx,
+        hdr,
+        rs,
+        &["A", "reset_counts", "get_new_count", "get_delete_count"],
+        &[],
+    );
+}
+
 #[test]
 fn test_explicit_everything() {
     let hdr = indoc! {"
@@ -8298,8 +12755,358 @@ fn test_skip_cxx_gen() {
     );
 }
 
+#[test]
+fn test_define_enables_ifdef() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #ifdef FEATURE_X
+        inline uint32_t gated() { return FEATURE_X + 2; }
+        #endif
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::gated(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["gated"], &[], None),
+        Some(Box::new(DefineSymbol("FEATURE_X", Some("1")))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_ifdef_without_define() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #ifdef FEATURE_X
+        inline uint32_t gated() { return 3; }
+        #endif
+    "};
+    let rs = quote! {
+        ffi::gated();
+    };
+    run_test_expect_fail("", hdr, rs, &["gated"], &[]);
+}
+
+#[test]
+fn test_abi_checks() {
+    let hdr = indoc! {"
+        #include <string>
+        inline std::string get_str() { return \"hello\"; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_str().as_ref().unwrap().to_str().unwrap(), "hello");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["get_str"], &[], None),
+        None,
+        Some(Box::new(CppMatcher::new(
+            &[
+                "static_assert(sizeof(bool) == 1",
+                "static_assert(static_cast<unsigned char>(-1) == 255",
+                "static_assert(static_cast<char>(-1)",
+            ],
+            &[],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_abi_checks_without_wrappers() {
+    // Nothing here needs a C++ wrapper, but the checks are still generated.
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::add(2, 3), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["add"], &[], None),
+        None,
+        Some(Box::new(CppMatcher::new(
+            &["static_assert(sizeof(bool) == 1"],
+            &["autocxx_wrapper"],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_abi_checks_char_signedness_mismatch() {
+    let hdr = indoc! {"
+        #include <string>
+        inline std::string get_str() { return \"hello\"; }
+    "};
+    let rs = quote! {
+        ffi::get_str();
+    };
+    // Compile the C++ with the opposite signedness to Rust's c_char.
+    let flag = if std::os::raw::c_char::MIN == 0 {
+        "-fsigned-char"
+    } else {
+        "-funsigned-char"
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["get_str"], &[], None),
+        make_clang_arg_adder(&[flag]),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_header_source() {
+    // The in-memory header itself includes one found on the include path.
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t base_value() { return 2; }
+    "};
+    let synthesized = indoc! {"
+        #include \"input.h\"
+        inline uint32_t synthesized_value() { return base_value() * 3; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::synthesized_value(), 6);
+    };
+    let hexathorpe = Token![#](Span::call_site());
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            #hexathorpe include "synthesized/values.h"
+            generate!("synthesized_value")
+        },
+        Some(Box::new(HeaderSource("synthesized/values.h", synthesized))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_compile_commands() {
+    // The header is only found, and only works, using the include path
+    // and define from the compilation database. The database's paths
+    // are relative, and it has several entries for the file we want,
+    // only some of which agree.
+    let proj = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(proj.path().join("include")).unwrap();
+    std::fs::create_dir_all(proj.path().join("build")).unwrap();
+    std::fs::write(
+        proj.path().join("include/extra.h"),
+        indoc! {"
+            #include <cstdint>
+            inline uint32_t extra_value() { return EXTRA_VALUE; }
+        "},
+    )
+    .unwrap();
+    let db_path = proj.path().join("build/compile_commands.json");
+    std::fs::write(
+        &db_path,
+        indoc! {r#"
+            [
+                {
+                    "directory": "..",
+                    "command": "c++ -Iinclude -DEXTRA_VALUE=42 -c src/main.cc -o main.o",
+                    "file": "src/main.cc"
+                },
+                {
+                    "directory": "..",
+                    "arguments": ["c++", "-I", "include", "-DEXTRA_VALUE=42", "-O2", "-c", "src/main.cc"],
+                    "file": "src/main.cc"
+                },
+                {
+                    "directory": "..",
+                    "command": "c++ -DEXTRA_VALUE=7 -c src/other.cc",
+                    "file": "src/other.cc"
+                }
+            ]
+        "#},
+    )
+    .unwrap();
+    let hdr = indoc! {"
+        #include \"extra.h\"
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::extra_value(), 42);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["extra_value"], &[], None),
+        Some(Box::new(UseCompileCommands(db_path, "src/main.cc"))),
+        None,
+        None,
+    );
+}
+
+/// Builds bindings with `trace_calls` on, makes some calls, and checks that
+/// exactly `expected` was logged. Whether anything is logged depends on the
+/// `trace_calls` feature of autocxx, which this crate's own `trace_calls`
+/// feature turns on.
+fn run_trace_calls_test(expected: proc_macro2::TokenStream) {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+        class Adder {
+        public:
+            Adder() : total(0) {}
+            void add(uint32_t a) { total += a; }
+            uint32_t get() const { return total; }
+        private:
+            uint32_t total;
+        };
+    "};
+    let rs = quote! {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        assert_eq!(ffi::add(2, 3), 5);
+        let mut adder = ffi::Adder::make_unique();
+        adder.pin_mut().add(4);
+        assert_eq!(adder.get(), 4);
+        let expected: &[&str] = &#expected;
+        assert_eq!(*CAPTURE.0.lock().unwrap(), expected);
+    };
+    let extra_rust = quote! {
+        struct Capture(std::sync::Mutex<Vec<String>>);
+        static CAPTURE: Capture = Capture(std::sync::Mutex::new(Vec::new()));
+        impl log::Log for Capture {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == "autocxx::ffi"
+            }
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.args().to_string());
+                }
+            }
+            fn flush(&self) {}
+        }
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["add", "Adder"], &[], None),
+        Some(Box::new(EnableTraceCalls)),
+        None,
+        Some(extra_rust),
+    );
+}
+
+#[test]
+#[cfg(feature = "trace_calls")]
+fn test_trace_calls() {
+    run_trace_calls_test(quote! {
+        [
+            "-> add",
+            "<- add",
+            "-> Adder::make_unique",
+            "<- Adder::make_unique",
+            "-> Adder::add",
+            "<- Adder::add",
+            "-> Adder::get",
+            "<- Adder::get",
+        ]
+    });
+}
+
+#[test]
+#[cfg(not(feature = "trace_calls"))]
+fn test_trace_calls_without_feature() {
+    run_trace_calls_test(quote! { [] });
+}
+
+#[test]
+fn test_rename_methods() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Client {
+        public:
+            Client() : value(0) {}
+            void SetValue(uint32_t v) { value = v; }
+            uint32_t GetValue() const { return value; }
+            uint32_t GetHTTPStatus() const { return 200; }
+            static uint32_t DefaultValue() { return 3; }
+        private:
+            uint32_t value;
+        };
+    "};
+    let rs = quote! {
+        let mut client = ffi::Client::make_unique();
+        client.pin_mut().set_value(4);
+        assert_eq!(client.get_value(), 4);
+        assert_eq!(client.get_http_status(), 200);
+        assert_eq!(ffi::Client::default_value(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Client"], &[], None),
+        Some(Box::new(RenameMethodsToSnakeCase)),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_properties() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Widget {
+        public:
+            Widget() : value(0), enabled(false) {}
+            uint32_t getValue() const { return value; }
+            void setValue(uint32_t v) { value = v; }
+            uint32_t getId() const { return 7; }
+            bool isEnabled() const { return enabled; }
+            void setEnabled(bool e) { enabled = e; }
+            bool hasValue() const { return value != 0; }
+            void reset() { value = 0; }
+        private:
+            uint32_t value;
+            bool enabled;
+        };
+    "};
+    let rs = quote! {
+        let mut widget = ffi::Widget::make_unique();
+        assert!(!widget.has_value());
+        widget.pin_mut().set_value(4);
+        assert_eq!(widget.value(), 4);
+        assert!(widget.has_value());
+        assert_eq!(widget.id(), 7);
+        widget.pin_mut().set_enabled(true);
+        assert!(widget.is_enabled());
+        widget.pin_mut().reset();
+        assert_eq!(widget.value(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Widget"], &[], Some(quote! { properties!("Widget") })),
+        None,
+        None,
+        None,
+    );
+}
+
 // Yet to test:
-// - Ifdef
 // - Out param pointers
 // - ExcludeUtilities
 // - Struct fields which are typedefs
@@ -85,6 +85,143 @@ pub fn subclass(attr: TokenStream, item: TokenStream) -> TokenStream {
     toks.into()
 }
 
+/// Attribute to allow C++ types to be stored inline as fields of a Rust
+/// `struct`. See documentation for `autocxx` crate.
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn cpp_fields(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        abort!(Span::call_site(), "Expected no attributes");
+    }
+    let mut s: ItemStruct =
+        syn::parse(item).unwrap_or_else(|_| abort!(Span::call_site(), "Expected a struct"));
+    if !s.generics.params.is_empty() {
+        use syn::spanned::Spanned;
+        abort!(
+            s.generics.span(),
+            "Structs containing C++ fields may not be generic"
+        );
+    }
+    let fields = match &mut s.fields {
+        Fields::Named(fields) => &mut fields.named,
+        _ => abort!(
+            Span::call_site(),
+            "Expect a struct with named fields - use struct A{} as opposed to struct A()"
+        ),
+    };
+    // Work out which fields are C++ objects, which must be constructed
+    // in place and only ever be accessed through a Pin.
+    let mut cpp_field_flags = Vec::new();
+    for field in fields.iter_mut() {
+        let before = field.attrs.len();
+        field.attrs.retain(|a| !a.path.is_ident("autocxx_field"));
+        cpp_field_flags.push(field.attrs.len() != before);
+    }
+    let id = &s.ident;
+    let vis = &s.vis;
+    let projection_id = Ident::new(&format!("{}Projection", id), Span::call_site());
+    let mut projection_fields = Vec::new();
+    let mut projection_inits = Vec::new();
+    let mut new_params = Vec::new();
+    let mut rust_field_writes = Vec::new();
+    let mut cpp_field_writes = Vec::new();
+    let mut pinned_field_types = Vec::new();
+    for (field, is_cpp) in fields.iter().zip(cpp_field_flags) {
+        let name = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let field_vis = &field.vis;
+        if is_cpp {
+            projection_fields.push(quote! {
+                #field_vis #name: ::std::pin::Pin<&'__pin mut #ty>
+            });
+            projection_inits.push(quote! {
+                #name: ::std::pin::Pin::new_unchecked(&mut this.#name)
+            });
+            new_params.push(quote! {
+                #name: impl autocxx::moveit::new::New<Output = #ty>
+            });
+            cpp_field_writes.push(quote! {
+                autocxx::moveit::new::New::new(
+                    #name,
+                    ::std::pin::Pin::new_unchecked(
+                        &mut *(::std::ptr::addr_of_mut!((*this).#name)
+                            as *mut ::std::mem::MaybeUninit<#ty>),
+                    ),
+                );
+            });
+            pinned_field_types.push(quote! { #name: #ty });
+        } else {
+            projection_fields.push(quote! {
+                #field_vis #name: &'__pin mut #ty
+            });
+            projection_inits.push(quote! {
+                #name: &mut this.#name
+            });
+            new_params.push(quote! {
+                #name: #ty
+            });
+            rust_field_writes.push(quote! {
+                ::std::ptr::addr_of_mut!((*this).#name).write(#name);
+            });
+        }
+    }
+    let toks = quote! {
+        #s
+
+        /// Projection of a pinned mutable reference into references to
+        /// each of its fields.
+        #[allow(dead_code)]
+        #vis struct #projection_id<'__pin> {
+            #(#projection_fields),*
+        }
+
+        #[allow(dead_code)]
+        impl #id {
+            /// Returns references to each field. Fields containing C++
+            /// objects remain pinned.
+            #vis fn project(self: ::std::pin::Pin<&mut Self>) -> #projection_id<'_> {
+                unsafe {
+                    let this = self.get_unchecked_mut();
+                    #projection_id {
+                        #(#projection_inits),*
+                    }
+                }
+            }
+
+            /// Constructs this struct in place, with each C++ field
+            /// constructed in its final location.
+            #vis fn new(#(#new_params),*) -> impl autocxx::moveit::new::New<Output = Self> {
+                unsafe {
+                    autocxx::moveit::new::by_raw(move |this: ::std::pin::Pin<&mut ::std::mem::MaybeUninit<Self>>| {
+                        let this = this.get_unchecked_mut().as_mut_ptr();
+                        #(#rust_field_writes)*
+                        #(#cpp_field_writes)*
+                    })
+                }
+            }
+        }
+
+        const _: () = {
+            // This struct is Unpin only if all its C++ fields are. A manual
+            // implementation of Unpin would conflict with this.
+            #[allow(dead_code)]
+            struct __PinnedFields<'__pin> {
+                __lifetime: ::std::marker::PhantomData<&'__pin ()>,
+                #(#pinned_field_types),*
+            }
+            impl<'__pin> ::std::marker::Unpin for #id where __PinnedFields<'__pin>: ::std::marker::Unpin {}
+
+            // A Drop implementation could move the C++ fields out from
+            // under their Pin, so forbid it.
+            trait MustNotImplDrop {}
+            #[allow(drop_bounds)]
+            impl<T: ::std::ops::Drop> MustNotImplDrop for T {}
+            impl MustNotImplDrop for #id {}
+        };
+    };
+    toks.into()
+}
+
 /// Attribute to state that a Rust type is to be exported to C++
 /// in the `extern "Rust"` section of the generated `cxx` bindings.
 #[proc_macro_error]
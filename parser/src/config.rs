@@ -14,11 +14,12 @@
 
 use std::{borrow::Cow, collections::HashSet};
 
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream, TokenTree};
 use quote::ToTokens;
 use syn::{
     parse::{Parse, ParseStream},
-    LitStr, Signature, Token,
+    punctuated::Punctuated,
+    ExprClosure, LitStr, Signature, Token, Visibility,
 };
 use syn::{Ident, Result as ParseResult};
 
@@ -99,6 +100,24 @@ impl Allowlist {
         Ok(())
     }
 
+    /// Commits to generating specific items, without yet naming any, for
+    /// directives which may or may not add an item later.
+    pub(crate) fn commit(&mut self, ident: &Ident) -> ParseResult<()> {
+        match self {
+            Allowlist::Unspecified(ref mut uncommitted_list) => {
+                *self = Allowlist::Specific(std::mem::take(uncommitted_list));
+            }
+            Allowlist::All => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "use either generate!/generate_pod! or generate_all!, not both.",
+                ))
+            }
+            Allowlist::Specific(_) => {}
+        };
+        Ok(())
+    }
+
     pub(crate) fn set_all(&mut self, ident: &Ident) -> ParseResult<()> {
         if matches!(self, Allowlist::Specific(..)) {
             return Err(syn::Error::new(
@@ -138,21 +157,557 @@ impl std::fmt::Debug for RustFun {
     }
 }
 
+/// A check of a function's arguments, given in `precondition!`, made in
+/// debug builds before each call.
+struct Precondition {
+    function: String,
+    predicate: ExprClosure,
+}
+
+impl std::fmt::Debug for Precondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Precondition")
+            .field("function", &self.function)
+            .field("predicate", &self.predicate.to_token_stream().to_string())
+            .finish()
+    }
+}
+
+struct RequestedVisibility {
+    name: String,
+    vis: Visibility,
+}
+
+impl std::fmt::Debug for RequestedVisibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestedVisibility")
+            .field("name", &self.name)
+            .field("vis", &self.vis.to_token_stream().to_string())
+            .finish()
+    }
+}
+
+/// A pointer parameter, and another parameter giving the number of
+/// elements it points to, which should together be a slice in Rust.
+#[derive(Debug)]
+pub struct SliceParam {
+    pub function: String,
+    pub pointer: String,
+    pub length: String,
+}
+
+/// A function pointer parameter, and a `void*` parameter which is passed
+/// back to the function it points to, which should together be a closure
+/// in Rust.
+#[derive(Debug)]
+pub struct ClosureCallback {
+    pub function: String,
+    pub callback: String,
+    pub context: String,
+}
+
+/// A C-style handle type whose values must be released by calling a
+/// particular C++ function.
+#[derive(Debug)]
+pub struct Resource {
+    pub handle: String,
+    pub close: String,
+    /// A C++ expression for a value which represents no resource, and
+    /// which therefore must not be closed.
+    pub invalid: Option<String>,
+}
+
+/// A type with intrusive reference counting, which objects of the type
+/// do themselves by means of two methods.
+#[derive(Debug)]
+pub struct IntrusiveRefcount {
+    pub ty: String,
+    pub add_ref: String,
+    pub release: String,
+}
+
+/// A type returned by coroutines, or anything else which completes in
+/// steps, which Rust should be able to await. Its methods resume it, tell
+/// whether it's done, and return its result once it is.
+#[derive(Debug)]
+pub struct Awaitable {
+    pub ty: String,
+    pub resume: String,
+    pub done: String,
+    pub result: String,
+}
+
+/// A class which overloads `operator->` to act as a smart pointer to
+/// another type, listed in `smart_pointer!`.
+#[derive(Debug)]
+pub struct SmartPointer {
+    pub ty: String,
+    pub target: String,
+    /// Whether the pointee may be modified through a non-const pointer.
+    pub mutable: bool,
+}
+
+/// A type whose objects may or may not hold a value, as a `std::optional`
+/// or a handle may not.
+#[derive(Debug)]
+pub struct Validity {
+    pub ty: String,
+    /// A method which returns whether the object is valid, optionally
+    /// prefixed with `!` for a method which returns whether it's not (such
+    /// as `empty`). If absent, the object's `explicit operator bool` is
+    /// used.
+    pub check: Option<String>,
+}
+
+/// An enum whose enumerators are bit flags, to be combined using bitwise
+/// OR, which should be represented in Rust as a newtype with a builder.
+#[derive(Debug)]
+pub struct FlagsEnum {
+    pub ty: String,
+    /// Groups of enumerators which are mutually exclusive, such that
+    /// setting one clears the others.
+    pub exclusive_groups: Vec<Vec<String>>,
+}
+
+/// A function-like macro which should be bound as a function, with the
+/// C++ signature that function should have.
+#[derive(Debug)]
+pub struct MacroFunction {
+    pub name: String,
+    pub return_type: String,
+    pub param_types: Vec<String>,
+}
+
+/// One of the overloads of a free function, or one instantiation of a
+/// function template, picked by its signature in `select_overload!`.
+#[derive(Debug)]
+pub struct SelectedOverload {
+    pub function: String,
+    pub return_type: String,
+    pub param_types: Vec<String>,
+}
+
+/// An instantiation of a function template with explicit template
+/// arguments, and the Rust name to give it, listed in
+/// `instantiate_fn_named!`.
+#[derive(Debug)]
+pub struct NamedInstantiation {
+    pub function: String,
+    pub template_args: String,
+    pub rust_name: String,
+}
+
+impl NamedInstantiation {
+    /// How C++ names the instantiation, without the function's namespace:
+    /// for `ns::foo` and `<int, 3>`, `foo<int, 3>`.
+    pub fn instantiated_name(&self) -> String {
+        format!(
+            "{}{}",
+            self.function.rsplit("::").next().unwrap(),
+            self.template_args
+        )
+    }
+}
+
+/// A method which a class inherits from a base bindgen can't describe,
+/// such as a CRTP base, with the C++ signature the method has.
+#[derive(Debug)]
+pub struct InheritedMethod {
+    pub class: String,
+    pub method: String,
+    pub return_type: String,
+    pub param_types: Vec<String>,
+    pub is_const: bool,
+}
+
+/// How a C++ container listed in `iterable!` may be iterated over: which
+/// operations its iterators support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IteratorCategory {
+    /// Only `operator++`.
+    Forward,
+    /// `operator--` too.
+    Bidirectional,
+    /// `operator-` between two iterators too, as used to find how many
+    /// elements remain.
+    RandomAccess,
+}
+
+/// How a function listed in `string_return!` turns the bytes of the
+/// `std::string` it returns into a `String`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringReturn {
+    /// Replace invalid UTF-8 with U+FFFD.
+    Lossy,
+    /// Return a `Result`, which is an error if they're not valid UTF-8.
+    Strict,
+}
+
+/// A C++ container which should be iterable from Rust, and the type of its
+/// elements. If it's a set, listed in `cpp_set!`, whether a value is in it
+/// may be asked too.
+#[derive(Debug)]
+pub struct Iterable {
+    pub container: String,
+    pub element: String,
+    pub category: IteratorCategory,
+    pub is_set: bool,
+}
+
+/// A property of the target on which a `generate_if!` directive depends.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TargetCondition {
+    PointerWidth(u32),
+    BigEndian(bool),
+}
+
+impl TargetCondition {
+    /// Whether this holds for a target with this pointer width, in bits,
+    /// and endianness.
+    pub fn holds(&self, pointer_width: u32, big_endian: bool) -> bool {
+        match self {
+            TargetCondition::PointerWidth(width) => *width == pointer_width,
+            TargetCondition::BigEndian(big) => *big == big_endian,
+        }
+    }
+}
+
+impl Parse for TargetCondition {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let key: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value: LitStr = input.parse()?;
+        if key == "target_pointer_width" {
+            match value.value().as_str() {
+                "16" => Ok(TargetCondition::PointerWidth(16)),
+                "32" => Ok(TargetCondition::PointerWidth(32)),
+                "64" => Ok(TargetCondition::PointerWidth(64)),
+                _ => Err(syn::Error::new(
+                    value.span(),
+                    "expected \"16\", \"32\" or \"64\"",
+                )),
+            }
+        } else if key == "target_endian" {
+            match value.value().as_str() {
+                "little" => Ok(TargetCondition::BigEndian(false)),
+                "big" => Ok(TargetCondition::BigEndian(true)),
+                _ => Err(syn::Error::new(
+                    value.span(),
+                    "expected \"little\" or \"big\"",
+                )),
+            }
+        } else {
+            Err(syn::Error::new(
+                key.span(),
+                "expected target_pointer_width or target_endian",
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "reproduction_case")]
+impl ToTokens for TargetCondition {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(match self {
+            TargetCondition::PointerWidth(width) => {
+                let width = width.to_string();
+                quote! { target_pointer_width = #width }
+            }
+            TargetCondition::BigEndian(true) => quote! { target_endian = "big" },
+            TargetCondition::BigEndian(false) => quote! { target_endian = "little" },
+        })
+    }
+}
+
+/// The prefix of the C++ item generated to evaluate each macro listed in
+/// `macro_const!` or `macro_function!`. The item can't have the macro's
+/// own name, since that would be expanded too.
+const MACRO_PROBE_PREFIX: &str = "autocxx_macro_";
+
+/// The name of the C++ item generated to evaluate a macro.
+pub fn macro_probe_name(macro_name: &str) -> String {
+    format!("{}{}", MACRO_PROBE_PREFIX, macro_name)
+}
+
+/// The prefix of the C++ function generated to return the address of the
+/// calling thread's instance of each variable listed in
+/// `thread_local_global!`.
+const THREAD_LOCAL_PROBE_PREFIX: &str = "autocxx_thread_local_";
+
+/// The name of the C++ function, in the same namespace as a `thread_local`
+/// variable, which returns the calling thread's instance of it: for
+/// `ns::counter`, `ns::autocxx_thread_local_counter`.
+pub fn thread_local_probe_name(var_name: &str) -> String {
+    match var_name.rsplit_once("::") {
+        Some((ns, name)) => format!("{}::{}{}", ns, THREAD_LOCAL_PROBE_PREFIX, name),
+        None => format!("{}{}", THREAD_LOCAL_PROBE_PREFIX, var_name),
+    }
+}
+
+/// The prefix of the C++ function generated to call each method listed in
+/// `inherited_method!`.
+const INHERITED_METHOD_PROBE_PREFIX: &str = "autocxx_inherited_";
+
+/// The name of the C++ function, in the same namespace as a class, which
+/// calls a method it inherits: for `ns::Derived::describe`,
+/// `ns::autocxx_inherited_Derived_describe`.
+pub fn inherited_method_probe_name(class: &str, method: &str) -> String {
+    match class.rsplit_once("::") {
+        Some((ns, class)) => format!(
+            "{}::{}{}_{}",
+            ns, INHERITED_METHOD_PROBE_PREFIX, class, method
+        ),
+        None => format!("{}{}_{}", INHERITED_METHOD_PROBE_PREFIX, class, method),
+    }
+}
+
+/// The prefix of the C++ function generated to call each overload picked
+/// in `select_overload!`.
+const SELECTED_OVERLOAD_PROBE_PREFIX: &str = "autocxx_overload_";
+
+/// The name of the C++ function, in the same namespace as a function,
+/// which calls the overload of it picked by the `index`th
+/// `select_overload!`: for `ns::twice`, `ns::autocxx_overload_0_twice`.
+fn selected_overload_probe_name(function: &str, index: usize) -> String {
+    match function.rsplit_once("::") {
+        Some((ns, name)) => format!(
+            "{}::{}{}_{}",
+            ns, SELECTED_OVERLOAD_PROBE_PREFIX, index, name
+        ),
+        None => format!("{}{}_{}", SELECTED_OVERLOAD_PROBE_PREFIX, index, function),
+    }
+}
+
+/// The prefix of the C++ function declared with the type of each
+/// instantiation listed in `instantiate_fn_named!`.
+const NAMED_INSTANTIATION_PROBE_PREFIX: &str = "autocxx_instantiation_";
+
+/// The name of the C++ function, in the same namespace as a function
+/// template, declared with the type of an instantiation of it which is
+/// given this Rust name: for `ns::foo` and `foo_int_3`,
+/// `ns::autocxx_instantiation_foo_int_3`.
+fn named_instantiation_probe_name(function: &str, rust_name: &str) -> String {
+    match function.rsplit_once("::") {
+        Some((ns, _)) => format!("{}::{}{}", ns, NAMED_INSTANTIATION_PROBE_PREFIX, rust_name),
+        None => format!("{}{}", NAMED_INSTANTIATION_PROBE_PREFIX, rust_name),
+    }
+}
+
+/// The name of the typedef, in the same namespace as a class template,
+/// generated for an enum nested in one of its instantiations, as listed in
+/// `template_enum!`: for `ns::Widget<int>::State`, `ns::Widget_int_State`.
+pub fn template_enum_probe_name(instantiated: &str) -> String {
+    let open = instantiated.find('<').unwrap_or(instantiated.len());
+    let ns = instantiated[..open].rsplit_once("::").map(|(ns, _)| ns);
+    let start = ns.map(|ns| ns.len() + 2).unwrap_or(0);
+    let name = instantiated[start..]
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    match ns {
+        Some(ns) => format!("{}::{}", ns, name),
+        None => name,
+    }
+}
+
+/// The name of the enum within a class template from which an enum in one
+/// of its instantiations comes, without any template arguments: for
+/// `ns::Widget<int>::State`, `ns::Widget::State`.
+fn template_enum_generic_name(instantiated: &str) -> String {
+    let mut depth = 0;
+    instantiated
+        .chars()
+        .filter(|c| {
+            match c {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    return false;
+                }
+                _ => {}
+            }
+            depth == 0
+        })
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// Splits a C++ function signature such as `int(const char*, int)` into
+/// its return type and parameter types.
+fn parse_function_signature(signature: &str) -> Option<(String, Vec<String>)> {
+    let open = signature.find('(')?;
+    let return_type = signature[..open].trim();
+    let params = signature[open + 1..].trim_end().strip_suffix(')')?.trim();
+    if return_type.is_empty() {
+        return None;
+    }
+    Some((return_type.to_string(), split_param_types(params)?))
+}
+
+/// Splits a signature written as `(const char*, int) -> int` into its
+/// return type and parameter types. Without a `->`, the return type is
+/// `void`.
+fn parse_arrow_signature(signature: &str) -> Option<(String, Vec<String>)> {
+    let signature = signature.trim().strip_prefix('(')?;
+    let mut depth = 0i32;
+    let close = signature.char_indices().find_map(|(i, c)| {
+        match c {
+            '<' | '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            '>' | ')' => depth -= 1,
+            _ => {}
+        }
+        None
+    })?;
+    let return_type = match signature[close + 1..].trim() {
+        "" => "void",
+        rest => rest.strip_prefix("->")?.trim(),
+    };
+    if return_type.is_empty() {
+        return None;
+    }
+    Some((
+        return_type.to_string(),
+        split_param_types(signature[..close].trim())?,
+    ))
+}
+
+/// Splits a comma-separated list of C++ parameter types, such as
+/// `const char*, std::pair<int, int>`. `void` is an empty list.
+fn split_param_types(params: &str) -> Option<Vec<String>> {
+    let mut param_types = Vec::new();
+    if !params.is_empty() && params != "void" {
+        let mut depth = 0i32;
+        let mut start = 0;
+        for (i, c) in params.char_indices() {
+            match c {
+                '<' | '(' => depth += 1,
+                '>' | ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    param_types.push(params[start..i].trim().to_string());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        param_types.push(params[start..].trim().to_string());
+    }
+    if param_types.iter().any(|param| param.is_empty()) {
+        return None;
+    }
+    Some(param_types)
+}
+
+/// A constructor given its own Rust name in `name_ctor!`, picked by its
+/// parameter types.
+#[derive(Debug)]
+struct NamedConstructor {
+    ty: String,
+    param_types: Vec<String>,
+    name: String,
+}
+
+/// A C++ type written without the spaces which don't matter, so that
+/// `const Point &` and `const Point&` are the same.
+fn normalize_cpp_type(ty: &str) -> String {
+    let words: Vec<_> = ty.split_whitespace().collect();
+    let mut normalized = String::new();
+    for word in words {
+        let needs_space = matches!(
+            (normalized.chars().last(), word.chars().next()),
+            (Some(a), Some(b)) if (a.is_alphanumeric() || a == '_') && (b.is_alphanumeric() || b == '_')
+        );
+        if needs_space {
+            normalized.push(' ');
+        }
+        normalized.push_str(word);
+    }
+    normalized
+}
+
+/// Types already bound by another `include_cpp!` block, which this block
+/// should refer to rather than define again.
+#[derive(Debug)]
+struct ReusedTypes {
+    module: RustPath,
+    types: Vec<String>,
+}
+
 #[derive(Debug)]
 pub struct IncludeCppConfig {
     pub inclusions: Vec<String>,
     pub unsafe_policy: UnsafePolicy,
+    pub exceptions_as_panics: bool,
+    pub panics_as_exceptions: bool,
     pub parse_only: bool,
     pub exclude_impls: bool,
     pod_requests: Vec<String>,
+    force_opaques: Vec<String>,
     pub allowlist: Allowlist,
     blocklist: Vec<String>,
     constructor_blocklist: Vec<String>,
     exclude_utilities: bool,
+    translate_doxygen: bool,
     mod_name: Option<Ident>,
     pub rust_types: Vec<RustPath>,
     pub subclasses: Vec<Subclass>,
     pub extern_rust_funs: Vec<RustFun>,
+    /// Where any `generate!` directives which weren't written inside the
+    /// `include_cpp!` came from, for instance a line of a file passed to
+    /// `autocxx-gen --generate-list`, so that problems can be reported
+    /// there. Pairs of the name to generate and its origin.
+    pub generate_origins: Vec<(String, String)>,
+    offloads: Vec<String>,
+    nonzeros: Vec<String>,
+    non_null_returns: Vec<String>,
+    nullable_returns: Vec<String>,
+    checked_indexes: Vec<String>,
+    properties: Vec<String>,
+    transparent_newtypes: Vec<String>,
+    volatile_fields: Vec<(String, String)>,
+    pointer_wrappers: Vec<String>,
+    reused_types: Vec<ReusedTypes>,
+    extern_cxx_types: Vec<(String, RustPath)>,
+    type_conversions: Vec<(String, RustPath)>,
+    owned_returns: Vec<(String, String)>,
+    visibilities: Vec<RequestedVisibility>,
+    slice_params: Vec<SliceParam>,
+    c_str_params: Vec<(String, String)>,
+    c_str_returns: Vec<String>,
+    preconditions: Vec<Precondition>,
+    stripped_prefixes: Vec<String>,
+    closure_callbacks: Vec<ClosureCallback>,
+    resources: Vec<Resource>,
+    intrusive_refcounts: Vec<IntrusiveRefcount>,
+    awaitables: Vec<Awaitable>,
+    validities: Vec<Validity>,
+    smart_pointers: Vec<SmartPointer>,
+    fn_tables: Vec<String>,
+    flags: Vec<FlagsEnum>,
+    out_params: Vec<(String, String)>,
+    unique_array_returns: Vec<(String, String)>,
+    array_returns: Vec<(String, String)>,
+    null_terminated_array_returns: Vec<String>,
+    error_code_params: Vec<(String, String)>,
+    bitsets: Vec<(String, usize)>,
+    lifetimes: Vec<(String, String)>,
+    static_ref_returns: Vec<String>,
+    emplace_returns: Vec<String>,
+    tuple_returns: Vec<String>,
+    string_returns: Vec<(String, StringReturn)>,
+    symbol_resolver: Option<RustPath>,
+    macro_consts: Vec<String>,
+    macro_functions: Vec<MacroFunction>,
+    thread_local_globals: Vec<String>,
+    selected_overloads: Vec<SelectedOverload>,
+    named_instantiations: Vec<NamedInstantiation>,
+    named_ctors: Vec<NamedConstructor>,
+    inherited_methods: Vec<InheritedMethod>,
+    template_enums: Vec<String>,
+    any_castables: Vec<String>,
+    iterables: Vec<Iterable>,
+    conditional_generates: Vec<(TargetCondition, String)>,
 }
 
 impl Parse for IncludeCppConfig {
@@ -166,15 +721,69 @@ impl Parse for IncludeCppConfig {
         let mut parse_only = false;
         let mut exclude_impls = false;
         let mut unsafe_policy = UnsafePolicy::AllFunctionsUnsafe;
+        let mut exceptions_as_panics = false;
+        let mut panics_as_exceptions = false;
         let mut allowlist = Allowlist::default();
         let mut blocklist = Vec::new();
         let mut constructor_blocklist = Vec::new();
         let mut pod_requests = Vec::new();
+        let mut force_opaques = Vec::new();
         let mut rust_types = Vec::new();
         let mut exclude_utilities = false;
+        let mut translate_doxygen = false;
         let mut mod_name = None;
         let mut subclasses = Vec::new();
         let mut extern_rust_funs = Vec::new();
+        let mut offloads = Vec::new();
+        let mut nonzeros = Vec::new();
+        let mut non_null_returns = Vec::new();
+        let mut nullable_returns = Vec::new();
+        let mut checked_indexes = Vec::new();
+        let mut properties = Vec::new();
+        let mut transparent_newtypes = Vec::new();
+        let mut volatile_fields = Vec::new();
+        let mut pointer_wrappers = Vec::new();
+        let mut reused_types = Vec::new();
+        let mut extern_cxx_types = Vec::new();
+        let mut type_conversions = Vec::new();
+        let mut owned_returns = Vec::new();
+        let mut visibilities = Vec::new();
+        let mut slice_params = Vec::new();
+        let mut c_str_params = Vec::new();
+        let mut c_str_returns = Vec::new();
+        let mut preconditions = Vec::new();
+        let mut stripped_prefixes = Vec::new();
+        let mut closure_callbacks = Vec::new();
+        let mut resources = Vec::new();
+        let mut intrusive_refcounts = Vec::new();
+        let mut awaitables = Vec::new();
+        let mut validities = Vec::new();
+        let mut smart_pointers = Vec::new();
+        let mut fn_tables = Vec::new();
+        let mut flags = Vec::new();
+        let mut out_params = Vec::new();
+        let mut unique_array_returns = Vec::new();
+        let mut array_returns = Vec::new();
+        let mut null_terminated_array_returns = Vec::new();
+        let mut error_code_params = Vec::new();
+        let mut bitsets = Vec::new();
+        let mut lifetimes = Vec::new();
+        let mut static_ref_returns = Vec::new();
+        let mut emplace_returns = Vec::new();
+        let mut tuple_returns = Vec::new();
+        let mut string_returns = Vec::new();
+        let mut symbol_resolver = None;
+        let mut macro_consts = Vec::new();
+        let mut thread_local_globals = Vec::new();
+        let mut selected_overloads = Vec::new();
+        let mut named_instantiations: Vec<NamedInstantiation> = Vec::new();
+        let mut named_ctors: Vec<NamedConstructor> = Vec::new();
+        let mut inherited_methods = Vec::new();
+        let mut template_enums: Vec<String> = Vec::new();
+        let mut any_castables = Vec::new();
+        let mut iterables = Vec::new();
+        let mut conditional_generates = Vec::new();
+        let mut macro_functions = Vec::new();
 
         while !input.is_empty() {
             let has_hexathorpe = input.parse::<Option<syn::token::Pound>>()?.is_some();
@@ -192,12 +801,48 @@ impl Parse for IncludeCppConfig {
                     syn::parenthesized!(args in input);
                     let generate: syn::LitStr = args.parse()?;
                     allowlist.push(generate)?;
+                } else if ident == "generate_if" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let condition: TargetCondition = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let generate: syn::LitStr = args.parse()?;
+                    conditional_generates.push((condition, generate.value()));
+                    allowlist.commit(&ident)?;
                 } else if ident == "generate_pod" {
                     let args;
                     syn::parenthesized!(args in input);
                     let generate_pod: syn::LitStr = args.parse()?;
                     pod_requests.push(generate_pod.value());
                     allowlist.push(generate_pod)?;
+                } else if ident == "transparent_newtype" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let newtype: syn::LitStr = args.parse()?;
+                    transparent_newtypes.push(newtype.value());
+                    pod_requests.push(newtype.value());
+                    allowlist.push(newtype)?;
+                } else if ident == "volatile_field" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let field: syn::LitStr = args.parse()?;
+                    volatile_fields.push((ty.value(), field.value()));
+                } else if ident == "bitset" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let width: syn::LitInt = args.parse()?;
+                    bitsets.push((name.value(), width.base10_parse()?));
+                    allowlist.push(name)?;
+                } else if ident == "force_opaque" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let force_opaque: syn::LitStr = args.parse()?;
+                    force_opaques.push(force_opaque.value());
+                    allowlist.push(force_opaque)?;
                 } else if ident == "pod" {
                     let args;
                     syn::parenthesized!(args in input);
@@ -245,10 +890,28 @@ impl Parse for IncludeCppConfig {
                 } else if ident == "exclude_utilities" {
                     exclude_utilities = true;
                     swallow_parentheses(&input, &ident)?;
+                } else if ident == "translate_doxygen" {
+                    translate_doxygen = true;
+                    swallow_parentheses(&input, &ident)?;
                 } else if ident == "safety" {
                     let args;
                     syn::parenthesized!(args in input);
-                    unsafe_policy = args.parse()?;
+                    // Alongside the unsafe policy, this may contain
+                    // exceptions_as_panics and panics_as_exceptions.
+                    let items = Punctuated::<TokenTree, Token![,]>::parse_terminated(&args)?;
+                    let mut policy = TokenStream::new();
+                    for item in items {
+                        match item {
+                            TokenTree::Ident(id) if id == "exceptions_as_panics" => {
+                                exceptions_as_panics = true
+                            }
+                            TokenTree::Ident(id) if id == "panics_as_exceptions" => {
+                                panics_as_exceptions = true
+                            }
+                            _ => policy.extend(std::iter::once(item)),
+                        }
+                    }
+                    unsafe_policy = syn::parse2(policy)?;
                 } else if ident == "extern_rust_fun" {
                     let args;
                     syn::parenthesized!(args in input);
@@ -256,138 +919,1309 @@ impl Parse for IncludeCppConfig {
                     args.parse::<syn::token::Comma>()?;
                     let sig: syn::Signature = args.parse()?;
                     extern_rust_funs.push(RustFun { path, sig });
-                } else {
-                    return Err(syn::Error::new(
-                        ident.span(),
-                        "expected generate, generate_pod, nested_type, safety or exclude_utilities",
-                    ));
-                }
-            }
-            if input.is_empty() {
-                break;
-            }
-        }
-
-        Ok(IncludeCppConfig {
-            inclusions,
-            unsafe_policy,
-            parse_only,
-            exclude_impls,
-            pod_requests,
-            rust_types,
-            allowlist,
-            blocklist,
-            constructor_blocklist,
-            exclude_utilities,
-            mod_name,
-            subclasses,
-            extern_rust_funs,
-        })
-    }
-}
-
-fn swallow_parentheses(input: &ParseStream, latest_ident: &Ident) -> ParseResult<()> {
-    let args;
-    syn::parenthesized!(args in input);
-    if args.is_empty() {
-        Ok(())
-    } else {
-        Err(syn::Error::new(
-            latest_ident.span(),
-            "expected no arguments to directive",
-        ))
+                } else if ident == "offload" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let offload: syn::LitStr = args.parse()?;
+                    offloads.push(offload.value());
+                } else if ident == "reuse_from" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let module: RustPath = args.parse()?;
+                    let mut types = Vec::new();
+                    while args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        if args.is_empty() {
+                            break;
+                        }
+                        let ty: syn::LitStr = args.parse()?;
+                        types.push(ty.value());
+                    }
+                    if types.is_empty() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "reuse_from! needs the names of the types to reuse",
+                        ));
+                    }
+                    reused_types.push(ReusedTypes { module, types });
+                } else if ident == "extern_cxx_type" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let path: RustPath = args.parse()?;
+                    extern_cxx_types.push((ty.value(), path));
+                } else if ident == "type_conversion" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let path: RustPath = args.parse()?;
+                    type_conversions.push((ty.value(), path));
+                    allowlist.push(ty)?;
+                } else if ident == "register_pointer_wrapper" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let pointer_wrapper: syn::LitStr = args.parse()?;
+                    pointer_wrappers.push(pointer_wrapper.value());
+                } else if ident == "nonzero" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let nonzero: syn::LitStr = args.parse()?;
+                    nonzeros.push(nonzero.value());
+                } else if ident == "non_null_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    non_null_returns.push(function.value());
+                } else if ident == "nullable_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    nullable_returns.push(function.value());
+                } else if ident == "properties" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    properties.push(ty.value());
+                } else if ident == "checked_index" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    checked_indexes.push(ty.value());
+                } else if ident == "owned_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let deleter: syn::LitStr = args.parse()?;
+                    owned_returns.push((function.value(), deleter.value()));
+                } else if ident == "out_param" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let param: syn::LitStr = args.parse()?;
+                    out_params.push((function.value(), param.value()));
+                } else if ident == "unique_array_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let len: syn::LitStr = args.parse()?;
+                    unique_array_returns.push((function.value(), len.value()));
+                } else if ident == "array_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let len: syn::LitStr = args.parse()?;
+                    array_returns.push((function.value(), len.value()));
+                } else if ident == "null_terminated_array_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    null_terminated_array_returns.push(function.value());
+                } else if ident == "error_code_param" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let param: syn::LitStr = args.parse()?;
+                    error_code_params.push((function.value(), param.value()));
+                } else if ident == "lifetime" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    for keyword in ["return", "from"] {
+                        args.parse::<syn::token::Comma>()?;
+                        let lit: syn::LitStr = args.parse()?;
+                        if lit.value() != keyword {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                format!(
+                                    "expected \"{}\": the syntax is lifetime!(\"function\", \"return\", \"from\", \"param\")",
+                                    keyword
+                                ),
+                            ));
+                        }
+                    }
+                    args.parse::<syn::token::Comma>()?;
+                    let param: syn::LitStr = args.parse()?;
+                    lifetimes.push((function.value(), param.value()));
+                } else if ident == "static_ref_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    static_ref_returns.push(function.value());
+                } else if ident == "emplace_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    emplace_returns.push(function.value());
+                } else if ident == "tuple_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    tuple_returns.push(function.value());
+                } else if ident == "string_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    let conversion = if args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        let strict: Ident = args.parse()?;
+                        if strict != "strict" {
+                            return Err(syn::Error::new(strict.span(), "expected strict"));
+                        }
+                        StringReturn::Strict
+                    } else {
+                        StringReturn::Lossy
+                    };
+                    string_returns.push((function.value(), conversion));
+                } else if ident == "symbol_resolver" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let path: RustPath = args.parse()?;
+                    if symbol_resolver.is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "symbol_resolver! may be given only once",
+                        ));
+                    }
+                    symbol_resolver = Some(path);
+                } else if ident == "thread_local_global" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::LitStr = args.parse()?;
+                    thread_local_globals.push(name.value());
+                } else if ident == "select_overload" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let signature: syn::LitStr = args.parse()?;
+                    let (return_type, param_types) = parse_arrow_signature(&signature.value())
+                        .ok_or_else(|| {
+                            syn::Error::new(
+                                signature.span(),
+                                "expected a signature such as \"(int, int) -> int\"",
+                            )
+                        })?;
+                    selected_overloads.push(SelectedOverload {
+                        function: function.value(),
+                        return_type,
+                        param_types,
+                    });
+                    allowlist.commit(&ident)?;
+                } else if ident == "instantiate_fn_named" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let template_args: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let rust_name: syn::LitStr = args.parse()?;
+                    let template_args_value = template_args.value().trim().to_string();
+                    if !template_args_value.starts_with('<') || !template_args_value.ends_with('>')
+                    {
+                        return Err(syn::Error::new(
+                            template_args.span(),
+                            "expected template arguments such as \"<int, 3>\"",
+                        ));
+                    }
+                    if syn::parse_str::<Ident>(&rust_name.value()).is_err() {
+                        return Err(syn::Error::new(
+                            rust_name.span(),
+                            "the name must be a Rust identifier",
+                        ));
+                    }
+                    if named_instantiations.iter().any(|named| {
+                        named_instantiation_probe_name(&named.function, &named.rust_name)
+                            == named_instantiation_probe_name(&function.value(), &rust_name.value())
+                    }) {
+                        return Err(syn::Error::new(
+                            rust_name.span(),
+                            "another instantiation in this namespace already has this name",
+                        ));
+                    }
+                    named_instantiations.push(NamedInstantiation {
+                        function: function.value(),
+                        template_args: template_args_value,
+                        rust_name: rust_name.value(),
+                    });
+                    allowlist.commit(&ident)?;
+                } else if ident == "name_ctor" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let signature: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let name: syn::LitStr = args.parse()?;
+                    let param_types = match parse_arrow_signature(&signature.value()) {
+                        Some((return_type, param_types)) if return_type == "void" => param_types,
+                        _ => {
+                            return Err(syn::Error::new(
+                                signature.span(),
+                                "expected constructor parameters such as \"(int, int)\"",
+                            ))
+                        }
+                    };
+                    if syn::parse_str::<Ident>(&name.value()).is_err() {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            "the constructor name must be a Rust identifier",
+                        ));
+                    }
+                    // Constructors which aren't named keep the names `new`,
+                    // `new1` and so on, so those are taken.
+                    let name_value = name.value();
+                    if let Some(suffix) = name_value.strip_prefix("new") {
+                        if suffix.chars().all(|c| c.is_ascii_digit()) {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                "this name is used for constructors not given one by name_ctor!",
+                            ));
+                        }
+                    }
+                    if named_ctors
+                        .iter()
+                        .any(|named| named.ty == ty.value() && named.name == name_value)
+                    {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            "another constructor of this type already has this name",
+                        ));
+                    }
+                    let param_types: Vec<_> = param_types
+                        .iter()
+                        .map(|ty| normalize_cpp_type(ty))
+                        .collect();
+                    if named_ctors
+                        .iter()
+                        .any(|named| named.ty == ty.value() && named.param_types == param_types)
+                    {
+                        return Err(syn::Error::new(
+                            signature.span(),
+                            "this constructor has already been given a name",
+                        ));
+                    }
+                    named_ctors.push(NamedConstructor {
+                        ty: ty.value(),
+                        param_types,
+                        name: name_value,
+                    });
+                } else if ident == "any_castable" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    any_castables.push(ty.value());
+                } else if ident == "iterable" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let container: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let element: syn::LitStr = args.parse()?;
+                    let category = if args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        let category: Ident = args.parse()?;
+                        if category == "bidirectional" {
+                            IteratorCategory::Bidirectional
+                        } else if category == "random_access" {
+                            IteratorCategory::RandomAccess
+                        } else {
+                            return Err(syn::Error::new(
+                                category.span(),
+                                "expected bidirectional or random_access",
+                            ));
+                        }
+                    } else {
+                        IteratorCategory::Forward
+                    };
+                    iterables.push(Iterable {
+                        container: container.value(),
+                        element: element.value(),
+                        category,
+                        is_set: false,
+                    });
+                } else if ident == "cpp_set" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let container: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let element: syn::LitStr = args.parse()?;
+                    // A std::set's iterators go both ways; a
+                    // std::unordered_set's only forwards.
+                    let category = if args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        let ordered: Ident = args.parse()?;
+                        if ordered != "ordered" {
+                            return Err(syn::Error::new(ordered.span(), "expected ordered"));
+                        }
+                        IteratorCategory::Bidirectional
+                    } else {
+                        IteratorCategory::Forward
+                    };
+                    iterables.push(Iterable {
+                        container: container.value(),
+                        element: element.value(),
+                        category,
+                        is_set: true,
+                    });
+                } else if ident == "macro_const" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::LitStr = args.parse()?;
+                    macro_consts.push(name.value());
+                } else if ident == "macro_function" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let signature: syn::LitStr = args.parse()?;
+                    let (return_type, param_types) = parse_function_signature(&signature.value())
+                        .ok_or_else(|| {
+                        syn::Error::new(
+                            signature.span(),
+                            "expected a C++ function signature such as \"int(int, int)\"",
+                        )
+                    })?;
+                    macro_functions.push(MacroFunction {
+                        name: name.value(),
+                        return_type,
+                        param_types,
+                    });
+                } else if ident == "template_enum" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let instantiated: syn::LitStr = args.parse()?;
+                    let value = instantiated.value();
+                    let is_nested_in_instantiation = matches!(value.rsplit_once("::"),
+                        Some((class, _)) if class.trim_end().ends_with('>'));
+                    if !is_nested_in_instantiation {
+                        return Err(syn::Error::new(
+                            instantiated.span(),
+                            "expected an enum within an instantiation of a class template, such as \"Widget<int>::State\"",
+                        ));
+                    }
+                    let generic_name = template_enum_generic_name(&value);
+                    if template_enums
+                        .iter()
+                        .any(|other| template_enum_generic_name(other) == generic_name)
+                    {
+                        return Err(syn::Error::new(
+                            instantiated.span(),
+                            "bindgen describes an enum nested in every instantiation of a class template as the same enum, so only one instantiation of it can be bound",
+                        ));
+                    }
+                    template_enums.push(value);
+                } else if ident == "inherited_method" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let class: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let method: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let signature: syn::LitStr = args.parse()?;
+                    let signature_value = signature.value();
+                    let (signature_value, is_const) =
+                        match signature_value.trim_end().strip_suffix("const") {
+                            Some(signature_value) => (signature_value, true),
+                            None => (signature_value.as_str(), false),
+                        };
+                    let (return_type, param_types) = parse_function_signature(signature_value)
+                        .ok_or_else(|| {
+                            syn::Error::new(
+                                signature.span(),
+                                "expected a C++ method signature such as \"int(int) const\"",
+                            )
+                        })?;
+                    inherited_methods.push(InheritedMethod {
+                        class: class.value(),
+                        method: method.value(),
+                        return_type,
+                        param_types,
+                        is_const,
+                    });
+                } else if ident == "slice_param" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let pointer: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let length: syn::LitStr = args.parse()?;
+                    slice_params.push(SliceParam {
+                        function: function.value(),
+                        pointer: pointer.value(),
+                        length: length.value(),
+                    });
+                } else if ident == "c_str_param" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let param: syn::LitStr = args.parse()?;
+                    c_str_params.push((function.value(), param.value()));
+                } else if ident == "c_str_return" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    c_str_returns.push(function.value());
+                } else if ident == "precondition" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let predicate: ExprClosure = args.parse()?;
+                    if predicate.inputs.len() != 1 {
+                        return Err(syn::Error::new_spanned(
+                            &predicate.inputs,
+                            "a precondition takes one parameter, the tuple of arguments",
+                        ));
+                    }
+                    preconditions.push(Precondition {
+                        function: function.value(),
+                        predicate,
+                    });
+                } else if ident == "strip_prefix" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let prefix: syn::LitStr = args.parse()?;
+                    if prefix.value().is_empty() {
+                        return Err(syn::Error::new(
+                            prefix.span(),
+                            "strip_prefix! needs a prefix which isn't empty",
+                        ));
+                    }
+                    stripped_prefixes.push(prefix.value());
+                } else if ident == "closure_callback" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let function: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let callback: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let context: syn::LitStr = args.parse()?;
+                    closure_callbacks.push(ClosureCallback {
+                        function: function.value(),
+                        callback: callback.value(),
+                        context: context.value(),
+                    });
+                } else if ident == "resource" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let handle: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let close: syn::LitStr = args.parse()?;
+                    let invalid = if args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        Some(args.parse::<syn::LitStr>()?.value())
+                    } else {
+                        None
+                    };
+                    resources.push(Resource {
+                        handle: handle.value(),
+                        close: close.value(),
+                        invalid,
+                    });
+                } else if ident == "intrusive_refcount" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let add_ref: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let release: syn::LitStr = args.parse()?;
+                    intrusive_refcounts.push(IntrusiveRefcount {
+                        ty: ty.value(),
+                        add_ref: add_ref.value(),
+                        release: release.value(),
+                    });
+                } else if ident == "register_awaitable" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let resume: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let done: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let result: syn::LitStr = args.parse()?;
+                    awaitables.push(Awaitable {
+                        ty: ty.value(),
+                        resume: resume.value(),
+                        done: done.value(),
+                        result: result.value(),
+                    });
+                } else if ident == "validity" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    let check = if args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        Some(args.parse::<syn::LitStr>()?.value())
+                    } else {
+                        None
+                    };
+                    validities.push(Validity {
+                        ty: ty.value(),
+                        check,
+                    });
+                } else if ident == "smart_pointer" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let target: syn::LitStr = args.parse()?;
+                    let mutable = if args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        args.parse::<syn::token::Mut>()?;
+                        true
+                    } else {
+                        false
+                    };
+                    smart_pointers.push(SmartPointer {
+                        ty: ty.value(),
+                        target: target.value(),
+                        mutable,
+                    });
+                } else if ident == "fn_table" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    fn_tables.push(ty.value());
+                } else if ident == "flags" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    let mut exclusive_groups = Vec::new();
+                    while args.parse::<Option<syn::token::Comma>>()?.is_some() {
+                        let group;
+                        syn::bracketed!(group in args);
+                        let members: syn::punctuated::Punctuated<syn::LitStr, syn::token::Comma> =
+                            group.parse_terminated(|input| input.parse())?;
+                        exclusive_groups.push(members.iter().map(|m| m.value()).collect());
+                    }
+                    flags.push(FlagsEnum {
+                        ty: ty.value(),
+                        exclusive_groups,
+                    });
+                    allowlist.push(ty)?;
+                } else if ident == "visibility" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let name: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let vis: Visibility = args.parse()?;
+                    visibilities.push(RequestedVisibility {
+                        name: name.value(),
+                        vis,
+                    });
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "expected generate, generate_pod, nested_type, safety or exclude_utilities",
+                    ));
+                }
+            }
+            if input.is_empty() {
+                break;
+            }
+        }
+
+        Ok(IncludeCppConfig {
+            inclusions,
+            unsafe_policy,
+            exceptions_as_panics,
+            panics_as_exceptions,
+            parse_only,
+            exclude_impls,
+            pod_requests,
+            force_opaques,
+            rust_types,
+            allowlist,
+            blocklist,
+            constructor_blocklist,
+            exclude_utilities,
+            translate_doxygen,
+            mod_name,
+            subclasses,
+            extern_rust_funs,
+            generate_origins: Vec::new(),
+            offloads,
+            nonzeros,
+            non_null_returns,
+            nullable_returns,
+            checked_indexes,
+            properties,
+            transparent_newtypes,
+            volatile_fields,
+            pointer_wrappers,
+            reused_types,
+            extern_cxx_types,
+            type_conversions,
+            owned_returns,
+            visibilities,
+            slice_params,
+            c_str_params,
+            c_str_returns,
+            preconditions,
+            stripped_prefixes,
+            closure_callbacks,
+            resources,
+            intrusive_refcounts,
+            awaitables,
+            validities,
+            smart_pointers,
+            fn_tables,
+            flags,
+            out_params,
+            unique_array_returns,
+            array_returns,
+            null_terminated_array_returns,
+            error_code_params,
+            bitsets,
+            lifetimes,
+            static_ref_returns,
+            emplace_returns,
+            tuple_returns,
+            string_returns,
+            symbol_resolver,
+            macro_consts,
+            macro_functions,
+            thread_local_globals,
+            selected_overloads,
+            named_instantiations,
+            named_ctors,
+            inherited_methods,
+            template_enums,
+            any_castables,
+            iterables,
+            conditional_generates,
+        })
+    }
+}
+
+fn swallow_parentheses(input: &ParseStream, latest_ident: &Ident) -> ParseResult<()> {
+    let args;
+    syn::parenthesized!(args in input);
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            latest_ident.span(),
+            "expected no arguments to directive",
+        ))
+    }
+}
+
+impl IncludeCppConfig {
+    /// Where a `generate!` directive came from, if it wasn't written inside
+    /// the `include_cpp!`.
+    pub fn get_generate_origin(&self, name: &str) -> Option<&str> {
+        self.generate_origins
+            .iter()
+            .find(|(generate, _)| generate == name)
+            .map(|(_, origin)| origin.as_str())
+    }
+
+    pub fn get_pod_requests(&self) -> &[String] {
+        &self.pod_requests
+    }
+
+    /// Types which must be opaque, even if they could be POD, as listed
+    /// in `force_opaque!`.
+    pub fn get_force_opaques(&self) -> &[String] {
+        &self.force_opaques
+    }
+
+    pub fn get_mod_name(&self) -> Ident {
+        self.mod_name
+            .as_ref()
+            .cloned()
+            .unwrap_or_else(|| Ident::new("ffi", Span::call_site()))
+    }
+
+    /// Whether to avoid generating the standard helpful utility
+    /// functions which we normally include in every mod.
+    pub fn exclude_utilities(&self) -> bool {
+        self.exclude_utilities
+    }
+
+    /// Whether to turn the Doxygen tags in C++ comments into Markdown in
+    /// the Rust doc comments we generate from them.
+    pub fn translate_doxygen(&self) -> bool {
+        self.translate_doxygen
+    }
+
+    /// Items which the user has explicitly asked us to generate;
+    /// we should raise an error if we weren't able to do so.
+    pub fn must_generate_list(&self) -> Box<dyn Iterator<Item = String> + '_> {
+        if let Allowlist::Specific(items) = &self.allowlist {
+            Box::new(items.iter().chain(self.pod_requests.iter()).cloned())
+        } else {
+            Box::new(self.pod_requests.iter().cloned())
+        }
+    }
+
+    /// The allowlist of items to be passed into bindgen, if any.
+    pub fn bindgen_allowlist(&self) -> Option<Box<dyn Iterator<Item = String> + '_>> {
+        match &self.allowlist {
+            Allowlist::All => None,
+            Allowlist::Specific(items) => Some(Box::new(
+                items
+                    .iter()
+                    .chain(self.pod_requests.iter())
+                    .cloned()
+                    .chain(self.active_utilities())
+                    .chain(self.macro_consts.iter().map(|name| macro_probe_name(name)))
+                    .chain(
+                        self.thread_local_globals
+                            .iter()
+                            .map(|name| thread_local_probe_name(name)),
+                    )
+                    .chain(self.get_selected_overloads().map(|(probe, _)| probe))
+                    .chain(self.get_named_instantiations().map(|(probe, _)| probe))
+                    .chain(self.get_template_enums().map(|(probe, _)| probe))
+                    .chain(self.inherited_methods.iter().map(|inherited| {
+                        inherited_method_probe_name(&inherited.class, &inherited.method)
+                    }))
+                    .chain(self.any_castables.iter().cloned())
+                    .chain(self.iterables.iter().flat_map(|iterable| {
+                        [iterable.container.clone(), iterable.element.clone()]
+                    }))
+                    .chain(Some("std::any".to_string()).filter(|_| !self.any_castables.is_empty()))
+                    .chain(
+                        self.macro_functions
+                            .iter()
+                            .map(|function| macro_probe_name(&function.name)),
+                    )
+                    .chain(self.subclasses.iter().flat_map(|sc| {
+                        [
+                            format!("{}Cpp", sc.subclass),
+                            sc.subclass.to_string(), // TODO may not be necessary
+                            sc.superclass.clone(),
+                        ]
+                    })),
+            )),
+            Allowlist::Unspecified(_) => unreachable!(),
+        }
+    }
+
+    fn active_utilities(&self) -> Vec<String> {
+        if self.exclude_utilities {
+            Vec::new()
+        } else {
+            vec![self.get_makestring_name().to_string()]
+        }
+    }
+
+    /// Whether this type is on the allowlist specified by the user.
+    ///
+    /// A note on the allowlist handling in general. It's used in two places:
+    /// 1) As directives to bindgen
+    /// 2) After bindgen has generated code, to filter the APIs which
+    ///    we pass to cxx.
+    /// This second pass may seem redundant. But sometimes bindgen generates
+    /// unnecessary stuff.
+    pub fn is_on_allowlist(&self, cpp_name: &str) -> bool {
+        match self.bindgen_allowlist() {
+            None => true,
+            Some(mut items) => {
+                items.any(|item| item == cpp_name)
+                    || self.active_utilities().iter().any(|item| *item == cpp_name)
+                    || self.is_subclass_holder(cpp_name)
+                    || self.is_subclass_cpp(cpp_name)
+                    || self.is_rust_fun(cpp_name)
+                    || self.is_macro_const(cpp_name)
+            }
+        }
+    }
+
+    pub fn is_on_blocklist(&self, cpp_name: &str) -> bool {
+        self.blocklist.contains(&cpp_name.to_string())
+    }
+
+    pub fn is_on_constructor_blocklist(&self, cpp_name: &str) -> bool {
+        self.constructor_blocklist.contains(&cpp_name.to_string())
+    }
+
+    pub fn get_blocklist(&self) -> impl Iterator<Item = &String> {
+        self.blocklist.iter()
+    }
+
+    /// Whether we should generate a variant of this function which
+    /// runs on another thread and returns a future.
+    pub fn is_offloaded(&self, cpp_name: &str) -> bool {
+        self.offloads.contains(&cpp_name.to_string())
+    }
+
+    /// If this type, identified by its fully-qualified C++ name, was
+    /// already bound by another `include_cpp!` block, the Rust path of the
+    /// output module of that block.
+    pub fn get_reused_type_module(&self, cpp_name: &str) -> Option<&RustPath> {
+        self.reused_types
+            .iter()
+            .find(|r| r.types.iter().any(|ty| ty == cpp_name))
+            .map(|r| &r.module)
+    }
+
+    /// If this type, identified by its fully-qualified C++ name, is
+    /// already defined in a hand-written `cxx::bridge`, its Rust path there.
+    pub fn get_extern_cxx_type(&self, cpp_name: &str) -> Option<&RustPath> {
+        self.extern_cxx_types
+            .iter()
+            .find(|(ty, _)| ty == cpp_name)
+            .map(|(_, path)| path)
+    }
+
+    /// If this type, identified by its fully-qualified C++ name, should be
+    /// converted to and from a Rust type whenever it's passed by value or
+    /// const reference, the Rust path of that type.
+    pub fn get_type_conversion(&self, cpp_name: &str) -> Option<&RustPath> {
+        self.type_conversions
+            .iter()
+            .find(|(ty, _)| ty == cpp_name)
+            .map(|(_, path)| path)
+    }
+
+    /// Whether this template, identified by its fully-qualified C++ name,
+    /// wraps a pointer which is never null.
+    pub fn is_pointer_wrapper(&self, cpp_name: &str) -> bool {
+        self.pointer_wrappers.contains(&cpp_name.to_string())
+    }
+
+    /// Whether this function's integer return value is documented never
+    /// to be zero.
+    pub fn is_nonzero(&self, cpp_name: &str) -> bool {
+        self.nonzeros.contains(&cpp_name.to_string())
+    }
+
+    /// Whether the `UniquePtr` returned by this function should be checked
+    /// for null straight away, as requested by `non_null_return!`.
+    pub fn is_non_null_return(&self, cpp_name: &str) -> bool {
+        self.non_null_returns.contains(&cpp_name.to_string())
+    }
+
+    /// Whether the `UniquePtr` returned by this function may legitimately
+    /// be null, so should be returned as an `Option`, as requested by
+    /// `nullable_return!`.
+    pub fn is_nullable_return(&self, cpp_name: &str) -> bool {
+        self.nullable_returns.contains(&cpp_name.to_string())
+    }
+
+    /// Whether this container type's `at` method should be accompanied
+    /// by a `get` method which checks the index against `size` in Rust.
+    pub fn is_checked_index(&self, cpp_name: &str) -> bool {
+        self.checked_indexes.contains(&cpp_name.to_string())
+    }
+
+    /// Whether this type's getters and setters should be named like
+    /// properties, as requested by `properties!`.
+    pub fn has_properties(&self, cpp_name: &str) -> bool {
+        self.properties.iter().any(|ty| ty == cpp_name)
+    }
+
+    /// Whether this single-field struct should become a Rust
+    /// `#[repr(transparent)]` newtype.
+    pub fn is_transparent_newtype(&self, cpp_name: &str) -> bool {
+        self.transparent_newtypes.contains(&cpp_name.to_string())
+    }
+
+    /// The fields of this POD type which are `volatile`, as listed in
+    /// `volatile_field!`.
+    pub fn get_volatile_fields<'a>(&'a self, cpp_name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.volatile_fields
+            .iter()
+            .filter(move |(ty, _)| ty == cpp_name)
+            .map(|(_, field)| field.as_str())
+    }
+
+    /// If the pointer returned by this function should be owned by
+    /// Rust, the C++ function which must be called to free it.
+    pub fn get_owned_return_deleter(&self, cpp_name: &str) -> Option<&str> {
+        self.owned_returns
+            .iter()
+            .find(|(function, _)| function == cpp_name)
+            .map(|(_, deleter)| deleter.as_str())
+    }
+
+    /// If this function fills in a pointer to a pointer which should
+    /// instead be returned, the name of that parameter.
+    pub fn get_out_param(&self, cpp_name: &str) -> Option<&str> {
+        self.out_params
+            .iter()
+            .find(|(function, _)| function == cpp_name)
+            .map(|(_, param)| param.as_str())
+    }
+
+    /// If this function returns a `std::unique_ptr<T[]>`, the name of the
+    /// parameter which gives the array's length, or which the function
+    /// fills in with it.
+    pub fn get_unique_array_return(&self, cpp_name: &str) -> Option<&str> {
+        self.unique_array_returns
+            .iter()
+            .find(|(function, _)| function == cpp_name)
+            .map(|(_, len)| len.as_str())
+    }
+
+    /// If this function returns a pointer to an array which we should
+    /// borrow, as listed in `array_return!`, the name of the parameter or
+    /// function which gives the array's length.
+    pub fn get_array_return(&self, cpp_name: &str) -> Option<&str> {
+        self.array_returns
+            .iter()
+            .find(|(function, _)| function == cpp_name)
+            .map(|(_, len)| len.as_str())
+    }
+
+    /// Whether this function returns an array of pointers which ends with
+    /// a null pointer, as listed in `null_terminated_array_return!`.
+    pub fn is_null_terminated_array_return(&self, cpp_name: &str) -> bool {
+        self.null_terminated_array_returns
+            .iter()
+            .any(|f| f == cpp_name)
+    }
+
+    /// If this function reports failure through a `std::error_code&`
+    /// parameter which should instead become a Rust `Result`, the name of
+    /// that parameter.
+    pub fn get_error_code_param(&self, cpp_name: &str) -> Option<&str> {
+        self.error_code_params
+            .iter()
+            .find(|(function, _)| function == cpp_name)
+            .map(|(_, param)| param.as_str())
+    }
+
+    /// If this is a typedef of a `std::bitset` which should be represented
+    /// in Rust as an `autocxx::bitset::BitSet`, its number of bits.
+    pub fn get_bitset_width(&self, cpp_name: &str) -> Option<usize> {
+        self.bitsets
+            .iter()
+            .find(|(name, _)| name == cpp_name)
+            .map(|(_, width)| *width)
+    }
+
+    /// If the reference returned by this function borrows from one
+    /// particular parameter, as requested by `lifetime!`, the name of that
+    /// parameter, or `this` for the receiver.
+    pub fn get_return_lifetime_source(&self, cpp_name: &str) -> Option<&str> {
+        self.lifetimes
+            .iter()
+            .find(|(function, _)| function == cpp_name)
+            .map(|(_, param)| param.as_str())
+    }
+
+    /// Whether the reference returned by this function lives forever, as
+    /// declared by `static_ref_return!`.
+    pub fn is_static_ref_return(&self, cpp_name: &str) -> bool {
+        self.static_ref_returns.iter().any(|f| f == cpp_name)
+    }
+
+    /// Whether this function should construct the object it returns by
+    /// value in place, returning a `New`, as declared by `emplace_return!`.
+    pub fn is_emplace_return(&self, cpp_name: &str) -> bool {
+        self.emplace_returns.iter().any(|f| f == cpp_name)
+    }
+
+    /// Whether the `std::tuple` this function returns should become a Rust
+    /// tuple, as declared by `tuple_return!`.
+    pub fn is_tuple_return(&self, cpp_name: &str) -> bool {
+        self.tuple_returns.iter().any(|f| f == cpp_name)
+    }
+
+    /// If the `std::string` this function returns should become a `String`,
+    /// as declared by `string_return!`, how its bytes are converted.
+    pub fn get_string_return(&self, cpp_name: &str) -> Option<StringReturn> {
+        self.string_returns
+            .iter()
+            .find(|(f, _)| f == cpp_name)
+            .map(|(_, conversion)| *conversion)
+    }
+
+    /// The Rust path of the `autocxx::SymbolResolver` through which free
+    /// functions find the C++ they call at runtime, as given in
+    /// `symbol_resolver!`, if there is one.
+    pub fn get_symbol_resolver(&self) -> Option<&RustPath> {
+        self.symbol_resolver.as_ref()
+    }
+
+    /// Object-like macros which should be bound as constants, as listed
+    /// in `macro_const!`.
+    pub fn get_macro_consts(&self) -> &[String] {
+        &self.macro_consts
+    }
+
+    /// Whether this is an object-like macro listed in `macro_const!`.
+    pub fn is_macro_const(&self, name: &str) -> bool {
+        self.macro_consts.iter().any(|m| m == name)
+    }
+
+    /// Function-like macros which should be bound as functions, as listed
+    /// in `macro_function!`.
+    pub fn get_macro_functions(&self) -> &[MacroFunction] {
+        &self.macro_functions
+    }
+
+    /// If this is the name of the C++ item generated to evaluate a macro
+    /// listed in `macro_const!` or `macro_function!`, the macro's name.
+    pub fn macro_for_probe(&self, name: &str) -> Option<&str> {
+        let macro_name = name.strip_prefix(MACRO_PROBE_PREFIX)?;
+        self.macro_consts
+            .iter()
+            .chain(self.macro_functions.iter().map(|function| &function.name))
+            .find(|m| *m == macro_name)
+            .map(|m| m.as_str())
+    }
+
+    /// `thread_local` variables which should get an accessor for the
+    /// calling thread's instance, as listed in `thread_local_global!`.
+    pub fn get_thread_local_globals(&self) -> &[String] {
+        &self.thread_local_globals
+    }
+
+    /// If this is the name of the C++ function generated to return a
+    /// variable listed in `thread_local_global!`, the variable's name,
+    /// without its namespace.
+    pub fn thread_local_for_probe(&self, cpp_name: &str) -> Option<&str> {
+        self.thread_local_globals
+            .iter()
+            .find(|var| thread_local_probe_name(var) == cpp_name)
+            .map(|var| var.rsplit("::").next().unwrap())
+    }
+
+    /// The overloads picked in `select_overload!`, each with the name of
+    /// the C++ function generated to call it.
+    pub fn get_selected_overloads(&self) -> impl Iterator<Item = (String, &SelectedOverload)> {
+        self.selected_overloads
+            .iter()
+            .enumerate()
+            .map(|(i, selected)| {
+                (
+                    selected_overload_probe_name(&selected.function, i),
+                    selected,
+                )
+            })
+    }
+
+    /// If this is the name of the C++ function generated to call an
+    /// overload picked in `select_overload!`, the name of the function,
+    /// without its namespace.
+    pub fn selected_overload_for_probe(&self, cpp_name: &str) -> Option<&str> {
+        self.get_selected_overloads()
+            .find(|(probe, _)| probe == cpp_name)
+            .map(|(_, selected)| selected.function.rsplit("::").next().unwrap())
+    }
+
+    /// The instantiations listed in `instantiate_fn_named!`, each with the
+    /// name of the C++ function declared with its type.
+    pub fn get_named_instantiations(&self) -> impl Iterator<Item = (String, &NamedInstantiation)> {
+        self.named_instantiations.iter().map(|named| {
+            (
+                named_instantiation_probe_name(&named.function, &named.rust_name),
+                named,
+            )
+        })
+    }
+
+    /// If this is the name of the C++ function declared with the type of
+    /// an instantiation listed in `instantiate_fn_named!`, that
+    /// instantiation.
+    pub fn named_instantiation_for_probe(&self, cpp_name: &str) -> Option<&NamedInstantiation> {
+        self.get_named_instantiations()
+            .find(|(probe, _)| probe == cpp_name)
+            .map(|(_, named)| named)
+    }
+
+    /// Whether this is a function of which `select_overload!` picks an
+    /// overload, so that none of the overloads bindgen finds should be
+    /// bound.
+    pub fn has_selected_overload(&self, cpp_name: &str) -> bool {
+        self.selected_overloads
+            .iter()
+            .any(|selected| selected.function == cpp_name)
+    }
+
+    /// The Rust name given in `name_ctor!` to the constructor of this type
+    /// which takes parameters of these C++ types, if any.
+    pub fn get_ctor_name(&self, ty: &str, param_types: &[String]) -> Option<&str> {
+        self.named_ctors
+            .iter()
+            .find(|named| {
+                named.ty == ty
+                    && named.param_types.len() == param_types.len()
+                    && named
+                        .param_types
+                        .iter()
+                        .zip(param_types)
+                        .all(|(a, b)| *a == normalize_cpp_type(b))
+            })
+            .map(|named| named.name.as_str())
+    }
+
+    /// The enums nested in instantiations of class templates listed in
+    /// `template_enum!`, each with the name of the typedef generated for it.
+    pub fn get_template_enums(&self) -> impl Iterator<Item = (String, &str)> {
+        self.template_enums.iter().map(|instantiated| {
+            (
+                template_enum_probe_name(instantiated),
+                instantiated.as_str(),
+            )
+        })
+    }
+
+    /// If an instantiation of the enum with this fully-qualified C++ name,
+    /// nested in a class template, is listed in `template_enum!`, the name
+    /// of the typedef generated for it, which C++ should use instead.
+    pub fn template_enum_probe_for(&self, cpp_name: &str) -> Option<String> {
+        self.template_enums
+            .iter()
+            .find(|instantiated| template_enum_generic_name(instantiated) == cpp_name)
+            .map(|instantiated| template_enum_probe_name(instantiated))
+    }
+
+    /// Methods which classes inherit from bases bindgen can't describe, as
+    /// listed in `inherited_method!`.
+    pub fn get_inherited_methods(&self) -> &[InheritedMethod] {
+        &self.inherited_methods
+    }
+
+    /// If this is the name of the C++ function generated to call a method
+    /// listed in `inherited_method!`, that method.
+    pub fn inherited_method_for_probe(&self, cpp_name: &str) -> Option<&InheritedMethod> {
+        self.inherited_methods.iter().find(|inherited| {
+            inherited_method_probe_name(&inherited.class, &inherited.method) == cpp_name
+        })
+    }
+
+    /// Types which may be stored in, and downcast from, a `std::any`, as
+    /// listed in `any_castable!`.
+    pub fn get_any_castables(&self) -> &[String] {
+        &self.any_castables
+    }
+
+    /// C++ containers which should be iterable from Rust, as listed in
+    /// `iterable!`.
+    pub fn get_iterables(&self) -> &[Iterable] {
+        &self.iterables
+    }
+
+    /// The prefix of the names of the C++ functions, and types, generated to
+    /// iterate over containers listed in `iterable!`.
+    pub fn get_iterable_helper_prefix(&self) -> Cow<'_, str> {
+        self.uniquify_name_per_mod("autocxx_iter")
+    }
+
+    /// Pairs of pointer and length parameters of this function which
+    /// should become slices.
+    pub fn get_slice_params<'a>(
+        &'a self,
+        cpp_name: &'a str,
+    ) -> impl Iterator<Item = &'a SliceParam> {
+        self.slice_params
+            .iter()
+            .filter(move |sp| sp.function == cpp_name)
+    }
+
+    /// The `const char*` parameters of this function or method which should
+    /// accept a `&CStr` or a `&str`.
+    pub fn get_c_str_params<'a>(&'a self, cpp_name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.c_str_params
+            .iter()
+            .filter(move |(function, _)| function == cpp_name)
+            .map(|(_, param)| param.as_str())
+    }
+
+    /// Whether this method, identified by its fully-qualified C++ name,
+    /// returns a `const char*` which should become a `&CStr` borrowed from
+    /// the object, as requested by `c_str_return!`.
+    pub fn is_c_str_return(&self, cpp_name: &str) -> bool {
+        self.c_str_returns
+            .iter()
+            .any(|function| function == cpp_name)
     }
-}
 
-impl IncludeCppConfig {
-    pub fn get_pod_requests(&self) -> &[String] {
-        &self.pod_requests
+    /// The checks given in `precondition!` of the arguments of this free
+    /// function.
+    pub fn get_preconditions<'a>(
+        &'a self,
+        cpp_name: &'a str,
+    ) -> impl Iterator<Item = &'a ExprClosure> {
+        self.preconditions
+            .iter()
+            .filter(move |precondition| precondition.function == cpp_name)
+            .map(|precondition| &precondition.predicate)
     }
 
-    pub fn get_mod_name(&self) -> Ident {
-        self.mod_name
-            .as_ref()
-            .cloned()
-            .unwrap_or_else(|| Ident::new("ffi", Span::call_site()))
+    /// The name of this free function without any prefix listed in
+    /// `strip_prefix!`, if it has one. The longest such prefix is removed,
+    /// but only if what's left can begin an identifier.
+    pub fn strip_function_prefix<'a>(&self, name: &'a str) -> Option<&'a str> {
+        self.stripped_prefixes
+            .iter()
+            .filter_map(|prefix| name.strip_prefix(prefix.as_str()))
+            .filter(|rest| matches!(rest.chars().next(), Some(c) if c.is_alphabetic() || c == '_'))
+            .min_by_key(|rest| rest.len())
     }
 
-    /// Whether to avoid generating the standard helpful utility
-    /// functions which we normally include in every mod.
-    pub fn exclude_utilities(&self) -> bool {
-        self.exclude_utilities
+    /// The callback and context parameters of this function, if they
+    /// should become a closure.
+    pub fn get_closure_callback(&self, cpp_name: &str) -> Option<&ClosureCallback> {
+        self.closure_callbacks
+            .iter()
+            .find(|cc| cc.function == cpp_name)
     }
 
-    /// Items which the user has explicitly asked us to generate;
-    /// we should raise an error if we weren't able to do so.
-    pub fn must_generate_list(&self) -> Box<dyn Iterator<Item = String> + '_> {
-        if let Allowlist::Specific(items) = &self.allowlist {
-            Box::new(items.iter().chain(self.pod_requests.iter()).cloned())
-        } else {
-            Box::new(self.pod_requests.iter().cloned())
-        }
+    /// If values of this handle type, identified by its fully-qualified
+    /// C++ name, are resources which must be released, details of how.
+    pub fn get_resource(&self, cpp_name: &str) -> Option<&Resource> {
+        self.resources.iter().find(|r| r.handle == cpp_name)
     }
 
-    /// The allowlist of items to be passed into bindgen, if any.
-    pub fn bindgen_allowlist(&self) -> Option<Box<dyn Iterator<Item = String> + '_>> {
-        match &self.allowlist {
-            Allowlist::All => None,
-            Allowlist::Specific(items) => Some(Box::new(
-                items
-                    .iter()
-                    .chain(self.pod_requests.iter())
-                    .cloned()
-                    .chain(self.active_utilities())
-                    .chain(self.subclasses.iter().flat_map(|sc| {
-                        [
-                            format!("{}Cpp", sc.subclass),
-                            sc.subclass.to_string(), // TODO may not be necessary
-                            sc.superclass.clone(),
-                        ]
-                    })),
-            )),
-            Allowlist::Unspecified(_) => unreachable!(),
-        }
+    /// If this type, identified by its fully-qualified C++ name, counts
+    /// references to itself, details of how.
+    pub fn get_intrusive_refcount(&self, cpp_name: &str) -> Option<&IntrusiveRefcount> {
+        self.intrusive_refcounts.iter().find(|r| r.ty == cpp_name)
     }
 
-    fn active_utilities(&self) -> Vec<String> {
-        if self.exclude_utilities {
-            Vec::new()
-        } else {
-            vec![self.get_makestring_name().to_string()]
-        }
+    /// If this type, identified by its fully-qualified C++ name, may be
+    /// awaited, the methods with which to do so.
+    pub fn get_awaitable(&self, cpp_name: &str) -> Option<&Awaitable> {
+        self.awaitables.iter().find(|a| a.ty == cpp_name)
     }
 
-    /// Whether this type is on the allowlist specified by the user.
-    ///
-    /// A note on the allowlist handling in general. It's used in two places:
-    /// 1) As directives to bindgen
-    /// 2) After bindgen has generated code, to filter the APIs which
-    ///    we pass to cxx.
-    /// This second pass may seem redundant. But sometimes bindgen generates
-    /// unnecessary stuff.
-    pub fn is_on_allowlist(&self, cpp_name: &str) -> bool {
-        match self.bindgen_allowlist() {
-            None => true,
-            Some(mut items) => {
-                items.any(|item| item == cpp_name)
-                    || self.active_utilities().iter().any(|item| *item == cpp_name)
-                    || self.is_subclass_holder(cpp_name)
-                    || self.is_subclass_cpp(cpp_name)
-                    || self.is_rust_fun(cpp_name)
-            }
-        }
+    /// If objects of this type, identified by its fully-qualified C++ name,
+    /// may or may not be valid, details of how to tell.
+    pub fn get_validity(&self, cpp_name: &str) -> Option<&Validity> {
+        self.validities.iter().find(|v| v.ty == cpp_name)
     }
 
-    pub fn is_on_blocklist(&self, cpp_name: &str) -> bool {
-        self.blocklist.contains(&cpp_name.to_string())
+    /// If this type, identified by its fully-qualified C++ name, is a smart
+    /// pointer listed in `smart_pointer!`, what it points to.
+    pub fn get_smart_pointer(&self, cpp_name: &str) -> Option<&SmartPointer> {
+        self.smart_pointers.iter().find(|sp| sp.ty == cpp_name)
     }
 
-    pub fn is_on_constructor_blocklist(&self, cpp_name: &str) -> bool {
-        self.constructor_blocklist.contains(&cpp_name.to_string())
+    /// Whether this struct, identified by its fully-qualified C++ name, is a
+    /// table of function pointers listed in `fn_table!`, which should get
+    /// methods to call them.
+    pub fn is_fn_table(&self, cpp_name: &str) -> bool {
+        self.fn_tables.iter().any(|ty| ty == cpp_name)
     }
 
-    pub fn get_blocklist(&self) -> impl Iterator<Item = &String> {
-        self.blocklist.iter()
+    /// If this enum, identified by its fully-qualified C++ name, holds
+    /// bit flags, details of how they may be combined.
+    pub fn get_flags(&self, cpp_name: &str) -> Option<&FlagsEnum> {
+        self.flags.iter().find(|f| f.ty == cpp_name)
+    }
+
+    /// Any visibility requested for a given item or namespace, identified
+    /// by its fully-qualified C++ name. If there's nothing specific to
+    /// this name, we fall back to the innermost enclosing namespace
+    /// which has a visibility given.
+    pub fn get_visibility(&self, cpp_name: &str) -> Option<&Visibility> {
+        let mut candidate = cpp_name;
+        loop {
+            if let Some(requested) = self.visibilities.iter().find(|v| v.name == candidate) {
+                return Some(&requested.vis);
+            }
+            candidate = &candidate[..candidate.rfind("::")?];
+        }
     }
 
     /// In case there are multiple sets of ffi mods in a single binary,
@@ -404,6 +2238,12 @@ impl IncludeCppConfig {
         self.uniquify_name_per_mod("autocxx_make_string")
     }
 
+    /// The prefix of the names of the C++ functions generated to use
+    /// `std::any`s.
+    pub fn get_any_helper_prefix(&self) -> Cow<'_, str> {
+        self.uniquify_name_per_mod("autocxx_any")
+    }
+
     pub fn is_rust_type(&self, id: &Ident) -> bool {
         self.rust_types
             .iter()
@@ -447,6 +2287,19 @@ impl IncludeCppConfig {
         )
     }
 
+    /// Adds the items listed in `generate_if!` directives whose condition
+    /// holds for the target to those to generate.
+    pub fn apply_target(&mut self, pointer_width: u32, big_endian: bool) {
+        if let Allowlist::Specific(items) = &mut self.allowlist {
+            items.extend(
+                self.conditional_generates
+                    .iter()
+                    .filter(|(condition, _)| condition.holds(pointer_width, big_endian))
+                    .map(|(_, name)| name.clone()),
+            );
+        }
+    }
+
     pub fn confirm_complete(&mut self, auto_allowlist: bool) -> ParseResult<()> {
         if matches!(self.allowlist, Allowlist::Unspecified(_)) {
             if auto_allowlist {
@@ -481,8 +2334,14 @@ impl ToTokens for IncludeCppConfig {
             })
         }
         let unsafety = &self.unsafe_policy;
+        let exceptions_as_panics = self
+            .exceptions_as_panics
+            .then(|| quote! { , exceptions_as_panics });
+        let panics_as_exceptions = self
+            .panics_as_exceptions
+            .then(|| quote! { , panics_as_exceptions });
         tokens.extend(quote! {
-            safety!(#unsafety)
+            safety!(#unsafety #exceptions_as_panics #panics_as_exceptions)
         });
         if self.exclude_impls {
             tokens.extend(quote! { exclude_impls!() });
@@ -493,15 +2352,269 @@ impl ToTokens for IncludeCppConfig {
         if self.exclude_utilities {
             tokens.extend(quote! { exclude_utilities!() });
         }
+        if self.translate_doxygen {
+            tokens.extend(quote! { translate_doxygen!() });
+        }
         for i in &self.pod_requests {
             tokens.extend(quote! { pod!(#i) });
         }
+        for i in &self.force_opaques {
+            tokens.extend(quote! { force_opaque!(#i) });
+        }
         for i in &self.blocklist {
             tokens.extend(quote! { block!(#i) });
         }
         for i in &self.constructor_blocklist {
             tokens.extend(quote! { block_constructors!(#i) });
         }
+        for i in &self.offloads {
+            tokens.extend(quote! { offload!(#i) });
+        }
+        for i in &self.nonzeros {
+            tokens.extend(quote! { nonzero!(#i) });
+        }
+        for i in &self.non_null_returns {
+            tokens.extend(quote! { non_null_return!(#i) });
+        }
+        for i in &self.nullable_returns {
+            tokens.extend(quote! { nullable_return!(#i) });
+        }
+        for i in &self.checked_indexes {
+            tokens.extend(quote! { checked_index!(#i) });
+        }
+        for i in &self.properties {
+            tokens.extend(quote! { properties!(#i) });
+        }
+        for i in &self.transparent_newtypes {
+            tokens.extend(quote! { transparent_newtype!(#i) });
+        }
+        for (ty, field) in &self.volatile_fields {
+            tokens.extend(quote! { volatile_field!(#ty, #field) });
+        }
+        for ReusedTypes { module, types } in &self.reused_types {
+            tokens.extend(quote! { reuse_from!(#module #(, #types)*) });
+        }
+        for (ty, path) in &self.extern_cxx_types {
+            tokens.extend(quote! { extern_cxx_type!(#ty, #path) });
+        }
+        for (ty, path) in &self.type_conversions {
+            tokens.extend(quote! { type_conversion!(#ty, #path) });
+        }
+        for i in &self.pointer_wrappers {
+            tokens.extend(quote! { register_pointer_wrapper!(#i) });
+        }
+        for (function, deleter) in &self.owned_returns {
+            tokens.extend(quote! { owned_return!(#function, #deleter) });
+        }
+        for (function, param) in &self.out_params {
+            tokens.extend(quote! { out_param!(#function, #param) });
+        }
+        for (function, len) in &self.unique_array_returns {
+            tokens.extend(quote! { unique_array_return!(#function, #len) });
+        }
+        for (function, len) in &self.array_returns {
+            tokens.extend(quote! { array_return!(#function, #len) });
+        }
+        for function in &self.null_terminated_array_returns {
+            tokens.extend(quote! { null_terminated_array_return!(#function) });
+        }
+        for (function, param) in &self.error_code_params {
+            tokens.extend(quote! { error_code_param!(#function, #param) });
+        }
+        for (name, width) in &self.bitsets {
+            let width = proc_macro2::Literal::usize_unsuffixed(*width);
+            tokens.extend(quote! { bitset!(#name, #width) });
+        }
+        for (function, param) in &self.lifetimes {
+            tokens.extend(quote! { lifetime!(#function, "return", "from", #param) });
+        }
+        for function in &self.static_ref_returns {
+            tokens.extend(quote! { static_ref_return!(#function) });
+        }
+        for function in &self.emplace_returns {
+            tokens.extend(quote! { emplace_return!(#function) });
+        }
+        for function in &self.tuple_returns {
+            tokens.extend(quote! { tuple_return!(#function) });
+        }
+        for (function, conversion) in &self.string_returns {
+            match conversion {
+                StringReturn::Lossy => tokens.extend(quote! { string_return!(#function) }),
+                StringReturn::Strict => tokens.extend(quote! { string_return!(#function, strict) }),
+            }
+        }
+        if let Some(path) = &self.symbol_resolver {
+            tokens.extend(quote! { symbol_resolver!(#path) });
+        }
+        for name in &self.thread_local_globals {
+            tokens.extend(quote! { thread_local_global!(#name) });
+        }
+        for SelectedOverload {
+            function,
+            return_type,
+            param_types,
+        } in &self.selected_overloads
+        {
+            let signature = format!("({}) -> {}", param_types.join(", "), return_type);
+            tokens.extend(quote! { select_overload!(#function, #signature) });
+        }
+        for NamedInstantiation {
+            function,
+            template_args,
+            rust_name,
+        } in &self.named_instantiations
+        {
+            tokens.extend(quote! { instantiate_fn_named!(#function, #template_args, #rust_name) });
+        }
+        for NamedConstructor {
+            ty,
+            param_types,
+            name,
+        } in &self.named_ctors
+        {
+            let signature = format!("({})", param_types.join(", "));
+            tokens.extend(quote! { name_ctor!(#ty, #signature, #name) });
+        }
+        for InheritedMethod {
+            class,
+            method,
+            return_type,
+            param_types,
+            is_const,
+        } in &self.inherited_methods
+        {
+            let mut signature = format!("{}({})", return_type, param_types.join(", "));
+            if *is_const {
+                signature.push_str(" const");
+            }
+            tokens.extend(quote! { inherited_method!(#class, #method, #signature) });
+        }
+        for instantiated in &self.template_enums {
+            tokens.extend(quote! { template_enum!(#instantiated) });
+        }
+        for ty in &self.any_castables {
+            tokens.extend(quote! { any_castable!(#ty) });
+        }
+        for Iterable {
+            container,
+            element,
+            category,
+            is_set,
+        } in &self.iterables
+        {
+            tokens.extend(match category {
+                IteratorCategory::Forward if *is_set => quote! { cpp_set!(#container, #element) },
+                IteratorCategory::Bidirectional if *is_set => {
+                    quote! { cpp_set!(#container, #element, ordered) }
+                }
+                IteratorCategory::Forward => quote! { iterable!(#container, #element) },
+                IteratorCategory::Bidirectional => {
+                    quote! { iterable!(#container, #element, bidirectional) }
+                }
+                IteratorCategory::RandomAccess => {
+                    quote! { iterable!(#container, #element, random_access) }
+                }
+            });
+        }
+        for name in &self.macro_consts {
+            tokens.extend(quote! { macro_const!(#name) });
+        }
+        for MacroFunction {
+            name,
+            return_type,
+            param_types,
+        } in &self.macro_functions
+        {
+            let signature = format!("{}({})", return_type, param_types.join(", "));
+            tokens.extend(quote! { macro_function!(#name, #signature) });
+        }
+        for SliceParam {
+            function,
+            pointer,
+            length,
+        } in &self.slice_params
+        {
+            tokens.extend(quote! { slice_param!(#function, #pointer, #length) });
+        }
+        for Precondition {
+            function,
+            predicate,
+        } in &self.preconditions
+        {
+            tokens.extend(quote! { precondition!(#function, #predicate) });
+        }
+        for (function, param) in &self.c_str_params {
+            tokens.extend(quote! { c_str_param!(#function, #param) });
+        }
+        for function in &self.c_str_returns {
+            tokens.extend(quote! { c_str_return!(#function) });
+        }
+        for prefix in &self.stripped_prefixes {
+            tokens.extend(quote! { strip_prefix!(#prefix) });
+        }
+        for ClosureCallback {
+            function,
+            callback,
+            context,
+        } in &self.closure_callbacks
+        {
+            tokens.extend(quote! { closure_callback!(#function, #callback, #context) });
+        }
+        for Resource {
+            handle,
+            close,
+            invalid,
+        } in &self.resources
+        {
+            let invalid = invalid.iter();
+            tokens.extend(quote! { resource!(#handle, #close #(, #invalid)*) });
+        }
+        for IntrusiveRefcount {
+            ty,
+            add_ref,
+            release,
+        } in &self.intrusive_refcounts
+        {
+            tokens.extend(quote! { intrusive_refcount!(#ty, #add_ref, #release) });
+        }
+        for Awaitable {
+            ty,
+            resume,
+            done,
+            result,
+        } in &self.awaitables
+        {
+            tokens.extend(quote! { register_awaitable!(#ty, #resume, #done, #result) });
+        }
+        for Validity { ty, check } in &self.validities {
+            let check = check.iter();
+            tokens.extend(quote! { validity!(#ty #(, #check)*) });
+        }
+        for SmartPointer {
+            ty,
+            target,
+            mutable,
+        } in &self.smart_pointers
+        {
+            let mutable = mutable.then(|| quote! { , mut });
+            tokens.extend(quote! { smart_pointer!(#ty, #target #mutable) });
+        }
+        for ty in &self.fn_tables {
+            tokens.extend(quote! { fn_table!(#ty) });
+        }
+        for FlagsEnum {
+            ty,
+            exclusive_groups,
+        } in &self.flags
+        {
+            let groups = exclusive_groups
+                .iter()
+                .map(|group| quote! { [#(#group),*] });
+            tokens.extend(quote! { flags!(#ty #(, #groups)*) });
+        }
+        for RequestedVisibility { name, vis } in &self.visibilities {
+            tokens.extend(quote! { visibility!(#name, #vis) });
+        }
         for path in &self.rust_types {
             tokens.extend(quote! { rust_type!(#path) });
         }
@@ -514,8 +2627,11 @@ impl ToTokens for IncludeCppConfig {
             }
             Allowlist::Unspecified(_) => panic!("Allowlist mode not yet determined"),
         }
+        for (condition, name) in &self.conditional_generates {
+            tokens.extend(quote! { generate_if!(#condition, #name) });
+        }
         if let Some(mod_name) = &self.mod_name {
-            tokens.extend(quote! { mod_name!(#mod_name) });
+            tokens.extend(quote! { name!(#mod_name) });
         }
         for i in &self.extern_rust_funs {
             let p = &i.path;
@@ -532,7 +2648,8 @@ impl ToTokens for IncludeCppConfig {
 
 #[cfg(test)]
 mod parse_tests {
-    use crate::config::UnsafePolicy;
+    use crate::config::{IncludeCppConfig, IteratorCategory, StringReturn, UnsafePolicy};
+    use quote::ToTokens;
     use syn::parse_quote;
     #[test]
     fn test_safety_unsafe() {
@@ -555,4 +2672,466 @@ mod parse_tests {
         let us: UnsafePolicy = parse_quote! {};
         assert_eq!(us, UnsafePolicy::AllFunctionsUnsafe)
     }
+
+    #[test]
+    fn test_visibility_falls_back_to_namespace() {
+        let config: IncludeCppConfig = parse_quote! {
+            visibility!("a", pub(crate))
+            visibility!("a::b::C", pub(super))
+        };
+        let vis_of = |name| {
+            config
+                .get_visibility(name)
+                .map(|vis| vis.to_token_stream().to_string())
+        };
+        assert_eq!(vis_of("a::b::C").as_deref(), Some("pub (super)"));
+        assert_eq!(vis_of("a::b::D").as_deref(), Some("pub (crate)"));
+        assert_eq!(vis_of("a").as_deref(), Some("pub (crate)"));
+        assert_eq!(vis_of("ab::C"), None);
+    }
+
+    #[test]
+    fn test_safety_exceptions_as_panics() {
+        let config: IncludeCppConfig = parse_quote! {
+            safety!(unsafe_ffi, exceptions_as_panics)
+        };
+        assert_eq!(config.unsafe_policy, UnsafePolicy::AllFunctionsSafe);
+        assert!(config.exceptions_as_panics);
+        let config: IncludeCppConfig = parse_quote! {
+            safety!(exceptions_as_panics)
+        };
+        assert_eq!(config.unsafe_policy, UnsafePolicy::AllFunctionsUnsafe);
+        assert!(config.exceptions_as_panics);
+        assert!(!config.panics_as_exceptions);
+    }
+
+    #[test]
+    fn test_safety_panics_as_exceptions() {
+        let config: IncludeCppConfig = parse_quote! {
+            safety!(unsafe_ffi, panics_as_exceptions)
+        };
+        assert_eq!(config.unsafe_policy, UnsafePolicy::AllFunctionsSafe);
+        assert!(config.panics_as_exceptions);
+        assert!(!config.exceptions_as_panics);
+        let config: IncludeCppConfig = parse_quote! {
+            safety!(exceptions_as_panics, panics_as_exceptions)
+        };
+        assert_eq!(config.unsafe_policy, UnsafePolicy::AllFunctionsUnsafe);
+        assert!(config.exceptions_as_panics);
+        assert!(config.panics_as_exceptions);
+    }
+
+    #[test]
+    fn test_inherited_method() {
+        let config: IncludeCppConfig = parse_quote! {
+            inherited_method!("ns::Derived", "describe", "uint32_t() const")
+            inherited_method!("Derived", "set", "void(int, std::pair<int, int>)")
+        };
+        let methods = config.get_inherited_methods();
+        assert!(methods[0].is_const);
+        assert!(methods[0].param_types.is_empty());
+        assert!(!methods[1].is_const);
+        assert_eq!(methods[1].param_types, ["int", "std::pair<int, int>"]);
+        assert_eq!(
+            config
+                .inherited_method_for_probe("ns::autocxx_inherited_Derived_describe")
+                .map(|inherited| inherited.method.as_str()),
+            Some("describe")
+        );
+        assert!(config
+            .inherited_method_for_probe("autocxx_inherited_Derived_describe")
+            .is_none());
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"inherited_method!("D", "f", "const")"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_iterable() {
+        let config: IncludeCppConfig = parse_quote! {
+            iterable!("Bag", "uint32_t")
+            iterable!("ns::List", "ns::Item", bidirectional)
+            iterable!("Vec", "double", random_access)
+        };
+        let categories: Vec<_> = config
+            .get_iterables()
+            .iter()
+            .map(|iterable| iterable.category)
+            .collect();
+        assert_eq!(
+            categories,
+            [
+                IteratorCategory::Forward,
+                IteratorCategory::Bidirectional,
+                IteratorCategory::RandomAccess
+            ]
+        );
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"iterable!("Bag", "int", sorted)"#).is_err());
+    }
+
+    #[test]
+    fn test_cpp_set() {
+        let config: IncludeCppConfig = parse_quote! {
+            iterable!("Bag", "uint32_t")
+            cpp_set!("Ids", "int", ordered)
+            cpp_set!("Seen", "std::string")
+        };
+        let sets: Vec<_> = config
+            .get_iterables()
+            .iter()
+            .filter(|iterable| iterable.is_set)
+            .map(|iterable| (iterable.container.as_str(), iterable.category))
+            .collect();
+        assert_eq!(
+            sets,
+            [
+                ("Ids", IteratorCategory::Bidirectional),
+                ("Seen", IteratorCategory::Forward)
+            ]
+        );
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"cpp_set!("Ids", "int", sorted)"#).is_err());
+    }
+
+    #[test]
+    fn test_type_conversion() {
+        let config: IncludeCppConfig = parse_quote! {
+            type_conversion!("MyColor", crate::rgb::Color)
+            type_conversion!("geo::Point", mint::Point2)
+        };
+        let path = config.get_type_conversion("MyColor").unwrap();
+        assert_eq!(path.to_token_stream().to_string(), "crate :: rgb :: Color");
+        assert_eq!(
+            config
+                .get_type_conversion("geo::Point")
+                .unwrap()
+                .get_final_ident(),
+            "Point2"
+        );
+        assert!(config.get_type_conversion("Point").is_none());
+    }
+
+    #[test]
+    fn test_volatile_field() {
+        let config: IncludeCppConfig = parse_quote! {
+            generate_pod!("Registers")
+            volatile_field!("Registers", "status")
+            volatile_field!("Registers", "data")
+        };
+        let fields: Vec<_> = config.get_volatile_fields("Registers").collect();
+        assert_eq!(fields, ["status", "data"]);
+        assert_eq!(config.get_volatile_fields("Other").count(), 0);
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"volatile_field!("Registers")"#).is_err());
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        let config: IncludeCppConfig = parse_quote! {
+            strip_prefix!("mylib_")
+            strip_prefix!("mylib_io_")
+        };
+        assert_eq!(config.strip_function_prefix("mylib_open"), Some("open"));
+        assert_eq!(config.strip_function_prefix("mylib_io_read"), Some("read"));
+        assert_eq!(config.strip_function_prefix("other_open"), None);
+        assert_eq!(config.strip_function_prefix("mylib_"), None);
+        assert_eq!(config.strip_function_prefix("mylib_2d"), None);
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"strip_prefix!("")"#).is_err());
+    }
+
+    #[test]
+    fn test_precondition() {
+        let config: IncludeCppConfig = parse_quote! {
+            precondition!("divide", |args| *args.1 != 0)
+            precondition!("divide", |(a, b)| *a >= *b)
+        };
+        assert_eq!(config.get_preconditions("divide").count(), 2);
+        assert_eq!(config.get_preconditions("multiply").count(), 0);
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"precondition!("divide", |a, b| *b != 0)"#)
+                .is_err()
+        );
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"precondition!("divide", true)"#).is_err());
+    }
+
+    #[test]
+    fn test_template_enum() {
+        let config: IncludeCppConfig = parse_quote! {
+            template_enum!("ns::Widget<std::pair<int, char> >::State")
+        };
+        let template_enums: Vec<_> = config.get_template_enums().collect();
+        assert_eq!(
+            template_enums,
+            [(
+                "ns::Widget_std_pair_int_char_State".to_string(),
+                "ns::Widget<std::pair<int, char> >::State"
+            )]
+        );
+        assert_eq!(
+            config
+                .template_enum_probe_for("ns::Widget::State")
+                .as_deref(),
+            Some("ns::Widget_std_pair_int_char_State")
+        );
+        assert!(config.template_enum_probe_for("Widget::State").is_none());
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"template_enum!("Widget::State")"#).is_err());
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"template_enum!("Widget<int>::State") template_enum!("Widget<char>::State")"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_emplace_return() {
+        let config: IncludeCppConfig = parse_quote! {
+            emplace_return!("ns::make_widget")
+        };
+        assert!(config.is_emplace_return("ns::make_widget"));
+        assert!(!config.is_emplace_return("make_widget"));
+    }
+
+    #[test]
+    fn test_name() {
+        let config: IncludeCppConfig = parse_quote! {
+            name!(graphics_ffi)
+            generate!("draw")
+        };
+        assert_eq!(config.get_mod_name(), "graphics_ffi");
+        assert_eq!(config.get_rs_filename(), "autocxx-graphics_ffi-gen.rs");
+        assert_eq!(config.uniquify_name_per_mod("draw"), "draw_graphics_ffi");
+        #[cfg(feature = "reproduction_case")]
+        {
+            let reparsed: IncludeCppConfig = syn::parse2(config.to_token_stream()).unwrap();
+            assert_eq!(reparsed.get_mod_name(), "graphics_ffi");
+        }
+        let default: IncludeCppConfig = parse_quote! {
+            generate!("draw")
+        };
+        assert_eq!(default.get_mod_name(), "ffi");
+        assert_eq!(default.uniquify_name_per_mod("draw"), "draw");
+    }
+
+    #[test]
+    fn test_tuple_return() {
+        let config: IncludeCppConfig = parse_quote! {
+            tuple_return!("ns::parse")
+        };
+        assert!(config.is_tuple_return("ns::parse"));
+        assert!(!config.is_tuple_return("parse"));
+    }
+
+    #[test]
+    fn test_string_return() {
+        let config: IncludeCppConfig = parse_quote! {
+            string_return!("ns::name")
+            string_return!("ns::Widget::label", strict)
+        };
+        assert_eq!(
+            config.get_string_return("ns::name"),
+            Some(StringReturn::Lossy)
+        );
+        assert_eq!(
+            config.get_string_return("ns::Widget::label"),
+            Some(StringReturn::Strict)
+        );
+        assert_eq!(config.get_string_return("name"), None);
+        assert!(syn::parse_str::<IncludeCppConfig>("string_return!(\"name\", lossy)").is_err());
+    }
+
+    #[test]
+    fn test_symbol_resolver() {
+        let config: IncludeCppConfig = parse_quote! {
+            symbol_resolver!(crate::plugin::Resolver)
+        };
+        let path = config.get_symbol_resolver().unwrap();
+        assert_eq!(
+            path.to_token_stream().to_string(),
+            "crate :: plugin :: Resolver"
+        );
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(config.get_symbol_resolver().is_none());
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            "symbol_resolver!(crate::One) symbol_resolver!(crate::Two)"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_fn_table() {
+        let config: IncludeCppConfig = parse_quote! {
+            fn_table!("io::Ops")
+        };
+        assert!(config.is_fn_table("io::Ops"));
+        assert!(!config.is_fn_table("Ops"));
+    }
+
+    #[test]
+    fn test_c_str_return() {
+        let config: IncludeCppConfig = parse_quote! {
+            c_str_return!("ns::Widget::c_str")
+        };
+        assert!(config.is_c_str_return("ns::Widget::c_str"));
+        assert!(!config.is_c_str_return("Widget::c_str"));
+    }
+
+    #[test]
+    fn test_smart_pointer() {
+        let config: IncludeCppConfig = parse_quote! {
+            smart_pointer!("Handle", "Widget")
+            smart_pointer!("ns::Ptr", "ns::Widget", mut)
+        };
+        let handle = config.get_smart_pointer("Handle").unwrap();
+        assert_eq!(handle.target, "Widget");
+        assert!(!handle.mutable);
+        assert!(config.get_smart_pointer("ns::Ptr").unwrap().mutable);
+        assert!(config.get_smart_pointer("Ptr").is_none());
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"smart_pointer!("Ptr", "Widget", const)"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_select_overload() {
+        let config: IncludeCppConfig = parse_quote! {
+            select_overload!("twice", "(int) -> int")
+            select_overload!("ns::twice", "(std::pair<int, int>, const char*) -> double")
+            select_overload!("reset", "()")
+        };
+        let selected: Vec<_> = config.get_selected_overloads().collect();
+        assert_eq!(selected[0].0, "autocxx_overload_0_twice");
+        assert_eq!(selected[0].1.param_types, ["int"]);
+        assert_eq!(selected[1].0, "ns::autocxx_overload_1_twice");
+        assert_eq!(selected[1].1.return_type, "double");
+        assert_eq!(
+            selected[1].1.param_types,
+            ["std::pair<int, int>", "const char*"]
+        );
+        assert_eq!(selected[2].1.return_type, "void");
+        assert!(selected[2].1.param_types.is_empty());
+        assert_eq!(
+            config.selected_overload_for_probe("ns::autocxx_overload_1_twice"),
+            Some("twice")
+        );
+        assert!(config.has_selected_overload("ns::twice"));
+        assert!(!config.has_selected_overload("thrice"));
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"select_overload!("f", "int(int)")"#).is_err()
+        );
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"select_overload!("f", "(int) ->")"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_instantiate_fn_named() {
+        let config: IncludeCppConfig = parse_quote! {
+            instantiate_fn_named!("foo", "<int, 3>", "foo_int_3")
+            instantiate_fn_named!("ns::foo", " <double> ", "foo_double")
+        };
+        let named: Vec<_> = config.get_named_instantiations().collect();
+        assert_eq!(named[0].0, "autocxx_instantiation_foo_int_3");
+        assert_eq!(named[0].1.instantiated_name(), "foo<int, 3>");
+        assert_eq!(named[1].0, "ns::autocxx_instantiation_foo_double");
+        assert_eq!(named[1].1.instantiated_name(), "foo<double>");
+        assert_eq!(
+            config
+                .named_instantiation_for_probe("ns::autocxx_instantiation_foo_double")
+                .map(|named| named.rust_name.as_str()),
+            Some("foo_double")
+        );
+        assert!(config
+            .named_instantiation_for_probe("autocxx_instantiation_foo_double")
+            .is_none());
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"instantiate_fn_named!("foo", "int, 3", "foo_int_3")"#
+        )
+        .is_err());
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"instantiate_fn_named!("foo", "<int>", "foo<int>")"#
+        )
+        .is_err());
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"instantiate_fn_named!("foo", "<int>", "f") instantiate_fn_named!("foo", "<long>", "f")"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_name_ctor() {
+        let config: IncludeCppConfig = parse_quote! {
+            name_ctor!("Point", "(int, int)", "from_coords")
+            name_ctor!("ns::Point", "(const ns::Point &, double)", "scaled")
+            name_ctor!("Point", "()", "origin")
+        };
+        assert_eq!(
+            config.get_ctor_name("Point", &["int".into(), "int".into()]),
+            Some("from_coords")
+        );
+        assert_eq!(
+            config.get_ctor_name("ns::Point", &["const ns::Point&".into(), "double".into()]),
+            Some("scaled")
+        );
+        assert_eq!(config.get_ctor_name("Point", &[]), Some("origin"));
+        assert_eq!(config.get_ctor_name("Point", &["int".into()]), None);
+        assert_eq!(
+            config.get_ctor_name("ns::Point", &["int".into(), "int".into()]),
+            None
+        );
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"name_ctor!("Point", "(int) -> Point", "from_int")"#
+        )
+        .is_err());
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"name_ctor!("Point", "(int)", "from int")"#)
+                .is_err()
+        );
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"name_ctor!("Point", "(int)", "make") name_ctor!("Point", "(double)", "make")"#
+        )
+        .is_err());
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"name_ctor!("Point", "(int)", "a") name_ctor!("Point", "( int )", "b")"#
+        )
+        .is_err());
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"name_ctor!("Point", "(int)", "new")"#).is_err()
+        );
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"name_ctor!("Point", "(int)", "new2")"#).is_err()
+        );
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"name_ctor!("Point", "(int)", "newer")"#).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_macro_function() {
+        let config: IncludeCppConfig = parse_quote! {
+            macro_function!("MAKE_PAIR", "std::pair<int, int>(int, const char*)")
+            macro_function!("VERSION", "int(void)")
+        };
+        let functions = config.get_macro_functions();
+        assert_eq!(functions[0].return_type, "std::pair<int, int>");
+        assert_eq!(functions[0].param_types, ["int", "const char*"]);
+        assert!(functions[1].param_types.is_empty());
+        assert_eq!(
+            config.macro_for_probe("autocxx_macro_VERSION"),
+            Some("VERSION")
+        );
+        assert_eq!(config.macro_for_probe("VERSION"), None);
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"macro_function!("F", "int")"#).is_err());
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"macro_function!("F", "(int)")"#).is_err());
+    }
+
+    #[test]
+    fn test_lifetime() {
+        let config: IncludeCppConfig = parse_quote! {
+            lifetime!("A::get", "return", "from", "this")
+        };
+        assert_eq!(config.get_return_lifetime_source("A::get"), Some("this"));
+        assert_eq!(config.get_return_lifetime_source("A::set"), None);
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"lifetime!("A::get", "returns", "from", "this")"#
+        )
+        .is_err());
+    }
 }
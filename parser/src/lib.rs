@@ -17,7 +17,12 @@ pub mod file_locations;
 mod path;
 mod subclass_attrs;
 
-pub use config::{IncludeCppConfig, RustFun, Subclass, UnsafePolicy};
+pub use config::{
+    inherited_method_probe_name, macro_probe_name, thread_local_probe_name, Awaitable,
+    ClosureCallback, FlagsEnum, IncludeCppConfig, InheritedMethod, IntrusiveRefcount, Iterable,
+    IteratorCategory, MacroFunction, NamedInstantiation, Resource, RustFun, SelectedOverload,
+    SliceParam, SmartPointer, StringReturn, Subclass, TargetCondition, UnsafePolicy, Validity,
+};
 use file_locations::FileLocationStrategy;
 pub use path::RustPath;
 use proc_macro2::TokenStream as TokenStream2;
@@ -15,10 +15,14 @@
 use crate::ParseResult;
 use proc_macro2::Ident;
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::parse::{Parse, ParseStream};
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+};
 
 /// A little like [`syn::Path`] but simpler - contains only identifiers,
 /// no path arguments. Guaranteed to always have at least one identifier.
+/// The first may be `crate`, `self` or `super`.
 #[derive(Debug, Clone)]
 pub struct RustPath(Vec<Ident>);
 
@@ -54,7 +58,7 @@ impl ToTokens for RustPath {
 
 impl Parse for RustPath {
     fn parse(input: ParseStream) -> ParseResult<Self> {
-        let id: Ident = input.parse()?;
+        let id = input.call(Ident::parse_any)?;
         let mut p = RustPath::new_from_ident(id);
         while input.parse::<Option<syn::token::Colon2>>()?.is_some() {
             let id: Ident = input.parse()?;
@@ -0,0 +1,114 @@
+//! Used by generated code to borrow the arrays returned by functions
+//! listed in [`array_return`](crate::array_return!) and
+//! [`null_terminated_array_return`](crate::null_terminated_array_return!).
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{convert::TryInto, ffi::CStr, os::raw::c_char};
+
+/// An integer which gives the length of an array.
+pub trait ArrayLength {
+    /// The length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's negative or too big for a `usize`.
+    fn array_length(self) -> usize;
+}
+
+macro_rules! primitive_array_length {
+    ($($t:ty),*) => {
+        $(
+            impl ArrayLength for $t {
+                fn array_length(self) -> usize {
+                    self.try_into().expect("array length out of range")
+                }
+            }
+        )*
+    };
+}
+
+primitive_array_length!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! ctype_array_length {
+    ($($t:ident),*) => {
+        $(
+            impl ArrayLength for crate::$t {
+                fn array_length(self) -> usize {
+                    self.0.array_length()
+                }
+            }
+        )*
+    };
+}
+
+ctype_array_length!(
+    c_int,
+    c_uint,
+    c_long,
+    c_ulong,
+    c_longlong,
+    c_ulonglong,
+    c_short,
+    c_ushort,
+    c_uchar
+);
+
+/// Borrows an array of `len` elements. A null array is an empty slice.
+///
+/// # Safety
+///
+/// `ptr` must be null, or point to `len` initialized elements, which
+/// mustn't change or be freed for the lifetime `'a`.
+pub unsafe fn borrow_array<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if ptr.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Borrows the elements of an array of pointers which ends with a null
+/// pointer. A null array has no elements.
+///
+/// # Safety
+///
+/// `ptr` must be null, or point to an array of valid pointers which ends
+/// with a null one. Neither the array nor what its elements point to may
+/// change or be freed for the lifetime `'a`.
+pub unsafe fn borrow_null_terminated<'a, T>(mut ptr: *const *const T) -> Vec<&'a T> {
+    let mut elems = Vec::new();
+    if !ptr.is_null() {
+        while !(*ptr).is_null() {
+            elems.push(&**ptr);
+            ptr = ptr.add(1);
+        }
+    }
+    elems
+}
+
+/// Borrows the strings in an array of `char*` which ends with a null
+/// pointer. A null array has no strings.
+///
+/// # Safety
+///
+/// As for [`borrow_null_terminated`], and each element must point to a
+/// nul-terminated string.
+pub unsafe fn borrow_null_terminated_strings<'a>(ptr: *const *const c_char) -> Vec<&'a CStr> {
+    borrow_null_terminated(ptr)
+        .into_iter()
+        .map(|s| CStr::from_ptr(s))
+        .collect()
+}
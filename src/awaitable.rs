@@ -0,0 +1,112 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use cxx::{memory::UniquePtrTarget, UniquePtr};
+
+/// A C++ type which completes in steps, such as the task type returned by
+/// a coroutine. This is implemented for types listed in
+/// [`register_awaitable`](crate::register_awaitable!), so that they can be
+/// awaited by means of a [`CppFuture`].
+pub trait Awaitable {
+    /// What the C++ object results in once it's done.
+    type Output;
+
+    /// Runs the C++ object until its next suspension point.
+    fn resume(self: Pin<&mut Self>);
+
+    /// Whether the C++ object has finished.
+    fn is_done(&self) -> bool;
+
+    /// Takes the result, once the C++ object has finished.
+    fn result(self: Pin<&mut Self>) -> Self::Output;
+}
+
+/// A [`Future`] which drives a C++ [`Awaitable`], resuming it each time
+/// it's polled until it's done.
+///
+/// C++ has no way to tell Rust when it would be worth resuming the object
+/// again, so each time it's still not done, this asks to be polled again
+/// straight away. The object is resumed on whichever thread polls the
+/// future, which for most C++ types, since they're not [`Send`], is the
+/// thread which created it; if a coroutine must run on one specific
+/// thread, poll it using an executor which runs there.
+///
+/// C++ exceptions can't be caught, so one thrown by the coroutine ends the
+/// program; a coroutine which may fail should instead return its error as
+/// part of its result.
+pub struct CppFuture<T: Awaitable + UniquePtrTarget> {
+    task: UniquePtr<T>,
+}
+
+impl<T: Awaitable + UniquePtrTarget> CppFuture<T> {
+    /// Takes ownership of a C++ object, which must not be null, so that it
+    /// may be awaited.
+    pub fn new(task: UniquePtr<T>) -> Self {
+        assert!(!task.is_null(), "awaited a null C++ object");
+        Self { task }
+    }
+}
+
+impl<T: Awaitable + UniquePtrTarget> From<UniquePtr<T>> for CppFuture<T> {
+    fn from(task: UniquePtr<T>) -> Self {
+        Self::new(task)
+    }
+}
+
+// The C++ object is on the heap, so doesn't move when the future does.
+impl<T: Awaitable + UniquePtrTarget> Unpin for CppFuture<T> {}
+
+impl<T: Awaitable + UniquePtrTarget> Future for CppFuture<T> {
+    type Output = T::Output;
+
+    /// # Panics
+    ///
+    /// Panics if polled again after it has returned [`Poll::Ready`].
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T::Output> {
+        let this = self.get_mut();
+        assert!(
+            !this.task.is_null(),
+            "polled a CppFuture after it completed"
+        );
+        let mut task = this.task.pin_mut();
+        if !task.is_done() {
+            task.as_mut().resume();
+        }
+        if task.is_done() {
+            let result = task.result();
+            // Destroy the C++ object now, rather than keeping it around for
+            // as long as the future.
+            this.task = UniquePtr::null();
+            Poll::Ready(result)
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+impl<T: Awaitable + UniquePtrTarget> fmt::Debug for CppFuture<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CppFuture")
+            .field("done", &(self.task.is_null() || self.task.is_done()))
+            .finish()
+    }
+}
@@ -0,0 +1,231 @@
+//! A Rust equivalent of `std::bitset`. See [`bitset`](crate::bitset!).
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+/// A fixed-size set of `N` bits, stored in `WORDS` 64-bit words. This is
+/// how `autocxx` represents a C++ `std::bitset<N>`; the generated code
+/// names it by a type alias, so you shouldn't normally need to work out
+/// `WORDS` yourself. If you do, it must be [`words_needed`]`(N)`.
+///
+/// Like a `std::bitset`, this is a plain value: it is [`Copy`] and is
+/// passed to and from C++ by value. Bit 0 is the least significant bit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitSet<const N: usize, const WORDS: usize> {
+    // Bits beyond N are always zero.
+    words: [u64; WORDS],
+}
+
+impl<const N: usize, const WORDS: usize> BitSet<N, WORDS> {
+    /// A set with no bits set.
+    pub fn new() -> Self {
+        assert_eq!(
+            WORDS,
+            words_needed(N),
+            "BitSet<{}> needs {} words",
+            N,
+            words_needed(N)
+        );
+        Self { words: [0; WORDS] }
+    }
+
+    /// The number of bits, `N`.
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    /// Whether this has no bits at all, i.e. whether `N` is zero.
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Whether bit `i` is set. Panics if `i` is out of range.
+    pub fn get(&self, i: usize) -> bool {
+        let (word, mask) = Self::locate(i);
+        self.words[word] & mask != 0
+    }
+
+    /// Sets bit `i`. Panics if `i` is out of range.
+    pub fn set(&mut self, i: usize) -> &mut Self {
+        let (word, mask) = Self::locate(i);
+        self.words[word] |= mask;
+        self
+    }
+
+    /// Clears bit `i`. Panics if `i` is out of range.
+    pub fn reset(&mut self, i: usize) -> &mut Self {
+        let (word, mask) = Self::locate(i);
+        self.words[word] &= !mask;
+        self
+    }
+
+    /// Toggles bit `i`. Panics if `i` is out of range.
+    pub fn flip(&mut self, i: usize) -> &mut Self {
+        let (word, mask) = Self::locate(i);
+        self.words[word] ^= mask;
+        self
+    }
+
+    /// The number of bits which are set.
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Whether any bit is set.
+    pub fn any(&self) -> bool {
+        self.words.iter().any(|w| *w != 0)
+    }
+
+    /// Whether every bit is set.
+    pub fn all(&self) -> bool {
+        self.count() == N
+    }
+
+    /// The underlying words, least significant first.
+    #[doc(hidden)]
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Builds a set from words as produced by the generated C++. Any bits
+    /// beyond `N` are ignored.
+    #[doc(hidden)]
+    pub fn from_words(words: &[u64]) -> Self {
+        let mut result = Self::new();
+        for (dest, src) in result.words.iter_mut().zip(words) {
+            *dest = *src;
+        }
+        result.clear_unused_bits();
+        result
+    }
+
+    fn locate(i: usize) -> (usize, u64) {
+        assert!(i < N, "bit {} out of range for BitSet<{}>", i, N);
+        (i / 64, 1u64 << (i % 64))
+    }
+
+    fn clear_unused_bits(&mut self) {
+        let bits_in_last_word = N % 64;
+        if bits_in_last_word > 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1u64 << bits_in_last_word) - 1;
+            }
+        }
+    }
+}
+
+/// The number of 64-bit words needed to store `bits` bits.
+pub const fn words_needed(bits: usize) -> usize {
+    (bits >> 6) + (bits & 63 != 0) as usize
+}
+
+impl<const N: usize, const WORDS: usize> Default for BitSet<N, WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shows the bits most significant first, as `std::bitset::to_string`
+/// does.
+impl<const N: usize, const WORDS: usize> Display for BitSet<N, WORDS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for i in (0..N).rev() {
+            f.write_str(if self.get(i) { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, const WORDS: usize> Debug for BitSet<N, WORDS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BitSet<{}>({})", N, self)
+    }
+}
+
+macro_rules! bitwise_op {
+    ($trt:ident, $method:ident, $assign_trt:ident, $assign_method:ident, $op:tt) => {
+        impl<const N: usize, const WORDS: usize> $assign_trt for BitSet<N, WORDS> {
+            fn $assign_method(&mut self, rhs: Self) {
+                for (a, b) in self.words.iter_mut().zip(rhs.words.iter()) {
+                    *a = *a $op *b;
+                }
+            }
+        }
+
+        impl<const N: usize, const WORDS: usize> $trt for BitSet<N, WORDS> {
+            type Output = Self;
+            fn $method(mut self, rhs: Self) -> Self {
+                self.$assign_method(rhs);
+                self
+            }
+        }
+    };
+}
+
+bitwise_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bitwise_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bitwise_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl<const N: usize, const WORDS: usize> Not for BitSet<N, WORDS> {
+    type Output = Self;
+    fn not(mut self) -> Self {
+        for w in self.words.iter_mut() {
+            *w = !*w;
+        }
+        self.clear_unused_bits();
+        self
+    }
+}
+
+/// The error when a value doesn't fit: either a `u64` with bits set
+/// beyond `N`, or a `BitSet` with bits set beyond the 64 a `u64` can hold.
+/// The equivalent of the `std::overflow_error` thrown by
+/// `std::bitset::to_ullong`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitSetOverflow;
+
+impl Display for BitSetOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("value does not fit in the available bits")
+    }
+}
+
+impl std::error::Error for BitSetOverflow {}
+
+impl<const N: usize, const WORDS: usize> TryFrom<u64> for BitSet<N, WORDS> {
+    type Error = BitSetOverflow;
+    fn try_from(value: u64) -> Result<Self, BitSetOverflow> {
+        let result = Self::from_words(&[value]);
+        if result.words.first().copied().unwrap_or_default() == value {
+            Ok(result)
+        } else {
+            Err(BitSetOverflow)
+        }
+    }
+}
+
+impl<const N: usize, const WORDS: usize> TryFrom<BitSet<N, WORDS>> for u64 {
+    type Error = BitSetOverflow;
+    fn try_from(value: BitSet<N, WORDS>) -> Result<Self, BitSetOverflow> {
+        match value.words.split_first() {
+            None => Ok(0),
+            Some((first, rest)) if rest.iter().all(|w| *w == 0) => Ok(*first),
+            Some(_) => Err(BitSetOverflow),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Used by generated code to pass strings to the `const char*` parameters
+//! listed in [`c_str_param`](crate::c_str_param!).
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    borrow::Cow,
+    ffi::{CStr, CString},
+};
+
+/// A string which may be passed to C++ as a `const char*`. A [`CStr`] is
+/// passed as it is. A Rust string is copied into a temporary [`CString`],
+/// which lasts only until the end of the statement which makes the call,
+/// so C++ mustn't keep the pointer.
+pub trait CStrArg {
+    /// The string, terminated by a NUL.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Rust string contains a NUL, since C++ would take that
+    /// to be the end of it.
+    fn as_c_str(&self) -> Cow<'_, CStr>;
+}
+
+impl CStrArg for &CStr {
+    fn as_c_str(&self) -> Cow<'_, CStr> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl CStrArg for &CString {
+    fn as_c_str(&self) -> Cow<'_, CStr> {
+        Cow::Borrowed(CString::as_c_str(self))
+    }
+}
+
+impl CStrArg for &str {
+    fn as_c_str(&self) -> Cow<'_, CStr> {
+        Cow::Owned(to_c_string(self))
+    }
+}
+
+impl CStrArg for &String {
+    fn as_c_str(&self) -> Cow<'_, CStr> {
+        Cow::Owned(to_c_string(self))
+    }
+}
+
+fn to_c_string(s: &str) -> CString {
+    CString::new(s)
+        .expect("a string passed to C++ as a const char* contains a NUL, which would end it early")
+}
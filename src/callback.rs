@@ -0,0 +1,152 @@
+//! Support for C++ functions which take a callback, as a function pointer
+//! and a `void*` context, which Rust passes as a closure. See
+//! [`closure_callback`](crate::closure_callback!).
+//!
+//! A `std::function` parameter is passed as a closure too, with no need
+//! for a directive. The `std::function`, and every copy C++ makes of it,
+//! shares ownership of the closure, so it's freed once C++ has destroyed
+//! them all: for instance, when an event handler which C++ stored is
+//! unregistered. Its signature may use only pointers, integers and other
+//! C types.
+//!
+//! Either way, the closure must be [`Send`], since C++ may call it on any
+//! thread. It's an [`FnMut`], so mustn't run twice at once: if C++ calls
+//! it again while it's running, whether from within it or from another
+//! thread, the process aborts.
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt,
+    panic::AssertUnwindSafe,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::c_void;
+
+/// What the context pointer points to. The functions to free it and, for
+/// a `std::function`, to call it come first, so that C++ can find them
+/// without knowing the closure's type.
+#[repr(C)]
+struct Context<F> {
+    free: unsafe extern "C" fn(*mut c_void),
+    call: *const c_void,
+    running: AtomicBool,
+    closure: F,
+}
+
+impl<F> Context<F> {
+    fn new_raw(closure: F, call: *const c_void) -> *mut c_void {
+        Box::into_raw(Box::new(Self {
+            free: free_context::<F>,
+            call,
+            running: AtomicBool::new(false),
+            closure,
+        })) as *mut c_void
+    }
+}
+
+unsafe extern "C" fn free_context<F>(context: *mut c_void) {
+    // Dropping the closure could panic, which can't unwind into C++.
+    if std::panic::catch_unwind(AssertUnwindSafe(|| {
+        drop(Box::from_raw(context as *mut Context<F>))
+    }))
+    .is_err()
+    {
+        std::process::abort()
+    }
+}
+
+/// A closure which has been registered with C++ as a callback. This is
+/// what functions listed in [`closure_callback`](crate::closure_callback!)
+/// return.
+///
+/// C++ may call the closure for as long as it's registered, which Rust
+/// can't know, so dropping this does not free the closure: it's leaked.
+/// If C++ provides a way to unregister the callback, you can instead free
+/// the closure afterwards by calling [`CallbackHandle::free`].
+pub struct CallbackHandle {
+    context: *mut c_void,
+}
+
+impl CallbackHandle {
+    #[doc(hidden)]
+    pub fn new<F: Send + 'static>(closure: F) -> Self {
+        Self {
+            context: Context::new_raw(closure, std::ptr::null()),
+        }
+    }
+
+    /// The context pointer which was passed to C++ along with the
+    /// callback, for instance to pass to a function which unregisters it.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.context
+    }
+
+    /// Frees the closure.
+    ///
+    /// # Safety
+    ///
+    /// C++ must never call the callback again, for instance because it
+    /// has been unregistered, and must not be in the middle of calling it.
+    pub unsafe fn free(self) {
+        let free = *(self.context as *const unsafe extern "C" fn(*mut c_void));
+        free(self.context)
+    }
+}
+
+unsafe impl Send for CallbackHandle {}
+unsafe impl Sync for CallbackHandle {}
+
+impl fmt::Debug for CallbackHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CallbackHandle")
+            .field(&self.context)
+            .finish()
+    }
+}
+
+/// Makes a context for a closure, to give to C++ for a `std::function`
+/// which takes ownership of it. `call` is the function which calls the
+/// closure, given the context.
+#[doc(hidden)]
+pub fn owned_context<F: Send + 'static>(closure: F, call: *const c_void) -> *mut c_void {
+    Context::new_raw(closure, call)
+}
+
+/// Calls the closure behind a context pointer given to C++, by a
+/// [`CallbackHandle`] or for a `std::function`. A panic can't unwind into
+/// C++, so aborts instead, as does calling the closure while it's already
+/// running.
+///
+/// # Safety
+///
+/// `context` must be a context for a closure of type `F`, which has not
+/// been freed.
+#[doc(hidden)]
+pub unsafe fn call_closure<F, R>(context: *mut c_void, call: impl FnOnce(&mut F) -> R) -> R {
+    let context = context as *mut Context<F>;
+    if (*context).running.swap(true, Ordering::Acquire) {
+        eprintln!("autocxx: a closure was called by C++ while it was already running");
+        std::process::abort()
+    }
+    let closure = &mut (*context).closure;
+    let ret = match std::panic::catch_unwind(AssertUnwindSafe(|| call(closure))) {
+        Ok(ret) => ret,
+        Err(_) => std::process::abort(),
+    };
+    (*context).running.store(false, Ordering::Release);
+    ret
+}
@@ -0,0 +1,32 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cxx::{memory::UniquePtrTarget, UniquePtr};
+
+/// A type which can be stored in a C++ `std::any`, `A`, and downcast from
+/// one. This is implemented for types listed in
+/// [`any_castable`](crate::any_castable!), and used by the `wrap` and
+/// `downcast_ref` methods of the generated `CppAny`.
+///
+/// # Safety
+///
+/// `any_cast` must return either null, or a pointer to the value held by
+/// the `std::any`, which lasts as long as it does.
+pub unsafe trait AnyCastable<A: UniquePtrTarget> {
+    #[doc(hidden)]
+    fn any_cast(any: &A) -> *const Self;
+
+    #[doc(hidden)]
+    fn wrap(value: &Self) -> UniquePtr<A>;
+}
@@ -0,0 +1,35 @@
+//! Used by generated code to convert the C++ types listed in
+//! [`type_conversion`](crate::type_conversion!) to and from Rust types.
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cxx::{memory::UniquePtrTarget, UniquePtr};
+
+/// A Rust type which stands in for the C++ type `Cpp` when it's passed to
+/// or returned from C++ functions. Implement this for the Rust type given
+/// in [`type_conversion`](crate::type_conversion!).
+///
+/// Either way, the values are copies: C++ never sees the Rust value, nor
+/// Rust the C++ one, so changes to one aren't seen in the other.
+pub trait CppConversion<Cpp: UniquePtrTarget>: Sized {
+    /// Makes the Rust value from a C++ one which a function returned, by
+    /// value or by reference.
+    fn from_cpp(cpp: &Cpp) -> Self;
+
+    /// Makes the C++ value to pass to a function. It's moved into a
+    /// parameter taken by value, and lasts until the end of the statement
+    /// which makes the call for one taken by reference.
+    fn to_cpp(&self) -> UniquePtr<Cpp>;
+}
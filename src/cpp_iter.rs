@@ -0,0 +1,113 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{marker::PhantomData, pin::Pin};
+
+use cxx::{memory::UniquePtrTarget, UniquePtr};
+
+/// A C++ container which can be iterated over from Rust, from its
+/// `begin()` to its `end()`. This is implemented for containers listed in
+/// [`iterable`](crate::iterable!), as are [`IntoIterator`] for references to
+/// them, giving a [`CppIter`].
+///
+/// # Safety
+///
+/// `next` must return either null, once the cursor has reached the end,
+/// or a pointer to an element of the container, which lasts as long as
+/// the container does unless it's changed. `len`, if it gives a length,
+/// must give the number of elements which remain.
+pub unsafe trait CppIterable {
+    /// The type of the elements.
+    type Element;
+
+    #[doc(hidden)]
+    type Cursor: UniquePtrTarget;
+
+    #[doc(hidden)]
+    fn begin(&self) -> UniquePtr<Self::Cursor>;
+
+    #[doc(hidden)]
+    fn next(cursor: Pin<&mut Self::Cursor>) -> *const Self::Element;
+
+    #[doc(hidden)]
+    fn len(_cursor: &Self::Cursor) -> Option<usize> {
+        None
+    }
+}
+
+/// A C++ container whose iterators can go backwards, with `operator--`.
+/// Iterating over it gives a [`DoubleEndedIterator`].
+///
+/// # Safety
+///
+/// As for [`CppIterable::next`], but from the other end.
+pub unsafe trait CppDoubleEndedIterable: CppIterable {
+    #[doc(hidden)]
+    fn next_back(cursor: Pin<&mut Self::Cursor>) -> *const Self::Element;
+}
+
+/// A C++ container whose iterators are random-access. Iterating over it
+/// gives an [`ExactSizeIterator`].
+///
+/// # Safety
+///
+/// [`CppIterable::len`] must always give the length.
+pub unsafe trait CppExactSizeIterable: CppDoubleEndedIterable {}
+
+/// An iterator over the elements of a C++ container. Like the C++
+/// iterators it uses, it borrows the container, which can't be changed by
+/// Rust meanwhile.
+pub struct CppIter<'a, C: CppIterable> {
+    cursor: UniquePtr<C::Cursor>,
+    container: PhantomData<&'a C>,
+}
+
+impl<'a, C: CppIterable> CppIter<'a, C> {
+    /// An iterator over all the elements of the container.
+    pub fn new(container: &'a C) -> Self {
+        Self {
+            cursor: container.begin(),
+            container: PhantomData,
+        }
+    }
+}
+
+impl<'a, C: CppIterable> Iterator for CppIter<'a, C>
+where
+    C::Element: 'a,
+{
+    type Item = &'a C::Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe { C::next(self.cursor.pin_mut()).as_ref() }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match C::len(&self.cursor) {
+            Some(len) => (len, Some(len)),
+            None => (0, None),
+        }
+    }
+}
+
+impl<'a, C: CppDoubleEndedIterable> DoubleEndedIterator for CppIter<'a, C>
+where
+    C::Element: 'a,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        unsafe { C::next_back(self.cursor.pin_mut()).as_ref() }
+    }
+}
+
+impl<'a, C: CppExactSizeIterable> ExactSizeIterator for CppIter<'a, C> where C::Element: 'a {}
@@ -0,0 +1,98 @@
+//! Support for C++ functions which report failure through a
+//! `std::error_code`. See [`error_code_param`](crate::error_code_param!).
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Must match the generated C++ in `autocxx_throw_if_error_code`.
+const PREFIX: &str = "autocxx_error_code";
+const SEPARATOR: char = '\u{1f}';
+
+/// A `std::error_code` from a category other than the system or generic
+/// ones, which have no equivalent in Rust. This is found inside the
+/// `std::io::Error`, which you can get at using
+/// [`get_ref`](std::io::Error::get_ref) and then
+/// [`downcast_ref`](std::error::Error::downcast_ref).
+#[derive(Debug, Clone)]
+pub struct CppErrorCode {
+    value: i32,
+    category: String,
+    message: String,
+}
+
+impl CppErrorCode {
+    /// The value of the `std::error_code`.
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// The name of the `std::error_category`.
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    /// The message given by the `std::error_code`.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for CppErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}:{})", self.message, self.category, self.value)
+    }
+}
+
+impl std::error::Error for CppErrorCode {}
+
+/// Used by generated code to turn a `std::error_code` which was set by
+/// a C++ function into an `Err`. Any other C++ exception becomes a
+/// panic, as it would with `safety!(exceptions_as_panics)`.
+#[doc(hidden)]
+#[track_caller]
+pub fn into_io_result<T>(result: Result<T, cxx::Exception>) -> io::Result<T> {
+    result.map_err(|e| match error_from_what(e.what()) {
+        Some(err) => err,
+        None => panic!("{}", e.what()),
+    })
+}
+
+#[allow(clippy::io_other_error)] // io::Error::other needs a newer Rust
+fn error_from_what(what: &str) -> Option<io::Error> {
+    let mut fields = what.splitn(4, SEPARATOR);
+    if fields.next() != Some(PREFIX) {
+        return None;
+    }
+    let value = fields.next()?.parse().ok()?;
+    let category = fields.next()?;
+    let message = fields.next()?;
+    Some(match category {
+        "system" => io::Error::from_raw_os_error(value),
+        // Generic errors are errno values, which is what Unix uses for OS
+        // errors but not what Windows uses.
+        #[cfg(unix)]
+        "generic" => io::Error::from_raw_os_error(value),
+        _ => io::Error::new(
+            io::ErrorKind::Other,
+            CppErrorCode {
+                value,
+                category: category.to_string(),
+                message: message.to_string(),
+            },
+        ),
+    })
+}
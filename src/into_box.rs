@@ -0,0 +1,54 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cxx::{memory::UniquePtrTarget, ExternType, UniquePtr};
+use moveit::MakeCppStorage;
+
+/// Moves a C++ object out of a [`cxx::UniquePtr`] into a Rust [`Box`], so
+/// that it's owned the way any other Rust value is. This is implemented
+/// for types generated with [`generate_pod`](crate::generate_pod!), which
+/// cxx knows may be moved with a bitwise copy, and not for other types,
+/// which may refer to their own address.
+///
+/// The `Box` never takes over the C++ allocation, which came from C++
+/// `new` and so mustn't be freed by Rust's allocator. Instead, the object
+/// is copied into memory allocated by Rust, and the C++ allocation freed
+/// without running the object's destructor, since the object now lives
+/// in the `Box`.
+/// ```ignore
+/// let point: Box<ffi::Point> = ffi::Point::make_unique().into_box();
+/// ```
+pub trait IntoBox<T> {
+    /// Moves the object into a `Box`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pointer is null.
+    fn into_box(self) -> Box<T>;
+}
+
+impl<T> IntoBox<T> for UniquePtr<T>
+where
+    T: UniquePtrTarget + MakeCppStorage + ExternType<Kind = cxx::kind::Trivial>,
+{
+    fn into_box(self) -> Box<T> {
+        assert!(!self.is_null(), "into_box called on a null UniquePtr");
+        let ptr = self.into_raw();
+        unsafe {
+            let boxed = Box::new(std::ptr::read(ptr));
+            T::free_uninitialized_cpp_storage(ptr);
+            boxed
+        }
+    }
+}
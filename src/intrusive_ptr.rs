@@ -0,0 +1,137 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, ops::Deref, ptr::NonNull};
+
+/// A C++ type which counts references to itself. This is implemented for
+/// types listed in [`intrusive_refcount`](crate::intrusive_refcount!), so
+/// that they can be held in an [`IntrusivePtr`].
+///
+/// # Safety
+///
+/// `add_ref` must add a reference to the object, and `release` must
+/// remove one, freeing the object once none remain.
+pub unsafe trait IntrusiveRefcounted {
+    /// Adds a reference to the object.
+    ///
+    /// # Safety
+    ///
+    /// `this` must point to a valid object.
+    unsafe fn add_ref(this: *mut Self);
+
+    /// Releases a reference to the object, which may free it.
+    ///
+    /// # Safety
+    ///
+    /// `this` must point to a valid object, and the caller must own the
+    /// reference being released.
+    unsafe fn release(this: *mut Self);
+}
+
+/// A pointer to a C++ object which counts references to itself, much like
+/// an [`std::rc::Rc`]. Cloning an `IntrusivePtr` adds a reference, and
+/// dropping one releases it. Unlike a [`cxx::UniquePtr`], it's never null.
+///
+/// An `IntrusivePtr` may be sent to, or shared with, other threads only if
+/// the C++ type is [`Send`] and [`Sync`]. That's not the case by default;
+/// if the reference counting is atomic, and the object may be used from
+/// several threads at once, you can say so with `unsafe impl Send` and
+/// `unsafe impl Sync` for the type.
+pub struct IntrusivePtr<T: IntrusiveRefcounted> {
+    ptr: NonNull<T>,
+}
+
+impl<T: IntrusiveRefcounted> IntrusivePtr<T> {
+    /// Takes over a reference which has already been counted, without
+    /// adding another. This suits a factory function which returns an
+    /// object with a reference count of one, or any other function which
+    /// returns a reference the caller must release. Returns `None` if
+    /// `ptr` is null.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null, or point to a valid object, and the caller must
+    /// own the reference which this will release on drop.
+    pub unsafe fn from_raw(ptr: *mut T) -> Option<Self> {
+        NonNull::new(ptr).map(|ptr| Self { ptr })
+    }
+
+    /// Adds a reference to an object, for instance one which a function
+    /// returned without counting a reference for the caller. Returns `None`
+    /// if `ptr` is null.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null, or point to a valid object.
+    pub unsafe fn from_raw_add_ref(ptr: *mut T) -> Option<Self> {
+        let result = Self::from_raw(ptr);
+        if let Some(result) = &result {
+            T::add_ref(result.ptr.as_ptr());
+        }
+        result
+    }
+
+    /// Returns the raw pointer, without affecting the reference count.
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns the raw pointer, without affecting the reference count.
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// Gives up this reference without releasing it. The caller becomes
+    /// responsible for releasing it.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr.as_ptr();
+        std::mem::forget(self);
+        ptr
+    }
+
+    /// Whether two pointers point to the same object.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+}
+
+impl<T: IntrusiveRefcounted> Clone for IntrusivePtr<T> {
+    fn clone(&self) -> Self {
+        unsafe { T::add_ref(self.ptr.as_ptr()) };
+        Self { ptr: self.ptr }
+    }
+}
+
+impl<T: IntrusiveRefcounted> Drop for IntrusivePtr<T> {
+    fn drop(&mut self) {
+        unsafe { T::release(self.ptr.as_ptr()) }
+    }
+}
+
+impl<T: IntrusiveRefcounted> Deref for IntrusivePtr<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+unsafe impl<T: IntrusiveRefcounted + Send + Sync> Send for IntrusivePtr<T> {}
+unsafe impl<T: IntrusiveRefcounted + Send + Sync> Sync for IntrusivePtr<T> {}
+
+impl<T: IntrusiveRefcounted> fmt::Debug for IntrusivePtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntrusivePtr").field(&self.ptr).finish()
+    }
+}
@@ -20,7 +20,32 @@
 // do anything - all the magic is handled entirely by
 // autocxx_macro::include_cpp_impl.
 
+#[doc(hidden)]
+pub mod array_return;
+mod awaitable;
+pub mod bitset;
+mod c_str;
+pub mod callback;
+mod cpp_any;
+mod cpp_conversion;
+mod cpp_iter;
+pub mod error_code;
+mod into_box;
+mod intrusive_ptr;
+pub mod offload;
+mod owned_handle;
+mod owned_ptr;
+#[doc(hidden)]
+pub mod path;
+mod promote;
 pub mod subclass;
+mod symbol_resolver;
+mod thread_local_ref;
+#[cfg(feature = "trace_calls")]
+#[doc(hidden)]
+pub mod trace;
+mod unique_array;
+mod vector_into_iter;
 
 #[allow(unused_imports)] // doc cross-reference only
 use autocxx_engine::IncludeCppEngine;
@@ -194,6 +219,20 @@ use autocxx_engine::IncludeCppEngine;
 ///   but not `autocxx` (for example, at the time of writing `std::array`). See
 ///   the later section on 'combinining automatic and manual bindings'.
 ///
+/// ## Tracing calls into C++
+///
+/// To help diagnose ordering and lifetime problems, you can log every call
+/// from Rust into C++. Enable the `trace_calls` feature of both this crate
+/// and `autocxx-build` (or pass `--trace-calls` to `autocxx-gen`). Each call
+/// is then logged at trace level, with target `autocxx::ffi`, using the
+/// [`log`](https://docs.rs/log) crate: once on entry and once on exit.
+/// Nested calls on the same thread - for instance where C++ calls back into
+/// a Rust subclass, which calls into C++ again - are indented. This slightly
+/// changes the generated code (every call goes through a Rust wrapper
+/// function, and every method through a C++ wrapper function) so it is
+/// intended only for debugging. If the feature is not enabled on this crate,
+/// the wrapper functions don't log anything.
+///
 /// # Allowlisting
 ///
 /// How do you inform autocxx which bindings to generate? There are three
@@ -242,6 +281,14 @@ use autocxx_engine::IncludeCppEngine;
 /// * If you know that your C++ type can be safely byte-copied, then you can
 ///   override this behavior by using [`generate_pod`] instead of [`generate`].
 ///
+/// So a setter taking a `std::string` by value, to move it into a member, gets
+/// the string without it being copied: a Rust `String` or `&str` is copied
+/// once into a new `std::string`, since the two can't share a buffer, and
+/// that's moved into the parameter, as is a `UniquePtr<CxxString>` you pass.
+/// The same goes for the parameters of virtual methods which a [`subclass`]
+/// overrides. Of course, a setter which copies its parameter into the member
+/// still makes that copy.
+///
 /// There's not a significant ergonomic problem from the use of [`cxx::UniquePtr`].
 /// The main negative of the automatic boxing into [`cxx::UniquePtr`] is performance:
 /// specifically, the need to
@@ -251,7 +298,10 @@ use autocxx_engine::IncludeCppEngine;
 /// anyway).
 ///
 /// If you want your type to be transferred between Rust and C++ truly _by value_
-/// then use [`generate_pod`] instead of [`generate`].
+/// then use [`generate_pod`] instead of [`generate`]. Functions taking or
+/// returning such a type by value then need no autocxx C++ wrapper, and
+/// nothing is allocated on the heap. cxx itself still passes the value by
+/// pointer across the language boundary, however small it is.
 ///
 /// Specifically, to be compatible with [`generate_pod`], your C++ type must either:
 /// * Lack a move constructor _and_ lack a destructor
@@ -267,6 +317,13 @@ use autocxx_engine::IncludeCppEngine;
 /// is not declared as POD-safe, then we'll generate wrapper functions to move
 /// that type into and out of [`cxx::UniquePtr`]s.
 ///
+/// That's impossible for a type which can be neither moved nor copied,
+/// for instance because it deletes both its copy and move constructors,
+/// or holds a field of such a type. You can still create such types in a
+/// [`cxx::UniquePtr`] using their constructors, and pass them to and from
+/// C++ by reference, but any function taking or returning them by value
+/// is skipped, with documentation saying why.
+///
 /// There is one other option under construction. The `moveit` crate replicates
 /// C++ value move and copying semantics in Rust. There is limited early support
 /// for `moveit` within autocxx; specifically, you can call C++ constructors
@@ -278,6 +335,18 @@ use autocxx_engine::IncludeCppEngine;
 /// is therefore best avoided for now until it's more complete - but see
 /// `examples/non-trivial-type-on-stack` if you want to see how to use it.
 ///
+/// A move constructor which isn't `noexcept` may throw. If it does, the
+/// exception becomes a Rust panic, as it would for any function with
+/// `safety!(exceptions_as_panics)`, whether or not that's given: the
+/// moved-from object, which C++ leaves valid, is still destroyed just once,
+/// and the destination is left uninitialized and isn't dropped. Whether a
+/// move constructor is `noexcept`, including by a `noexcept(expr)` which
+/// depends on template parameters, is up to the C++ compiler, for which
+/// catching an exception which can't be thrown costs nothing. Note that
+/// space allocated by [`moveit::EmplaceUnpinned::emplace`] for a
+/// [`cxx::UniquePtr`] isn't freed if the move panics, so prefer
+/// [`moveit::moveit`] or `Box::emplace` for types whose moves may throw.
+///
 /// ### References and pointers
 ///
 /// We follow [cxx] norms here. Specifically:
@@ -325,6 +394,23 @@ use autocxx_engine::IncludeCppEngine;
 ///   ```
 ///   This may be simplified in future.
 ///
+/// ### `std::enable_shared_from_this`
+///
+/// A type which derives directly from `std::enable_shared_from_this` gets
+/// `shared_from_this` and `weak_from_this` methods, taking `&self` and
+/// returning a [`cxx::SharedPtr`] or [`cxx::WeakPtr`] which shares
+/// ownership with the `std::shared_ptr`s which already own the object:
+///
+/// ```rust,ignore
+/// let widget: cxx::SharedPtr<ffi::Widget> = ffi::make_shared_widget();
+/// let another_owner = widget.as_ref().unwrap().shared_from_this();
+/// ```
+///
+/// Calling `shared_from_this` on an object which no `std::shared_ptr` owns,
+/// such as one in a [`cxx::UniquePtr`], is a mistake. Where the C++ library
+/// provides `weak_from_this`, as those for C++17 do, you then get a null
+/// pointer; otherwise the C++ behavior is undefined, so take care.
+///
 /// ## Construction
 ///
 /// Types gain a `make_unique` associated function. At present they only
@@ -332,17 +418,40 @@ use autocxx_engine::IncludeCppEngine;
 /// which should be resolved in future.
 /// This will (of course) return a [`cxx::UniquePtr`] containing that type.
 ///
+/// Types also gain a `new` associated function which returns a
+/// [`moveit::New`]. This can be used to construct the type on the Rust stack
+/// using [`moveit::moveit`], or inline within a Rust struct using
+/// [`cpp_fields`].
+///
 /// ## Built-in types
 ///
 /// The generated code uses `cxx` for interop: see that crate for many important
 /// considerations including safety and the list of built-in types, for example
 /// [`cxx::UniquePtr`] and [`cxx::CxxString`].
 ///
+/// A `std::vector` is a [`cxx::CxxVector`]. Where its elements are
+/// primitives, or types listed in `generate_pod!`, its
+/// [`as_slice`](cxx::CxxVector::as_slice) and
+/// [`as_mut_slice`](cxx::CxxVector::as_mut_slice) give a view of its data
+/// without copying, borrowing from the vector. An empty vector gives an
+/// empty slice. Other element types can't be viewed this way, since Rust
+/// can't know their layout.
+/// A returned vector of such elements can also be consumed, yielding them
+/// by value, using [IntoVectorIter].
+///
 /// There are almost no `autocxx`-specific types. At present, we do have
 /// [`c_int`] and similar, to wrap the integer types whose length
 /// varies in C++. It's hoped to contribute full support here to [cxx]
 /// in a future change.
 ///
+/// C++ `bool` and `char` become Rust's `bool` and
+/// [`c_char`](std::os::raw::c_char), so the generated C++ contains
+/// `static_assert`s that `bool` is one byte, that `CHAR_BIT` is 8, and that
+/// `char` has the same signedness as `c_char` for the Rust target. If your
+/// C++ is built with `-funsigned-char` (or `-fsigned-char`) against a target
+/// where Rust disagrees, it will fail to compile rather than misinterpret
+/// values.
+///
 /// ## Strings
 ///
 /// `autocxx` uses [cxx::CxxString]. However, as noted above, we can't
@@ -372,12 +481,45 @@ use autocxx_engine::IncludeCppEngine;
 /// string on the stack, and is generally incompatible with the
 /// [cxx::UniquePtr]-based approaches we use here.
 ///
+/// ### `std::string_view`
+///
+/// Functions which take a `std::string_view` (by value or by const
+/// reference) accept a `&str` from Rust. No copy is taken: the view
+/// points at the Rust string's own bytes, which is fine because the
+/// view only lives for the duration of the call. Don't write C++ which
+/// squirrels away such a view for later.
+///
+/// Functions which return a `std::string_view` give you a `&[u8]`,
+/// because there's no guarantee that the C++ data is UTF-8; use
+/// [std::str::from_utf8] if you need a `&str`. As with any other returned
+/// reference, this is only supported when the lifetime can be tied to
+/// exactly one input reference - typically the `self` of a method.
+/// Rust can't check that the C++ really did return a view into that object
+/// rather than into some temporary, so be wary of APIs which do the latter.
+///
+/// ### `std::filesystem::path`
+///
+/// Functions which take a `std::filesystem::path` (by value or by const
+/// reference) accept a `&Path` from Rust, and C++ builds a temporary
+/// `path` from it for the duration of the call. Functions which return a
+/// `std::filesystem::path` (by value or by const reference) give you a
+/// `PathBuf`. Either way the path's native representation is copied
+/// unchanged, so Unix paths which aren't UTF-8 survive the trip, and on
+/// Windows the UTF-16 is passed as-is. Separators are likewise left
+/// alone rather than normalized. This needs C++17.
+///
 /// ## Preprocessor symbols
 ///
 /// `#define` and other preprocessor symbols will appear as constants.
-/// At present there is no way to do compile-time disablement of code
+/// At present there is no way to do compile-time disablement of Rust code
 /// (equivalent of `#ifdef`).
 ///
+/// If your headers have APIs behind `#ifdef`s, use `Builder::define`
+/// in your `build.rs` rather than passing `-D` via extra clang args.
+/// That way the same symbols are used both when generating bindings
+/// and when compiling the C++, and the generated header will fail to
+/// compile if it's later built with those symbols missing.
+///
 /// ## Integer types
 ///
 /// For C++ types with a defined size, just go ahead and use `u64`, `i32` etc.
@@ -386,6 +528,12 @@ use autocxx_engine::IncludeCppEngine;
 /// For now, this doesn't quite work: instead you need to wrap these values
 /// in a newtype wrapper such as [c_int] or [c_ulong] in this crate.
 ///
+/// Parameters of those types, and of type `double`, accept anything which
+/// C++ would losslessly promote to them: see [`PromotesTo`]. So you can
+/// pass an `i32` where C++ takes a `long`, or an `f32` where it takes a
+/// `double`, but you need an explicit cast to pass an `i64` to an `int`,
+/// or an `i32` to an `unsigned long`.
+///
 /// ## String constants
 ///
 /// Whether from a preprocessor symbol or from a C++ `char*` constant,
@@ -415,6 +563,12 @@ use autocxx_engine::IncludeCppEngine;
 /// This is essentially awful without `rust-analyzer` IDE support, which isn't
 /// quite there yet.
 ///
+/// The exception is a pair of overloads which differ only in the const-ness
+/// of their pointer or reference parameters (or of `this`), such as
+/// `void take(A&)` and `void take(const A&)`. The const one is given a
+/// `_const` suffix - `take` and `take_const` - so you can tell which takes
+/// `Pin<&mut A>` and which `&A`. Any further overloads get numbers as usual.
+///
 /// `autocxx` doesn't yet support default paramters.
 ///
 /// It's fairly likely we'll change the model here in the future, such that
@@ -441,11 +595,37 @@ use autocxx_engine::IncludeCppEngine;
 /// this will be improved in future. At present such types have a name
 /// `AutocxxConcrete{n}` but this may change in future.
 ///
+/// Because these types are opaque, autocxx never assumes that an
+/// instantiation shares the layout or API of the primary template. This
+/// matters where a library provides an explicit specialization with different
+/// behavior (for example, a bit-packed `Vec<bool>`). bindgen doesn't report
+/// the members of specializations, so autocxx can't yet generate methods for
+/// them; instead, write free functions taking the specialization by reference
+/// and allowlist those. This works even if the primary template is only
+/// declared, not defined. Partial specializations are not understood by
+/// bindgen, so functions involving them may be ignored.
+///
+/// Alias templates, such as `template<typename T> using Ptr = std::shared_ptr<T>`,
+/// are treated as whatever they stand for: a function taking `Ptr<Thing>`
+/// takes a `cxx::SharedPtr<Thing>` in Rust. Where the target is one of the
+/// generic types cxx supports, a generic Rust type alias `Ptr<T>` is
+/// generated too.
+///
 /// ## Exceptions
 ///
-/// Exceptions are not supported. If your C++ code is compiled with exceptions,
-/// you can expect serious runtime explosions. The underlying [cxx] crate has
-/// exception support, so it would be possible to add them.
+/// By default, a C++ exception which escapes into Rust is undefined behavior,
+/// so you can expect serious runtime explosions. Add `exceptions_as_panics`
+/// to [`safety!`](crate::safety!) to turn such exceptions into Rust panics,
+/// and `panics_as_exceptions` to turn panics in [`subclass`] methods into C++
+/// exceptions; see [`safety!`](crate::safety!) for details.
+///
+/// ## Blocking calls and `async` code
+///
+/// A C++ function which blocks for a long time can be listed in an
+/// [`offload`] directive. autocxx then generates, alongside the normal
+/// function, a `{name}_offloaded` variant which runs the call via a
+/// [`offload::Spawner`] of your choice and returns an [`offload::Offloaded`]
+/// future. See [`offload`] for details.
 ///
 /// # Subclasses
 ///
@@ -541,6 +721,26 @@ macro_rules! generate {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Generate Rust bindings for the given C++ type or function, as
+/// [generate] does, but only for targets with the given pointer width
+/// or endianness:
+/// ```ignore
+/// generate_if!(target_pointer_width = "64", "read_large_file")
+/// generate_if!(target_endian = "big", "swap_to_little_endian")
+/// ```
+/// The target is that for which clang analyzes the C++: the one given in
+/// a `--target` clang argument, if there is one, or otherwise the one
+/// which Cargo is building for, as given to a build script. That way,
+/// when cross-compiling, functions which the target doesn't have are
+/// never bound. Check in your Rust code too, using the matching `#[cfg]`:
+/// `#[cfg(target_pointer_width = "64")]`.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_if {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Generate as "plain old data" and add to allowlist.
 /// Generate Rust bindings for the given C++ type such that
 /// it can be passed and owned by value in Rust. This only works
@@ -548,6 +748,8 @@ macro_rules! generate {
 /// destructor - you'll encounter a compile error otherwise.
 /// If your type doesn't match that description, use [generate]
 /// instead, and own the type using [UniquePtr][autocxx_engine::cxx::UniquePtr].
+/// A `UniquePtr` to a type generated as POD can be turned into a Rust
+/// `Box` using [IntoBox].
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
@@ -555,6 +757,63 @@ macro_rules! generate_pod {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Generate a C++ struct with a single field, such as
+/// `struct UserId { uint64_t v; };`, as a Rust `#[repr(transparent)]`
+/// newtype: `pub struct UserId(pub u64);`. The field is reached as `.0`,
+/// and there are `From` implementations to convert to and from the
+/// field's type. Like [generate_pod], this adds the type to the allowlist
+/// and requires that it be plain old data. Note that constructing the
+/// newtype from Rust bypasses any C++ constructor, just as setting the
+/// field of any other POD type would.
+/// ```ignore
+/// transparent_newtype!("UserId")
+/// ```
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! transparent_newtype {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Lists a `volatile` field of a type generated with [generate_pod], so
+/// that it's only accessed from Rust as a volatile location. Rust has no
+/// volatile types, and bindgen doesn't say which fields are volatile, so
+/// without this the field would be an ordinary one, and the compiler
+/// could elide or merge reads and writes of it. The field becomes
+/// private, and the type gets `read_` and `write_` methods for it, which
+/// use [std::ptr::read_volatile] and [std::ptr::write_volatile].
+/// ```ignore
+/// // C++: struct Registers { volatile uint32_t status; uint32_t control; };
+/// include_cpp!(
+///     #include "registers.h"
+///     generate_pod!("Registers")
+///     volatile_field!("Registers", "status")
+/// );
+///
+/// regs.write_status(1);
+/// while regs.read_status() != 0 {}
+/// ```
+///
+/// Since the field is private, the type can't be built using a struct
+/// expression, so use one of its constructors. A type which isn't POD
+/// has no fields accessible from Rust anyway, so it can't be listed.
+///
+/// Only fields need listing. Pointers to `volatile` data, whether they're
+/// parameters, return values or fields, are passed as raw pointers, which
+/// should be dereferenced with [std::ptr::read_volatile] and
+/// [std::ptr::write_volatile]. A `volatile` parameter passed by value
+/// means nothing to the caller, so it's an ordinary parameter.
+/// `volatile`-qualified methods aren't supported: autocxx can't tell them
+/// apart from other methods, and the C++ generated to call them may not
+/// compile.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! volatile_field {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Generate Rust bindings for all C++ types and functions
 /// found. Highly experimental and not recommended.
 /// A directive to be included inside
@@ -565,6 +824,21 @@ macro_rules! generate_all {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Generate Rust bindings for the given C++ type, treating it as opaque
+/// even if it could be "plain old data". It's then owned using
+/// [UniquePtr][autocxx_engine::cxx::UniquePtr], and its fields can't be
+/// accessed from Rust, so Rust code doesn't depend on its layout: for
+/// instance, if it may change in later versions of a library. Functions
+/// which return it by value return a `UniquePtr` instead.
+/// This overrides [generate_pod] for the same type, so the type must
+/// not be a field of another type which is generated as POD.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! force_opaque {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Generate as "plain old data". For use with [generate_all]
 /// and similarly experimental.
 #[macro_export]
@@ -581,6 +855,45 @@ macro_rules! exclude_utilities {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Turn the Doxygen in C++ comments into Markdown in the Rust doc
+/// comments generated from them. Comments are carried across to the
+/// generated items anyway; with this `@brief` is dropped, `@param`s become
+/// a list of parameters and `@return` a sentence saying what's returned:
+///
+/// ```ignore
+/// // C++: /// @brief Computes an area.
+/// //      /// @param width The width.
+/// //      /// @return The area.
+/// //      uint32_t area(uint32_t width);
+/// include_cpp!(
+///     #include "shapes.h"
+///     generate!("area")
+///     translate_doxygen!()
+/// );
+/// ```
+///
+/// gives `area` the documentation:
+///
+/// ```text
+/// Computes an area.
+///
+/// # Parameters
+///
+/// * `width`: The width.
+///
+/// Returns the area.
+/// ```
+///
+/// Each overload keeps its own comment. Other Doxygen commands are left
+/// as they are.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! translate_doxygen {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Entirely block some type from appearing in the generated
 /// code. This can be useful if there is a type which is not
 /// understood by bindgen or autocxx, and incorrect code is
@@ -607,136 +920,1854 @@ macro_rules! block_constructors {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// The name of the mod to be generated with the FFI code.
-/// The default is `ffi`.
+/// Additionally generate a variant of a function which runs on another
+/// thread and can be awaited from `async` code. The function must also
+/// be allowlisted using [`generate`] or similar.
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("slow_add")
+///     offload!("slow_add")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// async fn add() -> u32 {
+///     ffi::slow_add_offloaded(autocxx::offload::ThreadSpawner, 2, 3).await
+/// }
+/// ```
+///
+/// The first parameter to the `_offloaded` function is an
+/// [`offload::Spawner`] which decides where the call runs: perhaps
+/// your own thread pool, or your async runtime's equivalent of
+/// `spawn_blocking`. Any parameter conversions (for instance, turning
+/// a `&str` into a [`cxx::UniquePtr<cxx::CxxString>`]) happen on the
+/// calling thread before the call is handed over.
+///
+/// The returned [`offload::Offloaded`] future is [`Send`] if the return
+/// type is. The call begins immediately. Dropping the future does not
+/// cancel it: the C++ function always runs to completion, and its result
+/// is simply discarded.
+///
+/// Because the call may outlive the caller's stack frame, offloaded
+/// functions may not take or return references or pointers; autocxx
+/// will ignore any such function, with an explanation in the generated
+/// documentation. Parameters and return values must also be [`Send`],
+/// which in practice means built-in types, strings, and types whose
+/// [`Send`]ness you've asserted yourself.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! name {
+macro_rules! offload {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Specifies a global safety policy for functions generated
-/// from these headers. By default (without such a `safety!`
-/// directive) all such functions are marked as `unsafe` and
-/// therefore can only be called within an `unsafe {}` block
-/// or some `unsafe` function which you create.
+/// Refer to types already bound by another [`include_cpp`] block, instead
+/// of defining them again. Give the path of that block's output module,
+/// which must start with `crate`, then the C++ names of the types:
 ///
-/// Alternatively, by specifying a `safety!` block you can
-/// declare that most generated functions are in fact safe.
-/// Specifically, you'd specify:
-/// `safety!(unsafe)`
-/// or
-/// `safety!(unsafe_ffi)`
-/// These two options are functionally identical. If you're
-/// unsure, simply use `unsafe`. The reason for the
-/// latter option is if you have code review policies which
-/// might want to give a different level of scrutiny to
-/// C++ interop as opposed to other types of unsafe Rust code.
-/// Maybe in your organization, C++ interop is less scary than
-/// a low-level Rust data structure using pointer manipulation.
-/// Or maybe it's more scary. Either way, using `unsafe` for
-/// the data structure and using `unsafe_ffi` for the C++
-/// interop allows you to apply different linting tools and
-/// policies to the different options.
+/// ```ignore
+/// include_cpp!(
+///     #include "common.h"
+///     generate!("common::Thing")
+///     safety!(unsafe_ffi)
+/// );
 ///
-/// Irrespective, C++ code is of course unsafe. It's worth
-/// noting that use of C++ can cause unexpected unsafety at
-/// a distance in faraway Rust code. As with any use of the
-/// `unsafe` keyword in Rust, *you the human* are declaring
-/// that you've analyzed all possible ways that the code
-/// can be used and you are guaranteeing to the compiler that
-/// no badness can occur. Good luck.
+/// mod other {
+///     autocxx::include_cpp!(
+///         #include "other.h"
+///         name!(ffi)
+///         reuse_from!(crate::ffi, "common::Thing")
+///         generate!("use_thing")
+///         safety!(unsafe_ffi)
+///     );
+/// }
+/// ```
 ///
-/// Generated C++ APIs which use raw pointers remain `unsafe`
-/// no matter what policy you choose.
+/// The types are then the very same Rust types in both blocks, so values
+/// can be passed freely between them. Both blocks must see the same C++
+/// definition of each type, which is most easily ensured by including
+/// the same header. Don't also [`generate`] a reused type in the later
+/// block.
+///
+/// Reused types are opaque to the later block even if the earlier block
+/// made them [`generate_pod`], so the later block passes them by value
+/// only within a `cxx::UniquePtr`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! safety {
+macro_rules! reuse_from {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Whether to avoid generating [`cxx::UniquePtr`] and [`cxx::Vector`]
-/// implementations. This is primarily useful for reducing test cases and
-/// shouldn't be used in normal operation.
+/// Refer to a type already defined in a hand-written `cxx::bridge`,
+/// instead of defining it again, so that functions bound by autocxx can
+/// take and return it. Give the C++ name of the type, then its Rust path
+/// in the bridge:
+///
+/// ```ignore
+/// #[cxx::bridge]
+/// mod bridge {
+///     unsafe extern "C++" {
+///         include!("input.h");
+///         type Counter;
+///         fn make_counter() -> UniquePtr<Counter>;
+///     }
+/// }
+///
+/// include_cpp!(
+///     #include "input.h"
+///     extern_cxx_type!("Counter", crate::bridge::Counter)
+///     generate!("counter_value")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let counter = bridge::make_counter();
+/// let value = ffi::counter_value(&counter);
+/// ```
+///
+/// The bridge must declare the type with the same C++ name and namespace;
+/// `cxx` checks this when compiling. The type is opaque, and its C++
+/// support code comes from the bridge alone. Don't also [`generate`] it.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! exclude_impls {
+macro_rules! extern_cxx_type {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Deprecated - use [`extern_rust_type`] instead.
+/// Convert a C++ type to and from a Rust type of your own whenever a
+/// function passes it by value or by const reference, so that Rust code
+/// deals only in the Rust type. Give the C++ name of the type, then the
+/// Rust path to your type, which should start with `crate` or the name of
+/// another crate, and implement [`CppConversion`] for your type to supply
+/// the conversions in each direction:
+///
+/// ```ignore
+/// // C++: struct MyColor { uint8_t r, g, b; };
+/// //      MyColor brighten(const MyColor& color);
+/// include_cpp!(
+///     #include "color.h"
+///     type_conversion!("MyColor", crate::rgb::Color)
+///     generate!("brighten")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// impl autocxx::CppConversion<ffi::MyColor> for rgb::Color {
+///     fn from_cpp(cpp: &ffi::MyColor) -> Self {
+///         rgb::Color::new(cpp.red(), cpp.green(), cpp.blue())
+///     }
+///     fn to_cpp(&self) -> cxx::UniquePtr<ffi::MyColor> {
+///         ffi::MyColor::make_unique(self.r, self.g, self.b)
+///     }
+/// }
+///
+/// let brighter: rgb::Color = ffi::brighten(&rgb::Color::new(10, 20, 30));
+/// ```
+///
+/// A parameter taken by value takes your type by value, and one taken by
+/// const reference takes a reference to it; either way, C++ gets a new
+/// value made by `to_cpp`. A returned value or const reference becomes a
+/// value of your type made by `from_cpp`, so it no longer borrows from
+/// anything. Since changes couldn't be seen on the other side, mutable
+/// references and pointers stay as the C++ type, as does everything about
+/// the C++ type's own methods and constructors, so that you can still use
+/// them to write the conversions. The C++ type stays as it is in the
+/// methods of subclasses too.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-#[deprecated]
-macro_rules! rust_type {
+macro_rules! type_conversion {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// See [`extern_rust::extern_rust_type`].
+/// Declare that a C++ template, such as `gsl::not_null`, wraps a pointer
+/// which is never null:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("get_value")
+///     register_pointer_wrapper!("gsl::not_null")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: uint32_t get_value(gsl::not_null<const Thing*> thing);
+/// let value = ffi::get_value(&thing);
+/// ```
+///
+/// A wrapped raw pointer becomes a reference: `&T` for a pointer to const,
+/// or `Pin<&mut T>` otherwise. A wrapped smart pointer such as
+/// `std::shared_ptr<T>` becomes the corresponding [`cxx`] smart pointer.
+/// The generated C++ relies on the wrapper being implicitly convertible
+/// from and to what it wraps, and on its unary `*` operator, as is
+/// the case for `gsl::not_null`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! extern_rust_type {
+macro_rules! register_pointer_wrapper {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// See [`subclass::subclass`].
+/// Declare that a C++ function returning a fixed-width integer never
+/// returns zero - for instance, because it returns an ID or a handle. The
+/// function then returns the corresponding [`std::num`] type, such as
+/// [`NonZeroU32`](std::num::NonZeroU32), so that `Option` of it takes no
+/// extra space:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("get_id")
+///     nonzero!("get_id")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let id: std::num::NonZeroU32 = ffi::get_id();
+/// ```
+///
+/// The function panics if the C++ breaks its promise and returns zero
+/// anyway.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! subclass {
+macro_rules! nonzero {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-#[doc(hidden)]
+/// Declare that a C++ function returning a [`cxx::UniquePtr`] never returns
+/// null. The function then checks as soon as it returns, and panics, naming
+/// itself, if it does return null, rather than leaving the null pointer to
+/// cause a panic somewhere else entirely:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("make_widget")
+///     non_null_return!("make_widget")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let widget = ffi::make_widget(); // panics if it's null
+/// ```
+///
+/// For a function which may legitimately return null, use
+/// [`nullable_return`] instead; a function can't be listed in both. Methods
+/// aren't supported yet, so bindings to a method listed in either
+/// directive are skipped with an error.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! usage {
-    (__docs) => {};
-    ($($tt:tt)*) => {
-        compile_error! {r#"usage:  include_cpp! {
-                   #include "path/to/header.h"
-                   generate!(...)
-                   generate_pod!(...)
-               }
-"#}
-    };
+macro_rules! non_null_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-#[doc(hidden)]
-pub use autocxx_macro::include_cpp_impl;
-
-#[doc(hidden)]
-pub use autocxx_macro::cpp_semantics;
-
-macro_rules! ctype_wrapper {
-    ($r:ident, $c:expr, $d:expr) => {
-        #[doc=$d]
-        #[derive(Debug, Eq, Copy, Clone, PartialEq, Hash)]
-        #[allow(non_camel_case_types)]
-        #[repr(transparent)]
-        pub struct $r(pub ::std::os::raw::$r);
-
-        unsafe impl autocxx_engine::cxx::ExternType for $r {
-            type Id = autocxx_engine::cxx::type_id!($c);
-            type Kind = autocxx_engine::cxx::kind::Trivial;
-        }
-
-        impl From<::std::os::raw::$r> for $r {
-            fn from(val: ::std::os::raw::$r) -> Self {
-                Self(val)
-            }
-        }
-
-        impl From<$r> for ::std::os::raw::$r {
-            fn from(val: $r) -> Self {
-                val.0
-            }
-        }
-    };
+/// Declare that a C++ function returning a [`cxx::UniquePtr`] may return
+/// null, so that it returns an `Option<UniquePtr<T>>` which is `None`
+/// if so:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("find_widget")
+///     nullable_return!("find_widget")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// if let Some(widget) = ffi::find_widget() {
+///     // ...
+/// }
+/// ```
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! nullable_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-ctype_wrapper!(
-    c_ulonglong,
-    "c_ulonglong",
-    "Newtype wrapper for an unsigned long long"
+/// Declare that a C++ container type's `at` method, which throws
+/// `std::out_of_range` if given an index which is out of range, should be
+/// accompanied by a `get` method which checks the index against `size()`
+/// in Rust and returns `None` instead, so that C++ never throws:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("IntList")
+///     checked_index!("IntList")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let list = ffi::IntList::make_unique();
+/// assert_eq!(list.get(100), None);
+/// ```
+///
+/// The const `at` method is used, which must take a single integer index,
+/// and `size` must be a const method returning the same type. The check
+/// and the access happen separately, so if another thread might change
+/// the size of the container in between, you must prevent that yourself.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! checked_index {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Name the getters and setters of a C++ type like Rust properties:
+///
+/// ```ignore
+/// // C++: class Widget { uint32_t getValue() const; void setValue(uint32_t); ... };
+/// include_cpp!(
+///     #include "widget.h"
+///     generate!("Widget")
+///     properties!("Widget")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let mut widget = ffi::Widget::make_unique();
+/// widget.pin_mut().set_value(4);
+/// assert_eq!(widget.value(), 4);
+/// ```
+///
+/// `getValue` or `GetValue` becomes `value`, and `setValue` becomes
+/// `set_value`. Boolean getters such as `isEnabled` and `hasValue` become
+/// `is_enabled` and `has_value`. A getter needn't have a matching setter.
+/// Other methods keep their names, and these names override any given
+/// by `Builder::rename_methods`. If two methods end up with the same name,
+/// bindings to the second are skipped, with an explanation.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! properties {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Take ownership of the raw pointer returned by a C++ function, and
+/// free it using a given C++ function when it's dropped. This suits C
+/// factory functions whose results must be freed by some specific function
+/// rather than by `delete`:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("make_thing")
+///     owned_return!("make_thing", "free_thing")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let mut thing = ffi::make_thing();
+/// if !thing.is_null() {
+///     thing.pin_mut().do_something();
+/// }
+/// // free_thing is called here.
+/// ```
+///
+/// The function must return a pointer, and is then bound such that it
+/// returns an [`OwnedPtr`] instead, which calls the deleter when dropped.
+/// A null return gives a null [`OwnedPtr`], and the deleter isn't called.
+/// The deleter must be callable from C++ with just that pointer; if it needs
+/// extra context, write a small C++ function which supplies it and name that
+/// instead. The deleter doesn't need to be listed in [`generate`].
+///
+/// If the function is also listed in [`out_param`], it's the pointer
+/// filled in through the out parameter which becomes an [`OwnedPtr`].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! owned_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a C-style handle type - typically a typedef for an integer
+/// or a pointer - represents a resource which must be released by calling
+/// a given C++ function:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("open_file")
+///     resource!("file_handle", "close_file", "-1")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let file = ffi::open_file("foo.txt");
+/// ffi::read_file(file.get());
+/// // close_file is called here.
+/// ```
+///
+/// Functions which return this handle type are bound such that they
+/// return an [`OwnedHandle`] instead, which calls the close function
+/// exactly once when dropped. The optional third argument is a C++
+/// expression for an invalid handle value, such as `"-1"` or `"nullptr"`;
+/// the close function won't be called for such a handle. The close
+/// function doesn't need to be listed in [`generate`].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! resource {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a C++ type counts references to itself, by means of a
+/// method which adds a reference and a method which releases one and frees
+/// the object once none remain (as COM objects do with `AddRef` and
+/// `Release`):
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("IThing")
+///     generate!("create_thing")
+///     intrusive_refcount!("IThing", "AddRef", "Release")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // create_thing returns a reference which the caller must release.
+/// let thing = unsafe { autocxx::IntrusivePtr::from_raw(ffi::create_thing()) }.unwrap();
+/// let another = thing.clone(); // AddRef is called here...
+/// drop(another); // ... and Release here.
+/// ```
+///
+/// The type then implements [`IntrusiveRefcounted`], so can be held in an
+/// [`IntrusivePtr`], which adds a reference when it's cloned and releases one
+/// when it's dropped. Functions returning pointers to the type still return
+/// raw pointers, because only you know whether each returns a reference
+/// which has already been added for the caller (as a factory function
+/// usually does) or one which is merely borrowed. Use
+/// [`IntrusivePtr::from_raw`] for the former and
+/// [`IntrusivePtr::from_raw_add_ref`] for the latter.
+///
+/// An [`IntrusivePtr`] can't be sent between threads unless the type is
+/// also [`Send`] and [`Sync`], which you can declare if the reference
+/// counting is atomic and the object is otherwise thread-safe.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! intrusive_refcount {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a C++ type, such as the task type returned by a coroutine,
+/// completes in steps and may be awaited. Give the type, then a method
+/// which resumes it, a const method which returns whether it's done, and
+/// a method which returns its result once it is:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("IntTask")
+///     generate!("compute")
+///     register_awaitable!("IntTask", "resume", "done", "result")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let answer = autocxx::CppFuture::new(ffi::compute()).await;
+/// ```
+///
+/// The type then implements [`Awaitable`], and a [`CppFuture`] can drive
+/// an object of it to completion. C++ can't tell Rust when it's worth
+/// resuming the object again, so each time it's not yet done, the future
+/// asks to be polled again straight away.
+///
+/// The object is resumed on whichever thread polls the future. A
+/// [`CppFuture`] can't be sent between threads unless the type is
+/// [`Send`], so if the coroutine must run on one particular thread, run
+/// the future there. An exception thrown by any of the three methods ends
+/// the program, so a coroutine which may fail should return its error as
+/// part of its result.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! register_awaitable {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that objects of a C++ type may or may not hold a value, as
+/// optional-like types and handles may not, so that the type implements
+/// [`IsValid`]:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("FileHandle")
+///     generate!("MaybeName")
+///     generate!("open_file")
+///     validity!("FileHandle")
+///     validity!("MaybeName", "!empty")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let file = ffi::open_file("foo.txt");
+/// if let Some(file) = file.as_option() {
+///     // ...
+/// }
+/// ```
+///
+/// By default, an object is valid if its `explicit operator bool` returns
+/// `true`. Otherwise, name a method which says whether it's valid (such
+/// as `"valid"`), or whether it's not, prefixed with `!` (such as
+/// `"!empty"`). If a type has more than one of these, choose whichever
+/// matches your idea of validity.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! validity {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a C++ class is a smart pointer, which overloads
+/// `operator->` to point to another type, so that it implements
+/// [`Deref`](std::ops::Deref) to that type and its methods can be called
+/// through it:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("WidgetPtr")
+///     generate!("Widget")
+///     smart_pointer!("WidgetPtr", "Widget", mut)
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let mut ptr = ffi::WidgetPtr::make_unique();
+/// let size = ptr.size(); // a method of Widget
+/// ptr.pin_mut().deref_pin_mut().resize(size * 2);
+/// ```
+///
+/// bindgen doesn't tell us about `operator->`, so the type it points to
+/// must be given. Like C++, `deref` follows `operator->` until it gets to a
+/// plain pointer, so a smart pointer may return another. It uses the
+/// `const` overload of `operator->`. Add `mut` if the pointee may be
+/// modified through a non-`const` smart pointer, which then gains a
+/// `deref_pin_mut` method giving a `Pin<&mut>` to it; there's no
+/// `DerefMut`, since C++ objects can't be given out as plain `&mut`
+/// references.
+///
+/// Both panic if the smart pointer turns out to be null, rather than
+/// giving out a reference to nothing.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! smart_pointer {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a C++ struct is a table of function pointers, such as
+/// the "ops" structs C libraries use for callbacks and drivers, so that
+/// each function pointer field gets a method which calls it:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate_pod!("FileOps")
+///     generate!("get_file_ops")
+///     fn_table!("FileOps")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let ops = ffi::get_file_ops();
+/// let len = unsafe { ops.read(ctx, buf.as_mut_ptr(), buf.len()) };
+/// if ops.has_close() {
+///     unsafe { ops.close(ctx) };
+/// }
+/// ```
+///
+/// A field `read` gets a method `read`, taking the same arguments as the
+/// function it points to, and a method `has_read` which says whether it's
+/// set. Calling a function pointer which isn't set terminates the
+/// program, so check optional operations first. Any context pointer the
+/// functions take is passed like any other argument. Making a table from
+/// Rust closures isn't supported.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! fn_table {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a C++ enum holds bit flags, which may be combined using
+/// bitwise OR. A Rust `enum` can only hold one of its enumerators, so
+/// instead the enum becomes a `#[repr(transparent)]` newtype around its
+/// underlying integer, with an associated constant for each enumerator,
+/// and the usual bitwise operators. There's also a builder, with a
+/// method named after each enumerator in snake case:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("open_file")
+///     flags!("Perms", ["READ_ONLY", "READ_WRITE"])
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let perms = ffi::PermsBuilder::new().read_write().exec().build();
+/// assert!(perms.contains(ffi::Perms::EXEC));
+/// ffi::open_file(perms | ffi::Perms::APPEND);
+/// ```
+///
+/// Each optional list of enumerators after the name is a group which are
+/// mutually exclusive; setting one of them with the builder clears the
+/// others. Enumerators such as `NONE` or `ALL`, representing no flags or
+/// several, are fine too.
+///
+/// A function which updates flags in place, such as
+/// `void add_exec(Perms& perms)`, takes a `&mut Perms`. Other enums can't
+/// be passed by non-const reference, since C++ could store a value which
+/// isn't one of their enumerators, so list such an enum here too.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! flags {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Return the pointer which a C++ function fills in through a
+/// pointer-to-pointer parameter, instead of passing that parameter:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("create_thing")
+///     out_param!("create_thing", "out")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: int create_thing(uint32_t value, Thing** out);
+/// let (status, thing) = ffi::create_thing(42);
+/// ```
+///
+/// The pointer is null until the C++ function fills it in. A function
+/// which returns `void` returns just the pointer; otherwise, a tuple of its
+/// own return value and the pointer. The pointer is raw, unless the function
+/// is also listed in [`owned_return`], in which case it's an [`OwnedPtr`].
+///
+/// Without this directive, a `T**` parameter is passed as a raw
+/// pointer-to-pointer, which suits other uses such as arrays of pointers.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! out_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Take ownership of the `std::unique_ptr<T[]>` which a C++ function
+/// returns, as a [`UniqueArray`], which can be used as a slice:
+///
+/// ```ignore
+/// // C++: std::unique_ptr<int[]> read_samples(size_t* count);
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("read_samples")
+///     unique_array_return!("read_samples", "count")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let samples = ffi::read_samples();
+/// let total: i32 = samples.iter().map(|s| s.0).sum();
+/// ```
+///
+/// A `std::unique_ptr<T[]>` doesn't know its length, so you must name the
+/// parameter which gives it. If that's an integer, the caller still
+/// passes it; if it's a pointer to one, the function fills it in, and the
+/// caller doesn't pass it. The array is freed with `delete[]` when the
+/// [`UniqueArray`] is dropped. The elements must be POD, since Rust
+/// can't otherwise hold them in a slice.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! unique_array_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Borrow the array which a C++ function returns as a pointer, as a
+/// slice, given where its length comes from:
+///
+/// ```ignore
+/// // C++: const Item* get_items();
+/// //      size_t item_count();
+/// include_cpp!(
+///     #include "items.h"
+///     generate!("get_items")
+///     generate!("item_count")
+///     array_return!("get_items", "item_count")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// for item in unsafe { ffi::get_items() } { /* ... */ }
+/// ```
+///
+/// The length may come from a parameter of the function: if that's an
+/// integer, the caller still passes it; if it's a pointer to one, the
+/// function fills it in, and the caller doesn't pass it. Otherwise, the
+/// length comes from the function of that name, which must take no
+/// parameters and must also be generated, and is called straight after.
+/// A null pointer is an empty slice. The elements must be POD, since Rust
+/// can't otherwise hold them in a slice.
+///
+/// The array still belongs to C++. If the function has exactly one
+/// reference parameter, the slice borrows from it, so that object can't be
+/// changed or dropped while the slice is in use. Otherwise `autocxx` has no
+/// way to know how long the array lasts, so the slice is `'static` and the
+/// function is `unsafe`: the caller must not use the slice once C++ has
+/// changed or freed the array, and may want to copy it straight away, for
+/// instance using `to_vec()`. If instead the caller becomes responsible for
+/// freeing it, see [unique_array_return] and [owned_return].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! array_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Borrow the elements of the array of pointers which a C++ function
+/// returns, which ends with a null pointer, as a `Vec` of references. An
+/// array of `char*` becomes a `Vec<&CStr>`:
+///
+/// ```ignore
+/// // C++: const char* const* get_names();
+/// include_cpp!(
+///     #include "names.h"
+///     generate!("get_names")
+///     null_terminated_array_return!("get_names")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let names: Vec<&str> = unsafe { ffi::get_names() }
+///     .into_iter()
+///     .map(|name| name.to_str().unwrap())
+///     .collect();
+/// ```
+///
+/// A null array has no elements. As with [array_return], the array still
+/// belongs to C++, so the references borrow from the function's only
+/// reference parameter, or else are `'static` and the function is `unsafe`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! null_terminated_array_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Return a [`std::io::Result`] from a C++ function which reports failure
+/// through a `std::error_code&` parameter, instead of passing that
+/// parameter:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("open_thing")
+///     error_code_param!("open_thing", "ec")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: uint32_t open_thing(uint32_t id, std::error_code& ec);
+/// match ffi::open_thing(42) {
+///     Ok(handle) => { /* ... */ }
+///     Err(e) => eprintln!("couldn't open: {}", e),
+/// }
+/// ```
+///
+/// If the C++ function leaves the `std::error_code` clear, you get its
+/// return value in an `Ok`; the return value is discarded if the code is
+/// set. Codes in `std::system_category()` become OS errors, as do those in
+/// `std::generic_category()` on Unix, where they also use `errno` values.
+/// Codes in other categories become an error of kind
+/// [`Other`](std::io::ErrorKind::Other) containing an
+/// [`error_code::CppErrorCode`] giving the value, category and message.
+///
+/// If the C++ function instead throws an exception, that becomes a panic.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! error_code_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Represent a typedef of a `std::bitset` as a Rust
+/// [`bitset::BitSet`], giving its number of bits:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("flip_flags")
+///     bitset!("Flags", 16)
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: typedef std::bitset<16> Flags;
+/// //      Flags flip_flags(Flags);
+/// let mut flags = ffi::Flags::new();
+/// flags.set(3);
+/// let flipped = ffi::flip_flags(flags);
+/// assert_eq!(flipped.count(), 15);
+/// ```
+///
+/// `ffi::Flags` is then an alias for a [`bitset::BitSet`], a [`Copy`]
+/// value which functions take and return by value, or by const reference
+/// where the C++ does. The width must match the C++ or the generated code
+/// won't compile. Any width is allowed; widths up to 64 bits can also be
+/// converted to and from `u64`.
+///
+/// This works only through a typedef (or `using` alias) because
+/// `bindgen` can't tell us the width of a plain `std::bitset<N>`.
+/// Mutable references to bitsets aren't supported.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! bitset {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// State which parameter a returned reference borrows from, where the
+/// function takes several references and so Rust can't know. Give the
+/// parameter's name, or `this` for the receiver of a method:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("first")
+///     generate!("Registry")
+///     lifetime!("first", "return", "from", "v")
+///     lifetime!("Registry::lookup", "return", "from", "this")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: const Item& first(const Items& v, const Options& opts);
+/// //      const Item& Registry::lookup(const Key& key) const;
+/// ```
+///
+/// The generated function gets an explicit lifetime tying the returned
+/// reference to that parameter alone, so the other references need live
+/// only as long as the call. Without this directive, functions which
+/// return a reference but take more than one are not generated at all.
+/// That includes builder methods which return `*this` and take other
+/// references; name `this` as the source, so that calls can be chained:
+///
+/// ```ignore
+/// // C++: RequestBuilder& RequestBuilder::header(const std::string& name, const std::string& value);
+/// //      RequestBuilder& RequestBuilder::timeout(uint32_t seconds);
+/// // lifetime!("RequestBuilder::header", "return", "from", "this")
+/// builder.pin_mut().header(&name, &value).timeout(30);
+/// ```
+///
+/// `autocxx` has no way to check your claim: if the C++ actually returns
+/// a reference into some other parameter, safe Rust code could use it
+/// after that parameter is gone.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! lifetime {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// State that the reference a function returns lives forever, as one to a
+/// global singleton does, so that Rust gets a `&'static T` or a
+/// `Pin<&'static mut T>`:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("Logger")
+///     static_ref_return!("Logger::instance")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: static Logger& Logger::instance();
+/// let logger: Pin<&'static mut ffi::Logger> = ffi::Logger::instance();
+/// ```
+///
+/// Without this directive, functions which return a reference but take
+/// none are not generated at all, since Rust can't know how long the
+/// reference lasts. Name methods by their class.
+///
+/// `autocxx` has no way to check your claim. Take care with singletons
+/// which are `thread_local`: these last only as long as their thread, so
+/// a reference to one mustn't be sent to another thread. Calling a
+/// function which returns `Pin<&'static mut T>` twice gives two mutable
+/// references to the same object, so the Rust code must take care that
+/// only one is used at once.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! static_ref_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// State that a function which returns a non-POD type by value should
+/// instead return an [`moveit::new::New`], which constructs the object in
+/// place wherever you put it, rather than a [`cxx::UniquePtr`] to a copy on
+/// the heap:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("Widget")
+///     generate!("make_widget")
+///     emplace_return!("make_widget")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: Widget make_widget(uint32_t size);
+/// use autocxx::moveit::Emplace;
+/// moveit! {
+///     let widget = ffi::make_widget(3);
+/// }
+/// let boxed = Box::emplace(ffi::make_widget(4));
+/// ```
+///
+/// The `New` holds on to the arguments, and calls the function only when
+/// it's used. So it borrows any references it's given, and the C++ sees
+/// nothing until then. The object is move constructed into place, which
+/// C++ compilers elide. If the function throws and exceptions become
+/// panics, as with [`safety!(exceptions_as_panics)`](crate::safety!), the
+/// object is never constructed and, as for throwing moves, space allocated by
+/// [`moveit::EmplaceUnpinned::emplace`] for a [`cxx::UniquePtr`] is then
+/// leaked.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! emplace_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// State that a free function which returns a `std::tuple` should instead
+/// return a Rust tuple of its elements:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("parse")
+///     tuple_return!("parse")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: std::tuple<bool, uint32_t, std::string> parse(const std::string& input);
+/// let (ok, len, rest) = ffi::parse(&input);
+/// ```
+///
+/// Each element is given back as the function would give it if it returned
+/// that element alone: primitives and POD types by value, other types in a
+/// [`cxx::UniquePtr`]. An empty `std::tuple<>` becomes `()`. Elements which
+/// are references or pointers aren't supported, and nor are methods.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! tuple_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// State that a function or method which returns a `std::string` should
+/// instead return a Rust `String`, copied from it as soon as it returns,
+/// rather than a [`cxx::UniquePtr`] to a [`cxx::CxxString`]:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("user_name")
+///     generate!("Widget")
+///     string_return!("user_name")
+///     string_return!("Widget::label", strict)
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: std::string user_name();
+/// let name: String = ffi::user_name();
+/// // C++: const std::string& Widget::label() const;
+/// let label: Result<String, std::string::FromUtf8Error> = widget.label();
+/// ```
+///
+/// Bytes which aren't valid UTF-8 are replaced with U+FFFD unless you add
+/// `strict`, in which case you get a `Result` which is an error if any
+/// are found; either way, NULs within the string are kept. Name a method
+/// by its class too. The string may be returned by value or by const
+/// reference.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! string_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Call the C++ functions at runtime through addresses which your own
+/// [`SymbolResolver`] finds, for instance in a plugin loaded with `dlopen`,
+/// rather than linking to them. Give the Rust path to the resolver, which
+/// should start with `crate` or the name of another crate:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "plugin.h"
+///     symbol_resolver!(crate::plugin::Plugin)
+///     generate!("plugin_add")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // In crate::plugin:
+/// pub struct Plugin;
+///
+/// unsafe impl autocxx::SymbolResolver for Plugin {
+///     fn resolve(symbol: &str) -> Option<*const std::ffi::c_void> {
+///         let library = LIBRARY.get()?;
+///         unsafe { library.get::<*const std::ffi::c_void>(symbol.as_bytes()).ok() }
+///             .map(|sym| *sym)
+///     }
+/// }
+///
+/// assert_eq!(ffi::plugin_add(c_int(2), c_int(3)), c_int(5));
+/// ```
+///
+/// Each function's symbol is resolved the first time it's called, so the
+/// library needn't be loaded until then; if the resolver can't find it, or
+/// [`SymbolResolver::check_library`] rejects the library, the call panics,
+/// naming the symbol. The symbol is the one bindgen says the function is
+/// linked by, which C++ mangles unless the function is `extern "C"`.
+///
+/// This applies to every free function which is generated, but not to
+/// methods, which are still linked as usual, and nor are overloaded
+/// functions or those listed in [`emplace_return`]. The C++ which autocxx
+/// generates doesn't refer to the functions which are resolved, so they
+/// needn't be linked into your program.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! symbol_resolver {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate a function which returns the calling thread's instance of a
+/// C++ `thread_local` variable, as a [`ThreadLocalRef`]:
+///
+/// ```ignore
+/// // C++: extern thread_local uint32_t counter;
+/// //      void increment();
+/// include_cpp!(
+///     #include "counter.h"
+///     generate!("increment")
+///     thread_local_global!("counter")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// ffi::increment();
+/// let count: u32 = *ffi::counter();
+/// ```
+///
+/// The function has the variable's name, in the same namespace. Each
+/// thread has its own instance, which C++ initializes the first time that
+/// thread uses it, which may be by calling this function. The instance
+/// lasts only as long as its thread, so a [`ThreadLocalRef`] can't be sent
+/// to another thread, and only lets you borrow the variable for as long
+/// as you hold it.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! thread_local_global {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Allow a type to be stored in, and downcast from, a C++ `std::any`:
+///
+/// ```ignore
+/// // C++: std::any keep(std::any value);
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("keep")
+///     any_castable!("Point")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let any = ffi::CppAny::wrap(&point);
+/// let kept = ffi::keep(any);
+/// let point: Option<&ffi::Point> = kept.downcast_ref::<ffi::Point>();
+/// ```
+///
+/// This generates `CppAny`, a name for `std::any`, with methods to wrap a
+/// copy of a listed type in a new one, to borrow the value it holds if
+/// it's of a listed type, and to find whether it holds a value at all and
+/// the name of its type. `downcast_ref` uses `std::any_cast`, so gives
+/// `None` if the `std::any` is empty or holds some other type, including
+/// one which isn't listed. A `std::any` may only hold a type which can
+/// be copied.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! any_castable {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Bind a method which a class inherits from a base that bindgen can't
+/// describe. bindgen doesn't tell us about the methods of a class
+/// template's instantiations, so this is the way to reach those a class
+/// gets from a base such as a CRTP (curiously recurring template pattern)
+/// base, whose template parameter is the class itself:
+///
+/// ```ignore
+/// // C++: template<typename D> struct Shape {
+/// //          uint32_t sides() const { return static_cast<const D*>(this)->count(); }
+/// //      };
+/// //      struct Square : Shape<Square> { uint32_t count() const { return 4; } };
+/// include_cpp!(
+///     #include "shapes.h"
+///     generate!("Square")
+///     inherited_method!("Square", "sides", "uint32_t() const")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let square = ffi::Square::make_unique();
+/// assert_eq!(square.sides(), 4);
+/// ```
+///
+/// Give the class's namespace-qualified name, the method's name, and its
+/// signature, followed by `const` if it's a const method. The method is
+/// bound just as if the class had declared it itself, so its parameters
+/// and return type go through the same conversions. Since it's called on
+/// the derived class, calls the base makes to the derived class through
+/// `static_cast` work as in C++, however many CRTP layers there are.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! inherited_method {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Bind an enum nested in a class template, as it is in one instantiation
+/// of the template:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("get_state")
+///     generate!("Widget_int_State")
+///     template_enum!("Widget<int>::State")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: Widget<int>::State get_state();
+/// assert!(matches!(ffi::get_state(), ffi::Widget_int_State::Busy));
+/// ```
+///
+/// The enum is named after the instantiation, with each run of characters
+/// which can't be part of an identifier replaced by an underscore, in the
+/// template's namespace: `ns::Widget<std::string>::State` is
+/// `ffi::ns::Widget_std_string_State`. There's also a name without the
+/// template arguments, which bindgen gives it.
+///
+/// bindgen describes `Widget<int>::State` and `Widget<char>::State` as the
+/// same enum, though they're different types to C++, so only one
+/// instantiation of each nested enum may be listed; that's the one which
+/// functions taking or returning any instantiation of it are bound for.
+/// The values of its enumerators are those bindgen finds in the template,
+/// so this doesn't work for enums whose values depend on the template's
+/// parameters.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! template_enum {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Iterate over a C++ container from Rust. References to the container
+/// get an implementation of [`IntoIterator`], giving a [`CppIter`] which
+/// walks from `begin()` to `end()` and borrows each element in turn:
+///
+/// ```ignore
+/// // C++: class Playlist {
+/// //      public:
+/// //          std::list<uint32_t>::const_iterator begin() const;
+/// //          std::list<uint32_t>::const_iterator end() const;
+/// //      };
+/// include_cpp!(
+///     #include "playlist.h"
+///     generate!("Playlist")
+///     iterable!("Playlist", "uint32_t", bidirectional)
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let playlist = ffi::Playlist::make_unique();
+/// for track in &*playlist {
+///     println!("{}", track);
+/// }
+/// let newest_first: Vec<u32> = (&*playlist).into_iter().rev().copied().collect();
+/// ```
+///
+/// Give the container's namespace-qualified name and the type of its
+/// elements. Anything `std::begin` and `std::end` work on will do, so
+/// that includes the standard containers. By default, its iterators need
+/// only go forwards. If they can go backwards too (`operator--`), say
+/// `bidirectional` and the iterator is a [`DoubleEndedIterator`]; if
+/// they're random-access (`operator+` and `operator-`), say
+/// `random_access` and it's also an [`ExactSizeIterator`], whose length
+/// is `end() - begin()`.
+///
+/// As in C++, the container mustn't be changed while it's iterated over,
+/// which the borrow ensures from the Rust side.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! iterable {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Query a C++ set, such as a `std::set` or `std::unordered_set`, from
+/// Rust without copying it. The set gets `contains`, `insert`, `len` and
+/// `is_empty` methods, and is iterable as if it were listed in [iterable]:
+///
+/// ```ignore
+/// // C++: typedef std::set<uint32_t> Ids;
+/// //      Ids active_ids();
+/// include_cpp!(
+///     #include "ids.h"
+///     generate!("active_ids")
+///     cpp_set!("Ids", "uint32_t", ordered)
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let mut ids = ffi::active_ids();
+/// assert!(ids.contains(&42));
+/// let highest = (&*ids).into_iter().next_back();
+/// let (inserted, id) = ids.pin_mut().insert(&7);
+/// ```
+///
+/// Give the set's namespace-qualified name, or that of a typedef of it,
+/// and the type of its elements. `contains` calls the set's `find`, so
+/// works for anything with `find`, `end` and `size` as the standard sets
+/// have. Say `ordered` for a sorted set such as a `std::set`, which is
+/// then iterated in order, in either direction; otherwise, the set is
+/// iterated forwards, in whatever order it holds its values.
+///
+/// `insert` calls the set's `insert`, which returns a
+/// `std::pair<iterator, bool>`; you get back the `bool`, saying whether
+/// the value was inserted or was already there, and a reference to the
+/// element the iterator points to, which is the existing one in the
+/// latter case. Elements are only ever borrowed, and `contains` and
+/// `insert` take the value by reference, copying it into the set if need
+/// be, so they may be of types which Rust can't hold by value, such as
+/// `std::string`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! cpp_set {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Bind an object-like macro as a Rust constant:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     macro_const!("MAX_SIZE")
+/// );
+///
+/// // C++: #define MAX_SIZE (512 * 2)
+/// assert_eq!(ffi::MAX_SIZE, 1024);
+/// ```
+///
+/// Macros don't appear in the C++ AST, and only those which expand to a
+/// plain literal are otherwise seen at all. The macro may instead expand
+/// to any expression which C++ can evaluate as a `constexpr` integer,
+/// floating-point number or `bool`, and the constant gets the type of
+/// that expression. The macro must be defined by the headers you include.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! macro_const {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Bind a function-like macro as a function, with the given C++
+/// signature:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     macro_function!("SQUARE", "int(int)")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: #define SQUARE(x) ((x) * (x))
+/// assert_eq!(ffi::SQUARE(c_int(3)), c_int(9));
+/// ```
+///
+/// This works for macros which take each argument once, as an
+/// expression, and expand to an expression, since `autocxx` binds a C++
+/// function which returns the macro applied to its parameters. It will
+/// not work for macros which paste tokens, take types as arguments or
+/// expand to statements. The function's parameters and return value may
+/// be of any type which `autocxx` could pass to an ordinary function.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! macro_function {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Bind one overload of a free function, picked by its signature, where
+/// `autocxx` can't bind the right one itself. That's usually because the
+/// overloads are function templates, enabled only for some types using
+/// `std::enable_if`, which bindgen can't see at all:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     select_overload!("twice", "(int) -> int")
+///     select_overload!("twice", "(double) -> double")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: template <typename T, std::enable_if_t<std::is_arithmetic_v<T>, int> = 0>
+/// //      T twice(T v) { return v + v; }
+/// assert_eq!(ffi::twice(c_int(3)), c_int(6));
+/// assert_eq!(ffi::twice1(1.5), 3.0);
+/// ```
+///
+/// The signature lists the parameter types, and the return type after a
+/// `->`, which may be left out if it's `void`. `autocxx` binds a C++
+/// function which calls the function through a pointer of exactly that
+/// type, so C++ picks the overload, or instantiates the template, which
+/// matches it; if none does, the C++ won't compile. Each overload picked
+/// this way is named like any other overload, and none of those bindgen
+/// finds are bound, so there's no need for [generate] too. The function
+/// may be in a namespace, given as `"ns::twice"`, but can't be a method.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! select_overload {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Bind an instantiation of a function template with explicit template
+/// arguments, giving it a Rust name. This is for templates which can only
+/// be called that way, such as those taking non-type template arguments
+/// which can't be deduced from any parameter, so can't be picked with
+/// [select_overload]:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     instantiate_fn_named!("foo", "<int, 3>", "foo_int_3")
+///     instantiate_fn_named!("foo", "<long, 4>", "foo_long_4")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: template <typename T, int N> T foo() { return T(N); }
+/// assert_eq!(ffi::foo_int_3(), c_int(3));
+/// ```
+///
+/// The template arguments are written as they would be in C++, including
+/// the angle brackets. The function may be in a namespace, given as
+/// `"ns::foo"`, in which case the Rust function is in that namespace too,
+/// but can't be a method. If the instantiation can't be made, the C++
+/// won't compile, and bindgen reports the compiler's error. There's no
+/// need for [generate] too.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! instantiate_fn_named {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Gives one of the constructors of a type a Rust name of its own, in
+/// place of `new` followed by a number saying which overload it is:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("Point")
+///     name_ctor!("Point", "(int, int)", "from_coords")
+///     name_ctor!("Point", "(const char*)", "parse")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// use autocxx::moveit::EmplaceUnpinned;
+/// let p = cxx::UniquePtr::emplace(ffi::Point::from_coords(c_int(3), c_int(4)));
+/// ```
+///
+/// The constructor is picked by its parameter types, written as they are
+/// in C++, without `this`; spaces which don't matter may be left out.
+/// Its `make_unique` counterpart is called `make_unique_from_coords`.
+/// Other constructors keep the usual names, so a constructor can't be
+/// named `new` or `new` followed by a number, and each constructor may be
+/// given only one name. If a method of the type has the same name,
+/// bindings to the constructor are skipped rather than giving either of
+/// them a different name.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! name_ctor {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Passes a pointer parameter, together with a parameter giving the number
+/// of elements it points to, as a single Rust slice.
+/// ```ignore
+/// // C++: size_t checksum(const std::byte* data, size_t len);
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("checksum")
+///     slice_param!("checksum", "data", "len")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let sum = ffi::checksum(b"hello");
+/// ```
+///
+/// The arguments are the fully-qualified name of the function, and the
+/// names of the pointer and length parameters. A `const T*` becomes a
+/// `&[T]` and a `T*` becomes a `&mut [T]`, except that a `std::byte`
+/// pointer becomes a slice of `u8`. The length can be any integer type;
+/// the function panics if the slice is too long for it. Since the length is
+/// now guaranteed to be correct, the slice doesn't of itself make the
+/// function `unsafe`. Each pointer can be given only one length, and
+/// each length can describe only one pointer. At present this works only
+/// for free functions, not methods.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! slice_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Passes a `const char*` parameter as anything implementing [`CStrArg`]:
+/// a `&CStr`, which C++ gets as it is, or a `&str`, which is copied into a
+/// temporary NUL-terminated string.
+/// ```ignore
+/// // C++: size_t count_vowels(const char* text);
+/// include_cpp!(
+///     #include "text.h"
+///     generate!("count_vowels")
+///     c_str_param!("count_vowels", "text")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// assert_eq!(ffi::count_vowels("banana"), 3);
+/// assert_eq!(ffi::count_vowels(CStr::from_bytes_with_nul(b"apple\0").unwrap()), 2);
+/// ```
+///
+/// The arguments are the fully-qualified name of the function, or of the
+/// method, such as `"ns::Text::find"`, and the name of the parameter. A
+/// temporary string lasts only until the end of the statement which
+/// makes the call, so this is only for functions which don't keep the
+/// pointer. The function panics if it's given a `&str` containing a NUL.
+/// Since the string is now guaranteed to be valid, it doesn't of itself
+/// make the function `unsafe`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! c_str_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Returns the `const char*` returned by a method as a
+/// [`&CStr`](std::ffi::CStr) borrowed from the object, rather than as a
+/// pointer, so it can be read without copying:
+/// ```ignore
+/// // C++: class Person { public: const char* c_name() const; ... };
+/// include_cpp!(
+///     #include "person.h"
+///     generate!("Person")
+///     c_str_return!("Person::c_name")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let person = ffi::Person::make_unique();
+/// assert_eq!(person.c_name().to_str().unwrap(), "Ada");
+/// ```
+///
+/// The argument is the fully-qualified name of the method. The string
+/// borrows from the object, which therefore can't be changed or dropped
+/// while it's in use, so this is only for methods whose string lasts as
+/// long as the object does and isn't changed through other means. The
+/// method panics if it returns a null pointer. A method returning a
+/// `const std::string&` needs no directive, since it already returns a
+/// borrowed [`cxx::CxxString`], whose `to_str` gives a `&str`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! c_str_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Checks the arguments of a free function before each call to it, in
+/// debug builds, for instance to catch a call which would fail a C++
+/// `assert` with a Rust panic saying which:
+///
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("divide")
+///     precondition!("divide", |args| *args.1 != 0)
+///     safety!(unsafe_ffi)
+/// );
+///
+/// // C++: inline uint32_t divide(uint32_t a, uint32_t b) { assert(b != 0); return a / b; }
+/// ffi::divide(6, 0); // panics: "precondition of divide violated: *args.1 != 0"
+/// ```
+///
+/// The predicate is a closure which is passed a tuple of references to
+/// the arguments, in order, so it may refer to as many of them as it
+/// likes, and returns whether the call may go ahead. A function may be
+/// given several preconditions, which are checked in the order they're
+/// listed. Numbers are passed as exactly the types the function takes,
+/// rather than as anything which promotes to them, so that the predicate
+/// knows their types. In release builds, or rather without
+/// `debug_assertions`, the checks are left out altogether. Only free
+/// functions are supported: bindings to a method listed here, as
+/// `"Type::method"`, are skipped with an error.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! precondition {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Names free functions without a prefix which a C library puts on all
+/// of them.
+/// ```ignore
+/// // C++: int mylib_open(const char* path); void mylib_close(int fd);
+/// include_cpp!(
+///     #include "mylib.h"
+///     generate!("mylib_open")
+///     generate!("mylib_close")
+///     strip_prefix!("mylib_")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let fd = unsafe { ffi::open(path.as_ptr()) };
+/// ffi::close(fd);
+/// ```
+///
+/// Functions are still named in other directives, such as `generate!`, by
+/// their C++ names, and functions without the prefix keep their names.
+/// If several prefixes are given, the longest which a function has is
+/// removed, but a prefix isn't removed if what would be left can't begin
+/// an identifier, such as the `2` of `mylib_2d_area`. A name which is
+/// left as a Rust keyword gets a trailing underscore, so `mylib_type`
+/// becomes `type_`. If a name without its prefix would be the same as
+/// that of another function in the same namespace, bindings aren't
+/// generated for the stripped function, since it wouldn't be clear which
+/// one the name meant. Methods aren't affected.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! strip_prefix {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Passes a function pointer parameter, together with a `void*` parameter
+/// which is passed back to the function it points to, as a single Rust
+/// closure.
+/// ```ignore
+/// // C++: void on_event(void (*cb)(void* ctx, int event), void* ctx);
+/// include_cpp!(
+///     #include "input.h"
+///     generate!("on_event")
+///     closure_callback!("on_event", "cb", "ctx")
+///     safety!(unsafe_ffi)
+/// );
+///
+/// let handle = ffi::on_event(|event| println!("Event {:?}", event));
+/// ```
+///
+/// The arguments are the fully-qualified name of the function, and the
+/// names of the function pointer and context parameters. The closure
+/// takes the same parameters as the function pointed to, other than the
+/// first `void*`, which is the context. Those parameters, and any return
+/// value, must be pointers, integers or other C types.
+///
+/// The closure must be [`Send`], since C++ may call it on any thread. A
+/// panic within it aborts the process, since it can't unwind into C++.
+///
+/// The function returns, in addition to anything else, a
+/// [`callback::CallbackHandle`]. Since Rust can't know how long C++ will
+/// go on calling the closure, it lives until you free it by calling
+/// [`callback::CallbackHandle::free`] after unregistering it; otherwise
+/// it's leaked. The handle's pointer is the context which was given to
+/// C++, which may be needed to unregister it. At present this works only
+/// for free functions, not methods.
+///
+/// A `std::function` parameter needs no directive: it's passed as a
+/// closure, which C++ owns. See [`callback`] for details.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! closure_callback {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Sets the Rust visibility of generated items, instead of the default
+/// `pub`. The first argument names either a C++ namespace or an individual
+/// item by its fully-qualified C++ name; the second is any Rust visibility.
+/// ```ignore
+/// include_cpp!(
+///     #include "input.h"
+///     generate_all!()
+///     visibility!("internal", pub(crate))
+///     visibility!("internal::Config", pub)
+/// );
+/// pub use ffi::*; // doesn't re-export ffi::internal
+/// ```
+///
+/// When an item is named, that takes precedence; otherwise the innermost
+/// enclosing namespace which has a visibility given is used. A namespace's
+/// visibility also applies to the Rust `mod` for that namespace, so in the
+/// example above `internal::Config` is `pub` but nonetheless can't be
+/// reached from outside your crate.
+///
+/// Restricting a type's visibility doesn't stop public functions from
+/// using it: those functions still compile, but callers outside the
+/// visibility scope won't be able to name the type.
+/// Visibilities such as `pub(super)` are relative to the generated mod
+/// for the item's namespace.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! visibility {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// The name of the mod to be generated with the FFI code.
+/// The default is `ffi`.
+///
+/// Each [include_cpp] block in a crate needs a different name. They may
+/// bind the same C++ functions; to share a type between them, use
+/// [reuse_from] in the later block.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! name {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Specifies a global safety policy for functions generated
+/// from these headers. By default (without such a `safety!`
+/// directive) all such functions are marked as `unsafe` and
+/// therefore can only be called within an `unsafe {}` block
+/// or some `unsafe` function which you create.
+///
+/// Alternatively, by specifying a `safety!` block you can
+/// declare that most generated functions are in fact safe.
+/// Specifically, you'd specify:
+/// `safety!(unsafe)`
+/// or
+/// `safety!(unsafe_ffi)`
+/// These two options are functionally identical. If you're
+/// unsure, simply use `unsafe`. The reason for the
+/// latter option is if you have code review policies which
+/// might want to give a different level of scrutiny to
+/// C++ interop as opposed to other types of unsafe Rust code.
+/// Maybe in your organization, C++ interop is less scary than
+/// a low-level Rust data structure using pointer manipulation.
+/// Or maybe it's more scary. Either way, using `unsafe` for
+/// the data structure and using `unsafe_ffi` for the C++
+/// interop allows you to apply different linting tools and
+/// policies to the different options.
+///
+/// Irrespective, C++ code is of course unsafe. It's worth
+/// noting that use of C++ can cause unexpected unsafety at
+/// a distance in faraway Rust code. As with any use of the
+/// `unsafe` keyword in Rust, *you the human* are declaring
+/// that you've analyzed all possible ways that the code
+/// can be used and you are guaranteeing to the compiler that
+/// no badness can occur. Good luck.
+///
+/// Generated C++ APIs which use raw pointers remain `unsafe`
+/// no matter what policy you choose.
+///
+/// ## Exceptions
+///
+/// By default, a C++ exception which escapes into Rust is undefined
+/// behavior. If you add `exceptions_as_panics`, for example
+/// `safety!(unsafe_ffi, exceptions_as_panics)`, then instead every call
+/// into C++ catches any exception and turns it into a Rust panic whose
+/// payload is a `String` containing the exception's `what()` (or a generic
+/// message for exceptions not derived from `std::exception`). The exception
+/// is caught on the C++ side and passed back to Rust as an ordinary return
+/// value, so nothing unwinds across the language boundary; the panic
+/// starts in Rust code and can be caught with [`std::panic::catch_unwind`]
+/// like any other. This requires a C++ wrapper function for every API,
+/// so there's a small cost per call.
+///
+/// The other way round, a panic in the Rust implementation of a C++
+/// virtual method for a [`subclass`](crate::subclass::subclass) would unwind into C++. If you add
+/// `panics_as_exceptions`, for example
+/// `safety!(unsafe_ffi, panics_as_exceptions)`, then the panic is caught
+/// before it leaves Rust and is thrown in C++ as a `rust::Error`, whose
+/// `what()` is the panic message (or a generic message for panics whose
+/// payload isn't a string). If C++ exceptions are disabled, which cxx
+/// must be told by defining `RUST_CXX_NO_EXCEPTIONS`, cxx instead prints
+/// the message and aborts. The two options may be given together.
+#[macro_export]
+macro_rules! safety {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Whether to avoid generating [`cxx::UniquePtr`] and [`cxx::Vector`]
+/// implementations. This is primarily useful for reducing test cases and
+/// shouldn't be used in normal operation.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! exclude_impls {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Deprecated - use [`extern_rust_type`] instead.
+#[macro_export]
+#[deprecated]
+macro_rules! rust_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// See [`extern_rust::extern_rust_type`].
+#[macro_export]
+macro_rules! extern_rust_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// See [`subclass::subclass`].
+#[macro_export]
+macro_rules! subclass {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! usage {
+    (__docs) => {};
+    ($($tt:tt)*) => {
+        compile_error! {r#"usage:  include_cpp! {
+                   #include "path/to/header.h"
+                   generate!(...)
+                   generate_pod!(...)
+               }
+"#}
+    };
+}
+
+/// Used by generated code to log a call into C++, if the `trace_calls`
+/// feature is enabled. Otherwise, this expands to nothing.
+#[cfg(feature = "trace_calls")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trace_ffi_call {
+    ($name:expr) => {
+        let _trace_guard = $crate::trace::TraceGuard::enter($name);
+    };
+}
+
+#[cfg(not(feature = "trace_calls"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trace_ffi_call {
+    ($name:expr) => {};
+}
+
+/// Used by generated code to turn a C++ exception into a panic, if
+/// `safety!(exceptions_as_panics)` was specified.
+#[doc(hidden)]
+#[track_caller]
+pub fn unwrap_cpp_exception<T>(result: Result<T, cxx::Exception>) -> T {
+    match result {
+        Ok(t) => t,
+        Err(e) => panic!("{}", e.what()),
+    }
+}
+
+/// Used by generated code to check, in debug builds, a precondition given
+/// in [`precondition`] before calling C++.
+#[doc(hidden)]
+#[track_caller]
+pub fn check_precondition<A>(args: A, predicate: impl FnOnce(A) -> bool, message: &str) {
+    if !predicate(args) {
+        panic!("{}", message);
+    }
+}
+
+#[doc(hidden)]
+pub use autocxx_macro::include_cpp_impl;
+
+#[doc(hidden)]
+pub use autocxx_macro::cpp_semantics;
+
+macro_rules! ctype_wrapper {
+    ($r:ident, $c:expr, $d:expr) => {
+        #[doc=$d]
+        #[derive(Debug, Default, Eq, Copy, Clone, PartialEq, Hash)]
+        #[allow(non_camel_case_types)]
+        #[repr(transparent)]
+        pub struct $r(pub ::std::os::raw::$r);
+
+        unsafe impl autocxx_engine::cxx::ExternType for $r {
+            type Id = autocxx_engine::cxx::type_id!($c);
+            type Kind = autocxx_engine::cxx::kind::Trivial;
+        }
+
+        impl From<::std::os::raw::$r> for $r {
+            fn from(val: ::std::os::raw::$r) -> Self {
+                Self(val)
+            }
+        }
+
+        impl From<$r> for ::std::os::raw::$r {
+            fn from(val: $r) -> Self {
+                val.0
+            }
+        }
+    };
+}
+
+ctype_wrapper!(
+    c_ulonglong,
+    "c_ulonglong",
+    "Newtype wrapper for an unsigned long long"
 );
 ctype_wrapper!(c_longlong, "c_longlong", "Newtype wrapper for a long long");
 ctype_wrapper!(c_ulong, "c_ulong", "Newtype wrapper for an unsigned long");
@@ -751,6 +2782,30 @@ ctype_wrapper!(c_uint, "c_uint", "Newtype wrapper for an unsigned int");
 ctype_wrapper!(c_int, "c_int", "Newtype wrapper for an int");
 ctype_wrapper!(c_uchar, "c_uchar", "Newtype wrapper for an unsigned char");
 
+/// Newtype wrapper for a C++ `std::byte`. Slices of these are passed
+/// as plain `&[u8]` - see [`slice_param`].
+#[derive(Debug, Eq, Copy, Clone, PartialEq, Hash)]
+#[allow(non_camel_case_types)]
+#[repr(transparent)]
+pub struct c_byte(pub u8);
+
+unsafe impl autocxx_engine::cxx::ExternType for c_byte {
+    type Id = autocxx_engine::cxx::type_id!(c_byte);
+    type Kind = autocxx_engine::cxx::kind::Trivial;
+}
+
+impl From<u8> for c_byte {
+    fn from(val: u8) -> Self {
+        Self(val)
+    }
+}
+
+impl From<c_byte> for u8 {
+    fn from(val: c_byte) -> Self {
+        val.0
+    }
+}
+
 /// Newtype wrapper for a C void. Only useful as a `*c_void`
 #[allow(non_camel_case_types)]
 #[repr(transparent)]
@@ -813,6 +2868,53 @@ pub mod extern_rust {
     pub use autocxx_macro::extern_rust_function;
 }
 
+pub use awaitable::{Awaitable, CppFuture};
+pub use c_str::CStrArg;
+pub use cpp_any::AnyCastable;
+pub use cpp_conversion::CppConversion;
+pub use cpp_iter::{CppDoubleEndedIterable, CppExactSizeIterable, CppIter, CppIterable};
+pub use into_box::IntoBox;
+pub use intrusive_ptr::{IntrusivePtr, IntrusiveRefcounted};
+pub use owned_handle::OwnedHandle;
+pub use owned_ptr::OwnedPtr;
+pub use promote::PromotesTo;
+#[doc(hidden)]
+pub use symbol_resolver::ResolvedSymbol;
+pub use symbol_resolver::SymbolResolver;
+pub use thread_local_ref::ThreadLocalRef;
+pub use unique_array::UniqueArray;
+pub use vector_into_iter::{IntoVectorIter, VectorIntoIter};
+
+/// Store C++ objects inline within a Rust `struct`, instead of behind a
+/// [`cxx::UniquePtr`]. Mark each such field with `#[autocxx_field]`:
+/// ```rust,ignore
+/// #[autocxx::cpp_fields]
+/// struct Holder {
+///     label: String,
+///     #[autocxx_field]
+///     counter: ffi::Counter,
+/// }
+///
+/// moveit! { let mut holder = Holder::new("hello".to_string(), ffi::Counter::new()); }
+/// holder.as_mut().project().counter.increment();
+/// ```
+/// C++ objects may refer to their own address, so they may never move
+/// once constructed. This attribute therefore generates:
+/// * a `new` associated function which takes a value for each ordinary field,
+///   and a [`moveit::New`] for each `#[autocxx_field]`. This returns
+///   a [`moveit::New`] for the whole struct, which you can emplace
+///   using [`moveit::moveit`] or [`moveit::Emplace`] (e.g. `Box::emplace`),
+///   such that the C++ fields are constructed directly in their final location.
+/// * a `project` method which turns a `Pin<&mut Self>` into a struct called
+///   `{name}Projection`, containing a `Pin<&mut T>` for each C++ field and
+///   a `&mut T` for each other field. C++ methods can be called on the former.
+///
+/// The struct is [`Unpin`] only if all its C++ fields are, so it can't be moved once
+/// pinned; and it may not implement [`Drop`], since that could move the
+/// C++ fields out from under their [`std::pin::Pin`]. If a C++ constructor
+/// panics, any fields already constructed are leaked rather than dropped.
+pub use autocxx_macro::cpp_fields;
+
 /// Equivalent to [`std::convert::AsMut`], but returns a pinned mutable reference
 /// such that cxx methods can be called on it.
 pub trait PinMut<T>: AsRef<T> {
@@ -820,6 +2922,105 @@ pub trait PinMut<T>: AsRef<T> {
     fn pin_mut(&mut self) -> std::pin::Pin<&mut T>;
 }
 
+/// A C++ type whose objects may or may not hold a value, such as a
+/// `std::optional` or a handle. This is implemented for types listed in
+/// [`validity`](crate::validity!).
+pub trait IsValid {
+    /// Whether this object holds a value.
+    fn is_valid(&self) -> bool;
+
+    /// This object, if it holds a value.
+    fn as_option(&self) -> Option<&Self> {
+        if self.is_valid() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+/// Copies just the base class part of a C++ object, "slicing" off the rest.
+/// This is implemented for each copyable, non-POD base of a C++ class.
+///
+/// A Rust function which takes a C++ class by value takes a
+/// [`cxx::UniquePtr`] to it, whose object it moves from; so you can't pass
+/// an object of a derived class there by accident, as you might in C++. If you do mean to pass a copy of the base class part of
+/// it, call this, which borrows the derived object and returns a new
+/// `UniquePtr` to the copy:
+///
+/// ```ignore
+/// ffi::take_base(derived.slice_to_base());
+/// ```
+///
+/// Whatever the derived class has overridden, or added, is lost in the copy.
+pub trait SliceToBase<B: cxx::memory::UniquePtrTarget> {
+    /// Copies the base class part of this object into a new heap
+    /// allocation, leaving this object untouched.
+    fn slice_to_base(&self) -> cxx::UniquePtr<B>;
+}
+
+/// Downcasts from a polymorphic C++ base class to one of its derived
+/// classes, using `dynamic_cast`. This is implemented for each base which
+/// has virtual functions, for each class derived from it; it's usually
+/// easier to call through [`Downcast`].
+///
+/// Downcasting needs RTTI. If your C++ is built without it, pass
+/// `-fno-rtti` to autocxx too, and this won't be implemented.
+pub trait DynamicCast<D> {
+    /// This object as the derived class, or `None` if it's not one.
+    fn dynamic_cast(&self) -> Option<&D>;
+
+    /// This object as the derived class, mutably, or `None` if it's not
+    /// one.
+    fn dynamic_cast_mut(self: std::pin::Pin<&mut Self>) -> Option<std::pin::Pin<&mut D>>;
+}
+
+/// Lets you name the derived class when downcasting, for instance
+/// `shape.downcast::<ffi::Circle>()`. Implemented for everything; calls
+/// [`DynamicCast`].
+pub trait Downcast {
+    /// This object as the given derived class, or `None` if it's not one.
+    fn downcast<D>(&self) -> Option<&D>
+    where
+        Self: DynamicCast<D>,
+    {
+        self.dynamic_cast()
+    }
+
+    /// This object as the given derived class, mutably, or `None` if it's
+    /// not one.
+    fn downcast_mut<D>(self: std::pin::Pin<&mut Self>) -> Option<std::pin::Pin<&mut D>>
+    where
+        Self: DynamicCast<D>,
+    {
+        self.dynamic_cast_mut()
+    }
+}
+
+impl<T: ?Sized> Downcast for T {}
+
+/// Compares C++ objects by identity, rather than by value as
+/// [`PartialEq`] would: two references are the same object if they have
+/// the same address. Implemented for C++ types, that is, those which
+/// implement [`cxx::ExternType`], so that a `UniquePtr` or other handle
+/// isn't itself compared: `a.ptr_eq(&b)` on two `UniquePtr`s compares the
+/// objects they point to.
+///
+/// A `Pin<&mut T>` can be compared by reborrowing it as a `&T`, for
+/// instance `a.ptr_eq(&*pinned)`. With multiple inheritance, the base
+/// class parts of an object may not be at the same address as the object
+/// itself, so compare objects as the same type: upcast both to a base with
+/// [`AsRef`] and compare those, just as C++ would convert both pointers
+/// to a common type before comparing them.
+pub trait PtrEq {
+    /// Whether `self` and `other` are the same object.
+    fn ptr_eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl<T: cxx::ExternType + ?Sized> PtrEq for T {}
+
 /// Imports which you're likely to want to use.
 pub mod prelude {
     pub use crate::c_int;
@@ -834,7 +3035,13 @@ pub mod prelude {
     pub use crate::c_void;
     pub use crate::cpp_semantics;
     pub use crate::include_cpp;
+    pub use crate::Downcast;
+    pub use crate::IntoBox;
+    pub use crate::IntoVectorIter;
+    pub use crate::IsValid;
     pub use crate::PinMut;
+    pub use crate::PtrEq;
+    pub use crate::SliceToBase;
     pub use moveit::moveit;
     pub use moveit::new::New;
 }
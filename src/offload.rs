@@ -0,0 +1,145 @@
+//! Module to run blocking C++ functions on some other thread, and await
+//! their results. See [`offload`](crate::offload!) for details.
+
+// Copyright 2021 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    future::Future,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+/// A job which has been offloaded to run elsewhere.
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Something which can run a blocking [`Job`] somewhere other than the
+/// current thread - for example, a thread pool, or your async runtime's
+/// facility for running blocking tasks. For tokio, this might be:
+/// ```ignore
+/// ffi::slow_call_offloaded(|job: autocxx::offload::Job| { tokio::task::spawn_blocking(job); }, 42)
+/// ```
+/// If you don't have any such facility, [`ThreadSpawner`] starts a new
+/// thread for each job.
+pub trait Spawner {
+    /// Arrange for the job to be run. If the job is dropped without
+    /// being run, the corresponding [`Offloaded`] future will panic
+    /// when next polled.
+    fn spawn(&self, job: Job);
+}
+
+impl<F: Fn(Job)> Spawner for F {
+    fn spawn(&self, job: Job) {
+        self(job)
+    }
+}
+
+/// A [`Spawner`] which runs each job on a newly spawned thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, job: Job) {
+        thread::spawn(job);
+    }
+}
+
+struct State<R> {
+    result: Option<thread::Result<R>>,
+    waker: Option<Waker>,
+    abandoned: bool,
+}
+
+/// Notes in the shared state if the job gets dropped without having run
+/// to completion, so that the future doesn't wait forever.
+struct AbandonGuard<R>(Option<Arc<Mutex<State<R>>>>);
+
+impl<R> Drop for AbandonGuard<R> {
+    fn drop(&mut self) {
+        if let Some(state) = self.0.take() {
+            let mut state = state.lock().unwrap();
+            state.abandoned = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A future representing a C++ call which has been offloaded to another
+/// thread. This is [`Send`] whenever the result type is [`Send`].
+///
+/// The call starts as soon as the offloaded function is called, not when
+/// this future is first polled. Dropping this future does not cancel the
+/// C++ call: it will still run to completion, and its result will then be
+/// dropped on whichever thread ran it.
+///
+/// If the C++ call panics, the panic is resumed when this future is
+/// polled.
+#[must_use = "the C++ call runs regardless, but its result is discarded unless this is awaited"]
+pub struct Offloaded<R> {
+    state: Arc<Mutex<State<R>>>,
+}
+
+impl<R> Future for Offloaded<R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(panic)) => {
+                drop(state);
+                resume_unwind(panic)
+            }
+            None if state.abandoned => {
+                panic!("offloaded C++ call was dropped by its Spawner without running")
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Run `f` by means of `spawner`, returning a future which resolves to
+/// its result. Normally called only by code generated for functions
+/// listed in [`offload`](crate::offload!).
+pub fn offload<S, F, R>(spawner: S, f: F) -> Offloaded<R>
+where
+    S: Spawner,
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let state = Arc::new(Mutex::new(State {
+        result: None,
+        waker: None,
+        abandoned: false,
+    }));
+    let mut guard = AbandonGuard(Some(state.clone()));
+    spawner.spawn(Box::new(move || {
+        let result = catch_unwind(AssertUnwindSafe(f));
+        let state = guard.0.take().unwrap();
+        let mut state = state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }));
+    Offloaded { state }
+}
@@ -0,0 +1,68 @@
+// Copyright 2021 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// A C-style handle - an integer or a pointer - which owns some resource
+/// that must be released by calling a particular C++ function. This is
+/// what functions returning a type listed in
+/// [`resource`](crate::resource!) return.
+///
+/// The handle is released exactly once, when this is dropped. For that
+/// reason, this is neither [`Clone`] nor [`Copy`], even though the
+/// handle itself is; use [`OwnedHandle::get`] to pass the handle to
+/// other C++ functions without giving up ownership.
+pub struct OwnedHandle<H: Copy> {
+    handle: H,
+    close: unsafe fn(H),
+}
+
+impl<H: Copy> OwnedHandle<H> {
+    /// Take ownership of a handle, which will be passed to `close`
+    /// when this is dropped.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may release `handle`, and `close` must be safe to
+    /// call with it. If `handle` may be an invalid value which must not
+    /// be released, `close` must check for that.
+    pub unsafe fn from_raw(handle: H, close: unsafe fn(H)) -> Self {
+        Self { handle, close }
+    }
+
+    /// Returns the handle, which remains owned by this object.
+    pub fn get(&self) -> H {
+        self.handle
+    }
+
+    /// Gives up ownership of the handle. The caller becomes responsible
+    /// for releasing it.
+    pub fn into_raw(self) -> H {
+        let handle = self.handle;
+        std::mem::forget(self);
+        handle
+    }
+}
+
+impl<H: Copy> Drop for OwnedHandle<H> {
+    fn drop(&mut self) {
+        unsafe { (self.close)(self.handle) }
+    }
+}
+
+impl<H: Copy + fmt::Debug> fmt::Debug for OwnedHandle<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedHandle").field(&self.handle).finish()
+    }
+}
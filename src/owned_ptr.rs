@@ -0,0 +1,110 @@
+// Copyright 2021 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, ops::Deref, pin::Pin};
+
+/// An owning pointer to a C++ object which must be freed by some
+/// particular C++ function, rather than by `delete`. This is what
+/// functions listed in [`owned_return`](crate::owned_return!) return.
+/// It behaves much like a [`cxx::UniquePtr`]: it may be null, and
+/// dereferencing a null `OwnedPtr` panics.
+pub struct OwnedPtr<T> {
+    ptr: *mut T,
+    deleter: unsafe fn(*mut T),
+}
+
+impl<T> OwnedPtr<T> {
+    /// Take ownership of a raw pointer, which will be passed to `deleter`
+    /// when this is dropped, unless it's null.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null, or point to a valid object which nothing else
+    /// will free, and which `deleter` knows how to free.
+    pub unsafe fn from_raw(ptr: *mut T, deleter: unsafe fn(*mut T)) -> Self {
+        Self { ptr, deleter }
+    }
+
+    /// Whether this pointer is null.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// Returns a reference to the object, or `None` if this pointer is null.
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns a pinned mutable reference to the object, or `None` if this
+    /// pointer is null.
+    pub fn as_mut(&mut self) -> Option<Pin<&mut T>> {
+        unsafe { self.ptr.as_mut().map(|r| Pin::new_unchecked(r)) }
+    }
+
+    /// Returns a pinned mutable reference to the object, on which C++
+    /// methods can be called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this pointer is null.
+    pub fn pin_mut(&mut self) -> Pin<&mut T> {
+        match self.as_mut() {
+            Some(target) => target,
+            None => panic!("called pin_mut on a null OwnedPtr"),
+        }
+    }
+
+    /// Returns the raw pointer, which remains owned by this object.
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    /// Returns the raw pointer, which remains owned by this object.
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Gives up ownership of the raw pointer. The caller becomes
+    /// responsible for freeing it.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        ptr
+    }
+}
+
+impl<T> Deref for OwnedPtr<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self.as_ref() {
+            Some(target) => target,
+            None => panic!("called deref on a null OwnedPtr"),
+        }
+    }
+}
+
+impl<T> Drop for OwnedPtr<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { (self.deleter)(self.ptr) }
+        }
+    }
+}
+
+impl<T> fmt::Debug for OwnedPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedPtr").field(&self.ptr).finish()
+    }
+}
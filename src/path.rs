@@ -0,0 +1,91 @@
+//! Conversions between Rust paths and the native representation of
+//! `std::filesystem::path`. See [`include_cpp`](crate::include_cpp).
+
+// Copyright 2021 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// The bytes of `std::filesystem::path::native()` for this path. On Unix
+/// these are simply the bytes of the `OsStr`, whether or not they are
+/// UTF-8. On Windows they are the native-endian bytes of its UTF-16.
+pub fn path_to_native(path: &Path) -> Cow<'_, [u8]> {
+    imp::path_to_native(path)
+}
+
+/// The path whose `std::filesystem::path::native()` has the given bytes;
+/// the inverse of [`path_to_native`].
+pub fn path_from_native(bytes: Vec<u8>) -> PathBuf {
+    imp::path_from_native(bytes)
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::borrow::Cow;
+    use std::ffi::OsString;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Path, PathBuf};
+
+    pub(super) fn path_to_native(path: &Path) -> Cow<'_, [u8]> {
+        Cow::Borrowed(path.as_os_str().as_bytes())
+    }
+
+    pub(super) fn path_from_native(bytes: Vec<u8>) -> PathBuf {
+        OsString::from_vec(bytes).into()
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::borrow::Cow;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Path, PathBuf};
+
+    pub(super) fn path_to_native(path: &Path) -> Cow<'_, [u8]> {
+        Cow::Owned(
+            path.as_os_str()
+                .encode_wide()
+                .flat_map(u16::to_ne_bytes)
+                .collect(),
+        )
+    }
+
+    pub(super) fn path_from_native(bytes: Vec<u8>) -> PathBuf {
+        let wide: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .collect();
+        OsString::from_wide(&wide).into()
+    }
+}
+
+// Elsewhere, assume that C++ uses UTF-8 paths.
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::borrow::Cow;
+    use std::path::{Path, PathBuf};
+
+    pub(super) fn path_to_native(path: &Path) -> Cow<'_, [u8]> {
+        match path.to_string_lossy() {
+            Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+            Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+        }
+    }
+
+    pub(super) fn path_from_native(bytes: Vec<u8>) -> PathBuf {
+        String::from_utf8_lossy(&bytes).into_owned().into()
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::os::raw;
+
+use crate::{c_int, c_long, c_longlong, c_short, c_uint, c_ulong, c_ulonglong, c_ushort};
+
+/// A number which C++ would implicitly convert to `T` without losing any
+/// information: a narrower integer of the same signedness, or an `f32`
+/// for an `f64`. A C++ function which takes an integer type such as
+/// `long`, or a `double`, accepts anything which promotes to it, so an
+/// `i32` may be passed for a `long` without a cast.
+///
+/// Conversions which might lose information, or which change signedness,
+/// aren't promotions, so still need an explicit cast. Whether a conversion
+/// is lossless may depend on the platform: an `i64` promotes to a
+/// `c_long` only where a `long` has 64 bits.
+pub trait PromotesTo<T> {
+    /// Converts the number.
+    fn promote(self) -> T;
+}
+
+mod sealed {
+    pub trait Signed {}
+    pub trait Unsigned {}
+    pub trait Float {}
+}
+
+macro_rules! mark {
+    ($marker:ident, $($t:ty),*) => {
+        $(impl sealed::$marker for $t {})*
+    };
+}
+
+mark!(Signed, i8, i16, i32, i64, c_short, c_int, c_long, c_longlong);
+mark!(
+    Unsigned,
+    u8,
+    u16,
+    u32,
+    u64,
+    c_ushort,
+    c_uint,
+    c_ulong,
+    c_ulonglong
+);
+mark!(Float, f32, f64);
+
+macro_rules! promotes_to_ctype {
+    ($marker:ident, $($r:ident),*) => {
+        $(
+            impl<T: Into<raw::$r> + sealed::$marker> PromotesTo<$r> for T {
+                fn promote(self) -> $r {
+                    $r(self.into())
+                }
+            }
+        )*
+    };
+}
+
+promotes_to_ctype!(Signed, c_short, c_int, c_long, c_longlong);
+promotes_to_ctype!(Unsigned, c_ushort, c_uint, c_ulong, c_ulonglong);
+
+impl<T: Into<f64> + sealed::Float> PromotesTo<f64> for T {
+    fn promote(self) -> f64 {
+        self.into()
+    }
+}
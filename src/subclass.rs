@@ -17,6 +17,7 @@
 
 use std::{
     cell::RefCell,
+    panic::AssertUnwindSafe,
     pin::Pin,
     rc::{Rc, Weak},
 };
@@ -226,6 +227,15 @@ pub trait CppPeerConstructor<CppPeer: CppSubclassCppPeer>: Sized {
 /// method will be called instead. Naturally, you must implement any pure virtual
 /// methods.
 ///
+/// # How to call protected methods
+///
+/// Like a C++ subclass, yours may call the protected methods of its
+/// superclass. Call them on the C++ peer: `self.peer().foo()`, or
+/// `self.peer_mut().foo()` for a non-const method. Protected virtual
+/// methods may be implemented like any others, and their superclass
+/// implementation called as `foo_super`. A protected superclass
+/// constructor can be used to construct your subclass.
+///
 /// # How it works
 ///
 /// This actually consists of two objects: this object itself and a C++-side
@@ -267,8 +277,6 @@ pub trait CppPeerConstructor<CppPeer: CppSubclassCppPeer>: Sized {
 ///   will give the option to use `Arc` and `Mutex` internally rather than
 ///   `Rc` and `RefCell`, solving this problem.
 ///
-/// * *Protected methods.* We don't do anything clever here - they're public.
-///
 /// * *Non-trivial class hierarchies*. We don't yet consider virtual methods
 ///   on base classes of base classes. This is a temporary limitation,
 ///   [see this issue](https://github.com/google/autocxx/issues/610).
@@ -390,3 +398,27 @@ where
         Self::new_self_owned(Self::default())
     }
 }
+
+/// Runs the Rust implementation of a C++ virtual method, catching any
+/// panic, as requested by `safety!(panics_as_exceptions)`. cxx then throws
+/// it as a `rust::Error` carrying the panic message, rather than letting it
+/// unwind into C++, or aborts if C++ exceptions are disabled.
+#[doc(hidden)]
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let msg = if let Some(msg) = payload.downcast_ref::<&'static str>() {
+            msg.to_string()
+        } else if let Some(msg) = payload.downcast_ref::<String>() {
+            msg.clone()
+        } else {
+            "Rust panic with a payload which was not a string".to_string()
+        };
+        // Dropping the payload runs arbitrary code, which could itself
+        // panic; that mustn't reach C++ either.
+        if std::panic::catch_unwind(AssertUnwindSafe(move || drop(payload))).is_err() {
+            eprintln!("Rust panic ({}) whose payload panicked when dropped", msg);
+            std::process::abort();
+        }
+        msg
+    })
+}
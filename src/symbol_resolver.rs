@@ -0,0 +1,79 @@
+//! Looks up, at runtime, the addresses of the C++ functions which bindings
+//! using [`symbol_resolver`](crate::symbol_resolver!) call, instead of
+//! linking against them.
+
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Finds the C++ functions which the bindings call at runtime, for instance
+/// in a library loaded with `dlopen` or the `libloading` crate, as given in
+/// [`symbol_resolver`](crate::symbol_resolver!).
+///
+/// # Safety
+///
+/// The address given for each symbol must be that of a function of the
+/// type which the C++ headers declare, which lasts as long as the program
+/// may call it: a library mustn't be unloaded once a function in it has
+/// been called.
+pub unsafe trait SymbolResolver {
+    /// The address of the function with this symbol, or `None` if there's
+    /// no such function. The symbol is the one the function is linked by,
+    /// so for anything but an `extern "C"` function it's mangled.
+    fn resolve(symbol: &str) -> Option<*const c_void>;
+
+    /// Checks that the library is one the bindings can call, for instance
+    /// by comparing a version which it exports with the one the headers
+    /// describe. This is called before each symbol is resolved; each is
+    /// resolved the first time its function is called.
+    fn check_library() -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The address of the function called by a binding, found the first time
+/// it's called.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct ResolvedSymbol(AtomicUsize);
+
+impl ResolvedSymbol {
+    pub const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    /// Panics if the library is rejected or the symbol can't be found.
+    pub fn get<R: SymbolResolver>(&self, symbol: &str) -> usize {
+        let address = self.0.load(Ordering::Acquire);
+        if address != 0 {
+            return address;
+        }
+        if let Err(problem) = R::check_library() {
+            panic!(
+                "the library which should contain {} can't be used: {}",
+                symbol, problem
+            );
+        }
+        let address = match R::resolve(symbol) {
+            Some(address) if !address.is_null() => address as usize,
+            _ => panic!("the C++ function with symbol {} couldn't be found", symbol),
+        };
+        self.0.store(address, Ordering::Release);
+        address
+    }
+}
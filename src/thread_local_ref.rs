@@ -0,0 +1,66 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, marker::PhantomData, ops::Deref, ptr::NonNull};
+
+/// A reference to the calling thread's instance of a C++ `thread_local`
+/// variable, as returned by the accessors generated for variables listed
+/// in [`thread_local_global`](crate::thread_local_global!).
+///
+/// The instance lasts only as long as its thread, so this can't be sent
+/// to, or shared with, another thread, and it can't be `'static`: borrow
+/// the variable through it, by dereferencing it, for only as long as you
+/// hold it.
+pub struct ThreadLocalRef<T> {
+    ptr: NonNull<T>,
+    // Neither Send nor Sync.
+    _thread_bound: PhantomData<*mut T>,
+}
+
+impl<T> ThreadLocalRef<T> {
+    /// Refers to the calling thread's instance of a variable.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to the calling thread's instance of a
+    /// `thread_local` variable, which must not be changed while the
+    /// reference is dereferenced.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(ptr: *mut T) -> Self {
+        Self {
+            ptr: NonNull::new(ptr).expect("thread_local variable had a null address"),
+            _thread_bound: PhantomData,
+        }
+    }
+
+    /// The address of the calling thread's instance of the variable, for
+    /// instance to change it.
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+}
+
+impl<T> Deref for ThreadLocalRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> fmt::Debug for ThreadLocalRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ThreadLocalRef").field(&self.ptr).finish()
+    }
+}
@@ -0,0 +1,52 @@
+//! Support for logging calls into C++. See the `trace_calls` feature
+//! described in [`include_cpp`](crate::include_cpp).
+
+// Copyright 2021 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+
+/// The `log` target used for all traced calls.
+pub const TARGET: &str = "autocxx::ffi";
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Logs entry to a C++ call when created, and exit when dropped.
+/// Calls which happen while another is in progress on the same
+/// thread - for instance, C++ calling back into Rust which calls
+/// into C++ again - are indented accordingly.
+pub struct TraceGuard {
+    name: &'static str,
+}
+
+impl TraceGuard {
+    pub fn enter(name: &'static str) -> Self {
+        let depth = DEPTH.with(|d| d.replace(d.get() + 1));
+        log::trace!(target: TARGET, "{:indent$}-> {}", "", name, indent = depth * 2);
+        Self { name }
+    }
+}
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        let depth = DEPTH.with(|d| {
+            let depth = d.get() - 1;
+            d.set(depth);
+            depth
+        });
+        log::trace!(target: TARGET, "{:indent$}<- {}", "", self.name, indent = depth * 2);
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// An owned array of C++ POD values, as returned in a `std::unique_ptr<T[]>`
+/// by functions listed in [`unique_array_return`](crate::unique_array_return!).
+/// It dereferences to a slice, and frees the array with `delete[]` when
+/// dropped. A null array is an empty slice.
+pub struct UniqueArray<T> {
+    ptr: *mut T,
+    len: usize,
+    deleter: unsafe fn(*mut T),
+}
+
+impl<T> UniqueArray<T> {
+    /// Take ownership of an array of `len` elements, which will be passed
+    /// to `deleter` when this is dropped, unless it's null.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null, or point to `len` initialized elements which
+    /// nothing else will free, and which `deleter` knows how to free.
+    pub unsafe fn from_raw(ptr: *mut T, len: usize, deleter: unsafe fn(*mut T)) -> Self {
+        let len = if ptr.is_null() { 0 } else { len };
+        Self { ptr, len, deleter }
+    }
+
+    /// Whether the array is null.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// The elements of the array.
+    pub fn as_slice(&self) -> &[T] {
+        if self.ptr.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+        }
+    }
+
+    /// The elements of the array, mutably.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.ptr.is_null() {
+            &mut []
+        } else {
+            unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+        }
+    }
+
+    /// Gives up ownership of the raw pointer. The caller becomes
+    /// responsible for freeing it.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        ptr
+    }
+}
+
+impl<T> Deref for UniqueArray<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for UniqueArray<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> Drop for UniqueArray<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { (self.deleter)(self.ptr) }
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for UniqueArray<T> {}
+unsafe impl<T: Sync> Sync for UniqueArray<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for UniqueArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::iter::FusedIterator;
+
+use cxx::{kind::Trivial, vector::VectorElement, CxxVector, ExternType, UniquePtr};
+
+/// Consumes a [`cxx::CxxVector`] returned from C++, yielding its elements
+/// by value. This is implemented for vectors of primitives and of types
+/// generated with [`generate_pod`](crate::generate_pod!), which cxx knows
+/// may be moved with a bitwise copy, and not for other types, which would
+/// each need moving by C++: use [`cxx::CxxVector::iter`] to borrow those
+/// instead.
+///
+/// Each element is copied out as it's reached. The vector itself is
+/// destroyed when the iterator is dropped, along with any elements which
+/// weren't reached; since they're trivially destructible, nothing is
+/// destroyed twice.
+/// ```ignore
+/// let lengths: Vec<i32> = ffi::get_lengths().into_iter().collect();
+/// ```
+pub trait IntoVectorIter<T: VectorElement> {
+    /// Consumes the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pointer is null.
+    fn into_iter(self) -> VectorIntoIter<T>;
+}
+
+impl<T> IntoVectorIter<T> for UniquePtr<CxxVector<T>>
+where
+    T: VectorElement + ExternType<Kind = Trivial>,
+{
+    fn into_iter(self) -> VectorIntoIter<T> {
+        assert!(!self.is_null(), "into_iter called on a null UniquePtr");
+        let back = self.len();
+        VectorIntoIter {
+            vec: self,
+            front: 0,
+            back,
+        }
+    }
+}
+
+/// An iterator which yields the elements of a [`cxx::CxxVector`] by value.
+/// See [`IntoVectorIter`].
+pub struct VectorIntoIter<T>
+where
+    T: VectorElement,
+{
+    vec: UniquePtr<CxxVector<T>>,
+    front: usize,
+    back: usize,
+}
+
+impl<T> VectorIntoIter<T>
+where
+    T: VectorElement + ExternType<Kind = Trivial>,
+{
+    /// Copies out an element which hasn't yet been yielded. The original
+    /// stays in the vector until it's destroyed, but is never used again.
+    fn read_elem(&self, index: usize) -> T {
+        unsafe { std::ptr::read(&self.vec.as_slice()[index]) }
+    }
+}
+
+impl<T> Iterator for VectorIntoIter<T>
+where
+    T: VectorElement + ExternType<Kind = Trivial>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.front == self.back {
+            None
+        } else {
+            let elem = self.read_elem(self.front);
+            self.front += 1;
+            Some(elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for VectorIntoIter<T>
+where
+    T: VectorElement + ExternType<Kind = Trivial>,
+{
+    fn next_back(&mut self) -> Option<T> {
+        if self.front == self.back {
+            None
+        } else {
+            self.back -= 1;
+            Some(self.read_elem(self.back))
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for VectorIntoIter<T> where T: VectorElement + ExternType<Kind = Trivial> {}
+
+impl<T> FusedIterator for VectorIntoIter<T> where T: VectorElement + ExternType<Kind = Trivial> {}